@@ -0,0 +1,775 @@
+//! Generates the compiled-in signature packs from `src/defaults/*.sigs.json`.
+//!
+//! Each pack file is a `signatures.v1` document (see `schemas/`). The script
+//! turns it into a `GENERATED` [`SignaturePack`] constant plus one `SIG_IDX_*`
+//! constant per signature — its index in the pack table it landed in — and
+//! writes `$OUT_DIR/defaults/<pack>.rs`, which the pack module `include!`s.
+//! Signatures the compiled-in tables can't express, or that lack a
+//! `category`/`severity`/`confidence` rating (exclusions aside), fail the
+//! build instead of being dropped silently. The file's `rules` are compiled
+//! into post-order expression nodes (`crate::rules::RuleDb`), with a reverse
+//! index from each signature to the rules using it; a rule needs a
+//! `severity` and may only reference the file's own signatures.
+//!
+//! [`SignaturePack`]: src/defaults.rs
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+
+use serde_json::{Map, Value};
+
+fn main() {
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let pack_dir = manifest_dir.join("src/defaults");
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap()).join("defaults");
+    fs::create_dir_all(&out_dir).unwrap();
+
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed={}", pack_dir.display());
+
+    let mut files: Vec<PathBuf> = fs::read_dir(&pack_dir)
+        .unwrap_or_else(|e| panic!("{}: {e}", pack_dir.display()))
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.to_string_lossy().ends_with(".sigs.json"))
+        .collect();
+    files.sort();
+
+    for path in files {
+        println!("cargo:rerun-if-changed={}", path.display());
+        let file_name = path.file_name().unwrap().to_string_lossy().into_owned();
+        let stem = file_name.trim_end_matches(".sigs.json");
+        let code = generate(&path, stem).unwrap_or_else(|e| panic!("{file_name}: {e}"));
+        fs::write(out_dir.join(format!("{stem}.rs")), code).unwrap();
+    }
+}
+
+/// Pack tables filled from the signature file, in file order
+#[derive(Default)]
+struct Pack {
+    /// FNV-1a over the signature file and the pack module source
+    hash: u32,
+    /// `(SIG_IDX_* name, index)`
+    indices: Vec<(String, usize)>,
+    mac_prefixes: Vec<String>,
+    /// Vendor → min_sightings, for every vendor in `mac_prefixes`
+    vendor_min: BTreeMap<String, u8>,
+    ssid_patterns: Vec<String>,
+    ssid_exact: Vec<String>,
+    ssid_keywords: Vec<String>,
+    ble_name_patterns: Vec<String>,
+    ble_service_uuids_16: Vec<String>,
+    ble_standard_uuids_16: Vec<String>,
+    ble_service_uuids_32: Vec<String>,
+    ble_service_uuids_128: Vec<String>,
+    ble_manufacturer_ids: Vec<String>,
+    ble_ad_patterns: Vec<String>,
+    wifi_vendor_ies: Vec<String>,
+    wps_keywords: Vec<String>,
+    wifi_networks: Vec<String>,
+    ssid_exclusions: Vec<String>,
+    name_exclusions: Vec<String>,
+    /// `(id, table, index)` of every signature, in file order
+    signatures: Vec<(String, &'static str, usize)>,
+    rule_ids: Vec<String>,
+    rule_nodes: Vec<String>,
+    rules: Vec<String>,
+    /// `(signature, rule)` for every signature a rule uses
+    rules_by_sig: Vec<(usize, usize)>,
+}
+
+fn generate(path: &Path, stem: &str) -> Result<String, String> {
+    let json = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let doc: Value = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+    if doc.get("version") != Some(&Value::from(1)) {
+        return Err("expected \"version\": 1".into());
+    }
+    let signatures = doc
+        .get("signatures")
+        .and_then(Value::as_array)
+        .ok_or("missing \"signatures\" array")?;
+
+    let mut pack = Pack::default();
+    // The module adds hand-written tables, so it is part of the version too
+    let module = fs::read(path.with_file_name(format!("{stem}.rs"))).unwrap_or_default();
+    pack.hash = fnv1a(fnv1a(FNV_OFFSET, json.as_bytes()), &module);
+    for sig in signatures {
+        let sig = sig.as_object().ok_or("signature is not an object")?;
+        let id = str_field(sig, "id")?;
+        let valid_id = id.len() >= 2
+            && id
+                .bytes()
+                .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || b == b'_')
+            && id.as_bytes()[0].is_ascii_alphanumeric()
+            && id.as_bytes()[id.len() - 1].is_ascii_alphanumeric();
+        if !valid_id {
+            return Err(format!("invalid signature id `{id}`"));
+        }
+        let constant = format!("SIG_IDX_{}", id.to_ascii_uppercase().replace('-', "_"));
+        if pack.indices.iter().any(|(name, _)| *name == constant) {
+            return Err(format!("duplicate signature id `{id}`"));
+        }
+        let (table, index) =
+            add_signature(&mut pack, sig).map_err(|e| format!("signature `{id}`: {e}"))?;
+        pack.indices.push((constant, index));
+        pack.signatures.push((id.to_string(), table, index));
+    }
+
+    let rules = match doc.get("rules") {
+        None => &[][..],
+        Some(rules) => rules.as_array().ok_or("\"rules\" is not an array")?,
+    };
+    for rule in rules {
+        let rule = rule.as_object().ok_or("rule is not an object")?;
+        let id = str_field(rule, "id")?;
+        add_rule(&mut pack, rule).map_err(|e| format!("rule `{id}`: {e}"))?;
+    }
+
+    Ok(render(&pack, stem))
+}
+
+/// Add one signature to its table and return the table and its index there.
+fn add_signature(
+    pack: &mut Pack,
+    sig: &Map<String, Value>,
+) -> Result<(&'static str, usize), String> {
+    let kind = str_field(sig, "type")?;
+    // Exclusions drop matches rather than make them, so carry no rating
+    if kind == "exclusion" {
+        return add_exclusion(pack, sig);
+    }
+    let description = sig.get("description").and_then(Value::as_str);
+    let meta = meta(sig)?;
+    let min_sightings = match sig.get("min_sightings") {
+        None => 1,
+        Some(v) => v
+            .as_u64()
+            .and_then(|n| u8::try_from(n).ok())
+            .filter(|n| *n >= 1)
+            .ok_or("min_sightings must be 1-255")?,
+    };
+    if min_sightings != 1 && kind != "mac_oui" {
+        return Err("min_sightings is only supported on mac_oui in compiled-in packs".into());
+    }
+
+    match kind {
+        "mac_oui" => {
+            let oui = parse_oui(str_field(sig, "oui")?)?;
+            let vendor = description.ok_or("mac_oui needs a description (the vendor)")?;
+            // The filter looks thresholds up by vendor name
+            if let Some(&existing) = pack.vendor_min.get(vendor) {
+                if existing != min_sightings {
+                    return Err(format!(
+                        "min_sightings {min_sightings} differs from {existing} on other `{vendor}` OUIs"
+                    ));
+                }
+            }
+            pack.vendor_min.insert(vendor.into(), min_sightings);
+            let entry = format!(
+                "([0x{:02X}, 0x{:02X}, 0x{:02X}], {vendor:?}, {meta})",
+                oui[0], oui[1], oui[2]
+            );
+            Ok(push(&mut pack.mac_prefixes, "MacPrefixes", entry))
+        }
+        "wifi_ssid" => {
+            let value = str_field(sig, "value")?;
+            match (str_field(sig, "match")?, case_sensitive(sig)) {
+                (kind @ ("regex" | "glob"), true) => {
+                    let description = description.ok_or("SSID patterns need a description")?;
+                    let glob = if kind == "regex" {
+                        suffix_regex_to_glob(value)?
+                    } else {
+                        value.to_string()
+                    };
+                    let entry = format!(
+                        "crate::defaults::SsidPattern {{ glob: {glob:?}, \
+                         description: {description:?}, meta: {meta} }}"
+                    );
+                    Ok(push(&mut pack.ssid_patterns, "SsidPatterns", entry))
+                }
+                ("exact", true) => Ok(push(
+                    &mut pack.ssid_exact,
+                    "SsidExact",
+                    format!("({value:?}, {meta})"),
+                )),
+                ("contains", false) => {
+                    if value.chars().any(|c| c.is_ascii_uppercase()) {
+                        return Err("case-insensitive SSID keywords must be lowercase".into());
+                    }
+                    Ok(push(
+                        &mut pack.ssid_keywords,
+                        "SsidKeywords",
+                        format!("({value:?}, {meta})"),
+                    ))
+                }
+                _ => Err("wifi_ssid must be case-sensitive regex, glob or exact, or \
+                          case-insensitive contains"
+                    .into()),
+            }
+        }
+        "ble_name" => match (str_field(sig, "match")?, case_sensitive(sig)) {
+            ("contains", false) => {
+                let value = str_field(sig, "value")?;
+                Ok(push(
+                    &mut pack.ble_name_patterns,
+                    "BleNamePatterns",
+                    format!("({value:?}, {meta})"),
+                ))
+            }
+            _ => Err("ble_name must be case-insensitive contains".into()),
+        },
+        "ble_service_uuid" => {
+            let uuid = parse_uuid(str_field(sig, "uuid")?)?;
+            let description = description.ok_or("ble_service_uuid needs a description")?;
+            let Some(uuid) = short_uuid(uuid) else {
+                return Ok(match alias_32(uuid) {
+                    Some(uuid) => push(
+                        &mut pack.ble_service_uuids_32,
+                        "BleServiceUuids32",
+                        format!("(0x{uuid:08X}, {description:?}, {meta})"),
+                    ),
+                    // UUIDs off the Bluetooth base UUID have no short alias
+                    None => push(
+                        &mut pack.ble_service_uuids_128,
+                        "BleServiceUuids128",
+                        format!("(0x{uuid:032X}, {description:?}, {meta})"),
+                    ),
+                });
+            };
+            let entry = format!("(0x{uuid:04X}, {description:?}, {meta})");
+            // SIG-assigned GATT services are reported as `ble_uuid_std`
+            if (0x1800..=0x18FF).contains(&uuid) {
+                Ok(push(
+                    &mut pack.ble_standard_uuids_16,
+                    "BleStandardUuids16",
+                    entry,
+                ))
+            } else {
+                Ok(push(
+                    &mut pack.ble_service_uuids_16,
+                    "BleServiceUuids16",
+                    entry,
+                ))
+            }
+        }
+        "ble_manufacturer_id" => {
+            let id = sig
+                .get("company_id")
+                .and_then(Value::as_u64)
+                .and_then(|n| u16::try_from(n).ok())
+                .ok_or("company_id must be 0-65535")?;
+            let description = description.ok_or("ble_manufacturer_id needs a description")?;
+            let entry = format!("(0x{id:04X}, {description:?}, {meta})");
+            Ok(push(
+                &mut pack.ble_manufacturer_ids,
+                "BleManufacturerIds",
+                entry,
+            ))
+        }
+        "ble_ad_bytes" => {
+            let bytes = parse_pattern_bytes(sig.get("bytes").ok_or("missing `bytes`")?)?;
+            if bytes.is_empty() || bytes.len() > MAX_AD_LEN {
+                return Err(format!(
+                    "ble_ad_bytes patterns must be 1-{MAX_AD_LEN} bytes"
+                ));
+            }
+            let offset = match sig.get("offset") {
+                None => None,
+                Some(v) => Some(
+                    v.as_u64()
+                        .and_then(|n| u16::try_from(n).ok())
+                        .ok_or("offset must be 0-65535")?,
+                ),
+            };
+            let description = description.ok_or("ble_ad_bytes needs a description")?;
+            let bytes: Vec<String> = bytes.iter().map(|b| format!("{b:?}")).collect();
+            let entry = format!(
+                "crate::defaults::AdBytesPattern {{ bytes: &[{}], offset: {offset:?}, \
+                 description: {description:?}, meta: {meta} }}",
+                bytes.join(", ")
+            );
+            Ok(push(&mut pack.ble_ad_patterns, "BleAdPatterns", entry))
+        }
+        "wifi_vendor_ie" => {
+            let oui = parse_oui(str_field(sig, "oui")?)?;
+            let description = description.ok_or("wifi_vendor_ie needs a description")?;
+            let bytes = match sig.get("bytes") {
+                None => Vec::new(),
+                Some(bytes) => parse_pattern_bytes(bytes)?,
+            };
+            if bytes.len() > MAX_VENDOR_IE_DATA {
+                return Err(format!(
+                    "vendor IE patterns match at most {MAX_VENDOR_IE_DATA} bytes after the OUI"
+                ));
+            }
+            let bytes: Vec<String> = bytes.iter().map(|b| format!("{b:?}")).collect();
+            let entry = format!(
+                "crate::defaults::VendorIePattern {{ oui: [0x{:02X}, 0x{:02X}, 0x{:02X}], \
+                 bytes: &[{}], description: {description:?}, meta: {meta} }}",
+                oui[0],
+                oui[1],
+                oui[2],
+                bytes.join(", ")
+            );
+            Ok(push(&mut pack.wifi_vendor_ies, "WifiVendorIes", entry))
+        }
+        "wifi_wps" => {
+            let value = str_field(sig, "value")?;
+            if str_field(sig, "match")? != "contains" || case_sensitive(sig) {
+                return Err("wifi_wps must be case-insensitive contains".into());
+            }
+            if value.chars().any(|c| c.is_ascii_uppercase()) {
+                return Err("case-insensitive WPS keywords must be lowercase".into());
+            }
+            let field = match sig.get("field").and_then(Value::as_str) {
+                None => "None",
+                Some("manufacturer") => "Some(crate::scanner::WpsField::Manufacturer)",
+                Some("model_name") => "Some(crate::scanner::WpsField::ModelName)",
+                Some("device_name") => "Some(crate::scanner::WpsField::DeviceName)",
+                Some(other) => return Err(format!("unknown WPS field `{other}`")),
+            };
+            let description = description.unwrap_or(value);
+            let entry = format!("({field}, {value:?}, {description:?}, {meta})");
+            Ok(push(&mut pack.wps_keywords, "WpsKeywords", entry))
+        }
+        "wifi_network" => {
+            let kind = match str_field(sig, "network")? {
+                "mesh" => "crate::scanner::NetworkKind::Mesh",
+                "p2p_group" => "crate::scanner::NetworkKind::P2pGroup",
+                other => return Err(format!("unknown network `{other}`")),
+            };
+            // Without a value, any network of the kind matches
+            let value = match sig.get("value") {
+                None => "",
+                Some(_) => {
+                    if str_field(sig, "match")? != "contains" || case_sensitive(sig) {
+                        return Err("wifi_network names must be case-insensitive contains".into());
+                    }
+                    str_field(sig, "value")?
+                }
+            };
+            if value.chars().any(|c| c.is_ascii_uppercase()) {
+                return Err("case-insensitive network names must be lowercase".into());
+            }
+            let description = description.ok_or("wifi_network needs a description")?;
+            let entry = format!("({kind}, {value:?}, {description:?}, {meta})");
+            Ok(push(&mut pack.wifi_networks, "WifiNetworks", entry))
+        }
+        other => Err(format!(
+            "`{other}` signatures can't be compiled in; load them at runtime instead"
+        )),
+    }
+}
+
+/// Add an `exclusion` signature to the SSID or name exclusion table.
+fn add_exclusion(
+    pack: &mut Pack,
+    sig: &Map<String, Value>,
+) -> Result<(&'static str, usize), String> {
+    let value = str_field(sig, "value")?;
+    if str_field(sig, "match")? != "contains" || case_sensitive(sig) {
+        return Err("exclusions must be case-insensitive contains".into());
+    }
+    if value.chars().any(|c| c.is_ascii_uppercase()) {
+        return Err("case-insensitive exclusion keywords must be lowercase".into());
+    }
+    let excludes = sig.get("excludes").and_then(Value::as_str);
+    let entry = format!("({value:?}, {excludes:?})");
+    match str_field(sig, "transport")? {
+        "wifi" => Ok(push(&mut pack.ssid_exclusions, "SsidExclusions", entry)),
+        "ble" => Ok(push(&mut pack.name_exclusions, "NameExclusions", entry)),
+        other => Err(format!("unknown transport `{other}`")),
+    }
+}
+
+/// Compile a rule's `detect` expression into the pack's rule nodes.
+fn add_rule(pack: &mut Pack, rule: &Map<String, Value>) -> Result<(), String> {
+    let id = str_field(rule, "id")?;
+    if pack.rule_ids.iter().any(|existing| existing == id) {
+        return Err("duplicate rule id".into());
+    }
+    pack.rule_ids.push(id.to_string());
+    let name = str_field(rule, "name")?;
+    let severity = level(rule, "severity")?;
+    let action = match rule.get("action") {
+        Some(action) => format!("Some({})", rule_action(action)?),
+        None => "None".into(),
+    };
+    let start = pack.rule_nodes.len();
+    let depth = compile_expr(pack, rule.get("detect").ok_or("missing `detect`")?)?;
+    if depth > MAX_RULE_DEPTH {
+        return Err(format!(
+            "expression needs {depth} stack slots, more than {MAX_RULE_DEPTH}"
+        ));
+    }
+    pack.rules.push(format!(
+        "crate::rules::Rule {{ id: {id:?}, name: {name:?}, \
+         severity: crate::defaults::Severity::{severity}, action: {action}, \
+         start: {start}, len: {} }}",
+        pack.rule_nodes.len() - start
+    ));
+    Ok(())
+}
+
+/// A rule's `action` as a `RuleAction` expression
+fn rule_action(action: &Value) -> Result<String, String> {
+    let action = action.as_object().ok_or("`action` is not an object")?;
+    let beeps = action.get("beeps").and_then(Value::as_u64).unwrap_or(1);
+    if beeps > MAX_BEEPS {
+        return Err(format!("`beeps` must be at most {MAX_BEEPS}"));
+    }
+    let pattern = match action.get("pattern").and_then(Value::as_str) {
+        None | Some("short") => "Short",
+        Some("long") => "Long",
+        Some(other) => return Err(format!("unknown beep pattern `{other}`")),
+    };
+    let led = match action.get("led").and_then(Value::as_str) {
+        None => "None".into(),
+        Some(color) => {
+            let variant = LED_COLORS
+                .iter()
+                .find(|(name, _)| *name == color)
+                .ok_or_else(|| format!("unknown LED color `{color}`"))?
+                .1;
+            format!("Some(crate::rules::LedColor::{variant})")
+        }
+    };
+    Ok(format!(
+        "crate::rules::RuleAction {{ beeps: {beeps}, \
+         pattern: crate::rules::BeepPattern::{pattern}, led: {led} }}"
+    ))
+}
+
+/// Append `expr` to the rule nodes in post-order and return the evaluation
+/// stack depth it needs.
+fn compile_expr(pack: &mut Pack, expr: &Value) -> Result<usize, String> {
+    let expr = expr.as_object().ok_or("expression is not an object")?;
+    if let Some(id) = expr.get("sig") {
+        let id = id.as_str().ok_or("`sig` must be a signature id")?;
+        let sig = pack
+            .signatures
+            .iter()
+            .position(|(sig_id, _, _)| sig_id == id)
+            .ok_or_else(|| format!("unknown signature `{id}`"))?;
+        if pack.signatures[sig].1.ends_with("Exclusions") {
+            return Err(format!("`{id}` is an exclusion, which never matches"));
+        }
+        let node = match expr.get("within") {
+            None => format!("Sig({sig})"),
+            Some(secs) => {
+                let secs = secs
+                    .as_u64()
+                    .filter(|secs| (1..=u16::MAX as u64).contains(secs))
+                    .ok_or("`within` must be 1-65535 seconds")?;
+                format!("Within {{ sig: {sig}, secs: {secs} }}")
+            }
+        };
+        pack.rule_nodes
+            .push(format!("crate::rules::ExprNode::{node}"));
+        pack.rules_by_sig.push((sig, pack.rules.len()));
+        return Ok(1);
+    }
+    if let Some(child) = expr.get("not") {
+        let depth = compile_expr(pack, child)?;
+        pack.rule_nodes.push("crate::rules::ExprNode::Not".into());
+        return Ok(depth);
+    }
+    let (op, children) = match (expr.get("anyOf"), expr.get("allOf"), expr.get("of")) {
+        (Some(children), None, None) => ("anyOf", children),
+        (None, Some(children), None) => ("allOf", children),
+        (None, None, Some(children)) if expr.contains_key("atLeast") => ("atLeast", children),
+        _ => return Err("expression must be one of sig, anyOf, allOf, atLeast or not".into()),
+    };
+    let children = children.as_array().ok_or("operands must be an array")?;
+    if children.is_empty() || children.len() > u8::MAX as usize {
+        return Err(format!("{op} takes 1-255 operands"));
+    }
+    let count = children.len();
+    let node = match op {
+        "anyOf" => format!("AnyOf({count})"),
+        "allOf" => format!("AllOf({count})"),
+        _ => {
+            let n = expr["atLeast"]
+                .as_u64()
+                .filter(|&n| n >= 1 && n as usize <= count)
+                .ok_or_else(|| format!("atLeast must be between 1 and its {count} operands"))?;
+            format!("AtLeast {{ n: {n}, count: {count} }}")
+        }
+    };
+    // Operand i is evaluated on top of the i values before it
+    let mut depth = 0;
+    for (i, child) in children.iter().enumerate() {
+        depth = depth.max(i + compile_expr(pack, child)?);
+    }
+    pack.rule_nodes
+        .push(format!("crate::rules::ExprNode::{node}"));
+    Ok(depth)
+}
+
+fn render(pack: &Pack, stem: &str) -> String {
+    let mut out = String::new();
+    writeln!(
+        out,
+        "// Generated by build.rs from src/defaults/{stem}.sigs.json. Do not edit.\n"
+    )
+    .unwrap();
+    for (name, index) in &pack.indices {
+        writeln!(out, "pub const {name}: usize = {index};").unwrap();
+    }
+    writeln!(
+        out,
+        "pub const SIG_COUNT: usize = {};",
+        pack.signatures.len()
+    )
+    .unwrap();
+
+    // Only vendors held back past the first sighting need an entry
+    let vendor_min: Vec<String> = pack
+        .vendor_min
+        .iter()
+        .filter(|(_, min)| **min > 1)
+        .map(|(vendor, min)| format!("({vendor:?}, {min})"))
+        .collect();
+
+    writeln!(
+        out,
+        "\npub const GENERATED: crate::defaults::SignaturePack = crate::defaults::SignaturePack {{"
+    )
+    .unwrap();
+    writeln!(out, "    name: {:?},", stem.replace('_', "-")).unwrap();
+    writeln!(out, "    hash: 0x{:08X},", pack.hash).unwrap();
+    for (field, entries) in [
+        ("mac_prefixes", &pack.mac_prefixes),
+        ("mac_prefix_min_sightings", &vendor_min),
+        ("ssid_patterns", &pack.ssid_patterns),
+        ("ssid_exact", &pack.ssid_exact),
+        ("ssid_keywords", &pack.ssid_keywords),
+        ("ble_name_patterns", &pack.ble_name_patterns),
+        ("ble_service_uuids_16", &pack.ble_service_uuids_16),
+        ("ble_standard_uuids_16", &pack.ble_standard_uuids_16),
+        ("ble_service_uuids_32", &pack.ble_service_uuids_32),
+        ("ble_service_uuids_128", &pack.ble_service_uuids_128),
+        ("ble_manufacturer_ids", &pack.ble_manufacturer_ids),
+        ("ble_ad_patterns", &pack.ble_ad_patterns),
+        ("wifi_vendor_ies", &pack.wifi_vendor_ies),
+        ("wps_keywords", &pack.wps_keywords),
+        ("wifi_networks", &pack.wifi_networks),
+        ("ssid_exclusions", &pack.ssid_exclusions),
+        ("name_exclusions", &pack.name_exclusions),
+    ] {
+        writeln!(out, "    {field}: &[").unwrap();
+        for entry in entries {
+            writeln!(out, "        {entry},").unwrap();
+        }
+        writeln!(out, "    ],").unwrap();
+    }
+    writeln!(out, "    signatures: &[").unwrap();
+    for (_, table, index) in &pack.signatures {
+        writeln!(
+            out,
+            "        (crate::defaults::SigTable::{table}, {index}),"
+        )
+        .unwrap();
+    }
+    writeln!(out, "    ],").unwrap();
+    writeln!(out, "    rules: crate::rules::RuleDb {{").unwrap();
+    for (field, entries) in [("nodes", &pack.rule_nodes), ("rules", &pack.rules)] {
+        writeln!(out, "        {field}: &[").unwrap();
+        for entry in entries {
+            writeln!(out, "            {entry},").unwrap();
+        }
+        writeln!(out, "        ],").unwrap();
+    }
+    let mut by_sig = pack.rules_by_sig.clone();
+    by_sig.sort_unstable();
+    by_sig.dedup();
+    writeln!(out, "        by_sig: &[").unwrap();
+    for (sig, rule) in by_sig {
+        writeln!(out, "            ({sig}, {rule}),").unwrap();
+    }
+    writeln!(out, "        ],").unwrap();
+    writeln!(out, "    }},").unwrap();
+    writeln!(out, "    ..crate::defaults::SignaturePack::EMPTY\n}};").unwrap();
+    out
+}
+
+const FNV_OFFSET: u32 = 0x811C_9DC5;
+
+/// 32-bit FNV-1a, continuing from `hash`
+fn fnv1a(hash: u32, bytes: &[u8]) -> u32 {
+    bytes
+        .iter()
+        .fold(hash, |hash, &b| (hash ^ b as u32).wrapping_mul(0x0100_0193))
+}
+
+/// Append `entry` to the table named `name` (its `SigTable` variant)
+fn push(table: &mut Vec<String>, name: &'static str, entry: String) -> (&'static str, usize) {
+    table.push(entry);
+    (name, table.len() - 1)
+}
+
+fn str_field<'a>(sig: &'a Map<String, Value>, key: &str) -> Result<&'a str, String> {
+    sig.get(key)
+        .and_then(Value::as_str)
+        .ok_or_else(|| format!("missing string field \"{key}\""))
+}
+
+/// The signature's rating as a `SignatureMeta` expression. All three rating
+/// fields are required for compiled-in signatures; `device_class` is optional.
+fn meta(sig: &Map<String, Value>) -> Result<String, String> {
+    let category = match str_field(sig, "category")? {
+        "alpr" => "Alpr",
+        "gunshot_detector" => "GunshotDetector",
+        "camera" => "Camera",
+        "tracker" => "Tracker",
+        "drone" => "Drone",
+        "attack_tool" => "AttackTool",
+        "other" => "Other",
+        other => return Err(format!("unknown category `{other}`")),
+    };
+    let device_class = match sig.get("device_class").and_then(Value::as_str) {
+        None => String::new(),
+        Some(class) => {
+            let variant = match class {
+                "lpr_camera" => "LprCamera",
+                "acoustic_sensor" => "AcousticSensor",
+                "body_camera" => "BodyCamera",
+                "personal_tracker" => "PersonalTracker",
+                "pentest_tool" => "PentestTool",
+                "drone" => "Drone",
+                "unknown" => "Unknown",
+                other => return Err(format!("unknown device_class `{other}`")),
+            };
+            format!(".with_device_class(crate::defaults::DeviceClass::{variant})")
+        }
+    };
+    Ok(format!(
+        "crate::defaults::SignatureMeta::new(crate::defaults::Category::{category}, \
+         crate::defaults::Severity::{}, crate::defaults::Confidence::{}){device_class}",
+        level(sig, "severity")?,
+        level(sig, "confidence")?
+    ))
+}
+
+/// A `low`/`medium`/`high` field as its `Severity`/`Confidence` variant
+fn level(obj: &Map<String, Value>, key: &str) -> Result<&'static str, String> {
+    match str_field(obj, key)? {
+        "low" => Ok("Low"),
+        "medium" => Ok("Medium"),
+        "high" => Ok("High"),
+        other => Err(format!("{key} must be low, medium or high, not `{other}`")),
+    }
+}
+
+/// The schema's `case_sensitive`, default true
+fn case_sensitive(sig: &Map<String, Value>) -> bool {
+    sig.get("case_sensitive")
+        .and_then(Value::as_bool)
+        .unwrap_or(true)
+}
+
+/// Parse a 16-bit ("3100"), 32-bit ("0000fe2c") or full 128-bit service UUID
+/// into its 128-bit value
+fn parse_uuid(s: &str) -> Result<u128, String> {
+    let digits: String = s.chars().filter(|&c| c != '-').collect();
+    match u128::from_str_radix(&digits, 16) {
+        Ok(short) if digits.len() == 4 || digits.len() == 8 && s.len() == 8 => {
+            Ok(BASE_UUID | (short << 96))
+        }
+        Ok(uuid) if digits.len() == 32 && s.len() == 36 => Ok(uuid),
+        _ => Err(format!("invalid service UUID `{s}`")),
+    }
+}
+
+/// Bluetooth base UUID, 00000000-0000-1000-8000-00805f9b34fb
+const BASE_UUID: u128 = 0x00000000_0000_1000_8000_00805F9B34FB;
+
+/// The 16-bit alias of a UUID on the Bluetooth base UUID
+fn short_uuid(uuid: u128) -> Option<u16> {
+    (uuid & !(0xFFFF << 96) == BASE_UUID).then_some((uuid >> 96) as u16)
+}
+
+/// The 32-bit alias of a UUID on the Bluetooth base UUID
+fn alias_32(uuid: u128) -> Option<u32> {
+    (uuid & !(0xFFFF_FFFF << 96) == BASE_UUID).then_some((uuid >> 96) as u32)
+}
+
+/// Parse "B4:1E:52" into OUI bytes
+/// Advertisement bytes the scanner keeps (`scanner::MAX_AD_LEN`)
+const MAX_AD_LEN: usize = 31;
+
+/// Evaluation stack of a rule expression (`rules::MAX_DEPTH`)
+const MAX_RULE_DEPTH: usize = 16;
+
+/// Most beeps a rule `action` may ask for (`rules::MAX_BEEPS`)
+const MAX_BEEPS: u64 = 5;
+
+/// Schema LED color names and their `rules::LedColor` variants
+const LED_COLORS: [(&str, &str); 7] = [
+    ("red", "Red"),
+    ("orange", "Orange"),
+    ("yellow", "Yellow"),
+    ("green", "Green"),
+    ("blue", "Blue"),
+    ("purple", "Purple"),
+    ("white", "White"),
+];
+
+/// Vendor element bytes the scanner keeps after the OUI
+/// (`scanner::MAX_VENDOR_IE_DATA`)
+const MAX_VENDOR_IE_DATA: usize = 8;
+
+/// Parse a pattern's `bytes` array: integers are exact, `null` a wildcard.
+fn parse_pattern_bytes(bytes: &Value) -> Result<Vec<Option<u8>>, String> {
+    bytes
+        .as_array()
+        .ok_or("bytes must be an array")?
+        .iter()
+        .map(|b| match b {
+            Value::Null => Ok(None),
+            b => b
+                .as_u64()
+                .and_then(|n| u8::try_from(n).ok())
+                .map(Some)
+                .ok_or_else(|| format!("invalid pattern byte `{b}`")),
+        })
+        .collect()
+}
+
+fn parse_oui(s: &str) -> Result<[u8; 3], String> {
+    let parts: Vec<&str> = s.split(':').collect();
+    let bytes: Vec<u8> = parts
+        .iter()
+        .filter(|part| part.len() == 2)
+        .filter_map(|part| u8::from_str_radix(part, 16).ok())
+        .collect();
+    match (parts.len(), bytes.as_slice()) {
+        (3, &[a, b, c]) => Ok([a, b, c]),
+        _ => Err(format!("invalid OUI `{s}`")),
+    }
+}
+
+/// Translate the one regex shape the firmware evaluates — a literal prefix
+/// and a fixed-length hex or decimal suffix, e.g. `^Flock-[0-9A-Fa-f]{6}$` —
+/// into the equivalent `SsidPattern` glob.
+fn suffix_regex_to_glob(regex: &str) -> Result<String, String> {
+    let unsupported =
+        || format!("regex `{regex}` is not a literal prefix plus [0-9A-Fa-f]{{n}} or [0-9]{{n}}");
+    let body = regex
+        .strip_prefix('^')
+        .and_then(|r| r.strip_suffix('$'))
+        .ok_or_else(unsupported)?;
+    let (prefix, suffix) = body.split_once('[').ok_or_else(unsupported)?;
+    if prefix.contains(|c: char| "\\.*+?()[]{}|^$".contains(c)) {
+        return Err(unsupported());
+    }
+    let (class, len) = suffix.split_once("]{").ok_or_else(unsupported)?;
+    if !matches!(class, "0-9A-Fa-f" | "0-9") {
+        return Err(unsupported());
+    }
+    let len: usize = len
+        .strip_suffix('}')
+        .and_then(|n| n.parse().ok())
+        .ok_or_else(unsupported)?;
+    Ok(format!("{prefix}{}", format!("[{class}]").repeat(len)))
+}
@@ -0,0 +1,190 @@
+/// Signature-independent SSID heuristics.
+///
+/// Some suspicious networks match no signature: an SSID that looks
+/// machine-generated, one imitating a common public network with lookalike
+/// characters (an evil twin), or a hidden SSID behind capability bits no
+/// ordinary access point sets. [`check_ssid`] flags these; with
+/// `FilterConfig::ssid_heuristics` on, `filter::SsidHeuristics` reports them
+/// as low-severity `ssid_anomaly` matches.
+use crate::hidden;
+
+/// Capability information: infrastructure (access point) BSS
+const CAPABILITY_ESS: u16 = 0x0001;
+
+/// Capability information: independent (ad-hoc) BSS
+const CAPABILITY_IBSS: u16 = 0x0002;
+
+/// Public network names evil twins commonly imitate
+pub const COMMON_SSIDS: &[&str] = &[
+    "xfinitywifi",
+    "attwifi",
+    "Starbucks WiFi",
+    "Google Starbucks",
+    "eduroam",
+    "Boingo Hotspot",
+    "Wayport_Access",
+    "CableWiFi",
+    "optimumwifi",
+    "SpectrumWiFi",
+    "BTWiFi",
+    "Free Public WiFi",
+];
+
+/// Why an SSID looks suspicious
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SsidAnomaly {
+    /// A long run of letters and digits with no pronounceable structure
+    RandomString,
+    /// Reads as the given common network name but isn't it
+    Lookalike(&'static str),
+    /// Hidden SSID with capability bits other than a plain access point's
+    HiddenCapability,
+}
+
+/// The first anomaly `ssid` shows, if any. `capability` is the frame's
+/// capability information, for beacons and probe responses.
+pub fn check_ssid(ssid: &str, capability: Option<u16>) -> Option<SsidAnomaly> {
+    if hidden::is_hidden(ssid) {
+        let capability = capability?;
+        let bss = capability & (CAPABILITY_ESS | CAPABILITY_IBSS);
+        return (bss != CAPABILITY_ESS).then_some(SsidAnomaly::HiddenCapability);
+    }
+    if let Some(name) = lookalike_of(ssid) {
+        return Some(SsidAnomaly::Lookalike(name));
+    }
+    looks_random(ssid).then_some(SsidAnomaly::RandomString)
+}
+
+/// Whether `ssid` looks machine-generated: at least 12 ASCII letters and
+/// digits, switching between upper case, lower case and digits at least
+/// every third character, with under 30% vowels among the letters.
+pub fn looks_random(ssid: &str) -> bool {
+    let bytes = ssid.as_bytes();
+    if bytes.len() < 12 || !bytes.iter().all(u8::is_ascii_alphanumeric) {
+        return false;
+    }
+    let class = |b: &u8| match b {
+        b'A'..=b'Z' => 0,
+        b'a'..=b'z' => 1,
+        _ => 2,
+    };
+    let switches = bytes
+        .windows(2)
+        .filter(|pair| class(&pair[0]) != class(&pair[1]))
+        .count();
+    let letters = bytes.iter().filter(|b| b.is_ascii_alphabetic()).count();
+    let vowels = bytes.iter().filter(|b| b"aeiouAEIOU".contains(b)).count();
+    switches * 3 >= bytes.len() && vowels * 10 < letters * 3
+}
+
+/// The common network `ssid` imitates: one it matches once lookalike
+/// characters, digits standing in for letters, invisible characters and
+/// surrounding spaces are folded away. The genuine name, and variants
+/// differing only in ASCII case, don't count.
+pub fn lookalike_of(ssid: &str) -> Option<&'static str> {
+    COMMON_SSIDS
+        .iter()
+        .copied()
+        .find(|name| !ssid.eq_ignore_ascii_case(name) && folded(ssid).eq(folded(name)))
+}
+
+/// `s` with zero-width characters and surrounding whitespace dropped, then
+/// each character folded to the ASCII letter it resembles
+fn folded(s: &str) -> impl Iterator<Item = char> + '_ {
+    s.trim()
+        .chars()
+        .filter(|c| !matches!(c, '\u{200B}'..='\u{200D}' | '\u{FEFF}'))
+        .map(fold)
+}
+
+/// Characters are written as escapes: most are indistinguishable from the
+/// ASCII letters they fold to.
+fn fold(c: char) -> char {
+    match c {
+        // Cyrillic
+        '\u{0430}' | '\u{0410}' => 'a',
+        '\u{0412}' => 'b',
+        '\u{0435}' | '\u{0415}' | '\u{0451}' => 'e',
+        '\u{043A}' | '\u{041A}' => 'k',
+        '\u{041C}' => 'm',
+        '\u{041D}' => 'h',
+        '\u{043E}' | '\u{041E}' => 'o',
+        '\u{0440}' | '\u{0420}' => 'p',
+        '\u{0441}' | '\u{0421}' => 'c',
+        '\u{0422}' => 't',
+        '\u{0443}' | '\u{0423}' => 'y',
+        '\u{0445}' | '\u{0425}' => 'x',
+        '\u{0455}' | '\u{0405}' => 's',
+        // Cyrillic i and j, Greek iota, script l
+        '\u{0456}' | '\u{0406}' | '\u{0458}' | '\u{0408}' => 'l',
+        '\u{03B9}' | '\u{0399}' | '\u{2113}' => 'l',
+        // Greek
+        '\u{03B1}' | '\u{0391}' => 'a',
+        '\u{0392}' => 'b',
+        '\u{0395}' => 'e',
+        '\u{03BF}' | '\u{039F}' => 'o',
+        '\u{03A1}' => 'p',
+        '\u{03A4}' => 't',
+        // Digits and letters easily mistaken for one another
+        '0' => 'o',
+        '1' | 'i' | 'I' | 'l' | '|' => 'l',
+        '3' => 'e',
+        '5' => 's',
+        _ => c.to_ascii_lowercase(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn random_strings() {
+        assert!(looks_random("xK9qT2mWz7Lp"));
+        assert!(looks_random("7f3c9d2b8c1f"));
+        assert!(!looks_random("MyHomeNetwork"));
+        assert!(!looks_random("SpectrumSetup12"));
+        assert!(!looks_random("NETGEAR47"));
+        assert!(!looks_random("Flock-A1B2C3D4E5"));
+    }
+
+    #[test]
+    fn lookalikes() {
+        assert_eq!(lookalike_of("attw1fi"), Some("attwifi"));
+        assert_eq!(lookalike_of("xfinitywifi "), Some("xfinitywifi"));
+        // Cyrillic a and o
+        assert_eq!(
+            lookalike_of("St\u{0430}rbucks WiFi"),
+            Some("Starbucks WiFi")
+        );
+        assert_eq!(lookalike_of("edur\u{043E}am"), Some("eduroam"));
+        assert_eq!(lookalike_of("attwi\u{200B}fi"), Some("attwifi"));
+        assert_eq!(lookalike_of("attwifi"), None);
+        assert_eq!(lookalike_of("ATTWIFI"), None);
+        assert_eq!(lookalike_of("HomeNet"), None);
+    }
+
+    #[test]
+    fn hidden_ssid_capabilities() {
+        // Plain access point
+        assert_eq!(check_ssid("", Some(0x0411)), None);
+        // Ad-hoc, or neither ESS nor IBSS
+        assert_eq!(
+            check_ssid("", Some(0x0002)),
+            Some(SsidAnomaly::HiddenCapability)
+        );
+        assert_eq!(
+            check_ssid("\0\0\0", Some(0x0000)),
+            Some(SsidAnomaly::HiddenCapability)
+        );
+        // Probe requests carry no capability field
+        assert_eq!(check_ssid("", None), None);
+    }
+
+    #[test]
+    fn ordinary_ssids_pass() {
+        for ssid in ["Flock-A1B2C3", "xfinitywifi", "DIRECT-xy-GoPro", "Home 5G"] {
+            assert_eq!(check_ssid(ssid, Some(0x0001)), None, "{ssid}");
+        }
+    }
+}
@@ -0,0 +1,42 @@
+/// Battery monitor.
+///
+/// Samples the M5StickC Plus2's battery voltage, which GPIO38 sees through a
+/// 1:2 divider, and publishes the charge in [`crate::BATTERY_PCT`] for the
+/// beacon summary. Uncalibrated: good to a few percent, which is all the
+/// advertisement carries.
+use core::sync::atomic::Ordering;
+
+use embassy_time::{Duration, Timer};
+use esp_hal::analog::adc::{Adc, AdcConfig, Attenuation};
+use esp_hal::peripherals::{ADC1, GPIO38};
+
+use crate::{beacon, board};
+
+/// Pin voltage at a full-scale reading with 11 dB attenuation, in millivolts
+const ADC_FULL_SCALE_MV: u32 = 3300;
+
+/// Largest 12-bit reading
+const ADC_MAX: u32 = 4095;
+
+/// Time between samples
+const SAMPLE_INTERVAL_S: u64 = 60;
+
+#[embassy_executor::task]
+pub async fn battery_task(adc: ADC1<'static>, pin: GPIO38<'static>) {
+    let mut config = AdcConfig::new();
+    let mut pin = config.enable_pin(pin, Attenuation::_11dB);
+    let mut adc = Adc::new(adc, config);
+    log::info!("Battery sense on GPIO{}", board::BATTERY_ADC_PIN);
+
+    loop {
+        // A one-shot conversion takes microseconds
+        let raw = loop {
+            if let Ok(raw) = adc.read_oneshot(&mut pin) {
+                break raw as u32;
+            }
+        };
+        let millivolts = raw * ADC_FULL_SCALE_MV / ADC_MAX * board::BATTERY_DIVIDER;
+        crate::BATTERY_PCT.store(beacon::battery_pct(millivolts), Ordering::Relaxed);
+        Timer::after(Duration::from_secs(SAMPLE_INTERVAL_S)).await;
+    }
+}
@@ -0,0 +1,276 @@
+/// Detection summary carried in AirHound's own BLE advertisement.
+///
+/// When enabled, the manufacturer-specific data of the GATT advertisement
+/// carries a compact [`BeaconSummary`] so a companion can glance at the
+/// situation without connecting, and nearby AirHound units can see each
+/// other's state. [`ActiveSeverity`] tracks the severity it advertises, and
+/// [`PeerTable`] rate-limits reports of other units.
+use heapless::Vec;
+
+/// Bluetooth SIG company ID reserved for internal/test use. The [`MAGIC`]
+/// bytes disambiguate AirHound payloads from other users of this ID.
+pub const COMPANY_ID: u16 = 0xFFFF;
+
+/// Payload marker following the company ID
+pub const MAGIC: [u8; 2] = *b"AH";
+
+/// Payload layout version
+pub const VERSION: u8 = 1;
+
+/// Encoded length of the manufacturer data (company ID included); one less
+/// when the battery is not measured
+pub const ENCODED_LEN: usize = 12;
+
+/// Severity value meaning "nothing detected"
+pub const SEVERITY_NONE: u8 = 0;
+
+/// Battery value meaning "not measured", as sent before the byte was
+/// omitted instead
+const BATTERY_UNKNOWN: u8 = 0xFF;
+
+/// How long a match's severity stays in the advertised summary
+pub const ACTIVE_SEVERITY_MS: u32 = 5 * 60 * 1000;
+
+/// LiPo cell voltage read as empty and full, in millivolts
+const BATTERY_EMPTY_MV: u32 = 3300;
+const BATTERY_FULL_MV: u32 = 4150;
+
+/// Rough charge of a single LiPo cell from its voltage, linear between
+/// empty and full
+pub fn battery_pct(millivolts: u32) -> u8 {
+    let above_empty = millivolts.clamp(BATTERY_EMPTY_MV, BATTERY_FULL_MV) - BATTERY_EMPTY_MV;
+    (above_empty * 100 / (BATTERY_FULL_MV - BATTERY_EMPTY_MV)) as u8
+}
+
+/// Snapshot advertised by an AirHound unit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BeaconSummary {
+    pub scanning: bool,
+    /// WiFi matches since boot (saturating)
+    pub wifi_matches: u16,
+    /// BLE matches since boot (saturating)
+    pub ble_matches: u16,
+    /// Highest severity among current detections ([`ActiveSeverity`]),
+    /// [`SEVERITY_NONE`] if none
+    pub severity: u8,
+    /// Battery level in percent, if the board can measure it
+    pub battery_pct: Option<u8>,
+}
+
+impl BeaconSummary {
+    /// Build a summary from the firmware's `u32` match counters, with no
+    /// severity or battery level.
+    pub fn new(scanning: bool, wifi_matches: u32, ble_matches: u32) -> Self {
+        Self {
+            scanning,
+            wifi_matches: wifi_matches.min(u16::MAX as u32) as u16,
+            ble_matches: ble_matches.min(u16::MAX as u32) as u16,
+            severity: SEVERITY_NONE,
+            battery_pct: None,
+        }
+    }
+
+    /// Encode as manufacturer-specific AD data:
+    /// `[company_id: u16, "AH", version, flags, wifi: u16, ble: u16, severity, battery]`,
+    /// leaving out the battery byte when it is not measured.
+    pub fn encode(&self) -> Vec<u8, ENCODED_LEN> {
+        let mut out = Vec::new();
+        let _ = out.extend_from_slice(&COMPANY_ID.to_le_bytes());
+        let _ = out.extend_from_slice(&MAGIC);
+        let _ = out.extend_from_slice(&[VERSION, self.scanning as u8]);
+        let _ = out.extend_from_slice(&self.wifi_matches.to_le_bytes());
+        let _ = out.extend_from_slice(&self.ble_matches.to_le_bytes());
+        let _ = out.push(self.severity);
+        if let Some(pct) = self.battery_pct {
+            let _ = out.push(pct);
+        }
+        out
+    }
+
+    /// Decode manufacturer-specific AD data (company ID included). Returns
+    /// `None` if it is not an AirHound summary of a known version.
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        if data.len() < ENCODED_LEN - 1
+            || u16::from_le_bytes([data[0], data[1]]) != COMPANY_ID
+            || data[2..4] != MAGIC
+            || data[4] != VERSION
+        {
+            return None;
+        }
+        Some(Self {
+            scanning: data[5] & 1 != 0,
+            wifi_matches: u16::from_le_bytes([data[6], data[7]]),
+            ble_matches: u16::from_le_bytes([data[8], data[9]]),
+            severity: data[10],
+            battery_pct: data.get(11).copied().filter(|&pct| pct != BATTERY_UNKNOWN),
+        })
+    }
+}
+
+/// Highest severity among matches within the last [`ACTIVE_SEVERITY_MS`],
+/// for [`BeaconSummary::severity`]. Timestamps are milliseconds since boot
+/// (`u32`, wrapping).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ActiveSeverity {
+    /// Latest match at each severity, low to high
+    last_ms: [Option<u32>; 3],
+}
+
+impl ActiveSeverity {
+    pub const fn new() -> Self {
+        Self { last_ms: [None; 3] }
+    }
+
+    /// Note a match of `severity` (1–3; others are ignored)
+    pub fn record(&mut self, severity: u8, now_ms: u32) {
+        if let Some(last) = (severity as usize)
+            .checked_sub(1)
+            .and_then(|i| self.last_ms.get_mut(i))
+        {
+            *last = Some(now_ms);
+        }
+    }
+
+    /// Highest severity matched within the window, [`SEVERITY_NONE`] if none
+    pub fn current(&self, now_ms: u32) -> u8 {
+        self.last_ms
+            .iter()
+            .rposition(|last| last.is_some_and(|at| now_ms.wrapping_sub(at) < ACTIVE_SEVERITY_MS))
+            .map_or(SEVERITY_NONE, |i| i as u8 + 1)
+    }
+}
+
+/// Remembers the last reported summary of nearby units so each one is
+/// reported when its state changes, or at most once per `min_interval_ms`.
+pub struct PeerTable<const N: usize> {
+    peers: Vec<([u8; 6], BeaconSummary, u32), N>,
+    min_interval_ms: u32,
+}
+
+impl<const N: usize> PeerTable<N> {
+    pub const fn new(min_interval_ms: u32) -> Self {
+        Self {
+            peers: Vec::new(),
+            min_interval_ms,
+        }
+    }
+
+    /// Record a sighting. Returns `true` if it should be reported.
+    /// When the table is full the stalest entry is replaced.
+    pub fn observe(&mut self, mac: &[u8; 6], summary: BeaconSummary, now_ms: u32) -> bool {
+        if let Some(entry) = self.peers.iter_mut().find(|(m, _, _)| m == mac) {
+            let due = now_ms.wrapping_sub(entry.2) >= self.min_interval_ms;
+            if entry.1 == summary && !due {
+                return false;
+            }
+            entry.1 = summary;
+            entry.2 = now_ms;
+            return true;
+        }
+
+        let entry = (*mac, summary, now_ms);
+        if self.peers.push(entry).is_err() {
+            if let Some(stalest) = self
+                .peers
+                .iter_mut()
+                .max_by_key(|(_, _, seen)| now_ms.wrapping_sub(*seen))
+            {
+                *stalest = entry;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ── Encoding ────────────────────────────────────────────────────
+
+    #[test]
+    fn summary_round_trip() {
+        let summary = BeaconSummary {
+            battery_pct: Some(87),
+            severity: 3,
+            ..BeaconSummary::new(true, 12, 70_000)
+        };
+        let bytes = summary.encode();
+        assert_eq!(&bytes[0..5], &[0xFF, 0xFF, b'A', b'H', VERSION]);
+        let decoded = BeaconSummary::decode(&bytes).unwrap();
+        assert_eq!(decoded, summary);
+        assert_eq!(decoded.ble_matches, u16::MAX);
+    }
+
+    #[test]
+    fn unknown_battery_is_left_out() {
+        let bytes = BeaconSummary::new(false, 0, 0).encode();
+        assert_eq!(bytes.len(), ENCODED_LEN - 1);
+        assert_eq!(BeaconSummary::decode(&bytes).unwrap().battery_pct, None);
+        // Older units sent 0xFF instead
+        let mut legacy = bytes.clone();
+        legacy.push(0xFF).unwrap();
+        assert_eq!(BeaconSummary::decode(&legacy).unwrap().battery_pct, None);
+    }
+
+    #[test]
+    fn battery_pct_is_linear_and_clamped() {
+        assert_eq!(battery_pct(3000), 0);
+        assert_eq!(battery_pct(3725), 50);
+        assert_eq!(battery_pct(4150), 100);
+        assert_eq!(battery_pct(4300), 100);
+    }
+
+    #[test]
+    fn decode_rejects_foreign_payloads() {
+        let mut bytes = BeaconSummary::new(true, 1, 1).encode();
+        assert!(BeaconSummary::decode(&bytes[..8]).is_none());
+        bytes[2] = b'X';
+        assert!(BeaconSummary::decode(&bytes).is_none());
+        // Apple manufacturer data
+        assert!(BeaconSummary::decode(&[0x4C, 0x00, 0x12, 0x19, 0, 0, 0, 0, 0, 0, 0, 0]).is_none());
+    }
+
+    // ── ActiveSeverity ──────────────────────────────────────────────
+
+    #[test]
+    fn active_severity_is_highest_recent_match() {
+        let mut active = ActiveSeverity::new();
+        assert_eq!(active.current(0), SEVERITY_NONE);
+        active.record(3, 0);
+        active.record(2, 60_000);
+        assert_eq!(active.current(60_000), 3);
+        // The high match ages out, the medium one is still recent
+        assert_eq!(active.current(ACTIVE_SEVERITY_MS), 2);
+        assert_eq!(active.current(60_000 + ACTIVE_SEVERITY_MS), SEVERITY_NONE);
+        active.record(0, 0);
+        active.record(9, 0);
+        assert_eq!(active.current(60_000 + ACTIVE_SEVERITY_MS), SEVERITY_NONE);
+    }
+
+    // ── PeerTable ───────────────────────────────────────────────────
+
+    #[test]
+    fn peer_reported_on_change_or_interval() {
+        let mut table = PeerTable::<4>::new(10_000);
+        let mac = [1, 2, 3, 4, 5, 6];
+        let summary = BeaconSummary::new(true, 1, 0);
+        assert!(table.observe(&mac, summary, 0));
+        assert!(!table.observe(&mac, summary, 1_000));
+        assert!(table.observe(&mac, BeaconSummary::new(true, 2, 0), 2_000));
+        assert!(!table.observe(&mac, BeaconSummary::new(true, 2, 0), 3_000));
+        assert!(table.observe(&mac, BeaconSummary::new(true, 2, 0), 12_000));
+    }
+
+    #[test]
+    fn full_table_evicts_stalest_peer() {
+        let mut table = PeerTable::<2>::new(10_000);
+        let summary = BeaconSummary::new(true, 0, 0);
+        assert!(table.observe(&[1; 6], summary, 0));
+        assert!(table.observe(&[2; 6], summary, 500));
+        assert!(table.observe(&[3; 6], summary, 1_000));
+        // [2; 6] survived eviction, [1; 6] did not
+        assert!(!table.observe(&[2; 6], summary, 1_500));
+        assert!(table.observe(&[1; 6], summary, 1_600));
+    }
+}
@@ -0,0 +1,228 @@
+/// Coarse bearing from two sensors' RSSI differential.
+///
+/// Two AirHound units with known placement — front and back of a car, or left
+/// and right pockets — see the same device at slightly different strengths;
+/// the body or vehicle between them shadows the far unit by several dB. A
+/// [`DualSensor`] pairs near-simultaneous observations of one MAC from both
+/// units and reports which side the device is on.
+///
+/// Observations from the other unit arrive through the companion, which
+/// relays both units' messages, so this runs wherever both streams meet.
+/// The firmware doesn't use it: a unit only sees its own readings.
+use heapless::Vec;
+
+/// Axis the two sensors are placed along. Sensor A is the front (or left)
+/// unit, sensor B the back (or right) one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    FrontBack,
+    LeftRight,
+}
+
+/// Which of the pair produced an observation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sensor {
+    A,
+    B,
+}
+
+/// Coarse direction toward a device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bearing {
+    Front,
+    Back,
+    Left,
+    Right,
+    /// Difference below the threshold — abeam, or too close to call
+    Ambiguous,
+}
+
+impl Bearing {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Bearing::Front => "front",
+            Bearing::Back => "back",
+            Bearing::Left => "left",
+            Bearing::Right => "right",
+            Bearing::Ambiguous => "ambiguous",
+        }
+    }
+}
+
+/// Geometry and calibration of a sensor pair.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DualSensor {
+    pub axis: Axis,
+    /// Added to sensor B's RSSI to equalize the two units (antenna and
+    /// mounting differences), measured with a device equidistant from both
+    pub b_offset_db: i8,
+    /// Minimum differential to call a side
+    pub threshold_db: u8,
+    /// Maximum time between the two observations to pair them
+    pub max_skew_ms: u32,
+}
+
+impl DualSensor {
+    pub const fn new(axis: Axis) -> Self {
+        Self {
+            axis,
+            b_offset_db: 0,
+            threshold_db: 6,
+            max_skew_ms: 2_000,
+        }
+    }
+
+    /// Bearing from one RSSI reading per sensor.
+    pub fn bearing(&self, rssi_a: i8, rssi_b: i8) -> Bearing {
+        let diff = rssi_a as i16 - (rssi_b as i16 + self.b_offset_db as i16);
+        if diff.unsigned_abs() < self.threshold_db as u16 {
+            return Bearing::Ambiguous;
+        }
+        match (self.axis, diff > 0) {
+            (Axis::FrontBack, true) => Bearing::Front,
+            (Axis::FrontBack, false) => Bearing::Back,
+            (Axis::LeftRight, true) => Bearing::Left,
+            (Axis::LeftRight, false) => Bearing::Right,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Reading {
+    rssi: i8,
+    ts_ms: u32,
+}
+
+/// Pairs observations of up to `N` devices from both sensors.
+pub struct BearingEstimator<const N: usize> {
+    sensors: DualSensor,
+    /// (MAC, latest from A, latest from B)
+    devices: Vec<([u8; 6], Option<Reading>, Option<Reading>), N>,
+}
+
+impl<const N: usize> BearingEstimator<N> {
+    pub const fn new(sensors: DualSensor) -> Self {
+        Self {
+            sensors,
+            devices: Vec::new(),
+        }
+    }
+
+    /// Record an observation. Returns a bearing once both sensors have seen
+    /// `mac` within `max_skew_ms` of each other. When the table is full the
+    /// device seen longest ago is replaced.
+    pub fn observe(
+        &mut self,
+        sensor: Sensor,
+        mac: &[u8; 6],
+        rssi: i8,
+        ts_ms: u32,
+    ) -> Option<Bearing> {
+        let reading = Some(Reading { rssi, ts_ms });
+        let index = match self.devices.iter().position(|(m, _, _)| m == mac) {
+            Some(index) => index,
+            None => {
+                let entry = (*mac, None, None);
+                if self.devices.push(entry).is_err() {
+                    let stalest = self
+                        .devices
+                        .iter()
+                        .enumerate()
+                        .max_by_key(|(_, (_, a, b))| {
+                            let last = a.iter().chain(b.iter()).map(|r| r.ts_ms).max();
+                            last.map_or(u32::MAX, |t| ts_ms.wrapping_sub(t))
+                        })
+                        .map(|(i, _)| i)?;
+                    self.devices[stalest] = entry;
+                    stalest
+                } else {
+                    self.devices.len() - 1
+                }
+            }
+        };
+
+        let entry = &mut self.devices[index];
+        match sensor {
+            Sensor::A => entry.1 = reading,
+            Sensor::B => entry.2 = reading,
+        }
+        let (a, b) = (entry.1?, entry.2?);
+        let skew = a.ts_ms.abs_diff(b.ts_ms);
+        (skew <= self.sensors.max_skew_ms).then(|| self.sensors.bearing(a.rssi, b.rssi))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAC: [u8; 6] = [0x7C, 0x11, 0x22, 0x33, 0x44, 0x55];
+
+    // ── DualSensor ──────────────────────────────────────────────────
+
+    #[test]
+    fn stronger_sensor_gives_side() {
+        let car = DualSensor::new(Axis::FrontBack);
+        assert_eq!(car.bearing(-55, -70), Bearing::Front);
+        assert_eq!(car.bearing(-72, -60), Bearing::Back);
+        let pockets = DualSensor::new(Axis::LeftRight);
+        assert_eq!(pockets.bearing(-50, -65), Bearing::Left);
+        assert_eq!(pockets.bearing(-65, -50), Bearing::Right);
+    }
+
+    #[test]
+    fn small_difference_is_ambiguous() {
+        let car = DualSensor::new(Axis::FrontBack);
+        assert_eq!(car.bearing(-60, -64), Bearing::Ambiguous);
+    }
+
+    #[test]
+    fn calibration_offset_applies_to_b() {
+        let car = DualSensor {
+            b_offset_db: 8,
+            ..DualSensor::new(Axis::FrontBack)
+        };
+        // B reads 8 dB low; raw -60/-70 is effectively -60/-62
+        assert_eq!(car.bearing(-60, -70), Bearing::Ambiguous);
+    }
+
+    // ── Estimator ───────────────────────────────────────────────────
+
+    #[test]
+    fn needs_both_sensors_within_skew() {
+        let mut est = BearingEstimator::<4>::new(DualSensor::new(Axis::FrontBack));
+        assert_eq!(est.observe(Sensor::A, &MAC, -55, 1_000), None);
+        assert_eq!(
+            est.observe(Sensor::B, &MAC, -70, 1_500),
+            Some(Bearing::Front)
+        );
+        // Latest A reading is now too old to pair
+        assert_eq!(est.observe(Sensor::B, &MAC, -50, 5_000), None);
+        assert_eq!(
+            est.observe(Sensor::A, &MAC, -70, 5_200),
+            Some(Bearing::Back)
+        );
+    }
+
+    #[test]
+    fn devices_are_tracked_separately() {
+        let mut est = BearingEstimator::<4>::new(DualSensor::new(Axis::LeftRight));
+        est.observe(Sensor::A, &MAC, -50, 0);
+        assert_eq!(est.observe(Sensor::B, &[1; 6], -80, 100), None);
+        assert_eq!(est.observe(Sensor::B, &MAC, -80, 100), Some(Bearing::Left));
+    }
+
+    #[test]
+    fn full_table_replaces_stalest_device() {
+        let mut est = BearingEstimator::<2>::new(DualSensor::new(Axis::FrontBack));
+        est.observe(Sensor::A, &[1; 6], -50, 0);
+        est.observe(Sensor::A, &[2; 6], -50, 1_000);
+        est.observe(Sensor::A, &[3; 6], -50, 2_000);
+        // [1; 6] was evicted, so its B reading has nothing to pair with
+        assert_eq!(est.observe(Sensor::B, &[1; 6], -70, 2_100), None);
+        assert_eq!(
+            est.observe(Sensor::B, &[3; 6], -70, 2_100),
+            Some(Bearing::Front)
+        );
+    }
+}
@@ -19,12 +19,20 @@ mod hw {
     pub const BUZZER_FREQ_HZ: u32 = 2000;
     pub const BUZZER_BEEP_MS: u64 = 200;
     pub const BOARD_NAME: &str = "xiao_esp32s3";
+
+    // microSD slot on the Sense expansion board (SPI) — peripherals are passed by type
+    pub const SD_CS: u8 = 21;
+    pub const SD_SCK: u8 = 7;
+    pub const SD_MISO: u8 = 8;
+    pub const SD_MOSI: u8 = 9;
+
+    pub const HAS_BATTERY_SENSE: bool = false;
 }
 
 #[allow(dead_code)]
 #[cfg(feature = "m5stickc")]
 mod hw {
-    pub const LED_PIN: u8 = 10; // Built-in LED
+    pub const LED_PIN: u8 = 10; // Built-in red LED, active low
     pub const HAS_PSRAM: bool = false;
     pub const HAS_GPS_HEADER: bool = false;
     pub const HAS_DISPLAY: bool = true;
@@ -32,11 +40,17 @@ mod hw {
     pub const DISPLAY_WIDTH: u16 = 135;
     pub const DISPLAY_HEIGHT: u16 = 240;
     pub const BUZZER_PIN: u8 = 2;
+    pub const BUTTON_A_PIN: u8 = 37; // Front button, active low
     pub const BOARD_NAME: &str = "m5stickc_plus2";
 
     /// GPIO4 must be held HIGH to keep the device powered on
     pub const POWER_HOLD_PIN: u8 = 4;
 
+    /// Battery voltage through a 1:2 divider, on ADC1
+    pub const HAS_BATTERY_SENSE: bool = true;
+    pub const BATTERY_ADC_PIN: u8 = 38;
+    pub const BATTERY_DIVIDER: u32 = 2;
+
     // Display SPI pins (ST7789V2) — peripherals are passed by type
     pub const DISPLAY_MOSI: u8 = 15;
     pub const DISPLAY_CLK: u8 = 13;
@@ -54,6 +68,7 @@ mod hw {
 #[cfg(not(any(feature = "xiao", feature = "m5stickc")))]
 mod hw {
     pub const BOARD_NAME: &str = "unknown";
+    pub const HAS_BATTERY_SENSE: bool = false;
 }
 
 pub use hw::*;
@@ -1,8 +1,9 @@
 /// Buzzer driver using LEDC PWM.
 ///
 /// Drives a passive buzzer at the board-configured frequency and GPIO pin.
-/// The buzzer task waits for signals on `BUZZER_SIGNAL` and produces a short
-/// beep when a surveillance device match is detected.
+/// The buzzer task waits for signals on `BUZZER_SIGNAL` and plays the
+/// `RuleAction` each carries — the beep count and length the matched
+/// detection rules chose (`rules::alert_action`).
 use core::sync::atomic::Ordering;
 
 use embassy_time::{Duration, Timer};
@@ -13,6 +14,7 @@ use esp_hal::ledc::{Ledc, LowSpeed};
 use esp_hal::time::Rate;
 
 use crate::board;
+use crate::rules::BeepPattern;
 
 #[cfg(all(feature = "m5stickc", feature = "xiao"))]
 compile_error!("Only one board feature (m5stickc or xiao) may be enabled at a time");
@@ -52,15 +54,24 @@ pub async fn buzzer_task(
     let rx = crate::BUZZER_SIGNAL.receiver();
 
     loop {
-        rx.receive().await;
+        let action = rx.receive().await;
 
         if !crate::BUZZER_ENABLED.load(Ordering::Relaxed) {
             continue;
         }
 
-        // 50% duty = loudest for passive buzzer
-        channel0.set_duty(50).unwrap();
-        Timer::after(Duration::from_millis(board::BUZZER_BEEP_MS)).await;
-        channel0.set_duty(0).unwrap();
+        let beep_ms = match action.pattern {
+            BeepPattern::Short => board::BUZZER_BEEP_MS,
+            BeepPattern::Long => board::BUZZER_BEEP_MS * 3,
+        };
+        for beep in 0..action.beeps {
+            if beep > 0 {
+                Timer::after(Duration::from_millis(board::BUZZER_BEEP_MS)).await;
+            }
+            // 50% duty = loudest for passive buzzer
+            channel0.set_duty(50).unwrap();
+            Timer::after(Duration::from_millis(beep_ms)).await;
+            channel0.set_duty(0).unwrap();
+        }
     }
 }
@@ -0,0 +1,259 @@
+/// Raw bytes behind recent matches.
+///
+/// A scan message says why a device matched, not what it sent. For the last
+/// [`EVIDENCE_SLOTS`] reported matches, [`EvidenceRing`] keeps what was on
+/// the air: the leading [`MAX_EVIDENCE_LEN`] bytes of the 802.11 frame, or
+/// the BLE advertising data. The firmware answers `get_evidence` with one
+/// `evidence` message per entry, oldest first; std hosts can write the ring
+/// out with [`EvidenceRing::pcap`].
+use heapless::{Deque, String, Vec};
+
+use crate::correlate::Radio;
+use crate::scanner::{BleEvent, WiFiEvent};
+
+/// Matches kept
+pub const EVIDENCE_SLOTS: usize = 8;
+
+/// Bytes kept per match — the header and leading elements of a beacon or
+/// probe
+pub const MAX_EVIDENCE_LEN: usize = 128;
+
+/// An entry's bytes as lowercase hex
+pub type EvidenceHex = String<{ 2 * MAX_EVIDENCE_LEN }>;
+
+/// pcap link type for BLE link-layer packets behind a pseudo-header
+#[cfg(feature = "std")]
+pub const LINKTYPE_BLUETOOTH_LE_LL_WITH_PHDR: u32 = 256;
+
+/// Access address of every advertising channel packet
+#[cfg(feature = "std")]
+const ADV_ACCESS_ADDRESS: u32 = 0x8E89_BED6;
+
+/// Pseudo-header flags: packet dewhitened, signal power valid
+#[cfg(feature = "std")]
+const PHDR_FLAGS: u16 = 0x0001 | 0x0002;
+
+/// The bytes of one matched frame or advertisement
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Evidence {
+    pub radio: Radio,
+    /// Device the match was reported for
+    pub mac: [u8; 6],
+    /// Random BLE address
+    pub mac_randomized: bool,
+    /// WiFi channel; none for BLE
+    pub channel: Option<u8>,
+    pub rssi: i8,
+    /// Length of the whole frame or advertising data; `data` may hold less
+    pub len: u16,
+    /// The 802.11 frame without FCS, or the AD structures
+    pub data: Vec<u8, MAX_EVIDENCE_LEN>,
+    /// Uptime in milliseconds when received
+    pub ts: u32,
+}
+
+impl Evidence {
+    pub fn wifi(event: &WiFiEvent, ts: u32) -> Self {
+        Self {
+            radio: Radio::WiFi,
+            mac: event.mac,
+            mac_randomized: false,
+            channel: Some(event.channel),
+            rssi: event.rssi,
+            len: event.raw_len,
+            data: head(&event.raw),
+            ts,
+        }
+    }
+
+    pub fn ble(event: &BleEvent, ts: u32) -> Self {
+        Self {
+            radio: Radio::Ble,
+            mac: event.mac,
+            mac_randomized: event.mac_randomized,
+            channel: None,
+            rssi: event.rssi,
+            len: event.ad_data.len() as u16,
+            data: head(&event.ad_data),
+            ts,
+        }
+    }
+
+    pub fn hex(&self) -> EvidenceHex {
+        const DIGITS: &[u8; 16] = b"0123456789abcdef";
+        let mut out = EvidenceHex::new();
+        for &byte in &self.data {
+            let _ = out.push(DIGITS[(byte >> 4) as usize] as char);
+            let _ = out.push(DIGITS[(byte & 0x0F) as usize] as char);
+        }
+        out
+    }
+}
+
+/// The first [`MAX_EVIDENCE_LEN`] of `data`
+fn head(data: &[u8]) -> Vec<u8, MAX_EVIDENCE_LEN> {
+    Vec::from_slice(&data[..data.len().min(MAX_EVIDENCE_LEN)]).unwrap_or_default()
+}
+
+/// The last `N` matches' bytes, oldest first
+#[derive(Debug, Clone)]
+pub struct EvidenceRing<const N: usize = EVIDENCE_SLOTS> {
+    entries: Deque<Evidence, N>,
+}
+
+impl<const N: usize> EvidenceRing<N> {
+    pub const fn new() -> Self {
+        Self {
+            entries: Deque::new(),
+        }
+    }
+
+    /// Keep `evidence`, dropping the oldest entry once full.
+    pub fn push(&mut self, evidence: Evidence) {
+        if self.entries.is_full() {
+            self.entries.pop_front();
+        }
+        let _ = self.entries.push_back(evidence);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Evidence> {
+        self.entries.iter()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const N: usize> EvidenceRing<N> {
+    /// The entries from `radio` as a pcap, timestamped from `boot_ms` (Unix
+    /// time of boot in milliseconds). WiFi frames are raw 802.11; BLE
+    /// advertising data goes out as an ADV_IND packet from the entry's
+    /// address, behind a pseudo-header carrying the RSSI. The RF channel is
+    /// not known and left 0, and the CRC is zeroed and marked unchecked.
+    pub fn pcap(&self, radio: Radio, boot_ms: u64) -> std::vec::Vec<u8> {
+        use crate::evidence::{pcap_header, pcap_record, LINKTYPE_IEEE802_11};
+
+        let linktype = match radio {
+            Radio::WiFi => LINKTYPE_IEEE802_11,
+            Radio::Ble => LINKTYPE_BLUETOOTH_LE_LL_WITH_PHDR,
+        };
+        let mut out = pcap_header(linktype);
+        for entry in self.iter().filter(|e| e.radio == radio) {
+            let ts_ms = boot_ms + entry.ts as u64;
+            match radio {
+                Radio::WiFi => pcap_record(&mut out, ts_ms, &entry.data, entry.len as usize),
+                Radio::Ble => {
+                    let packet = adv_packet(entry);
+                    pcap_record(&mut out, ts_ms, &packet, packet.len());
+                }
+            }
+        }
+        out
+    }
+}
+
+impl<const N: usize> Default for EvidenceRing<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A BLE entry as a link-layer ADV_IND packet with its pseudo-header
+#[cfg(feature = "std")]
+fn adv_packet(entry: &Evidence) -> std::vec::Vec<u8> {
+    let mut packet = std::vec::Vec::with_capacity(25 + entry.data.len());
+    packet.push(0); // RF channel
+    packet.push(entry.rssi as u8);
+    packet.push(0); // noise power
+    packet.push(0); // access address offenses
+    packet.extend_from_slice(&ADV_ACCESS_ADDRESS.to_le_bytes());
+    packet.extend_from_slice(&PHDR_FLAGS.to_le_bytes());
+    packet.extend_from_slice(&ADV_ACCESS_ADDRESS.to_le_bytes());
+    // PDU type 0 (ADV_IND), TxAdd set for a random address
+    packet.push(if entry.mac_randomized { 0x40 } else { 0x00 });
+    packet.push((6 + entry.data.len()) as u8);
+    // Addresses are kept as sent, least significant byte first
+    packet.extend_from_slice(&entry.mac);
+    packet.extend_from_slice(&entry.data);
+    packet.extend_from_slice(&[0; 3]); // CRC
+    packet
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ble(n: u8) -> Evidence {
+        Evidence {
+            radio: Radio::Ble,
+            mac: [n, 0x22, 0x33, 0x44, 0x55, 0xC6],
+            mac_randomized: true,
+            channel: None,
+            rssi: -60,
+            len: 3,
+            data: Vec::from_slice(&[0x02, 0x01, n]).unwrap(),
+            ts: n as u32 * 1000,
+        }
+    }
+
+    #[test]
+    fn ring_drops_the_oldest() {
+        let mut ring = EvidenceRing::<2>::new();
+        assert!(ring.is_empty());
+        for n in 1..=3 {
+            ring.push(ble(n));
+        }
+        assert_eq!(ring.len(), 2);
+        let kept: std::vec::Vec<_> = ring.iter().map(|e| e.data[2]).collect();
+        assert_eq!(kept, [2, 3]);
+    }
+
+    #[test]
+    fn hex_is_lowercase() {
+        let mut evidence = ble(0xAB);
+        evidence.data = Vec::from_slice(&[0x00, 0x0F, 0xAB]).unwrap();
+        assert_eq!(evidence.hex().as_str(), "000fab");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn pcap_per_radio() {
+        let mut ring = EvidenceRing::<4>::new();
+        ring.push(ble(1));
+        ring.push(Evidence {
+            radio: Radio::WiFi,
+            mac: [0xB4, 0x1E, 0x52, 0x01, 0x02, 0x03],
+            mac_randomized: false,
+            channel: Some(6),
+            rssi: -50,
+            len: 300,
+            data: Vec::from_slice(&[0x80, 0x00]).unwrap(),
+            ts: 2_500,
+        });
+        let boot_ms = 1_767_225_600_000;
+
+        let wifi = ring.pcap(Radio::WiFi, boot_ms);
+        assert_eq!(&wifi[20..24], &105u32.to_le_bytes());
+        assert_eq!(&wifi[24..28], &1_767_225_602u32.to_le_bytes());
+        assert_eq!(&wifi[28..32], &500_000u32.to_le_bytes());
+        // Captured length, then the frame's
+        assert_eq!(&wifi[32..40], &[2, 0, 0, 0, 0x2C, 0x01, 0, 0]);
+        assert_eq!(&wifi[40..], &[0x80, 0x00]);
+
+        let ble = ring.pcap(Radio::Ble, boot_ms);
+        assert_eq!(&ble[20..24], &256u32.to_le_bytes());
+        let packet = &ble[40..];
+        assert_eq!(packet.len(), 10 + 4 + 2 + 6 + 3 + 3);
+        assert_eq!(packet[1] as i8, -60);
+        assert_eq!(&packet[10..14], &[0xD6, 0xBE, 0x89, 0x8E]);
+        assert_eq!(&packet[14..16], &[0x40, 9]);
+        assert_eq!(&packet[16..22], &[1, 0x22, 0x33, 0x44, 0x55, 0xC6]);
+        assert_eq!(&packet[22..25], &[0x02, 0x01, 1]);
+    }
+}
@@ -0,0 +1,308 @@
+/// 2.4 GHz WiFi channel plans per regulatory region.
+///
+/// Which channels the sniffer hops depends on where the device is used:
+/// channels 12–13 are not allowed in the US, and Japan adds channel 14.
+/// The region is part of the persisted [`crate::filter::FilterConfig`] and set
+/// with the `set_region` host command; the firmware also hands the matching
+/// country code to the WiFi driver so it accepts the channels.
+///
+/// A [`ChannelMask`] (`set_channel_filter`) narrows the plan further, to
+/// skip channels nobody uses locally or to camp on a known camera's channel.
+///
+/// How long each channel is listened to adapts to what it carries: a
+/// [`DwellScheduler`] lengthens the dwell on channels producing matches or
+/// heavy traffic and shortens it on dead ones.
+use serde::{Deserialize, Serialize};
+
+use crate::scanner::{DEFAULT_DWELL_MS, MAX_CHANNEL};
+
+/// Shortest dwell, on a channel where nothing was heard
+pub const MIN_DWELL_MS: u64 = 50;
+
+/// Dwell on a channel that just produced a match
+pub const MAX_DWELL_MS: u64 = 400;
+
+/// Dwell a busy channel tends toward
+pub const BUSY_DWELL_MS: u64 = 2 * DEFAULT_DWELL_MS;
+
+/// Frame rate (frames/s) above which a channel counts as busy
+pub const BUSY_FRAMES_PER_S: u64 = 50;
+
+/// Every this many sweeps each channel gets at least [`DEFAULT_DWELL_MS`],
+/// so a beacon starting up on a dead channel is still caught
+pub const PROBE_SWEEPS: u8 = 8;
+
+/// Regulatory region selecting the 2.4 GHz channel plan
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Region {
+    /// United States / Canada: channels 1–11
+    Fcc,
+    /// Europe and most other countries: channels 1–13
+    #[default]
+    Etsi,
+    /// Japan: channels 1–14
+    Mkk,
+}
+
+impl Region {
+    pub const ALL: [Region; 3] = [Region::Fcc, Region::Etsi, Region::Mkk];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Region::Fcc => "fcc",
+            Region::Etsi => "etsi",
+            Region::Mkk => "mkk",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|region| region.as_str() == name)
+    }
+
+    /// Channels to hop, in order
+    pub fn channels(self) -> &'static [u8] {
+        const ALL_CHANNELS: [u8; 14] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14];
+        match self {
+            Region::Fcc => &ALL_CHANNELS[..11],
+            Region::Etsi => &ALL_CHANNELS[..13],
+            Region::Mkk => &ALL_CHANNELS,
+        }
+    }
+
+    pub fn allows(self, channel: u8) -> bool {
+        self.channels().contains(&channel)
+    }
+
+    /// ISO 3166 country code for the WiFi driver, NUL-terminated
+    pub fn country_code(self) -> &'static [u8; 3] {
+        match self {
+            Region::Fcc => b"US\0",
+            Region::Etsi => b"EU\0",
+            Region::Mkk => b"JP\0",
+        }
+    }
+}
+
+/// Allowlist of 2.4 GHz channels, bit `n` for channel `n`. Channels the
+/// region doesn't allow stay off regardless.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ChannelMask(u16);
+
+impl ChannelMask {
+    /// Every channel, channels 1–14
+    pub const ALL: Self = Self(0x7FFE);
+
+    /// Mask of `channels`, rejecting an empty list or a channel outside 1–14
+    pub fn from_channels(channels: &[u8]) -> Option<Self> {
+        if channels.is_empty() {
+            return None;
+        }
+        let mut bits = 0;
+        for &ch in channels {
+            if !(1..=14).contains(&ch) {
+                return None;
+            }
+            bits |= 1 << ch;
+        }
+        Some(Self(bits))
+    }
+
+    pub fn allows(self, channel: u8) -> bool {
+        channel < 16 && self.0 & (1 << channel) != 0
+    }
+
+    /// `region`'s channels that pass the mask, in hop order
+    pub fn filter(self, region: Region) -> impl Iterator<Item = u8> {
+        region
+            .channels()
+            .iter()
+            .copied()
+            .filter(move |&ch| self.allows(ch))
+    }
+}
+
+impl Default for ChannelMask {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+/// One channel's dwell and the activity totals at its last visit
+#[derive(Debug, Clone, Copy)]
+struct ChannelDwell {
+    dwell_ms: u64,
+    frames: u32,
+    matches: u32,
+}
+
+/// Per-channel dwell times for the hop task, adapted after every visit from
+/// the channel's frame and match counts (`scanner::ChannelStats`). A match
+/// raises the dwell to [`MAX_DWELL_MS`]; otherwise it moves halfway toward
+/// [`BUSY_DWELL_MS`] on a busy channel, [`DEFAULT_DWELL_MS`] on a quiet one,
+/// or [`MIN_DWELL_MS`] on a dead one.
+pub struct DwellScheduler {
+    channels: [ChannelDwell; MAX_CHANNEL as usize],
+    sweep: u8,
+}
+
+impl DwellScheduler {
+    pub const fn new() -> Self {
+        Self {
+            channels: [ChannelDwell {
+                dwell_ms: DEFAULT_DWELL_MS,
+                frames: 0,
+                matches: 0,
+            }; MAX_CHANNEL as usize],
+            sweep: 0,
+        }
+    }
+
+    /// Count a new sweep over the channel plan.
+    pub fn next_sweep(&mut self) {
+        self.sweep = (self.sweep + 1) % PROBE_SWEEPS;
+    }
+
+    /// How long to listen on `channel` this visit.
+    pub fn dwell_ms(&self, channel: u8) -> u64 {
+        let Some(slot) = self.slot(channel) else {
+            return DEFAULT_DWELL_MS;
+        };
+        if self.sweep == 0 {
+            slot.dwell_ms.max(DEFAULT_DWELL_MS)
+        } else {
+            slot.dwell_ms
+        }
+    }
+
+    /// Account a visit of `dwell_ms` to `channel`, given its running frame
+    /// and match totals, and set its next dwell.
+    pub fn record(&mut self, channel: u8, dwell_ms: u64, frames: u32, matches: u32) {
+        let Some(index) = (channel as usize).checked_sub(1) else {
+            return;
+        };
+        let Some(slot) = self.channels.get_mut(index) else {
+            return;
+        };
+        let heard = frames.wrapping_sub(slot.frames) as u64;
+        let matched = matches.wrapping_sub(slot.matches);
+        slot.frames = frames;
+        slot.matches = matches;
+
+        slot.dwell_ms = if matched > 0 {
+            MAX_DWELL_MS
+        } else {
+            let target = if heard == 0 {
+                MIN_DWELL_MS
+            } else if heard * 1000 >= BUSY_FRAMES_PER_S * dwell_ms {
+                BUSY_DWELL_MS
+            } else {
+                DEFAULT_DWELL_MS
+            };
+            (slot.dwell_ms + target) / 2
+        };
+    }
+
+    fn slot(&self, channel: u8) -> Option<&ChannelDwell> {
+        self.channels.get((channel as usize).checked_sub(1)?)
+    }
+}
+
+impl Default for DwellScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn channel_plans() {
+        assert_eq!(Region::Fcc.channels().last(), Some(&11));
+        assert_eq!(Region::Etsi.channels().len(), 13);
+        assert_eq!(Region::Mkk.channels().last(), Some(&14));
+        assert!(!Region::Fcc.allows(12));
+        assert!(!Region::Etsi.allows(14));
+        assert!(Region::Mkk.allows(14));
+        assert!(!Region::Mkk.allows(0));
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for region in Region::ALL {
+            assert_eq!(Region::from_name(region.as_str()), Some(region));
+        }
+        assert_eq!(Region::from_name("ETSI"), None);
+        assert_eq!(Region::default(), Region::Etsi);
+    }
+
+    #[test]
+    fn channel_mask_narrows_the_plan() {
+        let mask = ChannelMask::from_channels(&[1, 6, 11, 13]).unwrap();
+        assert!(mask.allows(6));
+        assert!(!mask.allows(7));
+        let mut fcc = mask.filter(Region::Fcc);
+        assert_eq!(
+            (fcc.next(), fcc.next(), fcc.next()),
+            (Some(1), Some(6), Some(11))
+        );
+        assert_eq!(fcc.next(), None);
+        assert_eq!(mask.filter(Region::Etsi).last(), Some(13));
+        assert_eq!(ChannelMask::ALL.filter(Region::Mkk).count(), 14);
+        assert!(!ChannelMask::ALL.allows(0));
+    }
+
+    #[test]
+    fn channel_mask_rejects_bad_lists() {
+        assert!(ChannelMask::from_channels(&[]).is_none());
+        assert!(ChannelMask::from_channels(&[0]).is_none());
+        assert!(ChannelMask::from_channels(&[6, 15]).is_none());
+        assert_eq!(ChannelMask::default(), ChannelMask::ALL);
+    }
+
+    #[test]
+    fn dwell_follows_activity() {
+        let mut dwell = DwellScheduler::new();
+        dwell.next_sweep();
+        assert_eq!(dwell.dwell_ms(6), DEFAULT_DWELL_MS);
+
+        // Dead channel: shrinks toward the minimum
+        for _ in 0..6 {
+            dwell.record(13, dwell.dwell_ms(13), 0, 0);
+        }
+        assert_eq!(dwell.dwell_ms(13), MIN_DWELL_MS + 1);
+
+        // Busy channel: 20 frames in 120 ms is well over 50/s
+        dwell.record(1, DEFAULT_DWELL_MS, 20, 0);
+        assert_eq!(dwell.dwell_ms(1), (DEFAULT_DWELL_MS + BUSY_DWELL_MS) / 2);
+        // Totals are running: the same count again means nothing new heard
+        dwell.record(1, 180, 20, 0);
+        assert!(dwell.dwell_ms(1) < BUSY_DWELL_MS);
+
+        // A match camps on the channel, then decays with quiet traffic
+        dwell.record(6, DEFAULT_DWELL_MS, 3, 1);
+        assert_eq!(dwell.dwell_ms(6), MAX_DWELL_MS);
+        dwell.record(6, MAX_DWELL_MS, 6, 1);
+        assert_eq!(dwell.dwell_ms(6), (MAX_DWELL_MS + DEFAULT_DWELL_MS) / 2);
+
+        // Out-of-plan channels keep the default
+        dwell.record(0, 100, 5, 5);
+        assert_eq!(dwell.dwell_ms(15), DEFAULT_DWELL_MS);
+    }
+
+    #[test]
+    fn dead_channels_are_probed() {
+        let mut dwell = DwellScheduler::new();
+        for sweep in 1..PROBE_SWEEPS {
+            dwell.next_sweep();
+            assert_ne!(dwell.sweep, 0, "sweep {sweep}");
+            dwell.record(13, dwell.dwell_ms(13), 0, 0);
+        }
+        assert!(dwell.dwell_ms(13) < DEFAULT_DWELL_MS);
+        dwell.next_sweep();
+        assert_eq!(dwell.dwell_ms(13), DEFAULT_DWELL_MS);
+    }
+}
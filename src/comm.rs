@@ -1,9 +1,32 @@
-/// Communication helpers — NDJSON serialization, command parsing, line reader.
+/// Communication helpers — NDJSON serialization, command parsing, line reader,
+/// command input multiplexing.
 ///
 /// Pure protocol logic with no hardware or OS dependencies.
 /// BLE GATT definitions and channel types are in the firmware binary (`main.rs`).
-use crate::filter::FilterConfig;
+use crate::channel::{ChannelMask, Region};
+use crate::error::AirhoundError;
+use crate::filter::{parse_mac, Categories, FilterConfig};
+use crate::following::FollowingPolicy;
+use crate::irk::Irk;
+use crate::lingering::LingeringPolicy;
+use crate::location::{Location, StalePolicy};
 use crate::protocol::{DeviceMessage, HostCommand, RawCommand, MAX_MSG_LEN};
+use crate::route::Subscription;
+use crate::rssi::ProximityZones;
+use crate::scanner::BleScanConfig;
+use crate::schedule::QuietHours;
+use crate::sigbundle;
+use crate::sigfile::RuntimeSignature;
+use crate::watch::DEFAULT_WATCH_SECS;
+
+/// Byte length of the status snapshot characteristic value
+pub const STATUS_CHAR_LEN: usize = 10;
+
+/// Byte length of the match counters characteristic value
+pub const COUNTERS_CHAR_LEN: usize = 8;
+
+/// Byte length of the settings characteristic value
+pub const SETTINGS_CHAR_LEN: usize = 6;
 
 /// BLE GATT service UUIDs for AirHound.
 ///
@@ -14,56 +37,202 @@ use crate::protocol::{DeviceMessage, HostCommand, RawCommand, MAX_MSG_LEN};
 pub mod ble_uuids {
     /// AirHound primary service UUID
     pub const SERVICE: &str = "4a690001-1c4a-4e3c-b5d8-f47b2e1c0a9d";
+    /// [`SERVICE`] as the numeric value `scanner::BleEvent` reports
+    /// 128-bit UUIDs as — advertised in our scan response so other units
+    /// can tell us apart
+    pub const SERVICE_128: u128 = 0x4a69_0001_1c4a_4e3c_b5d8_f47b_2e1c_0a9d;
     /// TX characteristic — scan results, notify
     pub const TX_CHAR: &str = "4a690002-1c4a-4e3c-b5d8-f47b2e1c0a9d";
     /// RX characteristic — commands, write
     pub const RX_CHAR: &str = "4a690003-1c4a-4e3c-b5d8-f47b2e1c0a9d";
+    /// Status snapshot characteristic — read ([`super::encode_status`])
+    pub const STATUS_CHAR: &str = "4a690004-1c4a-4e3c-b5d8-f47b2e1c0a9d";
+    /// Match counters characteristic — read ([`super::encode_counters`])
+    pub const COUNTERS_CHAR: &str = "4a690005-1c4a-4e3c-b5d8-f47b2e1c0a9d";
+    /// Settings characteristic — read/write ([`super::DeviceSettings`])
+    pub const SETTINGS_CHAR: &str = "4a690006-1c4a-4e3c-b5d8-f47b2e1c0a9d";
 }
 
 /// BLE advertising name
 pub const BLE_ADV_NAME: &str = "AirHound";
 
+/// Static random address every AirHound unit advertises from
+pub const BLE_ADDRESS: [u8; 6] = [0xff, 0x8f, 0x1a, 0x05, 0xe4, 0xab];
+
 /// Maximum BLE notification payload (MTU-3)
 pub const BLE_MAX_NOTIFY: usize = 20;
 
 // ── Serialization helpers ──────────────────────────────────────────────
 
 /// Serialize a DeviceMessage to JSON bytes and write to the output buffer.
-/// Returns the number of bytes written, or None if serialization failed.
-pub fn serialize_message(msg: &DeviceMessage, buf: &mut [u8]) -> Option<usize> {
+/// Returns the number of bytes written, newline included.
+pub fn serialize_message(msg: &DeviceMessage, buf: &mut [u8]) -> Result<usize, AirhoundError> {
     match serde_json_core::to_slice(msg, buf) {
         Ok(len) if len < buf.len() => {
             // Append newline for NDJSON
             buf[len] = b'\n';
-            Some(len + 1)
+            Ok(len + 1)
         }
-        _ => None,
+        _ => Err(AirhoundError::BufferTooSmall),
     }
 }
 
 /// Deserialize a HostCommand from a JSON byte slice.
 ///
 /// Uses [`RawCommand`] as an intermediate because `serde_json_core` does not
-/// support internally tagged enums (no `deserialize_any`).
-pub fn parse_command(data: &[u8]) -> Option<HostCommand> {
+/// support internally tagged enums (no `deserialize_any`). Fails with
+/// [`AirhoundError::Malformed`] for blank lines and invalid JSON,
+/// [`AirhoundError::Unsupported`] for unknown command names, and
+/// [`AirhoundError::InvalidArgument`] for missing or out-of-range fields.
+pub fn parse_command(data: &[u8]) -> Result<HostCommand, AirhoundError> {
     // Strip trailing newline/whitespace
     let trimmed = trim_trailing_whitespace(data);
     if trimmed.is_empty() {
-        return None;
-    }
-    let (raw, _) = serde_json_core::from_slice::<RawCommand>(trimmed).ok()?;
-    match raw.cmd.as_str() {
-        "start" => Some(HostCommand::Start),
-        "stop" => Some(HostCommand::Stop),
-        "status" => Some(HostCommand::GetStatus),
-        "set_rssi" => raw
-            .min_rssi
-            .map(|min_rssi| HostCommand::SetRssi { min_rssi }),
-        "set_buzzer" => raw
-            .enabled
-            .map(|enabled| HostCommand::SetBuzzer { enabled }),
-        _ => None,
+        return Err(AirhoundError::Malformed);
     }
+    let (raw, _) =
+        serde_json_core::from_slice::<RawCommand>(trimmed).map_err(|_| AirhoundError::Malformed)?;
+    let cmd = match raw.cmd.as_str() {
+        "start" => HostCommand::Start,
+        "stop" => HostCommand::Stop,
+        "status" => HostCommand::GetStatus,
+        "get_sig_version" => HostCommand::GetSigVersion,
+        "get_stats" => HostCommand::GetStats,
+        "get_channel_stats" => HostCommand::GetChannelStats,
+        "get_evidence" => HostCommand::GetEvidence,
+        "set_rssi" => HostCommand::SetRssi {
+            min_rssi: arg(raw.min_rssi)?,
+        },
+        "set_buzzer" => HostCommand::SetBuzzer {
+            enabled: arg(raw.enabled)?,
+        },
+        "set_beacon" => HostCommand::SetBeacon {
+            enabled: arg(raw.enabled)?,
+        },
+        "set_rssi_smoothing" => HostCommand::SetRssiSmoothing {
+            enabled: arg(raw.enabled)?,
+        },
+        "set_report_self" => HostCommand::SetReportSelf {
+            enabled: arg(raw.enabled)?,
+        },
+        "set_ssid_heuristics" => HostCommand::SetSsidHeuristics {
+            enabled: arg(raw.enabled)?,
+        },
+        "set_timing_heuristics" => HostCommand::SetTimingHeuristics {
+            enabled: arg(raw.enabled)?,
+        },
+        "set_sequence_heuristics" => HostCommand::SetSequenceHeuristics {
+            enabled: arg(raw.enabled)?,
+        },
+        "set_fcs_check" => HostCommand::SetFcsCheck {
+            enabled: arg(raw.enabled)?,
+        },
+        "set_zones" => HostCommand::SetZones(arg(ProximityZones::try_new(
+            arg(raw.immediate)?,
+            arg(raw.near)?,
+        ))?),
+        "set_low_power" => HostCommand::SetLowPower {
+            enabled: arg(raw.enabled)?,
+        },
+        "set_time" => HostCommand::SetTime {
+            epoch_s: arg(raw.epoch)?,
+            utc_offset_min: raw.utc_offset_min.unwrap_or(0),
+        },
+        "set_location" => HostCommand::SetLocation(arg(Location::try_host(
+            arg(raw.lat)?,
+            arg(raw.lon)?,
+            raw.acc,
+        ))?),
+        "set_following" => HostCommand::SetFollowing(arg(FollowingPolicy::try_new(
+            arg(raw.sightings)?,
+            arg(raw.window_min)?,
+            arg(raw.distance_m)?,
+        ))?),
+        "set_lingering" => HostCommand::SetLingering(arg(LingeringPolicy::try_new(
+            arg(raw.duration_min)?,
+            arg(raw.min_rssi)?,
+        ))?),
+        "set_location_stale" => {
+            HostCommand::SetLocationStale(arg(StalePolicy::try_new(arg(raw.max_age_s)?))?)
+        }
+        "set_quiet_hours" => HostCommand::SetQuietHours(arg(QuietHours::try_new(
+            arg(raw.start_min)?,
+            arg(raw.end_min)?,
+        ))?),
+        "set_categories" => HostCommand::SetCategories(Categories {
+            alpr: raw.alpr.unwrap_or(true),
+            gunshot_detector: raw.gunshot_detector.unwrap_or(true),
+            camera: raw.camera.unwrap_or(true),
+            tracker: raw.tracker.unwrap_or(true),
+            drone: raw.drone.unwrap_or(true),
+            attack_tool: raw.attack_tool.unwrap_or(true),
+            other: raw.other.unwrap_or(true),
+        }),
+        "add_irk" => HostCommand::AddIrk(arg(Irk::from_hex(arg(raw.irk)?.as_str()))?),
+        "clear_irks" => HostCommand::ClearIrks,
+        "set_region" => HostCommand::SetRegion(arg(Region::from_name(arg(raw.region)?.as_str()))?),
+        // Without a list, every channel of the region is hopped again
+        "set_channel_filter" => HostCommand::SetChannelFilter(match &raw.channels {
+            Some(channels) => arg(ChannelMask::from_channels(channels))?,
+            None => ChannelMask::ALL,
+        }),
+        "set_ble_scan" => HostCommand::SetBleScan(arg(BleScanConfig::try_new(
+            arg(raw.interval_ms)?,
+            arg(raw.window_ms)?,
+            raw.active.unwrap_or(true),
+        ))?),
+        "subscribe" => {
+            let min_severity = raw.min_severity.unwrap_or(0);
+            let sub = match &raw.types {
+                Some(types) => arg(Subscription::from_names(
+                    types.iter().map(|t| t.as_str()),
+                    min_severity,
+                ))?,
+                None => Subscription::all().with_min_severity(min_severity),
+            };
+            HostCommand::Subscribe(sub)
+        }
+        "watch" => HostCommand::Watch {
+            mac: arg(parse_mac(arg(raw.mac.as_deref())?))?,
+            duration_s: raw.duration_s.unwrap_or(DEFAULT_WATCH_SECS),
+        },
+        "unwatch" => HostCommand::Unwatch {
+            mac: arg(parse_mac(arg(raw.mac.as_deref())?))?,
+        },
+        "label" => HostCommand::Label {
+            mac: arg(parse_mac(arg(raw.mac.as_deref())?))?,
+            name: raw.name.unwrap_or_default(),
+        },
+        "sig_begin" => HostCommand::SigBegin { len: arg(raw.len)? },
+        "sig_chunk" => HostCommand::SigChunk {
+            offset: arg(raw.offset)?,
+            data: arg(sigbundle::chunk_from_hex(arg(raw.data)?.as_str()))?,
+        },
+        "sig_commit" => HostCommand::SigCommit,
+        "sig_rollback" => HostCommand::SigRollback,
+        "add_signature" => HostCommand::AddSignature {
+            sig: runtime_signature(&raw)?,
+            description: raw.description.clone().unwrap_or_default(),
+        },
+        "remove_signature" => HostCommand::RemoveSignature(runtime_signature(&raw)?),
+        "test_rule" => HostCommand::TestRule {
+            blob: arg(sigbundle::chunk_from_hex(arg(raw.data)?.as_str()))?,
+        },
+        _ => return Err(AirhoundError::Unsupported),
+    };
+    Ok(cmd)
+}
+
+/// The `type`/`value` pair of `add_signature` and `remove_signature`
+fn runtime_signature(raw: &RawCommand) -> Result<RuntimeSignature, AirhoundError> {
+    let kind = arg(raw.sig_type.as_deref())?;
+    RuntimeSignature::parse(kind, arg(raw.value.as_deref())?)
+}
+
+/// A required command field: missing or rejected values are
+/// [`AirhoundError::InvalidArgument`].
+fn arg<T>(value: Option<T>) -> Result<T, AirhoundError> {
+    value.ok_or(AirhoundError::InvalidArgument)
 }
 
 /// Process a received host command and update state accordingly.
@@ -86,8 +255,12 @@ pub fn handle_command(
             log::info!("Scanning stopped by host command");
             None
         }
-        HostCommand::GetStatus => {
-            // Status message will be constructed by the caller with real uptime/heap data
+        HostCommand::GetStatus
+        | HostCommand::GetSigVersion
+        | HostCommand::GetStats
+        | HostCommand::GetChannelStats
+        | HostCommand::GetEvidence => {
+            // Reply constructed by the caller with live device state
             None
         }
         HostCommand::SetRssi { min_rssi } => {
@@ -99,6 +272,297 @@ pub fn handle_command(
             log::info!("Buzzer {}", if *enabled { "enabled" } else { "disabled" });
             Some(*enabled)
         }
+        HostCommand::SetBeacon { enabled } => {
+            config.beacon = *enabled;
+            log::info!(
+                "Beacon summary {}",
+                if *enabled { "enabled" } else { "disabled" }
+            );
+            None
+        }
+        HostCommand::SetRssiSmoothing { enabled } => {
+            config.rssi_smoothing = *enabled;
+            log::info!(
+                "RSSI smoothing {}",
+                if *enabled { "enabled" } else { "disabled" }
+            );
+            None
+        }
+        HostCommand::SetReportSelf { enabled } => {
+            config.report_self = *enabled;
+            log::info!(
+                "Own and co-located AirHound advertisements {}",
+                if *enabled { "reported" } else { "suppressed" }
+            );
+            None
+        }
+        HostCommand::SetSsidHeuristics { enabled } => {
+            config.ssid_heuristics = *enabled;
+            log::info!(
+                "SSID heuristics {}",
+                if *enabled { "enabled" } else { "disabled" }
+            );
+            None
+        }
+        HostCommand::SetTimingHeuristics { enabled } => {
+            config.timing_heuristics = *enabled;
+            log::info!(
+                "Beacon timing heuristics {}",
+                if *enabled { "enabled" } else { "disabled" }
+            );
+            None
+        }
+        HostCommand::SetSequenceHeuristics { enabled } => {
+            config.sequence_heuristics = *enabled;
+            log::info!(
+                "Sequence number heuristics {}",
+                if *enabled { "enabled" } else { "disabled" }
+            );
+            None
+        }
+        HostCommand::SetFcsCheck { enabled } => {
+            config.fcs_check = *enabled;
+            log::info!(
+                "WiFi FCS check {}",
+                if *enabled { "enabled" } else { "disabled" }
+            );
+            None
+        }
+        HostCommand::SetZones(zones) => {
+            config.zones = *zones;
+            log::info!(
+                "Proximity zones: immediate >= {} dBm, near >= {} dBm",
+                zones.immediate,
+                zones.near
+            );
+            None
+        }
+        HostCommand::SetLocationStale(policy) => {
+            config.location_stale = *policy;
+            log::info!("Locations stale after {} s", policy.max_age_s);
+            None
+        }
+        HostCommand::SetFollowing(policy) => {
+            config.following = *policy;
+            log::info!(
+                "Following: {} sightings in {} min over {} m",
+                policy.sightings,
+                policy.window_min,
+                policy.distance_m
+            );
+            None
+        }
+        HostCommand::SetLingering(policy) => {
+            config.lingering = *policy;
+            log::info!(
+                "Lingering: {} min at >= {} dBm",
+                policy.duration_min,
+                policy.min_rssi
+            );
+            None
+        }
+        HostCommand::Watch { .. }
+        | HostCommand::Unwatch { .. }
+        | HostCommand::Label { .. }
+        | HostCommand::SetTime { .. }
+        | HostCommand::SetLocation(_)
+        | HostCommand::AddIrk(_)
+        | HostCommand::ClearIrks
+        | HostCommand::SigBegin { .. }
+        | HostCommand::SigChunk { .. }
+        | HostCommand::SigCommit
+        | HostCommand::SigRollback
+        | HostCommand::AddSignature { .. }
+        | HostCommand::RemoveSignature(_)
+        | HostCommand::TestRule { .. } => {
+            // Applied by the firmware, which owns the watch list, label,
+            // IRK and runtime signature tables, event log, clock, location,
+            // and config store
+            None
+        }
+        HostCommand::Subscribe(_) => {
+            // Applied per connection by the BLE layer
+            log::info!("Subscription ignored outside a BLE connection");
+            None
+        }
+        HostCommand::SetLowPower { enabled } => {
+            config.low_power = *enabled;
+            log::info!(
+                "Low-power BLE-only mode {}",
+                if *enabled { "enabled" } else { "disabled" }
+            );
+            None
+        }
+        HostCommand::SetRegion(region) => {
+            config.region = *region;
+            log::info!("Region set to {}", region.as_str());
+            None
+        }
+        HostCommand::SetChannelFilter(channels) => {
+            config.channels = *channels;
+            log::info!(
+                "WiFi channel filter: {} of {} channels",
+                channels.filter(config.region).count(),
+                config.region.channels().len()
+            );
+            None
+        }
+        HostCommand::SetQuietHours(hours) => {
+            config.quiet_hours = *hours;
+            log::info!(
+                "Quiet hours {:02}:{:02}-{:02}:{:02}",
+                hours.start_min / 60,
+                hours.start_min % 60,
+                hours.end_min / 60,
+                hours.end_min % 60
+            );
+            None
+        }
+        HostCommand::SetCategories(categories) => {
+            config.categories = *categories;
+            log::info!("Reported categories updated");
+            None
+        }
+        HostCommand::Configure(settings) => {
+            settings.apply(config);
+            *scanning = settings.scanning;
+            log::info!("Settings updated via GATT");
+            Some(settings.buzzer)
+        }
+        HostCommand::SetBleScan(scan) => {
+            config.ble_scan = *scan;
+            log::info!(
+                "BLE scan set to {}ms/{}ms ({}%), {}",
+                scan.window_ms,
+                scan.interval_ms,
+                scan.duty_cycle_percent(),
+                if scan.active { "active" } else { "passive" }
+            );
+            None
+        }
+    }
+}
+
+// ── GATT characteristic values ─────────────────────────────────────
+//
+// Fixed-size little-endian layouts for companions that poll state with plain
+// GATT reads instead of subscribing to the NDJSON notification stream.
+
+/// Encode the status snapshot:
+/// `[flags (bit0 scanning), ble_clients, uptime_s: u32, heap_free: u32]`.
+pub fn encode_status(
+    scanning: bool,
+    ble_clients: u8,
+    uptime_secs: u32,
+    heap_free: u32,
+) -> [u8; STATUS_CHAR_LEN] {
+    let mut out = [0u8; STATUS_CHAR_LEN];
+    out[0] = scanning as u8;
+    out[1] = ble_clients;
+    out[2..6].copy_from_slice(&uptime_secs.to_le_bytes());
+    out[6..10].copy_from_slice(&heap_free.to_le_bytes());
+    out
+}
+
+/// Encode the match counters: `[wifi_matches: u32, ble_matches: u32]`.
+pub fn encode_counters(wifi_matches: u32, ble_matches: u32) -> [u8; COUNTERS_CHAR_LEN] {
+    let mut out = [0u8; COUNTERS_CHAR_LEN];
+    out[0..4].copy_from_slice(&wifi_matches.to_le_bytes());
+    out[4..8].copy_from_slice(&ble_matches.to_le_bytes());
+    out
+}
+
+/// User-adjustable settings exposed by the read/write settings characteristic.
+///
+/// Layout: `[min_rssi: i8, flags, ble_interval_ms: u16, ble_window_ms: u16]`
+/// with flags bit0 scanning, bit1 WiFi enabled, bit2 BLE enabled, bit3 active
+/// BLE scan, bit4 buzzer, bit5 beacon summary, bit6 low-power BLE-only mode.
+/// Only these fields are carried; a write is applied onto the current
+/// [`FilterConfig`] with [`DeviceSettings::apply`], leaving everything else
+/// as it was.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DeviceSettings {
+    pub min_rssi: i8,
+    pub wifi_enabled: bool,
+    pub ble_enabled: bool,
+    pub ble_scan: BleScanConfig,
+    pub beacon: bool,
+    pub low_power: bool,
+    pub scanning: bool,
+    pub buzzer: bool,
+}
+
+impl DeviceSettings {
+    const SCANNING: u8 = 1 << 0;
+    const WIFI: u8 = 1 << 1;
+    const BLE: u8 = 1 << 2;
+    const BLE_ACTIVE: u8 = 1 << 3;
+    const BUZZER: u8 = 1 << 4;
+    const BEACON: u8 = 1 << 5;
+    const LOW_POWER: u8 = 1 << 6;
+
+    /// The characteristic's view of `config` and the runtime toggles
+    pub fn new(config: &FilterConfig, scanning: bool, buzzer: bool) -> Self {
+        Self {
+            min_rssi: config.min_rssi,
+            wifi_enabled: config.wifi_enabled,
+            ble_enabled: config.ble_enabled,
+            ble_scan: config.ble_scan,
+            beacon: config.beacon,
+            low_power: config.low_power,
+            scanning,
+            buzzer,
+        }
+    }
+
+    /// Apply the characteristic's fields onto `config`
+    pub fn apply(&self, config: &mut FilterConfig) {
+        config.min_rssi = self.min_rssi;
+        config.wifi_enabled = self.wifi_enabled;
+        config.ble_enabled = self.ble_enabled;
+        config.ble_scan = self.ble_scan;
+        config.beacon = self.beacon;
+        config.low_power = self.low_power;
+    }
+
+    pub fn to_bytes(&self) -> [u8; SETTINGS_CHAR_LEN] {
+        let flag = |set: bool, bit: u8| if set { bit } else { 0 };
+        let mut out = [0u8; SETTINGS_CHAR_LEN];
+        out[0] = self.min_rssi as u8;
+        out[1] = flag(self.scanning, Self::SCANNING)
+            | flag(self.wifi_enabled, Self::WIFI)
+            | flag(self.ble_enabled, Self::BLE)
+            | flag(self.ble_scan.active, Self::BLE_ACTIVE)
+            | flag(self.buzzer, Self::BUZZER)
+            | flag(self.beacon, Self::BEACON)
+            | flag(self.low_power, Self::LOW_POWER);
+        out[2..4].copy_from_slice(&self.ble_scan.interval_ms.to_le_bytes());
+        out[4..6].copy_from_slice(&self.ble_scan.window_ms.to_le_bytes());
+        out
+    }
+
+    /// Decode a settings write. Returns `None` for a short write or an
+    /// invalid BLE scan duty cycle.
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        if data.len() < SETTINGS_CHAR_LEN {
+            return None;
+        }
+        let flags = data[1];
+        let ble_scan = BleScanConfig::try_new(
+            u16::from_le_bytes([data[2], data[3]]),
+            u16::from_le_bytes([data[4], data[5]]),
+            flags & Self::BLE_ACTIVE != 0,
+        )?;
+        Some(Self {
+            min_rssi: data[0] as i8,
+            wifi_enabled: flags & Self::WIFI != 0,
+            ble_enabled: flags & Self::BLE != 0,
+            ble_scan,
+            beacon: flags & Self::BEACON != 0,
+            low_power: flags & Self::LOW_POWER != 0,
+            scanning: flags & Self::SCANNING != 0,
+            buzzer: flags & Self::BUZZER != 0,
+        })
     }
 }
 
@@ -109,6 +573,8 @@ pub fn handle_command(
 pub struct LineReader {
     buf: [u8; MAX_MSG_LEN],
     pos: usize,
+    /// The current line overflowed the buffer; drop bytes until its end
+    overflowed: bool,
 }
 
 impl LineReader {
@@ -116,32 +582,103 @@ impl LineReader {
         Self {
             buf: [0; MAX_MSG_LEN],
             pos: 0,
+            overflowed: false,
         }
     }
 
     /// Feed a byte into the reader. Returns a complete line (without newline)
-    /// when one is detected.
-    pub fn feed(&mut self, byte: u8) -> Option<&[u8]> {
+    /// when one is detected, or [`AirhoundError::BufferTooSmall`] at the end
+    /// of a line longer than [`MAX_MSG_LEN`], which is discarded whole.
+    pub fn feed(&mut self, byte: u8) -> Option<Result<&[u8], AirhoundError>> {
         if byte == b'\n' || byte == b'\r' {
-            if self.pos > 0 {
-                let line = &self.buf[..self.pos];
-                self.pos = 0;
-                Some(line)
+            let len = core::mem::take(&mut self.pos);
+            if core::mem::take(&mut self.overflowed) {
+                Some(Err(AirhoundError::BufferTooSmall))
+            } else if len > 0 {
+                Some(Ok(&self.buf[..len]))
             } else {
                 None
             }
+        } else if self.overflowed {
+            None
         } else if self.pos < self.buf.len() {
             self.buf[self.pos] = byte;
             self.pos += 1;
             None
         } else {
-            // Overflow — discard and reset
-            self.pos = 0;
+            self.overflowed = true;
             None
         }
     }
 }
 
+// ── Command input multiplexer ──────────────────────────────────────────
+
+/// Byte source a host command arrived on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandSource {
+    /// USB serial (UART bridge or USB-Serial-JTAG)
+    Serial,
+    /// A BLE client writing the RX characteristic
+    Ble,
+}
+
+/// Handle to an input opened on a [`CommandMux`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputId(u8);
+
+/// NDJSON command input shared by every byte source.
+///
+/// Each open input (the serial port, each BLE connection) gets its own line
+/// buffer, so bytes arriving interleaved from several sources never mix into
+/// one line. Up to `N` inputs can be open at once.
+pub struct CommandMux<const N: usize> {
+    inputs: [Option<(CommandSource, LineReader)>; N],
+}
+
+impl<const N: usize> CommandMux<N> {
+    pub const fn new() -> Self {
+        Self {
+            inputs: [const { None }; N],
+        }
+    }
+
+    /// Open an input for `source`. Returns `None` when all `N` are in use.
+    pub fn open(&mut self, source: CommandSource) -> Option<InputId> {
+        let index = self.inputs.iter().position(Option::is_none)?;
+        self.inputs[index] = Some((source, LineReader::new()));
+        Some(InputId(index as u8))
+    }
+
+    /// Close an input, discarding any partial line.
+    pub fn close(&mut self, id: InputId) {
+        if let Some(input) = self.inputs.get_mut(id.0 as usize) {
+            *input = None;
+        }
+    }
+
+    /// Source of an open input.
+    pub fn source(&self, id: InputId) -> Option<CommandSource> {
+        self.inputs
+            .get(id.0 as usize)?
+            .as_ref()
+            .map(|(source, _)| *source)
+    }
+
+    /// Feed one byte from input `id`. Returns the parsed command, or why it
+    /// was rejected, when the byte completes a line.
+    pub fn feed(&mut self, id: InputId, byte: u8) -> Option<Result<HostCommand, AirhoundError>> {
+        let (_, reader) = self.inputs.get_mut(id.0 as usize)?.as_mut()?;
+        reader.feed(byte).map(|line| line.and_then(parse_command))
+    }
+}
+
+impl<const N: usize> Default for CommandMux<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 fn trim_trailing_whitespace(data: &[u8]) -> &[u8] {
     let mut end = data.len();
     while end > 0
@@ -158,9 +695,13 @@ fn trim_trailing_whitespace(data: &[u8]) -> &[u8] {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::defaults::{Category, Confidence, DeviceClass, Severity};
     use crate::protocol::{
         DeviceMessage, HostCommand, MacString, MatchReason, NameString, VERSION,
     };
+    use crate::route::MessageKind;
+    use crate::rssi::Proximity;
+    use crate::schedule::TsSource;
     use heapless::Vec;
 
     // ── serialize_message tests ─────────────────────────────────────
@@ -174,6 +715,9 @@ mod tests {
             ble_clients: 0,
             board: "test",
             version: VERSION,
+            sig_version: 0,
+            region: "fcc",
+            gps: None,
         };
         let mut buf = [0u8; 512];
         let len = serialize_message(&msg, &mut buf).unwrap();
@@ -195,10 +739,16 @@ mod tests {
             ble_clients: 0,
             board: "test",
             version: VERSION,
+            sig_version: 0,
+            region: "etsi",
+            gps: None,
         };
         // Buffer too small for JSON + newline
         let mut buf = [0u8; 10];
-        assert!(serialize_message(&msg, &mut buf).is_none());
+        assert_eq!(
+            serialize_message(&msg, &mut buf),
+            Err(AirhoundError::BufferTooSmall)
+        );
     }
 
     #[test]
@@ -208,12 +758,34 @@ mod tests {
         let matches = Vec::<MatchReason, 4>::new();
         let msg = DeviceMessage::WiFiScan {
             mac: &mac,
+            mac_randomized: false,
+            via: None,
             ssid: &ssid,
             rssi: -50,
+            rssi_avg: None,
+            zone: Proximity::Near,
+            approaching: false,
             ch: 1,
             frame: "beacon",
+            action: None,
+            security: None,
+            severity: Severity::Medium,
+            category: Category::Other,
+            device_class: DeviceClass::Unknown,
+            confidence: Confidence::Medium,
             matches: &matches,
+            rules: &[],
+            truncated: false,
+            label: None,
+            device_id: None,
+            remote_id: None,
+            p2p: None,
+            network: None,
+            quiet: false,
+            loc: None,
+            location_stale: false,
             ts: 100,
+            ts_source: TsSource::Uptime,
         };
         let mut buf = [0u8; 512];
         let len = serialize_message(&msg, &mut buf).unwrap();
@@ -241,6 +813,30 @@ mod tests {
         assert!(matches!(cmd, HostCommand::GetStatus));
     }
 
+    #[test]
+    fn parse_get_sig_version_command() {
+        let cmd = parse_command(br#"{"cmd":"get_sig_version"}"#).unwrap();
+        assert_eq!(cmd, HostCommand::GetSigVersion);
+    }
+
+    #[test]
+    fn parse_get_stats_command() {
+        let cmd = parse_command(br#"{"cmd":"get_stats"}"#).unwrap();
+        assert_eq!(cmd, HostCommand::GetStats);
+    }
+
+    #[test]
+    fn parse_get_channel_stats_command() {
+        let cmd = parse_command(br#"{"cmd":"get_channel_stats"}"#).unwrap();
+        assert_eq!(cmd, HostCommand::GetChannelStats);
+    }
+
+    #[test]
+    fn parse_get_evidence_command() {
+        let cmd = parse_command(br#"{"cmd":"get_evidence"}"#).unwrap();
+        assert_eq!(cmd, HostCommand::GetEvidence);
+    }
+
     #[test]
     fn parse_set_rssi_command() {
         let cmd = parse_command(br#"{"cmd":"set_rssi","min_rssi":-80}"#).unwrap();
@@ -259,6 +855,205 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_set_beacon_command() {
+        let cmd = parse_command(br#"{"cmd":"set_beacon","enabled":true}"#).unwrap();
+        assert_eq!(cmd, HostCommand::SetBeacon { enabled: true });
+        assert_eq!(
+            parse_command(br#"{"cmd":"set_beacon"}"#),
+            Err(AirhoundError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn parse_set_ble_scan_command() {
+        let cmd = parse_command(
+            br#"{"cmd":"set_ble_scan","interval_ms":1000,"window_ms":100,"active":false}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            cmd,
+            HostCommand::SetBleScan(BleScanConfig {
+                interval_ms: 1000,
+                window_ms: 100,
+                active: false,
+            })
+        );
+    }
+
+    #[test]
+    fn parse_set_ble_scan_defaults_to_active() {
+        let cmd =
+            parse_command(br#"{"cmd":"set_ble_scan","interval_ms":200,"window_ms":50}"#).unwrap();
+        assert!(matches!(cmd, HostCommand::SetBleScan(scan) if scan.active));
+    }
+
+    #[test]
+    fn parse_set_ble_scan_rejects_invalid_window() {
+        assert_eq!(
+            parse_command(br#"{"cmd":"set_ble_scan","interval_ms":50,"window_ms":100}"#),
+            Err(AirhoundError::InvalidArgument)
+        );
+        assert_eq!(
+            parse_command(br#"{"cmd":"set_ble_scan","interval_ms":100}"#),
+            Err(AirhoundError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn parse_subscribe_command() {
+        let cmd = parse_command(
+            br#"{"cmd":"subscribe","types":["wifi","ble","status"],"min_severity":2}"#,
+        )
+        .unwrap();
+        let HostCommand::Subscribe(sub) = cmd else {
+            panic!("expected Subscribe, got {cmd:?}");
+        };
+        assert!(sub.includes(MessageKind::WiFi));
+        assert!(sub.includes(MessageKind::Status));
+        assert!(!sub.includes(MessageKind::Health));
+        assert_eq!(sub.min_severity, 2);
+    }
+
+    #[test]
+    fn parse_subscribe_defaults_to_everything() {
+        let cmd = parse_command(br#"{"cmd":"subscribe"}"#).unwrap();
+        assert_eq!(cmd, HostCommand::Subscribe(Subscription::all()));
+        assert_eq!(
+            parse_command(br#"{"cmd":"subscribe","types":["wifi","nope"]}"#),
+            Err(AirhoundError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn parse_watch_command() {
+        let cmd = parse_command(br#"{"cmd":"watch","mac":"aa:bb:cc:01:02:03","duration_s":120}"#)
+            .unwrap();
+        assert_eq!(
+            cmd,
+            HostCommand::Watch {
+                mac: [0xAA, 0xBB, 0xCC, 0x01, 0x02, 0x03],
+                duration_s: 120,
+            }
+        );
+        let cmd = parse_command(br#"{"cmd":"watch","mac":"AA:BB:CC:01:02:03"}"#).unwrap();
+        assert!(
+            matches!(cmd, HostCommand::Watch { duration_s, .. } if duration_s == DEFAULT_WATCH_SECS)
+        );
+    }
+
+    #[test]
+    fn parse_watch_rejects_bad_mac() {
+        assert_eq!(
+            parse_command(br#"{"cmd":"watch"}"#),
+            Err(AirhoundError::InvalidArgument)
+        );
+        assert_eq!(
+            parse_command(br#"{"cmd":"watch","mac":"AA:BB:CC"}"#),
+            Err(AirhoundError::InvalidArgument)
+        );
+        assert_eq!(
+            parse_command(br#"{"cmd":"unwatch","mac":"AA:BB:CC:01:02:ZZ"}"#),
+            Err(AirhoundError::InvalidArgument)
+        );
+        assert_eq!(
+            parse_command(br#"{"cmd":"unwatch","mac":"AA:BB:CC:01:02:03"}"#),
+            Ok(HostCommand::Unwatch {
+                mac: [0xAA, 0xBB, 0xCC, 0x01, 0x02, 0x03]
+            })
+        );
+    }
+
+    #[test]
+    fn parse_label_command() {
+        let cmd =
+            parse_command(br#"{"cmd":"label","mac":"7C:11:22:33:44:55","name":"Mom's AirTag"}"#)
+                .unwrap();
+        let HostCommand::Label { mac, name } = cmd else {
+            panic!("expected Label, got {cmd:?}");
+        };
+        assert_eq!(mac, [0x7C, 0x11, 0x22, 0x33, 0x44, 0x55]);
+        assert_eq!(name.as_str(), "Mom's AirTag");
+
+        // Omitted name clears the label
+        let cmd = parse_command(br#"{"cmd":"label","mac":"7C:11:22:33:44:55"}"#).unwrap();
+        assert!(matches!(cmd, HostCommand::Label { name, .. } if name.is_empty()));
+        assert_eq!(
+            parse_command(br#"{"cmd":"label","name":"x"}"#),
+            Err(AirhoundError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn parse_sig_update_commands() {
+        assert_eq!(
+            parse_command(br#"{"cmd":"sig_begin","len":600}"#),
+            Ok(HostCommand::SigBegin { len: 600 })
+        );
+        let cmd = parse_command(br#"{"cmd":"sig_chunk","offset":128,"data":"4148Sb01"}"#);
+        assert_eq!(cmd, Err(AirhoundError::InvalidArgument));
+        let cmd = parse_command(br#"{"cmd":"sig_chunk","offset":128,"data":"4148Cb01"}"#).unwrap();
+        let HostCommand::SigChunk { offset, data } = cmd else {
+            panic!("expected SigChunk, got {cmd:?}");
+        };
+        assert_eq!(offset, 128);
+        assert_eq!(data.as_slice(), [0x41, 0x48, 0xCB, 0x01]);
+        assert_eq!(
+            parse_command(br#"{"cmd":"sig_chunk","offset":0,"data":"414"}"#),
+            Err(AirhoundError::InvalidArgument)
+        );
+        assert_eq!(
+            parse_command(br#"{"cmd":"sig_commit"}"#),
+            Ok(HostCommand::SigCommit)
+        );
+        assert_eq!(
+            parse_command(br#"{"cmd":"sig_rollback"}"#),
+            Ok(HostCommand::SigRollback)
+        );
+    }
+
+    #[test]
+    fn parse_test_rule_command() {
+        let cmd = parse_command(br#"{"cmd":"test_rule","data":"41485244"}"#).unwrap();
+        let HostCommand::TestRule { blob } = cmd else {
+            panic!("expected TestRule, got {cmd:?}");
+        };
+        assert_eq!(blob.as_slice(), b"AHRD");
+        assert_eq!(
+            parse_command(br#"{"cmd":"test_rule"}"#),
+            Err(AirhoundError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn parse_runtime_signature_commands() {
+        let cmd = parse_command(
+            br#"{"cmd":"add_signature","type":"mac_oui","value":"aa:bb:cc","description":"Verkada"}"#,
+        )
+        .unwrap();
+        let HostCommand::AddSignature { sig, description } = cmd else {
+            panic!("expected AddSignature, got {cmd:?}");
+        };
+        assert_eq!(sig, RuntimeSignature::MacOui([0xAA, 0xBB, 0xCC]));
+        assert_eq!(description.as_str(), "Verkada");
+
+        let cmd = parse_command(br#"{"cmd":"remove_signature","type":"ble_name","value":"Cam"}"#);
+        assert_eq!(
+            cmd,
+            Ok(HostCommand::RemoveSignature(RuntimeSignature::BleName(
+                "Cam".try_into().unwrap()
+            )))
+        );
+        assert_eq!(
+            parse_command(br#"{"cmd":"add_signature","type":"ble_uuid","value":"3100"}"#),
+            Err(AirhoundError::Unsupported)
+        );
+        assert_eq!(
+            parse_command(br#"{"cmd":"add_signature","value":"flock"}"#),
+            Err(AirhoundError::InvalidArgument)
+        );
+    }
+
     #[test]
     fn parse_command_strips_trailing_whitespace() {
         let cmd = parse_command(b"{\"cmd\":\"start\"}\n  \r\n").unwrap();
@@ -267,29 +1062,44 @@ mod tests {
 
     #[test]
     fn parse_command_rejects_malformed_json() {
-        assert!(parse_command(b"not json at all").is_none());
+        assert_eq!(
+            parse_command(b"not json at all"),
+            Err(AirhoundError::Malformed)
+        );
     }
 
     #[test]
     fn parse_command_rejects_empty_input() {
-        assert!(parse_command(b"").is_none());
-        assert!(parse_command(b"   \n").is_none());
+        assert_eq!(parse_command(b""), Err(AirhoundError::Malformed));
+        assert_eq!(parse_command(b"   \n"), Err(AirhoundError::Malformed));
     }
 
     #[test]
     fn parse_command_rejects_unknown_command() {
-        assert!(parse_command(br#"{"cmd":"restart"}"#).is_none());
-        assert!(parse_command(br#"{"cmd":"reboot"}"#).is_none());
+        assert_eq!(
+            parse_command(br#"{"cmd":"restart"}"#),
+            Err(AirhoundError::Unsupported)
+        );
+        assert_eq!(
+            parse_command(br#"{"cmd":"reboot"}"#),
+            Err(AirhoundError::Unsupported)
+        );
     }
 
     #[test]
-    fn parse_set_rssi_missing_field_returns_none() {
-        assert!(parse_command(br#"{"cmd":"set_rssi"}"#).is_none());
+    fn parse_set_rssi_missing_field_is_invalid() {
+        assert_eq!(
+            parse_command(br#"{"cmd":"set_rssi"}"#),
+            Err(AirhoundError::InvalidArgument)
+        );
     }
 
     #[test]
-    fn parse_set_buzzer_missing_field_returns_none() {
-        assert!(parse_command(br#"{"cmd":"set_buzzer"}"#).is_none());
+    fn parse_set_buzzer_missing_field_is_invalid() {
+        assert_eq!(
+            parse_command(br#"{"cmd":"set_buzzer"}"#),
+            Err(AirhoundError::InvalidArgument)
+        );
     }
 
     #[test]
@@ -333,6 +1143,317 @@ mod tests {
         assert_eq!(config.min_rssi, -75);
     }
 
+    #[test]
+    fn handle_set_beacon_updates_config() {
+        let mut config = FilterConfig::new();
+        let mut scanning = true;
+        handle_command(
+            &HostCommand::SetBeacon { enabled: true },
+            &mut config,
+            &mut scanning,
+        );
+        assert!(config.beacon);
+    }
+
+    #[test]
+    fn parse_and_handle_set_rssi_smoothing() {
+        let cmd = parse_command(br#"{"cmd":"set_rssi_smoothing","enabled":true}"#).unwrap();
+        assert_eq!(cmd, HostCommand::SetRssiSmoothing { enabled: true });
+        let mut config = FilterConfig::new();
+        let mut scanning = true;
+        handle_command(&cmd, &mut config, &mut scanning);
+        assert!(config.rssi_smoothing);
+    }
+
+    #[test]
+    fn parse_and_handle_set_report_self() {
+        let cmd = parse_command(br#"{"cmd":"set_report_self","enabled":true}"#).unwrap();
+        assert_eq!(cmd, HostCommand::SetReportSelf { enabled: true });
+        let mut config = FilterConfig::new();
+        assert!(!config.report_self);
+        let mut scanning = true;
+        handle_command(&cmd, &mut config, &mut scanning);
+        assert!(config.report_self);
+    }
+
+    #[test]
+    fn parse_and_handle_set_ssid_heuristics() {
+        let cmd = parse_command(br#"{"cmd":"set_ssid_heuristics","enabled":true}"#).unwrap();
+        assert_eq!(cmd, HostCommand::SetSsidHeuristics { enabled: true });
+        let mut config = FilterConfig::new();
+        let mut scanning = true;
+        handle_command(&cmd, &mut config, &mut scanning);
+        assert!(config.ssid_heuristics);
+        assert!(!config.timing_heuristics);
+        assert!(!config.sequence_heuristics);
+    }
+
+    #[test]
+    fn parse_and_handle_set_timing_heuristics() {
+        let cmd = parse_command(br#"{"cmd":"set_timing_heuristics","enabled":true}"#).unwrap();
+        assert_eq!(cmd, HostCommand::SetTimingHeuristics { enabled: true });
+        let mut config = FilterConfig::new();
+        let mut scanning = true;
+        handle_command(&cmd, &mut config, &mut scanning);
+        assert!(config.timing_heuristics);
+        assert!(!config.ssid_heuristics);
+    }
+
+    #[test]
+    fn parse_and_handle_set_sequence_heuristics() {
+        let cmd = parse_command(br#"{"cmd":"set_sequence_heuristics","enabled":true}"#).unwrap();
+        assert_eq!(cmd, HostCommand::SetSequenceHeuristics { enabled: true });
+        let mut config = FilterConfig::new();
+        let mut scanning = true;
+        handle_command(&cmd, &mut config, &mut scanning);
+        assert!(config.sequence_heuristics);
+        assert!(!config.ssid_heuristics);
+    }
+
+    #[test]
+    fn parse_and_handle_set_fcs_check() {
+        let cmd = parse_command(br#"{"cmd":"set_fcs_check","enabled":true}"#).unwrap();
+        assert_eq!(cmd, HostCommand::SetFcsCheck { enabled: true });
+        let mut config = FilterConfig::new();
+        let mut scanning = true;
+        handle_command(&cmd, &mut config, &mut scanning);
+        assert!(config.fcs_check);
+        assert_eq!(
+            parse_command(br#"{"cmd":"set_fcs_check"}"#),
+            Err(AirhoundError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn service_uuid_constants_agree() {
+        let mut hex: heapless::String<32> = heapless::String::new();
+        for c in ble_uuids::SERVICE.chars().filter(|&c| c != '-') {
+            hex.push(c).unwrap();
+        }
+        assert_eq!(u128::from_str_radix(&hex, 16), Ok(ble_uuids::SERVICE_128));
+    }
+
+    #[test]
+    fn parse_and_handle_set_zones() {
+        let cmd = parse_command(br#"{"cmd":"set_zones","immediate":-45,"near":-65}"#).unwrap();
+        let zones = ProximityZones::try_new(-45, -65).unwrap();
+        assert_eq!(cmd, HostCommand::SetZones(zones));
+        let mut config = FilterConfig::new();
+        let mut scanning = true;
+        handle_command(&cmd, &mut config, &mut scanning);
+        assert_eq!(config.zones, zones);
+        // Near must be weaker than immediate
+        assert_eq!(
+            parse_command(br#"{"cmd":"set_zones","immediate":-70,"near":-50}"#),
+            Err(AirhoundError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn parse_and_handle_set_low_power() {
+        let cmd = parse_command(br#"{"cmd":"set_low_power","enabled":true}"#).unwrap();
+        assert_eq!(cmd, HostCommand::SetLowPower { enabled: true });
+        let mut config = FilterConfig::new();
+        let mut scanning = true;
+        handle_command(&cmd, &mut config, &mut scanning);
+        assert!(config.low_power);
+        assert!(!config.wifi_active());
+        // The normal-mode duty cycle is preserved for switching back
+        assert_eq!(config.ble_scan, BleScanConfig::new());
+    }
+
+    #[test]
+    fn parse_and_handle_set_region() {
+        let cmd = parse_command(br#"{"cmd":"set_region","region":"mkk"}"#).unwrap();
+        assert_eq!(cmd, HostCommand::SetRegion(Region::Mkk));
+        assert_eq!(
+            parse_command(br#"{"cmd":"set_region","region":"arib"}"#),
+            Err(AirhoundError::InvalidArgument)
+        );
+        assert_eq!(
+            parse_command(br#"{"cmd":"set_region"}"#),
+            Err(AirhoundError::InvalidArgument)
+        );
+        let mut config = FilterConfig::new();
+        let mut scanning = true;
+        handle_command(&cmd, &mut config, &mut scanning);
+        assert_eq!(config.region, Region::Mkk);
+        assert!(config.region.allows(14));
+    }
+
+    #[test]
+    fn parse_and_handle_set_channel_filter() {
+        let cmd = parse_command(br#"{"cmd":"set_channel_filter","channels":[1,6,11]}"#).unwrap();
+        let mask = ChannelMask::from_channels(&[1, 6, 11]).unwrap();
+        assert_eq!(cmd, HostCommand::SetChannelFilter(mask));
+        for bad in [
+            &br#"{"cmd":"set_channel_filter","channels":[]}"#[..],
+            br#"{"cmd":"set_channel_filter","channels":[6,15]}"#,
+        ] {
+            assert_eq!(parse_command(bad), Err(AirhoundError::InvalidArgument));
+        }
+        let mut config = FilterConfig::new();
+        let mut scanning = true;
+        handle_command(&cmd, &mut config, &mut scanning);
+        assert_eq!(config.channels, mask);
+
+        let cmd = parse_command(br#"{"cmd":"set_channel_filter"}"#).unwrap();
+        handle_command(&cmd, &mut config, &mut scanning);
+        assert_eq!(config.channels, ChannelMask::ALL);
+    }
+
+    #[test]
+    fn parse_set_location() {
+        let cmd =
+            parse_command(br#"{"cmd":"set_location","lat":476062000,"lon":-1223321000,"acc":5}"#)
+                .unwrap();
+        let location = Location::try_host(476_062_000, -1_223_321_000, Some(5)).unwrap();
+        assert_eq!(cmd, HostCommand::SetLocation(location));
+        assert_eq!(
+            parse_command(br#"{"cmd":"set_location","lat":950000000,"lon":0}"#),
+            Err(AirhoundError::InvalidArgument)
+        );
+        // Accuracy is optional; coordinates are not
+        let cmd = parse_command(br#"{"cmd":"set_location","lat":0,"lon":0}"#).unwrap();
+        assert!(matches!(
+            cmd,
+            HostCommand::SetLocation(Location { acc: None, .. })
+        ));
+        assert_eq!(
+            parse_command(br#"{"cmd":"set_location","lat":0}"#),
+            Err(AirhoundError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn parse_and_handle_set_location_stale() {
+        let cmd = parse_command(br#"{"cmd":"set_location_stale","max_age_s":120}"#).unwrap();
+        let policy = StalePolicy::try_new(120).unwrap();
+        assert_eq!(cmd, HostCommand::SetLocationStale(policy));
+        let mut config = FilterConfig::new();
+        let mut scanning = true;
+        handle_command(&cmd, &mut config, &mut scanning);
+        assert_eq!(config.location_stale, policy);
+        assert_eq!(
+            parse_command(br#"{"cmd":"set_location_stale","max_age_s":0}"#),
+            Err(AirhoundError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn parse_and_handle_set_following() {
+        let cmd = parse_command(
+            br#"{"cmd":"set_following","sightings":3,"window_min":20,"distance_m":800}"#,
+        )
+        .unwrap();
+        let policy = FollowingPolicy::try_new(3, 20, 800).unwrap();
+        assert_eq!(cmd, HostCommand::SetFollowing(policy));
+        let mut config = FilterConfig::new();
+        let mut scanning = true;
+        handle_command(&cmd, &mut config, &mut scanning);
+        assert_eq!(config.following, policy);
+        assert_eq!(
+            parse_command(
+                br#"{"cmd":"set_following","sightings":1,"window_min":20,"distance_m":800}"#
+            ),
+            Err(AirhoundError::InvalidArgument)
+        );
+        assert_eq!(
+            parse_command(br#"{"cmd":"set_following","sightings":3}"#),
+            Err(AirhoundError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn parse_and_handle_set_lingering() {
+        let cmd =
+            parse_command(br#"{"cmd":"set_lingering","duration_min":30,"min_rssi":-70}"#).unwrap();
+        let policy = LingeringPolicy::try_new(30, -70).unwrap();
+        assert_eq!(cmd, HostCommand::SetLingering(policy));
+        let mut config = FilterConfig::new();
+        let mut scanning = true;
+        handle_command(&cmd, &mut config, &mut scanning);
+        assert_eq!(config.lingering, policy);
+        assert_eq!(
+            parse_command(br#"{"cmd":"set_lingering","duration_min":0,"min_rssi":-70}"#),
+            Err(AirhoundError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn parse_set_time_and_quiet_hours() {
+        let cmd = parse_command(br#"{"cmd":"set_time","epoch":1767225600,"utc_offset_min":-420}"#)
+            .unwrap();
+        assert_eq!(
+            cmd,
+            HostCommand::SetTime {
+                epoch_s: 1_767_225_600,
+                utc_offset_min: -420
+            }
+        );
+        assert_eq!(
+            parse_command(br#"{"cmd":"set_time"}"#),
+            Err(AirhoundError::InvalidArgument)
+        );
+
+        let cmd =
+            parse_command(br#"{"cmd":"set_quiet_hours","start_min":1320,"end_min":420}"#).unwrap();
+        let hours = QuietHours::try_new(1320, 420).unwrap();
+        assert_eq!(cmd, HostCommand::SetQuietHours(hours));
+        assert_eq!(
+            parse_command(br#"{"cmd":"set_quiet_hours","start_min":1500,"end_min":0}"#),
+            Err(AirhoundError::InvalidArgument)
+        );
+
+        let mut config = FilterConfig::new();
+        let mut scanning = true;
+        handle_command(&cmd, &mut config, &mut scanning);
+        assert_eq!(config.quiet_hours, hours);
+    }
+
+    #[test]
+    fn parse_and_handle_set_categories() {
+        let cmd = parse_command(br#"{"cmd":"set_categories","tracker":false,"attack_tool":false}"#)
+            .unwrap();
+        let mut config = FilterConfig::new();
+        config.categories.drone = false;
+        let mut scanning = true;
+        handle_command(&cmd, &mut config, &mut scanning);
+        assert!(!config.categories.tracker);
+        assert!(!config.categories.attack_tool);
+        // Categories left out are switched back on
+        assert!(config.categories.drone);
+        assert!(config.categories.alpr);
+    }
+
+    #[test]
+    fn parse_irk_commands() {
+        let cmd = parse_command(br#"{"cmd":"add_irk","irk":"ec0234a357c8ad05341010a60a397d9b"}"#)
+            .unwrap();
+        assert_eq!(
+            cmd,
+            HostCommand::AddIrk(Irk::from_hex("ec0234a357c8ad05341010a60a397d9b").unwrap())
+        );
+        assert_eq!(
+            parse_command(br#"{"cmd":"add_irk","irk":"ec02"}"#),
+            Err(AirhoundError::InvalidArgument)
+        );
+        assert_eq!(
+            parse_command(br#"{"cmd":"clear_irks"}"#),
+            Ok(HostCommand::ClearIrks)
+        );
+    }
+
+    #[test]
+    fn handle_set_ble_scan_updates_config() {
+        let scan = BleScanConfig::try_new(500, 50, false).unwrap();
+        let mut config = FilterConfig::new();
+        let mut scanning = true;
+        let result = handle_command(&HostCommand::SetBleScan(scan), &mut config, &mut scanning);
+        assert_eq!(result, None);
+        assert_eq!(config.ble_scan, scan);
+    }
+
     #[test]
     fn handle_set_buzzer_returns_state() {
         let cmd = HostCommand::SetBuzzer { enabled: false };
@@ -357,6 +1478,87 @@ mod tests {
         assert!(scanning);
     }
 
+    // ── GATT characteristic tests ───────────────────────────────────
+
+    #[test]
+    fn status_char_layout() {
+        let out = encode_status(true, 2, 3600, 45000);
+        assert_eq!(out[0], 1);
+        assert_eq!(out[1], 2);
+        assert_eq!(u32::from_le_bytes([out[2], out[3], out[4], out[5]]), 3600);
+        assert_eq!(u32::from_le_bytes([out[6], out[7], out[8], out[9]]), 45000);
+    }
+
+    #[test]
+    fn counters_char_layout() {
+        let out = encode_counters(7, 0x0102_0304);
+        assert_eq!(out, [7, 0, 0, 0, 4, 3, 2, 1]);
+    }
+
+    #[test]
+    fn settings_round_trip() {
+        let config = FilterConfig {
+            min_rssi: -70,
+            ble_enabled: false,
+            ble_scan: BleScanConfig::try_new(1000, 100, false).unwrap(),
+            ..FilterConfig::new()
+        };
+        let settings = DeviceSettings::new(&config, true, false);
+        let bytes = settings.to_bytes();
+        assert_eq!(bytes[0], -70i8 as u8);
+        assert_eq!(bytes[1], 0b0_0011);
+        let decoded = DeviceSettings::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded, settings);
+    }
+
+    #[test]
+    fn settings_rejects_short_or_invalid_writes() {
+        assert!(DeviceSettings::from_bytes(&[0xB0, 0x1F, 100, 0]).is_none());
+        // window (200) > interval (100)
+        assert!(DeviceSettings::from_bytes(&[0xB0, 0x1F, 100, 0, 200, 0]).is_none());
+    }
+
+    #[test]
+    fn handle_configure_applies_settings() {
+        let settings = DeviceSettings {
+            min_rssi: -60,
+            ..DeviceSettings::new(&FilterConfig::new(), false, true)
+        };
+        let mut config = FilterConfig::new();
+        let mut scanning = true;
+        let result = handle_command(
+            &HostCommand::Configure(settings),
+            &mut config,
+            &mut scanning,
+        );
+        assert_eq!(result, Some(true));
+        assert_eq!(config.min_rssi, -60);
+        assert!(!scanning);
+    }
+
+    #[test]
+    fn handle_configure_keeps_other_settings() {
+        let hours = QuietHours::try_new(1320, 420).unwrap();
+        let mut config = FilterConfig {
+            quiet_hours: hours,
+            report_self: true,
+            fcs_check: true,
+            ..FilterConfig::new()
+        };
+        let mut scanning = true;
+        // The characteristic carries none of these
+        let settings = DeviceSettings::from_bytes(&[0xB0, 0x07, 0xE8, 0x03, 0x64, 0x00]).unwrap();
+        handle_command(
+            &HostCommand::Configure(settings),
+            &mut config,
+            &mut scanning,
+        );
+        assert_eq!(config.min_rssi, -80);
+        assert_eq!(config.quiet_hours, hours);
+        assert!(config.report_self);
+        assert!(config.fcs_check);
+    }
+
     // ── LineReader tests ────────────────────────────────────────────
 
     #[test]
@@ -364,7 +1566,7 @@ mod tests {
         let mut reader = LineReader::new();
         assert!(reader.feed(b'h').is_none());
         assert!(reader.feed(b'i').is_none());
-        let line = reader.feed(b'\n').unwrap();
+        let line = reader.feed(b'\n').unwrap().unwrap();
         assert_eq!(line, b"hi");
     }
 
@@ -373,7 +1575,7 @@ mod tests {
         let mut reader = LineReader::new();
         reader.feed(b'o');
         reader.feed(b'k');
-        let line = reader.feed(b'\r').unwrap();
+        let line = reader.feed(b'\r').unwrap().unwrap();
         assert_eq!(line, b"ok");
     }
 
@@ -395,7 +1597,7 @@ mod tests {
         // Feed last byte
         assert!(reader.feed(json[json.len() - 1]).is_none());
         // Feed newline to yield
-        let line = reader.feed(b'\n').unwrap();
+        let line = reader.feed(b'\n').unwrap().unwrap();
         assert_eq!(line, &json[..]);
     }
 
@@ -406,14 +1608,16 @@ mod tests {
         for i in 0..MAX_MSG_LEN {
             reader.feed(b'A' + (i % 26) as u8);
         }
-        // Next byte overflows — should discard
+        // Further bytes overflow and are discarded along with the line
         assert!(reader.feed(b'X').is_none());
-        // After overflow reset, newline on empty buffer yields nothing
+        assert!(reader.feed(b'Y').is_none());
+        // The end of the line reports the overflow
+        assert_eq!(reader.feed(b'\n'), Some(Err(AirhoundError::BufferTooSmall)));
         assert!(reader.feed(b'\n').is_none());
         // But new data works
         reader.feed(b'o');
         reader.feed(b'k');
-        let line = reader.feed(b'\n').unwrap();
+        let line = reader.feed(b'\n').unwrap().unwrap();
         assert_eq!(line, b"ok");
     }
 
@@ -421,10 +1625,67 @@ mod tests {
     fn line_reader_multiple_lines() {
         let mut reader = LineReader::new();
         for &b in b"line1\nline2\n" {
-            if let Some(line) = reader.feed(b) {
+            if let Some(Ok(line)) = reader.feed(b) {
                 let s = core::str::from_utf8(line).unwrap();
                 assert!(s == "line1" || s == "line2");
             }
         }
     }
+
+    // ── CommandMux tests ────────────────────────────────────────────
+
+    fn feed_all<const N: usize>(
+        mux: &mut CommandMux<N>,
+        id: InputId,
+        bytes: &[u8],
+    ) -> Option<Result<HostCommand, AirhoundError>> {
+        bytes.iter().filter_map(|&b| mux.feed(id, b)).last()
+    }
+
+    #[test]
+    fn mux_keeps_interleaved_inputs_apart() {
+        let mut mux = CommandMux::<2>::new();
+        let serial = mux.open(CommandSource::Serial).unwrap();
+        let ble = mux.open(CommandSource::Ble).unwrap();
+        assert_eq!(mux.source(serial), Some(CommandSource::Serial));
+        assert_eq!(mux.source(ble), Some(CommandSource::Ble));
+
+        assert!(feed_all(&mut mux, serial, br#"{"cmd":"st"#).is_none());
+        assert!(feed_all(&mut mux, ble, br#"{"cmd":"status"}"#).is_none());
+        assert!(feed_all(&mut mux, serial, br#"op"}"#).is_none());
+        assert_eq!(mux.feed(ble, b'\n'), Some(Ok(HostCommand::GetStatus)));
+        assert_eq!(mux.feed(serial, b'\n'), Some(Ok(HostCommand::Stop)));
+    }
+
+    #[test]
+    fn mux_reports_invalid_lines() {
+        let mut mux = CommandMux::<1>::new();
+        let serial = mux.open(CommandSource::Serial).unwrap();
+        assert_eq!(
+            feed_all(&mut mux, serial, b"not json\n"),
+            Some(Err(AirhoundError::Malformed))
+        );
+        assert_eq!(
+            feed_all(&mut mux, serial, b"{\"cmd\":\"start\"}\r\n"),
+            Some(Ok(HostCommand::Start))
+        );
+    }
+
+    #[test]
+    fn mux_reuses_closed_inputs() {
+        let mut mux = CommandMux::<1>::new();
+        let first = mux.open(CommandSource::Ble).unwrap();
+        assert!(mux.open(CommandSource::Serial).is_none());
+
+        // Closing discards the partial line
+        feed_all(&mut mux, first, br#"{"cmd":"sta"#);
+        mux.close(first);
+        assert!(mux.feed(first, b'\n').is_none());
+
+        let second = mux.open(CommandSource::Serial).unwrap();
+        assert_eq!(
+            feed_all(&mut mux, second, b"{\"cmd\":\"stop\"}\n"),
+            Some(Ok(HostCommand::Stop))
+        );
+    }
 }
@@ -0,0 +1,361 @@
+/// Stream compression for NDJSON output on slow or size-limited links.
+///
+/// [`Compressor`]/[`Decompressor`] implement heatshrink-compatible LZSS
+/// (window 2^8, lookahead 2^4) with fixed-size state, suitable for firmware
+/// writing to SD card or a serial/LoRa link. The window persists across
+/// `compress` calls, so repeated JSON keys in successive messages compress
+/// well. A stream ends with [`Compressor::finish`]; the receiving side starts a
+/// fresh [`Decompressor`] per stream.
+///
+/// With the `deflate` feature, std hosts also get raw-deflate helpers for
+/// exports where compatibility with standard tools matters more than RAM.
+///
+/// log2 of the back-reference window size
+pub const WINDOW_BITS: u8 = 8;
+
+/// log2 of the maximum match length
+pub const LOOKAHEAD_BITS: u8 = 4;
+
+const WINDOW: usize = 1 << WINDOW_BITS;
+const MAX_MATCH: usize = 1 << LOOKAHEAD_BITS;
+
+/// Shortest match worth a back-reference: 13 bits vs 9 bits per literal
+const MIN_MATCH: usize = 2;
+
+/// Bit-level output cursor over a caller-provided buffer
+struct BitSink<'a> {
+    out: &'a mut [u8],
+    len: usize,
+}
+
+impl BitSink<'_> {
+    fn push_byte(&mut self, byte: u8) -> Option<()> {
+        *self.out.get_mut(self.len)? = byte;
+        self.len += 1;
+        Some(())
+    }
+}
+
+/// Streaming heatshrink encoder.
+pub struct Compressor {
+    history: [u8; WINDOW],
+    /// Next write position in `history`
+    head: usize,
+    /// Valid bytes in `history` (saturates at WINDOW)
+    filled: usize,
+    /// Pending output bits, MSB-first
+    acc: u32,
+    nbits: u8,
+}
+
+impl Compressor {
+    pub const fn new() -> Self {
+        Self {
+            history: [0; WINDOW],
+            head: 0,
+            filled: 0,
+            acc: 0,
+            nbits: 0,
+        }
+    }
+
+    /// Compress `input`, appending whole bytes to `out`. Up to 7 bits may
+    /// stay pending until the next call or [`finish`](Self::finish).
+    /// Returns bytes written, or `None` if `out` is too small (the stream is
+    /// then unusable and should be restarted).
+    pub fn compress(&mut self, input: &[u8], out: &mut [u8]) -> Option<usize> {
+        let mut sink = BitSink { out, len: 0 };
+        let mut pos = 0;
+        while pos < input.len() {
+            let (distance, length) = self.longest_match(input, pos);
+            if length >= MIN_MATCH {
+                self.put_bits(0, 1, &mut sink)?;
+                self.put_bits((distance - 1) as u32, WINDOW_BITS, &mut sink)?;
+                self.put_bits((length - 1) as u32, LOOKAHEAD_BITS, &mut sink)?;
+                for &byte in &input[pos..pos + length] {
+                    self.remember(byte);
+                }
+                pos += length;
+            } else {
+                self.put_bits(1, 1, &mut sink)?;
+                self.put_bits(input[pos] as u32, 8, &mut sink)?;
+                self.remember(input[pos]);
+                pos += 1;
+            }
+        }
+        Some(sink.len)
+    }
+
+    /// Flush pending bits (zero-padded) and reset for a new stream.
+    pub fn finish(&mut self, out: &mut [u8]) -> Option<usize> {
+        let mut sink = BitSink { out, len: 0 };
+        if self.nbits > 0 {
+            sink.push_byte((self.acc << (8 - self.nbits)) as u8)?;
+        }
+        *self = Self::new();
+        Some(sink.len)
+    }
+
+    fn put_bits(&mut self, value: u32, count: u8, sink: &mut BitSink) -> Option<()> {
+        self.acc = (self.acc << count) | (value & ((1 << count) - 1));
+        self.nbits += count;
+        while self.nbits >= 8 {
+            self.nbits -= 8;
+            sink.push_byte((self.acc >> self.nbits) as u8)?;
+        }
+        self.acc &= (1 << self.nbits) - 1;
+        Some(())
+    }
+
+    fn remember(&mut self, byte: u8) {
+        self.history[self.head] = byte;
+        self.head = (self.head + 1) % WINDOW;
+        self.filled = (self.filled + 1).min(WINDOW);
+    }
+
+    /// Byte `back` positions before the next byte to encode. Every consumed
+    /// input byte is already in the history window.
+    fn byte_before(&self, back: usize) -> u8 {
+        self.history[(self.head + WINDOW - back) % WINDOW]
+    }
+
+    /// Longest match for `input[pos..]` within the window: (distance, length).
+    fn longest_match(&self, input: &[u8], pos: usize) -> (usize, usize) {
+        let available = (input.len() - pos).min(MAX_MATCH);
+        let reach = self.filled;
+        let mut best = (0, 0);
+        for distance in 1..=reach {
+            let mut length = 0;
+            // Matches may overlap the bytes being encoded (distance < length)
+            while length < available {
+                let candidate = if length < distance {
+                    self.byte_before(distance - length)
+                } else {
+                    input[pos + length - distance]
+                };
+                if candidate != input[pos + length] {
+                    break;
+                }
+                length += 1;
+            }
+            if length > best.1 {
+                best = (distance, length);
+                if length == available {
+                    break;
+                }
+            }
+        }
+        best
+    }
+}
+
+impl Default for Compressor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Streaming heatshrink decoder.
+pub struct Decompressor {
+    history: [u8; WINDOW],
+    head: usize,
+    acc: u32,
+    nbits: u8,
+}
+
+impl Decompressor {
+    pub const fn new() -> Self {
+        Self {
+            history: [0; WINDOW],
+            head: 0,
+            acc: 0,
+            nbits: 0,
+        }
+    }
+
+    /// Decode as much of `input` as possible into `out`. Incomplete tokens
+    /// are carried over to the next call. Returns bytes written, or `None` if
+    /// `out` is too small.
+    pub fn decompress(&mut self, input: &[u8], out: &mut [u8]) -> Option<usize> {
+        let mut len = 0;
+        for &byte in input {
+            self.acc = (self.acc << 8) | byte as u32;
+            self.nbits += 8;
+
+            loop {
+                if self.nbits < 1 {
+                    break;
+                }
+                let is_literal = (self.acc >> (self.nbits - 1)) & 1 == 1;
+                if is_literal {
+                    if self.nbits < 9 {
+                        break;
+                    }
+                    self.nbits -= 9;
+                    let value = (self.acc >> self.nbits) as u8;
+                    *out.get_mut(len)? = value;
+                    len += 1;
+                    self.remember(value);
+                } else {
+                    let needed = 1 + WINDOW_BITS + LOOKAHEAD_BITS;
+                    if self.nbits < needed {
+                        break;
+                    }
+                    self.nbits -= needed;
+                    let token = self.acc >> self.nbits;
+                    let distance = ((token >> LOOKAHEAD_BITS) & (WINDOW as u32 - 1)) as usize + 1;
+                    let length = (token & (MAX_MATCH as u32 - 1)) as usize + 1;
+                    for _ in 0..length {
+                        let value = self.history[(self.head + WINDOW - distance) % WINDOW];
+                        *out.get_mut(len)? = value;
+                        len += 1;
+                        self.remember(value);
+                    }
+                }
+                self.acc &= (1 << self.nbits) - 1;
+            }
+        }
+        Some(len)
+    }
+
+    fn remember(&mut self, byte: u8) {
+        self.history[self.head] = byte;
+        self.head = (self.head + 1) % WINDOW;
+    }
+}
+
+impl Default for Decompressor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Raw deflate for std hosts (no zlib/gzip header).
+#[cfg(feature = "deflate")]
+pub mod deflate {
+    use std::io::{self, Read, Write};
+
+    use flate2::read::DeflateDecoder;
+    use flate2::write::DeflateEncoder;
+    use flate2::Compression;
+
+    /// Compress a complete buffer.
+    pub fn compress(data: &[u8]) -> io::Result<Vec<u8>> {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data)?;
+        encoder.finish()
+    }
+
+    /// Decompress a complete buffer.
+    pub fn decompress(data: &[u8]) -> io::Result<Vec<u8>> {
+        let mut out = Vec::new();
+        DeflateDecoder::new(data).read_to_end(&mut out)?;
+        Ok(out)
+    }
+
+    /// Wrap a writer so everything written to it is deflated, e.g. an NDJSON
+    /// export file. Call `finish()` on the result to flush the final block.
+    pub fn writer<W: Write>(inner: W) -> DeflateEncoder<W> {
+        DeflateEncoder::new(inner, Compression::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &[u8] = br#"{"type":"wifi","mac":"B4:1E:52:AB:CD:EF","ssid":"Flock-A1B2C3","rssi":-45,"ch":6,"frame":"beacon","match":[{"type":"mac_oui","detail":"Flock Safety"}],"ts":1000}
+"#;
+
+    fn round_trip(chunks: &[&[u8]]) -> (usize, heapless::Vec<u8, 4096>) {
+        let mut compressor = Compressor::new();
+        let mut packed = [0u8; 4096];
+        let mut packed_len = 0;
+        for chunk in chunks {
+            packed_len += compressor
+                .compress(chunk, &mut packed[packed_len..])
+                .unwrap();
+        }
+        packed_len += compressor.finish(&mut packed[packed_len..]).unwrap();
+
+        let mut decompressor = Decompressor::new();
+        let mut out = [0u8; 4096];
+        let out_len = decompressor
+            .decompress(&packed[..packed_len], &mut out)
+            .unwrap();
+        (
+            packed_len,
+            heapless::Vec::from_slice(&out[..out_len]).unwrap(),
+        )
+    }
+
+    // ── Heatshrink round trips ──────────────────────────────────────
+
+    #[test]
+    fn round_trip_single_message() {
+        let (packed_len, out) = round_trip(&[SAMPLE]);
+        assert_eq!(out.as_slice(), SAMPLE);
+        assert!(packed_len < SAMPLE.len());
+    }
+
+    #[test]
+    fn window_carries_across_messages() {
+        let (single, _) = round_trip(&[SAMPLE]);
+        let (double, out) = round_trip(&[SAMPLE, SAMPLE]);
+        assert_eq!(&out[..SAMPLE.len()], SAMPLE);
+        assert_eq!(&out[SAMPLE.len()..], SAMPLE);
+        // The second copy is almost entirely back-references
+        assert!(double - single < SAMPLE.len() / 4);
+    }
+
+    #[test]
+    fn round_trip_runs_and_incompressible_bytes() {
+        let run = [b'a'; 100];
+        let (packed_len, out) = round_trip(&[&run]);
+        assert_eq!(out.as_slice(), &run[..]);
+        assert!(packed_len < 20);
+
+        let mut noise = [0u8; 300];
+        let mut x = 0x1234_5678u32;
+        for b in noise.iter_mut() {
+            x ^= x << 13;
+            x ^= x >> 17;
+            x ^= x << 5;
+            *b = x as u8;
+        }
+        let (_, out) = round_trip(&[&noise]);
+        assert_eq!(out.as_slice(), &noise[..]);
+    }
+
+    #[test]
+    fn decompress_accepts_split_input() {
+        let mut compressor = Compressor::new();
+        let mut packed = [0u8; 512];
+        let mut len = compressor.compress(SAMPLE, &mut packed).unwrap();
+        len += compressor.finish(&mut packed[len..]).unwrap();
+
+        let mut decompressor = Decompressor::new();
+        let mut out = [0u8; 512];
+        let mut out_len = 0;
+        for byte in &packed[..len] {
+            out_len += decompressor
+                .decompress(core::slice::from_ref(byte), &mut out[out_len..])
+                .unwrap();
+        }
+        assert_eq!(&out[..out_len], SAMPLE);
+    }
+
+    #[test]
+    fn small_output_buffer_fails() {
+        let mut compressor = Compressor::new();
+        let mut packed = [0u8; 8];
+        assert!(compressor.compress(SAMPLE, &mut packed).is_none());
+    }
+
+    #[test]
+    fn empty_input_produces_nothing() {
+        let (packed_len, out) = round_trip(&[b""]);
+        assert_eq!(packed_len, 0);
+        assert!(out.is_empty());
+    }
+}
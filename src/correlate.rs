@@ -0,0 +1,233 @@
+/// Cross-radio correlation of WiFi and BLE detections.
+///
+/// A Flock camera beacons its WiFi SSID and advertises over BLE from MACs
+/// a few addresses apart in the same OUI. [`CrossRadio`] remembers recent
+/// detections from each radio and pairs a new one with a detection from the
+/// other radio whose MAC is adjacent, so `filter_task` can report the two as
+/// one device with more confidence than either sighting alone.
+use heapless::Vec;
+
+use crate::defaults::SignatureMeta;
+
+/// Detections older than this are no longer paired
+pub const CORRELATION_WINDOW_MS: u32 = 60 * 1000;
+
+/// Largest difference between the device-specific halves of two MACs that
+/// still counts as the same device
+pub const MAX_MAC_DISTANCE: u32 = 8;
+
+/// Radio a detection was made on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Radio {
+    WiFi,
+    Ble,
+}
+
+impl Radio {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Radio::WiFi => "wifi",
+            Radio::Ble => "ble",
+        }
+    }
+}
+
+/// One reported detection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Detection {
+    pub mac: [u8; 6],
+    pub rssi: i8,
+    pub meta: SignatureMeta,
+}
+
+/// A WiFi and a BLE detection taken to be the same device
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Correlation {
+    pub wifi: Detection,
+    pub ble: Detection,
+    /// Rating of the stronger detection, one step more confident
+    pub meta: SignatureMeta,
+}
+
+/// Whether `a` and `b` share an OUI and their device-specific halves are at
+/// most [`MAX_MAC_DISTANCE`] apart.
+pub fn macs_adjacent(a: &[u8; 6], b: &[u8; 6]) -> bool {
+    let nic = |mac: &[u8; 6]| u32::from_be_bytes([0, mac[3], mac[4], mac[5]]);
+    a[..3] == b[..3] && nic(a).abs_diff(nic(b)) <= MAX_MAC_DISTANCE
+}
+
+struct Entry {
+    radio: Radio,
+    detection: Detection,
+    /// MAC of the other radio's detection this one was reported with
+    partner: Option<[u8; 6]>,
+    last_ms: u32,
+}
+
+/// Recent detections from both radios, up to `N` in all. Timestamps are
+/// milliseconds since boot (`u32`, wrapping).
+pub struct CrossRadio<const N: usize> {
+    entries: Vec<Entry, N>,
+}
+
+impl<const N: usize> CrossRadio<N> {
+    pub const fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Record a detection on `radio` and return its correlation with the
+    /// other radio, if it has a new partner there. A pair is reported once
+    /// while both sides keep being seen within [`CORRELATION_WINDOW_MS`].
+    pub fn observe(
+        &mut self,
+        radio: Radio,
+        detection: Detection,
+        now_ms: u32,
+    ) -> Option<Correlation> {
+        self.entries
+            .retain(|e| now_ms.wrapping_sub(e.last_ms) < CORRELATION_WINDOW_MS);
+
+        let index = match self
+            .entries
+            .iter()
+            .position(|e| e.radio == radio && e.detection.mac == detection.mac)
+        {
+            Some(index) => {
+                let entry = &mut self.entries[index];
+                entry.detection = detection;
+                entry.last_ms = now_ms;
+                index
+            }
+            None => self.insert(Entry {
+                radio,
+                detection,
+                partner: None,
+                last_ms: now_ms,
+            })?,
+        };
+
+        let other = self
+            .entries
+            .iter()
+            .position(|e| e.radio != radio && macs_adjacent(&e.detection.mac, &detection.mac))?;
+        let other_mac = self.entries[other].detection.mac;
+        if self.entries[index].partner == Some(other_mac) {
+            return None;
+        }
+        self.entries[index].partner = Some(other_mac);
+        self.entries[other].partner = Some(detection.mac);
+
+        let partner = self.entries[other].detection;
+        let (wifi, ble) = match radio {
+            Radio::WiFi => (detection, partner),
+            Radio::Ble => (partner, detection),
+        };
+        let mut meta = if ble.meta.outranks(&wifi.meta) {
+            ble.meta
+        } else {
+            wifi.meta
+        };
+        meta.confidence = wifi.meta.confidence.max(ble.meta.confidence).raised();
+        Some(Correlation { wifi, ble, meta })
+    }
+
+    /// Add an entry, replacing the one heard from longest ago when full.
+    /// Returns its index.
+    fn insert(&mut self, entry: Entry) -> Option<usize> {
+        match self.entries.push(entry) {
+            Ok(()) => Some(self.entries.len() - 1),
+            Err(entry) => {
+                let now_ms = entry.last_ms;
+                let (index, stalest) = self
+                    .entries
+                    .iter_mut()
+                    .enumerate()
+                    .max_by_key(|(_, e)| now_ms.wrapping_sub(e.last_ms))?;
+                *stalest = entry;
+                Some(index)
+            }
+        }
+    }
+}
+
+impl<const N: usize> Default for CrossRadio<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::defaults::{Category, Confidence, Severity};
+
+    const WIFI_MAC: [u8; 6] = [0x58, 0x8E, 0x81, 0x01, 0x02, 0x03];
+    const BLE_MAC: [u8; 6] = [0x58, 0x8E, 0x81, 0x01, 0x02, 0x05];
+
+    fn detection(mac: [u8; 6], confidence: Confidence) -> Detection {
+        Detection {
+            mac,
+            rssi: -60,
+            meta: SignatureMeta::new(Category::Alpr, Severity::High, confidence),
+        }
+    }
+
+    #[test]
+    fn adjacent_macs() {
+        assert!(macs_adjacent(&WIFI_MAC, &BLE_MAC));
+        assert!(macs_adjacent(
+            &[0x58, 0x8E, 0x81, 0x01, 0x02, 0xFF],
+            &[0x58, 0x8E, 0x81, 0x01, 0x03, 0x01]
+        ));
+        assert!(!macs_adjacent(
+            &WIFI_MAC,
+            &[0x58, 0x8E, 0x81, 0x01, 0x02, 0x20]
+        ));
+        assert!(!macs_adjacent(
+            &WIFI_MAC,
+            &[0x58, 0x8E, 0x82, 0x01, 0x02, 0x03]
+        ));
+    }
+
+    #[test]
+    fn pairs_wifi_and_ble_with_boosted_confidence() {
+        let mut radios = CrossRadio::<4>::new();
+        let wifi = detection(WIFI_MAC, Confidence::Medium);
+        let ble = detection(BLE_MAC, Confidence::Low);
+        assert_eq!(radios.observe(Radio::WiFi, wifi, 0), None);
+        let pair = radios.observe(Radio::Ble, ble, 1_000).unwrap();
+        assert_eq!(pair.wifi.mac, WIFI_MAC);
+        assert_eq!(pair.ble.mac, BLE_MAC);
+        assert_eq!(pair.meta.confidence, Confidence::High);
+        assert_eq!(pair.meta.category, Category::Alpr);
+    }
+
+    #[test]
+    fn reports_a_pair_once() {
+        let mut radios = CrossRadio::<4>::new();
+        let wifi = detection(WIFI_MAC, Confidence::Medium);
+        let ble = detection(BLE_MAC, Confidence::Medium);
+        radios.observe(Radio::WiFi, wifi, 0);
+        assert!(radios.observe(Radio::Ble, ble, 1_000).is_some());
+        assert_eq!(radios.observe(Radio::WiFi, wifi, 2_000), None);
+        assert_eq!(radios.observe(Radio::Ble, ble, 3_000), None);
+    }
+
+    #[test]
+    fn same_radio_is_not_paired() {
+        let mut radios = CrossRadio::<4>::new();
+        radios.observe(Radio::WiFi, detection(WIFI_MAC, Confidence::Medium), 0);
+        let other = radios.observe(Radio::WiFi, detection(BLE_MAC, Confidence::Medium), 0);
+        assert_eq!(other, None);
+    }
+
+    #[test]
+    fn detections_expire() {
+        let mut radios = CrossRadio::<4>::new();
+        radios.observe(Radio::WiFi, detection(WIFI_MAC, Confidence::Medium), 0);
+        let ble = detection(BLE_MAC, Confidence::Medium);
+        assert_eq!(radios.observe(Radio::Ble, ble, CORRELATION_WINDOW_MS), None);
+    }
+}
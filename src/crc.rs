@@ -0,0 +1,39 @@
+/// CRC-32 (IEEE 802.3), the checksum of 802.11 frame check sequences and of
+/// zip entries.
+///
+/// Table-driven: this lookup table for the reflected polynomial `0xEDB88320`
+/// is built at compile time, so a checksum is allocation-free and safe to
+/// compute in ISR context.
+const TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg());
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// CRC-32 of `data`
+pub fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, &byte| {
+        (crc >> 8) ^ TABLE[((crc ^ byte as u32) & 0xFF) as usize]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+}
@@ -0,0 +1,345 @@
+/// Constant Tone Extension (CTE) direction finding.
+///
+/// Parses HCI LE Connectionless IQ Report events and derives a coarse
+/// angle-of-arrival from a two-antenna array. Only controllers with CTE
+/// support produce these reports (the ESP32 and ESP32-S3 controllers do not);
+/// this is the plumbing a bearing display needs once one does.
+///
+/// Angle estimation assumes the reference period is sampled on antenna 0 and
+/// the switching pattern then alternates antenna 1, antenna 0 starting at the
+/// first sample slot. Positive angles point toward antenna 1.
+use heapless::Vec;
+
+/// HCI LE meta subevent code for Connectionless IQ Report
+pub const HCI_SUBEVT_CONNECTIONLESS_IQ_REPORT: u8 = 0x15;
+
+/// Maximum IQ samples per report (8 reference + 74 switched)
+pub const MAX_IQ_SAMPLES: usize = 82;
+
+/// Samples taken during the 8 µs reference period, one per microsecond
+const REFERENCE_SAMPLES: usize = 8;
+
+/// Wavelength at the centre of the 2.4 GHz band (2440 MHz), in millimetres
+const WAVELENGTH_MM: f32 = 122.9;
+
+const PI: f32 = core::f32::consts::PI;
+
+/// CTE type carried by the advertising train
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CteType {
+    /// Angle of Arrival — receiver switches antennas
+    Aoa,
+    /// Angle of Departure, 1 µs slots — transmitter switches antennas
+    Aod1us,
+    /// Angle of Departure, 2 µs slots
+    Aod2us,
+}
+
+impl CteType {
+    pub fn from_hci(value: u8) -> Option<Self> {
+        match value {
+            0x00 => Some(CteType::Aoa),
+            0x01 => Some(CteType::Aod1us),
+            0x02 => Some(CteType::Aod2us),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CteType::Aoa => "aoa",
+            CteType::Aod1us => "aod_1us",
+            CteType::Aod2us => "aod_2us",
+        }
+    }
+}
+
+/// One in-phase/quadrature sample
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IqSample {
+    pub i: i8,
+    pub q: i8,
+}
+
+/// A parsed Connectionless IQ Report
+#[derive(Debug, Clone)]
+pub struct IqReport {
+    /// Periodic advertising sync handle — the caller maps it to a device
+    pub sync_handle: u16,
+    pub channel: u8,
+    /// RSSI in dBm (the HCI value is in 0.1 dBm units)
+    pub rssi: i8,
+    pub cte_type: CteType,
+    /// Switch/sample slot duration in microseconds (1 or 2)
+    pub slot_us: u8,
+    pub samples: Vec<IqSample, MAX_IQ_SAMPLES>,
+}
+
+/// Parse LE Connectionless IQ Report parameters (after the subevent code).
+/// Returns `None` for malformed reports and reports whose samples the
+/// controller could not capture (packet status 0xFF).
+pub fn parse_iq_report(params: &[u8]) -> Option<IqReport> {
+    if params.len() < 12 || params[8] == 0xFF {
+        return None;
+    }
+    let sample_count = params[11] as usize;
+    if sample_count > MAX_IQ_SAMPLES || params.len() < 12 + 2 * sample_count {
+        return None;
+    }
+
+    let rssi_tenths = i16::from_le_bytes([params[3], params[4]]);
+    let mut samples = Vec::new();
+    for pair in params[12..12 + 2 * sample_count].chunks_exact(2) {
+        let _ = samples.push(IqSample {
+            i: pair[0] as i8,
+            q: pair[1] as i8,
+        });
+    }
+
+    Some(IqReport {
+        sync_handle: u16::from_le_bytes([params[0], params[1]]),
+        channel: params[2],
+        rssi: (rssi_tenths / 10).clamp(i8::MIN as i16, i8::MAX as i16) as i8,
+        cte_type: CteType::from_hci(params[6])?,
+        slot_us: match params[7] {
+            0x02 => 2,
+            _ => 1,
+        },
+        samples,
+    })
+}
+
+/// Estimate the angle of arrival, in degrees from broadside, for a
+/// two-antenna array with `spacing_mm` between elements (at most half a
+/// wavelength, ~61 mm, to stay unambiguous).
+///
+/// Returns `None` without a full reference period plus at least one
+/// antenna-1/antenna-0 sample pair, or if the samples carry no signal.
+pub fn estimate_aoa(report: &IqReport, spacing_mm: f32) -> Option<i8> {
+    let samples = &report.samples;
+    if samples.len() < REFERENCE_SAMPLES + 2 || spacing_mm <= 0.0 {
+        return None;
+    }
+
+    // Carrier rotation per microsecond (frequency offset), from the reference
+    // period where every sample is on antenna 0
+    let mut rotation = (0.0, 0.0);
+    for k in 0..REFERENCE_SAMPLES - 1 {
+        let p = mul_conj(complex(samples[k + 1]), complex(samples[k]));
+        rotation = (rotation.0 + p.0, rotation.1 + p.1);
+    }
+    let rotation = normalize(rotation)?;
+
+    // Rotation between consecutive sample slots (switch slot + sample slot)
+    let mut step = (1.0, 0.0);
+    for _ in 0..2 * report.slot_us {
+        step = mul(step, rotation);
+    }
+
+    // For each (antenna 1, antenna 0) pair, bring the antenna 0 sample back
+    // to the antenna 1 sample time and accumulate the phase difference
+    let mut diff = (0.0, 0.0);
+    for pair in samples[REFERENCE_SAMPLES..].chunks_exact(2) {
+        let a1 = complex(pair[0]);
+        let a0 = mul_conj(complex(pair[1]), step);
+        let p = mul_conj(a1, a0);
+        diff = (diff.0 + p.0, diff.1 + p.1);
+    }
+    normalize(diff)?;
+
+    let phase = atan2(diff.1, diff.0);
+    let sin_theta = (phase * WAVELENGTH_MM / (2.0 * PI * spacing_mm)).clamp(-1.0, 1.0);
+    let theta = atan2(sin_theta, sqrt(1.0 - sin_theta * sin_theta));
+    let degrees = theta * 180.0 / PI;
+    Some(if degrees >= 0.0 {
+        (degrees + 0.5) as i8
+    } else {
+        (degrees - 0.5) as i8
+    })
+}
+
+// ── no_std float helpers (core has no trig or sqrt) ──────────────────
+
+fn complex(s: IqSample) -> (f32, f32) {
+    (s.i as f32, s.q as f32)
+}
+
+fn mul(a: (f32, f32), b: (f32, f32)) -> (f32, f32) {
+    (a.0 * b.0 - a.1 * b.1, a.0 * b.1 + a.1 * b.0)
+}
+
+/// `a * conj(b)` — rotates `a` back by the phase of `b`
+fn mul_conj(a: (f32, f32), b: (f32, f32)) -> (f32, f32) {
+    (a.0 * b.0 + a.1 * b.1, a.1 * b.0 - a.0 * b.1)
+}
+
+fn normalize(a: (f32, f32)) -> Option<(f32, f32)> {
+    let mag = sqrt(a.0 * a.0 + a.1 * a.1);
+    if mag < 1e-6 {
+        return None;
+    }
+    Some((a.0 / mag, a.1 / mag))
+}
+
+fn sqrt(x: f32) -> f32 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    let mut guess = if x > 1.0 { x } else { 1.0 };
+    for _ in 0..24 {
+        guess = 0.5 * (guess + x / guess);
+    }
+    guess
+}
+
+/// Polynomial atan2, max error ~0.01°.
+fn atan2(y: f32, x: f32) -> f32 {
+    let (ax, ay) = (if x < 0.0 { -x } else { x }, if y < 0.0 { -y } else { y });
+    if ax == 0.0 && ay == 0.0 {
+        return 0.0;
+    }
+    let a = if ax > ay { ay / ax } else { ax / ay };
+    let s = a * a;
+    let mut r = ((-0.046_496_473 * s + 0.159_314_22) * s - 0.327_622_76) * s * a + a;
+    if ay > ax {
+        r = PI / 2.0 - r;
+    }
+    if x < 0.0 {
+        r = PI - r;
+    }
+    if y < 0.0 {
+        -r
+    } else {
+        r
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build report parameters with `count` IQ pairs
+    fn report_params(count: u8, iq: &[(i8, i8)]) -> heapless::Vec<u8, 200> {
+        let mut p = heapless::Vec::new();
+        p.extend_from_slice(&[0x01, 0x00]).unwrap(); // sync handle
+        p.push(17).unwrap(); // channel
+        p.extend_from_slice(&(-655i16).to_le_bytes()).unwrap(); // -65.5 dBm
+        p.push(0).unwrap(); // RSSI antenna
+        p.push(0x00).unwrap(); // AoA
+        p.push(0x02).unwrap(); // 2 us slots
+        p.push(0x00).unwrap(); // CRC OK
+        p.extend_from_slice(&[0, 0]).unwrap(); // event counter
+        p.push(count).unwrap();
+        for &(i, q) in iq {
+            p.push(i as u8).unwrap();
+            p.push(q as u8).unwrap();
+        }
+        p
+    }
+
+    /// Synthesize a CTE with carrier offset `omega` rad/us and an
+    /// antenna-1 phase lead of `delta` rad
+    fn synth(omega: f32, delta: f32, slot_us: u8) -> IqReport {
+        let mut samples = Vec::new();
+        let at = |phase: f32| {
+            // Rotate (100, 0) by `phase` via small steps to avoid trig
+            let steps = 1000;
+            let (c, s) = (
+                1.0 - (phase / steps as f32).powi(2) / 2.0,
+                phase / steps as f32,
+            );
+            let mut v = (100.0f32, 0.0f32);
+            for _ in 0..steps {
+                v = (v.0 * c - v.1 * s, v.0 * s + v.1 * c);
+            }
+            IqSample {
+                i: v.0.round() as i8,
+                q: v.1.round() as i8,
+            }
+        };
+        for t in 0..REFERENCE_SAMPLES {
+            samples.push(at(omega * t as f32)).unwrap();
+        }
+        let spacing = 2.0 * slot_us as f32;
+        for k in 0..16 {
+            let t = 12.0 + spacing * k as f32;
+            let antenna_phase = if k % 2 == 0 { delta } else { 0.0 };
+            samples.push(at(omega * t + antenna_phase)).unwrap();
+        }
+        IqReport {
+            sync_handle: 0,
+            channel: 0,
+            rssi: -60,
+            cte_type: CteType::Aoa,
+            slot_us,
+            samples,
+        }
+    }
+
+    // ── Report parsing ──────────────────────────────────────────────
+
+    #[test]
+    fn parse_report_fields() {
+        let iq = [(10, -10); 9];
+        let report = parse_iq_report(&report_params(9, &iq)).unwrap();
+        assert_eq!(report.sync_handle, 1);
+        assert_eq!(report.channel, 17);
+        assert_eq!(report.rssi, -65);
+        assert_eq!(report.cte_type, CteType::Aoa);
+        assert_eq!(report.slot_us, 2);
+        assert_eq!(report.samples.len(), 9);
+        assert_eq!(report.samples[0], IqSample { i: 10, q: -10 });
+    }
+
+    #[test]
+    fn parse_rejects_truncated_and_failed_reports() {
+        let iq = [(1, 1); 9];
+        let params = report_params(9, &iq);
+        assert!(parse_iq_report(&params[..params.len() - 1]).is_none());
+
+        let mut failed = params.clone();
+        failed[8] = 0xFF;
+        assert!(parse_iq_report(&failed).is_none());
+    }
+
+    // ── Angle estimation ────────────────────────────────────────────
+
+    #[test]
+    fn broadside_signal_is_zero_degrees() {
+        let report = synth(0.4, 0.0, 2);
+        let angle = estimate_aoa(&report, 61.45).unwrap();
+        assert!(angle.abs() <= 1, "got {angle}");
+    }
+
+    #[test]
+    fn phase_lead_maps_to_angle() {
+        // Half-wavelength spacing: delta = pi * sin(theta); 30 degrees -> pi/2
+        let report = synth(0.3, PI / 2.0, 2);
+        let angle = estimate_aoa(&report, WAVELENGTH_MM / 2.0).unwrap();
+        assert!((angle - 30).abs() <= 2, "got {angle}");
+
+        let report = synth(-0.2, -PI / 2.0, 1);
+        let angle = estimate_aoa(&report, WAVELENGTH_MM / 2.0).unwrap();
+        assert!((angle + 30).abs() <= 2, "got {angle}");
+    }
+
+    #[test]
+    fn too_few_samples_or_silence_gives_none() {
+        let mut report = synth(0.1, 0.0, 2);
+        report.samples.truncate(REFERENCE_SAMPLES + 1);
+        assert!(estimate_aoa(&report, 50.0).is_none());
+
+        for s in report.samples.iter_mut() {
+            *s = IqSample { i: 0, q: 0 };
+        }
+        assert!(estimate_aoa(&report, 50.0).is_none());
+    }
+
+    #[test]
+    fn atan2_matches_quadrants() {
+        assert!((atan2(1.0, 1.0) - PI / 4.0).abs() < 1e-3);
+        assert!((atan2(1.0, -1.0) - 3.0 * PI / 4.0).abs() < 1e-3);
+        assert!((atan2(-1.0, -1.0) + 3.0 * PI / 4.0).abs() < 1e-3);
+        assert!((sqrt(2.0) - core::f32::consts::SQRT_2).abs() < 1e-5);
+    }
+}
@@ -1,225 +1,565 @@
-/// Default filter data compiled into the firmware.
+/// Default filter data compiled into the firmware, split into signature packs.
 ///
-/// MAC OUI prefixes merged from FlockOff (~88 entries), FlockSquawk (20 entries),
-/// and flock-you. SSID patterns, BLE name patterns, Raven UUIDs, and manufacturer
-/// IDs from FlockSquawk and flock-you.
-
-/// Known MAC OUI prefixes (3-byte prefix, vendor name).
+/// Each pack is a [`SignaturePack`] in its own submodule, selected by a
+/// `pack-*` cargo feature so memory-constrained builds only carry the
+/// signatures they need. [`PACKS`] lists the enabled packs in match order;
+/// the filter engine and the sniffer prefilter iterate it. Tests always see
+/// every pack.
 ///
-/// Sources: FlockOff defaultTargets.h, FlockSquawk DeviceSignatures.h, flock-you main.cpp
-pub static MAC_PREFIXES: &[([u8; 3], &str)] = &[
-    // === Flock Safety ===
-    ([0xB4, 0x1E, 0x52], "Flock Safety"),
-    // === Silicon Labs OUI (FlockSquawk / flock-you) ===
-    ([0x58, 0x8E, 0x81], "Silicon Labs"),
-    ([0xCC, 0xCC, 0xCC], "Silicon Labs"),
-    ([0xEC, 0x1B, 0xBD], "Silicon Labs"),
-    ([0x90, 0x35, 0xEA], "Silicon Labs"),
-    ([0x04, 0x0D, 0x84], "Silicon Labs"),
-    ([0xF0, 0x82, 0xC0], "Silicon Labs"),
-    ([0x1C, 0x34, 0xF1], "Silicon Labs"),
-    ([0x38, 0x5B, 0x44], "Silicon Labs"),
-    ([0x94, 0x34, 0x69], "Silicon Labs"),
-    ([0xB4, 0xE3, 0xF9], "Silicon Labs"),
-    ([0x70, 0xC9, 0x4E], "Silicon Labs"),
-    ([0x3C, 0x91, 0x80], "Silicon Labs"),
-    ([0xD8, 0xF3, 0xBC], "Silicon Labs"),
-    ([0x80, 0x30, 0x49], "Silicon Labs"),
-    ([0x14, 0x5A, 0xFC], "Silicon Labs"),
-    ([0x74, 0x4C, 0xA1], "Silicon Labs"),
-    ([0x08, 0x3A, 0x88], "Silicon Labs"),
-    ([0x9C, 0x2F, 0x9D], "Silicon Labs"),
-    ([0x94, 0x08, 0x53], "Silicon Labs"),
-    ([0xE4, 0xAA, 0xEA], "Silicon Labs"),
-    // === Avigilon Alta ===
-    ([0x70, 0x1A, 0xD5], "Avigilon Alta"),
-    // === Axis Communications AB ===
-    ([0x00, 0x40, 0x8C], "Axis Communications"),
-    ([0xAC, 0xCC, 0x8E], "Axis Communications"),
-    ([0xB8, 0xA4, 0x4F], "Axis Communications"),
-    ([0xE8, 0x27, 0x25], "Axis Communications"),
-    // === China Dragon Technology ===
-    ([0x1C, 0x79, 0x2D], "China Dragon Technology"),
-    ([0x3C, 0x3B, 0xAD], "China Dragon Technology"),
-    ([0x40, 0x9C, 0xA7], "China Dragon Technology"),
-    ([0x54, 0xAE, 0xBC], "China Dragon Technology"),
-    ([0x5C, 0x8A, 0xAE], "China Dragon Technology"),
-    ([0x6C, 0x05, 0xD3], "China Dragon Technology"),
-    ([0xA4, 0x6B, 0x40], "China Dragon Technology"),
-    ([0xA8, 0x4F, 0xA4], "China Dragon Technology"),
-    ([0xA8, 0xA0, 0x92], "China Dragon Technology"),
-    ([0xB0, 0xAC, 0x82], "China Dragon Technology"),
-    ([0xBC, 0x2B, 0x02], "China Dragon Technology"),
-    ([0xC0, 0xE3, 0x50], "China Dragon Technology"),
-    ([0xC8, 0x26, 0xE2], "China Dragon Technology"),
-    ([0xC8, 0x8A, 0xD8], "China Dragon Technology"),
-    ([0x00, 0x7E, 0x56], "China Dragon Technology"),
-    ([0x04, 0x39, 0x26], "China Dragon Technology"),
-    ([0x24, 0xB7, 0x2A], "China Dragon Technology"),
-    ([0x3C, 0x7A, 0xAA], "China Dragon Technology"),
-    ([0x40, 0xAA, 0x56], "China Dragon Technology"),
-    ([0x44, 0xEF, 0xBF], "China Dragon Technology"),
-    ([0x78, 0x8A, 0x86], "China Dragon Technology"),
-    ([0x94, 0xE0, 0xD6], "China Dragon Technology"),
-    ([0xA0, 0x67, 0x20], "China Dragon Technology"),
-    ([0xA0, 0x9D, 0xC1], "China Dragon Technology"),
-    ([0xA8, 0x43, 0xA4], "China Dragon Technology"),
-    ([0xD0, 0xA4, 0x6F], "China Dragon Technology"),
-    ([0xE0, 0x51, 0xD8], "China Dragon Technology"),
-    ([0xE0, 0x75, 0x26], "China Dragon Technology"),
-    // === FLIR ===
-    ([0x00, 0x13, 0x56], "FLIR Radiation"),
-    ([0x00, 0x40, 0x7F], "FLIR Systems"),
-    ([0x00, 0x1B, 0xD8], "FLIR Systems"),
-    // === GeoVision ===
-    ([0x00, 0x13, 0xE2], "GeoVision"),
-    // === Hanwha Vision ===
-    ([0x44, 0xB4, 0x23], "Hanwha Vision"),
-    ([0x8C, 0x1D, 0x55], "Hanwha Vision"),
-    ([0xE4, 0x30, 0x22], "Hanwha Vision"),
-    // === March Networks ===
-    ([0x00, 0x10, 0xBE], "March Networks"),
-    ([0x00, 0x12, 0x81], "March Networks"),
-    // === Meta Platforms ===
-    ([0x48, 0x05, 0x60], "Meta Platforms"),
-    ([0x50, 0x99, 0x03], "Meta Platforms"),
-    ([0x78, 0xC4, 0xFA], "Meta Platforms"),
-    ([0x80, 0xF3, 0xEF], "Meta Platforms"),
-    ([0x84, 0x57, 0xF7], "Meta Platforms"),
-    ([0x88, 0x25, 0x08], "Meta Platforms"),
-    ([0x94, 0xF9, 0x29], "Meta Platforms"),
-    ([0xB4, 0x17, 0xA8], "Meta Platforms"),
-    ([0xC0, 0xDD, 0x8A], "Meta Platforms"),
-    ([0xCC, 0xA1, 0x74], "Meta Platforms"),
-    ([0xD0, 0xB3, 0xC2], "Meta Platforms"),
-    ([0xD4, 0xD6, 0x59], "Meta Platforms"),
-    // === Mobotix ===
-    ([0x00, 0x03, 0xC5], "Mobotix"),
-    // === Shenzhen Bilian Electronic ===
-    ([0x08, 0xEA, 0x40], "Shenzhen Bilian"),
-    ([0x0C, 0x8C, 0x24], "Shenzhen Bilian"),
-    ([0x0C, 0xCF, 0x89], "Shenzhen Bilian"),
-    ([0x10, 0xA4, 0xBE], "Shenzhen Bilian"),
-    ([0x14, 0x5D, 0x34], "Shenzhen Bilian"),
-    ([0x14, 0x6B, 0x9C], "Shenzhen Bilian"),
-    ([0x20, 0x32, 0x33], "Shenzhen Bilian"),
-    ([0x2C, 0xC3, 0xE6], "Shenzhen Bilian"),
-    ([0x30, 0x7B, 0xC9], "Shenzhen Bilian"),
-    ([0x34, 0x7D, 0xE4], "Shenzhen Bilian"),
-    ([0x38, 0x01, 0x46], "Shenzhen Bilian"),
-    ([0x38, 0x7A, 0xCC], "Shenzhen Bilian"),
-    ([0x44, 0x01, 0xBB], "Shenzhen Bilian"),
-    ([0x54, 0xEF, 0x33], "Shenzhen Bilian"),
-    ([0x60, 0xFB, 0x00], "Shenzhen Bilian"),
-    ([0x6C, 0xD5, 0x52], "Shenzhen Bilian"),
-    ([0x74, 0xEE, 0x2A], "Shenzhen Bilian"),
-    ([0x78, 0x22, 0x88], "Shenzhen Bilian"),
-    ([0x7C, 0xA7, 0xB0], "Shenzhen Bilian"),
-    ([0x84, 0xFC, 0x14], "Shenzhen Bilian"),
-    ([0x88, 0x49, 0x2D], "Shenzhen Bilian"),
-    ([0x94, 0xBA, 0x06], "Shenzhen Bilian"),
-    ([0x98, 0x03, 0xCF], "Shenzhen Bilian"),
-    ([0xA0, 0x9F, 0x10], "Shenzhen Bilian"),
-    ([0xA8, 0xB5, 0x8E], "Shenzhen Bilian"),
-    ([0xB4, 0x6D, 0xC2], "Shenzhen Bilian"),
-    ([0xC4, 0x3C, 0xB0], "Shenzhen Bilian"),
-    ([0xC8, 0xFE, 0x0F], "Shenzhen Bilian"),
-    ([0xCC, 0x64, 0x1A], "Shenzhen Bilian"),
-    ([0xE0, 0xB9, 0x4D], "Shenzhen Bilian"),
-    ([0xEC, 0x3D, 0xFD], "Shenzhen Bilian"),
-    ([0xF0, 0xC8, 0x14], "Shenzhen Bilian"),
-    ([0xFC, 0x23, 0xCD], "Shenzhen Bilian"),
-    ([0x20, 0xF4, 0x1B], "Shenzhen Bilian"),
-    ([0x28, 0xF3, 0x66], "Shenzhen Bilian"),
-    ([0x3C, 0x33, 0x00], "Shenzhen Bilian"),
-    ([0x44, 0x33, 0x4C], "Shenzhen Bilian"),
-    ([0xAC, 0xA2, 0x13], "Shenzhen Bilian"),
-    // === Sunell Electronics ===
-    ([0x00, 0x1C, 0x27], "Sunell Electronics"),
-];
+/// Pack signatures live in `src/defaults/<pack>.sigs.json`, in the
+/// `signatures.v1` schema format. `build.rs` generates each pack's tables from
+/// it as a `GENERATED` pack plus `SIG_IDX_*` constants (a signature's index in
+/// its table, named after its id); the pack module adds the fields the schema
+/// can't express (Matter vendors, Class of Device, FlockOff name keywords).
+/// The file's `rules` are compiled into the pack's [`RuleDb`], referring to
+/// signatures by their position in the file (see [`crate::rules`]).
+///
+/// Every entry carries a [`SignatureMeta`] rating what it indicates, so
+/// detections can be prioritized without a companion-side lookup table.
+use serde::{Deserialize, Serialize, Serializer};
 
-/// WiFi SSID exact-prefix patterns.
-/// Match if SSID starts with the prefix and remaining chars match the given format.
-pub static SSID_PATTERNS: &[SsidPattern] = &[
-    SsidPattern {
-        prefix: "Flock-",
-        suffix_len: 6,
-        suffix_kind: SuffixKind::HexChars,
-        description: "Flock Safety camera WiFi",
-    },
-    SsidPattern {
-        prefix: "Penguin-",
-        suffix_len: 10,
-        suffix_kind: SuffixKind::DecimalDigits,
-        description: "Penguin device WiFi",
-    },
+use crate::rules::RuleDb;
+use crate::scanner::{NetworkKind, WpsField};
+use crate::sigfile::{AdPatternRef, VendorIePatternRef};
+
+#[cfg(any(test, feature = "pack-attacker-tools"))]
+pub mod attacker_tools;
+#[cfg(any(test, feature = "pack-cameras-generic"))]
+pub mod cameras_generic;
+#[cfg(any(test, feature = "pack-core-flock"))]
+pub mod core_flock;
+#[cfg(any(test, feature = "pack-drones"))]
+pub mod drones;
+#[cfg(any(test, feature = "pack-trackers"))]
+pub mod trackers;
+
+/// Enabled signature packs, in match order
+pub static PACKS: &[&SignaturePack] = &[
+    #[cfg(any(test, feature = "pack-core-flock"))]
+    &core_flock::PACK,
+    #[cfg(any(test, feature = "pack-cameras-generic"))]
+    &cameras_generic::PACK,
+    #[cfg(any(test, feature = "pack-trackers"))]
+    &trackers::PACK,
+    #[cfg(any(test, feature = "pack-drones"))]
+    &drones::PACK,
+    #[cfg(any(test, feature = "pack-attacker-tools"))]
+    &attacker_tools::PACK,
 ];
 
-/// WiFi SSID exact-match names.
-pub static SSID_EXACT: &[&str] = &["FS Ext Battery"];
+/// A named set of compiled-in signatures. Fields a pack doesn't use are
+/// left empty with `..SignaturePack::EMPTY`. Each entry ends in its
+/// [`SignatureMeta`].
+#[derive(Debug)]
+pub struct SignaturePack {
+    /// Pack name, matching its `pack-<name>` cargo feature
+    pub name: &'static str,
+    /// FNV-1a hash of the pack's signature file and module source, from
+    /// `build.rs`
+    pub hash: u32,
+    /// MAC OUI prefixes (3-byte prefix, vendor name)
+    pub mac_prefixes: &'static [([u8; 3], &'static str, SignatureMeta)],
+    /// Vendors in `mac_prefixes` too generic to report on first sight, with
+    /// the number of sightings required
+    pub mac_prefix_min_sightings: &'static [(&'static str, u8)],
+    /// WiFi SSID glob patterns
+    pub ssid_patterns: &'static [SsidPattern],
+    /// WiFi SSID exact-match names
+    pub ssid_exact: &'static [(&'static str, SignatureMeta)],
+    /// WiFi SSID substring keywords (lowercase, matched case-insensitively)
+    pub ssid_keywords: &'static [(&'static str, SignatureMeta)],
+    /// Partial WiFi names, reported as `wifi_name` unless also an SSID keyword
+    pub wifi_name_keywords: &'static [(&'static str, SignatureMeta)],
+    /// BLE and Classic device name patterns (case-insensitive substring)
+    pub ble_name_patterns: &'static [(&'static str, SignatureMeta)],
+    /// Custom 16-bit BLE service UUIDs with their description
+    pub ble_service_uuids_16: &'static [(u16, &'static str, SignatureMeta)],
+    /// Standard 16-bit BLE service UUIDs associated with a device family,
+    /// reported as `ble_uuid_std`
+    pub ble_standard_uuids_16: &'static [(u16, &'static str, SignatureMeta)],
+    /// 32-bit BLE service UUIDs with their description, reported as
+    /// `ble_uuid`
+    pub ble_service_uuids_32: &'static [(u32, &'static str, SignatureMeta)],
+    /// Custom 128-bit BLE service UUIDs (canonical big-endian value) with
+    /// their description, reported as `ble_uuid`
+    pub ble_service_uuids_128: &'static [(u128, &'static str, SignatureMeta)],
+    /// BLE manufacturer company IDs with their description
+    pub ble_manufacturer_ids: &'static [(u16, &'static str, SignatureMeta)],
+    /// Raw BLE advertisement byte patterns, reported as `ble_ad`
+    pub ble_ad_patterns: &'static [AdBytesPattern],
+    /// WiFi vendor-specific information element patterns, reported as
+    /// `wifi_vendor_ie`
+    pub wifi_vendor_ies: &'static [VendorIePattern],
+    /// WPS keywords (lowercase, matched case-insensitively): (field, or any
+    /// field if `None`, keyword, description, meta). Reported as `wps`.
+    pub wps_keywords: &'static [(Option<WpsField>, &'static str, &'static str, SignatureMeta)],
+    /// Mesh and WiFi Direct group networks: (kind, name keyword — lowercase,
+    /// matched case-insensitively, empty for any name — description, meta).
+    /// Reported as `wifi_network`.
+    pub wifi_networks: &'static [(NetworkKind, &'static str, &'static str, SignatureMeta)],
+    /// Matter vendor IDs, matched against BLE commissioning advertisements
+    pub matter_vendor_ids: &'static [(u16, &'static str, SignatureMeta)],
+    /// iBeacon proximity UUIDs (canonical big-endian value) with their
+    /// description, reported as `ibeacon`
+    pub ibeacon_uuids: &'static [(u128, &'static str, SignatureMeta)],
+    /// Eddystone-UID namespaces with their description, reported as
+    /// `eddystone`
+    pub eddystone_namespaces: &'static [([u8; 10], &'static str, SignatureMeta)],
+    /// Bluetooth Classic Class of Device signatures: (mask, value,
+    /// description, meta). Matches when `cod & mask == value`.
+    pub bt_classic_cod: &'static [(u32, u32, &'static str, SignatureMeta)],
+    /// Exclusions by SSID keyword (lowercase, matched case-insensitively):
+    /// (keyword, detail of the matches they drop). `None` drops every match.
+    pub ssid_exclusions: &'static [(&'static str, Option<&'static str>)],
+    /// Exclusions by BLE or Classic device name keyword, as `ssid_exclusions`
+    pub name_exclusions: &'static [(&'static str, Option<&'static str>)],
+    /// Table position of each signature of the pack file, in file order —
+    /// the [`crate::rules::SigIdx`] rules refer to it by
+    pub signatures: &'static [(SigTable, u16)],
+    /// Detection rules over the pack's signatures
+    pub rules: RuleDb<'static>,
+}
 
-/// WiFi SSID substring keywords (case-insensitive).
-pub static SSID_KEYWORDS: &[&str] = &["flock", "penguin", "pigvision"];
+impl SignaturePack {
+    pub const EMPTY: SignaturePack = SignaturePack {
+        name: "",
+        hash: 0,
+        mac_prefixes: &[],
+        mac_prefix_min_sightings: &[],
+        ssid_patterns: &[],
+        ssid_exact: &[],
+        ssid_keywords: &[],
+        wifi_name_keywords: &[],
+        ble_name_patterns: &[],
+        ble_service_uuids_16: &[],
+        ble_standard_uuids_16: &[],
+        ble_service_uuids_32: &[],
+        ble_service_uuids_128: &[],
+        ble_manufacturer_ids: &[],
+        ble_ad_patterns: &[],
+        wifi_vendor_ies: &[],
+        wps_keywords: &[],
+        wifi_networks: &[],
+        matter_vendor_ids: &[],
+        ibeacon_uuids: &[],
+        eddystone_namespaces: &[],
+        bt_classic_cod: &[],
+        ssid_exclusions: &[],
+        name_exclusions: &[],
+        signatures: &[],
+        rules: RuleDb::EMPTY,
+    };
 
-/// WiFi SSID name keyword from FlockOff (matches partial name in beacon/probe).
-pub static WIFI_NAME_KEYWORDS: &[&str] = &["flock"];
+    /// The pack, once its rules pass [`RuleDb::validate`]. Packs initialize
+    /// their `PACK` through it, so a malformed rule fails the build.
+    pub const fn checked(self) -> Self {
+        assert!(
+            self.rules.validate(self.signatures.len()),
+            "pack rules reference missing nodes or signatures, or are malformed"
+        );
+        self
+    }
 
-/// BLE device name patterns (case-insensitive substring match).
-pub static BLE_NAME_PATTERNS: &[&str] = &["Flock", "Penguin", "FS Ext Battery", "Pigvision"];
+    /// The match detail reported for entry `index` of `table`: the vendor,
+    /// keyword or description. Exclusions give their keyword.
+    pub fn describe(&self, table: SigTable, index: usize) -> Option<&'static str> {
+        match table {
+            SigTable::MacPrefixes => self.mac_prefixes.get(index).map(|e| e.1),
+            SigTable::SsidPatterns => self.ssid_patterns.get(index).map(|e| e.description),
+            SigTable::SsidExact => self.ssid_exact.get(index).map(|e| e.0),
+            SigTable::SsidKeywords => self.ssid_keywords.get(index).map(|e| e.0),
+            SigTable::BleNamePatterns => self.ble_name_patterns.get(index).map(|e| e.0),
+            SigTable::BleServiceUuids16 => self.ble_service_uuids_16.get(index).map(|e| e.1),
+            SigTable::BleStandardUuids16 => self.ble_standard_uuids_16.get(index).map(|e| e.1),
+            SigTable::BleServiceUuids32 => self.ble_service_uuids_32.get(index).map(|e| e.1),
+            SigTable::BleServiceUuids128 => self.ble_service_uuids_128.get(index).map(|e| e.1),
+            SigTable::BleManufacturerIds => self.ble_manufacturer_ids.get(index).map(|e| e.1),
+            SigTable::BleAdPatterns => self.ble_ad_patterns.get(index).map(|e| e.description),
+            SigTable::WifiVendorIes => self.wifi_vendor_ies.get(index).map(|e| e.description),
+            SigTable::WpsKeywords => self.wps_keywords.get(index).map(|e| e.2),
+            SigTable::WifiNetworks => self.wifi_networks.get(index).map(|e| e.2),
+            SigTable::SsidExclusions => self.ssid_exclusions.get(index).map(|e| e.0),
+            SigTable::NameExclusions => self.name_exclusions.get(index).map(|e| e.0),
+        }
+    }
+}
 
-/// Raven custom BLE service UUIDs (16-bit short IDs).
-/// Full UUID: 0000XXXX-0000-1000-8000-00805f9b34fb
-pub static BLE_SERVICE_UUIDS_16: &[u16] = &[
-    0x3100, // Raven GPS service
-    0x3200, // Raven Power service
-    0x3300, // Raven Network service
-    0x3400, // Raven Upload service
-    0x3500, // Raven Error service
-];
+/// The [`SignaturePack`] tables `build.rs` fills from a signature file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SigTable {
+    MacPrefixes,
+    SsidPatterns,
+    SsidExact,
+    SsidKeywords,
+    BleNamePatterns,
+    BleServiceUuids16,
+    BleStandardUuids16,
+    BleServiceUuids32,
+    BleServiceUuids128,
+    BleManufacturerIds,
+    BleAdPatterns,
+    WifiVendorIes,
+    WpsKeywords,
+    WifiNetworks,
+    SsidExclusions,
+    NameExclusions,
+}
 
-/// Standard BLE service UUIDs also associated with Raven devices.
-pub static BLE_STANDARD_UUIDS_16: &[u16] = &[
-    0x180A, // Device Information
-    0x1809, // Health Thermometer
-    0x1819, // Location and Navigation
-];
+/// What kind of device a signature indicates and how much weight a match
+/// carries
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SignatureMeta {
+    pub category: Category,
+    /// What the device is, for grouping detections; defaults from the
+    /// category
+    pub device_class: DeviceClass,
+    pub severity: Severity,
+    /// How reliably the signature identifies the category — a vendor OUI is
+    /// stronger evidence than a generic chipset or a name keyword
+    pub confidence: Confidence,
+}
 
-/// BLE manufacturer company IDs.
-pub static BLE_MANUFACTURER_IDS: &[u16] = &[
-    0x09C8, // XUNTONG (associated with Flock Safety)
-];
+impl SignatureMeta {
+    /// Runtime signatures that don't rate themselves
+    pub const UNRATED: SignatureMeta =
+        SignatureMeta::new(Category::Other, Severity::Medium, Confidence::Medium);
+
+    pub const fn new(category: Category, severity: Severity, confidence: Confidence) -> Self {
+        Self {
+            category,
+            device_class: category.device_class(),
+            severity,
+            confidence,
+        }
+    }
+
+    /// Override the device class derived from the category
+    pub const fn with_device_class(self, device_class: DeviceClass) -> Self {
+        Self {
+            device_class,
+            ..self
+        }
+    }
 
-/// SSID suffix format kind
-#[derive(Debug, Clone, Copy)]
-pub enum SuffixKind {
-    /// Only hexadecimal characters (0-9, a-f, A-F)
-    HexChars,
-    /// Only decimal digits (0-9)
-    DecimalDigits,
+    /// Whether a match with this meta should headline a detection over one
+    /// with `other`: higher severity first, then higher confidence.
+    pub fn outranks(&self, other: &SignatureMeta) -> bool {
+        (self.severity, self.confidence) > (other.severity, other.confidence)
+    }
+}
+
+impl Default for SignatureMeta {
+    fn default() -> Self {
+        Self::UNRATED
+    }
+}
+
+/// Device category a signature indicates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Category {
+    /// Automated license plate readers (Flock Safety and similar)
+    Alpr,
+    /// Acoustic gunshot detectors (Raven)
+    GunshotDetector,
+    /// Other networked cameras
+    Camera,
+    /// Consumer item trackers
+    Tracker,
+    Drone,
+    /// WiFi/BLE attack and pentest tools
+    AttackTool,
+    /// Anything else, including categories newer than this firmware
+    #[serde(other)]
+    Other,
+}
+
+impl Category {
+    /// The device class a signature of this category indicates unless it
+    /// names a more specific one
+    pub const fn device_class(self) -> DeviceClass {
+        match self {
+            Category::Alpr => DeviceClass::LprCamera,
+            Category::GunshotDetector => DeviceClass::AcousticSensor,
+            Category::Tracker => DeviceClass::PersonalTracker,
+            Category::Drone => DeviceClass::Drone,
+            Category::AttackTool => DeviceClass::PentestTool,
+            Category::Camera | Category::Other => DeviceClass::Unknown,
+        }
+    }
+}
+
+/// Device taxonomy shared by every companion, so detections are grouped and
+/// colored the same way on each platform. Finer than [`Category`]: a
+/// `camera` signature may name a body-worn camera.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceClass {
+    /// License plate reader cameras
+    LprCamera,
+    /// Gunshot and other acoustic sensors
+    AcousticSensor,
+    /// Body-worn cameras, including camera glasses
+    BodyCamera,
+    /// Item and personal trackers
+    PersonalTracker,
+    /// WiFi/BLE pentest and attack tools
+    PentestTool,
+    Drone,
+    /// Not identified, including classes newer than this firmware
+    #[serde(other)]
+    Unknown,
+}
+
+/// How urgently a detection should be surfaced. Serialized as its number
+/// (1–3) to match the `severity` that `subscribe` filters on; signature files
+/// spell it out (`"low"`, `"medium"`, `"high"`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[repr(u8)]
+pub enum Severity {
+    Low = 1,
+    Medium = 2,
+    High = 3,
+}
+
+impl Serialize for Severity {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(*self as u8)
+    }
+}
+
+/// Confidence that a match really is the signature's category
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Confidence {
+    Low,
+    Medium,
+    High,
+}
+
+impl Confidence {
+    /// One step more confident, saturating at [`Confidence::High`]
+    pub const fn raised(self) -> Self {
+        match self {
+            Confidence::Low => Confidence::Medium,
+            Confidence::Medium | Confidence::High => Confidence::High,
+        }
+    }
+}
+
+/// Version of the compiled-in signatures: a hash over the enabled packs,
+/// so a companion can tell whether the device already carries the signature
+/// set it would push. Changes with any pack edit or pack feature selection.
+pub fn signature_version() -> u32 {
+    PACKS.iter().fold(0x811C_9DC5, |hash: u32, pack| {
+        pack.hash
+            .to_le_bytes()
+            .iter()
+            .fold(hash, |hash, &b| (hash ^ b as u32).wrapping_mul(0x0100_0193))
+    })
+}
+
+/// All MAC prefixes of the enabled packs
+pub fn mac_prefixes() -> impl Iterator<Item = &'static ([u8; 3], &'static str, SignatureMeta)> {
+    PACKS.iter().flat_map(|pack| pack.mac_prefixes)
 }
 
 /// A structured SSID matching pattern
 #[derive(Debug, Clone)]
 pub struct SsidPattern {
-    pub prefix: &'static str,
-    pub suffix_len: usize,
-    pub suffix_kind: SuffixKind,
+    /// Case-sensitive glob over the whole SSID, see [`crate::glob`]
+    pub glob: &'static str,
     pub description: &'static str,
+    pub meta: SignatureMeta,
 }
 
 impl SsidPattern {
     /// Check if an SSID matches this pattern
     pub fn matches(&self, ssid: &str) -> bool {
-        if let Some(suffix) = ssid.strip_prefix(self.prefix) {
-            if suffix.len() != self.suffix_len {
-                return false;
+        crate::glob::matches(self.glob, ssid, true)
+    }
+}
+
+/// A raw advertisement byte pattern, e.g. a tracker's manufacturer or
+/// service data layout. `None` bytes are wildcards.
+#[derive(Debug, Clone)]
+pub struct AdBytesPattern {
+    pub bytes: &'static [Option<u8>],
+    /// Fixed offset into the advertisement; `None` searches the whole payload
+    pub offset: Option<u16>,
+    pub description: &'static str,
+    pub meta: SignatureMeta,
+}
+
+impl AdBytesPattern {
+    pub fn as_ref(&self) -> AdPatternRef<'static> {
+        AdPatternRef {
+            bytes: self.bytes,
+            offset: self.offset,
+            description: self.description,
+            min_sightings: 1,
+            meta: self.meta,
+        }
+    }
+}
+
+/// A vendor-specific information element pattern: the vendor's OUI and the
+/// leading bytes after it. `None` bytes are wildcards.
+#[derive(Debug, Clone)]
+pub struct VendorIePattern {
+    pub oui: [u8; 3],
+    pub bytes: &'static [Option<u8>],
+    pub description: &'static str,
+    pub meta: SignatureMeta,
+}
+
+impl VendorIePattern {
+    pub fn as_ref(&self) -> VendorIePatternRef<'static> {
+        VendorIePatternRef {
+            oui: self.oui,
+            bytes: self.bytes,
+            description: self.description,
+            min_sightings: 1,
+            meta: self.meta,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_see_every_pack() {
+        let names: heapless::Vec<&str, 8> = PACKS.iter().map(|pack| pack.name).collect();
+        assert_eq!(
+            names.as_slice(),
+            [
+                "core-flock",
+                "cameras-generic",
+                "trackers",
+                "drones",
+                "attacker-tools"
+            ]
+        );
+    }
+
+    #[test]
+    fn signature_version_covers_every_pack() {
+        let hashes: heapless::Vec<u32, 8> = PACKS.iter().map(|pack| pack.hash).collect();
+        assert!(hashes.iter().all(|&hash| hash != 0));
+        assert!(hashes
+            .iter()
+            .enumerate()
+            .all(|(i, hash)| !hashes[..i].contains(hash)));
+        assert_ne!(signature_version(), 0x811C_9DC5);
+        assert_eq!(signature_version(), signature_version());
+    }
+
+    #[test]
+    fn weak_vendors_have_prefixes_in_their_pack() {
+        for pack in PACKS {
+            for (vendor, _) in pack.mac_prefix_min_sightings {
+                assert!(pack.mac_prefixes.iter().any(|(_, v, _)| v == vendor));
+            }
+        }
+    }
+
+    #[test]
+    fn generated_indices_point_at_their_signature() {
+        let pack = &core_flock::PACK;
+        assert_eq!(
+            pack.mac_prefixes[core_flock::SIG_IDX_FLOCK_SAFETY_B41E52],
+            (
+                [0xB4, 0x1E, 0x52],
+                "Flock Safety",
+                SignatureMeta::new(Category::Alpr, Severity::High, Confidence::High)
+            )
+        );
+        assert_eq!(
+            pack.ssid_patterns[core_flock::SIG_IDX_FLOCK_SSID].glob,
+            "Flock-[0-9A-Fa-f][0-9A-Fa-f][0-9A-Fa-f][0-9A-Fa-f][0-9A-Fa-f][0-9A-Fa-f]"
+        );
+        assert_eq!(
+            pack.ble_standard_uuids_16[core_flock::SIG_IDX_RAVEN_DEVICE_INFORMATION].0,
+            0x180A
+        );
+        assert_eq!(pack.mac_prefix_min_sightings, [("Silicon Labs", 3)]);
+        assert_eq!(
+            trackers::PACK.ble_service_uuids_16[trackers::SIG_IDX_CHIPOLO_FE33],
+            (
+                0xFE33,
+                "Chipolo tracker",
+                SignatureMeta::new(Category::Tracker, Severity::Medium, Confidence::High)
+            )
+        );
+        // A signature's own device class overrides its category's
+        let cameras = &cameras_generic::PACK;
+        assert_eq!(
+            cameras.mac_prefixes[cameras_generic::SIG_IDX_META_PLATFORMS_480560]
+                .2
+                .device_class,
+            DeviceClass::BodyCamera
+        );
+        assert_eq!(
+            cameras.mac_prefixes[cameras_generic::SIG_IDX_AXIS_COMMUNICATIONS_00408C]
+                .2
+                .device_class,
+            DeviceClass::Unknown
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn pack_files_load_as_runtime_signatures() {
+        let files: [&[u8]; 5] = [
+            include_bytes!("defaults/core_flock.sigs.json"),
+            include_bytes!("defaults/cameras_generic.sigs.json"),
+            include_bytes!("defaults/trackers.sigs.json"),
+            include_bytes!("defaults/drones.sigs.json"),
+            include_bytes!("defaults/attacker_tools.sigs.json"),
+        ];
+        for (json, pack) in files.iter().zip(PACKS) {
+            let mut db = crate::sigdb::SignatureDb::new();
+            let report = db.load_signatures(json).unwrap();
+            // Suffix regexes are only evaluated by the compiled-in packs
+            let regexes = core::str::from_utf8(json)
+                .unwrap()
+                .matches(r#""match": "regex""#)
+                .count();
+            assert_eq!(report.skipped as usize, regexes, "{}", pack.name);
+            assert_eq!(
+                db.mac_prefixes.len(),
+                pack.mac_prefixes.len(),
+                "{}",
+                pack.name
+            );
+            // Runtime loading reads the same ratings the build compiled in
+            for (loaded, compiled) in db.mac_prefixes.iter().zip(pack.mac_prefixes) {
+                assert_eq!(loaded.3, compiled.2, "{}", compiled.1);
             }
-            match self.suffix_kind {
-                SuffixKind::HexChars => suffix.chars().all(|c| c.is_ascii_hexdigit()),
-                SuffixKind::DecimalDigits => suffix.chars().all(|c| c.is_ascii_digit()),
+        }
+    }
+
+    #[test]
+    fn severity_serializes_as_its_number() {
+        let mut buf = [0u8; 64];
+        let meta = SignatureMeta::new(Category::GunshotDetector, Severity::High, Confidence::Low);
+        let len =
+            serde_json_core::to_slice(&(meta.severity, meta.category, meta.confidence), &mut buf)
+                .unwrap();
+        assert_eq!(&buf[..len], br#"[3,"gunshot_detector","low"]"#);
+        assert!(meta.outranks(&SignatureMeta::UNRATED));
+        assert!(!SignatureMeta::UNRATED.outranks(&SignatureMeta::UNRATED));
+    }
+
+    #[test]
+    fn ssid_keywords_are_lowercase() {
+        for pack in PACKS {
+            for (keyword, _) in pack.ssid_keywords.iter().chain(pack.wifi_name_keywords) {
+                assert!(!keyword.bytes().any(|b| b.is_ascii_uppercase()));
             }
-        } else {
-            false
         }
     }
 }
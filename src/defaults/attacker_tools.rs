@@ -0,0 +1,8 @@
+/// Offensive wireless tools: rogue access points, deauthers, and
+/// multi-protocol hacking gadgets. Worth knowing about when one shows up
+/// wherever you go.
+use super::SignaturePack;
+
+include!(concat!(env!("OUT_DIR"), "/defaults/attacker_tools.rs"));
+
+pub static PACK: SignaturePack = GENERATED.checked();
@@ -0,0 +1,29 @@
+/// Networked surveillance cameras from other vendors: IP camera makers, the
+/// OEM WiFi modules common in cheap cameras, camera glasses, body cameras by
+/// their WiFi Direct group name, and smart-home doorbells in Matter pairing
+/// mode.
+use super::{Category, Confidence, Severity, SignatureMeta, SignaturePack};
+
+/// Any device of the vendor in pairing mode, most of which aren't cameras
+const MATTER: SignatureMeta = SignatureMeta::new(Category::Camera, Severity::Low, Confidence::Low);
+const COD: SignatureMeta =
+    SignatureMeta::new(Category::Camera, Severity::Medium, Confidence::Medium);
+
+include!(concat!(env!("OUT_DIR"), "/defaults/cameras_generic.rs"));
+
+pub static PACK: SignaturePack = SignaturePack {
+    // Matter does not advertise the device type over BLE, so these flag any
+    // device of the vendor in pairing mode
+    matter_vendor_ids: &[
+        (0x6006, "Google Nest (Matter)", MATTER),
+        (0x1217, "Amazon (Matter)", MATTER),
+    ],
+    // `0x1FFC` selects the major and minor device class; service class bits
+    // are ignored
+    bt_classic_cod: &[
+        (0x1FFC, 0x0430, "Classic BT video camera", COD),
+        (0x1FFC, 0x0434, "Classic BT camcorder", COD),
+    ],
+    ..GENERATED
+}
+.checked();
@@ -0,0 +1,17 @@
+/// Flock Safety ALPR cameras, Raven gunshot detectors, and their accessories.
+///
+/// MAC prefixes from FlockOff defaultTargets.h, FlockSquawk DeviceSignatures.h,
+/// and flock-you main.cpp; SSID and BLE signatures from FlockSquawk and flock-you.
+use super::{Category, Confidence, Severity, SignatureMeta, SignaturePack};
+
+include!(concat!(env!("OUT_DIR"), "/defaults/core_flock.rs"));
+
+pub static PACK: SignaturePack = SignaturePack {
+    // From FlockOff (matches partial name in beacon/probe)
+    wifi_name_keywords: &[(
+        "flock",
+        SignatureMeta::new(Category::Alpr, Severity::High, Confidence::Low),
+    )],
+    ..GENERATED
+}
+.checked();
@@ -0,0 +1,11 @@
+/// Consumer drones and their controllers.
+///
+/// Drones set up their own access point for the controller or phone app.
+/// ASTM F3411 Remote ID broadcasts are matched by their BLE service data and
+/// WiFi beacon element, and DJI aircraft also beacon a proprietary DroneID
+/// element; the scanner decodes Remote ID itself (see [`crate::remote_id`]).
+use super::SignaturePack;
+
+include!(concat!(env!("OUT_DIR"), "/defaults/drones.rs"));
+
+pub static PACK: SignaturePack = GENERATED.checked();
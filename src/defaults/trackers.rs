@@ -0,0 +1,57 @@
+/// Consumer item trackers that can be slipped into a bag or car.
+///
+/// Tile and Chipolo list a vendor service UUID in their advertisements.
+/// AirTags and other Find My accessories advertise under Apple's company ID
+/// like every iPhone, so they are matched on the offline-finding payload
+/// (type 0x12, length 0x19) sent while away from their owner; SmartTags on
+/// their SmartThings Find service data (0xFD5A). Commercial asset-tracking
+/// and retail-analytics beacons are matched on their vendor's default
+/// iBeacon UUID or Eddystone namespace (the UUID's first four and last six
+/// bytes, by Google's recommended elision).
+use super::{Category, Confidence, DeviceClass, Severity, SignatureMeta, SignaturePack};
+
+/// A vendor's default UUID or namespace names the hardware, not whoever
+/// deployed it or why
+const BEACON: SignatureMeta = SignatureMeta::new(Category::Tracker, Severity::Low, Confidence::Low)
+    .with_device_class(DeviceClass::Unknown);
+
+include!(concat!(env!("OUT_DIR"), "/defaults/trackers.rs"));
+
+pub static PACK: SignaturePack = SignaturePack {
+    ibeacon_uuids: &[
+        (
+            0xB9407F30_F5F8_466E_AFF9_25556B57FE6D,
+            "Estimote beacon",
+            BEACON,
+        ),
+        (
+            0xF7826DA6_4FA2_4E98_8024_BC5B71E0893E,
+            "Kontakt.io beacon",
+            BEACON,
+        ),
+        (
+            0x2F234454_CF6D_4A0F_ADF2_F4911BA9FFA6,
+            "Radius Networks beacon",
+            BEACON,
+        ),
+    ],
+    eddystone_namespaces: &[
+        (
+            [0xED, 0xD1, 0xEB, 0xEA, 0xC0, 0x4E, 0x5D, 0xEF, 0xA0, 0x17],
+            "Estimote beacon",
+            BEACON,
+        ),
+        (
+            [0xF7, 0x82, 0x6D, 0xA6, 0xBC, 0x5B, 0x71, 0xE0, 0x89, 0x3E],
+            "Kontakt.io beacon",
+            BEACON,
+        ),
+        (
+            [0x2F, 0x23, 0x44, 0x54, 0xF4, 0x91, 0x1B, 0xA9, 0xFF, 0xA6],
+            "Radius Networks beacon",
+            BEACON,
+        ),
+    ],
+    ..GENERATED
+}
+.checked();
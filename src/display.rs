@@ -5,6 +5,10 @@
 /// to lay out text rows flicker-free: each row is padded to full display
 /// width and drawn with an explicit `background_color`, so every pixel
 /// is overwritten in a single pass with no intermediate blank frame.
+///
+/// Button A cycles from the status screen to a QR code of the last
+/// detection and one of the device's pairing info, so a phone camera can
+/// capture them without a BLE connection.
 use core::sync::atomic::Ordering;
 
 use embedded_graphics::mono_font::ascii::FONT_6X10;
@@ -15,7 +19,7 @@ use embedded_graphics::primitives::{PrimitiveStyle, Rectangle};
 use embedded_graphics::text::Text;
 use embedded_hal_bus::spi::ExclusiveDevice;
 use esp_hal::delay::Delay;
-use esp_hal::gpio::{Level, Output, OutputConfig};
+use esp_hal::gpio::{Input, InputConfig, Level, Output, OutputConfig};
 use esp_hal::spi::master::{Config as SpiConfig, Spi};
 use esp_hal::spi::Mode;
 use esp_hal::time::Rate;
@@ -29,7 +33,10 @@ use static_cell::StaticCell;
 use embassy_time::{Duration, Instant, Timer};
 
 use crate::board;
-use crate::protocol::VERSION;
+use crate::comm::{ble_uuids, BLE_ADV_NAME};
+use crate::filter::format_mac;
+use crate::protocol::{MacString, VERSION};
+use crate::qr::QrCode;
 
 // ── Display geometry ──────────────────────────────────────────────────
 
@@ -51,6 +58,18 @@ const FG: Rgb565 = Rgb565::WHITE;
 const ACCENT: Rgb565 = Rgb565::new(0, 50, 0);
 const DIM: Rgb565 = Rgb565::new(12, 24, 12);
 
+/// Light modules (and the quiet zone) of QR codes
+const QR_LIGHT: Rgb565 = Rgb565::WHITE;
+
+/// Quiet zone around a QR code, in modules
+const QR_QUIET_ZONE: i32 = 4;
+
+/// How often button A is sampled
+const BUTTON_POLL_MS: u64 = 50;
+
+/// Status screen refresh interval, in button polls
+const STATUS_REFRESH_POLLS: u32 = 10;
+
 // ── Flicker-free screen renderer ──────────────────────────────────────
 //
 // Text is drawn with MonoTextStyle's background_color set, so each
@@ -136,6 +155,54 @@ impl<'a, D: DrawTarget<Color = Rgb565>> Screen<'a, D> {
         self.y += ROW_H;
     }
 
+    /// Draw unpadded text starting at `x`, cut at the right edge. Advances cursor.
+    fn text_at(&mut self, x: i32, color: Rgb565, args: core::fmt::Arguments<'_>) {
+        self.buf.clear();
+        let _ = core::fmt::write(&mut self.buf, args);
+        self.buf.truncate(((W - x) / 6) as usize);
+        self.emit(color, BG, x);
+        self.y += ROW_H;
+    }
+
+    /// Draw a QR code in a full-height square on the left edge, with a
+    /// light quiet zone. Returns the square's width.
+    fn qr(&mut self, code: &QrCode) -> i32 {
+        let size = code.size() as i32;
+        let scale = H / (size + 2 * QR_QUIET_ZONE);
+        let side = scale * (size + 2 * QR_QUIET_ZONE);
+        let top = (H - side) / 2;
+        let _ = Rectangle::new(Point::new(0, top), Size::new(side as u32, side as u32))
+            .into_styled(PrimitiveStyle::with_fill(QR_LIGHT))
+            .draw(self.display);
+
+        // One rectangle per horizontal run of dark modules
+        let origin = QR_QUIET_ZONE * scale;
+        for y in 0..code.size() {
+            let mut x = 0;
+            while x < code.size() {
+                if !code.is_dark(x, y) {
+                    x += 1;
+                    continue;
+                }
+                let start = x;
+                while code.is_dark(x, y) {
+                    x += 1;
+                }
+                let run = Rectangle::new(
+                    Point::new(
+                        origin + start as i32 * scale,
+                        top + origin + y as i32 * scale,
+                    ),
+                    Size::new(((x - start) as i32 * scale) as u32, scale as u32),
+                );
+                let _ = run
+                    .into_styled(PrimitiveStyle::with_fill(Rgb565::BLACK))
+                    .draw(self.display);
+            }
+        }
+        side
+    }
+
     /// Draw a 1px horizontal divider. Advances cursor.
     fn divider(&mut self) {
         let _ = Rectangle::new(Point::new(0, self.y), Size::new(W as u32, 1))
@@ -177,6 +244,13 @@ macro_rules! row {
     };
 }
 
+/// Convenience: `text_at!(screen, X, COLOR, "fmt {}", args);`
+macro_rules! text_at {
+    ($s:expr, $x:expr, $color:expr, $($arg:tt)*) => {
+        $s.text_at($x, $color, format_args!($($arg)*))
+    };
+}
+
 /// Convenience: `centered!(screen, COLOR, "fmt {}", args);`
 macro_rules! centered {
     ($s:expr, $color:expr, $($arg:tt)*) => {
@@ -214,9 +288,18 @@ fn draw_status(display: &mut impl DrawTarget<Color = Rgb565>) {
         crate::BLE_MATCH_COUNT.load(Ordering::Relaxed)
     );
 
-    let last = critical_section::with(|cs| crate::LAST_MATCH.borrow(cs).borrow().clone());
-    if !last.is_empty() {
-        row!(s, Rgb565::GREEN, " Last: {}", last);
+    let (last, detection) = critical_section::with(|cs| {
+        let last = crate::LAST_MATCH.borrow(cs).borrow().clone();
+        (last, crate::LAST_DETECTION.borrow(cs).get())
+    });
+    if let Some(detection) = detection.filter(|_| !last.is_empty()) {
+        row!(
+            s,
+            Rgb565::GREEN,
+            " Last: {} - {}",
+            last,
+            detection.zone.as_str()
+        );
     } else {
         row!(s, DIM, " Last: ---");
     }
@@ -250,6 +333,124 @@ fn draw_status(display: &mut impl DrawTarget<Color = Rgb565>) {
     );
 }
 
+/// Screens cycled by button A
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Page {
+    Status,
+    Detection,
+    Pairing,
+}
+
+impl Page {
+    fn next(self) -> Self {
+        match self {
+            Self::Status => Self::Detection,
+            Self::Detection => Self::Pairing,
+            Self::Pairing => Self::Status,
+        }
+    }
+}
+
+/// Prepare the status screen: clear and paint the header background once.
+/// The header text draw covers the middle 10px each frame via
+/// background_color, but the 4px row-edge gap needs a one-time fill.
+fn enter_status(display: &mut impl DrawTarget<Color = Rgb565>) {
+    let mut s = Screen::new(display);
+    s.clear();
+    s.fill_band(ROW_H, HEADER_BG);
+}
+
+/// QR code of the last detection, as one line of plain text any phone
+/// scanner shows as-is.
+fn draw_detection(display: &mut impl DrawTarget<Color = Rgb565>, detection: &crate::LastDetection) {
+    let last = critical_section::with(|cs| crate::LAST_MATCH.borrow(cs).borrow().clone());
+    let mut mac = MacString::new();
+    format_mac(&detection.mac, &mut mac);
+
+    let mut payload = heapless::String::<160>::new();
+    let _ = core::fmt::write(
+        &mut payload,
+        format_args!(
+            "AIRHOUND {} {} {}dBm {}",
+            detection.radio, mac, detection.rssi, last
+        ),
+    );
+    // Wall-clock time when synced, otherwise uptime at the sighting
+    let elapsed_s = crate::uptime_ms().wrapping_sub(detection.uptime_ms) / 1000;
+    let _ = match crate::epoch_now() {
+        Some(now) => core::fmt::write(
+            &mut payload,
+            format_args!(" t={}", now.wrapping_sub(elapsed_s)),
+        ),
+        None => core::fmt::write(
+            &mut payload,
+            format_args!(" up={}s", detection.uptime_ms / 1000),
+        ),
+    };
+
+    let mut s = Screen::new(display);
+    s.clear();
+    let Some(code) = QrCode::encode(payload.as_bytes()) else {
+        centered!(s, DIM, "Detection too long for QR");
+        return;
+    };
+    let x = s.qr(&code) + 6;
+    s.skip(8);
+    text_at!(s, x, Rgb565::GREEN, "LAST DETECTION");
+    text_at!(s, x, FG, "{}", last);
+    text_at!(s, x, DIM, "{}", &mac[..8]);
+    text_at!(s, x, DIM, "{}", &mac[9..]);
+    text_at!(
+        s,
+        x,
+        DIM,
+        "{} {}dBm {}",
+        detection.radio,
+        detection.rssi,
+        detection.zone.as_str()
+    );
+    s.skip(ROW_H);
+    text_at!(s, x, DIM, "A: next");
+}
+
+fn draw_no_detection(display: &mut impl DrawTarget<Color = Rgb565>) {
+    let mut s = Screen::new(display);
+    s.clear();
+    s.skip(49);
+    centered!(s, FG, "No detections yet");
+    centered!(s, DIM, "A: next");
+}
+
+/// QR code of the device's BLE name and service UUID, for pairing a
+/// companion app.
+fn draw_pairing(display: &mut impl DrawTarget<Color = Rgb565>) {
+    let mut payload = heapless::String::<128>::new();
+    let _ = core::fmt::write(
+        &mut payload,
+        format_args!(
+            "AIRHOUND name={} board={} v={} service={}",
+            BLE_ADV_NAME,
+            board::BOARD_NAME,
+            VERSION,
+            ble_uuids::SERVICE
+        ),
+    );
+
+    let mut s = Screen::new(display);
+    s.clear();
+    let Some(code) = QrCode::encode(payload.as_bytes()) else {
+        return;
+    };
+    let x = s.qr(&code) + 6;
+    s.skip(8);
+    text_at!(s, x, ACCENT, "PAIR");
+    text_at!(s, x, FG, "{}", BLE_ADV_NAME);
+    text_at!(s, x, DIM, "v{}", VERSION);
+    text_at!(s, x, DIM, "{}", board::BOARD_NAME);
+    s.skip(ROW_H * 2);
+    text_at!(s, x, DIM, "A: next");
+}
+
 // ── Display task (hardware init + render loop) ────────────────────────
 
 #[embassy_executor::task]
@@ -261,6 +462,7 @@ pub async fn display_task(
     dc_pin: esp_hal::peripherals::GPIO14<'static>,
     rst_pin: esp_hal::peripherals::GPIO12<'static>,
     bl_pin: esp_hal::peripherals::GPIO27<'static>,
+    button_pin: esp_hal::peripherals::GPIO37<'static>,
 ) {
     log::info!("Display task starting");
 
@@ -323,17 +525,54 @@ pub async fn display_task(
     draw_splash(&mut display);
     Timer::after(Duration::from_secs(2)).await;
 
-    // Prepare for status loop: clear splash, paint header bg once.
-    // The header text draw covers the middle 10px each frame via
-    // background_color, but the 4px row-edge gap needs a one-time fill.
-    {
-        let mut s = Screen::new(&mut display);
-        s.clear();
-        s.fill_band(ROW_H, HEADER_BG);
-    }
+    // Button A has an external pull-up (GPIO37 is input-only)
+    let button = Input::new(button_pin, InputConfig::default());
+    let mut was_pressed = false;
+    let mut page = Page::Status;
+    let mut shown_detection = None;
+    let mut polls = 0;
+
+    enter_status(&mut display);
 
     loop {
-        draw_status(&mut display);
-        Timer::after(Duration::from_millis(500)).await;
+        let pressed = button.is_low();
+        let changed = pressed && !was_pressed;
+        was_pressed = pressed;
+        if changed {
+            page = page.next();
+            shown_detection = None;
+            polls = 0;
+        }
+
+        match page {
+            Page::Status => {
+                if changed {
+                    enter_status(&mut display);
+                }
+                if polls % STATUS_REFRESH_POLLS == 0 {
+                    draw_status(&mut display);
+                }
+            }
+            Page::Detection => {
+                // Redrawn only when a newer detection arrives
+                let detection = critical_section::with(|cs| crate::LAST_DETECTION.borrow(cs).get());
+                let stamp = detection.map(|d| d.uptime_ms);
+                if changed || stamp != shown_detection {
+                    match detection {
+                        Some(detection) => draw_detection(&mut display, &detection),
+                        None => draw_no_detection(&mut display),
+                    }
+                    shown_detection = stamp;
+                }
+            }
+            Page::Pairing => {
+                if changed {
+                    draw_pairing(&mut display);
+                }
+            }
+        }
+
+        polls = polls.wrapping_add(1);
+        Timer::after(Duration::from_millis(BUTTON_POLL_MS)).await;
     }
 }
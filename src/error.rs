@@ -0,0 +1,45 @@
+/// Error type for the library's parsers and encoders.
+///
+/// Small and `no_std` on purpose: callers mostly log the error or count it,
+/// so each variant only says which kind of failure happened, not where.
+/// Persistence backends keep their own [`crate::store::StoreError`].
+use core::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AirhoundError {
+    /// The output buffer, or a fixed-size input buffer, is too small
+    BufferTooSmall,
+    /// Input is not well-formed: invalid JSON, a truncated frame
+    Malformed,
+    /// Input is well-formed but not something we handle, e.g. an unknown
+    /// command name
+    Unsupported,
+    /// A recognized command with a missing or out-of-range field
+    InvalidArgument,
+    /// Signed data whose signature doesn't verify
+    BadSignature,
+    /// Data whose checksum doesn't match, e.g. a frame corrupted on air
+    BadChecksum,
+}
+
+impl AirhoundError {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AirhoundError::BufferTooSmall => "buffer too small",
+            AirhoundError::Malformed => "malformed input",
+            AirhoundError::Unsupported => "unsupported",
+            AirhoundError::InvalidArgument => "invalid argument",
+            AirhoundError::BadSignature => "bad signature",
+            AirhoundError::BadChecksum => "bad checksum",
+        }
+    }
+}
+
+impl fmt::Display for AirhoundError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AirhoundError {}
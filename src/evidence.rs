@@ -0,0 +1,525 @@
+/// Evidence bundle export for std hosts.
+///
+/// Packages everything known about one detection — the NDJSON events, the
+/// matched 802.11 frames as a pcap, the GPS track around it, and a manifest —
+/// into a single zip. Every file's SHA-256 is listed in `manifest.json` and in
+/// a `SHA256SUMS` file, so the bundle can be checked with `sha256sum -c`
+/// after unzipping. Entries are stored uncompressed with a fixed timestamp:
+/// the same inputs always produce a byte-identical bundle.
+use std::io::{self, Write};
+
+use serde::Serialize;
+
+use crate::crc::crc32;
+use crate::protocol::VERSION;
+
+/// Bundle format identifier written to the manifest
+pub const FORMAT: &str = "airhound-evidence/1";
+
+/// pcap link type for raw 802.11 frames without a radiotap header
+pub const LINKTYPE_IEEE802_11: u32 = 105;
+
+/// A captured frame: milliseconds since the Unix epoch and the raw bytes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedFrame {
+    pub ts_ms: u64,
+    pub data: Vec<u8>,
+}
+
+/// One GPS fix of the track segment
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GpsFix {
+    /// Milliseconds since the Unix epoch
+    pub ts_ms: u64,
+    pub lat: f64,
+    pub lon: f64,
+}
+
+impl GpsFix {
+    /// A device fix ([`crate::gps::GpsFix`], 1e-7 degrees) in degrees.
+    /// `None` until the receiver has given the date.
+    pub fn from_gps(fix: &crate::gps::GpsFix) -> Option<Self> {
+        Some(Self {
+            ts_ms: fix.timestamp?,
+            lat: fix.lat as f64 / 1e7,
+            lon: fix.lon as f64 / 1e7,
+        })
+    }
+}
+
+/// Artifacts of one detection, ready to be written as a zip.
+#[derive(Debug, Clone, Default)]
+pub struct EvidenceBundle {
+    /// Human-readable summary, e.g. "Flock Safety camera, Main St"
+    pub summary: String,
+    /// Unix time the bundle was created, if known
+    pub created: Option<u64>,
+    /// NDJSON device messages, one per entry, without trailing newlines
+    pub events: Vec<String>,
+    /// Matched 802.11 frames
+    pub frames: Vec<CapturedFrame>,
+    /// GPS fixes around the detection, oldest first
+    pub track: Vec<GpsFix>,
+}
+
+#[derive(Serialize)]
+struct Manifest<'a> {
+    format: &'static str,
+    firmware: &'static str,
+    summary: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    created: Option<u64>,
+    events: usize,
+    frames: usize,
+    fixes: usize,
+    files: &'a [ManifestFile<'a>],
+}
+
+#[derive(Serialize)]
+struct ManifestFile<'a> {
+    name: &'a str,
+    size: usize,
+    sha256: &'a str,
+}
+
+impl EvidenceBundle {
+    /// Write the bundle as a zip archive.
+    pub fn write_zip<W: Write>(&self, out: W) -> io::Result<W> {
+        let events = self.events_ndjson();
+        let pcap = self.frames_pcap();
+        let track = self.track_csv();
+        let contents: [(&str, &[u8]); 3] = [
+            ("events.ndjson", &events),
+            ("frames.pcap", &pcap),
+            ("track.csv", &track),
+        ];
+
+        let hashes: Vec<String> = contents.iter().map(|(_, d)| hex(&sha256(d))).collect();
+        let files: Vec<ManifestFile> = contents
+            .iter()
+            .zip(&hashes)
+            .map(|((name, data), hash)| ManifestFile {
+                name,
+                size: data.len(),
+                sha256: hash,
+            })
+            .collect();
+        let manifest = self.manifest_json(&files)?;
+
+        let mut sums = String::new();
+        for (name, data) in contents.iter().chain([&("manifest.json", &manifest[..])]) {
+            sums.push_str(&format!("{}  {}\n", hex(&sha256(data)), name));
+        }
+
+        let mut zip = ZipWriter::new(out);
+        for (name, data) in contents {
+            zip.add(name, data)?;
+        }
+        zip.add("manifest.json", &manifest)?;
+        zip.add("SHA256SUMS", sums.as_bytes())?;
+        zip.finish()
+    }
+
+    fn events_ndjson(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        for event in &self.events {
+            out.extend_from_slice(event.trim_end().as_bytes());
+            out.push(b'\n');
+        }
+        out
+    }
+
+    /// The matched frames as an 802.11 pcap
+    fn frames_pcap(&self) -> Vec<u8> {
+        let mut out = pcap_header(LINKTYPE_IEEE802_11);
+        for frame in &self.frames {
+            pcap_record(&mut out, frame.ts_ms, &frame.data, frame.data.len());
+        }
+        out
+    }
+
+    fn track_csv(&self) -> Vec<u8> {
+        let mut out = String::from("ts_ms,lat,lon\n");
+        for fix in &self.track {
+            out.push_str(&format!("{},{:.7},{:.7}\n", fix.ts_ms, fix.lat, fix.lon));
+        }
+        out.into_bytes()
+    }
+
+    fn manifest_json(&self, files: &[ManifestFile]) -> io::Result<Vec<u8>> {
+        let manifest = Manifest {
+            format: FORMAT,
+            firmware: VERSION,
+            summary: &self.summary,
+            created: self.created,
+            events: self.events.len(),
+            frames: self.frames.len(),
+            fixes: self.track.len(),
+            files,
+        };
+        let mut buf = vec![0u8; 1024 + self.summary.len() * 6];
+        let len = serde_json_core::to_slice(&manifest, &mut buf)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "manifest too large"))?;
+        buf.truncate(len);
+        buf.push(b'\n');
+        Ok(buf)
+    }
+}
+
+// ── pcap ───────────────────────────────────────────────────────────────
+
+/// Header of a classic little-endian pcap with microsecond timestamps
+pub fn pcap_header(linktype: u32) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&0xA1B2_C3D4u32.to_le_bytes());
+    out.extend_from_slice(&2u16.to_le_bytes());
+    out.extend_from_slice(&4u16.to_le_bytes());
+    out.extend_from_slice(&0i32.to_le_bytes()); // UTC
+    out.extend_from_slice(&0u32.to_le_bytes()); // timestamp accuracy
+    out.extend_from_slice(&65_535u32.to_le_bytes()); // snaplen
+    out.extend_from_slice(&linktype.to_le_bytes());
+    out
+}
+
+/// Append a packet received at `ts_ms` (milliseconds since the Unix epoch):
+/// `data`, captured from a packet of `orig_len` bytes
+pub fn pcap_record(out: &mut Vec<u8>, ts_ms: u64, data: &[u8], orig_len: usize) {
+    out.extend_from_slice(&((ts_ms / 1000) as u32).to_le_bytes());
+    out.extend_from_slice(&((ts_ms % 1000 * 1000) as u32).to_le_bytes());
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out.extend_from_slice(&(orig_len as u32).to_le_bytes());
+    out.extend_from_slice(data);
+}
+
+// ── Zip (stored entries) ───────────────────────────────────────────────
+
+/// DOS date for 1980-01-01, 00:00 — fixed so bundles are reproducible
+const ZIP_DOS_DATE: u16 = (1 << 5) | 1;
+
+struct ZipEntry {
+    name: String,
+    crc: u32,
+    size: u32,
+    offset: u32,
+}
+
+/// Minimal zip writer: uncompressed entries, no zip64
+struct ZipWriter<W> {
+    out: W,
+    offset: u32,
+    entries: Vec<ZipEntry>,
+}
+
+impl<W: Write> ZipWriter<W> {
+    fn new(out: W) -> Self {
+        Self {
+            out,
+            offset: 0,
+            entries: Vec::new(),
+        }
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.out.write_all(bytes)?;
+        self.offset = u32::try_from(bytes.len())
+            .ok()
+            .and_then(|len| self.offset.checked_add(len))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "bundle exceeds 4 GiB"))?;
+        Ok(())
+    }
+
+    fn add(&mut self, name: &str, data: &[u8]) -> io::Result<()> {
+        let entry = ZipEntry {
+            name: name.into(),
+            crc: crc32(data),
+            size: data.len() as u32,
+            offset: self.offset,
+        };
+        let mut header = Vec::with_capacity(30 + name.len());
+        header.extend_from_slice(&0x0403_4B50u32.to_le_bytes());
+        header.extend_from_slice(&20u16.to_le_bytes()); // version needed
+        header.extend_from_slice(&0u16.to_le_bytes()); // flags
+        header.extend_from_slice(&0u16.to_le_bytes()); // stored
+        header.extend_from_slice(&0u16.to_le_bytes()); // time
+        header.extend_from_slice(&ZIP_DOS_DATE.to_le_bytes());
+        header.extend_from_slice(&entry.crc.to_le_bytes());
+        header.extend_from_slice(&entry.size.to_le_bytes());
+        header.extend_from_slice(&entry.size.to_le_bytes());
+        header.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes()); // extra length
+        header.extend_from_slice(name.as_bytes());
+        self.write(&header)?;
+        self.write(data)?;
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    fn finish(mut self) -> io::Result<W> {
+        let directory_offset = self.offset;
+        for i in 0..self.entries.len() {
+            let entry = &self.entries[i];
+            let mut record = Vec::with_capacity(46 + entry.name.len());
+            record.extend_from_slice(&0x0201_4B50u32.to_le_bytes());
+            record.extend_from_slice(&20u16.to_le_bytes()); // version made by
+            record.extend_from_slice(&20u16.to_le_bytes()); // version needed
+            record.extend_from_slice(&0u16.to_le_bytes()); // flags
+            record.extend_from_slice(&0u16.to_le_bytes()); // stored
+            record.extend_from_slice(&0u16.to_le_bytes()); // time
+            record.extend_from_slice(&ZIP_DOS_DATE.to_le_bytes());
+            record.extend_from_slice(&entry.crc.to_le_bytes());
+            record.extend_from_slice(&entry.size.to_le_bytes());
+            record.extend_from_slice(&entry.size.to_le_bytes());
+            record.extend_from_slice(&(entry.name.len() as u16).to_le_bytes());
+            record.extend_from_slice(&[0; 12]); // extra, comment, disk, attributes
+            record.extend_from_slice(&entry.offset.to_le_bytes());
+            record.extend_from_slice(entry.name.as_bytes());
+            self.write(&record)?;
+        }
+        let directory_size = self.offset - directory_offset;
+        let count = self.entries.len() as u16;
+
+        let mut end = Vec::with_capacity(22);
+        end.extend_from_slice(&0x0605_4B50u32.to_le_bytes());
+        end.extend_from_slice(&[0; 4]); // disk numbers
+        end.extend_from_slice(&count.to_le_bytes());
+        end.extend_from_slice(&count.to_le_bytes());
+        end.extend_from_slice(&directory_size.to_le_bytes());
+        end.extend_from_slice(&directory_offset.to_le_bytes());
+        end.extend_from_slice(&0u16.to_le_bytes()); // comment length
+        self.write(&end)?;
+        Ok(self.out)
+    }
+}
+
+// ── SHA-256 ────────────────────────────────────────────────────────────
+
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// SHA-256 digest of `data`
+pub fn sha256(data: &[u8]) -> [u8; 32] {
+    let mut h: [u32; 8] = [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+        0x5be0cd19,
+    ];
+
+    // Message, 0x80, zero padding, then the bit length in the last 8 bytes
+    let mut padded = data.to_vec();
+    padded.push(0x80);
+    while padded.len() % 64 != 56 {
+        padded.push(0);
+    }
+    padded.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+
+    for block in padded.chunks_exact(64) {
+        let mut w = [0u32; 64];
+        for (i, word) in block.chunks_exact(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16]
+                .wrapping_add(s0)
+                .wrapping_add(w[i - 7])
+                .wrapping_add(s1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut hh] = h;
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let t1 = hh
+                .wrapping_add(s1)
+                .wrapping_add(ch)
+                .wrapping_add(K[i])
+                .wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(maj);
+            hh = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+        for (state, add) in h.iter_mut().zip([a, b, c, d, e, f, g, hh]) {
+            *state = state.wrapping_add(add);
+        }
+    }
+
+    let mut out = [0u8; 32];
+    for (chunk, word) in out.chunks_exact_mut(4).zip(h) {
+        chunk.copy_from_slice(&word.to_be_bytes());
+    }
+    out
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bundle() -> EvidenceBundle {
+        EvidenceBundle {
+            summary: "Flock Safety camera, \"Main St\"".into(),
+            created: Some(1_767_225_600),
+            events: vec![
+                r#"{"type":"wifi","mac":"B4:1E:52:AA:BB:CC","rssi":-65,"ts":1}"#.into(),
+                r#"{"type":"wifi","mac":"B4:1E:52:AA:BB:CC","rssi":-58,"ts":2}"#.into(),
+            ],
+            frames: vec![CapturedFrame {
+                ts_ms: 1_767_225_600_250,
+                data: vec![0x80, 0x00, 0x00, 0x00],
+            }],
+            track: vec![GpsFix {
+                ts_ms: 1_767_225_600_000,
+                lat: 45.5231,
+                lon: -122.6765,
+            }],
+        }
+    }
+
+    /// (name, data) of every entry, read through the central directory
+    fn unzip(zip: &[u8]) -> Vec<(String, Vec<u8>)> {
+        let u16_at = |i: usize| u16::from_le_bytes([zip[i], zip[i + 1]]) as usize;
+        let u32_at = |i: usize| u32::from_le_bytes(zip[i..i + 4].try_into().unwrap()) as usize;
+
+        let end = zip.len() - 22;
+        assert_eq!(u32_at(end), 0x0605_4B50);
+        let count = u16_at(end + 10);
+        let mut pos = u32_at(end + 16);
+        let mut entries = Vec::new();
+        for _ in 0..count {
+            assert_eq!(u32_at(pos), 0x0201_4B50);
+            let crc = u32_at(pos + 16) as u32;
+            let size = u32_at(pos + 24);
+            let name_len = u16_at(pos + 28);
+            let offset = u32_at(pos + 42);
+            let name = String::from_utf8(zip[pos + 46..pos + 46 + name_len].to_vec()).unwrap();
+
+            assert_eq!(u32_at(offset), 0x0403_4B50);
+            let start = offset + 30 + u16_at(offset + 26) + u16_at(offset + 28);
+            let data = zip[start..start + size].to_vec();
+            assert_eq!(crc32(&data), crc);
+            entries.push((name, data));
+            pos += 46 + name_len;
+        }
+        entries
+    }
+
+    // ── Primitives ──────────────────────────────────────────────────
+
+    #[test]
+    fn sha256_known_vectors() {
+        assert_eq!(
+            hex(&sha256(b"")),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            hex(&sha256(b"abc")),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        // Two-block message
+        assert_eq!(
+            hex(&sha256(
+                b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"
+            )),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+        );
+    }
+
+    #[test]
+    fn track_from_device_fixes() {
+        let mut fix = crate::gps::GpsFix {
+            lat: 455_231_000,
+            lon: -1_226_765_000,
+            alt: None,
+            speed: None,
+            hdop: None,
+            timestamp: None,
+        };
+        assert_eq!(GpsFix::from_gps(&fix), None);
+        fix.timestamp = Some(1_767_225_600_000);
+        let converted = GpsFix::from_gps(&fix).unwrap();
+        assert_eq!(converted.ts_ms, 1_767_225_600_000);
+        assert!((converted.lat - 45.5231).abs() < 1e-9);
+        assert!((converted.lon + 122.6765).abs() < 1e-9);
+    }
+
+    #[test]
+    fn pcap_layout() {
+        let pcap = bundle().frames_pcap();
+        assert_eq!(&pcap[..4], &[0xD4, 0xC3, 0xB2, 0xA1]);
+        assert_eq!(&pcap[20..24], &LINKTYPE_IEEE802_11.to_le_bytes());
+        // Record header: seconds, microseconds, captured and original length
+        assert_eq!(&pcap[24..28], &1_767_225_600u32.to_le_bytes());
+        assert_eq!(&pcap[28..32], &250_000u32.to_le_bytes());
+        assert_eq!(&pcap[32..36], &4u32.to_le_bytes());
+        assert_eq!(&pcap[40..], &[0x80, 0x00, 0x00, 0x00]);
+    }
+
+    // ── Bundle ──────────────────────────────────────────────────────
+
+    #[test]
+    fn bundle_contains_all_artifacts() {
+        let zip = bundle().write_zip(Vec::new()).unwrap();
+        let entries = unzip(&zip);
+        let names: Vec<&str> = entries.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "events.ndjson",
+                "frames.pcap",
+                "track.csv",
+                "manifest.json",
+                "SHA256SUMS"
+            ]
+        );
+        assert_eq!(entries[0].1.iter().filter(|&&b| b == b'\n').count(), 2);
+        assert_eq!(
+            entries[2].1,
+            b"ts_ms,lat,lon\n1767225600000,45.5231000,-122.6765000\n"
+        );
+    }
+
+    #[test]
+    fn hashes_verify_every_file() {
+        let entries = unzip(&bundle().write_zip(Vec::new()).unwrap());
+        let manifest = core::str::from_utf8(&entries[3].1).unwrap();
+        assert!(manifest.starts_with(r#"{"format":"airhound-evidence/1""#));
+        assert!(manifest.contains(r#""summary":"Flock Safety camera, \"Main St\"""#));
+        assert!(manifest.contains(r#""events":2,"frames":1,"fixes":1"#));
+
+        let sums = core::str::from_utf8(&entries[4].1).unwrap();
+        assert_eq!(sums.lines().count(), 4);
+        for (line, (name, data)) in sums.lines().zip(&entries) {
+            let hash = hex(&sha256(data));
+            assert_eq!(line, format!("{}  {}", hash, name));
+            if name != "manifest.json" {
+                assert!(manifest.contains(&hash));
+            }
+        }
+    }
+
+    #[test]
+    fn bundle_is_reproducible() {
+        let first = bundle().write_zip(Vec::new()).unwrap();
+        let second = bundle().write_zip(Vec::new()).unwrap();
+        assert_eq!(first, second);
+    }
+}
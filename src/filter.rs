@@ -1,19 +1,61 @@
-/// Configurable filter engine for WiFi and BLE scan results.
+/// Configurable filter engine for WiFi, BLE, and Bluetooth Classic scan results.
 ///
-/// Evaluates scan results against compiled-in defaults and runtime config.
-/// Any filter match causes the result to be emitted. No scoring or state tracking —
-/// that's the companion app's job.
+/// Evaluates scan results against compiled-in defaults, runtime signatures from
+/// any [`SignatureSource`] (the fixed-capacity [`SignatureOverlay`] loaded at
+/// boot, or a heap-backed `sigdb::SignatureDb` with `alloc`), and runtime config. Any filter match
+/// causes the result to be emitted. Compiled-in signatures that matched are
+/// also run through the packs' detection rules ([`crate::rules`]), which name
+/// the device. No scoring or state tracking — that's the companion app's job.
+///
+/// Each filter function runs a [`Pipeline`] of [`FilterStage`]s; host
+/// consumers can build their own pipeline with extra stages.
+/// [`Pipeline::standard`] runs, in order: [`RssiGate`] (enable switches,
+/// `min_rssi`, channel mask) → [`SelfFilter`] (other AirHound units, unless
+/// `report_self`) → [`SignatureMatch`] (signatures, then `exclusion`
+/// signatures keyed on the SSID or name) → [`SsidHeuristics`] →
+/// [`TimingHeuristics`] → [`SequenceHeuristics`] (each behind its
+/// `FilterConfig` switch) → [`Rules`] → [`Allowlist`]. A stage returning
+/// `Flow::Stop` ends the run.
+///
+/// A result keeps up to [`MAX_MATCHES`] reasons, setting `truncated` on
+/// overflow; `FilterResult::meta` is the strongest matched signature's rating.
+/// Randomized addresses only match locally administered OUI prefixes and
+/// `randomized_mac_fingerprint` patterns. [`FilterStats`] counts evaluations,
+/// matches and per-signature hits, and [`estimate_distance`] turns RSSI and
+/// TX power into a rough free-space distance.
 use heapless::Vec;
+use serde::{Deserialize, Serialize};
 
+use crate::anomaly::{self, SsidAnomaly};
+use crate::channel::{ChannelMask, Region};
+use crate::comm;
 use crate::defaults::{
-    self, BLE_MANUFACTURER_IDS, BLE_NAME_PATTERNS, BLE_SERVICE_UUIDS_16, MAC_PREFIXES, SSID_EXACT,
-    SSID_KEYWORDS, SSID_PATTERNS, WIFI_NAME_KEYWORDS,
+    Category, Confidence, Severity, SigTable, SignatureMeta, SignaturePack, PACKS,
+};
+use crate::following::FollowingPolicy;
+use crate::lingering::LingeringPolicy;
+use crate::location::StalePolicy;
+use crate::protocol::{truncate_str, MatchReason, MATCH_DETAIL_LEN};
+use crate::rssi::ProximityZones;
+use crate::rules::{
+    evaluate_rules, evaluate_rules_recent, RecentSigs, RuleMatch, SigHit, SigIdx, MAX_RULE_MATCHES,
+};
+use crate::scanner::{
+    ActionCategory, BleScanConfig, Eddystone, IBeacon, MatterCommissioning, NetworkKind, VendorIe,
+    WpsField, WpsInfo, MAX_WPS_TEXT,
 };
-use crate::protocol::{MatchDetail, MatchReason};
+use crate::schedule::QuietHours;
+use crate::sequence::SequenceAnomaly;
+use crate::sigfile::{
+    AdPatternRef, ExclusionRef, SignatureOverlay, StringMatch, StringSigRef, VendorIePatternRef,
+};
+use crate::store::{ConfigBlob, ConfigKey};
+use crate::timing::TimingAnomaly;
+use crate::traffic::Traffic;
 
 /// Runtime filter configuration. Allows the companion app to adjust
 /// filtering without reflashing.
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct FilterConfig {
     /// Minimum RSSI threshold (dBm). Signals weaker than this are ignored.
     pub min_rssi: i8,
@@ -21,6 +63,64 @@ pub struct FilterConfig {
     pub wifi_enabled: bool,
     /// Whether BLE scanning is enabled
     pub ble_enabled: bool,
+    /// BLE scan duty cycle. Defaulted when absent so configs persisted by
+    /// older firmware still load.
+    #[serde(default)]
+    pub ble_scan: BleScanConfig,
+    /// Advertise a detection summary in our own BLE advertisement
+    #[serde(default)]
+    pub beacon: bool,
+    /// Low-power BLE-only mode: WiFi sniffer and channel hopping off, BLE
+    /// scanned at [`BleScanConfig::LOW_POWER`]. `ble_scan` is kept for when
+    /// the mode is switched off.
+    #[serde(default)]
+    pub low_power: bool,
+    /// Regulatory region, selecting the WiFi channels to hop
+    #[serde(default)]
+    pub region: Region,
+    /// WiFi channels hopped and reported, within the region's plan
+    #[serde(default)]
+    pub channels: ChannelMask,
+    /// Daily window in which alerts are quiet (needs synced time)
+    #[serde(default)]
+    pub quiet_hours: QuietHours,
+    /// Signature categories that are reported
+    #[serde(default)]
+    pub categories: Categories,
+    /// Report a per-device moving average of RSSI alongside each reading
+    #[serde(default)]
+    pub rssi_smoothing: bool,
+    /// RSSI thresholds of the proximity zones reported with each match
+    #[serde(default)]
+    pub zones: ProximityZones,
+    /// Filter BLE results from this unit's own advertisement and other
+    /// AirHound units like any device, instead of dropping them
+    /// ([`SelfFilter`])
+    #[serde(default)]
+    pub report_self: bool,
+    /// Flag suspicious SSIDs no signature covers ([`SsidHeuristics`])
+    #[serde(default)]
+    pub ssid_heuristics: bool,
+    /// Flag BSSIDs whose beacon timing is off ([`TimingHeuristics`])
+    #[serde(default)]
+    pub timing_heuristics: bool,
+    /// Flag transmitters whose sequence numbers jump
+    /// ([`SequenceHeuristics`])
+    #[serde(default)]
+    pub sequence_heuristics: bool,
+    /// Drop captured WiFi frames whose frame check sequence doesn't match
+    /// (`scanner::parse_wifi_frame_with_fcs`)
+    #[serde(default)]
+    pub fcs_check: bool,
+    /// Age beyond which the location tagged on a detection is marked stale
+    #[serde(default)]
+    pub location_stale: StalePolicy,
+    /// When a device heard along the user's way is reported as following
+    #[serde(default)]
+    pub following: FollowingPolicy,
+    /// When a tracker staying near the user is reported as lingering
+    #[serde(default)]
+    pub lingering: LingeringPolicy,
 }
 
 impl FilterConfig {
@@ -29,6 +129,37 @@ impl FilterConfig {
             min_rssi: -90,
             wifi_enabled: true,
             ble_enabled: true,
+            ble_scan: BleScanConfig::new(),
+            beacon: false,
+            low_power: false,
+            region: Region::Etsi,
+            channels: ChannelMask::ALL,
+            quiet_hours: QuietHours::DISABLED,
+            categories: Categories::ALL,
+            rssi_smoothing: false,
+            zones: ProximityZones::DEFAULT,
+            report_self: false,
+            ssid_heuristics: false,
+            timing_heuristics: false,
+            sequence_heuristics: false,
+            fcs_check: false,
+            location_stale: StalePolicy::DEFAULT,
+            following: FollowingPolicy::DEFAULT,
+            lingering: LingeringPolicy::DEFAULT,
+        }
+    }
+
+    /// Whether the WiFi sniffer should run.
+    pub const fn wifi_active(&self) -> bool {
+        self.wifi_enabled && !self.low_power
+    }
+
+    /// BLE scan parameters to apply, accounting for low-power mode.
+    pub const fn effective_ble_scan(&self) -> BleScanConfig {
+        if self.low_power {
+            BleScanConfig::LOW_POWER
+        } else {
+            self.ble_scan
         }
     }
 }
@@ -39,370 +170,2057 @@ impl Default for FilterConfig {
     }
 }
 
+/// Per-category switches. Matches of a disabled category are dropped, so a
+/// user who only cares about ALPR cameras can silence trackers and pentest
+/// tools without editing signatures. Fields missing from a stored config or
+/// a `set_categories` command are enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Categories {
+    pub alpr: bool,
+    pub gunshot_detector: bool,
+    pub camera: bool,
+    pub tracker: bool,
+    pub drone: bool,
+    pub attack_tool: bool,
+    pub other: bool,
+}
+
+impl Categories {
+    pub const ALL: Self = Self {
+        alpr: true,
+        gunshot_detector: true,
+        camera: true,
+        tracker: true,
+        drone: true,
+        attack_tool: true,
+        other: true,
+    };
+
+    /// Whether matches of `category` are reported.
+    pub const fn allows(&self, category: Category) -> bool {
+        match category {
+            Category::Alpr => self.alpr,
+            Category::GunshotDetector => self.gunshot_detector,
+            Category::Camera => self.camera,
+            Category::Tracker => self.tracker,
+            Category::Drone => self.drone,
+            Category::AttackTool => self.attack_tool,
+            Category::Other => self.other,
+        }
+    }
+}
+
+impl Default for Categories {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+impl ConfigBlob for FilterConfig {
+    const KEY: ConfigKey = ConfigKey::FilterConfig;
+
+    fn encode(&self, buf: &mut [u8]) -> Option<usize> {
+        serde_json_core::to_slice(self, buf).ok()
+    }
+
+    fn decode(data: &[u8]) -> Option<Self> {
+        serde_json_core::from_slice(data)
+            .ok()
+            .map(|(config, _)| config)
+    }
+}
+
 /// Input data for filtering a WiFi scan result
 pub struct WiFiScanInput<'a> {
     pub mac: &'a [u8; 6],
+    /// Locally administered (randomized) transmitter address, see
+    /// [`is_locally_administered`]
+    pub mac_randomized: bool,
     pub ssid: &'a str,
     pub rssi: i8,
+    /// Channel the frame was heard on
+    pub channel: u8,
+    /// Sustained data-frame volume of this transmitter
+    pub traffic: Traffic,
+    /// Leading information elements of a beacon or probe, for
+    /// `randomized_mac_fingerprint` signatures
+    pub ies: &'a [u8],
+    /// Vendor-specific elements of a beacon or probe
+    pub vendor_ies: &'a [VendorIe],
+    /// WPS device identity of a beacon or probe response
+    pub wps: Option<&'a WpsInfo>,
+    /// Capability information of a beacon or probe response
+    pub capability: Option<u16>,
+    /// What is off about this BSSID's beacon timing, from
+    /// `timing::TimingMonitor`
+    pub timing: Option<TimingAnomaly>,
+    /// What is off about this transmitter's sequence numbers, from
+    /// `sequence::SequenceMonitor`
+    pub sequence: Option<SequenceAnomaly>,
+    /// Category of an action frame
+    pub action: Option<ActionCategory>,
+    /// Mesh or WiFi Direct group a beacon or probe response announces;
+    /// `ssid` is its name
+    pub network: Option<NetworkKind>,
 }
 
 /// Input data for filtering a BLE scan result
 pub struct BleScanInput<'a> {
     pub mac: &'a [u8; 6],
+    /// Random (private or static) advertiser address, as reported by the
+    /// controller
+    pub mac_randomized: bool,
     pub name: &'a str,
     pub rssi: i8,
     /// 16-bit service UUIDs found in advertisement
     pub service_uuids_16: &'a [u16],
+    /// 32-bit service UUIDs found in advertisement
+    pub service_uuids_32: &'a [u32],
+    /// 128-bit service UUIDs found in advertisement
+    pub service_uuids_128: &'a [u128],
     /// Manufacturer company ID (0 if not present)
     pub manufacturer_id: u16,
+    /// Matter commissioning advertisement, if present
+    pub matter: Option<MatterCommissioning>,
+    /// iBeacon advertisement, if present
+    pub ibeacon: Option<IBeacon>,
+    /// Eddystone frame, if present
+    pub eddystone: Option<&'a Eddystone>,
+    /// Thread commissioning (TCAT) service data present
+    pub thread_commissioning: bool,
+    /// Raw advertisement payload, for `ble_ad_bytes` signatures
+    pub ad_data: &'a [u8],
+}
+
+/// Input data for filtering a Bluetooth Classic inquiry result
+pub struct BtClassicScanInput<'a> {
+    pub mac: &'a [u8; 6],
+    pub name: &'a str,
+    pub rssi: i8,
+    /// 24-bit Class of Device
+    pub class_of_device: u32,
+    /// Manufacturer company ID from extended inquiry response data (0 if
+    /// not present)
+    pub manufacturer_id: u16,
 }
 
-/// Result of filter evaluation
-pub struct FilterResult {
+/// Result of filter evaluation. Match details hold up to `N` bytes; the
+/// `*_sized` filter functions let std consumers keep longer descriptions.
+pub struct FilterResult<const N: usize = MATCH_DETAIL_LEN> {
     /// Whether any filter matched
     pub matched: bool,
-    /// Up to 4 match reasons
-    pub matches: Vec<MatchReason, 4>,
+    /// Up to [`MAX_MATCHES`] match reasons
+    pub matches: Vec<MatchReason<N>, MAX_MATCHES>,
+    /// Sightings of the device needed before reporting: the lowest
+    /// `min_sightings` among the matches, so any strong indicator fires on
+    /// first sight (see [`crate::sightings`])
+    pub min_sightings: u8,
+    /// Rating of the strongest match, including any beyond those kept,
+    /// raised to the severity of any matched rule
+    pub meta: SignatureMeta,
+    /// Compiled-in signatures among the matches, for rule evaluation
+    pub hits: Vec<SigHit, MAX_HITS>,
+    /// Detection rules the matched signatures satisfy
+    pub rules: Vec<RuleMatch, MAX_RULE_MATCHES>,
+    /// Matches, signature hits or rules were dropped for lack of room
+    pub truncated: bool,
+    /// Dropped unevaluated for being weaker than `FilterConfig::min_rssi`
+    pub rssi_rejected: bool,
+    /// Categories whose matches are kept
+    categories: Categories,
 }
 
-impl FilterResult {
-    fn new() -> Self {
+/// Match reasons a result keeps
+pub const MAX_MATCHES: usize = 4;
+
+/// Compiled-in signature matches kept for rule evaluation
+pub const MAX_HITS: usize = 8;
+
+impl<const N: usize> FilterResult<N> {
+    fn new(config: &FilterConfig) -> Self {
         Self {
             matched: false,
             matches: Vec::new(),
+            min_sightings: 1,
+            meta: SignatureMeta::UNRATED,
+            hits: Vec::new(),
+            rules: Vec::new(),
+            truncated: false,
+            rssi_rejected: false,
+            categories: config.categories,
         }
     }
 
-    fn add_match(&mut self, filter_type: &'static str, detail: &str) {
-        if self.matches.len() < 4 {
-            let mut d = MatchDetail::new();
-            // Truncate detail to fit
-            let truncated = if detail.len() <= 32 {
-                detail
-            } else {
-                &detail[..32]
-            };
-            let _ = d.push_str(truncated);
-            let _ = self.matches.push(MatchReason {
-                filter_type,
-                detail: d,
-            });
-        }
-        self.matched = true;
+    /// Add a match of the compiled-in signature at `sig`, recording it for
+    /// rules.
+    fn add_sig_match(
+        &mut self,
+        sig: SigHit,
+        filter_type: &'static str,
+        detail: &str,
+        min_sightings: u8,
+        meta: SignatureMeta,
+    ) {
+        self.hit(sig, meta);
+        self.add_weak_match(filter_type, detail, min_sightings, meta);
     }
-}
-
-/// Evaluate a WiFi scan result against all configured filters.
-pub fn filter_wifi(input: &WiFiScanInput, config: &FilterConfig) -> FilterResult {
-    let mut result = FilterResult::new();
 
-    if !config.wifi_enabled {
-        return result;
+    /// Record a compiled-in signature match for rule evaluation.
+    fn hit(&mut self, sig: SigHit, meta: SignatureMeta) {
+        if self.categories.allows(meta.category) && self.hits.push(sig).is_err() {
+            self.truncated = true;
+        }
     }
 
-    // RSSI threshold check
-    if input.rssi < config.min_rssi {
-        return result;
+    /// Add a match reported on first sight.
+    pub fn add_match(&mut self, filter_type: &'static str, detail: &str, meta: SignatureMeta) {
+        self.add_weak_match(filter_type, detail, 1, meta);
     }
 
-    // MAC OUI prefix check
-    check_mac_oui(input.mac, &mut result);
-
-    // SSID structured pattern check (e.g., Flock-XXXXXX)
-    for pattern in SSID_PATTERNS {
-        if pattern.matches(input.ssid) {
-            result.add_match("ssid_pattern", pattern.description);
+    /// Add a match that only counts once the device has been seen
+    /// `min_sightings` times.
+    pub fn add_weak_match(
+        &mut self,
+        filter_type: &'static str,
+        detail: &str,
+        min_sightings: u8,
+        meta: SignatureMeta,
+    ) {
+        if !self.categories.allows(meta.category) {
+            return;
         }
+        let reason = MatchReason::new(filter_type, detail, meta);
+        if self.matches.push(reason).is_err() {
+            self.truncated = true;
+        }
+        if self.matched {
+            self.min_sightings = self.min_sightings.min(min_sightings);
+            if meta.outranks(&self.meta) {
+                self.meta = meta;
+            }
+        } else {
+            self.min_sightings = min_sightings;
+            self.meta = meta;
+        }
+        self.matched = true;
     }
 
-    // SSID exact match check
-    for &exact in SSID_EXACT {
-        if input.ssid == exact {
-            result.add_match("ssid_exact", exact);
+    /// Drop the matches whose detail is `excludes`, or all of them if `None`,
+    /// and re-rate the result from the matches left.
+    pub fn exclude(&mut self, excludes: Option<&str>) {
+        match excludes {
+            Some(excludes) => {
+                let excludes = truncate_str::<N>(excludes).0;
+                self.matches.retain(|reason| reason.detail != excludes);
+                self.hits
+                    .retain(|hit| truncate_str::<N>(hit.description()).0 != excludes);
+            }
+            None => {
+                self.matches.clear();
+                self.hits.clear();
+            }
         }
+        self.matched = !self.matches.is_empty();
+        self.meta = self
+            .matches
+            .iter()
+            .map(|reason| reason.meta)
+            .reduce(|best, meta| if meta.outranks(&best) { meta } else { best })
+            .unwrap_or(SignatureMeta::UNRATED);
     }
 
-    // SSID keyword substring check (case-insensitive)
-    let ssid_lower: Vec<u8, 33> = input
-        .ssid
-        .bytes()
-        .take(33)
-        .map(|b| b.to_ascii_lowercase())
-        .collect();
-    let ssid_lower_str = core::str::from_utf8(&ssid_lower).unwrap_or("");
+    /// Evaluate the packs' rules over the matched compiled-in signatures. A
+    /// matched rule raises the detection's severity to its own.
+    fn apply_rules(&mut self) {
+        if self.hits.is_empty() {
+            return;
+        }
+        self.set_rules(evaluate_rules(&self.hits));
+    }
 
-    for &keyword in SSID_KEYWORDS {
-        if ssid_lower_str.contains(keyword) {
-            result.add_match("ssid_keyword", keyword);
+    /// Re-evaluate the rules with `within` nodes looking back over the
+    /// signatures in `recent` before `now_ms` as well; the filter itself
+    /// keeps no history.
+    pub fn apply_recent_rules(&mut self, recent: &RecentSigs, now_ms: u32) {
+        if self.hits.is_empty() {
+            return;
         }
+        self.set_rules(evaluate_rules_recent(&self.hits, recent, now_ms));
     }
 
-    // WiFi name keyword check (from FlockOff — matches partial names)
-    for &keyword in WIFI_NAME_KEYWORDS {
-        if ssid_lower_str.contains(keyword) {
-            // Only add if not already matched by SSID_KEYWORDS
-            if !SSID_KEYWORDS.contains(&keyword) {
-                result.add_match("wifi_name", keyword);
-            }
+    fn set_rules(&mut self, (rules, truncated): (Vec<RuleMatch, MAX_RULE_MATCHES>, bool)) {
+        self.rules = rules;
+        self.truncated |= truncated;
+        if let Some(severity) = self.rules.iter().map(|rule| rule.severity).max() {
+            self.meta.severity = self.meta.severity.max(severity);
         }
     }
+}
 
-    result
+/// Entries of one compiled-in table across the enabled packs, with the
+/// position rules know them by.
+fn compiled<T: 'static>(
+    table: SigTable,
+    entries: fn(&'static SignaturePack) -> &'static [T],
+) -> impl Iterator<Item = (SigHit, &'static T)> {
+    PACKS
+        .iter()
+        .enumerate()
+        .flat_map(move |(pack, &signatures)| {
+            entries(signatures)
+                .iter()
+                .enumerate()
+                .map(move |(index, entry)| {
+                    let sig = SigHit {
+                        pack: pack as u8,
+                        table,
+                        index: index as u16,
+                    };
+                    (sig, entry)
+                })
+        })
 }
 
-/// Evaluate a BLE scan result against all configured filters.
-pub fn filter_ble(input: &BleScanInput, config: &FilterConfig) -> FilterResult {
-    let mut result = FilterResult::new();
+/// Runtime signatures evaluated on top of the compiled-in packs. Table
+/// entries are `(value, description, min_sightings, meta)`.
+pub trait SignatureSource {
+    fn mac_prefixes(&self) -> impl Iterator<Item = ([u8; 3], &str, u8, SignatureMeta)>;
+    fn ssids(&self) -> impl Iterator<Item = StringSigRef<'_>>;
+    fn ble_names(&self) -> impl Iterator<Item = StringSigRef<'_>>;
+    fn service_uuids_16(&self) -> impl Iterator<Item = (u16, &str, u8, SignatureMeta)>;
+    fn manufacturer_ids(&self) -> impl Iterator<Item = (u16, &str, u8, SignatureMeta)>;
 
-    if !config.ble_enabled {
-        return result;
+    fn service_uuids_32(&self) -> impl Iterator<Item = (u32, &str, u8, SignatureMeta)> {
+        core::iter::empty()
     }
 
-    // RSSI threshold check
-    if input.rssi < config.min_rssi {
-        return result;
+    /// 128-bit service UUIDs off the Bluetooth base UUID
+    fn service_uuids_128(&self) -> impl Iterator<Item = (u128, &str, u8, SignatureMeta)> {
+        core::iter::empty()
     }
 
-    // MAC OUI prefix check
-    check_mac_oui(input.mac, &mut result);
+    /// Raw advertisement byte patterns
+    fn ad_patterns(&self) -> impl Iterator<Item = AdPatternRef<'_>> {
+        core::iter::empty()
+    }
 
-    // BLE device name pattern check (case-insensitive substring)
-    if !input.name.is_empty() {
-        let name_lower: Vec<u8, 33> = input
-            .name
-            .bytes()
-            .take(33)
-            .map(|b| b.to_ascii_lowercase())
-            .collect();
-        let name_lower_str = core::str::from_utf8(&name_lower).unwrap_or("");
-
-        for &pattern in BLE_NAME_PATTERNS {
-            let pattern_lower: Vec<u8, 33> = pattern
-                .bytes()
-                .take(33)
-                .map(|b| b.to_ascii_lowercase())
-                .collect();
-            let pattern_lower_str = core::str::from_utf8(&pattern_lower).unwrap_or("");
-
-            if name_lower_str.contains(pattern_lower_str) {
-                result.add_match("ble_name", pattern);
-            }
-        }
+    /// Information element patterns checked only on randomized WiFi
+    /// addresses (`randomized_mac_fingerprint`, transport `wifi`)
+    fn ie_fingerprints(&self) -> impl Iterator<Item = AdPatternRef<'_>> {
+        core::iter::empty()
     }
 
-    // BLE service UUID check (16-bit)
-    for &uuid in input.service_uuids_16 {
-        if BLE_SERVICE_UUIDS_16.contains(&uuid) {
-            result.add_match("ble_uuid", "Raven service UUID");
-        }
-        if defaults::BLE_STANDARD_UUIDS_16.contains(&uuid) {
-            result.add_match("ble_uuid_std", "Raven standard UUID");
-        }
+    /// Advertisement patterns checked only on random BLE addresses
+    /// (`randomized_mac_fingerprint`, transport `ble`)
+    fn ad_fingerprints(&self) -> impl Iterator<Item = AdPatternRef<'_>> {
+        core::iter::empty()
     }
 
-    // BLE manufacturer ID check
-    if input.manufacturer_id != 0 {
-        if BLE_MANUFACTURER_IDS.contains(&input.manufacturer_id) {
-            result.add_match("ble_mfr", "Known manufacturer ID");
-        }
+    /// WiFi vendor-specific information element patterns
+    fn vendor_ies(&self) -> impl Iterator<Item = VendorIePatternRef<'_>> {
+        core::iter::empty()
     }
 
-    result
-}
+    /// WPS signatures, each restricted to one field or matching any if `None`
+    fn wps(&self) -> impl Iterator<Item = (Option<WpsField>, StringSigRef<'_>)> {
+        core::iter::empty()
+    }
 
-/// Check MAC address against known OUI prefixes
-fn check_mac_oui(mac: &[u8; 6], result: &mut FilterResult) {
-    let oui = [mac[0], mac[1], mac[2]];
-    for &(ref prefix, vendor) in MAC_PREFIXES {
-        if oui == *prefix {
-            result.add_match("mac_oui", vendor);
-            return; // Only report first match (a MAC can only match one OUI)
-        }
+    /// Network kind signatures, each matched against the network's name
+    fn networks(&self) -> impl Iterator<Item = (NetworkKind, StringSigRef<'_>)> {
+        core::iter::empty()
     }
-}
 
-/// Format a 6-byte MAC address into "AA:BB:CC:DD:EE:FF" string
-pub fn format_mac(mac: &[u8; 6], buf: &mut crate::protocol::MacString) {
-    use core::fmt::Write;
-    let _ = write!(
-        buf,
-        "{:02X}:{:02X}:{:02X}:{:02X}:{:02X}:{:02X}",
-        mac[0], mac[1], mac[2], mac[3], mac[4], mac[5]
-    );
-}
+    /// Description and rating for access points flagged on data-frame
+    /// volume alone
+    fn high_traffic_aps(&self) -> Option<(&str, SignatureMeta)>;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Exclusions keyed on the WiFi SSID
+    fn ssid_exclusions(&self) -> impl Iterator<Item = ExclusionRef<'_>> {
+        core::iter::empty()
+    }
 
-    fn default_config() -> FilterConfig {
-        FilterConfig::new()
+    /// Exclusions keyed on the BLE or Classic device name
+    fn name_exclusions(&self) -> impl Iterator<Item = ExclusionRef<'_>> {
+        core::iter::empty()
     }
+}
 
-    // ── WiFi filter tests ───────────────────────────────────────────
+/// Two sources evaluated as one, e.g. the boot overlay plus signatures the
+/// companion added at runtime. The first source wins on OUI ties.
+impl<A: SignatureSource, B: SignatureSource> SignatureSource for (&A, &B) {
+    fn mac_prefixes(&self) -> impl Iterator<Item = ([u8; 3], &str, u8, SignatureMeta)> {
+        self.0.mac_prefixes().chain(self.1.mac_prefixes())
+    }
 
-    #[test]
-    fn wifi_known_flock_safety_mac_matches() {
-        let config = default_config();
-        let input = WiFiScanInput {
-            mac: &[0xB4, 0x1E, 0x52, 0x01, 0x02, 0x03],
-            ssid: "SomeNetwork",
-            rssi: -50,
-        };
-        let result = filter_wifi(&input, &config);
-        assert!(result.matched);
-        assert!(result.matches.iter().any(|m| m.filter_type == "mac_oui"));
+    fn ssids(&self) -> impl Iterator<Item = StringSigRef<'_>> {
+        self.0.ssids().chain(self.1.ssids())
     }
 
-    #[test]
-    fn wifi_silicon_labs_mac_matches() {
-        let config = default_config();
-        let input = WiFiScanInput {
-            mac: &[0x58, 0x8E, 0x81, 0xAA, 0xBB, 0xCC],
-            ssid: "",
-            rssi: -60,
-        };
-        let result = filter_wifi(&input, &config);
-        assert!(result.matched);
-        assert_eq!(result.matches[0].filter_type, "mac_oui");
-        assert!(result.matches[0].detail.contains("Silicon Labs"));
+    fn ble_names(&self) -> impl Iterator<Item = StringSigRef<'_>> {
+        self.0.ble_names().chain(self.1.ble_names())
     }
 
-    #[test]
-    fn wifi_ssid_pattern_flock_matches() {
-        let config = default_config();
-        let input = WiFiScanInput {
-            mac: &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
-            ssid: "Flock-A1B2C3",
-            rssi: -40,
-        };
-        let result = filter_wifi(&input, &config);
-        assert!(result.matched);
-        assert!(result
-            .matches
-            .iter()
-            .any(|m| m.filter_type == "ssid_pattern"));
+    fn service_uuids_16(&self) -> impl Iterator<Item = (u16, &str, u8, SignatureMeta)> {
+        self.0.service_uuids_16().chain(self.1.service_uuids_16())
     }
 
-    #[test]
-    fn wifi_ssid_pattern_penguin_matches() {
-        let config = default_config();
-        let input = WiFiScanInput {
-            mac: &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
-            ssid: "Penguin-1234567890",
-            rssi: -40,
-        };
-        let result = filter_wifi(&input, &config);
-        assert!(result.matched);
-        assert!(result
-            .matches
-            .iter()
-            .any(|m| m.filter_type == "ssid_pattern"));
+    fn manufacturer_ids(&self) -> impl Iterator<Item = (u16, &str, u8, SignatureMeta)> {
+        self.0.manufacturer_ids().chain(self.1.manufacturer_ids())
     }
 
-    #[test]
-    fn wifi_ssid_pattern_flock_wrong_suffix_no_pattern_match() {
-        let config = default_config();
-        // Too short suffix — pattern should NOT match, but keyword "flock" still matches
-        let input = WiFiScanInput {
-            mac: &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
-            ssid: "Flock-A1B",
-            rssi: -40,
-        };
-        let result = filter_wifi(&input, &config);
-        // No ssid_pattern match (wrong suffix length)
-        assert!(!result
-            .matches
-            .iter()
-            .any(|m| m.filter_type == "ssid_pattern"));
-        // But keyword "flock" still matches
-        assert!(result.matched);
+    fn service_uuids_32(&self) -> impl Iterator<Item = (u32, &str, u8, SignatureMeta)> {
+        self.0.service_uuids_32().chain(self.1.service_uuids_32())
     }
 
-    #[test]
-    fn wifi_ssid_exact_fs_ext_battery_matches() {
-        let config = default_config();
-        let input = WiFiScanInput {
-            mac: &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
-            ssid: "FS Ext Battery",
-            rssi: -40,
-        };
-        let result = filter_wifi(&input, &config);
-        assert!(result.matched);
-        assert!(result.matches.iter().any(|m| m.filter_type == "ssid_exact"));
+    fn service_uuids_128(&self) -> impl Iterator<Item = (u128, &str, u8, SignatureMeta)> {
+        self.0.service_uuids_128().chain(self.1.service_uuids_128())
     }
 
-    #[test]
-    fn wifi_ssid_keyword_case_insensitive() {
-        let config = default_config();
-        let input = WiFiScanInput {
-            mac: &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
-            ssid: "MyFLOCKNetwork",
-            rssi: -40,
-        };
-        let result = filter_wifi(&input, &config);
-        assert!(result.matched);
-        assert!(result
-            .matches
-            .iter()
-            .any(|m| m.filter_type == "ssid_keyword"));
+    fn ad_patterns(&self) -> impl Iterator<Item = AdPatternRef<'_>> {
+        self.0.ad_patterns().chain(self.1.ad_patterns())
     }
 
-    #[test]
-    fn wifi_no_match_for_innocent_network() {
-        let config = default_config();
-        let input = WiFiScanInput {
-            mac: &[0xAA, 0xBB, 0xCC, 0x01, 0x02, 0x03],
-            ssid: "Linksys-Home",
-            rssi: -50,
-        };
-        let result = filter_wifi(&input, &config);
-        assert!(!result.matched);
-        assert!(result.matches.is_empty());
+    fn ie_fingerprints(&self) -> impl Iterator<Item = AdPatternRef<'_>> {
+        self.0.ie_fingerprints().chain(self.1.ie_fingerprints())
     }
 
-    #[test]
-    fn wifi_rssi_below_threshold_no_match() {
-        let config = FilterConfig {
-            min_rssi: -70,
-            ..default_config()
-        };
-        let input = WiFiScanInput {
-            mac: &[0xB4, 0x1E, 0x52, 0x01, 0x02, 0x03], // Known Flock Safety OUI
-            ssid: "Flock-A1B2C3",
-            rssi: -80, // Below -70 threshold
-        };
-        let result = filter_wifi(&input, &config);
-        assert!(!result.matched);
+    fn ad_fingerprints(&self) -> impl Iterator<Item = AdPatternRef<'_>> {
+        self.0.ad_fingerprints().chain(self.1.ad_fingerprints())
     }
 
-    #[test]
-    fn wifi_disabled_no_match() {
-        let config = FilterConfig {
-            wifi_enabled: false,
-            ..default_config()
-        };
-        let input = WiFiScanInput {
-            mac: &[0xB4, 0x1E, 0x52, 0x01, 0x02, 0x03],
-            ssid: "Flock-A1B2C3",
-            rssi: -40,
-        };
-        let result = filter_wifi(&input, &config);
-        assert!(!result.matched);
+    fn vendor_ies(&self) -> impl Iterator<Item = VendorIePatternRef<'_>> {
+        self.0.vendor_ies().chain(self.1.vendor_ies())
     }
 
-    #[test]
-    fn wifi_multiple_match_reasons() {
-        let config = default_config();
-        // MAC matches Flock Safety AND SSID matches Flock pattern AND keyword
-        let input = WiFiScanInput {
-            mac: &[0xB4, 0x1E, 0x52, 0x01, 0x02, 0x03],
-            ssid: "Flock-A1B2C3",
-            rssi: -40,
-        };
-        let result = filter_wifi(&input, &config);
-        assert!(result.matched);
-        assert!(result.matches.len() >= 2);
+    fn wps(&self) -> impl Iterator<Item = (Option<WpsField>, StringSigRef<'_>)> {
+        self.0.wps().chain(self.1.wps())
+    }
+
+    fn networks(&self) -> impl Iterator<Item = (NetworkKind, StringSigRef<'_>)> {
+        self.0.networks().chain(self.1.networks())
+    }
+
+    fn high_traffic_aps(&self) -> Option<(&str, SignatureMeta)> {
+        self.0.high_traffic_aps().or(self.1.high_traffic_aps())
+    }
+
+    fn ssid_exclusions(&self) -> impl Iterator<Item = ExclusionRef<'_>> {
+        self.0.ssid_exclusions().chain(self.1.ssid_exclusions())
+    }
+
+    fn name_exclusions(&self) -> impl Iterator<Item = ExclusionRef<'_>> {
+        self.0.name_exclusions().chain(self.1.name_exclusions())
+    }
+}
+
+/// Traffic qualifying another match adds nothing to its rating
+const TRAFFIC_META: SignatureMeta =
+    SignatureMeta::new(Category::Other, Severity::Low, Confidence::Low);
+
+/// Thread commissioning says a smart-home device is being set up, not which
+const THREAD_META: SignatureMeta =
+    SignatureMeta::new(Category::Other, Severity::Low, Confidence::Low);
+
+/// SSID heuristics flag oddities, not a known device
+const ANOMALY_META: SignatureMeta =
+    SignatureMeta::new(Category::Other, Severity::Low, Confidence::Low);
+
+/// Two transmitters sharing a BSSID or address is rarely innocent
+const CONFLICT_META: SignatureMeta =
+    SignatureMeta::new(Category::Other, Severity::Medium, Confidence::Medium);
+
+/// FTM ranging locates a station to within metres — worth a look, though
+/// phones also range to access points for indoor positioning
+const RANGING_META: SignatureMeta =
+    SignatureMeta::new(Category::Other, Severity::Medium, Confidence::Low);
+
+/// Overlay used by [`filter_wifi`]/[`filter_ble`] — defaults only.
+static EMPTY_OVERLAY: SignatureOverlay = SignatureOverlay::new();
+
+/// A scan result on its way through a [`Pipeline`], whichever the radio
+#[derive(Clone, Copy)]
+pub enum ScanInput<'a> {
+    WiFi(&'a WiFiScanInput<'a>),
+    Ble(&'a BleScanInput<'a>),
+    BtClassic(&'a BtClassicScanInput<'a>),
+}
+
+impl ScanInput<'_> {
+    pub fn mac(&self) -> &[u8; 6] {
+        match self {
+            ScanInput::WiFi(input) => input.mac,
+            ScanInput::Ble(input) => input.mac,
+            ScanInput::BtClassic(input) => input.mac,
+        }
+    }
+
+    pub fn rssi(&self) -> i8 {
+        match self {
+            ScanInput::WiFi(input) => input.rssi,
+            ScanInput::Ble(input) => input.rssi,
+            ScanInput::BtClassic(input) => input.rssi,
+        }
+    }
+}
+
+/// Whether a [`Pipeline`] runs the stages after this one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    Continue,
+    /// The result is final, e.g. rejected before matching
+    Stop,
+}
+
+/// One step of a [`Pipeline`]: inspects the input and adds, drops or
+/// re-rates matches in the result.
+///
+/// The built-in stages are [`RssiGate`] → [`SelfFilter`] →
+/// [`SignatureMatch`] → [`SsidHeuristics`] → [`TimingHeuristics`] →
+/// [`SequenceHeuristics`] → [`Rules`] → [`Allowlist`]
+/// ([`Pipeline::standard`]). Host consumers — the Linux daemon, a Kismet
+/// companion — can add their own, e.g. a stage matching against an external
+/// database, without touching the filter module.
+/// Tuples of stages run in order and stop at the first [`Flow::Stop`].
+pub trait FilterStage {
+    fn apply<const N: usize>(
+        &self,
+        input: &ScanInput<'_>,
+        config: &FilterConfig,
+        result: &mut FilterResult<N>,
+    ) -> Flow;
+}
+
+impl<T: FilterStage> FilterStage for &T {
+    fn apply<const N: usize>(
+        &self,
+        input: &ScanInput<'_>,
+        config: &FilterConfig,
+        result: &mut FilterResult<N>,
+    ) -> Flow {
+        (**self).apply(input, config, result)
+    }
+}
+
+macro_rules! stage_tuple {
+    ($($stage:ident),+) => {
+        impl<$($stage: FilterStage),+> FilterStage for ($($stage,)+) {
+            #[allow(non_snake_case)]
+            fn apply<const N: usize>(
+                &self,
+                input: &ScanInput<'_>,
+                config: &FilterConfig,
+                result: &mut FilterResult<N>,
+            ) -> Flow {
+                let ($($stage,)+) = self;
+                $(
+                    if $stage.apply(input, config, result) == Flow::Stop {
+                        return Flow::Stop;
+                    }
+                )+
+                Flow::Continue
+            }
+        }
+    };
+}
+
+stage_tuple!(A, B);
+stage_tuple!(A, B, C);
+stage_tuple!(A, B, C, D);
+stage_tuple!(A, B, C, D, E);
+stage_tuple!(A, B, C, D, E, F);
+stage_tuple!(A, B, C, D, E, F, G);
+stage_tuple!(A, B, C, D, E, F, G, H);
+
+/// Drops results from a disabled radio, weaker than
+/// [`FilterConfig::min_rssi`] (setting [`FilterResult::rssi_rejected`]), or
+/// heard on a WiFi channel outside [`FilterConfig::channels`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RssiGate;
+
+impl FilterStage for RssiGate {
+    fn apply<const N: usize>(
+        &self,
+        input: &ScanInput<'_>,
+        config: &FilterConfig,
+        result: &mut FilterResult<N>,
+    ) -> Flow {
+        let enabled = match input {
+            ScanInput::WiFi(_) => config.wifi_active(),
+            // Classic shares the BLE enable switch
+            ScanInput::Ble(_) | ScanInput::BtClassic(_) => config.ble_enabled,
+        };
+        if !enabled {
+            return Flow::Stop;
+        }
+
+        if input.rssi() < config.min_rssi {
+            result.rssi_rejected = true;
+            return Flow::Stop;
+        }
+
+        // Frames from a filtered-out channel (heard on the way past it, or
+        // before the hop task picked up a new filter) aren't reported
+        if let ScanInput::WiFi(wifi) = input {
+            if !config.channels.allows(wifi.channel) {
+                return Flow::Stop;
+            }
+        }
+        Flow::Continue
+    }
+}
+
+/// Drops BLE results from this unit's own advertisement and from other
+/// AirHound units — one in the same car shows up in every scan — unless
+/// [`FilterConfig::report_self`] is set. Units are recognized by the shared
+/// advertising address ([`comm::BLE_ADDRESS`]) or the AirHound GATT service
+/// in their scan response.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SelfFilter;
+
+impl FilterStage for SelfFilter {
+    fn apply<const N: usize>(
+        &self,
+        input: &ScanInput<'_>,
+        config: &FilterConfig,
+        _result: &mut FilterResult<N>,
+    ) -> Flow {
+        let ScanInput::Ble(ble) = input else {
+            return Flow::Continue;
+        };
+        let airhound = *ble.mac == comm::BLE_ADDRESS
+            || ble
+                .service_uuids_128
+                .contains(&comm::ble_uuids::SERVICE_128);
+        if airhound && !config.report_self {
+            return Flow::Stop;
+        }
+        Flow::Continue
+    }
+}
+
+/// Matches the compiled-in packs plus runtime signatures from `S`, then
+/// applies the exclusions.
+pub struct SignatureMatch<'s, S>(pub &'s S);
+
+impl<S: SignatureSource> FilterStage for SignatureMatch<'_, S> {
+    fn apply<const N: usize>(
+        &self,
+        input: &ScanInput<'_>,
+        _config: &FilterConfig,
+        result: &mut FilterResult<N>,
+    ) -> Flow {
+        match input {
+            ScanInput::WiFi(wifi) => match_wifi(wifi, self.0, result),
+            ScanInput::Ble(ble) => match_ble(ble, self.0, result),
+            ScanInput::BtClassic(classic) => match_bt_classic(classic, self.0, result),
+        }
+        Flow::Continue
+    }
+}
+
+/// Adds a low-severity `ssid_anomaly` match for a WiFi SSID that looks
+/// random, imitates a common network, or hides behind unusual capability
+/// bits ([`anomaly::check_ssid`]), when [`FilterConfig::ssid_heuristics`] is
+/// set.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SsidHeuristics;
+
+impl FilterStage for SsidHeuristics {
+    fn apply<const N: usize>(
+        &self,
+        input: &ScanInput<'_>,
+        config: &FilterConfig,
+        result: &mut FilterResult<N>,
+    ) -> Flow {
+        let ScanInput::WiFi(wifi) = input else {
+            return Flow::Continue;
+        };
+        if !config.ssid_heuristics {
+            return Flow::Continue;
+        }
+        match anomaly::check_ssid(wifi.ssid, wifi.capability) {
+            Some(SsidAnomaly::RandomString) => {
+                result.add_match("ssid_anomaly", "random-looking SSID", ANOMALY_META)
+            }
+            Some(SsidAnomaly::Lookalike(name)) => {
+                use core::fmt::Write;
+                let mut detail = heapless::String::<48>::new();
+                let _ = write!(detail, "lookalike of {name}");
+                result.add_match("ssid_anomaly", &detail, ANOMALY_META);
+            }
+            Some(SsidAnomaly::HiddenCapability) => result.add_match(
+                "ssid_anomaly",
+                "hidden SSID, odd capabilities",
+                ANOMALY_META,
+            ),
+            None => {}
+        }
+        Flow::Continue
+    }
+}
+
+/// Adds a `beacon_timing` match for a beacon whose BSSID's timing is off
+/// ([`WiFiScanInput::timing`]) when [`FilterConfig::timing_heuristics`] is
+/// set: low severity for jitter and TSF resets, medium for a BSSID conflict.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TimingHeuristics;
+
+impl FilterStage for TimingHeuristics {
+    fn apply<const N: usize>(
+        &self,
+        input: &ScanInput<'_>,
+        config: &FilterConfig,
+        result: &mut FilterResult<N>,
+    ) -> Flow {
+        let ScanInput::WiFi(wifi) = input else {
+            return Flow::Continue;
+        };
+        if !config.timing_heuristics {
+            return Flow::Continue;
+        }
+        if let Some(anomaly) = wifi.timing {
+            let meta = match anomaly {
+                TimingAnomaly::BssidConflict => CONFLICT_META,
+                _ => ANOMALY_META,
+            };
+            result.add_match("beacon_timing", anomaly.as_str(), meta);
+        }
+        Flow::Continue
+    }
+}
+
+/// Adds a `seq_anomaly` match for a frame whose transmitter's sequence
+/// numbers jump ([`WiFiScanInput::sequence`]) when
+/// [`FilterConfig::sequence_heuristics`] is set: low severity for a jump,
+/// medium for two interleaved counters.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SequenceHeuristics;
+
+impl FilterStage for SequenceHeuristics {
+    fn apply<const N: usize>(
+        &self,
+        input: &ScanInput<'_>,
+        config: &FilterConfig,
+        result: &mut FilterResult<N>,
+    ) -> Flow {
+        let ScanInput::WiFi(wifi) = input else {
+            return Flow::Continue;
+        };
+        if !config.sequence_heuristics {
+            return Flow::Continue;
+        }
+        if let Some(anomaly) = wifi.sequence {
+            let meta = match anomaly {
+                SequenceAnomaly::Interleaved => CONFLICT_META,
+                SequenceAnomaly::Jump => ANOMALY_META,
+            };
+            result.add_match("seq_anomaly", anomaly.as_str(), meta);
+        }
+        Flow::Continue
+    }
+}
+
+/// Evaluates the packs' detection rules over the matched compiled-in
+/// signatures.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Rules;
+
+impl FilterStage for Rules {
+    fn apply<const N: usize>(
+        &self,
+        _input: &ScanInput<'_>,
+        _config: &FilterConfig,
+        result: &mut FilterResult<N>,
+    ) -> Flow {
+        result.apply_rules();
+        Flow::Continue
+    }
+}
+
+/// Clears the result for devices known to be harmless, by address.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Allowlist<'a> {
+    macs: &'a [[u8; 6]],
+}
+
+impl<'a> Allowlist<'a> {
+    pub const EMPTY: Allowlist<'static> = Allowlist { macs: &[] };
+
+    pub const fn new(macs: &'a [[u8; 6]]) -> Self {
+        Self { macs }
+    }
+}
+
+impl FilterStage for Allowlist<'_> {
+    fn apply<const N: usize>(
+        &self,
+        input: &ScanInput<'_>,
+        _config: &FilterConfig,
+        result: &mut FilterResult<N>,
+    ) -> Flow {
+        if !self.macs.contains(input.mac()) {
+            return Flow::Continue;
+        }
+        result.exclude(None);
+        result.rules.clear();
+        Flow::Stop
+    }
+}
+
+/// A sequence of [`FilterStage`]s run over each scan result.
+pub struct Pipeline<S> {
+    stages: S,
+}
+
+/// Stages of [`Pipeline::standard`]
+pub type StandardStages<'s, S> = (
+    RssiGate,
+    SelfFilter,
+    SignatureMatch<'s, S>,
+    SsidHeuristics,
+    TimingHeuristics,
+    SequenceHeuristics,
+    Rules,
+    Allowlist<'static>,
+);
+
+impl<'s, S: SignatureSource> Pipeline<StandardStages<'s, S>> {
+    /// The stages `filter_wifi()`/`filter_ble()`/`filter_bt_classic()` run,
+    /// with runtime signatures from `sigs`.
+    pub fn standard(sigs: &'s S) -> Self {
+        Self::new((
+            RssiGate,
+            SelfFilter,
+            SignatureMatch(sigs),
+            SsidHeuristics,
+            TimingHeuristics,
+            SequenceHeuristics,
+            Rules,
+            Allowlist::EMPTY,
+        ))
+    }
+}
+
+impl<S: FilterStage> Pipeline<S> {
+    pub const fn new(stages: S) -> Self {
+        Self { stages }
+    }
+
+    /// This pipeline followed by `stage`.
+    pub fn then<T: FilterStage>(self, stage: T) -> Pipeline<(S, T)> {
+        Pipeline::new((self.stages, stage))
+    }
+
+    /// Run every stage over `input`, stopping early if one says so.
+    pub fn run<const N: usize>(
+        &self,
+        input: &ScanInput<'_>,
+        config: &FilterConfig,
+    ) -> FilterResult<N> {
+        let mut result = FilterResult::new(config);
+        self.stages.apply(input, config, &mut result);
+        result
+    }
+}
+
+/// Evaluate a WiFi scan result against all configured filters.
+pub fn filter_wifi(input: &WiFiScanInput, config: &FilterConfig) -> FilterResult {
+    filter_wifi_with(input, config, &EMPTY_OVERLAY)
+}
+
+/// Evaluate a WiFi scan result against the defaults plus runtime `sigs` signatures.
+pub fn filter_wifi_with(
+    input: &WiFiScanInput,
+    config: &FilterConfig,
+    sigs: &impl SignatureSource,
+) -> FilterResult {
+    filter_wifi_sized(input, config, sigs)
+}
+
+/// [`filter_wifi_with`] with match details of capacity `N`.
+pub fn filter_wifi_sized<const N: usize>(
+    input: &WiFiScanInput,
+    config: &FilterConfig,
+    sigs: &impl SignatureSource,
+) -> FilterResult<N> {
+    Pipeline::standard(sigs).run(&ScanInput::WiFi(input), config)
+}
+
+/// Match a WiFi result against the defaults plus `sigs`, then drop what
+/// the exclusions cancel.
+fn match_wifi<const N: usize>(
+    input: &WiFiScanInput,
+    sigs: &impl SignatureSource,
+    result: &mut FilterResult<N>,
+) {
+    // MAC OUI prefix check
+    check_mac_oui(input.mac, input.mac_randomized, sigs, result);
+
+    // Randomized addresses: match on what the device advertises instead
+    if input.mac_randomized {
+        check_patterns(
+            input.ies,
+            sigs.ie_fingerprints().map(|pattern| (None, pattern)),
+            "fingerprint",
+            result,
+        );
+    }
+
+    // SSID glob pattern check (e.g., Flock-XXXXXX)
+    for (sig, pattern) in compiled(SigTable::SsidPatterns, |pack| pack.ssid_patterns) {
+        if pattern.matches(input.ssid) {
+            result.add_sig_match(sig, "ssid_pattern", pattern.description, 1, pattern.meta);
+        }
+    }
+
+    // SSID exact match check
+    for (sig, &(exact, meta)) in compiled(SigTable::SsidExact, |pack| pack.ssid_exact) {
+        if input.ssid == exact {
+            result.add_sig_match(sig, "ssid_exact", exact, 1, meta);
+        }
+    }
+
+    // SSID keyword substring check (case-insensitive)
+    let ssid_lower: Vec<u8, 33> = input
+        .ssid
+        .bytes()
+        .take(33)
+        .map(|b| b.to_ascii_lowercase())
+        .collect();
+    let ssid_lower_str = core::str::from_utf8(&ssid_lower).unwrap_or("");
+
+    for (sig, &(keyword, meta)) in compiled(SigTable::SsidKeywords, |pack| pack.ssid_keywords) {
+        if ssid_lower_str.contains(keyword) {
+            result.add_sig_match(sig, "ssid_keyword", keyword, 1, meta);
+        }
+    }
+
+    for pack in PACKS {
+        // WiFi name keyword check (from FlockOff — matches partial names)
+        for &(keyword, meta) in pack.wifi_name_keywords {
+            // Only add if not already matched as an SSID keyword
+            if ssid_lower_str.contains(keyword)
+                && !pack.ssid_keywords.iter().any(|&(k, _)| k == keyword)
+            {
+                result.add_match("wifi_name", keyword, meta);
+            }
+        }
+    }
+
+    // Runtime SSID signatures
+    for sig in sigs.ssids() {
+        if sig.matches(input.ssid) {
+            let filter_type = match sig.kind {
+                StringMatch::Exact => "ssid_exact",
+                StringMatch::Prefix | StringMatch::Glob => "ssid_pattern",
+                StringMatch::Contains => "ssid_keyword",
+            };
+            result.add_weak_match(filter_type, sig.description, sig.min_sightings, sig.meta);
+        }
+    }
+
+    // Vendor-specific information elements
+    let patterns = compiled(SigTable::WifiVendorIes, |pack| pack.wifi_vendor_ies);
+    check_vendor_ies(
+        input.vendor_ies,
+        patterns.map(|(sig, pattern)| (Some(sig), pattern.as_ref())),
+        result,
+    );
+    let patterns = sigs.vendor_ies().map(|pattern| (None, pattern));
+    check_vendor_ies(input.vendor_ies, patterns, result);
+
+    // WPS manufacturer, model and device name
+    if let Some(wps) = input.wps {
+        check_wps(wps, sigs, result);
+    }
+
+    // Mesh and WiFi Direct group networks, by kind and name
+    if let Some(network) = input.network {
+        for (sig, &(kind, keyword, description, meta)) in
+            compiled(SigTable::WifiNetworks, |pack| pack.wifi_networks)
+        {
+            if kind == network && ssid_lower_str.contains(keyword) {
+                result.add_sig_match(sig, "wifi_network", description, 1, meta);
+            }
+        }
+        for (kind, sig) in sigs.networks() {
+            if kind == network && sig.matches(input.ssid) {
+                result.add_weak_match("wifi_network", sig.description, sig.min_sightings, sig.meta);
+            }
+        }
+    }
+
+    // Fine Timing Measurement: someone nearby is ranging precisely
+    if input.action == Some(ActionCategory::FineTiming) {
+        result.add_match("ftm", "fine timing measurement", RANGING_META);
+    }
+
+    // Exclusions cancel matches before traffic can qualify them
+    check_exclusions(
+        input.ssid,
+        PACKS.iter().flat_map(|pack| pack.ssid_exclusions),
+        sigs.ssid_exclusions(),
+        result,
+    );
+
+    // Data-frame volume: qualifies existing matches, and on its own flags
+    // unknown APs when the signature file asks for it
+    match input.traffic {
+        Traffic::Normal => {}
+        _ if result.matched => result.add_match("traffic", "actively transmitting", TRAFFIC_META),
+        Traffic::AccessPoint => {
+            if let Some((description, meta)) = sigs.high_traffic_aps() {
+                result.add_match("traffic", description, meta);
+            }
+        }
+        Traffic::Station => {}
+    }
+}
+
+/// Evaluate a BLE scan result against all configured filters.
+pub fn filter_ble(input: &BleScanInput, config: &FilterConfig) -> FilterResult {
+    filter_ble_with(input, config, &EMPTY_OVERLAY)
+}
+
+/// Evaluate a BLE scan result against the defaults plus runtime `sigs` signatures.
+pub fn filter_ble_with(
+    input: &BleScanInput,
+    config: &FilterConfig,
+    sigs: &impl SignatureSource,
+) -> FilterResult {
+    filter_ble_sized(input, config, sigs)
+}
+
+/// [`filter_ble_with`] with match details of capacity `N`.
+pub fn filter_ble_sized<const N: usize>(
+    input: &BleScanInput,
+    config: &FilterConfig,
+    sigs: &impl SignatureSource,
+) -> FilterResult<N> {
+    Pipeline::standard(sigs).run(&ScanInput::Ble(input), config)
+}
+
+/// Match a BLE result against the defaults plus `sigs`, then drop what the
+/// exclusions cancel.
+fn match_ble<const N: usize>(
+    input: &BleScanInput,
+    sigs: &impl SignatureSource,
+    result: &mut FilterResult<N>,
+) {
+    // MAC OUI prefix check
+    check_mac_oui(input.mac, input.mac_randomized, sigs, result);
+
+    // Random addresses: match on what the device advertises instead
+    if input.mac_randomized {
+        check_patterns(
+            input.ad_data,
+            sigs.ad_fingerprints().map(|pattern| (None, pattern)),
+            "fingerprint",
+            result,
+        );
+    }
+
+    // BLE device name pattern check (case-insensitive substring)
+    check_name(input.name, sigs, "ble_name", result);
+
+    // BLE service UUID check (16-bit)
+    for &uuid in input.service_uuids_16 {
+        let custom = compiled(SigTable::BleServiceUuids16, |pack| {
+            pack.ble_service_uuids_16
+        });
+        for (sig, &(sig_uuid, description, meta)) in custom {
+            if sig_uuid == uuid {
+                result.add_sig_match(sig, "ble_uuid", description, 1, meta);
+            }
+        }
+        let standard = compiled(SigTable::BleStandardUuids16, |pack| {
+            pack.ble_standard_uuids_16
+        });
+        for (sig, &(sig_uuid, description, meta)) in standard {
+            if sig_uuid == uuid {
+                result.add_sig_match(sig, "ble_uuid_std", description, 1, meta);
+            }
+        }
+        for (sig_uuid, description, min_sightings, meta) in sigs.service_uuids_16() {
+            if sig_uuid == uuid {
+                result.add_weak_match("ble_uuid", description, min_sightings, meta);
+            }
+        }
+    }
+
+    // BLE service UUID check (32-bit)
+    for &uuid in input.service_uuids_32 {
+        let table = compiled(SigTable::BleServiceUuids32, |pack| {
+            pack.ble_service_uuids_32
+        });
+        for (sig, &(sig_uuid, description, meta)) in table {
+            if sig_uuid == uuid {
+                result.add_sig_match(sig, "ble_uuid", description, 1, meta);
+            }
+        }
+        for (sig_uuid, description, min_sightings, meta) in sigs.service_uuids_32() {
+            if sig_uuid == uuid {
+                result.add_weak_match("ble_uuid", description, min_sightings, meta);
+            }
+        }
+    }
+
+    // BLE service UUID check (128-bit)
+    for &uuid in input.service_uuids_128 {
+        let table = compiled(SigTable::BleServiceUuids128, |pack| {
+            pack.ble_service_uuids_128
+        });
+        for (sig, &(sig_uuid, description, meta)) in table {
+            if sig_uuid == uuid {
+                result.add_sig_match(sig, "ble_uuid", description, 1, meta);
+            }
+        }
+        for (sig_uuid, description, min_sightings, meta) in sigs.service_uuids_128() {
+            if sig_uuid == uuid {
+                result.add_weak_match("ble_uuid", description, min_sightings, meta);
+            }
+        }
+    }
+
+    // BLE manufacturer ID check
+    check_manufacturer(input.manufacturer_id, sigs, "ble_mfr", result);
+
+    // Raw advertisement byte patterns
+    let patterns = compiled(SigTable::BleAdPatterns, |pack| pack.ble_ad_patterns);
+    check_patterns(
+        input.ad_data,
+        patterns.map(|(sig, pattern)| (Some(sig), pattern.as_ref())),
+        "ble_ad",
+        result,
+    );
+    let patterns = sigs.ad_patterns().map(|pattern| (None, pattern));
+    check_patterns(input.ad_data, patterns, "ble_ad", result);
+
+    // Matter commissioning vendor check
+    if let Some(matter) = input.matter {
+        for &(vendor_id, description, meta) in PACKS.iter().flat_map(|pack| pack.matter_vendor_ids)
+        {
+            if matter.vendor_id == vendor_id {
+                result.add_match("matter", description, meta);
+            }
+        }
+    }
+
+    // iBeacon proximity UUID check
+    if let Some(ibeacon) = input.ibeacon {
+        for &(uuid, description, meta) in PACKS.iter().flat_map(|pack| pack.ibeacon_uuids) {
+            if ibeacon.uuid == uuid {
+                result.add_match("ibeacon", description, meta);
+            }
+        }
+    }
+
+    // Eddystone-UID namespace check
+    if let Some(namespace) = input.eddystone.and_then(Eddystone::namespace) {
+        for (sig_namespace, description, meta) in
+            PACKS.iter().flat_map(|pack| pack.eddystone_namespaces)
+        {
+            if namespace == sig_namespace {
+                result.add_match("eddystone", description, *meta);
+            }
+        }
+    }
+
+    // Thread devices being commissioned over BLE
+    if input.thread_commissioning {
+        result.add_match("thread", "Thread commissioning (TCAT)", THREAD_META);
+    }
+
+    check_exclusions(
+        input.name,
+        PACKS.iter().flat_map(|pack| pack.name_exclusions),
+        sigs.name_exclusions(),
+        result,
+    );
+}
+
+/// Evaluate a Bluetooth Classic inquiry result against all configured filters.
+pub fn filter_bt_classic(input: &BtClassicScanInput, config: &FilterConfig) -> FilterResult {
+    filter_bt_classic_with(input, config, &EMPTY_OVERLAY)
+}
+
+/// Evaluate a Bluetooth Classic inquiry result against the defaults plus
+/// runtime `sigs` signatures. Classic shares the BLE enable switch and name
+/// patterns — devices tend to use the same name on both transports.
+pub fn filter_bt_classic_with(
+    input: &BtClassicScanInput,
+    config: &FilterConfig,
+    sigs: &impl SignatureSource,
+) -> FilterResult {
+    filter_bt_classic_sized(input, config, sigs)
+}
+
+/// [`filter_bt_classic_with`] with match details of capacity `N`.
+pub fn filter_bt_classic_sized<const N: usize>(
+    input: &BtClassicScanInput,
+    config: &FilterConfig,
+    sigs: &impl SignatureSource,
+) -> FilterResult<N> {
+    Pipeline::standard(sigs).run(&ScanInput::BtClassic(input), config)
+}
+
+/// Match a Classic result against the defaults plus `sigs`, then drop what
+/// the exclusions cancel.
+fn match_bt_classic<const N: usize>(
+    input: &BtClassicScanInput,
+    sigs: &impl SignatureSource,
+    result: &mut FilterResult<N>,
+) {
+    // MAC OUI prefix check — BR/EDR addresses are never randomized
+    check_mac_oui(input.mac, false, sigs, result);
+
+    // Device name pattern check
+    check_name(input.name, sigs, "bt_name", result);
+
+    // Class of Device check
+    for &(mask, value, description, meta) in PACKS.iter().flat_map(|pack| pack.bt_classic_cod) {
+        if input.class_of_device & mask == value {
+            result.add_match("bt_cod", description, meta);
+        }
+    }
+
+    // Manufacturer from extended inquiry response data
+    check_manufacturer(input.manufacturer_id, sigs, "bt_mfr", result);
+
+    check_exclusions(
+        input.name,
+        PACKS.iter().flat_map(|pack| pack.name_exclusions),
+        sigs.name_exclusions(),
+        result,
+    );
+}
+
+/// Match a manufacturer company ID (0 for none) against the BLE
+/// manufacturer ID signatures, which Classic EIR data shares.
+fn check_manufacturer<const N: usize>(
+    manufacturer_id: u16,
+    sigs: &impl SignatureSource,
+    filter_type: &'static str,
+    result: &mut FilterResult<N>,
+) {
+    if manufacturer_id == 0 {
+        return;
+    }
+    let table = compiled(SigTable::BleManufacturerIds, |pack| {
+        pack.ble_manufacturer_ids
+    });
+    for (sig, &(id, description, meta)) in table {
+        if id == manufacturer_id {
+            result.add_sig_match(sig, filter_type, description, 1, meta);
+        }
+    }
+    for (id, description, min_sightings, meta) in sigs.manufacturer_ids() {
+        if id == manufacturer_id {
+            result.add_weak_match(filter_type, description, min_sightings, meta);
+        }
+    }
+}
+
+/// Drop matches cancelled by exclusions keyed on `text` (an SSID or device
+/// name). Compiled-in exclusions are lowercase keywords matched
+/// case-insensitively.
+fn check_exclusions<'a, const N: usize>(
+    text: &str,
+    compiled: impl Iterator<Item = &'a (&'static str, Option<&'static str>)>,
+    runtime: impl Iterator<Item = ExclusionRef<'a>>,
+    result: &mut FilterResult<N>,
+) {
+    if !result.matched || text.is_empty() {
+        return;
+    }
+
+    let text_lower: Vec<u8, 33> = text
+        .bytes()
+        .take(33)
+        .map(|b| b.to_ascii_lowercase())
+        .collect();
+    let text_lower_str = core::str::from_utf8(&text_lower).unwrap_or("");
+
+    for &(keyword, excludes) in compiled {
+        if text_lower_str.contains(keyword) {
+            result.exclude(excludes);
+        }
+    }
+    for exclusion in runtime {
+        if exclusion.pattern.matches(text) {
+            result.exclude(exclusion.excludes);
+        }
+    }
+}
+
+/// Check a device name against BLE name patterns (case-insensitive substring)
+/// and runtime name signatures.
+fn check_name<const N: usize>(
+    name: &str,
+    sigs: &impl SignatureSource,
+    filter_type: &'static str,
+    result: &mut FilterResult<N>,
+) {
+    if name.is_empty() {
+        return;
+    }
+
+    let name_lower: Vec<u8, 33> = name
+        .bytes()
+        .take(33)
+        .map(|b| b.to_ascii_lowercase())
+        .collect();
+    let name_lower_str = core::str::from_utf8(&name_lower).unwrap_or("");
+
+    for (sig, &(pattern, meta)) in
+        compiled(SigTable::BleNamePatterns, |pack| pack.ble_name_patterns)
+    {
+        let pattern_lower: Vec<u8, 33> = pattern
+            .bytes()
+            .take(33)
+            .map(|b| b.to_ascii_lowercase())
+            .collect();
+        let pattern_lower_str = core::str::from_utf8(&pattern_lower).unwrap_or("");
+
+        if name_lower_str.contains(pattern_lower_str) {
+            result.add_sig_match(sig, filter_type, pattern, 1, meta);
+        }
+    }
+
+    for sig in sigs.ble_names() {
+        if sig.matches(name) {
+            result.add_weak_match(filter_type, sig.description, sig.min_sightings, sig.meta);
+        }
+    }
+}
+
+/// Whether `mac` has the locally-administered bit set. Phones and many
+/// surveillance devices randomize their WiFi address this way, so its OUI
+/// names no vendor.
+pub fn is_locally_administered(mac: &[u8; 6]) -> bool {
+    mac[0] & 0x02 != 0
+}
+
+/// Check MAC address against known OUI prefixes (defaults first, then `sigs`).
+/// A randomized address carries no vendor OUI, so it only matches prefixes
+/// that are themselves locally administered (e.g. Pwnagotchi's `DE:AD:BE`).
+fn check_mac_oui<const N: usize>(
+    mac: &[u8; 6],
+    randomized: bool,
+    sigs: &impl SignatureSource,
+    result: &mut FilterResult<N>,
+) {
+    if randomized && !is_locally_administered(mac) {
+        return;
+    }
+    let oui = [mac[0], mac[1], mac[2]];
+    for (sig, &(ref prefix, vendor, meta)) in
+        compiled(SigTable::MacPrefixes, |pack| pack.mac_prefixes)
+    {
+        if oui == *prefix {
+            let min_sightings = PACKS[sig.pack as usize]
+                .mac_prefix_min_sightings
+                .iter()
+                .find(|(weak, _)| *weak == vendor)
+                .map_or(1, |&(_, min)| min);
+            result.add_sig_match(sig, "mac_oui", vendor, min_sightings, meta);
+            return; // Only report first match (a MAC can only match one OUI)
+        }
+    }
+    for (prefix, vendor, min_sightings, meta) in sigs.mac_prefixes() {
+        if oui == prefix {
+            result.add_weak_match("mac_oui", vendor, min_sightings, meta);
+            return;
+        }
+    }
+}
+
+/// Check an advertisement or a randomized device's IEs against byte
+/// patterns.
+fn check_patterns<'a, const N: usize>(
+    payload: &[u8],
+    patterns: impl Iterator<Item = (Option<SigHit>, AdPatternRef<'a>)>,
+    filter_type: &'static str,
+    result: &mut FilterResult<N>,
+) {
+    for (sig, pattern) in patterns {
+        if pattern.matches(payload) {
+            if let Some(sig) = sig {
+                result.hit(sig, pattern.meta);
+            }
+            result.add_weak_match(
+                filter_type,
+                pattern.description,
+                pattern.min_sightings,
+                pattern.meta,
+            );
+        }
+    }
+}
+
+/// Check a beacon or probe's vendor elements against vendor IE patterns.
+fn check_vendor_ies<'a, const N: usize>(
+    vendor_ies: &[VendorIe],
+    patterns: impl Iterator<Item = (Option<SigHit>, VendorIePatternRef<'a>)>,
+    result: &mut FilterResult<N>,
+) {
+    for (sig, pattern) in patterns {
+        if vendor_ies.iter().any(|ie| pattern.matches(ie)) {
+            if let Some(sig) = sig {
+                result.hit(sig, pattern.meta);
+            }
+            result.add_weak_match(
+                "wifi_vendor_ie",
+                pattern.description,
+                pattern.min_sightings,
+                pattern.meta,
+            );
+        }
+    }
+}
+
+/// Check a WPS identity against WPS keywords (case-insensitive substring)
+/// and runtime WPS signatures. Each signature is reported once, however
+/// many fields it matches.
+fn check_wps<const N: usize>(
+    wps: &WpsInfo,
+    sigs: &impl SignatureSource,
+    result: &mut FilterResult<N>,
+) {
+    let fields = WpsInfo::FIELDS.map(|field| {
+        let lower: Vec<u8, MAX_WPS_TEXT> = wps
+            .field(field)
+            .bytes()
+            .map(|b| b.to_ascii_lowercase())
+            .collect();
+        (field, lower)
+    });
+    let selected = |only: Option<WpsField>| {
+        fields
+            .iter()
+            .filter(move |(field, _)| only.is_none_or(|only| only == *field))
+    };
+
+    for (sig, &(only, keyword, description, meta)) in
+        compiled(SigTable::WpsKeywords, |pack| pack.wps_keywords)
+    {
+        let hit = selected(only)
+            .any(|(_, lower)| core::str::from_utf8(lower).is_ok_and(|text| text.contains(keyword)));
+        if hit {
+            result.add_sig_match(sig, "wps", description, 1, meta);
+        }
+    }
+
+    for (only, sig) in sigs.wps() {
+        if selected(only).any(|&(field, _)| sig.matches(wps.field(field))) {
+            result.add_weak_match("wps", sig.description, sig.min_sightings, sig.meta);
+        }
+    }
+}
+
+/// Compiled-in signatures [`FilterStats`] keeps hit counts for — as many as
+/// a `stats` message holds at full counts
+pub const STATS_SIGS: usize = 16;
+
+/// Filter counters since boot, for tuning signatures and spotting entries
+/// that never match. The filter itself keeps no state; the firmware
+/// [`record`](Self::record)s every result it gets.
+#[derive(Debug, Clone, Default)]
+pub struct FilterStats {
+    /// Scan results evaluated
+    pub evaluated: u32,
+    /// Results with at least one match
+    pub matched: u32,
+    /// Results dropped for being weaker than `FilterConfig::min_rssi`
+    pub rssi_rejected: u32,
+    /// Hits of signatures that found `sigs` full
+    pub untracked: u32,
+    /// `(pack, signature, hits)`: a pack's index in [`PACKS`] and the
+    /// [`SigIdx`] of one of its signatures, in order of first hit
+    sigs: Vec<(u8, SigIdx, u32), STATS_SIGS>,
+}
+
+impl FilterStats {
+    pub const fn new() -> Self {
+        Self {
+            evaluated: 0,
+            matched: 0,
+            rssi_rejected: 0,
+            untracked: 0,
+            sigs: Vec::new(),
+        }
+    }
+
+    /// Count one evaluation and the compiled-in signatures it matched.
+    pub fn record<const N: usize>(&mut self, result: &FilterResult<N>) {
+        self.evaluated = self.evaluated.wrapping_add(1);
+        if result.rssi_rejected {
+            self.rssi_rejected = self.rssi_rejected.wrapping_add(1);
+        }
+        if !result.matched {
+            return;
+        }
+        self.matched = self.matched.wrapping_add(1);
+        for hit in &result.hits {
+            let Some(sig) = PACKS.get(hit.pack as usize).and_then(|pack| {
+                let entry = (hit.table, hit.index);
+                pack.signatures.iter().position(|&sig| sig == entry)
+            }) else {
+                continue;
+            };
+            let sig = sig as SigIdx;
+            if let Some(count) = self
+                .sigs
+                .iter_mut()
+                .find(|(pack, idx, _)| *pack == hit.pack && *idx == sig)
+            {
+                count.2 = count.2.wrapping_add(1);
+            } else if self.sigs.push((hit.pack, sig, 1)).is_err() {
+                self.untracked = self.untracked.wrapping_add(1);
+            }
+        }
+    }
+
+    /// Hit counts per signature, as `(pack, signature, hits)`
+    pub fn sigs(&self) -> &[(u8, SigIdx, u32)] {
+        &self.sigs
+    }
+}
+
+/// `10^(k/20)` for `k` in 0..20, in thousandths
+const DB_STEPS_MILLI: [u32; 20] = [
+    1000, 1122, 1259, 1413, 1585, 1778, 1995, 2239, 2512, 2818, 3162, 3548, 3981, 4467, 5012, 5623,
+    6310, 7079, 7943, 8913,
+];
+
+/// Rough distance in meters to a device heard at `rssi`, given the RSSI it
+/// is expected to have at 1 m (`tx_power`, as in `BleEvent::tx_power`).
+///
+/// Uses the free-space log-distance model, `d = 10^((tx_power - rssi) / 20)`,
+/// rounded to 0.1 m and clamped to 0.1–1000 m. Walls and bodies attenuate
+/// more than free space, so indoors this overestimates; treat it as a
+/// ballpark, not a range.
+pub fn estimate_distance(rssi: i8, tx_power: i8) -> f32 {
+    let loss = (tx_power as i16 - rssi as i16).clamp(-20, 60);
+    // Offset by one decade (20 dB) so the result comes out in tenths of a
+    // meter, then split into whole decades and a remainder step
+    let (decades, step) = ((loss + 20) / 20, (loss + 20) % 20);
+    let milli = DB_STEPS_MILLI[step as usize] * 10u32.pow(decades as u32);
+    let tenths = ((milli + 500) / 1000).max(1);
+    tenths as f32 / 10.0
+}
+
+/// Format a 6-byte MAC address into "AA:BB:CC:DD:EE:FF" string
+pub fn format_mac(mac: &[u8; 6], buf: &mut crate::protocol::MacString) {
+    use core::fmt::Write;
+    let _ = write!(
+        buf,
+        "{:02X}:{:02X}:{:02X}:{:02X}:{:02X}:{:02X}",
+        mac[0], mac[1], mac[2], mac[3], mac[4], mac[5]
+    );
+}
+
+/// Parse an "AA:BB:CC:DD:EE:FF" string (either case) into MAC bytes
+pub fn parse_mac(s: &str) -> Option<[u8; 6]> {
+    let mut mac = [0u8; 6];
+    let mut parts = s.split(':');
+    for byte in mac.iter_mut() {
+        let part = parts.next()?;
+        if part.len() != 2 {
+            return None;
+        }
+        *byte = u8::from_str_radix(part, 16).ok()?;
+    }
+    parts.next().is_none().then_some(mac)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::defaults::DeviceClass;
+
+    fn default_config() -> FilterConfig {
+        FilterConfig::new()
+    }
+
+    // ── WiFi filter tests ───────────────────────────────────────────
+
+    #[test]
+    fn wifi_known_flock_safety_mac_matches() {
+        let config = default_config();
+        let input = WiFiScanInput {
+            mac: &[0xB4, 0x1E, 0x52, 0x01, 0x02, 0x03],
+            mac_randomized: false,
+            ssid: "SomeNetwork",
+            rssi: -50,
+            channel: 6,
+            traffic: Traffic::Normal,
+            ies: &[],
+            vendor_ies: &[],
+            wps: None,
+            capability: None,
+            timing: None,
+            sequence: None,
+            action: None,
+            network: None,
+        };
+        let result = filter_wifi(&input, &config);
+        assert!(result.matched);
+        assert!(result.matches.iter().any(|m| m.filter_type == "mac_oui"));
+    }
+
+    #[test]
+    fn wifi_silicon_labs_mac_matches() {
+        let config = default_config();
+        let input = WiFiScanInput {
+            mac: &[0x58, 0x8E, 0x81, 0xAA, 0xBB, 0xCC],
+            mac_randomized: false,
+            ssid: "",
+            rssi: -60,
+            channel: 6,
+            traffic: Traffic::Normal,
+            ies: &[],
+            vendor_ies: &[],
+            wps: None,
+            capability: None,
+            timing: None,
+            sequence: None,
+            action: None,
+            network: None,
+        };
+        let result = filter_wifi(&input, &config);
+        assert!(result.matched);
+        assert_eq!(result.matches[0].filter_type, "mac_oui");
+        assert!(result.matches[0].detail.contains("Silicon Labs"));
+    }
+
+    #[test]
+    fn wifi_ssid_pattern_flock_matches() {
+        let config = default_config();
+        let input = WiFiScanInput {
+            mac: &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+            mac_randomized: false,
+            ssid: "Flock-A1B2C3",
+            rssi: -40,
+            channel: 6,
+            traffic: Traffic::Normal,
+            ies: &[],
+            vendor_ies: &[],
+            wps: None,
+            capability: None,
+            timing: None,
+            sequence: None,
+            action: None,
+            network: None,
+        };
+        let result = filter_wifi(&input, &config);
+        assert!(result.matched);
+        assert!(result
+            .matches
+            .iter()
+            .any(|m| m.filter_type == "ssid_pattern"));
+    }
+
+    #[test]
+    fn wifi_direct_body_camera_matches() {
+        let config = default_config();
+        let input = WiFiScanInput {
+            mac: &[0x02, 0x00, 0x00, 0x00, 0x00, 0x01],
+            mac_randomized: true,
+            ssid: "DIRECT-7f-AXON Body 4",
+            rssi: -60,
+            channel: 6,
+            traffic: Traffic::Normal,
+            ies: &[],
+            vendor_ies: &[],
+            wps: None,
+            capability: None,
+            timing: None,
+            sequence: None,
+            action: None,
+            network: None,
+        };
+        let result = filter_wifi(&input, &config);
+        assert_eq!(result.matches[0].detail, "Axon body camera (WiFi Direct)");
+        assert_eq!(result.meta.device_class, DeviceClass::BodyCamera);
+        assert_eq!(result.rules[0].name, "Body Camera");
+        let alert = crate::rules::alert_action(&result.rules);
+        assert_eq!(alert.beeps, 2);
+        assert_eq!(alert.led, Some(crate::rules::LedColor::Blue));
+
+        for ssid in ["DIRECT-7f-Printer", "DIRECT-AXON", "AXON Body 4"] {
+            assert!(!filter_wifi(&WiFiScanInput { ssid, ..input }, &config).matched);
+        }
+    }
+
+    #[test]
+    fn mesh_and_p2p_group_networks_match() {
+        let config = default_config();
+        let input = WiFiScanInput {
+            mac: &[0x02, 0x00, 0x00, 0x00, 0x00, 0x01],
+            mac_randomized: true,
+            ssid: "DIRECT-AXON",
+            rssi: -60,
+            channel: 6,
+            traffic: Traffic::Normal,
+            ies: &[],
+            vendor_ies: &[],
+            wps: None,
+            capability: None,
+            timing: None,
+            sequence: None,
+            action: None,
+            network: Some(NetworkKind::P2pGroup),
+        };
+        // Owning a P2P group, the name needn't follow the DIRECT-xy- form
+        let result = filter_wifi(&input, &config);
+        assert_eq!(result.matches[0].filter_type, "wifi_network");
+        assert_eq!(result.matches[0].detail, "Axon body cam (P2P group owner)");
+        assert_eq!(result.rules[0].name, "Body Camera");
+        let printer = WiFiScanInput {
+            ssid: "DIRECT-7f-Printer",
+            ..input
+        };
+        assert!(!filter_wifi(&printer, &config).matched);
+
+        let mesh = WiFiScanInput {
+            ssid: "citymesh",
+            network: Some(NetworkKind::Mesh),
+            ..input
+        };
+        let result = filter_wifi(&mesh, &config);
+        assert_eq!(result.matches[0].detail, "802.11s mesh network");
+        assert_eq!(result.meta.severity, Severity::Low);
+    }
+
+    #[test]
+    fn wifi_ssid_pattern_penguin_matches() {
+        let config = default_config();
+        let input = WiFiScanInput {
+            mac: &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+            mac_randomized: false,
+            ssid: "Penguin-1234567890",
+            rssi: -40,
+            channel: 6,
+            traffic: Traffic::Normal,
+            ies: &[],
+            vendor_ies: &[],
+            wps: None,
+            capability: None,
+            timing: None,
+            sequence: None,
+            action: None,
+            network: None,
+        };
+        let result = filter_wifi(&input, &config);
+        assert!(result.matched);
+        assert!(result
+            .matches
+            .iter()
+            .any(|m| m.filter_type == "ssid_pattern"));
+    }
+
+    #[test]
+    fn wifi_ssid_pattern_flock_wrong_suffix_no_pattern_match() {
+        let config = default_config();
+        // Too short suffix — pattern should NOT match, but keyword "flock" still matches
+        let input = WiFiScanInput {
+            mac: &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+            mac_randomized: false,
+            ssid: "Flock-A1B",
+            rssi: -40,
+            channel: 6,
+            traffic: Traffic::Normal,
+            ies: &[],
+            vendor_ies: &[],
+            wps: None,
+            capability: None,
+            timing: None,
+            sequence: None,
+            action: None,
+            network: None,
+        };
+        let result = filter_wifi(&input, &config);
+        // No ssid_pattern match (wrong suffix length)
+        assert!(!result
+            .matches
+            .iter()
+            .any(|m| m.filter_type == "ssid_pattern"));
+        // But keyword "flock" still matches
+        assert!(result.matched);
+    }
+
+    #[test]
+    fn wifi_ssid_exact_fs_ext_battery_matches() {
+        let config = default_config();
+        let input = WiFiScanInput {
+            mac: &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+            mac_randomized: false,
+            ssid: "FS Ext Battery",
+            rssi: -40,
+            channel: 6,
+            traffic: Traffic::Normal,
+            ies: &[],
+            vendor_ies: &[],
+            wps: None,
+            capability: None,
+            timing: None,
+            sequence: None,
+            action: None,
+            network: None,
+        };
+        let result = filter_wifi(&input, &config);
+        assert!(result.matched);
+        assert!(result.matches.iter().any(|m| m.filter_type == "ssid_exact"));
+    }
+
+    #[test]
+    fn wifi_ssid_keyword_case_insensitive() {
+        let config = default_config();
+        let input = WiFiScanInput {
+            mac: &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+            mac_randomized: false,
+            ssid: "MyFLOCKNetwork",
+            rssi: -40,
+            channel: 6,
+            traffic: Traffic::Normal,
+            ies: &[],
+            vendor_ies: &[],
+            wps: None,
+            capability: None,
+            timing: None,
+            sequence: None,
+            action: None,
+            network: None,
+        };
+        let result = filter_wifi(&input, &config);
+        assert!(result.matched);
+        assert!(result
+            .matches
+            .iter()
+            .any(|m| m.filter_type == "ssid_keyword"));
+    }
+
+    #[test]
+    fn wifi_no_match_for_innocent_network() {
+        let config = default_config();
+        let input = WiFiScanInput {
+            mac: &[0xAA, 0xBB, 0xCC, 0x01, 0x02, 0x03],
+            mac_randomized: false,
+            ssid: "Linksys-Home",
+            rssi: -50,
+            channel: 6,
+            traffic: Traffic::Normal,
+            ies: &[],
+            vendor_ies: &[],
+            wps: None,
+            capability: None,
+            timing: None,
+            sequence: None,
+            action: None,
+            network: None,
+        };
+        let result = filter_wifi(&input, &config);
+        assert!(!result.matched);
+        assert!(result.matches.is_empty());
+    }
+
+    #[test]
+    fn wifi_rssi_below_threshold_no_match() {
+        let config = FilterConfig {
+            min_rssi: -70,
+            ..default_config()
+        };
+        let input = WiFiScanInput {
+            mac: &[0xB4, 0x1E, 0x52, 0x01, 0x02, 0x03], // Known Flock Safety OUI
+            mac_randomized: false,
+            ssid: "Flock-A1B2C3",
+            rssi: -80, // Below -70 threshold
+            channel: 6,
+            traffic: Traffic::Normal,
+            ies: &[],
+            vendor_ies: &[],
+            wps: None,
+            capability: None,
+            timing: None,
+            sequence: None,
+            action: None,
+            network: None,
+        };
+        let result = filter_wifi(&input, &config);
+        assert!(!result.matched);
+        assert!(result.rssi_rejected);
+    }
+
+    #[test]
+    fn wifi_filtered_channel_no_match() {
+        let config = FilterConfig {
+            channels: ChannelMask::from_channels(&[1, 6, 11]).unwrap(),
+            ..default_config()
+        };
+        let input = WiFiScanInput {
+            mac: &[0xB4, 0x1E, 0x52, 0x01, 0x02, 0x03],
+            mac_randomized: false,
+            ssid: "Flock-A1B2C3",
+            rssi: -40,
+            channel: 6,
+            traffic: Traffic::Normal,
+            ies: &[],
+            vendor_ies: &[],
+            wps: None,
+            capability: None,
+            timing: None,
+            sequence: None,
+            action: None,
+            network: None,
+        };
+        assert!(filter_wifi(&input, &config).matched);
+        let input = WiFiScanInput {
+            channel: 13,
+            ..input
+        };
+        assert!(!filter_wifi(&input, &config).matched);
+    }
+
+    #[test]
+    fn wifi_disabled_no_match() {
+        let config = FilterConfig {
+            wifi_enabled: false,
+            ..default_config()
+        };
+        let input = WiFiScanInput {
+            mac: &[0xB4, 0x1E, 0x52, 0x01, 0x02, 0x03],
+            mac_randomized: false,
+            ssid: "Flock-A1B2C3",
+            rssi: -40,
+            channel: 6,
+            traffic: Traffic::Normal,
+            ies: &[],
+            vendor_ies: &[],
+            wps: None,
+            capability: None,
+            timing: None,
+            sequence: None,
+            action: None,
+            network: None,
+        };
+        let result = filter_wifi(&input, &config);
+        assert!(!result.matched);
+    }
+
+    #[test]
+    fn wifi_multiple_match_reasons() {
+        let config = default_config();
+        // MAC matches Flock Safety AND SSID matches Flock pattern AND keyword
+        let input = WiFiScanInput {
+            mac: &[0xB4, 0x1E, 0x52, 0x01, 0x02, 0x03],
+            mac_randomized: false,
+            ssid: "Flock-A1B2C3",
+            rssi: -40,
+            channel: 6,
+            traffic: Traffic::Normal,
+            ies: &[],
+            vendor_ies: &[],
+            wps: None,
+            capability: None,
+            timing: None,
+            sequence: None,
+            action: None,
+            network: None,
+        };
+        let result = filter_wifi(&input, &config);
+        assert!(result.matched);
+        assert!(result.matches.len() >= 2);
+    }
+
+    #[test]
+    fn low_power_mode_disables_wifi_matches() {
+        let config = FilterConfig {
+            low_power: true,
+            ..default_config()
+        };
+        let input = WiFiScanInput {
+            mac: &[0xB4, 0x1E, 0x52, 0x01, 0x02, 0x03],
+            mac_randomized: false,
+            ssid: "Flock-A1B2C3",
+            rssi: -40,
+            channel: 6,
+            traffic: Traffic::Normal,
+            ies: &[],
+            vendor_ies: &[],
+            wps: None,
+            capability: None,
+            timing: None,
+            sequence: None,
+            action: None,
+            network: None,
+        };
+        assert!(!filter_wifi(&input, &config).matched);
+        assert_eq!(config.effective_ble_scan(), BleScanConfig::LOW_POWER);
+        assert_eq!(default_config().effective_ble_scan(), BleScanConfig::new());
+    }
+
+    #[test]
+    fn heavy_traffic_qualifies_existing_match() {
+        let input = WiFiScanInput {
+            mac: &[0xB4, 0x1E, 0x52, 0x01, 0x02, 0x03],
+            mac_randomized: false,
+            ssid: "",
+            rssi: -50,
+            channel: 6,
+            traffic: Traffic::Station,
+            ies: &[],
+            vendor_ies: &[],
+            wps: None,
+            capability: None,
+            timing: None,
+            sequence: None,
+            action: None,
+            network: None,
+        };
+        let result = filter_wifi(&input, &default_config());
+        assert_eq!(result.matches[0].filter_type, "mac_oui");
+        assert!(result
+            .matches
+            .iter()
+            .any(|m| m.filter_type == "traffic" && m.detail.as_str() == "actively transmitting"));
+    }
+
+    #[test]
+    fn heavy_traffic_alone_needs_signature() {
+        let input = WiFiScanInput {
+            mac: &[0x00; 6],
+            mac_randomized: false,
+            ssid: "",
+            rssi: -50,
+            channel: 6,
+            traffic: Traffic::AccessPoint,
+            ies: &[],
+            vendor_ies: &[],
+            wps: None,
+            capability: None,
+            timing: None,
+            sequence: None,
+            action: None,
+            network: None,
+        };
+        assert!(!filter_wifi(&input, &default_config()).matched);
+
+        let mut overlay = SignatureOverlay::new();
+        overlay.high_traffic_aps = Some((
+            crate::protocol::MatchDetail::try_from("high-traffic AP").unwrap(),
+            SignatureMeta::UNRATED,
+        ));
+        let result = filter_wifi_with(&input, &default_config(), &overlay);
+        assert_eq!(result.matches[0].filter_type, "traffic");
+        // Heavy client stations aren't flagged on volume alone
+        let station = WiFiScanInput {
+            traffic: Traffic::Station,
+            ..input
+        };
+        assert!(!filter_wifi_with(&station, &default_config(), &overlay).matched);
     }
 
     // ── BLE filter tests ────────────────────────────────────────────
@@ -412,138 +2230,1313 @@ mod tests {
         let config = default_config();
         let input = BleScanInput {
             mac: &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+            mac_randomized: false,
             name: "Flock Camera",
             rssi: -50,
             service_uuids_16: &[],
+            service_uuids_32: &[],
+            service_uuids_128: &[],
+            manufacturer_id: 0,
+            matter: None,
+            ibeacon: None,
+            eddystone: None,
+            thread_commissioning: false,
+            ad_data: &[],
+        };
+        let result = filter_ble(&input, &config);
+        assert!(result.matched);
+        assert!(result.matches.iter().any(|m| m.filter_type == "ble_name"));
+    }
+
+    #[test]
+    fn ble_name_fs_ext_battery_matches() {
+        let config = default_config();
+        let input = BleScanInput {
+            mac: &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+            mac_randomized: false,
+            name: "FS Ext Battery",
+            rssi: -50,
+            service_uuids_16: &[],
+            service_uuids_32: &[],
+            service_uuids_128: &[],
+            manufacturer_id: 0,
+            matter: None,
+            ibeacon: None,
+            eddystone: None,
+            thread_commissioning: false,
+            ad_data: &[],
+        };
+        let result = filter_ble(&input, &config);
+        assert!(result.matched);
+    }
+
+    #[test]
+    fn ble_name_pigvision_case_insensitive() {
+        let config = default_config();
+        let input = BleScanInput {
+            mac: &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+            mac_randomized: false,
+            name: "PIGVISION-device",
+            rssi: -50,
+            service_uuids_16: &[],
+            service_uuids_32: &[],
+            service_uuids_128: &[],
+            manufacturer_id: 0,
+            matter: None,
+            ibeacon: None,
+            eddystone: None,
+            thread_commissioning: false,
+            ad_data: &[],
+        };
+        let result = filter_ble(&input, &config);
+        assert!(result.matched);
+    }
+
+    #[test]
+    fn ble_manufacturer_id_xuntong_matches() {
+        let config = default_config();
+        let input = BleScanInput {
+            mac: &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+            mac_randomized: false,
+            name: "",
+            rssi: -50,
+            service_uuids_16: &[],
+            service_uuids_32: &[],
+            service_uuids_128: &[],
+            manufacturer_id: 0x09C8,
+            matter: None,
+            ibeacon: None,
+            eddystone: None,
+            thread_commissioning: false,
+            ad_data: &[],
+        };
+        let result = filter_ble(&input, &config);
+        assert!(result.matched);
+        assert!(result.matches.iter().any(|m| m.filter_type == "ble_mfr"));
+    }
+
+    #[test]
+    fn ble_raven_service_uuid_matches() {
+        let config = default_config();
+        let input = BleScanInput {
+            mac: &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+            mac_randomized: false,
+            name: "",
+            rssi: -50,
+            service_uuids_16: &[0x3100], // Raven GPS service
+            service_uuids_32: &[],
+            service_uuids_128: &[],
+            manufacturer_id: 0,
+            matter: None,
+            ibeacon: None,
+            eddystone: None,
+            thread_commissioning: false,
+            ad_data: &[],
+        };
+        let result = filter_ble(&input, &config);
+        assert!(result.matched);
+        assert!(result.matches.iter().any(|m| m.filter_type == "ble_uuid"));
+        assert!(result.rules.is_empty());
+
+        // Three Raven services satisfy the pack's "at least 3 of" rule
+        let input = BleScanInput {
+            service_uuids_16: &[0x3100, 0x3200, 0x1809],
+            ..input
+        };
+        let result = filter_ble(&input, &config);
+        assert_eq!(result.rules[0].name, "Raven Gunshot Detector");
+    }
+
+    #[test]
+    fn ble_standard_uuid_matches() {
+        let config = default_config();
+        let input = BleScanInput {
+            mac: &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+            mac_randomized: false,
+            name: "",
+            rssi: -50,
+            service_uuids_16: &[0x1819], // Location and Navigation
+            service_uuids_32: &[],
+            service_uuids_128: &[],
+            manufacturer_id: 0,
+            matter: None,
+            ibeacon: None,
+            eddystone: None,
+            thread_commissioning: false,
+            ad_data: &[],
+        };
+        let result = filter_ble(&input, &config);
+        assert!(result.matched);
+        assert!(result
+            .matches
+            .iter()
+            .any(|m| m.filter_type == "ble_uuid_std"));
+    }
+
+    #[test]
+    fn ble_tracker_payloads_match() {
+        let config = default_config();
+        // AirTag separated from its owner: Apple offline-finding payload
+        let airtag = [
+            0x1E, 0xFF, 0x4C, 0x00, 0x12, 0x19, 0x10, 0xAA, 0xBB, 0xCC, 0xDD, 0xEE,
+        ];
+        let input = BleScanInput {
+            mac: &[0xC1, 0x22, 0x33, 0x44, 0x55, 0x66],
+            mac_randomized: true,
+            name: "",
+            rssi: -50,
+            service_uuids_16: &[],
+            service_uuids_32: &[],
+            service_uuids_128: &[],
+            manufacturer_id: 0x004C,
+            matter: None,
+            ibeacon: None,
+            eddystone: None,
+            thread_commissioning: false,
+            ad_data: &airtag,
+        };
+        let result = filter_ble(&input, &config);
+        assert_eq!(result.matches.len(), 1);
+        assert_eq!(result.matches[0].filter_type, "ble_ad");
+        assert_eq!(result.meta.category, Category::Tracker);
+        assert_eq!(result.rules.len(), 1);
+        assert_eq!(result.rules[0].name, "Apple AirTag");
+
+        // SmartTag service data
+        let smarttag = [0x02, 0x01, 0x06, 0x0B, 0x16, 0x5A, 0xFD, 0x31, 0x00];
+        let result = filter_ble(
+            &BleScanInput {
+                manufacturer_id: 0,
+                ad_data: &smarttag,
+                ..input
+            },
+            &config,
+        );
+        assert_eq!(result.matches[0].detail, "Samsung SmartTag");
+
+        // Nearby-info advertisement of a phone
+        let iphone = [
+            0x0A, 0xFF, 0x4C, 0x00, 0x10, 0x05, 0x01, 0x18, 0x00, 0x00, 0x00,
+        ];
+        assert!(
+            !filter_ble(
+                &BleScanInput {
+                    ad_data: &iphone,
+                    ..input
+                },
+                &config
+            )
+            .matched
+        );
+    }
+
+    #[test]
+    fn overflowing_matches_mark_result_truncated() {
+        let config = default_config();
+        let meta = SignatureMeta::new(Category::Other, Severity::Low, Confidence::Low);
+        let mut result = FilterResult::<MATCH_DETAIL_LEN>::new(&config);
+        for _ in 0..MAX_MATCHES {
+            result.add_match("test", "kept", meta);
+        }
+        assert!(!result.truncated);
+        let high = SignatureMeta::new(Category::Other, Severity::High, Confidence::Low);
+        result.add_match("test", "dropped", high);
+        assert!(result.truncated);
+        assert_eq!(result.matches.len(), MAX_MATCHES);
+        // The dropped match still rates the result
+        assert_eq!(result.meta.severity, Severity::High);
+    }
+
+    #[test]
+    fn matched_rule_raises_severity() {
+        let config = default_config();
+        let pack = PACKS
+            .iter()
+            .position(|pack| pack.name == "trackers")
+            .unwrap();
+        let mut result = FilterResult::<MATCH_DETAIL_LEN>::new(&config);
+        result.meta = SignatureMeta::new(Category::Tracker, Severity::Low, Confidence::Low);
+        let _ = result.hits.push(SigHit {
+            pack: pack as u8,
+            table: SigTable::BleServiceUuids16,
+            index: crate::defaults::trackers::SIG_IDX_TILE_FEEC as u16,
+        });
+        result.apply_rules();
+        assert_eq!(result.rules[0].name, "Tile Tracker");
+        assert_eq!(result.meta.severity, Severity::Medium);
+        assert_eq!(result.meta.confidence, Confidence::Low);
+
+        // Excluding the only match leaves no hits for rules
+        result.exclude(Some("Tile tracker"));
+        assert!(result.hits.is_empty());
+    }
+
+    #[test]
+    fn rules_look_back_across_detections() {
+        let config = default_config();
+        let camera_ap = WiFiScanInput {
+            mac: &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+            mac_randomized: false,
+            ssid: "Flock-A1B2C3",
+            rssi: -40,
+            channel: 6,
+            traffic: Traffic::Normal,
+            ies: &[],
+            vendor_ies: &[],
+            wps: None,
+            capability: None,
+            timing: None,
+            sequence: None,
+            action: None,
+            network: None,
+        };
+        let camera_ble = BleScanInput {
+            mac: &[0xC1, 0x22, 0x33, 0x44, 0x55, 0x66],
+            mac_randomized: true,
+            name: "",
+            rssi: -50,
+            service_uuids_16: &[],
+            service_uuids_32: &[],
+            service_uuids_128: &[],
+            manufacturer_id: 0x09C8, // XUNTONG
+            matter: None,
+            ibeacon: None,
+            eddystone: None,
+            thread_commissioning: false,
+            ad_data: &[],
+        };
+        let mut recent = RecentSigs::new();
+        let mut result = filter_wifi(&camera_ap, &config);
+        result.apply_recent_rules(&recent, 0);
+        assert!(result.rules.is_empty());
+        recent.record(&result.hits, 0);
+
+        // The BLE module alone says nothing about the camera...
+        let mut result = filter_ble(&camera_ble, &config);
+        assert!(result.rules.is_empty());
+        // ...but it does 20 s after the camera's SSID
+        result.apply_recent_rules(&recent, 20_000);
+        assert_eq!(result.rules[0].name, "Flock Safety Camera");
+
+        let mut result = filter_ble(&camera_ble, &config);
+        result.apply_recent_rules(&recent, 40_000);
+        assert!(result.rules.is_empty());
+    }
+
+    #[test]
+    fn drone_remote_id_matches() {
+        let config = default_config();
+        // Remote ID service data carrying a Basic ID message
+        let ad = [0x1E, 0x16, 0xFA, 0xFF, 0x0D, 0x01, 0x02, 0x12, b'A'];
+        let input = BleScanInput {
+            mac: &[0xC1, 0x22, 0x33, 0x44, 0x55, 0x66],
+            mac_randomized: true,
+            name: "",
+            rssi: -70,
+            service_uuids_16: &[],
+            service_uuids_32: &[],
+            service_uuids_128: &[],
+            manufacturer_id: 0,
+            matter: None,
+            ibeacon: None,
+            eddystone: None,
+            thread_commissioning: false,
+            ad_data: &ad,
+        };
+        let result = filter_ble(&input, &config);
+        assert_eq!(result.matches[0].detail, "Drone Remote ID");
+        assert_eq!(result.meta.category, Category::Drone);
+
+        // Remote ID and DJI DroneID beacon elements
+        let elements = [
+            VendorIe {
+                oui: [0xFA, 0x0B, 0xBC],
+                data: Vec::from_slice(&[0x0D, 0x04, 0xF2]).unwrap(),
+            },
+            VendorIe {
+                oui: [0x26, 0x37, 0x12],
+                data: Vec::from_slice(&[0x58, 0x62, 0x13]).unwrap(),
+            },
+        ];
+        let input = WiFiScanInput {
+            mac: &[0x02, 0x11, 0x22, 0x33, 0x44, 0x55],
+            mac_randomized: true,
+            ssid: "",
+            rssi: -70,
+            channel: 6,
+            traffic: Traffic::Normal,
+            ies: &[],
+            vendor_ies: &elements,
+            wps: None,
+            capability: None,
+            timing: None,
+            sequence: None,
+            action: None,
+            network: None,
+        };
+        let result = filter_wifi(&input, &config);
+        let details: Vec<&str, 4> = result.matches.iter().map(|m| m.detail.as_str()).collect();
+        assert_eq!(details.as_slice(), ["Drone Remote ID", "DJI DroneID"]);
+        assert_eq!(result.matches[0].filter_type, "wifi_vendor_ie");
+    }
+
+    #[test]
+    fn ble_no_match_for_unknown_device() {
+        let config = default_config();
+        let input = BleScanInput {
+            mac: &[0xAA, 0xBB, 0xCC, 0x01, 0x02, 0x03],
+            mac_randomized: false,
+            name: "My Headphones",
+            rssi: -50,
+            service_uuids_16: &[0x180F], // Battery Service (not surveillance)
+            service_uuids_32: &[],
+            service_uuids_128: &[],
+            manufacturer_id: 0x004C, // Apple (not in our list)
+            matter: None,
+            ibeacon: None,
+            eddystone: None,
+            thread_commissioning: false,
+            ad_data: &[],
+        };
+        let result = filter_ble(&input, &config);
+        assert!(!result.matched);
+    }
+
+    #[test]
+    fn random_addresses_only_match_locally_administered_ouis() {
+        let config = default_config();
+        let mut input = BleScanInput {
+            mac: &[0xB4, 0x1E, 0x52, 0x01, 0x02, 0x03],
+            mac_randomized: false,
+            name: "",
+            rssi: -50,
+            service_uuids_16: &[],
+            service_uuids_32: &[],
+            service_uuids_128: &[],
+            manufacturer_id: 0,
+            matter: None,
+            ibeacon: None,
+            eddystone: None,
+            thread_commissioning: false,
+            ad_data: &[],
+        };
+        assert!(filter_ble(&input, &config).matched);
+        // Same bytes in a random address are a coincidence, not Flock Safety
+        input.mac_randomized = true;
+        assert!(!filter_ble(&input, &config).matched);
+
+        // A deliberately locally administered prefix still matches
+        assert!(is_locally_administered(&[0xDE, 0xAD, 0xBE, 0xEF, 0, 0]));
+        let wifi = WiFiScanInput {
+            mac: &[0xDE, 0xAD, 0xBE, 0xEF, 0x00, 0x01],
+            mac_randomized: true,
+            ssid: "",
+            rssi: -50,
+            channel: 6,
+            traffic: Traffic::Normal,
+            ies: &[],
+            vendor_ies: &[],
+            wps: None,
+            capability: None,
+            timing: None,
+            sequence: None,
+            action: None,
+            network: None,
+        };
+        assert_eq!(filter_wifi(&wifi, &config).matches[0].detail, "Pwnagotchi");
+    }
+
+    #[test]
+    fn ble_disabled_no_match() {
+        let config = FilterConfig {
+            ble_enabled: false,
+            ..default_config()
+        };
+        let input = BleScanInput {
+            mac: &[0xB4, 0x1E, 0x52, 0x01, 0x02, 0x03],
+            mac_randomized: false,
+            name: "Flock",
+            rssi: -50,
+            service_uuids_16: &[],
+            service_uuids_32: &[],
+            service_uuids_128: &[],
+            manufacturer_id: 0x09C8,
+            matter: None,
+            ibeacon: None,
+            eddystone: None,
+            thread_commissioning: false,
+            ad_data: &[],
+        };
+        let result = filter_ble(&input, &config);
+        assert!(!result.matched);
+    }
+
+    #[test]
+    fn ble_rssi_below_threshold_no_match() {
+        let config = FilterConfig {
+            min_rssi: -60,
+            ..default_config()
+        };
+        let input = BleScanInput {
+            mac: &[0xB4, 0x1E, 0x52, 0x01, 0x02, 0x03],
+            mac_randomized: false,
+            name: "Flock",
+            rssi: -70,
+            service_uuids_16: &[],
+            service_uuids_32: &[],
+            service_uuids_128: &[],
+            manufacturer_id: 0,
+            matter: None,
+            ibeacon: None,
+            eddystone: None,
+            thread_commissioning: false,
+            ad_data: &[],
+        };
+        let result = filter_ble(&input, &config);
+        assert!(!result.matched);
+    }
+
+    #[test]
+    fn ble_matter_vendor_matches() {
+        let config = default_config();
+        let matter = MatterCommissioning {
+            discriminator: 0x123,
+            vendor_id: 0x6006,
+            product_id: 1,
+        };
+        let mut input = BleScanInput {
+            mac: &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+            mac_randomized: false,
+            name: "",
+            rssi: -60,
+            service_uuids_16: &[],
+            service_uuids_32: &[],
+            service_uuids_128: &[],
+            manufacturer_id: 0,
+            matter: Some(matter),
+            ibeacon: None,
+            eddystone: None,
+            thread_commissioning: false,
+            ad_data: &[],
+        };
+        let result = filter_ble(&input, &config);
+        assert!(result.matched);
+        assert_eq!(result.matches[0].filter_type, "matter");
+
+        input.matter = Some(MatterCommissioning {
+            vendor_id: 0xFFF1, // CSA test vendor
+            ..matter
+        });
+        assert!(!filter_ble(&input, &config).matched);
+    }
+
+    #[test]
+    fn ble_ibeacon_uuid_matches() {
+        let config = default_config();
+        let ibeacon = IBeacon {
+            uuid: 0xF7826DA6_4FA2_4E98_8024_BC5B71E0893E,
+            major: 1,
+            minor: 2,
+            power: -59,
+        };
+        let mut input = BleScanInput {
+            mac: &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+            mac_randomized: false,
+            name: "",
+            rssi: -60,
+            service_uuids_16: &[],
+            service_uuids_32: &[],
+            service_uuids_128: &[],
+            manufacturer_id: 0x004C,
+            matter: None,
+            ibeacon: Some(ibeacon),
+            eddystone: None,
+            thread_commissioning: false,
+            ad_data: &[],
+        };
+        let result = filter_ble(&input, &config);
+        assert!(result.matched);
+        assert_eq!(result.matches[0].filter_type, "ibeacon");
+        assert_eq!(result.matches[0].detail, "Kontakt.io beacon");
+        assert_eq!(result.meta.category, Category::Tracker);
+        assert_eq!(result.meta.device_class, DeviceClass::Unknown);
+
+        input.ibeacon = Some(IBeacon {
+            uuid: 0xE2C56DB5_DFFB_48D2_B060_D0F5A71096E0,
+            ..ibeacon
+        });
+        assert!(!filter_ble(&input, &config).matched);
+    }
+
+    #[test]
+    fn ble_eddystone_namespace_matches() {
+        let config = default_config();
+        let eddystone = Eddystone::Uid {
+            namespace: [0xED, 0xD1, 0xEB, 0xEA, 0xC0, 0x4E, 0x5D, 0xEF, 0xA0, 0x17],
+            instance: [0; 6],
+        };
+        let input = BleScanInput {
+            mac: &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+            mac_randomized: false,
+            name: "",
+            rssi: -60,
+            service_uuids_16: &[],
+            service_uuids_32: &[],
+            service_uuids_128: &[],
+            manufacturer_id: 0,
+            matter: None,
+            ibeacon: None,
+            eddystone: Some(&eddystone),
+            thread_commissioning: false,
+            ad_data: &[],
+        };
+        let result = filter_ble(&input, &config);
+        assert!(result.matched);
+        assert_eq!(result.matches[0].filter_type, "eddystone");
+        assert_eq!(result.matches[0].detail, "Estimote beacon");
+
+        let eddystone = Eddystone::Url(heapless::String::try_from("https://example.com").unwrap());
+        let input = BleScanInput {
+            eddystone: Some(&eddystone),
+            ..input
+        };
+        assert!(!filter_ble(&input, &config).matched);
+    }
+
+    #[test]
+    fn ble_thread_commissioning_matches() {
+        let config = default_config();
+        let input = BleScanInput {
+            mac: &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+            mac_randomized: false,
+            name: "",
+            rssi: -60,
+            service_uuids_16: &[],
+            service_uuids_32: &[],
+            service_uuids_128: &[],
             manufacturer_id: 0,
+            matter: None,
+            ibeacon: None,
+            eddystone: None,
+            thread_commissioning: true,
+            ad_data: &[],
         };
         let result = filter_ble(&input, &config);
         assert!(result.matched);
-        assert!(result.matches.iter().any(|m| m.filter_type == "ble_name"));
+        assert_eq!(result.matches[0].filter_type, "thread");
     }
 
     #[test]
-    fn ble_name_fs_ext_battery_matches() {
+    fn strongest_match_rates_the_result() {
         let config = default_config();
         let input = BleScanInput {
             mac: &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
-            name: "FS Ext Battery",
-            rssi: -50,
-            service_uuids_16: &[],
+            mac_randomized: false,
+            name: "",
+            rssi: -60,
+            // Raven's generic Device Information service, then a Raven service
+            service_uuids_16: &[0x180A, 0x3100],
+            service_uuids_32: &[],
+            service_uuids_128: &[],
             manufacturer_id: 0,
+            matter: None,
+            ibeacon: None,
+            eddystone: None,
+            thread_commissioning: true,
+            ad_data: &[],
         };
         let result = filter_ble(&input, &config);
-        assert!(result.matched);
+        assert_eq!(result.matches.len(), 3);
+        assert_eq!(result.matches[0].meta.confidence, Confidence::Low);
+        assert_eq!(
+            result.meta,
+            SignatureMeta::new(Category::GunshotDetector, Severity::High, Confidence::High)
+        );
     }
 
     #[test]
-    fn ble_name_pigvision_case_insensitive() {
-        let config = default_config();
+    fn disabled_categories_are_dropped() {
+        let mut config = default_config();
+        config.categories.gunshot_detector = false;
         let input = BleScanInput {
             mac: &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
-            name: "PIGVISION-device",
-            rssi: -50,
-            service_uuids_16: &[],
+            mac_randomized: false,
+            name: "",
+            rssi: -60,
+            service_uuids_16: &[0x3100],
+            service_uuids_32: &[],
+            service_uuids_128: &[],
             manufacturer_id: 0,
+            matter: None,
+            ibeacon: None,
+            eddystone: None,
+            thread_commissioning: false,
+            ad_data: &[],
+        };
+        assert!(!filter_ble(&input, &config).matched);
+
+        // Other categories still match and set the rating
+        let input = BleScanInput {
+            thread_commissioning: true,
+            ..input
         };
         let result = filter_ble(&input, &config);
-        assert!(result.matched);
+        assert_eq!(result.matches.len(), 1);
+        assert_eq!(result.meta, THREAD_META);
     }
 
     #[test]
-    fn ble_manufacturer_id_xuntong_matches() {
+    fn ble_exclusion_drops_named_matches_only() {
         let config = default_config();
         let input = BleScanInput {
-            mac: &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
-            name: "",
+            mac: &[0x58, 0x8E, 0x81, 0x01, 0x02, 0x03], // Silicon Labs
+            mac_randomized: false,
+            name: "Living Room THERMOSTAT",
             rssi: -50,
             service_uuids_16: &[],
-            manufacturer_id: 0x09C8,
+            service_uuids_32: &[],
+            service_uuids_128: &[],
+            manufacturer_id: 0,
+            matter: None,
+            ibeacon: None,
+            eddystone: None,
+            thread_commissioning: false,
+            ad_data: &[],
+        };
+        let result = filter_ble(&input, &config);
+        assert!(!result.matched);
+        assert!(result.matches.is_empty());
+        assert_eq!(result.meta, SignatureMeta::UNRATED);
+
+        // Other matches on the same device survive
+        let input = BleScanInput {
+            name: "Flock Thermostat",
+            ..input
         };
         let result = filter_ble(&input, &config);
         assert!(result.matched);
-        assert!(result.matches.iter().any(|m| m.filter_type == "ble_mfr"));
+        assert_eq!(result.matches.len(), 1);
+        assert_eq!(result.matches[0].filter_type, "ble_name");
+
+        let input = BleScanInput {
+            name: "Sensor",
+            ..input
+        };
+        assert_eq!(
+            filter_ble(&input, &config).matches[0].filter_type,
+            "mac_oui"
+        );
     }
 
+    // ── Bluetooth Classic filter tests ──────────────────────────────
+
     #[test]
-    fn ble_raven_service_uuid_matches() {
+    fn bt_classic_video_camera_cod_matches() {
         let config = default_config();
-        let input = BleScanInput {
-            mac: &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+        let input = BtClassicScanInput {
+            mac: &[0x00, 0x11, 0x22, 0x33, 0x44, 0x55],
             name: "",
-            rssi: -50,
-            service_uuids_16: &[0x3100], // Raven GPS service
+            rssi: -60,
+            class_of_device: 0x20_0430,
             manufacturer_id: 0,
         };
-        let result = filter_ble(&input, &config);
+        let result = filter_bt_classic(&input, &config);
         assert!(result.matched);
-        assert!(result.matches.iter().any(|m| m.filter_type == "ble_uuid"));
+        assert_eq!(result.matches[0].filter_type, "bt_cod");
     }
 
     #[test]
-    fn ble_standard_uuid_matches() {
+    fn bt_classic_name_and_oui_match() {
         let config = default_config();
-        let input = BleScanInput {
-            mac: &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+        let input = BtClassicScanInput {
+            mac: &[0xB4, 0x1E, 0x52, 0x01, 0x02, 0x03],
+            name: "Flock Unit",
+            rssi: -60,
+            class_of_device: 0x5A_020C, // phone
+            manufacturer_id: 0,
+        };
+        let result = filter_bt_classic(&input, &config);
+        assert_eq!(result.matches.len(), 2);
+        assert_eq!(result.matches[0].filter_type, "mac_oui");
+        assert_eq!(result.matches[1].filter_type, "bt_name");
+    }
+
+    #[test]
+    fn bt_classic_respects_ble_switch_and_rssi() {
+        let input = BtClassicScanInput {
+            mac: &[0x00, 0x11, 0x22, 0x33, 0x44, 0x55],
             name: "",
-            rssi: -50,
-            service_uuids_16: &[0x1819], // Location and Navigation
+            rssi: -95,
+            class_of_device: 0x0430,
             manufacturer_id: 0,
         };
-        let result = filter_ble(&input, &config);
+        assert!(!filter_bt_classic(&input, &default_config()).matched);
+
+        let config = FilterConfig {
+            ble_enabled: false,
+            ..default_config()
+        };
+        let input = BtClassicScanInput { rssi: -50, ..input };
+        assert!(!filter_bt_classic(&input, &config).matched);
+    }
+
+    #[test]
+    fn bt_classic_eir_manufacturer_matches() {
+        let config = default_config();
+        let ble = BleScanInput {
+            mac: &[0x00, 0x11, 0x22, 0x33, 0x44, 0x55],
+            mac_randomized: false,
+            name: "",
+            rssi: -60,
+            service_uuids_16: &[],
+            service_uuids_32: &[],
+            service_uuids_128: &[],
+            manufacturer_id: 0x09C8,
+            matter: None,
+            ibeacon: None,
+            eddystone: None,
+            thread_commissioning: false,
+            ad_data: &[],
+        };
+        let expected = filter_ble(&ble, &config);
+        assert_eq!(expected.matches[0].filter_type, "ble_mfr");
+
+        let input = BtClassicScanInput {
+            mac: &[0x00, 0x11, 0x22, 0x33, 0x44, 0x55],
+            name: "",
+            rssi: -60,
+            class_of_device: 0,
+            manufacturer_id: 0x09C8,
+        };
+        let result = filter_bt_classic(&input, &config);
         assert!(result.matched);
-        assert!(result
-            .matches
-            .iter()
-            .any(|m| m.filter_type == "ble_uuid_std"));
+        assert_eq!(result.matches[0].filter_type, "bt_mfr");
+        assert_eq!(result.matches[0].detail, expected.matches[0].detail);
+    }
+
+    // ── Overlay tests ───────────────────────────────────────────────
+
+    fn test_overlay() -> SignatureOverlay {
+        let json = br#"{"version":1,"signatures":[
+            {"id":"acme-oui","type":"mac_oui","oui":"AA:BB:CC","description":"Acme Cameras"},
+            {"id":"acme-ssid","type":"wifi_ssid","match":"prefix","value":"ACME-","case_sensitive":false},
+            {"id":"acme-ble","type":"ble_name","match":"contains","value":"acmecam","case_sensitive":false},
+            {"id":"acme-mfr","type":"ble_manufacturer_id","company_id":4660,"description":"Acme mfr"}
+        ]}"#;
+        let mut overlay = SignatureOverlay::new();
+        crate::sigfile::load_signatures(json, &mut overlay).unwrap();
+        overlay
     }
 
     #[test]
-    fn ble_no_match_for_unknown_device() {
+    fn overlay_mac_oui_matches() {
+        let overlay = test_overlay();
+        let input = WiFiScanInput {
+            mac: &[0xAA, 0xBB, 0xCC, 0x01, 0x02, 0x03],
+            mac_randomized: false,
+            ssid: "",
+            rssi: -50,
+            channel: 6,
+            traffic: Traffic::Normal,
+            ies: &[],
+            vendor_ies: &[],
+            wps: None,
+            capability: None,
+            timing: None,
+            sequence: None,
+            action: None,
+            network: None,
+        };
+        assert!(!filter_wifi(&input, &default_config()).matched);
+        let result = filter_wifi_with(&input, &default_config(), &overlay);
+        assert_eq!(result.matches[0].filter_type, "mac_oui");
+        assert_eq!(result.matches[0].detail.as_str(), "Acme Cameras");
+    }
+
+    #[test]
+    fn combined_sources_match_either() {
+        let overlay = test_overlay();
+        let mut runtime = SignatureOverlay::new();
+        assert!(runtime.add(
+            &crate::sigfile::RuntimeSignature::SsidKeyword("hidden-cam".try_into().unwrap()),
+            "Runtime cam",
+        ));
+        let sigs = (&overlay, &runtime);
         let config = default_config();
-        let input = BleScanInput {
+
+        let from_overlay = WiFiScanInput {
             mac: &[0xAA, 0xBB, 0xCC, 0x01, 0x02, 0x03],
-            name: "My Headphones",
+            mac_randomized: false,
+            ssid: "",
             rssi: -50,
-            service_uuids_16: &[0x180F], // Battery Service (not surveillance)
-            manufacturer_id: 0x004C,     // Apple (not in our list)
+            channel: 6,
+            traffic: Traffic::Normal,
+            ies: &[],
+            vendor_ies: &[],
+            wps: None,
+            capability: None,
+            timing: None,
+            sequence: None,
+            action: None,
+            network: None,
         };
-        let result = filter_ble(&input, &config);
-        assert!(!result.matched);
+        assert!(filter_wifi_with(&from_overlay, &config, &sigs).matched);
+
+        let from_runtime = WiFiScanInput {
+            mac: &[0x02, 0x00, 0x00, 0x01, 0x02, 0x03],
+            mac_randomized: false,
+            ssid: "My-Hidden-Cam-01",
+            rssi: -50,
+            channel: 6,
+            traffic: Traffic::Normal,
+            ies: &[],
+            vendor_ies: &[],
+            wps: None,
+            capability: None,
+            timing: None,
+            sequence: None,
+            action: None,
+            network: None,
+        };
+        assert!(!filter_wifi_with(&from_runtime, &config, &overlay).matched);
+        let result = filter_wifi_with(&from_runtime, &config, &sigs);
+        assert_eq!(result.matches[0].detail.as_str(), "Runtime cam");
     }
 
     #[test]
-    fn ble_disabled_no_match() {
-        let config = FilterConfig {
-            ble_enabled: false,
-            ..default_config()
+    fn sized_filter_uses_requested_detail_capacity() {
+        let input = WiFiScanInput {
+            mac: &[0xB4, 0x1E, 0x52, 0x01, 0x02, 0x03],
+            mac_randomized: false,
+            ssid: "",
+            rssi: -50,
+            channel: 6,
+            traffic: Traffic::Normal,
+            ies: &[],
+            vendor_ies: &[],
+            wps: None,
+            capability: None,
+            timing: None,
+            sequence: None,
+            action: None,
+            network: None,
         };
+        let short = filter_wifi_sized::<8>(&input, &default_config(), &EMPTY_OVERLAY);
+        assert_eq!(short.matches[0].detail.as_str(), "Flock Sa");
+        let full = filter_wifi_sized::<64>(&input, &default_config(), &EMPTY_OVERLAY);
+        assert_eq!(full.matches[0].detail.as_str(), "Flock Safety");
+    }
+
+    #[test]
+    fn overlay_ssid_prefix_matches() {
+        let overlay = test_overlay();
+        let input = WiFiScanInput {
+            mac: &[0x00; 6],
+            mac_randomized: false,
+            ssid: "acme-lot-4",
+            rssi: -50,
+            channel: 6,
+            traffic: Traffic::Normal,
+            ies: &[],
+            vendor_ies: &[],
+            wps: None,
+            capability: None,
+            timing: None,
+            sequence: None,
+            action: None,
+            network: None,
+        };
+        let result = filter_wifi_with(&input, &default_config(), &overlay);
+        assert!(result.matched);
+        assert_eq!(result.matches[0].filter_type, "ssid_pattern");
+        assert_eq!(result.matches[0].detail.as_str(), "ACME-");
+    }
+
+    #[test]
+    fn overlay_ble_name_and_mfr_match() {
+        let overlay = test_overlay();
         let input = BleScanInput {
-            mac: &[0xB4, 0x1E, 0x52, 0x01, 0x02, 0x03],
-            name: "Flock",
+            mac: &[0x00; 6],
+            mac_randomized: false,
+            name: "AcmeCam 2",
             rssi: -50,
             service_uuids_16: &[],
-            manufacturer_id: 0x09C8,
+            service_uuids_32: &[],
+            service_uuids_128: &[],
+            manufacturer_id: 4660,
+            matter: None,
+            ibeacon: None,
+            eddystone: None,
+            thread_commissioning: false,
+            ad_data: &[],
         };
-        let result = filter_ble(&input, &config);
-        assert!(!result.matched);
+        let result = filter_ble_with(&input, &default_config(), &overlay);
+        assert!(result.matches.iter().any(|m| m.filter_type == "ble_name"));
+        assert!(result
+            .matches
+            .iter()
+            .any(|m| m.filter_type == "ble_mfr" && m.detail.as_str() == "Acme mfr"));
     }
 
     #[test]
-    fn ble_rssi_below_threshold_no_match() {
+    fn weak_indicator_needs_repeat_sightings() {
+        let silabs = WiFiScanInput {
+            mac: &[0x58, 0x8E, 0x81, 0xAA, 0xBB, 0xCC],
+            mac_randomized: false,
+            ssid: "",
+            rssi: -60,
+            channel: 6,
+            traffic: Traffic::Normal,
+            ies: &[],
+            vendor_ies: &[],
+            wps: None,
+            capability: None,
+            timing: None,
+            sequence: None,
+            action: None,
+            network: None,
+        };
+        let result = filter_wifi(&silabs, &default_config());
+        assert!(result.matched);
+        assert_eq!(result.min_sightings, 3);
+
+        // A strong indicator alongside fires on first sight
+        let flock = WiFiScanInput {
+            ssid: "Flock-A1B2C3",
+            ..silabs
+        };
+        assert_eq!(filter_wifi(&flock, &default_config()).min_sightings, 1);
+    }
+
+    #[test]
+    fn overlay_min_sightings_applies() {
+        let json = br#"{"signatures":[
+            {"id":"weak","type":"wifi_ssid","match":"prefix","value":"cam-","min_sightings":5}
+        ]}"#;
+        let mut overlay = SignatureOverlay::new();
+        crate::sigfile::load_signatures(json, &mut overlay).unwrap();
+        let input = WiFiScanInput {
+            mac: &[0x00; 6],
+            mac_randomized: false,
+            ssid: "cam-7",
+            rssi: -50,
+            channel: 6,
+            traffic: Traffic::Normal,
+            ies: &[],
+            vendor_ies: &[],
+            wps: None,
+            capability: None,
+            timing: None,
+            sequence: None,
+            action: None,
+            network: None,
+        };
+        let result = filter_wifi_with(&input, &default_config(), &overlay);
+        assert_eq!(result.min_sightings, 5);
+    }
+
+    #[test]
+    fn overlay_respects_rssi_threshold() {
+        let overlay = test_overlay();
         let config = FilterConfig {
             min_rssi: -60,
             ..default_config()
         };
-        let input = BleScanInput {
-            mac: &[0xB4, 0x1E, 0x52, 0x01, 0x02, 0x03],
-            name: "Flock",
+        let input = WiFiScanInput {
+            mac: &[0xAA, 0xBB, 0xCC, 0x01, 0x02, 0x03],
+            mac_randomized: false,
+            ssid: "ACME-1",
             rssi: -70,
+            channel: 6,
+            traffic: Traffic::Normal,
+            ies: &[],
+            vendor_ies: &[],
+            wps: None,
+            capability: None,
+            timing: None,
+            sequence: None,
+            action: None,
+            network: None,
+        };
+        assert!(!filter_wifi_with(&input, &config, &overlay).matched);
+    }
+
+    // ── Pipeline tests ──────────────────────────────────────────────
+
+    const FLOCK_MAC: [u8; 6] = [0xB4, 0x1E, 0x52, 0x01, 0x02, 0x03];
+
+    fn flock_wifi() -> WiFiScanInput<'static> {
+        WiFiScanInput {
+            mac: &FLOCK_MAC,
+            mac_randomized: false,
+            ssid: "Flock-A1B2C3",
+            rssi: -50,
+            channel: 6,
+            traffic: Traffic::Normal,
+            ies: &[],
+            vendor_ies: &[],
+            wps: None,
+            capability: None,
+            timing: None,
+            sequence: None,
+            action: None,
+            network: None,
+        }
+    }
+
+    /// Flags every result heard closer than -40 dBm
+    struct TooClose;
+
+    impl FilterStage for TooClose {
+        fn apply<const N: usize>(
+            &self,
+            input: &ScanInput<'_>,
+            _config: &FilterConfig,
+            result: &mut FilterResult<N>,
+        ) -> Flow {
+            if input.rssi() > -40 {
+                result.add_match("custom", "too close", THREAD_META);
+            }
+            Flow::Continue
+        }
+    }
+
+    #[test]
+    fn standard_pipeline_matches_filter_functions() {
+        let input = flock_wifi();
+        let config = default_config();
+        let piped: FilterResult =
+            Pipeline::standard(&EMPTY_OVERLAY).run(&ScanInput::WiFi(&input), &config);
+        let direct = filter_wifi(&input, &config);
+        assert_eq!(piped.matched, direct.matched);
+        assert_eq!(piped.matches.len(), direct.matches.len());
+        for (a, b) in piped.matches.iter().zip(&direct.matches) {
+            assert_eq!((a.filter_type, &a.detail), (b.filter_type, &b.detail));
+        }
+        assert_eq!(piped.hits, direct.hits);
+    }
+
+    #[test]
+    fn ssid_heuristics_are_opt_in() {
+        let mut input = flock_wifi();
+        input.mac = &[0xAA, 0xBB, 0xCC, 0x01, 0x02, 0x03];
+        input.ssid = "attw1fi";
+        assert!(!filter_wifi(&input, &default_config()).matched);
+
+        let config = FilterConfig {
+            ssid_heuristics: true,
+            ..default_config()
+        };
+        let result = filter_wifi(&input, &config);
+        assert!(result.matched);
+        assert_eq!(result.matches[0].filter_type, "ssid_anomaly");
+        assert_eq!(result.matches[0].detail.as_str(), "lookalike of attwifi");
+        assert_eq!(result.meta.severity, Severity::Low);
+
+        // Hidden beacon from an ad-hoc network
+        input.ssid = "";
+        input.capability = Some(0x0002);
+        let result = filter_wifi(&input, &config);
+        assert_eq!(
+            result.matches[0].detail.as_str(),
+            "hidden SSID, odd capabilities"
+        );
+    }
+
+    #[test]
+    fn beacon_timing_anomalies_match() {
+        let mut input = flock_wifi();
+        input.mac = &[0x02, 0xAA, 0xBB, 0xCC, 0xDD, 0xEE];
+        input.ssid = "HomeNet";
+        input.timing = Some(TimingAnomaly::BssidConflict);
+        assert!(!filter_wifi(&input, &default_config()).matched);
+
+        let config = FilterConfig {
+            timing_heuristics: true,
+            ..default_config()
+        };
+        let result = filter_wifi(&input, &config);
+        assert!(result.matched);
+        assert_eq!(result.matches[0].filter_type, "beacon_timing");
+        assert_eq!(result.matches[0].detail.as_str(), "BSSID conflict");
+        assert_eq!(result.meta.severity, Severity::Medium);
+
+        input.timing = Some(TimingAnomaly::TsfReset);
+        let result = filter_wifi(&input, &config);
+        assert_eq!(result.matches[0].detail.as_str(), "TSF reset");
+        assert_eq!(result.meta.severity, Severity::Low);
+    }
+
+    #[test]
+    fn sequence_anomalies_match() {
+        let mut input = flock_wifi();
+        input.mac = &[0x02, 0xAA, 0xBB, 0xCC, 0xDD, 0xEE];
+        input.ssid = "HomeNet";
+        input.sequence = Some(SequenceAnomaly::Interleaved);
+        assert!(!filter_wifi(&input, &default_config()).matched);
+
+        let config = FilterConfig {
+            sequence_heuristics: true,
+            ..default_config()
+        };
+        let result = filter_wifi(&input, &config);
+        assert_eq!(result.matches[0].filter_type, "seq_anomaly");
+        assert_eq!(result.matches[0].detail.as_str(), "interleaved sequences");
+        assert_eq!(result.meta.severity, Severity::Medium);
+
+        input.sequence = Some(SequenceAnomaly::Jump);
+        let result = filter_wifi(&input, &config);
+        assert_eq!(result.matches[0].detail.as_str(), "sequence jump");
+        assert_eq!(result.meta.severity, Severity::Low);
+    }
+
+    #[test]
+    fn ftm_ranging_matches() {
+        let mut input = flock_wifi();
+        input.mac = &[0x5A, 0x01, 0x02, 0x03, 0x04, 0x05];
+        input.ssid = "";
+        input.action = Some(ActionCategory::BlockAck);
+        assert!(!filter_wifi(&input, &default_config()).matched);
+
+        input.action = Some(ActionCategory::FineTiming);
+        let result = filter_wifi(&input, &default_config());
+        assert!(result.matched);
+        assert_eq!(result.matches[0].filter_type, "ftm");
+        assert_eq!(result.meta.severity, Severity::Medium);
+    }
+
+    #[test]
+    fn custom_stage_adds_matches() {
+        let mut input = flock_wifi();
+        input.mac = &[0xAA, 0xBB, 0xCC, 0x01, 0x02, 0x03];
+        input.ssid = "Linksys-Home";
+        input.rssi = -30;
+        let pipeline = Pipeline::new((RssiGate, SignatureMatch(&EMPTY_OVERLAY), TooClose, Rules));
+        let result: FilterResult = pipeline.run(&ScanInput::WiFi(&input), &default_config());
+        assert!(result.matched);
+        assert_eq!(result.matches[0].filter_type, "custom");
+    }
+
+    #[test]
+    fn gate_stops_before_later_stages() {
+        let mut input = flock_wifi();
+        input.rssi = -95;
+        let pipeline = Pipeline::new((RssiGate, TooClose)).then(SignatureMatch(&EMPTY_OVERLAY));
+        let result: FilterResult = pipeline.run(&ScanInput::WiFi(&input), &default_config());
+        assert!(!result.matched);
+        assert!(result.rssi_rejected);
+    }
+
+    #[test]
+    fn self_filter_drops_airhound_units() {
+        let mut input = BleScanInput {
+            mac: &comm::BLE_ADDRESS,
+            mac_randomized: true,
+            name: "Flock Camera",
+            rssi: -30,
             service_uuids_16: &[],
+            service_uuids_32: &[],
+            service_uuids_128: &[],
             manufacturer_id: 0,
+            matter: None,
+            ibeacon: None,
+            eddystone: None,
+            thread_commissioning: false,
+            ad_data: &[],
         };
-        let result = filter_ble(&input, &config);
+        assert!(!filter_ble(&input, &default_config()).matched);
+
+        // Another unit's scan response, from a different address
+        input.mac = &[0x00, 0x00, 0x00, 0x00, 0x00, 0x01];
+        input.mac_randomized = false;
+        input.service_uuids_128 = &[comm::ble_uuids::SERVICE_128];
+        assert!(!filter_ble(&input, &default_config()).matched);
+
+        let config = FilterConfig {
+            report_self: true,
+            ..default_config()
+        };
+        assert!(filter_ble(&input, &config).matched);
+    }
+
+    #[test]
+    fn allowlist_clears_known_devices() {
+        let input = flock_wifi();
+        let allowed = [FLOCK_MAC];
+        let pipeline = Pipeline::new((
+            RssiGate,
+            SignatureMatch(&EMPTY_OVERLAY),
+            Rules,
+            Allowlist::new(&allowed),
+        ));
+        let result: FilterResult = pipeline.run(&ScanInput::WiFi(&input), &default_config());
         assert!(!result.matched);
+        assert!(result.matches.is_empty());
+        assert!(result.rules.is_empty());
+        assert_eq!(result.meta, SignatureMeta::UNRATED);
+    }
+
+    // ── FilterStats tests ───────────────────────────────────────────
+
+    #[test]
+    fn stats_count_evaluations_and_signature_hits() {
+        let mut input = WiFiScanInput {
+            mac: &[0xB4, 0x1E, 0x52, 0x01, 0x02, 0x03], // Known Flock Safety OUI
+            mac_randomized: false,
+            ssid: "Linksys-Home",
+            rssi: -50,
+            channel: 6,
+            traffic: Traffic::Normal,
+            ies: &[],
+            vendor_ies: &[],
+            wps: None,
+            capability: None,
+            timing: None,
+            sequence: None,
+            action: None,
+            network: None,
+        };
+        let config = FilterConfig {
+            min_rssi: -70,
+            ..default_config()
+        };
+        let mut stats = FilterStats::new();
+        stats.record(&filter_wifi(&input, &config));
+        stats.record(&filter_wifi(&input, &config));
+        input.rssi = -80;
+        stats.record(&filter_wifi(&input, &config));
+        input.mac = &[0xAA, 0xBB, 0xCC, 0x01, 0x02, 0x03];
+        input.rssi = -50;
+        stats.record(&filter_wifi(&input, &config));
+
+        assert_eq!(stats.evaluated, 4);
+        assert_eq!(stats.matched, 2);
+        assert_eq!(stats.rssi_rejected, 1);
+        assert_eq!(stats.untracked, 0);
+        assert_eq!(stats.sigs().len(), 1);
+        let (pack, sig, hits) = stats.sigs()[0];
+        let (table, index) = PACKS[pack as usize].signatures[sig as usize];
+        assert_eq!(table, SigTable::MacPrefixes);
+        let hit = SigHit { pack, table, index };
+        assert_eq!(hit.description(), "Flock Safety");
+        assert_eq!(hits, 2);
+    }
+
+    // ── estimate_distance tests ─────────────────────────────────────
+
+    #[test]
+    fn estimate_distance_follows_free_space_loss() {
+        assert_eq!(estimate_distance(-59, -59), 1.0);
+        // Every 20 dB of extra loss is ten times the distance
+        assert_eq!(estimate_distance(-79, -59), 10.0);
+        assert_eq!(estimate_distance(-65, -59), 2.0);
+        assert_eq!(estimate_distance(-53, -59), 0.5);
+    }
+
+    #[test]
+    fn estimate_distance_is_clamped() {
+        assert_eq!(estimate_distance(-20, -59), 0.1);
+        assert_eq!(estimate_distance(-128, -40), 1000.0);
     }
 
     // ── format_mac tests ────────────────────────────────────────────
@@ -563,4 +3556,16 @@ mod tests {
         format_mac(&mac, &mut buf);
         assert_eq!(buf.as_str(), "00:0A:0B:00:00:01");
     }
+
+    #[test]
+    fn parse_mac_round_trips_format_mac() {
+        let mac = [0xB4, 0x1E, 0x52, 0x0A, 0x0B, 0x0C];
+        let mut buf = crate::protocol::MacString::new();
+        format_mac(&mac, &mut buf);
+        assert_eq!(parse_mac(&buf), Some(mac));
+        assert_eq!(parse_mac("b4:1e:52:0a:0b:0c"), Some(mac));
+        assert_eq!(parse_mac("B4:1E:52:0A:0B"), None);
+        assert_eq!(parse_mac("B4:1E:52:0A:0B:0C:0D"), None);
+        assert_eq!(parse_mac("B4-1E-52-0A-0B-0C"), None);
+    }
 }
@@ -0,0 +1,310 @@
+/// Following detection for devices that travel with the user.
+///
+/// A tracker slipped into a bag or onto a car is heard wherever its target
+/// goes, while the devices of the places passed by are heard in one spot.
+/// [`FollowingDetector`] records where the user was at each sighting of a
+/// matched device — at most one per [`SIGHTING_SPACING_MS`], so a device
+/// heard continuously at one stop is one sighting per minute, not hundreds —
+/// and reports a device once the [`FollowingPolicy`] is met: enough
+/// sightings within the window, spread over more than the set distance.
+/// Without a fresh location nothing is recorded; a stationary user is left
+/// to lingering detection.
+use heapless::Vec;
+use serde::{Deserialize, Serialize};
+
+use crate::location::Location;
+
+/// Sightings kept per device
+pub const MAX_SIGHTINGS: usize = 8;
+
+/// Sightings closer together than this count as one
+pub const SIGHTING_SPACING_MS: u32 = 60 * 1000;
+
+/// Sightings, distance and window that make a device a follower
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FollowingPolicy {
+    /// Sightings, each at least [`SIGHTING_SPACING_MS`] apart
+    pub sightings: u8,
+    /// Window the sightings must fall in, in minutes
+    pub window_min: u16,
+    /// Distance the user's locations at the sightings must span, in metres
+    pub distance_m: u16,
+}
+
+impl FollowingPolicy {
+    /// Four sightings over half an hour spanning 400 m: a walk across a
+    /// neighbourhood or a few minutes' drive, more than a bus stop's
+    /// neighbours share
+    pub const DEFAULT: Self = Self {
+        sightings: 4,
+        window_min: 30,
+        distance_m: 400,
+    };
+
+    /// Build a policy, rejecting fewer than two sightings or more than are
+    /// kept, and a zero window or distance
+    pub fn try_new(sightings: u8, window_min: u16, distance_m: u16) -> Option<Self> {
+        ((2..=MAX_SIGHTINGS as u8).contains(&sightings) && window_min > 0 && distance_m > 0)
+            .then_some(Self {
+                sightings,
+                window_min,
+                distance_m,
+            })
+    }
+
+    pub fn window_ms(&self) -> u32 {
+        self.window_min as u32 * 60 * 1000
+    }
+}
+
+impl Default for FollowingPolicy {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// A device that has followed the user, as returned by
+/// [`FollowingDetector::observe`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Follower {
+    /// Latest MAC of the device
+    pub mac: [u8; 6],
+    /// Synthetic ID linking its rotating MACs, if any
+    pub device_id: Option<u16>,
+    /// Sightings within the window, this one included
+    pub sightings: u8,
+    /// Greatest distance between the user's locations at two sightings
+    pub span_m: u32,
+    /// Uptime in milliseconds at the first sighting within the window
+    pub first_seen_ms: u32,
+}
+
+struct Point {
+    location: Location,
+    at_ms: u32,
+}
+
+struct Entry {
+    mac: [u8; 6],
+    device_id: Option<u16>,
+    /// Recorded sightings, oldest first
+    points: Vec<Point, MAX_SIGHTINGS>,
+    last_ms: u32,
+    /// Uptime of the last report, so a follower is reported once per window
+    reported_ms: Option<u32>,
+}
+
+/// Sighting locations of up to `N` devices. Timestamps are milliseconds
+/// since boot (`u32`, wrapping).
+pub struct FollowingDetector<const N: usize> {
+    entries: Vec<Entry, N>,
+}
+
+impl<const N: usize> FollowingDetector<N> {
+    pub const fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Record a sighting of `mac` — or of the device `device_id` links it
+    /// to — made at `location`. Returns the device once it meets `policy`,
+    /// then not again until a window has passed. When the table is full the
+    /// device seen longest ago is replaced.
+    pub fn observe(
+        &mut self,
+        mac: &[u8; 6],
+        device_id: Option<u16>,
+        location: &Location,
+        now_ms: u32,
+        policy: &FollowingPolicy,
+    ) -> Option<Follower> {
+        let window_ms = policy.window_ms();
+        let index = match self.entries.iter().position(|e| match device_id {
+            Some(id) => e.device_id == Some(id),
+            None => e.mac == *mac,
+        }) {
+            Some(index) => index,
+            None => self.insert(mac, device_id, now_ms),
+        };
+        let entry = &mut self.entries[index];
+        entry.mac = *mac;
+        entry.last_ms = now_ms;
+
+        while entry
+            .points
+            .first()
+            .is_some_and(|p| now_ms.wrapping_sub(p.at_ms) > window_ms)
+        {
+            entry.points.remove(0);
+        }
+        if entry
+            .points
+            .last()
+            .is_some_and(|p| now_ms.wrapping_sub(p.at_ms) < SIGHTING_SPACING_MS)
+        {
+            return None;
+        }
+        if entry.points.is_full() {
+            entry.points.remove(0);
+        }
+        let _ = entry.points.push(Point {
+            location: *location,
+            at_ms: now_ms,
+        });
+
+        if entry.points.len() < policy.sightings as usize
+            || entry
+                .reported_ms
+                .is_some_and(|at| now_ms.wrapping_sub(at) < window_ms)
+        {
+            return None;
+        }
+        let span_m = span_m(&entry.points);
+        if span_m <= policy.distance_m as u32 {
+            return None;
+        }
+        entry.reported_ms = Some(now_ms);
+        Some(Follower {
+            mac: entry.mac,
+            device_id: entry.device_id,
+            sightings: entry.points.len() as u8,
+            span_m,
+            first_seen_ms: entry.points[0].at_ms,
+        })
+    }
+
+    /// Add an entry for a new device, replacing the stalest if full.
+    /// Returns its index.
+    fn insert(&mut self, mac: &[u8; 6], device_id: Option<u16>, now_ms: u32) -> usize {
+        let entry = Entry {
+            mac: *mac,
+            device_id,
+            points: Vec::new(),
+            last_ms: now_ms,
+            reported_ms: None,
+        };
+        match self.entries.push(entry) {
+            Ok(()) => self.entries.len() - 1,
+            Err(entry) => {
+                let (index, _) = self
+                    .entries
+                    .iter()
+                    .enumerate()
+                    .max_by_key(|(_, e)| now_ms.wrapping_sub(e.last_ms))
+                    .expect("a full table has entries");
+                self.entries[index] = entry;
+                index
+            }
+        }
+    }
+}
+
+impl<const N: usize> Default for FollowingDetector<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Greatest distance between any two points
+fn span_m(points: &[Point]) -> u32 {
+    let mut span = 0;
+    for (i, a) in points.iter().enumerate() {
+        for b in &points[i + 1..] {
+            span = span.max(a.location.distance_m(&b.location));
+        }
+    }
+    span
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAC: [u8; 6] = [0x7C, 0x11, 0x22, 0x33, 0x44, 0x55];
+    const MINUTE: u32 = 60 * 1000;
+
+    /// A location `north_m` metres north of a fixed point
+    fn at(north_m: i32) -> Location {
+        Location::try_host(455_231_000 + north_m * 90, -1_226_765_000, None).unwrap()
+    }
+
+    #[test]
+    fn policy_is_range_checked() {
+        assert!(FollowingPolicy::try_new(2, 1, 1).is_some());
+        assert!(FollowingPolicy::try_new(1, 30, 400).is_none());
+        assert!(FollowingPolicy::try_new(9, 30, 400).is_none());
+        assert!(FollowingPolicy::try_new(4, 0, 400).is_none());
+        assert!(FollowingPolicy::try_new(4, 30, 0).is_none());
+    }
+
+    #[test]
+    fn device_travelling_with_the_user_is_reported_once() {
+        let policy = FollowingPolicy::DEFAULT;
+        let mut detector = FollowingDetector::<4>::new();
+        assert_eq!(detector.observe(&MAC, None, &at(0), 0, &policy), None);
+        // Heard again within the spacing: same sighting
+        assert_eq!(detector.observe(&MAC, None, &at(500), 1_000, &policy), None);
+        assert_eq!(
+            detector.observe(&MAC, None, &at(200), MINUTE, &policy),
+            None
+        );
+        assert_eq!(
+            detector.observe(&MAC, None, &at(300), 5 * MINUTE, &policy),
+            None
+        );
+
+        let follower = detector
+            .observe(&MAC, None, &at(600), 9 * MINUTE, &policy)
+            .unwrap();
+        assert_eq!(follower.sightings, 4);
+        assert!((595..605).contains(&follower.span_m), "{}", follower.span_m);
+        assert_eq!(follower.first_seen_ms, 0);
+
+        // Not again within the window
+        assert_eq!(
+            detector.observe(&MAC, None, &at(900), 13 * MINUTE, &policy),
+            None
+        );
+    }
+
+    #[test]
+    fn device_at_one_place_is_not_reported() {
+        let policy = FollowingPolicy::DEFAULT;
+        let mut detector = FollowingDetector::<4>::new();
+        for minute in 0..20 {
+            let location = at(minute as i32 * 10);
+            assert_eq!(
+                detector.observe(&MAC, None, &location, minute * MINUTE, &policy),
+                None
+            );
+        }
+    }
+
+    #[test]
+    fn old_sightings_fall_out_of_the_window() {
+        let policy = FollowingPolicy::try_new(2, 10, 400).unwrap();
+        let mut detector = FollowingDetector::<4>::new();
+        detector.observe(&MAC, None, &at(0), 0, &policy);
+        assert_eq!(
+            detector.observe(&MAC, None, &at(1_000), 11 * MINUTE, &policy),
+            None
+        );
+        assert!(detector
+            .observe(&MAC, None, &at(2_000), 12 * MINUTE, &policy)
+            .is_some());
+    }
+
+    #[test]
+    fn rotating_macs_are_followed_by_device_id() {
+        let policy = FollowingPolicy::try_new(2, 30, 400).unwrap();
+        let mut detector = FollowingDetector::<4>::new();
+        detector.observe(&MAC, Some(7), &at(0), 0, &policy);
+        let rotated = [0x7C, 0x11, 0x22, 0x33, 0x44, 0x66];
+        let follower = detector
+            .observe(&rotated, Some(7), &at(1_000), MINUTE, &policy)
+            .unwrap();
+        assert_eq!(follower.mac, rotated);
+        assert_eq!(follower.device_id, Some(7));
+    }
+}
@@ -0,0 +1,177 @@
+/// Shell-style glob matching for SSID and name signatures.
+///
+/// Patterns match the whole text: `*` matches any run of characters (including
+/// none), `?` exactly one character, and `[...]` one character from a class of
+/// literals and ranges (`[0-9A-F]`), negated with a leading `!` or `^`. A
+/// backslash matches the next character literally. An unterminated `[` is a
+/// literal. Allocation-free, with a single backtrack point, so matching is
+/// linear in practice and safe to run on every scan result.
+///
+/// Check whether `text` matches `pattern` in full. Without `case_sensitive`,
+/// ASCII letters compare case-insensitively, in classes too.
+pub fn matches(pattern: &str, text: &str, case_sensitive: bool) -> bool {
+    let eq = |a: char, b: char| {
+        if case_sensitive {
+            a == b
+        } else {
+            a.eq_ignore_ascii_case(&b)
+        }
+    };
+    let (mut p, mut t) = (pattern, text);
+    // Pattern after the last `*`, and the text position it is retried from
+    let mut star: Option<(&str, &str)> = None;
+
+    loop {
+        let mut pattern_chars = p.chars();
+        match pattern_chars.next() {
+            Some('*') => {
+                p = pattern_chars.as_str();
+                star = Some((p, t));
+                continue;
+            }
+            Some(c) => {
+                let mut text_chars = t.chars();
+                if let Some(ch) = text_chars.next() {
+                    let rest = pattern_chars.as_str();
+                    let (hit, rest) = match c {
+                        '?' => (true, rest),
+                        '[' => class(rest, ch, case_sensitive).unwrap_or((eq('[', ch), rest)),
+                        '\\' => {
+                            let mut escaped = rest.chars();
+                            match escaped.next() {
+                                Some(e) => (eq(e, ch), escaped.as_str()),
+                                None => (eq('\\', ch), rest),
+                            }
+                        }
+                        c => (eq(c, ch), rest),
+                    };
+                    if hit {
+                        p = rest;
+                        t = text_chars.as_str();
+                        continue;
+                    }
+                }
+            }
+            None if t.is_empty() => return true,
+            None => {}
+        }
+
+        // Mismatch: let the last `*` swallow one more character
+        let Some((star_p, star_t)) = star else {
+            return false;
+        };
+        let mut skipped = star_t.chars();
+        if skipped.next().is_none() {
+            return false;
+        }
+        p = star_p;
+        t = skipped.as_str();
+        star = Some((p, t));
+    }
+}
+
+/// Match `ch` against the class whose body starts at `body` (just past the
+/// `[`). Returns whether it matched and the pattern after the `]`, or `None`
+/// if the class is never closed.
+fn class(body: &str, ch: char, case_sensitive: bool) -> Option<(bool, &str)> {
+    let (negated, body) = match body.strip_prefix(['!', '^']) {
+        Some(rest) => (true, rest),
+        None => (false, body),
+    };
+    let in_range = |lo: char, hi: char| {
+        if case_sensitive {
+            (lo..=hi).contains(&ch)
+        } else {
+            [ch, ch.to_ascii_lowercase(), ch.to_ascii_uppercase()]
+                .iter()
+                .any(|c| (lo..=hi).contains(c))
+        }
+    };
+
+    let mut chars = body.chars();
+    let mut hit = false;
+    let mut first = true;
+    loop {
+        let c = chars.next()?;
+        // A `]` right after the opening bracket is a literal
+        if c == ']' && !first {
+            return Some((hit != negated, chars.as_str()));
+        }
+        first = false;
+        let mut lookahead = chars.clone();
+        match (lookahead.next(), lookahead.clone().next()) {
+            (Some('-'), Some(hi)) if hi != ']' => {
+                lookahead.next();
+                chars = lookahead;
+                hit |= in_range(c, hi);
+            }
+            _ => hit |= in_range(c, c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ── Wildcards ───────────────────────────────────────────────────
+
+    #[test]
+    fn literal_patterns_match_whole_text() {
+        assert!(matches("Flock", "Flock", true));
+        assert!(!matches("Flock", "Flock-1", true));
+        assert!(!matches("Flock-1", "Flock", true));
+        assert!(matches("", "", true));
+        assert!(!matches("", "x", true));
+    }
+
+    #[test]
+    fn star_and_question_mark() {
+        assert!(matches("DJI-*", "DJI-", true));
+        assert!(matches("DJI-*", "DJI-Mavic3-91AF", true));
+        assert!(!matches("DJI-*", "xDJI-Mavic", true));
+        assert!(matches("*cam*", "my-cam-01", true));
+        assert!(matches("AXON-????", "AXON-7F2C", true));
+        assert!(!matches("AXON-????", "AXON-7F2", true));
+        assert!(!matches("AXON-????", "AXON-7F2C1", true));
+        // Needs backtracking past an early partial match
+        assert!(matches("*-cam-??", "ab-cam-x-cam-01", true));
+        assert!(matches("**a*", "bba", true));
+    }
+
+    #[test]
+    fn case_insensitive_matching() {
+        assert!(!matches("dji-*", "DJI-Air", true));
+        assert!(matches("dji-*", "DJI-Air", false));
+        assert!(matches("[a-f]?", "E1", false));
+        assert!(!matches("[a-f]?", "E1", true));
+    }
+
+    #[test]
+    fn multibyte_characters_count_once() {
+        assert!(matches("Caf?", "Café", true));
+        assert!(matches("*é", "Café", true));
+    }
+
+    // ── Classes and escapes ─────────────────────────────────────────
+
+    #[test]
+    fn character_classes() {
+        let hex6 = "Flock-[0-9A-Fa-f][0-9A-Fa-f][0-9A-Fa-f][0-9A-Fa-f][0-9A-Fa-f][0-9A-Fa-f]";
+        assert!(matches(hex6, "Flock-A1b2C3", true));
+        assert!(!matches(hex6, "Flock-A1B2CG", true));
+        assert!(matches("[!0-9]*", "Cam1", true));
+        assert!(!matches("[^0-9]*", "1Cam", true));
+        assert!(matches("[]x]", "]", true));
+        assert!(matches("[a-]", "-", true));
+    }
+
+    #[test]
+    fn escapes_and_unterminated_classes() {
+        assert!(matches(r"Who\?", "Who?", true));
+        assert!(!matches(r"Who\?", "Who!", true));
+        assert!(matches(r"\*", "*", true));
+        assert!(matches("[abc", "[abc", true));
+        assert!(matches(r"end\", r"end\", true));
+    }
+}
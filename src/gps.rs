@@ -0,0 +1,577 @@
+/// NMEA 0183 GPS sentence parsing.
+///
+/// A GPS module on the board's GPS header (or a USB receiver on a host)
+/// streams ASCII sentences, one per line. [`GpsReader`] takes the bytes as
+/// they arrive, like [`crate::comm::LineReader`], and turns RMC and GGA
+/// sentences from any talker (`$GP`, `$GN`, `$GL`, ...) into [`GpsFix`]es.
+/// RMC carries the date and ground speed, GGA the altitude and HDOP; a
+/// fix merges what the two sentences of the same epoch reported. Values are
+/// fixed point, as in [`crate::remote_id`], so parsing needs no floats and
+/// gives the same result on every target.
+use serde::Serialize;
+
+use crate::error::AirhoundError;
+
+/// Longest sentence accepted: 82 characters by the standard, with some
+/// slack for receivers that exceed it
+pub const MAX_SENTENCE_LEN: usize = 96;
+
+/// Milliseconds in a day
+const DAY_MS: u32 = 24 * 60 * 60 * 1000;
+
+/// Centimetres per second in 1000 knots
+const CM_S_PER_KILOKNOT: u64 = 51_444;
+
+/// A position fix
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GpsFix {
+    /// Latitude in 1e-7 degrees, north positive
+    pub lat: i32,
+    /// Longitude in 1e-7 degrees, east positive
+    pub lon: i32,
+    /// Altitude above mean sea level in centimetres, from GGA
+    pub alt: Option<i32>,
+    /// Speed over ground in centimetres per second, from RMC
+    pub speed: Option<u32>,
+    /// Horizontal dilution of precision in hundredths, from GGA
+    pub hdop: Option<u16>,
+    /// Milliseconds since the Unix epoch, once an RMC sentence gave the date
+    pub timestamp: Option<u64>,
+}
+
+/// Fix quality, from the GGA quality indicator
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FixQuality {
+    /// No fix (0), or no sentence received yet
+    None,
+    /// Autonomous GNSS fix (1, or 3 for PPS)
+    Gps,
+    /// Differential fix: DGPS or SBAS (2)
+    Dgps,
+    /// RTK, fixed or float (4, 5)
+    Rtk,
+    /// Dead reckoning (6)
+    Estimated,
+}
+
+impl FixQuality {
+    /// The GGA quality field; unknown indicators are a plain fix
+    fn from_field(field: &[u8]) -> Self {
+        match field {
+            b"" | b"0" => FixQuality::None,
+            b"2" => FixQuality::Dgps,
+            b"4" | b"5" => FixQuality::Rtk,
+            b"6" => FixQuality::Estimated,
+            _ => FixQuality::Gps,
+        }
+    }
+}
+
+/// Receiver state for `status` messages, so companions can tell why scans
+/// carry no coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct GpsStatus {
+    pub fix: FixQuality,
+    /// Satellites in use
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub satellites: Option<u8>,
+    /// Horizontal dilution of precision in hundredths
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hdop: Option<u16>,
+    /// Seconds since the last fix; omitted if there has been none. Filled
+    /// in by the caller, which owns the clock.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fix_age: Option<u32>,
+}
+
+/// Recommended minimum data (RMC)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rmc {
+    /// UTC time of day in milliseconds
+    pub time: u32,
+    /// Days since the Unix epoch
+    pub date: Option<u32>,
+    /// `(lat, lon)`; none while the receiver has no valid fix
+    pub position: Option<(i32, i32)>,
+    /// Speed over ground in centimetres per second
+    pub speed: Option<u32>,
+}
+
+/// Fix data (GGA)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Gga {
+    /// UTC time of day in milliseconds
+    pub time: u32,
+    /// `(lat, lon)`; none without a fix
+    pub position: Option<(i32, i32)>,
+    pub quality: FixQuality,
+    /// Satellites in use
+    pub satellites: Option<u8>,
+    /// Horizontal dilution of precision in hundredths
+    pub hdop: Option<u16>,
+    /// Altitude above mean sea level in centimetres
+    pub alt: Option<i32>,
+}
+
+/// A parsed sentence
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sentence {
+    Rmc(Rmc),
+    Gga(Gga),
+}
+
+impl Sentence {
+    pub fn time(&self) -> u32 {
+        match self {
+            Sentence::Rmc(rmc) => rmc.time,
+            Sentence::Gga(gga) => gga.time,
+        }
+    }
+}
+
+/// Parse one sentence, without its line ending. The checksum is verified
+/// when present ([`AirhoundError::BadChecksum`]); sentences other than RMC
+/// and GGA are [`AirhoundError::Unsupported`].
+pub fn parse_sentence(line: &[u8]) -> Result<Sentence, AirhoundError> {
+    let body = match line {
+        [b'$', body @ ..] => body,
+        _ => return Err(AirhoundError::Malformed),
+    };
+    let body = match body.iter().rposition(|&b| b == b'*') {
+        Some(star) => {
+            let expected = hex_byte(&body[star + 1..]).ok_or(AirhoundError::Malformed)?;
+            let body = &body[..star];
+            if body.iter().fold(0, |sum, b| sum ^ b) != expected {
+                return Err(AirhoundError::BadChecksum);
+            }
+            body
+        }
+        None => body,
+    };
+
+    let mut fields = body.split(|&b| b == b',');
+    let address = fields.next().unwrap_or_default();
+    let [_, _, kind @ ..] = address else {
+        return Err(AirhoundError::Malformed);
+    };
+    let mut field = || fields.next().unwrap_or_default();
+    match kind {
+        b"RMC" => {
+            let time = time_of_day(field()).ok_or(AirhoundError::Malformed)?;
+            let valid = field() == b"A";
+            let position = position(field(), field(), field(), field());
+            let speed = fixed(field(), 3).and_then(|knots| {
+                // Thousandths of a knot
+                let cm_s = u64::try_from(knots).ok()? * CM_S_PER_KILOKNOT / 1_000_000;
+                u32::try_from(cm_s).ok()
+            });
+            let _course = field();
+            let date = date(field());
+            Ok(Sentence::Rmc(Rmc {
+                time,
+                date,
+                position: position.filter(|_| valid),
+                speed,
+            }))
+        }
+        b"GGA" => {
+            let time = time_of_day(field()).ok_or(AirhoundError::Malformed)?;
+            let position = position(field(), field(), field(), field());
+            let quality = FixQuality::from_field(field());
+            let satellites = digits(field()).and_then(|n| u8::try_from(n).ok());
+            let hdop = fixed(field(), 2).and_then(|n| u16::try_from(n).ok());
+            let alt = fixed(field(), 2).and_then(|n| i32::try_from(n).ok());
+            Ok(Sentence::Gga(Gga {
+                time,
+                position: position.filter(|_| quality != FixQuality::None),
+                quality,
+                satellites,
+                hdop,
+                alt,
+            }))
+        }
+        _ => Err(AirhoundError::Unsupported),
+    }
+}
+
+/// NMEA byte stream to fixes.
+pub struct GpsReader {
+    buf: [u8; MAX_SENTENCE_LEN],
+    pos: usize,
+    /// The current line overflowed the buffer; drop bytes until its end
+    overflowed: bool,
+    /// Date and time of day of the latest RMC sentence
+    date: Option<(u32, u32)>,
+    /// Time of day and the last fix reported, to merge the next sentence of
+    /// the same epoch into
+    last: Option<(u32, GpsFix)>,
+    /// Receiver state from the latest sentences, fix or not
+    status: GpsStatus,
+}
+
+impl GpsReader {
+    pub const fn new() -> Self {
+        Self {
+            buf: [0; MAX_SENTENCE_LEN],
+            pos: 0,
+            overflowed: false,
+            date: None,
+            last: None,
+            status: GpsStatus {
+                fix: FixQuality::None,
+                satellites: None,
+                hdop: None,
+                fix_age: None,
+            },
+        }
+    }
+
+    /// Fix quality, satellites and HDOP as last reported; `fix_age` is left
+    /// for the caller.
+    pub fn status(&self) -> GpsStatus {
+        self.status
+    }
+
+    /// Feed a byte. At the end of a line, returns the fix it completes, or
+    /// why the line was dropped: [`AirhoundError::Unsupported`] for other
+    /// sentence types, which callers usually ignore. Sentences without a
+    /// valid fix return nothing.
+    pub fn feed(&mut self, byte: u8) -> Option<Result<GpsFix, AirhoundError>> {
+        if byte == b'\n' || byte == b'\r' {
+            let len = core::mem::take(&mut self.pos);
+            if core::mem::take(&mut self.overflowed) {
+                Some(Err(AirhoundError::BufferTooSmall))
+            } else if len > 0 {
+                match parse_sentence(&self.buf[..len]) {
+                    Ok(sentence) => self.update(sentence).map(Ok),
+                    Err(e) => Some(Err(e)),
+                }
+            } else {
+                None
+            }
+        } else if self.overflowed {
+            None
+        } else if self.pos < self.buf.len() {
+            self.buf[self.pos] = byte;
+            self.pos += 1;
+            None
+        } else {
+            self.overflowed = true;
+            None
+        }
+    }
+
+    /// Fold a sentence into the fix of its epoch.
+    fn update(&mut self, sentence: Sentence) -> Option<GpsFix> {
+        let time = sentence.time();
+        let mut fix = match sentence {
+            Sentence::Rmc(rmc) => {
+                if let Some(date) = rmc.date {
+                    self.date = Some((date, time));
+                }
+                // RMC only says whether there is a fix; GGA says which kind
+                if rmc.position.is_none() {
+                    self.status.fix = FixQuality::None;
+                } else if self.status.fix == FixQuality::None {
+                    self.status.fix = FixQuality::Gps;
+                }
+                let (lat, lon) = rmc.position?;
+                GpsFix {
+                    lat,
+                    lon,
+                    alt: None,
+                    speed: rmc.speed,
+                    hdop: None,
+                    timestamp: None,
+                }
+            }
+            Sentence::Gga(gga) => {
+                self.status.fix = gga.quality;
+                self.status.satellites = gga.satellites;
+                self.status.hdop = gga.hdop;
+                let (lat, lon) = gga.position?;
+                GpsFix {
+                    lat,
+                    lon,
+                    alt: gga.alt,
+                    speed: None,
+                    hdop: gga.hdop,
+                    timestamp: None,
+                }
+            }
+        };
+        // A time of day before the RMC sentence's is past midnight
+        fix.timestamp = self.date.map(|(date, date_time)| {
+            let days = date as u64 + (time < date_time) as u64;
+            days * DAY_MS as u64 + time as u64
+        });
+        if let Some((_, prev)) = self.last.filter(|(last_time, _)| *last_time == time) {
+            fix.alt = fix.alt.or(prev.alt);
+            fix.speed = fix.speed.or(prev.speed);
+            fix.hdop = fix.hdop.or(prev.hdop);
+        }
+        self.last = Some((time, fix));
+        Some(fix)
+    }
+}
+
+impl Default for GpsReader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Two hex digits
+fn hex_byte(text: &[u8]) -> Option<u8> {
+    let [hi, lo] = text else {
+        return None;
+    };
+    let digit = |b: &u8| (*b as char).to_digit(16);
+    Some((digit(hi)? * 16 + digit(lo)?) as u8)
+}
+
+/// A run of decimal digits
+fn digits(text: &[u8]) -> Option<u32> {
+    if text.is_empty() || text.len() > 9 {
+        return None;
+    }
+    text.iter().try_fold(0u32, |n, b| {
+        b.is_ascii_digit().then(|| n * 10 + (b - b'0') as u32)
+    })
+}
+
+/// A decimal number scaled by `10^scale`, extra fraction digits dropped
+fn fixed(text: &[u8], scale: u32) -> Option<i64> {
+    let (negative, text) = match text {
+        [b'-', rest @ ..] => (true, rest),
+        _ => (false, text),
+    };
+    let (int, frac) = match text.iter().position(|&b| b == b'.') {
+        Some(dot) => (&text[..dot], &text[dot + 1..]),
+        None => (text, &[][..]),
+    };
+    let int = if int.is_empty() && !frac.is_empty() {
+        0
+    } else {
+        digits(int)?
+    };
+    let mut value = int as i64;
+    for i in 0..scale as usize {
+        let digit = match frac.get(i) {
+            Some(b) if b.is_ascii_digit() => (b - b'0') as i64,
+            Some(_) => return None,
+            None => 0,
+        };
+        value = value * 10 + digit;
+    }
+    Some(if negative { -value } else { value })
+}
+
+/// `hhmmss[.sss]` as milliseconds
+fn time_of_day(text: &[u8]) -> Option<u32> {
+    let (hm, seconds) = (text.get(..4)?, text.get(4..)?);
+    let hours = digits(&hm[..2])?;
+    let minutes = digits(&hm[2..])?;
+    let ms = u32::try_from(fixed(seconds, 3)?).ok()?;
+    (hours < 24 && minutes < 60 && ms < 61_000).then(|| (hours * 60 + minutes) * 60_000 + ms)
+}
+
+/// `ddmmyy` as days since the Unix epoch. GPS dates from 1980, so years
+/// from 80 up are 19yy and the rest 20yy.
+fn date(text: &[u8]) -> Option<u32> {
+    if text.len() != 6 {
+        return None;
+    }
+    let day = digits(&text[..2])?;
+    let month = digits(&text[2..4])?;
+    let year = match digits(&text[4..])? {
+        yy @ 80.. => 1900 + yy,
+        yy => 2000 + yy,
+    };
+    if !(1..=31).contains(&day) || !(1..=12).contains(&month) {
+        return None;
+    }
+    // Days from civil (Howard Hinnant), years starting in March
+    let (y, m) = if month <= 2 {
+        (year - 1, month + 9)
+    } else {
+        (year, month - 3)
+    };
+    let era = y / 400;
+    let year_of_era = y - era * 400;
+    let day_of_year = (153 * m + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    Some(era * 146_097 + day_of_era - 719_468)
+}
+
+/// `ddmm.mmmm,N,dddmm.mmmm,E` as 1e-7 degrees
+fn position(lat: &[u8], ns: &[u8], lon: &[u8], ew: &[u8]) -> Option<(i32, i32)> {
+    let lat = match ns {
+        b"N" => coordinate(lat, 90)?,
+        b"S" => -coordinate(lat, 90)?,
+        _ => return None,
+    };
+    let lon = match ew {
+        b"E" => coordinate(lon, 180)?,
+        b"W" => -coordinate(lon, 180)?,
+        _ => return None,
+    };
+    Some((lat, lon))
+}
+
+/// Degrees and decimal minutes as 1e-7 degrees, at most `max` degrees
+fn coordinate(text: &[u8], max: i64) -> Option<i32> {
+    const E7: i64 = 10_000_000;
+    if text.first() == Some(&b'-') {
+        return None;
+    }
+    // Minutes are the two digits before the point
+    let value = fixed(text, 7)?;
+    let (degrees, minutes) = (value / (100 * E7), value % (100 * E7));
+    if minutes >= 60 * E7 {
+        return None;
+    }
+    let e7 = degrees * E7 + (minutes + 30) / 60;
+    (e7 <= max * E7).then_some(e7 as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RMC: &str = "$GPRMC,123519,A,4807.038,N,01131.000,E,022.4,084.4,230394,003.1,W*6A";
+    const GGA: &str = "$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47";
+
+    fn feed(reader: &mut GpsReader, line: &str) -> Option<Result<GpsFix, AirhoundError>> {
+        let mut out = None;
+        for &b in line.as_bytes().iter().chain(b"\r\n") {
+            if let Some(result) = reader.feed(b) {
+                out = Some(result);
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn rmc_and_gga_sentences() {
+        let Ok(Sentence::Rmc(rmc)) = parse_sentence(RMC.as_bytes()) else {
+            panic!("not RMC");
+        };
+        // 48°07.038' N, 11°31.000' E
+        assert_eq!(rmc.position, Some((481_173_000, 115_166_667)));
+        assert_eq!(rmc.time, (12 * 3600 + 35 * 60 + 19) * 1000);
+        // 1994-03-23
+        assert_eq!(rmc.date, Some(8_847));
+        assert_eq!(rmc.speed, Some(1_152));
+
+        let Ok(Sentence::Gga(gga)) = parse_sentence(GGA.as_bytes()) else {
+            panic!("not GGA");
+        };
+        assert_eq!(gga.position, rmc.position);
+        assert_eq!(gga.quality, FixQuality::Gps);
+        assert_eq!(gga.satellites, Some(8));
+        assert_eq!(gga.hdop, Some(90));
+        assert_eq!(gga.alt, Some(54_540));
+    }
+
+    #[test]
+    fn bad_sentences_are_rejected() {
+        let corrupted = RMC.replace("4807", "4808");
+        assert_eq!(
+            parse_sentence(corrupted.as_bytes()),
+            Err(AirhoundError::BadChecksum)
+        );
+        assert_eq!(
+            parse_sentence(b"$GPGSV,1,1,00*79"),
+            Err(AirhoundError::Unsupported)
+        );
+        assert_eq!(parse_sentence(b"GPRMC,"), Err(AirhoundError::Malformed));
+        // No checksum is accepted; southern and western hemispheres
+        let Ok(Sentence::Gga(gga)) =
+            parse_sentence(b"$GNGGA,000001.00,3351.600,S,15112.600,W,2,10,1.25,-3.5,M,,M,,")
+        else {
+            panic!("not GGA");
+        };
+        assert_eq!(gga.position, Some((-338_600_000, -1_512_100_000)));
+        assert_eq!(gga.quality, FixQuality::Dgps);
+        assert_eq!(gga.alt, Some(-350));
+        // No fix yet
+        let Ok(Sentence::Rmc(rmc)) = parse_sentence(b"$GPRMC,000001,V,,,,,,,010125,,") else {
+            panic!("not RMC");
+        };
+        assert_eq!(rmc.position, None);
+        assert_eq!(rmc.date, Some(20_089));
+    }
+
+    #[test]
+    fn reader_merges_an_epoch() {
+        let mut reader = GpsReader::new();
+        assert_eq!(
+            feed(&mut reader, "$GPGSV,1,1,00*79"),
+            Some(Err(AirhoundError::Unsupported))
+        );
+
+        let fix = feed(&mut reader, RMC).unwrap().unwrap();
+        assert_eq!(fix.speed, Some(1_152));
+        assert_eq!(fix.alt, None);
+        let unix_day = 764_380_800_000;
+        assert_eq!(fix.timestamp, Some(unix_day + 45_319_000));
+
+        // GGA of the same epoch adds altitude and HDOP to the RMC fix
+        let fix = feed(&mut reader, GGA).unwrap().unwrap();
+        assert_eq!((fix.lat, fix.lon), (481_173_000, 115_166_667));
+        assert_eq!(fix.speed, Some(1_152));
+        assert_eq!(fix.alt, Some(54_540));
+        assert_eq!(fix.hdop, Some(90));
+        assert_eq!(fix.timestamp, Some(unix_day + 45_319_000));
+
+        // Past midnight, before the next RMC
+        let gga = "$GPGGA,000000,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,";
+        let fix = feed(&mut reader, gga).unwrap().unwrap();
+        assert_eq!(fix.timestamp, Some(unix_day + DAY_MS as u64));
+        assert_eq!(fix.speed, None);
+    }
+
+    #[test]
+    fn reader_tracks_receiver_status() {
+        let mut reader = GpsReader::new();
+        assert_eq!(reader.status().fix, FixQuality::None);
+
+        // Searching: satellites in view but no fix
+        let searching = "$GPGGA,123518,,,,,0,03,25.5,,M,,M,,";
+        assert_eq!(feed(&mut reader, searching), None);
+        let status = reader.status();
+        assert_eq!(status.fix, FixQuality::None);
+        assert_eq!((status.satellites, status.hdop), (Some(3), Some(2_550)));
+
+        // A valid RMC is a fix until GGA says which kind
+        feed(&mut reader, RMC);
+        assert_eq!(reader.status().fix, FixQuality::Gps);
+        let rtk = "$GPGGA,123519,4807.038,N,01131.000,E,4,12,0.6,545.4,M,46.9,M,,";
+        feed(&mut reader, rtk);
+        let status = reader.status();
+        assert_eq!(status.fix, FixQuality::Rtk);
+        assert_eq!((status.satellites, status.hdop), (Some(12), Some(60)));
+
+        // Lost
+        feed(&mut reader, "$GPRMC,123520,V,,,,,,,230394,,");
+        assert_eq!(reader.status().fix, FixQuality::None);
+
+        let mut buf = [0u8; 64];
+        let len = serde_json_core::to_slice(&reader.status(), &mut buf).unwrap();
+        assert_eq!(&buf[..len], br#"{"fix":"none","satellites":12,"hdop":60}"#);
+    }
+
+    #[test]
+    fn long_lines_are_dropped() {
+        let mut reader = GpsReader::new();
+        let long = [b'x'; MAX_SENTENCE_LEN + 1];
+        let long = core::str::from_utf8(&long).unwrap();
+        assert_eq!(
+            feed(&mut reader, long),
+            Some(Err(AirhoundError::BufferTooSmall))
+        );
+        assert!(feed(&mut reader, RMC).unwrap().is_ok());
+    }
+}
@@ -0,0 +1,253 @@
+/// GPX export for std hosts.
+///
+/// Writes a drive as a GPX 1.1 file that mapping tools (JOSM, QGIS, Google
+/// Earth, OsmAnd) open directly: the GPS track as one track segment, and a
+/// waypoint at every rule match, named after the rule and annotated with
+/// the RSSI it was heard at. Positions are the device's fixed point 1e-7
+/// degrees ([`crate::gps::GpsFix`], [`crate::location::Location`]), written
+/// exactly as decimal degrees. Output depends only on the inputs, like the
+/// evidence bundle's.
+use std::io::{self, Write};
+
+use crate::gps::GpsFix;
+use crate::protocol::VERSION;
+
+/// GPX 1.1 namespace
+pub const GPX_NS: &str = "http://www.topografix.com/GPX/1/1";
+
+/// A rule match to mark on the map
+#[derive(Debug, Clone, PartialEq)]
+pub struct Waypoint {
+    /// Milliseconds since the Unix epoch; none for detections stamped with
+    /// uptime only
+    pub ts_ms: Option<u64>,
+    /// Latitude in 1e-7 degrees, as in a scan message's `loc`
+    pub lat: i32,
+    /// Longitude in 1e-7 degrees
+    pub lon: i32,
+    /// Name of the matched rule
+    pub rule: String,
+    pub rssi: i8,
+    /// MAC of the matched device, if it should be shown
+    pub mac: Option<String>,
+}
+
+/// A drive, ready to be written as GPX.
+#[derive(Debug, Clone, Default)]
+pub struct Gpx {
+    /// Name of the file and its track, e.g. "Commute 2026-01-01"
+    pub name: String,
+    /// GPS fixes of the drive, oldest first. Fixes without a date are
+    /// written without a time.
+    pub track: Vec<GpsFix>,
+    /// Rule matches along the way
+    pub waypoints: Vec<Waypoint>,
+}
+
+impl Gpx {
+    /// Write the GPX document. Waypoints come before the track, as the GPX
+    /// schema orders them.
+    pub fn write_gpx<W: Write>(&self, mut out: W) -> io::Result<W> {
+        let name = escape(&self.name);
+        writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            out,
+            r#"<gpx version="1.1" creator="AirHound {}" xmlns="{}">"#,
+            VERSION, GPX_NS
+        )?;
+        writeln!(out, "  <metadata>")?;
+        writeln!(out, "    <name>{}</name>", name)?;
+        if let Some(start) = self.track.iter().find_map(|fix| fix.timestamp) {
+            writeln!(out, "    <time>{}</time>", iso8601(start))?;
+        }
+        writeln!(out, "  </metadata>")?;
+
+        for wpt in &self.waypoints {
+            writeln!(
+                out,
+                r#"  <wpt lat="{}" lon="{}">"#,
+                degrees(wpt.lat),
+                degrees(wpt.lon)
+            )?;
+            if let Some(ts_ms) = wpt.ts_ms {
+                writeln!(out, "    <time>{}</time>", iso8601(ts_ms))?;
+            }
+            writeln!(out, "    <name>{}</name>", escape(&wpt.rule))?;
+            match &wpt.mac {
+                Some(mac) => writeln!(
+                    out,
+                    "    <desc>{}, RSSI {} dBm</desc>",
+                    escape(mac),
+                    wpt.rssi
+                )?,
+                None => writeln!(out, "    <desc>RSSI {} dBm</desc>", wpt.rssi)?,
+            }
+            writeln!(out, "  </wpt>")?;
+        }
+
+        if !self.track.is_empty() {
+            writeln!(out, "  <trk>")?;
+            writeln!(out, "    <name>{}</name>", name)?;
+            writeln!(out, "    <trkseg>")?;
+            for fix in &self.track {
+                let (lat, lon) = (degrees(fix.lat), degrees(fix.lon));
+                match fix.timestamp {
+                    Some(ts_ms) => writeln!(
+                        out,
+                        r#"      <trkpt lat="{}" lon="{}"><time>{}</time></trkpt>"#,
+                        lat,
+                        lon,
+                        iso8601(ts_ms)
+                    )?,
+                    None => writeln!(out, r#"      <trkpt lat="{}" lon="{}"/>"#, lat, lon)?,
+                }
+            }
+            writeln!(out, "    </trkseg>")?;
+            writeln!(out, "  </trk>")?;
+        }
+        writeln!(out, "</gpx>")?;
+        Ok(out)
+    }
+}
+
+/// 1e-7 degrees as decimal degrees with all seven places
+fn degrees(e7: i32) -> String {
+    let abs = (e7 as i64).abs();
+    let sign = if e7 < 0 { "-" } else { "" };
+    format!("{}{}.{:07}", sign, abs / 10_000_000, abs % 10_000_000)
+}
+
+/// XML text and attribute escaping
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Unix milliseconds as an ISO 8601 UTC timestamp with milliseconds
+fn iso8601(ts_ms: u64) -> String {
+    let (days, ms) = (ts_ms / 86_400_000, ts_ms % 86_400_000);
+    // Civil from days (Howard Hinnant), years starting in March
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + (month <= 2) as u64;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        ms % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fix(lat: i32, lon: i32, timestamp: Option<u64>) -> GpsFix {
+        GpsFix {
+            lat,
+            lon,
+            alt: None,
+            speed: None,
+            hdop: None,
+            timestamp,
+        }
+    }
+
+    fn drive() -> Gpx {
+        Gpx {
+            name: "Commute <AM>".into(),
+            track: vec![
+                fix(455_231_000, -1_226_765_000, Some(1_767_225_600_000)),
+                fix(455_232_000, -1_226_766_000, Some(1_767_225_601_000)),
+            ],
+            waypoints: vec![Waypoint {
+                ts_ms: Some(1_767_225_600_250),
+                lat: 455_231_000,
+                lon: -1_226_765_000,
+                rule: "flock-camera".into(),
+                rssi: -62,
+                mac: Some("B4:1E:52:AA:BB:CC".into()),
+            }],
+        }
+    }
+
+    fn gpx(drive: &Gpx) -> String {
+        String::from_utf8(drive.write_gpx(Vec::new()).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn iso8601_dates() {
+        assert_eq!(iso8601(0), "1970-01-01T00:00:00.000Z");
+        assert_eq!(iso8601(1_767_225_600_250), "2026-01-01T00:00:00.250Z");
+        // Leap day
+        assert_eq!(
+            iso8601(951_782_400_000 + 45_296_789),
+            "2000-02-29T12:34:56.789Z"
+        );
+    }
+
+    #[test]
+    fn degrees_are_exact() {
+        assert_eq!(degrees(455_231_000), "45.5231000");
+        assert_eq!(degrees(-1_226_765_000), "-122.6765000");
+        assert_eq!(degrees(-5), "-0.0000005");
+        assert_eq!(degrees(i32::MIN), "-214.7483648");
+    }
+
+    #[test]
+    fn undated_fixes_have_no_time() {
+        let mut drive = drive();
+        drive.track[0].timestamp = None;
+        let out = gpx(&drive);
+        assert!(out.contains(r#"<trkpt lat="45.5231000" lon="-122.6765000"/>"#));
+        // The metadata time is the first dated fix
+        assert!(out.contains("    <time>2026-01-01T00:00:01.000Z</time>"));
+    }
+
+    #[test]
+    fn waypoints_precede_the_track() {
+        let out = gpx(&drive());
+        assert!(out.starts_with(r#"<?xml version="1.0" encoding="UTF-8"?>"#));
+        assert!(out.contains(r#"<wpt lat="45.5231000" lon="-122.6765000">"#));
+        assert!(out.contains("<time>2026-01-01T00:00:00.250Z</time>"));
+        assert!(out.contains("<name>flock-camera</name>"));
+        assert!(out.contains("<desc>B4:1E:52:AA:BB:CC, RSSI -62 dBm</desc>"));
+        assert!(out.contains("<name>Commute &lt;AM&gt;</name>"));
+        assert_eq!(out.matches("<trkpt ").count(), 2);
+        assert!(out.find("<wpt ").unwrap() < out.find("<trk>").unwrap());
+        assert!(out.ends_with("</gpx>\n"));
+    }
+
+    #[test]
+    fn waypoints_without_track_or_time() {
+        let mut drive = drive();
+        drive.track.clear();
+        drive.waypoints[0].ts_ms = None;
+        drive.waypoints[0].mac = None;
+        let out = gpx(&drive);
+        assert!(!out.contains("<trk>"));
+        assert!(!out.contains("<time>"));
+        assert!(out.contains("<desc>RSSI -62 dBm</desc>"));
+    }
+}
@@ -0,0 +1,328 @@
+/// Subsystem liveness monitoring.
+///
+/// Long-running subsystems (WiFi sniffer callback, channel hopper, BLE
+/// scanner) check in with a timestamp whenever they make progress. A periodic
+/// supervisor calls [`HealthMonitor::poll`] to find subsystems that have gone
+/// quiet for longer than their timeout, so a dead scan path is reported instead
+/// of looking like an empty neighborhood.
+///
+/// All state is atomic so check-ins are safe from ISR context.
+use core::sync::atomic::{AtomicU32, AtomicU8, Ordering};
+
+/// A monitored subsystem.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Subsystem {
+    /// Promiscuous-mode receive callback — checks in on every frame
+    WifiSniffer,
+    /// WiFi channel hop task — checks in on every hop
+    ChannelHop,
+    /// BLE scanner — checks in on every advertisement report batch
+    BleScanner,
+}
+
+impl Subsystem {
+    pub const COUNT: usize = 3;
+
+    pub const ALL: [Subsystem; Self::COUNT] = [
+        Subsystem::WifiSniffer,
+        Subsystem::ChannelHop,
+        Subsystem::BleScanner,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Subsystem::WifiSniffer => "wifi_sniffer",
+            Subsystem::ChannelHop => "channel_hop",
+            Subsystem::BleScanner => "ble_scanner",
+        }
+    }
+
+    /// Silence longer than this marks the subsystem stalled.
+    ///
+    /// Radio timeouts are generous: beacons are nearly everywhere, but a
+    /// remote area can legitimately go quiet for a while.
+    pub const fn timeout_ms(self) -> u32 {
+        match self {
+            Subsystem::WifiSniffer => 30_000,
+            Subsystem::ChannelHop => 5_000,
+            Subsystem::BleScanner => 60_000,
+        }
+    }
+
+    /// Silence longer than this warrants restarting the device, or `None`
+    /// if a stall should only be reported.
+    ///
+    /// Only the channel hop task checks in on its own schedule. The radio
+    /// paths check in per frame or advertisement, so their silence may just
+    /// be a quiet area or a parked channel, and a reset wouldn't change that.
+    pub const fn restart_after_ms(self) -> Option<u32> {
+        match self {
+            Subsystem::WifiSniffer => None,
+            Subsystem::ChannelHop => Some(30_000),
+            Subsystem::BleScanner => None,
+        }
+    }
+
+    const fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// A set of subsystems, stored as a bitmask.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SubsystemSet(u8);
+
+impl SubsystemSet {
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    pub fn contains(self, subsystem: Subsystem) -> bool {
+        self.0 & subsystem.bit() != 0
+    }
+
+    pub fn insert(&mut self, subsystem: Subsystem) {
+        self.0 |= subsystem.bit();
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn iter(self) -> impl Iterator<Item = Subsystem> {
+        Subsystem::ALL
+            .into_iter()
+            .filter(move |s| self.contains(*s))
+    }
+}
+
+/// Changes detected by one [`HealthMonitor::poll`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HealthChanges {
+    /// Subsystems that crossed their timeout since the last poll
+    pub stalled: SubsystemSet,
+    /// Previously stalled subsystems that checked in again
+    pub recovered: SubsystemSet,
+    /// Subsystems silent past their restart threshold
+    pub needs_restart: SubsystemSet,
+}
+
+impl HealthChanges {
+    pub fn is_empty(&self) -> bool {
+        self.stalled.is_empty() && self.recovered.is_empty() && self.needs_restart.is_empty()
+    }
+}
+
+/// Tracks the last check-in time of each [`Subsystem`].
+///
+/// Timestamps are milliseconds since boot (`u32`, wrapping). Every subsystem
+/// starts as if it checked in at time 0, so one that never starts is reported
+/// once its timeout has passed.
+pub struct HealthMonitor {
+    last_seen: [AtomicU32; Subsystem::COUNT],
+    stalled: AtomicU8,
+    /// Subsystems switched off on purpose (e.g. WiFi in low-power mode)
+    suspended: AtomicU8,
+}
+
+impl HealthMonitor {
+    pub const fn new() -> Self {
+        Self {
+            last_seen: [AtomicU32::new(0), AtomicU32::new(0), AtomicU32::new(0)],
+            stalled: AtomicU8::new(0),
+            suspended: AtomicU8::new(0),
+        }
+    }
+
+    /// Exclude `subsystem` from stall checks while it is deliberately off.
+    /// Resuming counts as a check-in at `now_ms`, so it gets a full timeout
+    /// to restart.
+    pub fn set_suspended(&self, subsystem: Subsystem, suspended: bool, now_ms: u32) {
+        if suspended {
+            self.suspended.fetch_or(subsystem.bit(), Ordering::Relaxed);
+        } else {
+            self.check_in(subsystem, now_ms);
+            self.suspended
+                .fetch_and(!subsystem.bit(), Ordering::Relaxed);
+        }
+    }
+
+    /// Record that `subsystem` made progress at `now_ms`.
+    pub fn check_in(&self, subsystem: Subsystem, now_ms: u32) {
+        self.last_seen[subsystem as usize].store(now_ms, Ordering::Relaxed);
+    }
+
+    /// Milliseconds since `subsystem` last checked in.
+    pub fn idle_ms(&self, subsystem: Subsystem, now_ms: u32) -> u32 {
+        now_ms.wrapping_sub(self.last_seen[subsystem as usize].load(Ordering::Relaxed))
+    }
+
+    /// Subsystems currently considered stalled.
+    pub fn stalled(&self) -> SubsystemSet {
+        SubsystemSet(self.stalled.load(Ordering::Relaxed))
+    }
+
+    /// Re-evaluate all subsystems. Stalls and recoveries are edge-triggered:
+    /// each is reported by exactly one poll.
+    pub fn poll(&self, now_ms: u32) -> HealthChanges {
+        let previous = self.stalled();
+        let suspended = SubsystemSet(self.suspended.load(Ordering::Relaxed));
+        let mut current = SubsystemSet::empty();
+        let mut changes = HealthChanges::default();
+
+        for subsystem in Subsystem::ALL {
+            if suspended.contains(subsystem) {
+                continue;
+            }
+            let idle = self.idle_ms(subsystem, now_ms);
+            if idle > subsystem.timeout_ms() {
+                current.insert(subsystem);
+                if !previous.contains(subsystem) {
+                    changes.stalled.insert(subsystem);
+                }
+            } else if previous.contains(subsystem) {
+                changes.recovered.insert(subsystem);
+            }
+            if subsystem
+                .restart_after_ms()
+                .is_some_and(|limit| idle > limit)
+            {
+                changes.needs_restart.insert(subsystem);
+            }
+        }
+
+        self.stalled.store(current.0, Ordering::Relaxed);
+        changes
+    }
+}
+
+impl Default for HealthMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all_checked_in(monitor: &HealthMonitor, now_ms: u32) {
+        for subsystem in Subsystem::ALL {
+            monitor.check_in(subsystem, now_ms);
+        }
+    }
+
+    // ── Stall detection ─────────────────────────────────────────────
+
+    #[test]
+    fn healthy_subsystems_report_nothing() {
+        let monitor = HealthMonitor::new();
+        all_checked_in(&monitor, 1_000);
+        assert!(monitor.poll(2_000).is_empty());
+        assert!(monitor.stalled().is_empty());
+    }
+
+    #[test]
+    fn silent_subsystem_is_reported_once() {
+        let monitor = HealthMonitor::new();
+        all_checked_in(&monitor, 1_000);
+        monitor.check_in(Subsystem::WifiSniffer, 10_000);
+        monitor.check_in(Subsystem::BleScanner, 10_000);
+
+        let changes = monitor.poll(10_000);
+        assert!(changes.stalled.contains(Subsystem::ChannelHop));
+        assert!(!changes.stalled.contains(Subsystem::WifiSniffer));
+        assert!(monitor.stalled().contains(Subsystem::ChannelHop));
+
+        // Still stalled, but already reported
+        monitor.check_in(Subsystem::WifiSniffer, 11_000);
+        monitor.check_in(Subsystem::BleScanner, 11_000);
+        assert!(monitor.poll(11_000).stalled.is_empty());
+    }
+
+    #[test]
+    fn never_started_subsystem_stalls_after_timeout() {
+        let monitor = HealthMonitor::new();
+        monitor.check_in(Subsystem::WifiSniffer, 58_000);
+        monitor.check_in(Subsystem::ChannelHop, 58_000);
+        assert!(monitor.poll(59_000).is_empty());
+        assert!(monitor.poll(61_000).stalled.contains(Subsystem::BleScanner));
+    }
+
+    #[test]
+    fn recovery_is_reported() {
+        let monitor = HealthMonitor::new();
+        all_checked_in(&monitor, 0);
+        assert!(monitor.poll(6_000).stalled.contains(Subsystem::ChannelHop));
+
+        monitor.check_in(Subsystem::ChannelHop, 6_100);
+        let changes = monitor.poll(6_200);
+        assert!(changes.recovered.contains(Subsystem::ChannelHop));
+        assert!(!monitor.stalled().contains(Subsystem::ChannelHop));
+    }
+
+    #[test]
+    fn restart_threshold() {
+        let monitor = HealthMonitor::new();
+        all_checked_in(&monitor, 0);
+        monitor.check_in(Subsystem::ChannelHop, 29_000);
+        monitor.check_in(Subsystem::BleScanner, 29_000);
+        assert!(monitor.poll(29_000).needs_restart.is_empty());
+
+        let changes = monitor.poll(121_000);
+        assert!(changes.needs_restart.contains(Subsystem::ChannelHop));
+        // A quiet neighborhood is no reason to reset
+        assert!(!changes.needs_restart.contains(Subsystem::WifiSniffer));
+        let later = monitor.poll(600_000);
+        assert!(!later.needs_restart.contains(Subsystem::BleScanner));
+    }
+
+    #[test]
+    fn suspended_subsystem_is_not_reported() {
+        let monitor = HealthMonitor::new();
+        all_checked_in(&monitor, 0);
+        monitor.set_suspended(Subsystem::WifiSniffer, true, 0);
+        monitor.set_suspended(Subsystem::ChannelHop, true, 0);
+        monitor.check_in(Subsystem::BleScanner, 200_000);
+        assert!(monitor.poll(200_000).is_empty());
+
+        // Resuming restarts the timeout instead of reporting a stall at once
+        monitor.set_suspended(Subsystem::ChannelHop, false, 200_000);
+        assert!(monitor.poll(201_000).is_empty());
+        assert!(monitor
+            .poll(206_000)
+            .stalled
+            .contains(Subsystem::ChannelHop));
+    }
+
+    #[test]
+    fn idle_time_survives_wraparound() {
+        let monitor = HealthMonitor::new();
+        monitor.check_in(Subsystem::ChannelHop, u32::MAX - 500);
+        assert_eq!(monitor.idle_ms(Subsystem::ChannelHop, 500), 1_001);
+    }
+
+    // ── SubsystemSet ────────────────────────────────────────────────
+
+    #[test]
+    fn subsystem_set_iterates_members() {
+        let mut set = SubsystemSet::empty();
+        set.insert(Subsystem::BleScanner);
+        set.insert(Subsystem::WifiSniffer);
+        let members: heapless::Vec<Subsystem, 3> = set.iter().collect();
+        assert_eq!(
+            members.as_slice(),
+            &[Subsystem::WifiSniffer, Subsystem::BleScanner]
+        );
+    }
+
+    #[test]
+    fn subsystem_names_are_snake_case() {
+        for subsystem in Subsystem::ALL {
+            assert!(subsystem
+                .as_str()
+                .bytes()
+                .all(|b| b.is_ascii_lowercase() || b == b'_'));
+        }
+    }
+}
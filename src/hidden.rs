@@ -0,0 +1,223 @@
+/// Hidden-SSID correlation.
+///
+/// Surveillance access points often beacon with a hidden SSID (empty or
+/// all NUL bytes) but answer probe requests with the real one. [`SsidCache`]
+/// remembers the SSID each BSSID revealed in a probe response, so later
+/// hidden beacons from it can be filtered under that name, and reports when
+/// a probe response names a BSSID whose hidden beacons were already seen so
+/// `filter_task` can evaluate them again.
+use heapless::{String, Vec};
+
+use crate::scanner::FrameType;
+
+/// A BSSID not heard from for this long is forgotten
+pub const SSID_CACHE_TTL_MS: u32 = 30 * 60 * 1000;
+
+/// Whether `ssid` is a hidden one: empty, or NUL bytes standing in for the
+/// real name's length.
+pub fn is_hidden(ssid: &str) -> bool {
+    ssid.bytes().all(|b| b == 0)
+}
+
+/// What a frame taught [`SsidCache`] about its BSSID's SSID
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SsidUpdate {
+    /// Nothing to act on
+    None,
+    /// A hidden beacon from a BSSID whose SSID is known; filter it under
+    /// this name
+    Backfilled(String<33>),
+    /// A probe response revealed the SSID of a BSSID seen beaconing hidden.
+    /// The last hidden beacon was heard at `rssi` on `channel`.
+    Revealed { rssi: i8, channel: u8 },
+}
+
+/// Last hidden beacon from a BSSID still waiting for its SSID
+#[derive(Clone, Copy)]
+struct HiddenBeacon {
+    rssi: i8,
+    channel: u8,
+}
+
+struct Entry {
+    bssid: [u8; 6],
+    /// Revealed SSID; empty until a probe response names it
+    ssid: String<33>,
+    pending: Option<HiddenBeacon>,
+    last_ms: u32,
+}
+
+/// BSSID → SSID cache for up to `N` access points. Timestamps are
+/// milliseconds since boot (`u32`, wrapping).
+pub struct SsidCache<const N: usize> {
+    entries: Vec<Entry, N>,
+}
+
+impl<const N: usize> SsidCache<N> {
+    pub const fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Record a beacon or probe response from `bssid`. Other frame types,
+    /// visible beacons, and probe responses from BSSIDs never seen hidden
+    /// are ignored.
+    pub fn observe(
+        &mut self,
+        bssid: &[u8; 6],
+        ssid: &str,
+        frame_type: FrameType,
+        rssi: i8,
+        channel: u8,
+        now_ms: u32,
+    ) -> SsidUpdate {
+        self.entries
+            .retain(|e| now_ms.wrapping_sub(e.last_ms) < SSID_CACHE_TTL_MS);
+
+        match frame_type {
+            FrameType::Beacon if is_hidden(ssid) => {
+                let beacon = HiddenBeacon { rssi, channel };
+                if let Some(entry) = self.entries.iter_mut().find(|e| e.bssid == *bssid) {
+                    entry.last_ms = now_ms;
+                    if !entry.ssid.is_empty() {
+                        return SsidUpdate::Backfilled(entry.ssid.clone());
+                    }
+                    entry.pending = Some(beacon);
+                    return SsidUpdate::None;
+                }
+                self.insert(Entry {
+                    bssid: *bssid,
+                    ssid: String::new(),
+                    pending: Some(beacon),
+                    last_ms: now_ms,
+                });
+                SsidUpdate::None
+            }
+            FrameType::ProbeResponse if !is_hidden(ssid) => {
+                let Some(entry) = self.entries.iter_mut().find(|e| e.bssid == *bssid) else {
+                    return SsidUpdate::None;
+                };
+                entry.ssid.clear();
+                let _ = entry.ssid.push_str(ssid);
+                entry.last_ms = now_ms;
+                match entry.pending.take() {
+                    Some(beacon) => SsidUpdate::Revealed {
+                        rssi: beacon.rssi,
+                        channel: beacon.channel,
+                    },
+                    None => SsidUpdate::None,
+                }
+            }
+            _ => SsidUpdate::None,
+        }
+    }
+
+    /// SSID revealed for `bssid`, if any.
+    pub fn ssid(&self, bssid: &[u8; 6]) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|e| e.bssid == *bssid && !e.ssid.is_empty())
+            .map(|e| e.ssid.as_str())
+    }
+
+    /// Add an entry, replacing the one heard from longest ago when full.
+    fn insert(&mut self, entry: Entry) {
+        if let Err(entry) = self.entries.push(entry) {
+            let now_ms = entry.last_ms;
+            let stalest = self
+                .entries
+                .iter_mut()
+                .max_by_key(|e| now_ms.wrapping_sub(e.last_ms));
+            if let Some(stalest) = stalest {
+                *stalest = entry;
+            }
+        }
+    }
+}
+
+impl<const N: usize> Default for SsidCache<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BSSID: [u8; 6] = [0x58, 0x8E, 0x81, 0x01, 0x02, 0x03];
+
+    fn hidden_beacon(cache: &mut SsidCache<4>, bssid: &[u8; 6], now_ms: u32) -> SsidUpdate {
+        cache.observe(bssid, "", FrameType::Beacon, -70, 6, now_ms)
+    }
+
+    fn probe_resp(cache: &mut SsidCache<4>, bssid: &[u8; 6], now_ms: u32) -> SsidUpdate {
+        cache.observe(bssid, "Flock-A1B2C3", FrameType::ProbeResponse, -60, 6, now_ms)
+    }
+
+    #[test]
+    fn hidden_ssids() {
+        assert!(is_hidden(""));
+        assert!(is_hidden("\0\0\0\0"));
+        assert!(!is_hidden("Flock"));
+        assert!(!is_hidden("\0a"));
+    }
+
+    #[test]
+    fn probe_response_reveals_pending_beacon() {
+        let mut cache = SsidCache::<4>::new();
+        assert_eq!(hidden_beacon(&mut cache, &BSSID, 0), SsidUpdate::None);
+        assert_eq!(
+            probe_resp(&mut cache, &BSSID, 1_000),
+            SsidUpdate::Revealed {
+                rssi: -70,
+                channel: 6
+            }
+        );
+        assert_eq!(cache.ssid(&BSSID), Some("Flock-A1B2C3"));
+        // Revealed once; later probe responses just refresh the name
+        assert_eq!(probe_resp(&mut cache, &BSSID, 2_000), SsidUpdate::None);
+    }
+
+    #[test]
+    fn later_hidden_beacons_are_backfilled() {
+        let mut cache = SsidCache::<4>::new();
+        hidden_beacon(&mut cache, &BSSID, 0);
+        probe_resp(&mut cache, &BSSID, 1_000);
+        match hidden_beacon(&mut cache, &BSSID, 2_000) {
+            SsidUpdate::Backfilled(ssid) => assert_eq!(ssid.as_str(), "Flock-A1B2C3"),
+            other => panic!("expected backfill, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn ignores_bssids_never_seen_hidden() {
+        let mut cache = SsidCache::<4>::new();
+        assert_eq!(probe_resp(&mut cache, &BSSID, 0), SsidUpdate::None);
+        assert_eq!(cache.ssid(&BSSID), None);
+        let visible = cache.observe(&BSSID, "Flock", FrameType::Beacon, -70, 6, 0);
+        assert_eq!(visible, SsidUpdate::None);
+    }
+
+    #[test]
+    fn entries_expire() {
+        let mut cache = SsidCache::<4>::new();
+        hidden_beacon(&mut cache, &BSSID, 0);
+        probe_resp(&mut cache, &BSSID, 1_000);
+        let later = 1_000 + SSID_CACHE_TTL_MS;
+        assert_eq!(hidden_beacon(&mut cache, &BSSID, later), SsidUpdate::None);
+    }
+
+    #[test]
+    fn full_cache_replaces_stalest() {
+        let mut cache = SsidCache::<2>::new();
+        cache.observe(&BSSID, "", FrameType::Beacon, -70, 6, 0);
+        cache.observe(&[1; 6], "", FrameType::Beacon, -70, 6, 100);
+        cache.observe(&[2; 6], "", FrameType::Beacon, -70, 6, 200);
+        let update = cache.observe(&BSSID, "Flock", FrameType::ProbeResponse, -60, 6, 300);
+        assert_eq!(update, SsidUpdate::None);
+        let update = cache.observe(&[1; 6], "Cam", FrameType::ProbeResponse, -60, 6, 300);
+        assert!(matches!(update, SsidUpdate::Revealed { .. }));
+    }
+}
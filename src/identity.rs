@@ -0,0 +1,209 @@
+/// MAC-rotation linking for matched devices.
+///
+/// Phones, trackers and some cameras rotate their randomized MAC every few
+/// minutes, which would split one device's history across dozens of
+/// addresses. [`IdentityLinker`] gives each matched randomized MAC a
+/// synthetic device ID, and hands a new MAC the ID of a recent one that
+/// carried the same payload — the WiFi SSID or vendor elements, or the BLE
+/// advertisement — at a similar RSSI.
+use heapless::Vec;
+
+use crate::hidden;
+use crate::scanner::VendorIe;
+
+/// An ID not seen under any of its MACs for this long is forgotten
+pub const LINK_WINDOW_MS: u32 = 5 * 60 * 1000;
+
+/// Largest RSSI difference (dB) between the old and new MAC's readings that
+/// still links them
+pub const LINK_RSSI_DELTA: u8 = 10;
+
+/// Payload fingerprint of a WiFi frame: its SSID, or for a hidden SSID its
+/// vendor elements — often all a camera that randomizes its MAC and hides
+/// its SSID keeps constant. `None` when it has neither.
+pub fn wifi_fingerprint(ssid: &str, vendor_ies: &[VendorIe]) -> Option<u32> {
+    if !hidden::is_hidden(ssid) {
+        return Some(fnv1a(fnv1a(FNV_OFFSET, &[0]), ssid.as_bytes()));
+    }
+    if vendor_ies.is_empty() {
+        return None;
+    }
+    let hash = vendor_ies.iter().fold(fnv1a(FNV_OFFSET, &[1]), |hash, ie| {
+        fnv1a(fnv1a(hash, &ie.oui), &ie.data)
+    });
+    Some(hash)
+}
+
+/// Payload fingerprint of a BLE advertisement: its raw AD bytes.
+pub fn ble_fingerprint(ad_data: &[u8]) -> Option<u32> {
+    (!ad_data.is_empty()).then(|| fnv1a(fnv1a(FNV_OFFSET, &[2]), ad_data))
+}
+
+const FNV_OFFSET: u32 = 0x811C_9DC5;
+
+/// FNV-1a over `bytes`, continuing from `hash`. Fingerprints start with a
+/// tag byte for their kind, so an SSID and an advertisement with the same
+/// bytes differ.
+fn fnv1a(hash: u32, bytes: &[u8]) -> u32 {
+    bytes
+        .iter()
+        .fold(hash, |hash, &b| (hash ^ b as u32).wrapping_mul(0x0100_0193))
+}
+
+struct Entry {
+    mac: [u8; 6],
+    id: u16,
+    fingerprint: u32,
+    rssi: i8,
+    last_ms: u32,
+}
+
+/// Synthetic IDs for up to `N` MACs. Timestamps are milliseconds since boot
+/// (`u32`, wrapping).
+pub struct IdentityLinker<const N: usize> {
+    entries: Vec<Entry, N>,
+    next_id: u16,
+}
+
+impl<const N: usize> IdentityLinker<N> {
+    pub const fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            next_id: 1,
+        }
+    }
+
+    /// Record a matched detection of `mac` carrying `fingerprint` and return
+    /// its device ID. A MAC keeps its ID; a new one takes the ID of the most
+    /// recently seen MAC with the same fingerprint and an RSSI within
+    /// [`LINK_RSSI_DELTA`], or a fresh ID.
+    pub fn observe(&mut self, mac: &[u8; 6], fingerprint: u32, rssi: i8, now_ms: u32) -> u16 {
+        self.entries
+            .retain(|e| now_ms.wrapping_sub(e.last_ms) < LINK_WINDOW_MS);
+
+        if let Some(entry) = self.entries.iter_mut().find(|e| e.mac == *mac) {
+            entry.fingerprint = fingerprint;
+            entry.rssi = rssi;
+            entry.last_ms = now_ms;
+            return entry.id;
+        }
+
+        let linked = self
+            .entries
+            .iter()
+            .filter(|e| e.fingerprint == fingerprint)
+            .filter(|e| e.rssi.abs_diff(rssi) <= LINK_RSSI_DELTA)
+            .min_by_key(|e| now_ms.wrapping_sub(e.last_ms))
+            .map(|e| e.id);
+        let id = linked.unwrap_or_else(|| {
+            let id = self.next_id;
+            self.next_id = self.next_id.checked_add(1).unwrap_or(1);
+            id
+        });
+        self.insert(Entry {
+            mac: *mac,
+            id,
+            fingerprint,
+            rssi,
+            last_ms: now_ms,
+        });
+        id
+    }
+
+    /// Add an entry, replacing the one seen longest ago when full.
+    fn insert(&mut self, entry: Entry) {
+        if let Err(entry) = self.entries.push(entry) {
+            let now_ms = entry.last_ms;
+            let stalest = self
+                .entries
+                .iter_mut()
+                .max_by_key(|e| now_ms.wrapping_sub(e.last_ms));
+            if let Some(stalest) = stalest {
+                *stalest = entry;
+            }
+        }
+    }
+}
+
+impl<const N: usize> Default for IdentityLinker<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAC_A: [u8; 6] = [0x5A, 0x01, 0x02, 0x03, 0x04, 0x05];
+    const MAC_B: [u8; 6] = [0x7E, 0x11, 0x12, 0x13, 0x14, 0x15];
+    const MAC_C: [u8; 6] = [0x6A, 0x21, 0x22, 0x23, 0x24, 0x25];
+
+    fn vendor_ie(oui: [u8; 3], data: &[u8]) -> VendorIe {
+        VendorIe {
+            oui,
+            data: Vec::from_slice(data).unwrap(),
+        }
+    }
+
+    #[test]
+    fn fingerprints() {
+        assert_eq!(wifi_fingerprint("", &[]), None);
+        assert_eq!(wifi_fingerprint("\0\0", &[]), None);
+        assert_eq!(ble_fingerprint(&[]), None);
+        assert_eq!(
+            wifi_fingerprint("Flock", &[]),
+            wifi_fingerprint("Flock", &[])
+        );
+        assert_ne!(
+            wifi_fingerprint("Flock", &[]),
+            wifi_fingerprint("Flock2", &[])
+        );
+        assert_ne!(wifi_fingerprint("ab", &[]), ble_fingerprint(b"ab"));
+    }
+
+    #[test]
+    fn hidden_ssids_fingerprint_vendor_elements() {
+        let ies = [vendor_ie([0x00, 0x17, 0xF2], &[0x0A, 0x01])];
+        let other = [vendor_ie([0x00, 0x17, 0xF2], &[0x0A, 0x02])];
+        let hidden = wifi_fingerprint("", &ies);
+        assert!(hidden.is_some());
+        assert_eq!(hidden, wifi_fingerprint("\0\0\0", &ies));
+        assert_ne!(hidden, wifi_fingerprint("", &other));
+        // A visible SSID takes precedence over the elements
+        assert_eq!(
+            wifi_fingerprint("Cam", &ies),
+            wifi_fingerprint("Cam", &other)
+        );
+    }
+
+    #[test]
+    fn mac_keeps_its_id() {
+        let mut ids = IdentityLinker::<4>::new();
+        let id = ids.observe(&MAC_A, 7, -60, 0);
+        assert_eq!(ids.observe(&MAC_A, 7, -80, 1_000), id);
+        assert_ne!(ids.observe(&MAC_B, 8, -60, 2_000), id);
+    }
+
+    #[test]
+    fn rotated_mac_inherits_id() {
+        let mut ids = IdentityLinker::<4>::new();
+        let id = ids.observe(&MAC_A, 7, -60, 0);
+        assert_eq!(ids.observe(&MAC_B, 7, -64, 30_000), id);
+        assert_eq!(ids.observe(&MAC_C, 7, -58, 60_000), id);
+    }
+
+    #[test]
+    fn distant_rssi_is_another_device() {
+        let mut ids = IdentityLinker::<4>::new();
+        let id = ids.observe(&MAC_A, 7, -50, 0);
+        assert_ne!(ids.observe(&MAC_B, 7, -85, 1_000), id);
+    }
+
+    #[test]
+    fn ids_expire() {
+        let mut ids = IdentityLinker::<4>::new();
+        let id = ids.observe(&MAC_A, 7, -60, 0);
+        assert_ne!(ids.observe(&MAC_B, 7, -60, LINK_WINDOW_MS), id);
+    }
+}
@@ -0,0 +1,298 @@
+/// Resolution of the user's own devices from their Identity Resolving Keys.
+///
+/// Phones, watches and earbuds hide behind Resolvable Private Addresses that
+/// rotate every ~15 minutes, so a user's own gear looks like a new unknown
+/// device — and a possible tracker — each time. The companion can provision
+/// the IRKs of those devices with `add_irk`; advertisers whose address
+/// resolves against one are marked `mine` and don't raise alerts.
+///
+/// Only RPAs are resolvable. Devices that rotate addresses without an IRK
+/// (e.g. Find My accessories separated from their owner) are unaffected.
+use heapless::Vec;
+
+use crate::store::{ConfigBlob, ConfigKey};
+
+/// Maximum number of provisioned IRKs
+pub const MAX_IRKS: usize = 8;
+
+/// A 128-bit Identity Resolving Key, most significant byte first (the order
+/// used in the Bluetooth Core specification and by the `add_irk` command).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Irk(pub [u8; 16]);
+
+impl Irk {
+    /// Parse 32 hex digits, optionally `:`-separated.
+    pub fn from_hex(s: &str) -> Option<Self> {
+        let mut key = [0u8; 16];
+        let mut digits = s.bytes().filter(|&b| b != b':');
+        for byte in key.iter_mut() {
+            let hi = hex_digit(digits.next()?)?;
+            let lo = hex_digit(digits.next()?)?;
+            *byte = hi << 4 | lo;
+        }
+        digits.next().is_none().then_some(Self(key))
+    }
+
+    /// Whether `addr` is a Resolvable Private Address generated from this
+    /// key. `addr` is in HCI order (least significant byte first), as
+    /// reported by the BLE scanner.
+    pub fn resolves(&self, addr: &[u8; 6]) -> bool {
+        is_rpa(addr) && ah(&self.0, [addr[5], addr[4], addr[3]]) == [addr[2], addr[1], addr[0]]
+    }
+}
+
+/// Whether `addr` (HCI order) is a Resolvable Private Address: the two most
+/// significant bits are `01`.
+pub fn is_rpa(addr: &[u8; 6]) -> bool {
+    addr[5] & 0xC0 == 0x40
+}
+
+fn hex_digit(b: u8) -> Option<u8> {
+    (b as char).to_digit(16).map(|d| d as u8)
+}
+
+/// Provisioned IRKs.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IrkTable {
+    keys: Vec<Irk, MAX_IRKS>,
+}
+
+impl IrkTable {
+    pub const fn new() -> Self {
+        Self { keys: Vec::new() }
+    }
+
+    /// Add `irk`. Returns `false` if the table is full; adding a key that is
+    /// already present succeeds without duplicating it.
+    pub fn add(&mut self, irk: Irk) -> bool {
+        self.keys.contains(&irk) || self.keys.push(irk).is_ok()
+    }
+
+    pub fn clear(&mut self) {
+        self.keys.clear();
+    }
+
+    /// Whether `addr` (HCI order) resolves against any provisioned key.
+    pub fn resolves(&self, addr: &[u8; 6]) -> bool {
+        is_rpa(addr) && self.keys.iter().any(|irk| irk.resolves(addr))
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+/// Binary layout: `[count, key[16]...]`.
+impl ConfigBlob for IrkTable {
+    const KEY: ConfigKey = ConfigKey::Irks;
+
+    fn encode(&self, buf: &mut [u8]) -> Option<usize> {
+        let len = 1 + self.keys.len() * 16;
+        let out = buf.get_mut(..len)?;
+        out[0] = self.keys.len() as u8;
+        for (chunk, irk) in out[1..].chunks_exact_mut(16).zip(&self.keys) {
+            chunk.copy_from_slice(&irk.0);
+        }
+        Some(len)
+    }
+
+    fn decode(data: &[u8]) -> Option<Self> {
+        let (&count, rest) = data.split_first()?;
+        let keys = rest.get(..count as usize * 16)?;
+        let mut table = Self::new();
+        for chunk in keys.chunks_exact(16) {
+            table.keys.push(Irk(chunk.try_into().ok()?)).ok()?;
+        }
+        Some(table)
+    }
+}
+
+// ── Random address hash ────────────────────────────────────────────────
+
+/// The `ah` random address hash (Core spec Vol 3 Part H §2.2.2):
+/// `e(k, 0^104 || prand) mod 2^24`, all values most significant byte first.
+fn ah(irk: &[u8; 16], prand: [u8; 3]) -> [u8; 3] {
+    let mut block = [0u8; 16];
+    block[13..].copy_from_slice(&prand);
+    let out = aes128_encrypt(irk, &block);
+    [out[13], out[14], out[15]]
+}
+
+/// AES S-box
+#[rustfmt::skip]
+const SBOX: [u8; 256] = [
+    0x63, 0x7c, 0x77, 0x7b, 0xf2, 0x6b, 0x6f, 0xc5, 0x30, 0x01, 0x67, 0x2b, 0xfe, 0xd7, 0xab, 0x76,
+    0xca, 0x82, 0xc9, 0x7d, 0xfa, 0x59, 0x47, 0xf0, 0xad, 0xd4, 0xa2, 0xaf, 0x9c, 0xa4, 0x72, 0xc0,
+    0xb7, 0xfd, 0x93, 0x26, 0x36, 0x3f, 0xf7, 0xcc, 0x34, 0xa5, 0xe5, 0xf1, 0x71, 0xd8, 0x31, 0x15,
+    0x04, 0xc7, 0x23, 0xc3, 0x18, 0x96, 0x05, 0x9a, 0x07, 0x12, 0x80, 0xe2, 0xeb, 0x27, 0xb2, 0x75,
+    0x09, 0x83, 0x2c, 0x1a, 0x1b, 0x6e, 0x5a, 0xa0, 0x52, 0x3b, 0xd6, 0xb3, 0x29, 0xe3, 0x2f, 0x84,
+    0x53, 0xd1, 0x00, 0xed, 0x20, 0xfc, 0xb1, 0x5b, 0x6a, 0xcb, 0xbe, 0x39, 0x4a, 0x4c, 0x58, 0xcf,
+    0xd0, 0xef, 0xaa, 0xfb, 0x43, 0x4d, 0x33, 0x85, 0x45, 0xf9, 0x02, 0x7f, 0x50, 0x3c, 0x9f, 0xa8,
+    0x51, 0xa3, 0x40, 0x8f, 0x92, 0x9d, 0x38, 0xf5, 0xbc, 0xb6, 0xda, 0x21, 0x10, 0xff, 0xf3, 0xd2,
+    0xcd, 0x0c, 0x13, 0xec, 0x5f, 0x97, 0x44, 0x17, 0xc4, 0xa7, 0x7e, 0x3d, 0x64, 0x5d, 0x19, 0x73,
+    0x60, 0x81, 0x4f, 0xdc, 0x22, 0x2a, 0x90, 0x88, 0x46, 0xee, 0xb8, 0x14, 0xde, 0x5e, 0x0b, 0xdb,
+    0xe0, 0x32, 0x3a, 0x0a, 0x49, 0x06, 0x24, 0x5c, 0xc2, 0xd3, 0xac, 0x62, 0x91, 0x95, 0xe4, 0x79,
+    0xe7, 0xc8, 0x37, 0x6d, 0x8d, 0xd5, 0x4e, 0xa9, 0x6c, 0x56, 0xf4, 0xea, 0x65, 0x7a, 0xae, 0x08,
+    0xba, 0x78, 0x25, 0x2e, 0x1c, 0xa6, 0xb4, 0xc6, 0xe8, 0xdd, 0x74, 0x1f, 0x4b, 0xbd, 0x8b, 0x8a,
+    0x70, 0x3e, 0xb5, 0x66, 0x48, 0x03, 0xf6, 0x0e, 0x61, 0x35, 0x57, 0xb9, 0x86, 0xc1, 0x1d, 0x9e,
+    0xe1, 0xf8, 0x98, 0x11, 0x69, 0xd9, 0x8e, 0x94, 0x9b, 0x1e, 0x87, 0xe9, 0xce, 0x55, 0x28, 0xdf,
+    0x8c, 0xa1, 0x89, 0x0d, 0xbf, 0xe6, 0x42, 0x68, 0x41, 0x99, 0x2d, 0x0f, 0xb0, 0x54, 0xbb, 0x16,
+];
+
+/// Multiply by x in GF(2^8)
+fn xtime(b: u8) -> u8 {
+    (b << 1) ^ if b & 0x80 != 0 { 0x1b } else { 0 }
+}
+
+/// Single-block AES-128 encryption (FIPS-197). The only cipher `ah` needs,
+/// so a table-light software version rather than a crypto dependency.
+fn aes128_encrypt(key: &[u8; 16], block: &[u8; 16]) -> [u8; 16] {
+    let mut round_key = *key;
+    let mut state = *block;
+    let mut rcon = 1u8;
+
+    for round in 1..=10 {
+        for (s, k) in state.iter_mut().zip(&round_key) {
+            *s ^= k;
+        }
+        // SubBytes + ShiftRows: state is column-major, row r shifts left by r
+        let mut shifted = [0u8; 16];
+        for (i, out) in shifted.iter_mut().enumerate() {
+            let (col, row) = (i / 4, i % 4);
+            *out = SBOX[state[(col + row) % 4 * 4 + row] as usize];
+        }
+        state = shifted;
+        if round < 10 {
+            for col in state.chunks_exact_mut(4) {
+                let all = col[0] ^ col[1] ^ col[2] ^ col[3];
+                let first = col[0];
+                for row in 0..4 {
+                    let next = if row == 3 { first } else { col[row + 1] };
+                    col[row] ^= all ^ xtime(col[row] ^ next);
+                }
+            }
+        }
+        // Next round key
+        let mut word = [round_key[13], round_key[14], round_key[15], round_key[12]];
+        for b in &mut word {
+            *b = SBOX[*b as usize];
+        }
+        word[0] ^= rcon;
+        rcon = xtime(rcon);
+        for i in 0..16 {
+            round_key[i] ^= if i < 4 { word[i] } else { round_key[i - 4] };
+        }
+    }
+
+    for (s, k) in state.iter_mut().zip(&round_key) {
+        *s ^= k;
+    }
+    state
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Core spec Vol 3 Part H §D.7 sample data
+    const SPEC_IRK: [u8; 16] = [
+        0xec, 0x02, 0x34, 0xa3, 0x57, 0xc8, 0xad, 0x05, 0x34, 0x10, 0x10, 0xa6, 0x0a, 0x39, 0x7d,
+        0x9b,
+    ];
+    /// RPA 70:81:94:0D:FB:AA (prand 0x708194, hash 0x0dfbaa) in HCI order
+    const SPEC_RPA: [u8; 6] = [0xaa, 0xfb, 0x0d, 0x94, 0x81, 0x70];
+
+    // ── AES ─────────────────────────────────────────────────────────
+
+    #[test]
+    fn aes_matches_fips_197_vector() {
+        let key: [u8; 16] = core::array::from_fn(|i| i as u8);
+        let block: [u8; 16] = core::array::from_fn(|i| (i as u8) * 0x11);
+        assert_eq!(
+            aes128_encrypt(&key, &block),
+            [
+                0x69, 0xc4, 0xe0, 0xd8, 0x6a, 0x7b, 0x04, 0x30, 0xd8, 0xcd, 0xb7, 0x80, 0x70, 0xb4,
+                0xc5, 0x5a
+            ]
+        );
+    }
+
+    #[test]
+    fn ah_matches_spec_sample() {
+        assert_eq!(ah(&SPEC_IRK, [0x70, 0x81, 0x94]), [0x0d, 0xfb, 0xaa]);
+    }
+
+    // ── Resolution ──────────────────────────────────────────────────
+
+    #[test]
+    fn resolves_rpa_from_its_irk_only() {
+        let irk = Irk(SPEC_IRK);
+        assert!(irk.resolves(&SPEC_RPA));
+        let mut other = SPEC_RPA;
+        other[0] ^= 1;
+        assert!(!irk.resolves(&other));
+        let mut wrong_key = SPEC_IRK;
+        wrong_key[0] ^= 1;
+        assert!(!Irk(wrong_key).resolves(&SPEC_RPA));
+    }
+
+    #[test]
+    fn only_rpas_are_resolved() {
+        // Same bytes with the top bits of a static random address
+        let mut static_addr = SPEC_RPA;
+        static_addr[5] |= 0xC0;
+        assert!(!is_rpa(&static_addr));
+        assert!(!Irk(SPEC_IRK).resolves(&static_addr));
+    }
+
+    #[test]
+    fn irk_parses_hex() {
+        let irk = Irk::from_hex("ec0234a357c8ad05341010a60a397d9b").unwrap();
+        assert_eq!(irk, Irk(SPEC_IRK));
+        let colons = "EC:02:34:A3:57:C8:AD:05:34:10:10:A6:0A:39:7D:9B";
+        assert_eq!(Irk::from_hex(colons), Some(irk));
+        assert!(Irk::from_hex("ec0234").is_none());
+        assert!(Irk::from_hex("ec0234a357c8ad05341010a60a397d9b00").is_none());
+        assert!(Irk::from_hex("zz0234a357c8ad05341010a60a397d9b").is_none());
+    }
+
+    #[test]
+    fn table_resolves_and_dedups() {
+        let mut table = IrkTable::new();
+        assert!(!table.resolves(&SPEC_RPA));
+        assert!(table.add(Irk([1; 16])));
+        assert!(table.add(Irk(SPEC_IRK)));
+        assert!(table.add(Irk(SPEC_IRK)));
+        assert_eq!(table.len(), 2);
+        assert!(table.resolves(&SPEC_RPA));
+        table.clear();
+        assert!(table.is_empty());
+    }
+
+    #[test]
+    fn table_rejects_when_full() {
+        let mut table = IrkTable::new();
+        for i in 0..MAX_IRKS as u8 {
+            assert!(table.add(Irk([i; 16])));
+        }
+        assert!(!table.add(Irk(SPEC_IRK)));
+    }
+
+    // ── Persistence ─────────────────────────────────────────────────
+
+    #[test]
+    fn blob_round_trip() {
+        let mut table = IrkTable::new();
+        table.add(Irk(SPEC_IRK));
+        table.add(Irk([7; 16]));
+        let mut buf = [0u8; crate::store::MAX_BLOB_LEN];
+        let len = table.encode(&mut buf).unwrap();
+        assert_eq!(len, 33);
+        assert_eq!(IrkTable::decode(&buf[..len]), Some(table));
+        assert!(IrkTable::decode(&buf[..len - 1]).is_none());
+    }
+}
@@ -0,0 +1,185 @@
+/// User-assigned device labels.
+///
+/// The companion pushes labels for devices the user knows ("Mom's AirTag")
+/// with the `label` command. Labels are echoed in every later message about
+/// that MAC and on the display, so known-benign devices are recognizable at a
+/// glance without being suppressed. The table is bounded and persisted through
+/// [`crate::store`].
+use heapless::{String, Vec};
+
+use crate::store::{ConfigBlob, ConfigKey};
+
+/// Maximum label length in bytes
+pub const LABEL_LEN: usize = 24;
+
+/// Maximum number of labelled devices
+pub const MAX_LABELS: usize = 12;
+
+/// A device label
+pub type Label = String<LABEL_LEN>;
+
+/// Labels keyed by MAC address.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LabelTable {
+    entries: Vec<([u8; 6], Label), MAX_LABELS>,
+}
+
+impl LabelTable {
+    pub const fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Set the label for `mac`; an empty label removes it. Returns `false` if
+    /// the table is full.
+    pub fn set(&mut self, mac: &[u8; 6], label: &str) -> bool {
+        if label.is_empty() {
+            self.remove(mac);
+            return true;
+        }
+        let (label, _) = crate::protocol::truncate_str::<LABEL_LEN>(label);
+        if let Some(entry) = self.entries.iter_mut().find(|(m, _)| m == mac) {
+            entry.1 = label;
+            return true;
+        }
+        self.entries.push((*mac, label)).is_ok()
+    }
+
+    /// Remove the label for `mac`. Returns `false` if there was none.
+    pub fn remove(&mut self, mac: &[u8; 6]) -> bool {
+        match self.entries.iter().position(|(m, _)| m == mac) {
+            Some(index) => {
+                self.entries.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn get(&self, mac: &[u8; 6]) -> Option<&Label> {
+        self.entries
+            .iter()
+            .find(|(m, _)| m == mac)
+            .map(|(_, label)| label)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Binary layout: `[count, (mac[6], len, label[len])...]` — at most
+/// `1 + 12 * 31` bytes, within `MAX_BLOB_LEN`.
+impl ConfigBlob for LabelTable {
+    const KEY: ConfigKey = ConfigKey::Labels;
+
+    fn encode(&self, buf: &mut [u8]) -> Option<usize> {
+        *buf.first_mut()? = self.entries.len() as u8;
+        let mut pos = 1;
+        for (mac, label) in &self.entries {
+            let end = pos + 7 + label.len();
+            let record = buf.get_mut(pos..end)?;
+            record[..6].copy_from_slice(mac);
+            record[6] = label.len() as u8;
+            record[7..].copy_from_slice(label.as_bytes());
+            pos = end;
+        }
+        Some(pos)
+    }
+
+    fn decode(data: &[u8]) -> Option<Self> {
+        let (&count, mut rest) = data.split_first()?;
+        let mut table = Self::new();
+        for _ in 0..count {
+            let mac: [u8; 6] = rest.get(..6)?.try_into().ok()?;
+            let len = *rest.get(6)? as usize;
+            let text = core::str::from_utf8(rest.get(7..7 + len)?).ok()?;
+            let label = Label::try_from(text).ok()?;
+            table.entries.push((mac, label)).ok()?;
+            rest = &rest[7 + len..];
+        }
+        Some(table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAC: [u8; 6] = [0x7C, 0x11, 0x22, 0x33, 0x44, 0x55];
+
+    // ── Table ───────────────────────────────────────────────────────
+
+    #[test]
+    fn set_get_and_replace() {
+        let mut table = LabelTable::new();
+        assert!(table.set(&MAC, "Mom's AirTag"));
+        assert_eq!(table.get(&MAC).map(|l| l.as_str()), Some("Mom's AirTag"));
+        assert!(table.set(&MAC, "Car keys"));
+        assert_eq!(table.get(&MAC).map(|l| l.as_str()), Some("Car keys"));
+        assert_eq!(table.len(), 1);
+        assert!(table.get(&[0; 6]).is_none());
+    }
+
+    #[test]
+    fn empty_label_removes() {
+        let mut table = LabelTable::new();
+        table.set(&MAC, "Mine");
+        assert!(table.set(&MAC, ""));
+        assert!(table.is_empty());
+    }
+
+    #[test]
+    fn long_label_is_truncated() {
+        let mut table = LabelTable::new();
+        table.set(&MAC, "A label much longer than twenty-four bytes");
+        assert_eq!(table.get(&MAC).unwrap().len(), LABEL_LEN);
+    }
+
+    #[test]
+    fn full_table_rejects_new_devices() {
+        let mut table = LabelTable::new();
+        for i in 0..MAX_LABELS as u8 {
+            assert!(table.set(&[i; 6], "x"));
+        }
+        assert!(!table.set(&MAC, "one too many"));
+        assert!(table.set(&[0; 6], "relabel ok"));
+    }
+
+    // ── Persistence ─────────────────────────────────────────────────
+
+    #[test]
+    fn blob_round_trip() {
+        let mut table = LabelTable::new();
+        table.set(&MAC, "Mom's AirTag");
+        table.set(&[1; 6], "Neighbor's cam");
+        let mut buf = [0u8; crate::store::MAX_BLOB_LEN];
+        let len = table.encode(&mut buf).unwrap();
+        assert_eq!(LabelTable::decode(&buf[..len]), Some(table));
+    }
+
+    #[test]
+    fn full_table_fits_blob() {
+        let mut table = LabelTable::new();
+        for i in 0..MAX_LABELS as u8 {
+            table.set(&[i; 6], "abcdefghijklmnopqrstuvwx");
+        }
+        let mut buf = [0u8; crate::store::MAX_BLOB_LEN];
+        assert!(table.encode(&mut buf).is_some());
+    }
+
+    #[test]
+    fn decode_rejects_truncated_blob() {
+        let mut table = LabelTable::new();
+        table.set(&MAC, "Mine");
+        let mut buf = [0u8; 64];
+        let len = table.encode(&mut buf).unwrap();
+        assert!(LabelTable::decode(&buf[..len - 1]).is_none());
+        assert!(LabelTable::decode(&[]).is_none());
+    }
+}
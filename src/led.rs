@@ -0,0 +1,27 @@
+/// Status LED driver.
+///
+/// Flashes the board LED when a detection's alert (`rules::alert_action`)
+/// asks for a color. The M5StickC's LED is single-color and active low, so
+/// every color lights it the same. The XIAO's WS2812 shares GPIO9 with the
+/// SD card's MOSI and is left unused.
+use embassy_time::{Duration, Timer};
+use esp_hal::gpio::{Level, Output, OutputConfig};
+
+use crate::board;
+
+/// How long the LED stays lit per alert
+const FLASH_MS: u64 = 500;
+
+#[embassy_executor::task]
+pub async fn led_task(led_pin: esp_hal::peripherals::GPIO10<'static>) {
+    let mut led = Output::new(led_pin, Level::High, OutputConfig::default());
+    log::info!("LED ready on GPIO{}", board::LED_PIN);
+
+    loop {
+        // Single-color LED: the color only says whether to flash
+        crate::LED_SIGNAL.wait().await;
+        led.set_low();
+        Timer::after(Duration::from_millis(FLASH_MS)).await;
+        led.set_high();
+    }
+}
@@ -5,11 +5,59 @@
 //! (embassy tasks, BLE GATT server, WiFi sniffer callbacks) lives in the
 //! firmware binary (`main.rs`).
 
-#![cfg_attr(not(test), no_std)]
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+pub mod anomaly;
+pub mod beacon;
+pub mod bearing;
 pub mod board;
+pub mod capture;
+pub mod channel;
 pub mod comm;
+pub mod compress;
+pub mod correlate;
+pub mod crc;
+pub mod cte;
 pub mod defaults;
+pub mod error;
+#[cfg(feature = "std")]
+pub mod evidence;
 pub mod filter;
+pub mod following;
+pub mod glob;
+pub mod gps;
+#[cfg(feature = "std")]
+pub mod gpx;
+pub mod health;
+pub mod hidden;
+pub mod identity;
+pub mod irk;
+pub mod label;
+pub mod lingering;
+pub mod location;
+#[cfg(feature = "std")]
+pub mod oui;
+pub mod prefilter;
+pub mod probe;
 pub mod protocol;
+pub mod qr;
+pub mod remote_id;
+pub mod route;
+pub mod rssi;
+pub mod rules;
 pub mod scanner;
+pub mod schedule;
+pub mod sequence;
+pub mod sigbundle;
+#[cfg(feature = "alloc")]
+pub mod sigdb;
+pub mod sigfile;
+pub mod sightings;
+pub mod store;
+pub mod timing;
+pub mod traffic;
+pub mod watch;
+pub mod wids;
@@ -0,0 +1,232 @@
+/// Lingering detection for trackers that stay near a stationary user.
+///
+/// Following detection needs the user to move; at a desk, in a café or at
+/// home a tracker planted on them is heard in one place like everything
+/// else. [`LingeringDetector`] instead times how long a tracker-class device
+/// has stayed within RSSI range without a break of [`LEFT_AFTER_MS`], and
+/// reports it once that reaches the [`LingeringPolicy`] duration — once per
+/// stay, so a device that leaves and comes back is timed afresh. Allowlisted
+/// devices never match and the user's own are not fed to it. Neither are
+/// sightings while the user is on the move (`LocationTracker::is_stationary`),
+/// unless there is no location to tell by.
+use heapless::Vec;
+use serde::{Deserialize, Serialize};
+
+/// Silence, or sightings out of range, after which a device has left
+pub const LEFT_AFTER_MS: u32 = 2 * 60 * 1000;
+
+/// How long a tracker must stay, and how close, to be reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LingeringPolicy {
+    /// Minutes within range
+    pub duration_min: u16,
+    /// Weakest RSSI (dBm) still within range
+    pub min_rssi: i8,
+}
+
+impl LingeringPolicy {
+    /// Ten minutes at -80 dBm or better: longer than someone passing by or
+    /// queueing next to you, within a room or two
+    pub const DEFAULT: Self = Self {
+        duration_min: 10,
+        min_rssi: -80,
+    };
+
+    /// Build a policy, rejecting a zero duration
+    pub fn try_new(duration_min: u16, min_rssi: i8) -> Option<Self> {
+        (duration_min > 0).then_some(Self {
+            duration_min,
+            min_rssi,
+        })
+    }
+
+    pub fn duration_ms(&self) -> u32 {
+        self.duration_min as u32 * 60 * 1000
+    }
+}
+
+impl Default for LingeringPolicy {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// A device that has stayed near the user, as returned by
+/// [`LingeringDetector::observe`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lingerer {
+    /// Latest MAC of the device
+    pub mac: [u8; 6],
+    /// Synthetic ID linking its rotating MACs, if any
+    pub device_id: Option<u16>,
+    /// Milliseconds since it came within range
+    pub duration_ms: u32,
+}
+
+struct Entry {
+    mac: [u8; 6],
+    device_id: Option<u16>,
+    first_ms: u32,
+    last_ms: u32,
+    reported: bool,
+}
+
+/// Stays of up to `N` devices. Timestamps are milliseconds since boot
+/// (`u32`, wrapping).
+pub struct LingeringDetector<const N: usize> {
+    entries: Vec<Entry, N>,
+}
+
+impl<const N: usize> LingeringDetector<N> {
+    pub const fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Record a sighting of `mac` — or of the device `device_id` links it
+    /// to — at `rssi`. Returns the device once it has stayed within range
+    /// for the `policy` duration, then not again during this stay. When the
+    /// table is full the device seen longest ago is replaced.
+    pub fn observe(
+        &mut self,
+        mac: &[u8; 6],
+        device_id: Option<u16>,
+        rssi: i8,
+        now_ms: u32,
+        policy: &LingeringPolicy,
+    ) -> Option<Lingerer> {
+        if rssi < policy.min_rssi {
+            return None;
+        }
+        let existing = self.entries.iter_mut().find(|e| match device_id {
+            Some(id) => e.device_id == Some(id),
+            None => e.mac == *mac,
+        });
+        let Some(entry) = existing else {
+            self.insert(mac, device_id, now_ms);
+            return None;
+        };
+
+        if now_ms.wrapping_sub(entry.last_ms) > LEFT_AFTER_MS {
+            entry.first_ms = now_ms;
+            entry.reported = false;
+        }
+        entry.mac = *mac;
+        entry.last_ms = now_ms;
+
+        let duration_ms = now_ms.wrapping_sub(entry.first_ms);
+        if entry.reported || duration_ms < policy.duration_ms() {
+            return None;
+        }
+        entry.reported = true;
+        Some(Lingerer {
+            mac: entry.mac,
+            device_id: entry.device_id,
+            duration_ms,
+        })
+    }
+
+    fn insert(&mut self, mac: &[u8; 6], device_id: Option<u16>, now_ms: u32) {
+        let entry = Entry {
+            mac: *mac,
+            device_id,
+            first_ms: now_ms,
+            last_ms: now_ms,
+            reported: false,
+        };
+        if let Err(entry) = self.entries.push(entry) {
+            let stalest = self
+                .entries
+                .iter_mut()
+                .max_by_key(|e| now_ms.wrapping_sub(e.last_ms));
+            if let Some(stalest) = stalest {
+                *stalest = entry;
+            }
+        }
+    }
+}
+
+impl<const N: usize> Default for LingeringDetector<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAC: [u8; 6] = [0x7C, 0x11, 0x22, 0x33, 0x44, 0x55];
+    const MINUTE: u32 = 60 * 1000;
+
+    #[test]
+    fn policy_rejects_zero_duration() {
+        assert!(LingeringPolicy::try_new(0, -80).is_none());
+        assert_eq!(
+            LingeringPolicy::try_new(10, -80),
+            Some(LingeringPolicy::DEFAULT)
+        );
+    }
+
+    #[test]
+    fn device_staying_in_range_is_reported_once() {
+        let policy = LingeringPolicy::DEFAULT;
+        let mut detector = LingeringDetector::<4>::new();
+        for minute in 0..10 {
+            assert_eq!(
+                detector.observe(&MAC, None, -70, minute * MINUTE, &policy),
+                None
+            );
+        }
+        let lingerer = detector
+            .observe(&MAC, None, -70, 10 * MINUTE, &policy)
+            .unwrap();
+        assert_eq!(lingerer.mac, MAC);
+        assert_eq!(lingerer.duration_ms, 10 * MINUTE);
+        assert_eq!(
+            detector.observe(&MAC, None, -70, 11 * MINUTE, &policy),
+            None
+        );
+    }
+
+    #[test]
+    fn leaving_restarts_the_stay() {
+        let policy = LingeringPolicy::DEFAULT;
+        let mut detector = LingeringDetector::<4>::new();
+        for minute in 0..=10 {
+            detector.observe(&MAC, None, -70, minute * MINUTE, &policy);
+        }
+        // Out of range for a while: only weak sightings
+        for minute in 11..14 {
+            assert_eq!(
+                detector.observe(&MAC, None, -90, minute * MINUTE, &policy),
+                None
+            );
+        }
+        // Back: timed from its return
+        for minute in 14..24 {
+            assert_eq!(
+                detector.observe(&MAC, None, -70, minute * MINUTE, &policy),
+                None
+            );
+        }
+        let lingerer = detector
+            .observe(&MAC, None, -70, 24 * MINUTE, &policy)
+            .unwrap();
+        assert_eq!(lingerer.duration_ms, 10 * MINUTE);
+    }
+
+    #[test]
+    fn rotating_macs_stay_by_device_id() {
+        let policy = LingeringPolicy::try_new(1, -80).unwrap();
+        let mut detector = LingeringDetector::<4>::new();
+        detector.observe(&MAC, Some(3), -60, 0, &policy);
+        let rotated = [0x7C, 0x11, 0x22, 0x33, 0x44, 0x66];
+        let lingerer = detector
+            .observe(&rotated, Some(3), -60, MINUTE, &policy)
+            .unwrap();
+        assert_eq!(lingerer.mac, rotated);
+        assert_eq!(lingerer.device_id, Some(3));
+    }
+}
@@ -0,0 +1,407 @@
+/// Where detections happened: the on-board GPS or the companion's position.
+///
+/// A board with a module on the GPS header has its own fixes; a phone
+/// companion without one pushes its position with `set_location`. Scan
+/// messages are tagged with whichever of the two is freshest, so either
+/// source takes over when the other goes quiet. Coordinates are 1e-7
+/// degrees, as in [`crate::gps`].
+///
+/// Between GPS fixes a slow-moving position is dead-reckoned from the last
+/// two, and a location older than the [`StalePolicy`] allows is flagged, so
+/// a detection in a parking garage isn't silently pinned to where the sky
+/// was last seen. The tracker also remembers where the user last stopped,
+/// so it can tell whether they are staying put.
+use serde::{Deserialize, Serialize};
+
+use crate::gps::GpsFix;
+
+/// Largest latitude and longitude in 1e-7 degrees
+const MAX_LAT: i32 = 900_000_000;
+const MAX_LON: i32 = 1_800_000_000;
+
+/// Typical position error of a consumer receiver per unit of HDOP, in
+/// centimetres — the GPS accuracy estimate is `hdop * UERE`
+const UERE_CM: u32 = 500;
+
+/// Centimetres per 1e-7 degree of latitude, scaled by 1e4
+const CM_PER_UNIT_E4: i64 = 11_132;
+
+/// Fastest ground speed dead-reckoned at, in cm/s (54 km/h). Faster, one
+/// turn between fixes moves the true track further than extrapolating
+/// gains.
+const MAX_DEAD_RECKON_CM_S: u32 = 1_500;
+
+/// Longest gap between two fixes still extrapolated from, in milliseconds
+const MAX_FIX_INTERVAL_MS: u32 = 2_000;
+
+/// Distance the user may drift from where they stopped and still count as
+/// stationary, in metres: clear of a receiver's wander at a desk
+const STATIONARY_RADIUS_M: u32 = 50;
+
+/// Fastest GPS ground speed still counted as standing still, in cm/s
+/// (1.8 km/h, under walking pace)
+const STATIONARY_CM_S: u32 = 50;
+
+/// How long the user must have stayed within [`STATIONARY_RADIUS_M`] to
+/// count as stationary, in milliseconds
+const STATIONARY_AFTER_MS: u32 = 60 * 1000;
+
+/// Where a location came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LocationSource {
+    /// GPS module on the board
+    Gps,
+    /// Companion's `set_location`
+    Host,
+}
+
+/// A position to tag detections with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Location {
+    /// Latitude in 1e-7 degrees, north positive
+    pub lat: i32,
+    /// Longitude in 1e-7 degrees, east positive
+    pub lon: i32,
+    /// Horizontal accuracy in metres: as the companion reported it, or
+    /// estimated from the GPS HDOP
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub acc: Option<u16>,
+    pub src: LocationSource,
+}
+
+impl Location {
+    /// A companion-reported position, rejecting coordinates off the globe.
+    pub fn try_host(lat: i32, lon: i32, acc: Option<u16>) -> Option<Self> {
+        ((-MAX_LAT..=MAX_LAT).contains(&lat) && (-MAX_LON..=MAX_LON).contains(&lon)).then_some(
+            Self {
+                lat,
+                lon,
+                acc,
+                src: LocationSource::Host,
+            },
+        )
+    }
+
+    /// The position of a GPS fix.
+    pub fn from_fix(fix: &GpsFix) -> Self {
+        Self {
+            lat: fix.lat,
+            lon: fix.lon,
+            // HDOP is in hundredths
+            acc: fix
+                .hdop
+                .map(|hdop| (hdop as u32 * UERE_CM / 10_000).min(u16::MAX as u32) as u16),
+            src: LocationSource::Gps,
+        }
+    }
+
+    /// Distance to `other` in metres, on an equirectangular projection:
+    /// within a percent over the few kilometres a drive spans.
+    pub fn distance_m(&self, other: &Location) -> u32 {
+        // cos(latitude) = sin(90° - latitude) by Bhaskara's approximation,
+        // in millidegrees and scaled by 1e6
+        let mid_mdeg = ((self.lat as i64 + other.lat as i64) / 2).abs() / 10_000;
+        let x = 90_000 - mid_mdeg;
+        let bx = x * (180_000 - x);
+        let cos_e6 = 4 * bx * 1_000_000 / (40_500_000_000 - bx);
+
+        let dlat = (self.lat as i64 - other.lat as i64).abs();
+        let mut dlon = (self.lon as i64 - other.lon as i64).abs();
+        // Across the antimeridian
+        if dlon > MAX_LON as i64 {
+            dlon = 2 * MAX_LON as i64 - dlon;
+        }
+        let dy = dlat * CM_PER_UNIT_E4 / 1_000_000;
+        let dx = dlon * CM_PER_UNIT_E4 / 1_000_000 * cos_e6 / 1_000_000;
+        ((dx * dx + dy * dy) as u64).isqrt().min(u32::MAX as u64) as u32
+    }
+}
+
+/// How old a location may get before detections tagged with it are
+/// marked `location_stale`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StalePolicy {
+    pub max_age_s: u16,
+}
+
+impl StalePolicy {
+    /// Half a minute: a few missed fixes or companion updates, well short
+    /// of a stop in a garage
+    pub const DEFAULT: Self = Self { max_age_s: 30 };
+
+    /// Build a policy, rejecting a zero age
+    pub fn try_new(max_age_s: u16) -> Option<Self> {
+        (max_age_s > 0).then_some(Self { max_age_s })
+    }
+
+    pub fn is_stale(&self, age_ms: u32) -> bool {
+        age_ms > self.max_age_s as u32 * 1000
+    }
+}
+
+impl Default for StalePolicy {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Latest location from each source, with the uptime it arrived at.
+#[derive(Debug, Clone, Default)]
+pub struct LocationTracker {
+    gps: Option<(Location, u32)>,
+    /// The GPS location before `gps`, to dead-reckon from
+    prev_gps: Option<(Location, u32)>,
+    /// Ground speed of the latest GPS fix in cm/s
+    speed: Option<u32>,
+    host: Option<(Location, u32)>,
+    /// Where the user stopped, and when: the first of the locations since
+    /// that all stayed within [`STATIONARY_RADIUS_M`] of it
+    anchor: Option<(Location, u32)>,
+}
+
+impl LocationTracker {
+    pub const fn new() -> Self {
+        Self {
+            gps: None,
+            prev_gps: None,
+            speed: None,
+            host: None,
+            anchor: None,
+        }
+    }
+
+    /// Record a location received at uptime `now_ms`, replacing the last
+    /// one from the same source.
+    pub fn update(&mut self, location: Location, now_ms: u32) {
+        let moved = self
+            .anchor
+            .is_none_or(|(anchor, _)| anchor.distance_m(&location) > stationary_radius(&location));
+        if moved {
+            self.anchor = Some((location, now_ms));
+        }
+        match location.src {
+            LocationSource::Gps => {
+                self.prev_gps = self.gps.replace((location, now_ms));
+                self.speed = None;
+            }
+            LocationSource::Host => self.host = Some((location, now_ms)),
+        }
+    }
+
+    /// Record a GPS fix received at uptime `now_ms`, with its speed for
+    /// dead reckoning.
+    pub fn update_fix(&mut self, fix: &GpsFix, now_ms: u32) {
+        self.update(Location::from_fix(fix), now_ms);
+        self.speed = fix.speed;
+    }
+
+    /// The freshest location, if any source has reported one, and whether
+    /// it is older than `policy` allows. A GPS location is moved along the
+    /// last two fixes' track for up to one fix interval.
+    pub fn current(&self, now_ms: u32, policy: StalePolicy) -> Option<(Location, bool)> {
+        let age = |(_, at): &(Location, u32)| now_ms.wrapping_sub(*at);
+        let (location, at) = match (self.gps, self.host) {
+            (Some(gps), Some(host)) if age(&host) < age(&gps) => host,
+            (Some(gps), _) => self.dead_reckon(now_ms).unwrap_or(gps),
+            (None, host) => host?,
+        };
+        Some((location, policy.is_stale(now_ms.wrapping_sub(at))))
+    }
+
+    /// Whether the user is staying put: their location, fresh under
+    /// `policy`, has kept within [`STATIONARY_RADIUS_M`] (or its accuracy)
+    /// for [`STATIONARY_AFTER_MS`], and the GPS ground speed, when it is the
+    /// source and reports one, is under walking pace. `None` without a fresh
+    /// location.
+    pub fn is_stationary(&self, now_ms: u32, policy: StalePolicy) -> Option<bool> {
+        let (location, stale) = self.current(now_ms, policy)?;
+        if stale {
+            return None;
+        }
+        let slow = match (location.src, self.speed) {
+            (LocationSource::Gps, Some(speed)) => speed <= STATIONARY_CM_S,
+            _ => true,
+        };
+        let stayed = self.anchor.is_some_and(|(anchor, since)| {
+            now_ms.wrapping_sub(since) >= STATIONARY_AFTER_MS
+                && anchor.distance_m(&location) <= stationary_radius(&location)
+        });
+        Some(slow && stayed)
+    }
+
+    /// The latest GPS location extrapolated to `now_ms`, when moving slowly
+    /// and the last two fixes came close together. Keeps the fix's arrival
+    /// time: an estimate is no fresher than what it is based on.
+    fn dead_reckon(&self, now_ms: u32) -> Option<(Location, u32)> {
+        let ((last, at), (prev, prev_at)) = (self.gps?, self.prev_gps?);
+        if self.speed? > MAX_DEAD_RECKON_CM_S {
+            return None;
+        }
+        let interval = at.wrapping_sub(prev_at);
+        if interval == 0 || interval > MAX_FIX_INTERVAL_MS {
+            return None;
+        }
+        let elapsed = now_ms.wrapping_sub(at).min(interval) as i64;
+        let step = |from: i32, to: i32| {
+            (to as i64 + (to as i64 - from as i64) * elapsed / interval as i64) as i32
+        };
+        let location = Location {
+            lat: step(prev.lat, last.lat).clamp(-MAX_LAT, MAX_LAT),
+            lon: step(prev.lon, last.lon).clamp(-MAX_LON, MAX_LON),
+            ..last
+        };
+        Some((location, at))
+    }
+}
+
+/// How far `location` may be from where the user stopped while they still
+/// count as stationary: a poor fix wanders further than a good one
+fn stationary_radius(location: &Location) -> u32 {
+    STATIONARY_RADIUS_M.max(location.acc.unwrap_or(0) as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fix(hdop: Option<u16>) -> GpsFix {
+        GpsFix {
+            lat: 481_173_000,
+            lon: 115_166_667,
+            alt: None,
+            speed: None,
+            hdop,
+            timestamp: None,
+        }
+    }
+
+    #[test]
+    fn host_locations_are_range_checked() {
+        let location = Location::try_host(-338_600_000, 1_512_100_000, Some(12)).unwrap();
+        assert_eq!(location.src, LocationSource::Host);
+        assert!(Location::try_host(900_000_001, 0, None).is_none());
+        assert!(Location::try_host(0, -1_800_000_001, None).is_none());
+
+        // HDOP 1.5 is about 7 m
+        assert_eq!(Location::from_fix(&fix(Some(150))).acc, Some(7));
+        assert_eq!(Location::from_fix(&fix(None)).acc, None);
+    }
+
+    #[test]
+    fn distances() {
+        let at = |lat, lon| Location::try_host(lat, lon, None).unwrap();
+        let portland = at(455_231_000, -1_226_765_000);
+        assert_eq!(portland.distance_m(&portland), 0);
+        // A tenth of a degree north is about 11.1 km
+        assert_eq!(
+            portland.distance_m(&at(456_231_000, -1_226_765_000)),
+            11_132
+        );
+        // ...and east, at 45.5° N, about 7.8 km
+        let east = portland.distance_m(&at(455_231_000, -1_225_765_000));
+        assert!((7_780..7_820).contains(&east), "{}", east);
+        // The short way across the antimeridian
+        let fiji = at(-180_000_000, 1_799_000_000).distance_m(&at(-180_000_000, -1_799_000_000));
+        assert!((21_000..21_300).contains(&fiji), "{}", fiji);
+    }
+
+    #[test]
+    fn freshest_source_wins() {
+        let policy = StalePolicy::DEFAULT;
+        let mut tracker = LocationTracker::new();
+        assert_eq!(tracker.current(0, policy), None);
+
+        let host = Location::try_host(476_062_000, -1_223_321_000, Some(5)).unwrap();
+        tracker.update(host, 1_000);
+        assert_eq!(tracker.current(2_000, policy), Some((host, false)));
+
+        let gps = Location::from_fix(&fix(Some(90)));
+        tracker.update(gps, 3_000);
+        assert_eq!(tracker.current(4_000, policy), Some((gps, false)));
+
+        // The companion takes over once the module goes quiet
+        tracker.update(host, 10_000);
+        assert_eq!(tracker.current(11_000, policy), Some((host, false)));
+    }
+
+    #[test]
+    fn old_locations_are_stale() {
+        let policy = StalePolicy::try_new(60).unwrap();
+        assert!(StalePolicy::try_new(0).is_none());
+        let mut tracker = LocationTracker::new();
+        let host = Location::try_host(0, 0, None).unwrap();
+        tracker.update(host, 1_000);
+        assert_eq!(tracker.current(61_000, policy), Some((host, false)));
+        // Ten minutes into the garage
+        assert_eq!(tracker.current(601_000, policy), Some((host, true)));
+    }
+
+    #[test]
+    fn slow_gps_positions_are_dead_reckoned() {
+        let policy = StalePolicy::DEFAULT;
+        let mut tracker = LocationTracker::new();
+        let walking = |lat| GpsFix {
+            lat,
+            speed: Some(150),
+            ..fix(None)
+        };
+        tracker.update_fix(&walking(481_000_000), 0);
+        // One fix is nothing to extrapolate from
+        assert_eq!(tracker.current(500, policy).unwrap().0.lat, 481_000_000);
+
+        tracker.update_fix(&walking(481_000_100), 1_000);
+        let (location, stale) = tracker.current(1_500, policy).unwrap();
+        assert_eq!(location.lat, 481_000_150);
+        assert_eq!(location.lon, 115_166_667);
+        assert!(!stale);
+        // At most one fix interval ahead, then the position holds
+        assert_eq!(tracker.current(5_000, policy).unwrap().0.lat, 481_000_200);
+        // ...and goes stale like any other
+        assert!(tracker.current(40_000, policy).unwrap().1);
+
+        // Driving: no extrapolation
+        let driving = GpsFix {
+            speed: Some(2_500),
+            ..walking(481_000_300)
+        };
+        tracker.update_fix(&driving, 2_000);
+        assert_eq!(tracker.current(2_500, policy).unwrap().0.lat, 481_000_300);
+    }
+
+    #[test]
+    fn stationary_users_are_told_from_moving_ones() {
+        let policy = StalePolicy::DEFAULT;
+        let mut tracker = LocationTracker::new();
+        assert_eq!(tracker.is_stationary(0, policy), None);
+
+        // Sitting at a café: the fix wanders a few metres
+        let at = |lat, speed| GpsFix {
+            lat,
+            speed: Some(speed),
+            ..fix(Some(150))
+        };
+        tracker.update_fix(&at(481_173_000, 0), 0);
+        tracker.update_fix(&at(481_173_100, 10), 30_000);
+        // ...not yet for long
+        assert_eq!(tracker.is_stationary(30_000, policy), Some(false));
+        tracker.update_fix(&at(481_173_200, 0), 60_000);
+        assert_eq!(tracker.is_stationary(60_000, policy), Some(true));
+
+        // Getting up: too fast, then 56 m on
+        tracker.update_fix(&at(481_173_900, 140), 61_000);
+        assert_eq!(tracker.is_stationary(61_000, policy), Some(false));
+        tracker.update_fix(&at(481_178_000, 0), 70_000);
+        assert_eq!(tracker.is_stationary(70_000, policy), Some(false));
+
+        // A companion location carries no speed: only distance counts
+        let host = |lat| Location::try_host(lat, 115_166_667, Some(10)).unwrap();
+        tracker.update(host(481_178_100), 130_000);
+        assert_eq!(tracker.is_stationary(130_000, policy), Some(true));
+        tracker.update(host(481_190_000), 140_000);
+        assert_eq!(tracker.is_stationary(140_000, policy), Some(false));
+
+        // No fresh location, no answer
+        assert_eq!(tracker.is_stationary(600_000, policy), None);
+    }
+}
@@ -17,19 +17,33 @@ use esp_backtrace as _;
 esp_bootloader_esp_idf::esp_app_desc!();
 
 // Hardware-specific modules (binary crate only)
+#[cfg(feature = "m5stickc")]
+mod battery;
 #[cfg(any(feature = "m5stickc", feature = "xiao"))]
 mod buzzer;
 #[cfg(feature = "m5stickc")]
 mod display;
+#[cfg(feature = "m5stickc")]
+mod led;
+mod nvs;
+#[cfg(feature = "sdcard")]
+mod sdcard;
 
 // Re-export library modules so binary submodules (display, buzzer) can use crate::*
-pub(crate) use airhound::{board, comm, defaults, filter, protocol, scanner};
+pub(crate) use airhound::{
+    beacon, board, capture, channel, comm, correlate, defaults, error, filter, following, gps,
+    health, hidden, identity, irk, label, lingering, location, prefilter, probe, protocol, route,
+    rssi, rules, scanner, schedule, sequence, sigbundle, sigfile, sightings, store, timing,
+    traffic, watch, wids,
+};
 
 use core::cell::{Cell, RefCell};
 use core::sync::atomic::{AtomicBool, AtomicU32, AtomicU8, Ordering};
 use critical_section::Mutex;
-use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::blocking_mutex::raw::{CriticalSectionRawMutex, NoopRawMutex};
 use embassy_sync::channel::Channel;
+use embassy_sync::pubsub::{PubSubChannel, WaitResult};
+use embassy_sync::signal::Signal;
 use embassy_time::{Duration, Instant, Timer};
 use esp_hal::interrupt::software::SoftwareInterruptControl;
 use esp_hal::timer::timg::TimerGroup;
@@ -37,10 +51,45 @@ use static_cell::StaticCell;
 
 use trouble_host::prelude::*;
 
-use comm::LineReader;
-use filter::{filter_ble, filter_wifi, format_mac, BleScanInput, FilterConfig, WiFiScanInput};
+use beacon::{ActiveSeverity, BeaconSummary, PeerTable};
+use capture::{Evidence, EvidenceRing};
+use channel::{DwellScheduler, Region};
+use comm::{CommandMux, CommandSource, DeviceSettings, InputId};
+use correlate::{Correlation, CrossRadio, Detection, Radio};
+use defaults::{DeviceClass, Severity, SignatureMeta};
+use error::AirhoundError;
+use filter::{
+    estimate_distance, filter_ble_with, filter_bt_classic_with, filter_wifi_with, format_mac,
+    BleScanInput, BtClassicScanInput, FilterConfig, FilterResult, FilterStats, SignatureSource,
+    WiFiScanInput,
+};
+use following::FollowingDetector;
+use health::{HealthMonitor, Subsystem};
+use hidden::{SsidCache, SsidUpdate};
+use identity::IdentityLinker;
+use irk::IrkTable;
+use label::LabelTable;
+use lingering::LingeringDetector;
+use location::{Location, LocationTracker, StalePolicy};
+use prefilter::Prefilter;
+use probe::{ClientProfile, ProbeProfiler};
 use protocol::{DeviceMessage, HostCommand, MacString, MsgBuffer, MAX_MSG_LEN, VERSION};
-use scanner::{BleEvent, ScanEvent, WiFiEvent};
+use route::Subscription;
+use rssi::{Proximity, RssiSmoother};
+use rules::{LedColor, RecentSigs, RuleAction};
+use scanner::{
+    BleEvent, BleScanConfig, BtClassicEvent, ChannelStats, FrameType, ScanEvent, ScanResponseCache,
+    Security, WiFiEvent,
+};
+use schedule::{TsSource, WallClock};
+use sequence::{SequenceAnomaly, SequenceMonitor};
+use sigfile::SignatureOverlay;
+use sightings::SightingCounter;
+use store::{ConfigKey, ConfigStore};
+use timing::TimingMonitor;
+use traffic::{Traffic, TrafficMonitor};
+use watch::{LostDevice, Sighting, WatchList};
+use wids::{Downgrade, SecurityMonitor};
 
 // ── BLE GATT server definition ──────────────────────────────────────
 //
@@ -57,9 +106,23 @@ struct AirHoundGattService {
     tx: [u8; 20],
 
     /// RX — host commands, write-only.
-    /// Companion sends NDJSON commands which are accumulated via LineReader.
+    /// Companion sends NDJSON commands, accumulated per client by `COMMAND_MUX`.
     #[characteristic(uuid = "4a690003-1c4a-4e3c-b5d8-f47b2e1c0a9d", write)]
     rx: [u8; 20],
+
+    /// Status snapshot, read-only — refreshed on every read.
+    /// Layout documented on `comm::encode_status`.
+    #[characteristic(uuid = "4a690004-1c4a-4e3c-b5d8-f47b2e1c0a9d", read)]
+    status: [u8; comm::STATUS_CHAR_LEN],
+
+    /// WiFi/BLE match counters, read-only — refreshed on every read.
+    #[characteristic(uuid = "4a690005-1c4a-4e3c-b5d8-f47b2e1c0a9d", read)]
+    counters: [u8; comm::COUNTERS_CHAR_LEN],
+
+    /// Active settings, read/write. Writes are applied through the command
+    /// task so they are persisted like NDJSON commands.
+    #[characteristic(uuid = "4a690006-1c4a-4e3c-b5d8-f47b2e1c0a9d", read, write)]
+    settings: [u8; comm::SETTINGS_CHAR_LEN],
 }
 
 /// Top-level AirHound GATT server.
@@ -72,11 +135,18 @@ struct AirHoundServer {
 
 type ScanChannel = Channel<CriticalSectionRawMutex, ScanEvent, 16>;
 type OutputChannel = Channel<CriticalSectionRawMutex, MsgBuffer, 8>;
-type BleOutputChannel = Channel<CriticalSectionRawMutex, MsgBuffer, 4>;
+type BleOutputChannel = PubSubChannel<CriticalSectionRawMutex, MsgBuffer, 4, MAX_BLE_CLIENTS, 1>;
 type CommandChannel = Channel<CriticalSectionRawMutex, HostCommand, 4>;
 
 // ── Static channels and shared state ─────────────────────────────────
 
+/// Maximum simultaneously connected BLE clients (e.g. a phone and a logger)
+const MAX_BLE_CLIENTS: usize = 3;
+
+/// Hex Ed25519 public key that signature bundles must be signed with.
+/// Bundle updates are disabled when the firmware is built without one.
+const BUNDLE_KEY: Option<&str> = option_env!("AIRHOUND_BUNDLE_KEY");
+
 /// Static channel for scan events from WiFi sniffer ISR + BLE scan task
 pub(crate) static SCAN_CHANNEL: ScanChannel = Channel::new();
 
@@ -86,50 +156,301 @@ static OUTPUT_CHANNEL: OutputChannel = Channel::new();
 /// Static channel for host commands
 static CMD_CHANNEL: CommandChannel = Channel::new();
 
-/// Static channel for BLE output — serial task clones messages here
-/// for the GATT server to send as notifications.
-static BLE_OUTPUT_CHANNEL: BleOutputChannel = Channel::new();
+/// Static channel for BLE output — serial task publishes clones here and
+/// every connected GATT client receives them, filtered by its subscription.
+static BLE_OUTPUT_CHANNEL: BleOutputChannel = PubSubChannel::new();
 
 /// Static filter config — shared between tasks via critical-section Mutex.
 /// Safe on Embassy's single-threaded executor; the Mutex only guards against
 /// ISR access (WiFi sniffer callback).
 static FILTER_CONFIG: Mutex<Cell<FilterConfig>> = Mutex::new(Cell::new(FilterConfig::new()));
 
+/// Mirror of `FilterConfig::fcs_check` for the WiFi sniffer callback, which
+/// would otherwise copy the whole config out of [`FILTER_CONFIG`] per frame
+static FCS_CHECK: AtomicBool = AtomicBool::new(false);
+
 /// Whether scanning is active (toggled by host Start/Stop commands)
 pub(crate) static SCANNING: AtomicBool = AtomicBool::new(true);
 
+/// Line buffers for host command input — one for serial, one per BLE client
+static COMMAND_MUX: Mutex<RefCell<CommandMux<{ MAX_BLE_CLIENTS + 1 }>>> =
+    Mutex::new(RefCell::new(CommandMux::new()));
+
 /// Number of connected BLE clients
 static BLE_CLIENTS: AtomicU8 = AtomicU8::new(0);
 
+/// The last extended advertising report said more data follows, so the next
+/// one continues its AD data rather than starting a structure
+static EXT_ADV_CONTINUES: AtomicBool = AtomicBool::new(false);
+
+/// Latest advertisement and scan response per address, so either report
+/// goes out with both payloads
+static SCAN_RESPONSES: Mutex<RefCell<ScanResponseCache<16>>> =
+    Mutex::new(RefCell::new(ScanResponseCache::new()));
+
 /// Match counters for display
 pub(crate) static WIFI_MATCH_COUNT: AtomicU32 = AtomicU32::new(0);
 pub(crate) static BLE_MATCH_COUNT: AtomicU32 = AtomicU32::new(0);
 
+/// MACs under investigation (host `watch` command) — shared between
+/// `command_task`, which edits it, and `filter_task`, which reports sightings.
+static WATCH_LIST: Mutex<RefCell<WatchList<4>>> = Mutex::new(RefCell::new(WatchList::new()));
+
+/// Signature hits of recent detections, replayed by the host `test_rule`
+/// command to dry-run candidate rules
+static EVENT_LOG: Mutex<RefCell<rules::EventLog>> =
+    Mutex::new(RefCell::new(rules::EventLog::new()));
+
+/// Raw frames and advertising data of the latest reported matches, for
+/// `get_evidence`
+static EVIDENCE: Mutex<RefCell<EvidenceRing>> = Mutex::new(RefCell::new(EvidenceRing::new()));
+
+/// Filter counters since boot — updated by `filter_task`, reported by
+/// `stats_task` and the host `get_stats` command
+static FILTER_STATS: Mutex<RefCell<FilterStats>> = Mutex::new(RefCell::new(FilterStats::new()));
+
+/// User-assigned device labels (host `label` command), persisted in NVS
+static LABELS: Mutex<RefCell<LabelTable>> = Mutex::new(RefCell::new(LabelTable::new()));
+
+/// IRKs of the user's own devices (host `add_irk` command), persisted in NVS
+static IRKS: Mutex<RefCell<IrkTable>> = Mutex::new(RefCell::new(IrkTable::new()));
+
+/// Wall-clock time from the host `set_time` command; unsynced until then
+static CLOCK: Mutex<Cell<WallClock>> = Mutex::new(Cell::new(WallClock::new()));
+
 /// Last match description for display
 pub(crate) static LAST_MATCH: Mutex<RefCell<heapless::String<32>>> =
     Mutex::new(RefCell::new(heapless::String::new()));
 
+/// Device behind the last match, for the display's QR screen
+#[derive(Clone, Copy)]
+#[cfg_attr(not(feature = "m5stickc"), allow(dead_code))]
+pub(crate) struct LastDetection {
+    pub(crate) mac: [u8; 6],
+    pub(crate) radio: &'static str,
+    pub(crate) rssi: i8,
+    pub(crate) zone: Proximity,
+    pub(crate) uptime_ms: u32,
+}
+
+/// Last match device, updated together with [`LAST_MATCH`]
+pub(crate) static LAST_DETECTION: Mutex<Cell<Option<LastDetection>>> = Mutex::new(Cell::new(None));
+
+/// Highest severity of recent matches, advertised in the beacon summary
+static ACTIVE_SEVERITY: Mutex<Cell<ActiveSeverity>> = Mutex::new(Cell::new(ActiveSeverity::new()));
+
+/// Battery charge in percent from `battery::battery_task`; [`u8::MAX`] on
+/// boards that can't measure it
+pub(crate) static BATTERY_PCT: AtomicU8 = AtomicU8::new(u8::MAX);
+
 /// Whether the buzzer is enabled
 pub(crate) static BUZZER_ENABLED: AtomicBool = AtomicBool::new(true);
 
-/// Signal channel for buzzer beeps
-pub(crate) static BUZZER_SIGNAL: Channel<CriticalSectionRawMutex, (), 1> = Channel::new();
+/// Signal channel for buzzer beeps, carrying the alert to play
+pub(crate) static BUZZER_SIGNAL: Channel<CriticalSectionRawMutex, RuleAction, 1> = Channel::new();
+
+/// LED flash requested by the latest alert
+pub(crate) static LED_SIGNAL: Signal<CriticalSectionRawMutex, LedColor> = Signal::new();
+
+/// New BLE scan parameters from `command_task` — the scanner restarts its
+/// session when signalled
+static BLE_SCAN_SIGNAL: Signal<CriticalSectionRawMutex, BleScanConfig> = Signal::new();
+
+/// `true` when the WiFi sniffer should stop (low-power BLE-only mode or WiFi
+/// disabled), `false` when it should run — signalled by `command_task`
+static WIFI_SUSPEND_SIGNAL: Signal<CriticalSectionRawMutex, bool> = Signal::new();
+
+/// How often the GATT advertisement is rebuilt while waiting for a client
+const BEACON_REFRESH_SECS: u64 = 10;
+
+/// Frame pre-check run in the sniffer ISR — loaded at boot from the
+/// signatures, extended at runtime with watched and matched MACs
+static PREFILTER: Prefilter = Prefilter::new();
+
+/// Per-channel WiFi activity — frames counted in the sniffer ISR, matches by
+/// `filter_task`; reported with the filter counters and on
+/// `get_channel_stats`
+static CHANNEL_STATS: ChannelStats = ChannelStats::new();
+
+/// Signatures added with `add_signature` — checked alongside the boot
+/// overlay and persisted in NVS. An async lock so the filter task can hold it
+/// for a whole event without masking interrupts.
+static RUNTIME_SIGS: embassy_sync::mutex::Mutex<CriticalSectionRawMutex, SignatureOverlay> =
+    embassy_sync::mutex::Mutex::new(SignatureOverlay::new());
+
+/// Serial of the signature bundle loaded at boot, for `sig_version` replies
+static BUNDLE_SERIAL: Mutex<Cell<Option<u32>>> = Mutex::new(Cell::new(None));
+
+/// Liveness check-ins from the scan path — polled by `health_task`
+static HEALTH: HealthMonitor = HealthMonitor::new();
+
+/// Latest GPS and companion positions, for tagging scan messages
+static LOCATION: Mutex<RefCell<LocationTracker>> = Mutex::new(RefCell::new(LocationTracker::new()));
+
+/// NMEA reader for the GPS header, and the uptime (ms) of its last fix —
+/// written by `gps_rx_task`, read for status messages
+#[cfg(feature = "gps")]
+static GPS: Mutex<RefCell<(gps::GpsReader, Option<u32>)>> =
+    Mutex::new(RefCell::new((gps::GpsReader::new(), None)));
+
+/// Uptime in milliseconds, truncated to the protocol's `u32` timestamps.
+fn uptime_ms() -> u32 {
+    (Instant::now().as_millis() & 0xFFFF_FFFF) as u32
+}
+
+/// An open [`COMMAND_MUX`] input, closed (partial line discarded) on drop.
+struct CommandInput(InputId);
+
+impl CommandInput {
+    fn open(source: CommandSource) -> Option<Self> {
+        critical_section::with(|cs| COMMAND_MUX.borrow(cs).borrow_mut().open(source)).map(Self)
+    }
+
+    fn feed(&self, byte: u8) -> Option<Result<HostCommand, AirhoundError>> {
+        critical_section::with(|cs| COMMAND_MUX.borrow(cs).borrow_mut().feed(self.0, byte))
+    }
+}
+
+impl Drop for CommandInput {
+    fn drop(&mut self) {
+        critical_section::with(|cs| COMMAND_MUX.borrow(cs).borrow_mut().close(self.0));
+    }
+}
 
 /// Get a snapshot of the current filter config.
 fn get_filter_config() -> FilterConfig {
     critical_section::with(|cs| FILTER_CONFIG.borrow(cs).get())
 }
 
+/// Replace the filter config, updating the flags mirrored for ISR use.
+fn set_filter_config(config: FilterConfig) {
+    critical_section::with(|cs| FILTER_CONFIG.borrow(cs).set(config));
+    FCS_CHECK.store(config.fcs_check, Ordering::Relaxed);
+}
+
+/// Whether alerts are currently in the configured quiet window.
+fn is_quiet(config: &FilterConfig) -> bool {
+    let clock = critical_section::with(|cs| CLOCK.borrow(cs).get());
+    config.quiet_hours.is_quiet(&clock, uptime_ms())
+}
+
+/// Message timestamp for uptime `at_ms`: Unix milliseconds once a GPS fix
+/// disciplined [`CLOCK`], else uptime.
+fn timestamp(at_ms: u32) -> (u64, TsSource) {
+    critical_section::with(|cs| CLOCK.borrow(cs).get()).timestamp(at_ms)
+}
+
+/// Freshest GPS or companion position to tag a detection with, and whether
+/// it is older than `policy` allows.
+fn current_location(policy: StalePolicy) -> (Option<Location>, bool) {
+    critical_section::with(|cs| LOCATION.borrow_ref(cs).current(uptime_ms(), policy))
+        .map_or((None, false), |(location, stale)| (Some(location), stale))
+}
+
+/// Whether the user is staying put, or `None` without a position fresher
+/// than `policy` allows.
+fn user_stationary(policy: StalePolicy) -> Option<bool> {
+    critical_section::with(|cs| LOCATION.borrow_ref(cs).is_stationary(uptime_ms(), policy))
+}
+
+/// Hand `action` to the buzzer and LED tasks. A busy buzzer drops it.
+fn alert(action: RuleAction) {
+    let _ = BUZZER_SIGNAL.try_send(action);
+    if let Some(color) = action.led {
+        LED_SIGNAL.signal(color);
+    }
+}
+
+/// Unix time now, if the host has synced the clock.
+#[cfg(feature = "m5stickc")]
+pub(crate) fn epoch_now() -> Option<u32> {
+    critical_section::with(|cs| CLOCK.borrow(cs).get()).epoch_s(uptime_ms())
+}
+
+/// Record the latest match for the display, and its severity for the beacon
+/// summary.
+fn set_last_match(
+    mac: &[u8; 6],
+    radio: &'static str,
+    rssi: i8,
+    zone: Proximity,
+    severity: Severity,
+    description: &str,
+) {
+    critical_section::with(|cs| {
+        let active = ACTIVE_SEVERITY.borrow(cs);
+        let mut updated = active.get();
+        updated.record(severity as u8, uptime_ms());
+        active.set(updated);
+
+        let mut s = LAST_MATCH.borrow(cs).borrow_mut();
+        s.clear();
+        let _ = s.push_str(description);
+        LAST_DETECTION.borrow(cs).set(Some(LastDetection {
+            mac: *mac,
+            radio,
+            rssi,
+            zone,
+            uptime_ms: uptime_ms(),
+        }));
+    });
+}
+
+/// GPS receiver state for status messages, on builds with a GPS module.
+#[cfg(feature = "gps")]
+fn status_gps() -> Option<gps::GpsStatus> {
+    let now = uptime_ms();
+    critical_section::with(|cs| {
+        let (reader, last_fix) = &*GPS.borrow_ref(cs);
+        Some(gps::GpsStatus {
+            fix_age: last_fix.map(|at| now.wrapping_sub(at) / 1000),
+            ..reader.status()
+        })
+    })
+}
+
+#[cfg(not(feature = "gps"))]
+fn status_gps() -> Option<gps::GpsStatus> {
+    None
+}
+
+/// Current user settings, as exposed by the GATT settings characteristic.
+fn current_settings() -> DeviceSettings {
+    DeviceSettings::new(
+        &get_filter_config(),
+        SCANNING.load(Ordering::Relaxed),
+        BUZZER_ENABLED.load(Ordering::Relaxed),
+    )
+}
+
+/// Binary status snapshot for the GATT status characteristic.
+fn status_snapshot() -> [u8; comm::STATUS_CHAR_LEN] {
+    comm::encode_status(
+        SCANNING.load(Ordering::Relaxed),
+        BLE_CLIENTS.load(Ordering::Relaxed),
+        (Instant::now().as_millis() / 1000) as u32,
+        esp_alloc::HEAP.free() as u32,
+    )
+}
+
 // ── WiFi sniffer (moved from scanner.rs — references SCAN_CHANNEL) ──
 
 /// WiFi sniffer callback — called from ISR context by the esp-radio sniffer.
 ///
-/// Parses raw 802.11 frames using `parse_wifi_frame()` (ieee80211 crate)
-/// and pushes matching events to the scan channel via `try_send` (non-blocking).
+/// Frames [`PREFILTER`] rules out are dropped first. The rest are parsed with
+/// `parse_wifi_frame_with_fcs()` (ieee80211 crate) — the sniffer delivers
+/// each frame with its FCS, checked when [`FCS_CHECK`] is set — and pushed
+/// to the scan channel via `try_send` (non-blocking).
 fn wifi_sniffer_callback(pkt: esp_radio::wifi::sniffer::PromiscuousPkt<'_>) {
-    let rssi = pkt.rx_cntl.rssi as i8;
+    HEALTH.check_in(Subsystem::WifiSniffer, uptime_ms());
     let channel = pkt.rx_cntl.channel as u8;
-    if let Some(event) = scanner::parse_wifi_frame(pkt.data, rssi, channel) {
+    CHANNEL_STATS.record_frame(channel);
+    if !PREFILTER.allows(pkt.data) {
+        return;
+    }
+    let rssi = pkt.rx_cntl.rssi as i8;
+    let check_fcs = FCS_CHECK.load(Ordering::Relaxed);
+    if let Ok(event) = scanner::parse_wifi_frame_with_fcs(pkt.data, rssi, channel, check_fcs) {
         let _ = SCAN_CHANNEL.try_send(ScanEvent::WiFi(event));
     }
 }
@@ -138,18 +459,90 @@ fn wifi_sniffer_callback(pkt: esp_radio::wifi::sniffer::PromiscuousPkt<'_>) {
 // The symbol is linked via esp-radio's WiFi driver.
 unsafe extern "C" {
     fn esp_wifi_set_channel(primary: u8, second: u32) -> i32;
+    fn esp_wifi_set_country_code(country: *const u8, ieee80211d_enabled: bool) -> i32;
+}
+
+/// Tell the WiFi driver which country's channels are allowed, so
+/// `esp_wifi_set_channel` accepts the whole plan (channel 14 needs JP).
+fn apply_region(region: Region) {
+    let err = unsafe { esp_wifi_set_country_code(region.country_code().as_ptr(), false) };
+    if err != 0 {
+        log::warn!(
+            "Setting WiFi country for {} failed: {}",
+            region.as_str(),
+            err
+        );
+    } else {
+        log::info!("WiFi region: {}", region.as_str());
+    }
 }
 
-/// WiFi channel hop task — cycles through 2.4 GHz channels to capture
-/// traffic across all channels.
+/// WiFi channel hop task — cycles through the 2.4 GHz channels of the
+/// configured regulatory region that pass the channel filter, dwelling on
+/// each as long as its recent activity warrants (`channel::DwellScheduler`).
+/// Owns the sniffer so it can switch
+/// promiscuous mode off entirely in low-power BLE-only mode or when WiFi
+/// scanning is disabled.
 #[embassy_executor::task]
-async fn wifi_channel_hop_task() {
+async fn wifi_channel_hop_task(mut sniffer: esp_radio::wifi::sniffer::Sniffer<'static>) {
+    let mut suspended = !get_filter_config().wifi_active();
+    let mut region = get_filter_config().region;
+    apply_region(region);
+    let mut scheduler = DwellScheduler::new();
     loop {
-        for &ch in scanner::WIFI_CHANNELS {
+        if suspended {
+            let _ = sniffer.set_promiscuous_mode(false);
+            HEALTH.set_suspended(Subsystem::WifiSniffer, true, uptime_ms());
+            HEALTH.set_suspended(Subsystem::ChannelHop, true, uptime_ms());
+            log::info!("WiFi sniffer suspended");
+
+            while WIFI_SUSPEND_SIGNAL.wait().await {}
+
+            suspended = false;
+            let _ = sniffer.set_promiscuous_mode(true);
+            HEALTH.set_suspended(Subsystem::WifiSniffer, false, uptime_ms());
+            HEALTH.set_suspended(Subsystem::ChannelHop, false, uptime_ms());
+            log::info!("WiFi sniffer resumed");
+        }
+
+        // Region and channel filter changes take effect at the start of the
+        // next sweep
+        let config = get_filter_config();
+        if config.region != region {
+            region = config.region;
+            apply_region(region);
+        }
+
+        let mut hopped = false;
+        scheduler.next_sweep();
+        for ch in config.channels.filter(region) {
+            hopped = true;
             unsafe {
                 esp_wifi_set_channel(ch, 0);
             }
-            Timer::after(Duration::from_millis(scanner::DEFAULT_DWELL_MS)).await;
+            HEALTH.check_in(Subsystem::ChannelHop, uptime_ms());
+            let dwell_ms = scheduler.dwell_ms(ch);
+            let dwell = Timer::after(Duration::from_millis(dwell_ms));
+            if let embassy_futures::select::Either::Second(true) =
+                embassy_futures::select::select(dwell, WIFI_SUSPEND_SIGNAL.wait()).await
+            {
+                suspended = true;
+                break;
+            }
+            let (frames, matches) = CHANNEL_STATS.counts(ch);
+            scheduler.record(ch, dwell_ms, frames, matches);
+        }
+
+        // A filter leaving none of the region's channels parks the radio
+        // (the filter drops what it hears); keep checking for a new one
+        if !hopped {
+            HEALTH.check_in(Subsystem::ChannelHop, uptime_ms());
+            let dwell = Timer::after(Duration::from_millis(scanner::DEFAULT_DWELL_MS));
+            if let embassy_futures::select::Either::Second(true) =
+                embassy_futures::select::select(dwell, WIFI_SUSPEND_SIGNAL.wait()).await
+            {
+                suspended = true;
+            }
         }
     }
 }
@@ -158,6 +551,20 @@ async fn wifi_channel_hop_task() {
 
 /// EventHandler for BLE advertisement reports from trouble-host.
 ///
+/// Join a legacy advertisement or scan response with the latest report of
+/// the other kind from the same address; `None` to parse `data` alone.
+fn merge_scan_response(
+    addr: &[u8; 6],
+    scan_response: bool,
+    data: &[u8],
+) -> Option<scanner::MergedAdData> {
+    critical_section::with(|cs| {
+        SCAN_RESPONSES
+            .borrow_ref_mut(cs)
+            .merge(addr, scan_response, data, uptime_ms())
+    })
+}
+
 /// Receives advertisement reports from the BLE stack runner, parses them
 /// using `BleAdvParser`, and pushes results to the scan channel.
 /// Called synchronously from the runner — must not block.
@@ -165,9 +572,57 @@ struct ScanEventHandler;
 
 impl EventHandler for ScanEventHandler {
     fn on_adv_reports(&self, mut it: LeAdvReportsIter<'_>) {
+        HEALTH.check_in(Subsystem::BleScanner, uptime_ms());
+        while let Some(Ok(report)) = it.next() {
+            let addr_bytes: &[u8; 6] = report.addr.raw().try_into().unwrap();
+            let scan_response = report.event_kind == LeAdvEventKind::ScanRsp;
+            // A structure running past the end would fail the whole report;
+            // keep the whole ones before it
+            let report_data = scanner::complete_ad_structures(report.data);
+            let merged = merge_scan_response(addr_bytes, scan_response, report_data);
+            let data = merged.as_deref().unwrap_or(report_data);
+            let Ok(mut event) = scanner::BleAdvParser::parse(addr_bytes, report.rssi, data) else {
+                continue;
+            };
+            event.mac_randomized = report.addr_kind != AddrKind::PUBLIC;
+            let _ = SCAN_CHANNEL.try_send(ScanEvent::Ble(event));
+        }
+    }
+
+    /// BLE 5 extended advertising, which newer trackers use for payloads
+    /// past 31 bytes or for coded-PHY range. Only the first fragment of
+    /// chained data is parsed.
+    fn on_ext_adv_reports(&self, mut it: LeExtAdvReportsIter<'_>) {
+        HEALTH.check_in(Subsystem::BleScanner, uptime_ms());
         while let Some(Ok(report)) = it.next() {
+            let more =
+                report.event_kind.data_status() == LeExtAdvDataStatus::IncompleteMoreExpected;
+            if EXT_ADV_CONTINUES.swap(more, Ordering::Relaxed) {
+                continue;
+            }
             let addr_bytes: &[u8; 6] = report.addr.raw().try_into().unwrap();
-            let event = scanner::BleAdvParser::parse(addr_bytes, report.rssi, report.data);
+            // A first fragment may end mid-structure, as may a malformed
+            // report; parse the whole ones
+            let report_data = scanner::complete_ad_structures(report.data);
+            // Legacy advertising reported through the extended event; a
+            // payload cut short has nothing to join
+            let merged = (!more)
+                .then(|| {
+                    let scan_response = report.event_kind.scan_response();
+                    merge_scan_response(addr_bytes, scan_response, report_data)
+                })
+                .flatten();
+            let data = merged.as_deref().unwrap_or(report_data);
+            let Ok(mut event) = scanner::BleAdvParser::parse_extended(
+                addr_bytes,
+                report.rssi,
+                data,
+                report.secondary_adv_phy as u8,
+                report.tx_power,
+            ) else {
+                continue;
+            };
+            event.mac_randomized = report.addr_kind != AddrKind::PUBLIC;
             let _ = SCAN_CHANNEL.try_send(ScanEvent::Ble(event));
         }
     }
@@ -199,18 +654,153 @@ async fn main(spawner: embassy_executor::Spawner) {
 
     log::info!("AirHound v{} starting on {}", VERSION, board::BOARD_NAME);
 
-    log::info!(
-        "Filter loaded: {} MAC prefixes, {} SSID patterns, {} BLE name patterns",
-        defaults::MAC_PREFIXES.len(),
-        defaults::SSID_PATTERNS.len(),
-        defaults::BLE_NAME_PATTERNS.len(),
-    );
+    for pack in defaults::PACKS {
+        log::info!(
+            "Signature pack {}: {} MAC prefixes, {} SSID patterns, {} BLE name patterns",
+            pack.name,
+            pack.mac_prefixes.len(),
+            pack.ssid_patterns.len(),
+            pack.ble_name_patterns.len(),
+        );
+    }
+
+    // Restore persisted configuration before any task reads it
+    let mut config_store = nvs::NvsStore::new(peripherals.FLASH);
+    match store::load_blob::<FilterConfig>(&mut config_store) {
+        Ok(config) => {
+            set_filter_config(config);
+            log::info!("Filter config restored (min_rssi {})", config.min_rssi);
+        }
+        Err(store::StoreError::NotFound) => {}
+        Err(e) => log::warn!("Stored filter config unreadable: {:?}", e),
+    }
+
+    match store::load_blob::<LabelTable>(&mut config_store) {
+        Ok(labels) => {
+            log::info!("{} device labels restored", labels.len());
+            critical_section::with(|cs| *LABELS.borrow(cs).borrow_mut() = labels);
+        }
+        Err(store::StoreError::NotFound) => {}
+        Err(e) => log::warn!("Stored labels unreadable: {:?}", e),
+    }
+
+    match store::load_blob::<IrkTable>(&mut config_store) {
+        Ok(irks) => {
+            log::info!("{} device IRKs restored", irks.len());
+            critical_section::with(|cs| *IRKS.borrow(cs).borrow_mut() = irks);
+        }
+        Err(store::StoreError::NotFound) => {}
+        Err(e) => log::warn!("Stored IRKs unreadable: {:?}", e),
+    }
+
+    // Runtime signature overlay — filled once at boot, read-only afterwards.
+    // Initialized in place: the overlay is several KiB and main's stack is small.
+    static SIG_OVERLAY: StaticCell<SignatureOverlay> = StaticCell::new();
+    let overlay = SIG_OVERLAY.init_with(SignatureOverlay::new);
+    #[cfg(feature = "sdcard")]
+    match sdcard::load_signatures(
+        peripherals.SPI2,
+        peripherals.GPIO7,
+        peripherals.GPIO8,
+        peripherals.GPIO9,
+        peripherals.GPIO21,
+        overlay,
+    ) {
+        Some(report) => log::info!(
+            "SD signatures: {} loaded, {} skipped",
+            report.loaded,
+            report.skipped
+        ),
+        None => log::info!("No SD signature file, using built-in signatures only"),
+    }
+    let bundle_key = BUNDLE_KEY.and_then(|hex| {
+        let key = sigbundle::parse_key(hex);
+        if key.is_none() {
+            log::warn!("AIRHOUND_BUNDLE_KEY is not a 32-byte hex key, bundle updates disabled");
+        }
+        key
+    });
+    if let Some(key) = &bundle_key {
+        let mut buf = alloc::vec![0u8; sigbundle::MAX_BUNDLE_LEN];
+        match sigbundle::load_installed(&mut config_store, key, &mut buf, overlay) {
+            Some(installed) => {
+                if installed.rolled_back {
+                    log::warn!("Installed signature bundle failed verification, rolled back");
+                }
+                log::info!(
+                    "Signature bundle #{}: {} loaded, {} skipped",
+                    installed.serial,
+                    installed.report.loaded,
+                    installed.report.skipped
+                );
+                critical_section::with(|cs| BUNDLE_SERIAL.borrow(cs).set(Some(installed.serial)));
+            }
+            None => log::info!("No signature bundle installed"),
+        }
+    }
+    let overlay: &'static SignatureOverlay = overlay;
+
+    PREFILTER.add_defaults();
+    for &(oui, ..) in &overlay.mac_prefixes {
+        PREFILTER.add_oui(oui);
+    }
+    // Unknown high-traffic APs can only be found by counting all data frames
+    PREFILTER.set_pass_all(overlay.high_traffic_aps.is_some());
+
+    // Signatures the companion added before the last reboot
+    let mut buf = [0u8; store::MAX_BLOB_LEN];
+    match config_store.load(ConfigKey::RuntimeSignatures, &mut buf) {
+        Ok(len) => {
+            let mut runtime = RUNTIME_SIGS.lock().await;
+            match runtime.restore_runtime(&buf[..len]) {
+                Ok(restored) => log::info!("{} runtime signatures restored", restored),
+                Err(e) => log::warn!("Stored runtime signatures unreadable: {}", e),
+            }
+            for &(oui, ..) in &runtime.mac_prefixes {
+                PREFILTER.add_oui(oui);
+            }
+        }
+        Err(store::StoreError::NotFound) => {}
+        Err(e) => log::warn!("Stored runtime signatures unreadable: {:?}", e),
+    }
 
     // Spawn non-BLE tasks
-    spawner.spawn(filter_task()).unwrap();
+    spawner.spawn(filter_task(overlay)).unwrap();
     spawner.spawn(output_serial_task()).unwrap();
     spawner.spawn(status_task()).unwrap();
-    spawner.spawn(command_task()).unwrap();
+    spawner.spawn(stats_task()).unwrap();
+    spawner.spawn(health_task()).unwrap();
+    spawner
+        .spawn(command_task(config_store, bundle_key))
+        .unwrap();
+
+    // Serial command input: USB-Serial-JTAG on the XIAO, the UART0 bridge
+    // on the M5StickC (esp-println keeps writing to the TX side)
+    #[cfg(feature = "xiao")]
+    {
+        let (rx, _tx) =
+            esp_hal::usb_serial_jtag::UsbSerialJtag::new(peripherals.USB_DEVICE).split();
+        spawner.spawn(serial_rx_task(rx)).unwrap();
+    }
+    #[cfg(feature = "m5stickc")]
+    {
+        let rx = esp_hal::uart::UartRx::new(peripherals.UART0, esp_hal::uart::Config::default())
+            .unwrap()
+            .with_rx(peripherals.GPIO3)
+            .into_async();
+        spawner.spawn(serial_rx_task(rx)).unwrap();
+    }
+
+    // NMEA from the GPS header; the module's RX line is left unconnected
+    #[cfg(feature = "gps")]
+    {
+        let config = esp_hal::uart::Config::default().with_baudrate(GPS_BAUD);
+        let rx = esp_hal::uart::UartRx::new(peripherals.UART1, config)
+            .unwrap()
+            .with_rx(peripherals.GPIO6)
+            .into_async();
+        spawner.spawn(gps_rx_task(rx)).unwrap();
+    }
 
     // Hold power on (M5StickC Plus2 needs GPIO4 HIGH to stay powered)
     #[cfg(feature = "m5stickc")]
@@ -232,6 +822,7 @@ async fn main(spawner: embassy_executor::Spawner) {
                 peripherals.GPIO14,
                 peripherals.GPIO12,
                 peripherals.GPIO27,
+                peripherals.GPIO37,
             ))
             .unwrap();
         log::info!("Display task spawned");
@@ -251,6 +842,22 @@ async fn main(spawner: embassy_executor::Spawner) {
         log::info!("Buzzer task spawned");
     }
 
+    // Status LED task
+    #[cfg(feature = "m5stickc")]
+    {
+        spawner.spawn(led::led_task(peripherals.GPIO10)).unwrap();
+        log::info!("LED task spawned");
+    }
+
+    // Battery monitor (M5StickC only; the XIAO has no battery sense line)
+    #[cfg(feature = "m5stickc")]
+    {
+        spawner
+            .spawn(battery::battery_task(peripherals.ADC1, peripherals.GPIO38))
+            .unwrap();
+        log::info!("Battery task spawned");
+    }
+
     log::info!(
         "Build target: {}",
         if cfg!(feature = "xiao") {
@@ -283,16 +890,17 @@ async fn main(spawner: embassy_executor::Spawner) {
         .set_promiscuous_mode(true)
         .expect("Promiscuous mode failed");
 
-    spawner.spawn(wifi_channel_hop_task()).unwrap();
+    spawner.spawn(wifi_channel_hop_task(sniffer)).unwrap();
 
     log::info!("WiFi sniffer initialized in promiscuous mode");
 
     let controller: ExternalController<_, 20> = ExternalController::new(connector);
 
-    static HOST_RESOURCES: StaticCell<HostResources<DefaultPacketPool, 1, 2>> = StaticCell::new();
+    static HOST_RESOURCES: StaticCell<HostResources<DefaultPacketPool, MAX_BLE_CLIENTS, 2>> =
+        StaticCell::new();
     let resources = HOST_RESOURCES.init(HostResources::new());
 
-    let address = Address::random([0xff, 0x8f, 0x1a, 0x05, 0xe4, 0xab]);
+    let address = Address::random(comm::BLE_ADDRESS);
 
     let stack = trouble_host::new(controller, resources).set_random_address(address);
     let Host {
@@ -316,12 +924,15 @@ async fn main(spawner: embassy_executor::Spawner) {
 
     // ── BLE orchestration ──────────────────────────────────────────────
     //
-    // Three concurrent futures via join3:
+    // Four concurrent futures via join4:
     //   1. BLE stack runner (drives HCI, delivers scan reports to handler)
-    //   2. BLE scanner (starts scan, keeps session alive)
-    //   3. GATT server (advertise, accept connections, send notifications)
+    //   2. BLE scanner (runs a scan session, restarts it when the duty cycle changes)
+    //   3. Advertiser (advertise, accept connections, hand them to a client slot)
+    //   4. Client slots (one per concurrent connection, send notifications)
 
-    let _ = embassy_futures::join::join3(
+    let handoff: Channel<NoopRawMutex, Connection<'_, DefaultPacketPool>, 1> = Channel::new();
+
+    let _ = embassy_futures::join::join4(
         // ── Runner: drives the BLE stack ────────────────────────────────
         async {
             loop {
@@ -331,39 +942,93 @@ async fn main(spawner: embassy_executor::Spawner) {
                 }
             }
         },
-        // ── Scanner: start BLE scan and keep session alive ──────────────
+        // ── Scanner: run a BLE scan session, restarting on config change ─
         async {
             let mut scanner = trouble_host::scan::Scanner::new(central);
-            let config = ScanConfig::default();
-
-            let result = scanner.scan(&config).await;
-            let _session = match result {
-                Ok(session) => session,
-                Err(e) => {
-                    log::error!("BLE scan failed to start: {:?}", e);
-                    return;
-                }
-            };
+            let mut scan = get_filter_config().effective_ble_scan();
 
-            log::info!("BLE scan started (active, continuous)");
-            // Session stays alive as long as _session exists.
-            // Reports flow through ScanEventHandler on the runner.
             loop {
-                Timer::after(Duration::from_secs(60)).await;
+                let config = ScanConfig {
+                    active: scan.active,
+                    // Extended advertising's primary channels: long-range
+                    // trackers advertise on the coded PHY
+                    phys: PhySet::M1Coded,
+                    interval: Duration::from_millis(scan.interval_ms as u64),
+                    window: Duration::from_millis(scan.window_ms as u64),
+                    ..Default::default()
+                };
+
+                // The original ESP32's Bluetooth 4.2 controller has no
+                // extended scanning; reports come in as legacy ones
+                #[cfg(feature = "esp32")]
+                let started = scanner.scan(&config).await;
+                #[cfg(not(feature = "esp32"))]
+                let started = scanner.scan_ext(&config).await;
+
+                match started {
+                    Ok(_session) => {
+                        log::info!(
+                            "BLE scan started ({}, {}ms/{}ms)",
+                            if scan.active { "active" } else { "passive" },
+                            scan.window_ms,
+                            scan.interval_ms
+                        );
+                        // Session stays alive as long as _session exists.
+                        // Reports flow through ScanEventHandler on the runner.
+                        scan = BLE_SCAN_SIGNAL.wait().await;
+                    }
+                    Err(e) => {
+                        log::error!("BLE scan failed to start: {:?}", e);
+                        Timer::after(Duration::from_secs(5)).await;
+                    }
+                }
             }
         },
-        // ── GATT server: advertise, connect, notify ─────────────────────
+        // ── Advertiser: advertise while a client slot is free ───────────
         async {
             loop {
-                // Build advertisement data
+                if BLE_CLIENTS.load(Ordering::Relaxed) as usize >= MAX_BLE_CLIENTS {
+                    Timer::after(Duration::from_secs(1)).await;
+                    continue;
+                }
+
+                // Build advertisement data, with the detection summary in
+                // manufacturer data when the beacon is enabled
+                let beacon = get_filter_config().beacon;
+                let summary = BeaconSummary {
+                    severity: critical_section::with(|cs| ACTIVE_SEVERITY.borrow(cs).get())
+                        .current(uptime_ms()),
+                    battery_pct: Some(BATTERY_PCT.load(Ordering::Relaxed))
+                        .filter(|&pct| pct != u8::MAX),
+                    ..BeaconSummary::new(
+                        SCANNING.load(Ordering::Relaxed),
+                        WIFI_MATCH_COUNT.load(Ordering::Relaxed),
+                        BLE_MATCH_COUNT.load(Ordering::Relaxed),
+                    )
+                }
+                .encode();
+                let base = [
+                    AdStructure::Flags(LE_GENERAL_DISCOVERABLE | BR_EDR_NOT_SUPPORTED),
+                    AdStructure::CompleteLocalName(comm::BLE_ADV_NAME.as_bytes()),
+                    AdStructure::ManufacturerSpecificData {
+                        company_identifier: beacon::COMPANY_ID,
+                        payload: &summary[2..],
+                    },
+                ];
+                let structures = if beacon { &base[..] } else { &base[..2] };
+
+                // The service UUID doesn't fit next to the summary; other
+                // units recognize ours in the scan response
+                let service = [comm::ble_uuids::SERVICE_128.to_le_bytes()];
+                let mut scan_data = [0u8; 31];
+                let scan_len = AdStructure::encode_slice(
+                    &[AdStructure::ServiceUuids128(&service)],
+                    &mut scan_data[..],
+                )
+                .unwrap_or(0);
+
                 let mut adv_data = [0u8; 31];
-                let adv_len = match AdStructure::encode_slice(
-                    &[
-                        AdStructure::Flags(LE_GENERAL_DISCOVERABLE | BR_EDR_NOT_SUPPORTED),
-                        AdStructure::CompleteLocalName(comm::BLE_ADV_NAME.as_bytes()),
-                    ],
-                    &mut adv_data[..],
-                ) {
+                let adv_len = match AdStructure::encode_slice(structures, &mut adv_data[..]) {
                     Ok(len) => len,
                     Err(e) => {
                         log::error!("Ad encode error: {:?}", e);
@@ -378,7 +1043,7 @@ async fn main(spawner: embassy_executor::Spawner) {
                         &Default::default(),
                         Advertisement::ConnectableScannableUndirected {
                             adv_data: &adv_data[..adv_len],
-                            scan_data: &[],
+                            scan_data: &scan_data[..scan_len],
                         },
                     )
                     .await
@@ -391,51 +1056,76 @@ async fn main(spawner: embassy_executor::Spawner) {
                     }
                 };
 
-                log::info!("BLE advertising as '{}'", comm::BLE_ADV_NAME);
+                log::debug!("BLE advertising as '{}'", comm::BLE_ADV_NAME);
 
-                // Wait for a central to connect
-                let conn = match advertiser.accept().await {
-                    Ok(c) => c,
-                    Err(e) => {
+                // Wait for a central to connect. Re-advertise periodically
+                // so the summary stays current and beacon toggles apply.
+                let conn = match embassy_futures::select::select(
+                    advertiser.accept(),
+                    Timer::after(Duration::from_secs(BEACON_REFRESH_SECS)),
+                )
+                .await
+                {
+                    embassy_futures::select::Either::First(Ok(c)) => c,
+                    embassy_futures::select::Either::First(Err(e)) => {
                         log::error!("BLE accept error: {:?}", e);
                         continue;
                     }
+                    embassy_futures::select::Either::Second(()) => continue,
                 };
 
-                let gatt_conn = match conn.with_attribute_server(&server) {
-                    Ok(gc) => gc,
-                    Err(e) => {
-                        log::error!("GATT setup error: {:?}", e);
-                        continue;
-                    }
-                };
-
-                log::info!("BLE client connected");
+                // Counted here so the slot check above sees it immediately
                 BLE_CLIENTS.fetch_add(1, Ordering::Relaxed);
-
-                // Handle the connection until disconnect
-                handle_gatt_connection(&gatt_conn, &server).await;
-
-                BLE_CLIENTS.fetch_sub(1, Ordering::Relaxed);
-                log::info!("BLE client disconnected, re-advertising");
+                handoff.send(conn).await;
             }
         },
+        // ── Client slots: serve each connection until disconnect ────────
+        embassy_futures::join::join_array(core::array::from_fn::<_, MAX_BLE_CLIENTS, _>(
+            |_| async {
+                loop {
+                    let conn = handoff.receive().await;
+                    match conn.with_attribute_server(&server) {
+                        Ok(gatt_conn) => {
+                            log::info!("BLE client connected");
+                            handle_gatt_connection(&gatt_conn, &server).await;
+                            log::info!("BLE client disconnected");
+                        }
+                        Err(e) => log::error!("GATT setup error: {:?}", e),
+                    }
+                    BLE_CLIENTS.fetch_sub(1, Ordering::Relaxed);
+                }
+            },
+        )),
     )
     .await;
 }
 
-/// Handle a GATT connection: forward output messages as notifications
-/// and process incoming writes as host commands.
+/// Handle a GATT connection: forward output messages matching the client's
+/// subscription as notifications and process incoming writes as host commands.
 async fn handle_gatt_connection<'s, P: PacketPool>(
     conn: &GattConnection<'_, 's, P>,
     server: &'s AirHoundServer<'_>,
 ) {
-    let ble_rx = BLE_OUTPUT_CHANNEL.receiver();
-    let mut line_reader = LineReader::new();
+    let Ok(mut ble_rx) = BLE_OUTPUT_CHANNEL.subscriber() else {
+        log::error!("No BLE output subscriber available");
+        return;
+    };
+    let Some(input) = CommandInput::open(CommandSource::Ble) else {
+        log::error!("No command input available");
+        return;
+    };
+    let mut subscription = Subscription::all();
 
     loop {
-        match embassy_futures::select::select(ble_rx.receive(), conn.next()).await {
-            embassy_futures::select::Either::First(msg) => {
+        match embassy_futures::select::select(ble_rx.next_message(), conn.next()).await {
+            embassy_futures::select::Either::First(WaitResult::Lagged(missed)) => {
+                log::warn!("BLE client lagged, {} messages dropped", missed);
+            }
+            embassy_futures::select::Either::First(WaitResult::Message(msg)) => {
+                if !subscription.accepts(&msg) {
+                    continue;
+                }
+
                 // Chunk the NDJSON message into BLE_MAX_NOTIFY-sized pieces.
                 // Pad with newlines so the companion NDJSON parser sees
                 // harmless empty lines instead of null bytes.
@@ -457,17 +1147,50 @@ async fn handle_gatt_connection<'s, P: PacketPool>(
                 match event {
                     GattConnectionEvent::Disconnected { .. } => return,
                     GattConnectionEvent::Gatt { event } => {
-                        // Check if this is a write to our RX characteristic
-                        if let GattEvent::Write(ref write_event) = event {
-                            if write_event.handle() == server.airhound_service.rx.handle {
-                                for &byte in write_event.data() {
-                                    if let Some(line) = line_reader.feed(byte) {
-                                        if let Some(cmd) = comm::parse_command(line) {
-                                            let _ = CMD_CHANNEL.try_send(cmd);
+                        let service = &server.airhound_service;
+                        match event {
+                            // Refresh polled values just before the stack serves the read
+                            GattEvent::Read(ref read_event) => {
+                                let handle = read_event.handle();
+                                if handle == service.status.handle {
+                                    let _ = service.status.set(server, &status_snapshot());
+                                } else if handle == service.counters.handle {
+                                    let counters = comm::encode_counters(
+                                        WIFI_MATCH_COUNT.load(Ordering::Relaxed),
+                                        BLE_MATCH_COUNT.load(Ordering::Relaxed),
+                                    );
+                                    let _ = service.counters.set(server, &counters);
+                                } else if handle == service.settings.handle {
+                                    let settings = current_settings().to_bytes();
+                                    let _ = service.settings.set(server, &settings);
+                                }
+                            }
+                            GattEvent::Write(ref write_event) => {
+                                if write_event.handle() == service.rx.handle {
+                                    for &byte in write_event.data() {
+                                        match input.feed(byte) {
+                                            Some(Ok(HostCommand::Subscribe(sub))) => {
+                                                subscription = sub;
+                                            }
+                                            Some(Ok(cmd)) => {
+                                                let _ = CMD_CHANNEL.try_send(cmd);
+                                            }
+                                            Some(Err(e)) => {
+                                                log::warn!("BLE command rejected: {}", e);
+                                            }
+                                            None => {}
                                         }
                                     }
+                                } else if write_event.handle() == service.settings.handle {
+                                    if let Some(settings) =
+                                        DeviceSettings::from_bytes(write_event.data())
+                                    {
+                                        let _ =
+                                            CMD_CHANNEL.try_send(HostCommand::Configure(settings));
+                                    }
                                 }
                             }
+                            _ => {}
                         }
                         // Must accept/reply to all GATT events
                         match event.accept() {
@@ -482,14 +1205,50 @@ async fn handle_gatt_connection<'s, P: PacketPool>(
     }
 }
 
+/// Per-device state the filter task keeps across scan events
+struct Trackers {
+    sightings: SightingCounter<32>,
+    recent: RecentSigs,
+    rssi: RssiSmoother<32>,
+    ssids: SsidCache<16>,
+    radios: CrossRadio<16>,
+    identities: IdentityLinker<32>,
+    clocks: TimingMonitor<32>,
+    sequences: SequenceMonitor<32>,
+    following: FollowingDetector<16>,
+    lingering: LingeringDetector<16>,
+}
+
+impl Trackers {
+    const fn new() -> Self {
+        Self {
+            sightings: SightingCounter::new(),
+            recent: RecentSigs::new(),
+            rssi: RssiSmoother::new(),
+            ssids: SsidCache::new(),
+            radios: CrossRadio::new(),
+            identities: IdentityLinker::new(),
+            clocks: TimingMonitor::new(),
+            sequences: SequenceMonitor::new(),
+            following: FollowingDetector::new(),
+            lingering: LingeringDetector::new(),
+        }
+    }
+}
+
 /// Filter task — receives raw scan events, applies filters, and serializes
 /// matching results to the output channel.
 #[embassy_executor::task]
-async fn filter_task() {
+async fn filter_task(overlay: &'static SignatureOverlay) {
     log::info!("Filter task started");
 
     let scan_rx = SCAN_CHANNEL.receiver();
     let output_tx = OUTPUT_CHANNEL.sender();
+    let mut peers = PeerTable::<8>::new(PEER_REPORT_INTERVAL_MS);
+    let mut traffic = TrafficMonitor::<32>::new();
+    let mut trackers = Trackers::new();
+    let mut networks = SecurityMonitor::<32>::new();
+    let mut clients = ProbeProfiler::<16>::new();
 
     loop {
         let event = scan_rx.receive().await;
@@ -500,125 +1259,872 @@ async fn filter_task() {
 
         let config = get_filter_config();
 
+        // Watched MACs are reported on every sighting, before any filtering
+        let (mac, rssi, radio) = match &event {
+            ScanEvent::WiFi(wifi) => (wifi.mac, wifi.rssi, "wifi"),
+            ScanEvent::Ble(ble) => (ble.mac, ble.rssi, "ble"),
+            ScanEvent::BtClassic(classic) => (classic.mac, classic.rssi, "bt_classic"),
+        };
+        critical_section::with(|cs| {
+            let mut watches = WATCH_LIST.borrow(cs).borrow_mut();
+            if let Some(sighting) = watches.observe(&mac, radio, rssi, uptime_ms()) {
+                send_watch(&mac, radio, rssi, &sighting, &output_tx);
+            }
+            while let Some(lost) = watches.take_lost(uptime_ms()) {
+                send_watch_lost(&lost, &output_tx);
+            }
+        });
+
+        let runtime = RUNTIME_SIGS.lock().await;
+        let sigs = (overlay, &*runtime);
         match event {
             ScanEvent::WiFi(ref wifi) => {
-                handle_wifi_event(wifi, &config, &output_tx).await;
+                // Hidden beacons take the SSID a probe response revealed
+                let update = trackers.ssids.observe(
+                    &wifi.mac,
+                    &wifi.ssid,
+                    wifi.frame_type,
+                    wifi.rssi,
+                    wifi.channel,
+                    uptime_ms(),
+                );
+                let backfilled;
+                let wifi = match update {
+                    SsidUpdate::Backfilled(ref ssid) => {
+                        let mut event = wifi.clone();
+                        event.ssid = ssid.clone();
+                        backfilled = event;
+                        &backfilled
+                    }
+                    _ => wifi,
+                };
+                if let Some(downgrade) =
+                    networks.observe(&wifi.ssid, &wifi.mac, wifi.security, uptime_ms())
+                {
+                    send_downgrade(wifi, &downgrade, &config, &output_tx);
+                }
+                if wifi.frame_type == FrameType::ProbeRequest {
+                    if let Some(profile) =
+                        clients.observe(&wifi.mac, &wifi.ssid, wifi.rssi, uptime_ms())
+                    {
+                        send_client_profile(profile, &output_tx);
+                    }
+                }
+                // Relayed frames count toward the relay's traffic, not the
+                // level of the device they came from
+                let level = match (wifi.frame_type, wifi.relay) {
+                    (FrameType::Data, None) => traffic.record(&wifi.mac, wifi.from_ds, uptime_ms()),
+                    (FrameType::Data, Some(relay)) => {
+                        traffic.record(&relay, wifi.from_ds, uptime_ms());
+                        traffic.traffic(&wifi.mac, uptime_ms())
+                    }
+                    _ => traffic.traffic(&wifi.mac, uptime_ms()),
+                };
+                handle_wifi_event(wifi, level, &config, &sigs, &mut trackers, &output_tx).await;
+
+                // Evaluate the BSSID's earlier hidden beacon again under its
+                // real SSID, with the elements the probe response repeats
+                if let SsidUpdate::Revealed { rssi, channel } = update {
+                    let mut beacon = wifi.clone();
+                    beacon.frame_type = FrameType::Beacon;
+                    beacon.rssi = rssi;
+                    beacon.channel = channel;
+                    handle_wifi_event(&beacon, level, &config, &sigs, &mut trackers, &output_tx)
+                        .await;
+                }
             }
             ScanEvent::Ble(ref ble) => {
-                handle_ble_event(ble, &config, &output_tx).await;
+                if let Some(summary) = ble.peer {
+                    if peers.observe(&ble.mac, summary, uptime_ms()) {
+                        send_peer(ble, &summary, &output_tx);
+                    }
+                    continue;
+                }
+                handle_ble_event(ble, &config, &sigs, &mut trackers, &output_tx).await;
+            }
+            ScanEvent::BtClassic(ref classic) => {
+                handle_bt_classic_event(classic, &config, &sigs, &mut trackers, &output_tx).await;
             }
         }
     }
 }
 
-async fn handle_wifi_event(
-    wifi: &WiFiEvent,
-    config: &FilterConfig,
+/// Log a filter result's signature hits for `test_rule` and count it in
+/// [`FILTER_STATS`].
+fn record_result(result: &FilterResult) {
+    critical_section::with(|cs| {
+        EVENT_LOG.borrow(cs).borrow_mut().push(&result.hits);
+        FILTER_STATS.borrow(cs).borrow_mut().record(result);
+    });
+}
+
+/// Keep the bytes of a reported match for `get_evidence`.
+fn record_evidence(evidence: Evidence) {
+    critical_section::with(|cs| EVIDENCE.borrow(cs).borrow_mut().push(evidence));
+}
+
+/// User label for `mac`, if one is set.
+fn label_for(mac: &[u8; 6]) -> Option<label::Label> {
+    critical_section::with(|cs| LABELS.borrow(cs).borrow().get(mac).cloned())
+}
+
+/// Report a sighting of a watched MAC with its recent RSSI history and
+/// closest approach so far.
+fn send_watch(
+    mac: &[u8; 6],
+    radio: &'static str,
+    rssi: i8,
+    sighting: &Sighting<'_>,
     output_tx: &embassy_sync::channel::Sender<'_, CriticalSectionRawMutex, MsgBuffer, 8>,
 ) {
-    let input = WiFiScanInput {
-        mac: &wifi.mac,
-        ssid: wifi.ssid.as_str(),
-        rssi: wifi.rssi,
-    };
-
-    let result = filter_wifi(&input, config);
-    if !result.matched {
-        return;
-    }
+    let mut mac_str = MacString::new();
+    format_mac(mac, &mut mac_str);
 
-    WIFI_MATCH_COUNT.fetch_add(1, Ordering::Relaxed);
+    let label = label_for(mac);
+    let msg = DeviceMessage::Watch {
+        mac: &mac_str,
+        radio,
+        rssi,
+        history: sighting.history,
+        closest_rssi: sighting.closest.rssi,
+        closest_ts: sighting.closest.ts,
+        label: label.as_deref(),
+        ts: uptime_ms(),
+    };
 
-    // Update last match description for display
-    if let Some(first) = result.matches.first() {
-        critical_section::with(|cs| {
-            let mut s = LAST_MATCH.borrow(cs).borrow_mut();
-            s.clear();
-            let _ = s.push_str(&first.detail);
-        });
+    let mut buf = MsgBuffer::new();
+    buf.resize_default(MAX_MSG_LEN).ok();
+    if let Ok(len) = comm::serialize_message(&msg, &mut buf) {
+        buf.truncate(len);
+        let _ = output_tx.try_send(buf);
     }
+}
 
-    // Trigger buzzer beep
-    let _ = BUZZER_SIGNAL.try_send(());
-
+/// Report the end of an encounter with a watched MAC.
+fn send_watch_lost(
+    lost: &LostDevice,
+    output_tx: &embassy_sync::channel::Sender<'_, CriticalSectionRawMutex, MsgBuffer, 8>,
+) {
     let mut mac_str = MacString::new();
-    format_mac(&wifi.mac, &mut mac_str);
+    format_mac(&lost.mac, &mut mac_str);
 
-    let ts = (Instant::now().as_millis() & 0xFFFF_FFFF) as u32;
-
-    let msg = DeviceMessage::WiFiScan {
+    let label = label_for(&lost.mac);
+    let msg = DeviceMessage::WatchLost {
         mac: &mac_str,
-        ssid: &wifi.ssid,
-        rssi: wifi.rssi,
-        ch: wifi.channel,
-        frame: wifi.frame_type.as_str(),
-        matches: &result.matches,
-        ts,
+        radio: lost.radio,
+        first_seen: lost.first_seen_ms,
+        last_seen: lost.last_seen_ms,
+        sightings: lost.sightings,
+        closest_rssi: lost.closest.rssi,
+        closest_ts: lost.closest.ts,
+        label: label.as_deref(),
+        ts: uptime_ms(),
     };
 
     let mut buf = MsgBuffer::new();
     buf.resize_default(MAX_MSG_LEN).ok();
-    if let Some(len) = comm::serialize_message(&msg, &mut buf) {
+    if let Ok(len) = comm::serialize_message(&msg, &mut buf) {
         buf.truncate(len);
         let _ = output_tx.try_send(buf);
     }
 }
 
-async fn handle_ble_event(
-    ble: &BleEvent,
+/// Report a known network seen with weaker security than usual.
+fn send_downgrade(
+    wifi: &WiFiEvent,
+    downgrade: &Downgrade,
     config: &FilterConfig,
     output_tx: &embassy_sync::channel::Sender<'_, CriticalSectionRawMutex, MsgBuffer, 8>,
 ) {
-    let input = BleScanInput {
-        mac: &ble.mac,
-        name: ble.name.as_str(),
+    log::warn!(
+        "Security downgrade: {} {} -> {}",
+        downgrade.ssid,
+        downgrade.expected.as_str(),
+        downgrade.seen.as_str()
+    );
+    if !is_quiet(config) {
+        alert(RuleAction::DEFAULT);
+    }
+
+    let mut bssid = MacString::new();
+    format_mac(&downgrade.bssid, &mut bssid);
+
+    let msg = DeviceMessage::Downgrade {
+        ssid: &wifi.ssid,
+        bssid: &bssid,
+        expected: downgrade.expected.as_str(),
+        seen: downgrade.seen.as_str(),
+        rssi: wifi.rssi,
+        ch: wifi.channel,
+        ts: uptime_ms(),
+    };
+
+    let mut buf = MsgBuffer::new();
+    buf.resize_default(MAX_MSG_LEN).ok();
+    if let Ok(len) = comm::serialize_message(&msg, &mut buf) {
+        buf.truncate(len);
+        let _ = output_tx.try_send(buf);
+    }
+}
+
+/// Minimum time between reports of an unchanged peer summary
+const PEER_REPORT_INTERVAL_MS: u32 = 30_000;
+
+/// Forward another AirHound unit's advertised summary to the companion.
+fn send_peer(
+    ble: &BleEvent,
+    summary: &BeaconSummary,
+    output_tx: &embassy_sync::channel::Sender<'_, CriticalSectionRawMutex, MsgBuffer, 8>,
+) {
+    let mut mac_str = MacString::new();
+    format_mac(&ble.mac, &mut mac_str);
+
+    let msg = DeviceMessage::Peer {
+        mac: &mac_str,
         rssi: ble.rssi,
-        service_uuids_16: &ble.service_uuids_16,
-        manufacturer_id: ble.manufacturer_id,
+        scanning: summary.scanning,
+        wifi: summary.wifi_matches,
+        ble: summary.ble_matches,
+        severity: summary.severity,
+        battery: summary.battery_pct,
+        ts: uptime_ms(),
     };
 
-    let result = filter_ble(&input, config);
+    let mut buf = MsgBuffer::new();
+    buf.resize_default(MAX_MSG_LEN).ok();
+    if let Ok(len) = comm::serialize_message(&msg, &mut buf) {
+        buf.truncate(len);
+        let _ = output_tx.try_send(buf);
+    }
+}
+
+async fn handle_wifi_event(
+    wifi: &WiFiEvent,
+    traffic: Traffic,
+    config: &FilterConfig,
+    sigs: &impl SignatureSource,
+    trackers: &mut Trackers,
+    output_tx: &embassy_sync::channel::Sender<'_, CriticalSectionRawMutex, MsgBuffer, 8>,
+) {
+    let mac_randomized = filter::is_locally_administered(&wifi.mac);
+    let timing = wifi
+        .timing
+        .and_then(|timing| trackers.clocks.observe(&wifi.mac, timing, uptime_ms()));
+    let sequence = wifi
+        .seq
+        .and_then(|seq| trackers.sequences.observe(&wifi.mac, seq, uptime_ms()));
+    // Two devices sharing the address: their readings make one meaningless
+    // average and trend
+    if sequence == Some(SequenceAnomaly::Interleaved) {
+        trackers.rssi.forget(&wifi.mac);
+    }
+    let input = WiFiScanInput {
+        mac: &wifi.mac,
+        mac_randomized,
+        ssid: wifi.ssid.as_str(),
+        rssi: wifi.rssi,
+        channel: wifi.channel,
+        traffic,
+        ies: &wifi.ies,
+        vendor_ies: &wifi.vendor_ies,
+        wps: wifi.wps.as_ref(),
+        capability: wifi.capability,
+        timing,
+        sequence,
+        action: wifi.action,
+        network: wifi.network,
+    };
+
+    let mut result = filter_wifi_with(&input, config, sigs);
+    record_result(&result);
     if !result.matched {
         return;
     }
+    CHANNEL_STATS.record_match(wifi.channel);
 
-    BLE_MATCH_COUNT.fetch_add(1, Ordering::Relaxed);
+    // Rules pairing signatures across detections look back over recent ones
+    result.apply_recent_rules(&trackers.recent, uptime_ms());
+    trackers.recent.record(&result.hits, uptime_ms());
+
+    let avg = trackers.rssi.observe(&wifi.mac, wifi.rssi, uptime_ms());
+    let rssi_avg = config.rssi_smoothing.then_some(avg);
+    let approaching = trackers.rssi.approaching(&wifi.mac);
+    let zone = config.zones.classify(rssi_avg.unwrap_or(wifi.rssi));
+
+    // Weak indicators wait until the device has been seen often enough
+    if trackers.sightings.observe(&wifi.mac, uptime_ms()) < result.min_sightings as u16 {
+        return;
+    }
+
+    record_evidence(Evidence::wifi(wifi, uptime_ms()));
+
+    // Let this device's data frames through from now on, so its traffic
+    // level keeps being tracked even if it matched on SSID alone
+    PREFILTER.add_oui([wifi.mac[0], wifi.mac[1], wifi.mac[2]]);
+
+    WIFI_MATCH_COUNT.fetch_add(1, Ordering::Relaxed);
+    let label = label_for(&wifi.mac);
+
+    // A randomized MAC takes the ID of the one it rotated from
+    let device_id = identity::wifi_fingerprint(&wifi.ssid, &wifi.vendor_ies)
+        .filter(|_| mac_randomized)
+        .map(|fp| {
+            trackers
+                .identities
+                .observe(&wifi.mac, fp, wifi.rssi, uptime_ms())
+        });
 
     // Update last match description for display
     if let Some(first) = result.matches.first() {
-        critical_section::with(|cs| {
-            let mut s = LAST_MATCH.borrow(cs).borrow_mut();
-            s.clear();
-            let _ = s.push_str(&first.detail);
+        let description = label.as_deref().unwrap_or(&first.detail);
+        set_last_match(
+            &wifi.mac,
+            "wifi",
+            wifi.rssi,
+            zone,
+            result.meta.severity,
+            description,
+        );
+    }
+
+    // Trigger buzzer beep, unless in quiet hours
+    let quiet = is_quiet(config);
+    if !quiet {
+        // Escalate for a device that is getting closer
+        let action = rules::alert_action(&result.rules);
+        alert(if approaching {
+            action.escalated()
+        } else {
+            action
         });
     }
 
-    // Trigger buzzer beep
-    let _ = BUZZER_SIGNAL.try_send(());
+    let mut mac_str = MacString::new();
+    format_mac(&wifi.mac, &mut mac_str);
+    let via = wifi.relay.map(|relay| {
+        let mut relay_str = MacString::new();
+        format_mac(&relay, &mut relay_str);
+        relay_str
+    });
+
+    let (ts, ts_source) = timestamp(uptime_ms());
+    let (loc, location_stale) = current_location(config.location_stale);
+
+    let msg = DeviceMessage::WiFiScan {
+        mac: &mac_str,
+        mac_randomized,
+        via: via.as_ref(),
+        ssid: &wifi.ssid,
+        rssi: wifi.rssi,
+        rssi_avg,
+        zone,
+        approaching,
+        ch: wifi.channel,
+        frame: wifi.frame_type.as_str(),
+        action: wifi.action.map(|action| action.as_str()),
+        security: (wifi.security != Security::Unknown).then(|| wifi.security.as_str()),
+        severity: result.meta.severity,
+        category: result.meta.category,
+        device_class: result.meta.device_class,
+        confidence: result.meta.confidence,
+        matches: &result.matches,
+        rules: &result.rules,
+        truncated: result.truncated,
+        label: label.as_deref(),
+        device_id,
+        remote_id: wifi.remote_id.as_ref(),
+        p2p: wifi.p2p,
+        network: wifi.network,
+        quiet,
+        loc,
+        location_stale,
+        ts,
+        ts_source,
+    };
+
+    let mut buf = MsgBuffer::new();
+    buf.resize_default(MAX_MSG_LEN).ok();
+    if let Ok(len) = comm::serialize_message(&msg, &mut buf) {
+        buf.truncate(len);
+        let _ = output_tx.try_send(buf);
+    }
+
+    let detection = Detection {
+        mac: wifi.mac,
+        rssi: wifi.rssi,
+        meta: result.meta,
+    };
+    if let Some(pair) = trackers.radios.observe(Radio::WiFi, detection, uptime_ms()) {
+        send_correlated(&pair, quiet, output_tx);
+    }
+}
+
+async fn handle_ble_event(
+    ble: &BleEvent,
+    config: &FilterConfig,
+    sigs: &impl SignatureSource,
+    trackers: &mut Trackers,
+    output_tx: &embassy_sync::channel::Sender<'_, CriticalSectionRawMutex, MsgBuffer, 8>,
+) {
+    let input = BleScanInput {
+        mac: &ble.mac,
+        mac_randomized: ble.mac_randomized,
+        name: ble.name.as_str(),
+        rssi: ble.rssi,
+        service_uuids_16: &ble.service_uuids_16,
+        service_uuids_32: &ble.service_uuids_32,
+        service_uuids_128: &ble.service_uuids_128,
+        manufacturer_id: ble.manufacturer_id,
+        matter: ble.matter,
+        ibeacon: ble.ibeacon,
+        eddystone: ble.eddystone.as_ref(),
+        thread_commissioning: ble.thread_commissioning,
+        ad_data: &ble.ad_data,
+    };
+
+    let mut result = filter_ble_with(&input, config, sigs);
+    record_result(&result);
+    if !result.matched {
+        return;
+    }
+
+    // Rules pairing signatures across detections look back over recent ones
+    result.apply_recent_rules(&trackers.recent, uptime_ms());
+    trackers.recent.record(&result.hits, uptime_ms());
+
+    let avg = trackers.rssi.observe(&ble.mac, ble.rssi, uptime_ms());
+    let rssi_avg = config.rssi_smoothing.then_some(avg);
+    let approaching = trackers.rssi.approaching(&ble.mac);
+    let zone = config.zones.classify(rssi_avg.unwrap_or(ble.rssi));
+    let dist_m = ble
+        .tx_power
+        .map(|tx_power| estimate_distance(rssi_avg.unwrap_or(ble.rssi), tx_power));
+
+    // Weak indicators wait until the device has been seen often enough
+    if trackers.sightings.observe(&ble.mac, uptime_ms()) < result.min_sightings as u16 {
+        return;
+    }
+
+    record_evidence(Evidence::ble(ble, uptime_ms()));
+
+    // The user's own devices are reported but never alert
+    let mine = critical_section::with(|cs| IRKS.borrow(cs).borrow().resolves(&ble.mac));
+    let label = label_for(&ble.mac);
+    let device_id = identity::ble_fingerprint(&ble.ad_data)
+        .filter(|_| ble.mac_randomized)
+        .map(|fp| {
+            trackers
+                .identities
+                .observe(&ble.mac, fp, ble.rssi, uptime_ms())
+        });
+    let quiet = is_quiet(config);
+
+    if !mine {
+        BLE_MATCH_COUNT.fetch_add(1, Ordering::Relaxed);
+
+        // Update last match description for display
+        if let Some(first) = result.matches.first() {
+            let description = label.as_deref().unwrap_or(&first.detail);
+            set_last_match(
+                &ble.mac,
+                "ble",
+                ble.rssi,
+                zone,
+                result.meta.severity,
+                description,
+            );
+        }
+
+        // Trigger buzzer beep, unless in quiet hours
+        if !quiet {
+            // Escalate for a device that is getting closer
+            let action = rules::alert_action(&result.rules);
+            alert(if approaching {
+                action.escalated()
+            } else {
+                action
+            });
+        }
+    }
 
     let mut mac_str = MacString::new();
     format_mac(&ble.mac, &mut mac_str);
 
-    let ts = (Instant::now().as_millis() & 0xFFFF_FFFF) as u32;
+    let (ts, ts_source) = timestamp(uptime_ms());
+    let (loc, location_stale) = current_location(config.location_stale);
 
     let msg = DeviceMessage::BleScan {
         mac: &mac_str,
+        mac_randomized: ble.mac_randomized,
         name: &ble.name,
         rssi: ble.rssi,
+        rssi_avg,
+        zone,
+        approaching,
+        dist_m,
         uuid: None, // TODO: format primary UUID if present
         mfr: ble.manufacturer_id,
+        phy: ble.secondary_phy.map(|phy| phy.as_str()),
+        severity: result.meta.severity,
+        category: result.meta.category,
+        device_class: result.meta.device_class,
+        confidence: result.meta.confidence,
         matches: &result.matches,
+        rules: &result.rules,
+        truncated: result.truncated,
+        label: label.as_deref(),
+        device_id,
+        remote_id: ble.remote_id.as_ref(),
+        findmy: ble.findmy,
+        mine,
+        quiet,
+        loc,
+        location_stale,
+        ts,
+        ts_source,
+    };
+
+    let mut buf = MsgBuffer::new();
+    buf.resize_default(MAX_MSG_LEN).ok();
+    if let Ok(len) = comm::serialize_message(&msg, &mut buf) {
+        buf.truncate(len);
+        let _ = output_tx.try_send(buf);
+    }
+
+    if mine {
+        return;
+    }
+    let detection = Detection {
+        mac: ble.mac,
+        rssi: ble.rssi,
+        meta: result.meta,
+    };
+    if let Some(pair) = trackers.radios.observe(Radio::Ble, detection, uptime_ms()) {
+        send_correlated(&pair, quiet, output_tx);
+    }
+    let loc = loc.filter(|_| !location_stale);
+    if let Some(loc) = loc {
+        check_following(
+            &ble.mac,
+            device_id,
+            &result.meta,
+            &loc,
+            config,
+            trackers,
+            output_tx,
+        );
+    }
+    check_lingering(&detection, device_id, loc, config, trackers, output_tx);
+}
+
+/// Feed a BLE or Classic detection, made with the user at `loc`, to
+/// following detection, and report the device once it has travelled along.
+fn check_following(
+    mac: &[u8; 6],
+    device_id: Option<u16>,
+    meta: &SignatureMeta,
+    loc: &Location,
+    config: &FilterConfig,
+    trackers: &mut Trackers,
+    output_tx: &embassy_sync::channel::Sender<'_, CriticalSectionRawMutex, MsgBuffer, 8>,
+) {
+    let now = uptime_ms();
+    let Some(follower) = trackers
+        .following
+        .observe(mac, device_id, loc, now, &config.following)
+    else {
+        return;
+    };
+
+    let mut mac_str = MacString::new();
+    format_mac(&follower.mac, &mut mac_str);
+    log::warn!(
+        "Following: {} seen {} times over {} m",
+        mac_str,
+        follower.sightings,
+        follower.span_m
+    );
+    let quiet = is_quiet(config);
+    if !quiet {
+        alert(RuleAction::URGENT);
+    }
+
+    let label = label_for(mac);
+    let (ts, ts_source) = timestamp(now);
+    let msg = DeviceMessage::FollowingAlert {
+        mac: &mac_str,
+        device_id: follower.device_id,
+        severity: Severity::High,
+        category: meta.category,
+        device_class: meta.device_class,
+        sightings: follower.sightings,
+        span_m: follower.span_m,
+        duration_s: now.wrapping_sub(follower.first_seen_ms) / 1000,
+        label: label.as_deref(),
+        quiet,
+        loc: *loc,
+        ts,
+        ts_source,
+    };
+
+    let mut buf = MsgBuffer::new();
+    buf.resize_default(MAX_MSG_LEN).ok();
+    if let Ok(len) = comm::serialize_message(&msg, &mut buf) {
+        buf.truncate(len);
+        let _ = output_tx.try_send(buf);
+    }
+}
+
+/// Time how long a tracker-class detection has stayed within range while
+/// the user stays put, and report it once it has lingered. `loc` is the
+/// user's fresh location, if any.
+fn check_lingering(
+    detection: &Detection,
+    device_id: Option<u16>,
+    loc: Option<Location>,
+    config: &FilterConfig,
+    trackers: &mut Trackers,
+    output_tx: &embassy_sync::channel::Sender<'_, CriticalSectionRawMutex, MsgBuffer, 8>,
+) {
+    if detection.meta.device_class != DeviceClass::PersonalTracker {
+        return;
+    }
+    // A tracker keeping up with a moving user is following detection's to
+    // find. Without a position there is no telling, so time it anyway.
+    if user_stationary(config.location_stale) == Some(false) {
+        return;
+    }
+    let now = uptime_ms();
+    let Some(lingerer) = trackers.lingering.observe(
+        &detection.mac,
+        device_id,
+        detection.rssi,
+        now,
+        &config.lingering,
+    ) else {
+        return;
+    };
+
+    let mut mac_str = MacString::new();
+    format_mac(&lingerer.mac, &mut mac_str);
+    log::warn!(
+        "Lingering: {} within range for {} s",
+        mac_str,
+        lingerer.duration_ms / 1000
+    );
+    let quiet = is_quiet(config);
+    if !quiet {
+        alert(RuleAction::DEFAULT.escalated());
+    }
+
+    let label = label_for(&lingerer.mac);
+    let (ts, ts_source) = timestamp(now);
+    let msg = DeviceMessage::LingeringAlert {
+        mac: &mac_str,
+        device_id: lingerer.device_id,
+        severity: Severity::Medium,
+        category: detection.meta.category,
+        device_class: detection.meta.device_class,
+        rssi: detection.rssi,
+        duration_s: lingerer.duration_ms / 1000,
+        label: label.as_deref(),
+        quiet,
+        loc,
         ts,
+        ts_source,
+    };
+
+    let mut buf = MsgBuffer::new();
+    buf.resize_default(MAX_MSG_LEN).ok();
+    if let Ok(len) = comm::serialize_message(&msg, &mut buf) {
+        buf.truncate(len);
+        let _ = output_tx.try_send(buf);
+    }
+}
+
+/// Report a WiFi and a BLE detection paired by [`CrossRadio`] as one device.
+fn send_correlated(
+    pair: &Correlation,
+    quiet: bool,
+    output_tx: &embassy_sync::channel::Sender<'_, CriticalSectionRawMutex, MsgBuffer, 8>,
+) {
+    let mut wifi_mac = MacString::new();
+    format_mac(&pair.wifi.mac, &mut wifi_mac);
+    let mut ble_mac = MacString::new();
+    format_mac(&pair.ble.mac, &mut ble_mac);
+    log::info!("Correlated WiFi {} with BLE {}", wifi_mac, ble_mac);
+
+    let msg = DeviceMessage::Correlated {
+        wifi_mac: &wifi_mac,
+        ble_mac: &ble_mac,
+        wifi_rssi: pair.wifi.rssi,
+        ble_rssi: pair.ble.rssi,
+        severity: pair.meta.severity,
+        category: pair.meta.category,
+        device_class: pair.meta.device_class,
+        confidence: pair.meta.confidence,
+        quiet,
+        ts: uptime_ms(),
     };
 
     let mut buf = MsgBuffer::new();
     buf.resize_default(MAX_MSG_LEN).ok();
-    if let Some(len) = comm::serialize_message(&msg, &mut buf) {
+    if let Ok(len) = comm::serialize_message(&msg, &mut buf) {
         buf.truncate(len);
         let _ = output_tx.try_send(buf);
     }
 }
 
+/// Report the directed SSIDs a probing client has asked for.
+fn send_client_profile(
+    profile: &ClientProfile,
+    output_tx: &embassy_sync::channel::Sender<'_, CriticalSectionRawMutex, MsgBuffer, 8>,
+) {
+    let mut mac_str = MacString::new();
+    format_mac(&profile.mac, &mut mac_str);
+
+    let msg = DeviceMessage::ClientProfile {
+        mac: &mac_str,
+        mac_randomized: filter::is_locally_administered(&profile.mac),
+        ssids: &profile.ssids,
+        dropped: profile.dropped,
+        probes: profile.probes,
+        rssi: profile.rssi,
+        first_ts: profile.first_ms,
+        ts: uptime_ms(),
+    };
+
+    let mut buf = MsgBuffer::new();
+    buf.resize_default(MAX_MSG_LEN).ok();
+    if let Ok(len) = comm::serialize_message(&msg, &mut buf) {
+        buf.truncate(len);
+        let _ = output_tx.try_send(buf);
+    }
+}
+
+/// Bluetooth Classic results. The ESP32 radio is dual-mode, but esp-radio
+/// brings the controller up in BLE-only mode, so nothing on-device produces
+/// these yet — a BR/EDR inquiry loop would feed `scanner::parse_hci_inquiry_event`
+/// output into SCAN_CHANNEL once the HCI transport allows it.
+async fn handle_bt_classic_event(
+    classic: &BtClassicEvent,
+    config: &FilterConfig,
+    sigs: &impl SignatureSource,
+    trackers: &mut Trackers,
+    output_tx: &embassy_sync::channel::Sender<'_, CriticalSectionRawMutex, MsgBuffer, 8>,
+) {
+    let input = BtClassicScanInput {
+        mac: &classic.mac,
+        name: classic.name.as_str(),
+        rssi: classic.rssi,
+        class_of_device: classic.class_of_device,
+        manufacturer_id: classic.manufacturer_id,
+    };
+
+    let mut result = filter_bt_classic_with(&input, config, sigs);
+    record_result(&result);
+    if !result.matched {
+        return;
+    }
+
+    // Rules pairing signatures across detections look back over recent ones
+    result.apply_recent_rules(&trackers.recent, uptime_ms());
+    trackers.recent.record(&result.hits, uptime_ms());
+
+    let avg = trackers
+        .rssi
+        .observe(&classic.mac, classic.rssi, uptime_ms());
+    let rssi_avg = config.rssi_smoothing.then_some(avg);
+    let approaching = trackers.rssi.approaching(&classic.mac);
+    let zone = config.zones.classify(rssi_avg.unwrap_or(classic.rssi));
+
+    // Weak indicators wait until the device has been seen often enough
+    if trackers.sightings.observe(&classic.mac, uptime_ms()) < result.min_sightings as u16 {
+        return;
+    }
+
+    BLE_MATCH_COUNT.fetch_add(1, Ordering::Relaxed);
+    let label = label_for(&classic.mac);
+
+    if let Some(first) = result.matches.first() {
+        let description = label.as_deref().unwrap_or(&first.detail);
+        set_last_match(
+            &classic.mac,
+            "bt_classic",
+            classic.rssi,
+            zone,
+            result.meta.severity,
+            description,
+        );
+    }
+
+    let quiet = is_quiet(config);
+    if !quiet {
+        // Escalate for a device that is getting closer
+        let action = rules::alert_action(&result.rules);
+        alert(if approaching {
+            action.escalated()
+        } else {
+            action
+        });
+    }
+
+    let mut mac_str = MacString::new();
+    format_mac(&classic.mac, &mut mac_str);
+
+    let (ts, ts_source) = timestamp(uptime_ms());
+    let (loc, location_stale) = current_location(config.location_stale);
+
+    let msg = DeviceMessage::BtClassicScan {
+        mac: &mac_str,
+        name: &classic.name,
+        rssi: classic.rssi,
+        rssi_avg,
+        zone,
+        approaching,
+        cod: classic.class_of_device,
+        severity: result.meta.severity,
+        category: result.meta.category,
+        device_class: result.meta.device_class,
+        confidence: result.meta.confidence,
+        matches: &result.matches,
+        rules: &result.rules,
+        truncated: result.truncated,
+        label: label.as_deref(),
+        quiet,
+        loc,
+        location_stale,
+        ts,
+        ts_source,
+    };
+
+    let mut buf = MsgBuffer::new();
+    buf.resize_default(MAX_MSG_LEN).ok();
+    if let Ok(len) = comm::serialize_message(&msg, &mut buf) {
+        buf.truncate(len);
+        let _ = output_tx.try_send(buf);
+    }
+
+    let loc = loc.filter(|_| !location_stale);
+    if let Some(loc) = loc {
+        check_following(
+            &classic.mac,
+            None,
+            &result.meta,
+            &loc,
+            config,
+            trackers,
+            output_tx,
+        );
+    }
+    let detection = Detection {
+        mac: classic.mac,
+        rssi: classic.rssi,
+        meta: result.meta,
+    };
+    check_lingering(&detection, None, loc, config, trackers, output_tx);
+}
+
 /// Serial output task — reads from output channel, logs to serial,
 /// and forwards a clone to the BLE output channel.
 #[embassy_executor::task]
@@ -630,8 +2136,8 @@ async fn output_serial_task() {
     loop {
         let msg = output_rx.receive().await;
 
-        // Forward to BLE output channel (non-blocking, drops if full or no client)
-        let _ = BLE_OUTPUT_CHANNEL.try_send(msg.clone());
+        // Fan out to BLE clients (non-blocking, slow clients lose the oldest)
+        BLE_OUTPUT_CHANNEL.publish_immediate(msg.clone());
 
         // Log to serial via esp-println
         if let Ok(s) = core::str::from_utf8(&msg) {
@@ -640,6 +2146,107 @@ async fn output_serial_task() {
     }
 }
 
+/// Serial command input task (XIAO) — polls the USB-Serial-JTAG RX FIFO and
+/// feeds bytes through the command mux.
+#[cfg(feature = "xiao")]
+#[embassy_executor::task]
+async fn serial_rx_task(
+    mut rx: esp_hal::usb_serial_jtag::UsbSerialJtagRx<'static, esp_hal::Blocking>,
+) {
+    let Some(input) = CommandInput::open(CommandSource::Serial) else {
+        log::error!("No command input available for serial");
+        return;
+    };
+    log::info!("Serial command input started");
+
+    let mut buf = [0u8; 64];
+    loop {
+        let len = rx.drain_rx_fifo(&mut buf);
+        if len == 0 {
+            Timer::after(Duration::from_millis(SERIAL_POLL_MS)).await;
+            continue;
+        }
+        feed_serial(&input, &buf[..len]);
+    }
+}
+
+/// Serial command input task (M5StickC) — reads UART0 RX and feeds bytes
+/// through the command mux.
+#[cfg(feature = "m5stickc")]
+#[embassy_executor::task]
+async fn serial_rx_task(mut rx: esp_hal::uart::UartRx<'static, esp_hal::Async>) {
+    let Some(input) = CommandInput::open(CommandSource::Serial) else {
+        log::error!("No command input available for serial");
+        return;
+    };
+    log::info!("Serial command input started");
+
+    let mut buf = [0u8; 64];
+    loop {
+        match rx.read_async(&mut buf).await {
+            Ok(len) => feed_serial(&input, &buf[..len]),
+            Err(e) => log::warn!("Serial RX error: {:?}", e),
+        }
+    }
+}
+
+/// NMEA default baud rate
+#[cfg(feature = "gps")]
+const GPS_BAUD: u32 = 9600;
+
+/// GPS input task (XIAO GPS header) — reads UART1 RX into the NMEA reader,
+/// notes when each fix arrives for the status `fix_age`, and hands fixes to
+/// [`LOCATION`] and their time to [`CLOCK`].
+#[cfg(feature = "gps")]
+#[embassy_executor::task]
+async fn gps_rx_task(mut rx: esp_hal::uart::UartRx<'static, esp_hal::Async>) {
+    log::info!("GPS input started");
+
+    let mut buf = [0u8; 64];
+    loop {
+        match rx.read_async(&mut buf).await {
+            Ok(len) => critical_section::with(|cs| {
+                let (reader, last_fix) = &mut *GPS.borrow_ref_mut(cs);
+                for &byte in &buf[..len] {
+                    if let Some(Ok(fix)) = reader.feed(byte) {
+                        *last_fix = Some(uptime_ms());
+                        if let Some(unix_ms) = fix.timestamp {
+                            let clock = CLOCK.borrow(cs);
+                            let mut disciplined = clock.get();
+                            disciplined.discipline(unix_ms, uptime_ms());
+                            clock.set(disciplined);
+                        }
+                        LOCATION.borrow_ref_mut(cs).update_fix(&fix, uptime_ms());
+                    }
+                }
+            }),
+            Err(e) => log::warn!("GPS RX error: {:?}", e),
+        }
+    }
+}
+
+/// How often the USB-Serial-JTAG RX FIFO is polled when idle
+#[cfg(feature = "xiao")]
+const SERIAL_POLL_MS: u64 = 20;
+
+/// Forward serial bytes' completed commands to `command_task`. Serial output
+/// is never filtered, so `subscribe` has nothing to apply to here.
+#[cfg(any(feature = "m5stickc", feature = "xiao"))]
+fn feed_serial(input: &CommandInput, bytes: &[u8]) {
+    for &byte in bytes {
+        match input.feed(byte) {
+            Some(Ok(HostCommand::Subscribe(_))) => log::info!("subscribe ignored on serial"),
+            Some(Ok(cmd)) => {
+                if CMD_CHANNEL.try_send(cmd).is_err() {
+                    log::warn!("Command queue full, serial command dropped");
+                }
+            }
+            Some(Err(e)) => log::warn!("Serial command rejected: {}", e),
+            None => {}
+        }
+    }
+}
+
 /// Periodic status reporting task
 #[embassy_executor::task]
 async fn status_task() {
@@ -655,41 +2262,416 @@ async fn status_task() {
             ble_clients: BLE_CLIENTS.load(Ordering::Relaxed),
             board: board::BOARD_NAME,
             version: VERSION,
+            sig_version: defaults::signature_version(),
+            region: get_filter_config().region.as_str(),
+            gps: status_gps(),
         };
 
         let mut buf = MsgBuffer::new();
         buf.resize_default(MAX_MSG_LEN).ok();
-        if let Some(len) = comm::serialize_message(&msg, &mut buf) {
+        if let Ok(len) = comm::serialize_message(&msg, &mut buf) {
             buf.truncate(len);
             let _ = OUTPUT_CHANNEL.try_send(buf);
         }
     }
 }
 
+/// Interval between periodic `stats` reports
+const STATS_INTERVAL_SECS: u64 = 300;
+
+/// Periodic filter and channel statistics reporting task
+#[embassy_executor::task]
+async fn stats_task() {
+    loop {
+        Timer::after(Duration::from_secs(STATS_INTERVAL_SECS)).await;
+        send_stats();
+        send_channel_stats();
+    }
+}
+
+/// Report the filter counters since boot.
+fn send_stats() {
+    let stats = critical_section::with(|cs| FILTER_STATS.borrow(cs).borrow().clone());
+    let msg = DeviceMessage::Stats {
+        evaluated: stats.evaluated,
+        matched: stats.matched,
+        rssi_rejected: stats.rssi_rejected,
+        untracked: stats.untracked,
+        sigs: stats.sigs(),
+        ts: uptime_ms(),
+    };
+
+    let mut buf = MsgBuffer::new();
+    buf.resize_default(MAX_MSG_LEN).ok();
+    if let Ok(len) = comm::serialize_message(&msg, &mut buf) {
+        buf.truncate(len);
+        let _ = OUTPUT_CHANNEL.try_send(buf);
+    }
+}
+
+/// Report the per-channel WiFi activity since boot.
+fn send_channel_stats() {
+    let channels = CHANNEL_STATS.snapshot();
+    let msg = DeviceMessage::ChannelStats {
+        channels: &channels,
+        ts: uptime_ms(),
+    };
+
+    let mut buf = MsgBuffer::new();
+    buf.resize_default(MAX_MSG_LEN).ok();
+    if let Ok(len) = comm::serialize_message(&msg, &mut buf) {
+        buf.truncate(len);
+        let _ = OUTPUT_CHANNEL.try_send(buf);
+    }
+}
+
+/// Send each kept match's bytes as an `evidence` message, oldest first.
+fn send_evidence() {
+    let ring = critical_section::with(|cs| EVIDENCE.borrow(cs).borrow().clone());
+    let count = ring.len() as u8;
+    for (i, evidence) in ring.iter().enumerate() {
+        let mut mac_str = MacString::new();
+        format_mac(&evidence.mac, &mut mac_str);
+        let data = evidence.hex();
+        let (ts, ts_source) = timestamp(evidence.ts);
+        let msg = DeviceMessage::Evidence {
+            radio: evidence.radio.as_str(),
+            mac: &mac_str,
+            index: i as u8 + 1,
+            count,
+            ch: evidence.channel,
+            rssi: evidence.rssi,
+            len: evidence.len,
+            data: &data,
+            ts,
+            ts_source,
+        };
+
+        let mut buf = MsgBuffer::new();
+        buf.resize_default(MAX_MSG_LEN).ok();
+        if let Ok(len) = comm::serialize_message(&msg, &mut buf) {
+            buf.truncate(len);
+            let _ = OUTPUT_CHANNEL.try_send(buf);
+        }
+    }
+}
+
+/// Health supervisor — reports subsystems that stop checking in (and their
+/// recovery), and resets the device if one stays silent past its restart
+/// threshold. Restarting a single radio path isn't possible: the BLE stack
+/// lives in `main`'s join and the sniffer callback is owned by esp-radio.
+#[embassy_executor::task]
+async fn health_task() {
+    loop {
+        Timer::after(Duration::from_secs(5)).await;
+
+        let now = uptime_ms();
+        let changes = HEALTH.poll(now);
+        if changes.is_empty() {
+            continue;
+        }
+
+        for subsystem in changes.stalled.iter() {
+            log::warn!("Subsystem stalled: {}", subsystem.as_str());
+            send_health(subsystem, "stalled", now);
+        }
+        for subsystem in changes.recovered.iter() {
+            log::info!("Subsystem recovered: {}", subsystem.as_str());
+            send_health(subsystem, "recovered", now);
+        }
+        if let Some(subsystem) = changes.needs_restart.iter().next() {
+            log::error!("Subsystem {} unresponsive, restarting", subsystem.as_str());
+            send_health(subsystem, "restarting", now);
+            // Give the output task a chance to flush the report
+            Timer::after(Duration::from_millis(500)).await;
+            esp_hal::system::software_reset();
+        }
+    }
+}
+
+fn send_health(subsystem: Subsystem, status: &'static str, now: u32) {
+    let msg = DeviceMessage::Health {
+        subsystem: subsystem.as_str(),
+        status,
+        idle_ms: HEALTH.idle_ms(subsystem, now),
+        ts: now,
+    };
+
+    let mut buf = MsgBuffer::new();
+    buf.resize_default(MAX_MSG_LEN).ok();
+    if let Ok(len) = comm::serialize_message(&msg, &mut buf) {
+        buf.truncate(len);
+        let _ = OUTPUT_CHANNEL.try_send(buf);
+    }
+}
+
+/// Replay the logged detections against each rule of a candidate rule
+/// blob and report how many each would have matched. Nothing is installed.
+fn test_rules(blob: &[u8]) {
+    let set = match rules::RuleDb::parse(blob) {
+        Ok(set) => set,
+        Err(e) => return send_rule_test(None, 0, 0, Some(e.as_str())),
+    };
+    let Some(pack) = set.pack_index() else {
+        return send_rule_test(None, 0, 0, Some(AirhoundError::Unsupported.as_str()));
+    };
+
+    let db = set.db();
+    for rule in db.rules {
+        let (matched, events) = critical_section::with(|cs| {
+            let log = EVENT_LOG.borrow(cs).borrow();
+            (
+                rules::test_rule(&db, rule, pack, log.events()),
+                log.len() as u16,
+            )
+        });
+        send_rule_test(Some(rule.id), matched, events, None);
+    }
+}
+
+fn send_rule_test(id: Option<&str>, matched: u16, events: u16, error: Option<&'static str>) {
+    let msg = DeviceMessage::RuleTest {
+        id,
+        matched,
+        events,
+        error,
+    };
+
+    let mut buf = MsgBuffer::new();
+    buf.resize_default(MAX_MSG_LEN).ok();
+    if let Ok(len) = comm::serialize_message(&msg, &mut buf) {
+        buf.truncate(len);
+        let _ = OUTPUT_CHANNEL.try_send(buf);
+    }
+}
+
+/// Report the outcome of a bundle transfer step. `Ok(Some(serial))` means
+/// the bundle was installed; `Ok(None)` means more chunks are expected.
+fn send_sig_update(result: Result<Option<u32>, sigbundle::UpdateError>, received: usize) {
+    match result {
+        Ok(None) => send_sig_update_state("receiving", received, None, None),
+        Ok(Some(serial)) => send_sig_update_state("installed", received, Some(serial), None),
+        Err(e) => {
+            log::warn!("Signature bundle rejected: {}", e.as_str());
+            send_sig_update_state("rejected", received, None, Some(e.as_str()))
+        }
+    }
+}
+
+fn send_sig_update_state(
+    state: &'static str,
+    received: usize,
+    serial: Option<u32>,
+    error: Option<&'static str>,
+) {
+    let msg = DeviceMessage::SigUpdate {
+        state,
+        received: received as u16,
+        serial,
+        error,
+    };
+
+    let mut buf = MsgBuffer::new();
+    buf.resize_default(MAX_MSG_LEN).ok();
+    if let Ok(len) = comm::serialize_message(&msg, &mut buf) {
+        buf.truncate(len);
+        let _ = OUTPUT_CHANNEL.try_send(buf);
+    }
+}
+
+/// Persist the companion's runtime signatures so they survive a reboot.
+fn save_runtime_sigs(config_store: &mut nvs::NvsStore, runtime: &SignatureOverlay) {
+    let mut buf = [0u8; store::MAX_BLOB_LEN];
+    let result = match runtime.encode_runtime(&mut buf) {
+        Ok(len) => config_store.save(ConfigKey::RuntimeSignatures, &buf[..len]),
+        Err(_) => Err(store::StoreError::TooLarge),
+    };
+    if let Err(e) = result {
+        log::warn!("Failed to persist runtime signatures: {:?}", e);
+    }
+}
+
 /// Host command processing task — drains CMD_CHANNEL, updates filter config
-/// and scanning state, responds to status requests.
+/// and scanning state, responds to status requests. Config changes are
+/// persisted to NVS so they survive a reboot. Signature bundles are staged
+/// here and take effect after the reboot that follows an install.
 #[embassy_executor::task]
-async fn command_task() {
+async fn command_task(mut config_store: nvs::NvsStore, bundle_key: Option<[u8; 32]>) {
     let cmd_rx = CMD_CHANNEL.receiver();
     let output_tx = OUTPUT_CHANNEL.sender();
+    let mut stager = sigbundle::BundleStager::<{ sigbundle::MAX_BUNDLE_LEN }>::new();
 
     loop {
         let cmd = cmd_rx.receive().await;
         let is_status_request = matches!(cmd, HostCommand::GetStatus);
+        let is_sig_version_request = matches!(cmd, HostCommand::GetSigVersion);
 
-        let mut config = get_filter_config();
+        let previous = get_filter_config();
+        let mut config = previous;
         let mut scanning = SCANNING.load(Ordering::Relaxed);
 
         let buzzer_state = comm::handle_command(&cmd, &mut config, &mut scanning);
 
+        match cmd {
+            HostCommand::Watch { mac, duration_s } => {
+                critical_section::with(|cs| {
+                    if !WATCH_LIST
+                        .borrow(cs)
+                        .borrow_mut()
+                        .add(&mac, duration_s, uptime_ms())
+                    {
+                        log::warn!("Watch list full");
+                    }
+                });
+                // Left set after unwatch: a stale bit only lets extra frames through
+                PREFILTER.add_oui([mac[0], mac[1], mac[2]]);
+            }
+            HostCommand::SetTime {
+                epoch_s,
+                utc_offset_min,
+            } => critical_section::with(|cs| {
+                let clock = CLOCK.borrow(cs);
+                let mut synced = clock.get();
+                synced.set(epoch_s, utc_offset_min, uptime_ms());
+                clock.set(synced);
+            }),
+            HostCommand::SetLocation(location) => critical_section::with(|cs| {
+                LOCATION.borrow_ref_mut(cs).update(location, uptime_ms());
+            }),
+            HostCommand::AddIrk(irk) => {
+                let irks = critical_section::with(|cs| {
+                    let mut irks = IRKS.borrow(cs).borrow_mut();
+                    if !irks.add(irk) {
+                        log::warn!("IRK table full");
+                    }
+                    irks.clone()
+                });
+                if let Err(e) = store::save_blob(&mut config_store, &irks) {
+                    log::warn!("Failed to persist IRKs: {:?}", e);
+                }
+            }
+            HostCommand::ClearIrks => {
+                let irks = critical_section::with(|cs| {
+                    let mut irks = IRKS.borrow(cs).borrow_mut();
+                    irks.clear();
+                    irks.clone()
+                });
+                if let Err(e) = store::save_blob(&mut config_store, &irks) {
+                    log::warn!("Failed to persist IRKs: {:?}", e);
+                }
+            }
+            HostCommand::Unwatch { mac } => critical_section::with(|cs| {
+                WATCH_LIST.borrow(cs).borrow_mut().remove(&mac);
+            }),
+            HostCommand::Label { mac, ref name } => {
+                let labels = critical_section::with(|cs| {
+                    let mut labels = LABELS.borrow(cs).borrow_mut();
+                    if !labels.set(&mac, name) {
+                        log::warn!("Label table full");
+                    }
+                    labels.clone()
+                });
+                if let Err(e) = store::save_blob(&mut config_store, &labels) {
+                    log::warn!("Failed to persist labels: {:?}", e);
+                }
+            }
+            HostCommand::AddSignature {
+                ref sig,
+                ref description,
+            } => {
+                let mut runtime = RUNTIME_SIGS.lock().await;
+                if !runtime.add(sig, description) {
+                    log::warn!("Runtime signature table full");
+                } else {
+                    if let sigfile::RuntimeSignature::MacOui(oui) = *sig {
+                        PREFILTER.add_oui(oui);
+                    }
+                    save_runtime_sigs(&mut config_store, &runtime);
+                }
+            }
+            HostCommand::RemoveSignature(ref sig) => {
+                // The prefilter bit stays set: it only lets extra frames through
+                let mut runtime = RUNTIME_SIGS.lock().await;
+                if runtime.remove(sig) {
+                    save_runtime_sigs(&mut config_store, &runtime);
+                }
+            }
+            HostCommand::TestRule { ref blob } => test_rules(blob),
+            HostCommand::GetStats => send_stats(),
+            HostCommand::GetChannelStats => send_channel_stats(),
+            HostCommand::GetEvidence => send_evidence(),
+            HostCommand::SigBegin { len } => {
+                let result = match bundle_key {
+                    Some(_) => stager.begin(len as usize),
+                    None => Err(sigbundle::UpdateError::NoKey),
+                };
+                send_sig_update(result.map(|()| None), stager.received());
+            }
+            HostCommand::SigChunk { offset, ref data } => {
+                let result = stager.chunk(offset as usize, data);
+                send_sig_update(result.map(|_| None), stager.received());
+            }
+            HostCommand::SigCommit => {
+                let received = stager.received();
+                let result = match bundle_key {
+                    Some(key) => stager.finish().and_then(|bundle| {
+                        let mut scratch = alloc::vec![0u8; sigbundle::MAX_BUNDLE_LEN];
+                        sigbundle::install(&mut config_store, bundle, &key, &mut scratch)
+                    }),
+                    None => Err(sigbundle::UpdateError::NoKey),
+                };
+                let installed = result.is_ok();
+                send_sig_update(result.map(Some), received);
+                if installed {
+                    log::info!("Signature bundle installed, restarting to apply");
+                    Timer::after(Duration::from_millis(500)).await;
+                    esp_hal::system::software_reset();
+                }
+            }
+            HostCommand::SigRollback => {
+                let result = match bundle_key {
+                    Some(key) => {
+                        let mut scratch = alloc::vec![0u8; sigbundle::MAX_BUNDLE_LEN];
+                        sigbundle::rollback(&mut config_store, &key, &mut scratch)
+                            .map(|bundle| bundle.serial)
+                    }
+                    None => Err(sigbundle::UpdateError::NoKey),
+                };
+                match result {
+                    Ok(serial) => {
+                        send_sig_update_state("rolled_back", 0, Some(serial), None);
+                        log::info!("Signature bundle rolled back, restarting to apply");
+                        Timer::after(Duration::from_millis(500)).await;
+                        esp_hal::system::software_reset();
+                    }
+                    Err(e) => send_sig_update_state("rejected", 0, None, Some(e.as_str())),
+                }
+            }
+            _ => {}
+        }
+
         if let Some(enabled) = buzzer_state {
             BUZZER_ENABLED.store(enabled, Ordering::Relaxed);
         }
 
         // Write back updated state
-        critical_section::with(|cs| FILTER_CONFIG.borrow(cs).set(config));
+        set_filter_config(config);
         SCANNING.store(scanning, Ordering::Relaxed);
 
+        if config.wifi_active() != previous.wifi_active() {
+            WIFI_SUSPEND_SIGNAL.signal(!config.wifi_active());
+        }
+
+        if config.effective_ble_scan() != previous.effective_ble_scan() {
+            BLE_SCAN_SIGNAL.signal(config.effective_ble_scan());
+        }
+
+        if config != previous {
+            if let Err(e) = store::save_blob(&mut config_store, &config) {
+                log::warn!("Failed to persist filter config: {:?}", e);
+            }
+        }
+
         // GetStatus: build and send a live status response
         if is_status_request {
             let uptime_secs = (Instant::now().as_millis() / 1000) as u32;
@@ -700,11 +2682,33 @@ async fn command_task() {
                 ble_clients: BLE_CLIENTS.load(Ordering::Relaxed),
                 board: board::BOARD_NAME,
                 version: VERSION,
+                sig_version: defaults::signature_version(),
+                region: config.region.as_str(),
+                gps: status_gps(),
+            };
+
+            let mut buf = MsgBuffer::new();
+            buf.resize_default(MAX_MSG_LEN).ok();
+            if let Ok(len) = comm::serialize_message(&msg, &mut buf) {
+                buf.truncate(len);
+                let _ = output_tx.try_send(buf);
+            }
+        }
+
+        if is_sig_version_request {
+            let packs: heapless::Vec<&'static str, 8> =
+                defaults::PACKS.iter().map(|pack| pack.name).collect();
+            let runtime = RUNTIME_SIGS.lock().await.len() as u16;
+            let msg = DeviceMessage::SigVersion {
+                defaults: defaults::signature_version(),
+                packs: &packs,
+                bundle: critical_section::with(|cs| BUNDLE_SERIAL.borrow(cs).get()),
+                runtime,
             };
 
             let mut buf = MsgBuffer::new();
             buf.resize_default(MAX_MSG_LEN).ok();
-            if let Some(len) = comm::serialize_message(&msg, &mut buf) {
+            if let Ok(len) = comm::serialize_message(&msg, &mut buf) {
                 buf.truncate(len);
                 let _ = output_tx.try_send(buf);
             }
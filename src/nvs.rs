@@ -0,0 +1,96 @@
+/// NVS flash backend for the library's [`ConfigStore`].
+///
+/// Uses the `nvs` data partition of the default ESP-IDF partition table
+/// (0x9000, 24 KiB) as raw flash: each [`ConfigKey`] owns one 4 KiB sector
+/// and holds a single framed record (see `store::encode_record`), except the
+/// two signature bundles, which share the last sector, and the runtime
+/// signatures, which share the IRK sector. The ESP-IDF NVS page
+/// format is not used — nothing else on the device reads it.
+use embedded_storage::{ReadStorage, Storage};
+use esp_storage::FlashStorage;
+
+use crate::store::{self, ConfigKey, ConfigStore, StoreError, RECORD_HEADER_LEN};
+
+/// Offset of the `nvs` partition in the default partition table
+const NVS_OFFSET: u32 = 0x9000;
+
+/// Size of the `nvs` partition
+const NVS_SIZE: u32 = 0x6000;
+
+/// One flash sector per configuration key
+const SECTOR_SIZE: u32 = 0x1000;
+
+pub struct NvsStore {
+    flash: FlashStorage<'static>,
+}
+
+impl NvsStore {
+    pub fn new(flash: esp_hal::peripherals::FLASH<'static>) -> Self {
+        Self {
+            flash: FlashStorage::new(flash),
+        }
+    }
+
+    /// Flash offset of the record for `key`
+    fn offset(key: ConfigKey) -> Result<u32, StoreError> {
+        let offset = match key {
+            // Half a sector each: a bundle record is at most 2 KiB
+            ConfigKey::SigBundle => 5 * SECTOR_SIZE,
+            ConfigKey::SigBundlePrev => 5 * SECTOR_SIZE + SECTOR_SIZE / 2,
+            // The partition has no sector left; both blobs fit in half of one
+            ConfigKey::RuntimeSignatures => 4 * SECTOR_SIZE + SECTOR_SIZE / 2,
+            _ => key.slot() as u32 * SECTOR_SIZE,
+        };
+        if offset + (RECORD_HEADER_LEN + key.max_len()) as u32 > NVS_SIZE {
+            return Err(StoreError::TooLarge);
+        }
+        Ok(NVS_OFFSET + offset)
+    }
+}
+
+impl ConfigStore for NvsStore {
+    // Header and payload are read and written separately, straight from the
+    // caller's buffer: a bundle record would not fit on the task stack.
+
+    fn load(&mut self, key: ConfigKey, buf: &mut [u8]) -> Result<usize, StoreError> {
+        let offset = Self::offset(key)?;
+        let mut header = [0u8; RECORD_HEADER_LEN];
+        self.flash
+            .read(offset, &mut header)
+            .map_err(|_| StoreError::Io)?;
+        let len = store::record_len(key, &header)?;
+        if len > key.max_len() {
+            return Err(StoreError::Corrupt);
+        }
+        let dest = buf.get_mut(..len).ok_or(StoreError::BufferTooSmall)?;
+        self.flash
+            .read(offset + RECORD_HEADER_LEN as u32, dest)
+            .map_err(|_| StoreError::Io)?;
+        store::check_record(&header, dest)?;
+        Ok(len)
+    }
+
+    fn save(&mut self, key: ConfigKey, data: &[u8]) -> Result<(), StoreError> {
+        if data.len() > key.max_len() {
+            return Err(StoreError::TooLarge);
+        }
+        let offset = Self::offset(key)?;
+        let header = store::record_header(key, data).ok_or(StoreError::TooLarge)?;
+        // FlashStorage::write handles the sector erase/read-modify-write. A
+        // reset between the two writes leaves a header whose checksum doesn't
+        // match the payload, which loads as Corrupt.
+        self.flash
+            .write(offset + RECORD_HEADER_LEN as u32, data)
+            .map_err(|_| StoreError::Io)?;
+        self.flash
+            .write(offset, &header)
+            .map_err(|_| StoreError::Io)
+    }
+
+    fn erase(&mut self, key: ConfigKey) -> Result<(), StoreError> {
+        // Clearing the magic byte is enough to make the record read as absent
+        self.flash
+            .write(Self::offset(key)?, &[0xFF; RECORD_HEADER_LEN])
+            .map_err(|_| StoreError::Io)
+    }
+}
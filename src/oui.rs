@@ -0,0 +1,240 @@
+/// Vendor lookup against the full IEEE OUI registry, for std hosts.
+///
+/// The compiled-in packs only know the ~140 prefixes they match on. The Linux
+/// daemon and the Kismet companion can load the IEEE registry exports
+/// (`oui.csv`, `mam.csv` and `oas.csv` from standards-oui.ieee.org) or
+/// Wireshark's `manuf` file into an [`OuiRegistry`] and name the vendor of any
+/// transmitter, matched or not. MA-M (28-bit) and MA-S (36-bit) blocks take
+/// precedence over the MA-L (24-bit) assignment they are carved from.
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+
+use crate::defaults::PACKS;
+use crate::filter::is_locally_administered;
+
+/// Assignment sizes, longest (most specific) first
+const BLOCK_BITS: [u8; 3] = [36, 28, 24];
+
+/// Vendor names by MAC prefix.
+#[derive(Debug, Clone, Default)]
+pub struct OuiRegistry {
+    /// Keyed by prefix length in bits and the prefix value
+    blocks: HashMap<(u8, u64), String>,
+}
+
+impl OuiRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of loaded assignments
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// Record `vendor` for the MACs whose top `bits` bits equal `prefix`.
+    /// Only 24, 28 and 36-bit assignments exist; other lengths are ignored.
+    pub fn insert(&mut self, prefix: u64, bits: u8, vendor: &str) {
+        if BLOCK_BITS.contains(&bits) && !vendor.is_empty() {
+            self.blocks.insert((bits, prefix), vendor.to_string());
+        }
+    }
+
+    /// Load an IEEE registry CSV export (`Registry,Assignment,Organization
+    /// Name,...`). Returns the number of assignments read.
+    pub fn load_csv(&mut self, text: &str) -> usize {
+        let mut added = 0;
+        for line in text.lines().skip(1) {
+            let mut fields = csv_fields(line);
+            let (Some(_registry), Some(assignment), Some(vendor)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                continue;
+            };
+            let bits = assignment.len() as u8 * 4;
+            if let Ok(prefix) = u64::from_str_radix(&assignment, 16) {
+                if BLOCK_BITS.contains(&bits) {
+                    self.insert(prefix, bits, vendor.trim());
+                    added += 1;
+                }
+            }
+        }
+        added
+    }
+
+    /// Load a Wireshark `manuf` file: `00:1B:C5<TAB>Short<TAB>Long name`,
+    /// with `/28` or `/36` after the address for smaller blocks. The long
+    /// name is used when present. Returns the number of assignments read.
+    pub fn load_manuf(&mut self, text: &str) -> usize {
+        let mut added = 0;
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or("");
+            let mut fields = line.split('\t').map(str::trim).filter(|f| !f.is_empty());
+            let (Some(address), Some(short)) = (fields.next(), fields.next()) else {
+                continue;
+            };
+            let vendor = fields.next().unwrap_or(short);
+            let (address, bits) = match address.split_once('/') {
+                Some((address, bits)) => (address, bits.parse().unwrap_or(0)),
+                None => (address, 24),
+            };
+            let digits: String = address.chars().filter(char::is_ascii_hexdigit).collect();
+            if !BLOCK_BITS.contains(&bits) || digits.len() * 4 < bits as usize {
+                continue;
+            }
+            if let Ok(prefix) = u64::from_str_radix(&digits[..bits as usize / 4], 16) {
+                self.insert(prefix, bits, vendor);
+                added += 1;
+            }
+        }
+        added
+    }
+
+    /// Load a registry file in either format, told apart by the IEEE CSV
+    /// header. Returns the number of assignments read.
+    pub fn load_path(&mut self, path: impl AsRef<Path>) -> io::Result<usize> {
+        let text = std::fs::read_to_string(path)?;
+        Ok(if text.starts_with("Registry,") {
+            self.load_csv(&text)
+        } else {
+            self.load_manuf(&text)
+        })
+    }
+
+    /// Vendor of `mac`: the most specific loaded assignment, else the
+    /// compiled-in signature for its OUI. Randomized (locally administered)
+    /// addresses have no vendor.
+    pub fn vendor(&self, mac: &[u8; 6]) -> Option<&str> {
+        if is_locally_administered(mac) {
+            return None;
+        }
+        let value = u64::from_be_bytes([0, 0, mac[0], mac[1], mac[2], mac[3], mac[4], mac[5]]);
+        BLOCK_BITS
+            .iter()
+            .find_map(|&bits| self.blocks.get(&(bits, value >> (48 - bits))))
+            .map(String::as_str)
+            .or_else(|| {
+                PACKS
+                    .iter()
+                    .flat_map(|pack| pack.mac_prefixes)
+                    .find(|(prefix, ..)| *prefix == mac[..3])
+                    .map(|&(_, vendor, _)| vendor)
+            })
+    }
+}
+
+/// Fields of one CSV line, unquoting `"..."` fields and `""` escapes
+fn csv_fields(line: &str) -> impl Iterator<Item = String> + '_ {
+    let mut chars = line.chars().peekable();
+    let mut done = line.is_empty();
+    core::iter::from_fn(move || {
+        if done {
+            return None;
+        }
+        let mut field = String::new();
+        let mut quoted = false;
+        loop {
+            match chars.next() {
+                None => {
+                    done = true;
+                    break;
+                }
+                Some('"') if quoted && chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                Some('"') if quoted || field.is_empty() => quoted = !quoted,
+                Some(',') if !quoted => break,
+                Some(c) => field.push(c),
+            }
+        }
+        Some(field)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CSV: &str = "\
+Registry,Assignment,Organization Name,Organization Address
+MA-L,00000C,\"Cisco Systems, Inc\",170 WEST TASMAN DRIVE San Jose CA US 95134
+MA-L,001BC5,IEEE Registration Authority,445 Hoes Lane Piscataway NJ US 08854
+MA-S,001BC5001,\"Acme \"\"Sensors\"\" Ltd\",Somewhere
+MA-M,F41A791,Example Cameras,Elsewhere
+";
+
+    // ── Loading ─────────────────────────────────────────────────────
+
+    #[test]
+    fn loads_ieee_csv_with_quoted_names() {
+        let mut registry = OuiRegistry::new();
+        assert_eq!(registry.load_csv(CSV), 4);
+        assert_eq!(
+            registry.vendor(&[0x00, 0x00, 0x0C, 0x12, 0x34, 0x56]),
+            Some("Cisco Systems, Inc")
+        );
+        assert_eq!(
+            registry.vendor(&[0xF4, 0x1A, 0x79, 0x1F, 0x00, 0x00]),
+            Some("Example Cameras")
+        );
+        assert_eq!(registry.vendor(&[0xF4, 0x1A, 0x79, 0x2F, 0x00, 0x00]), None);
+    }
+
+    #[test]
+    fn loads_wireshark_manuf() {
+        let manuf = "\
+# Wireshark manuf
+00:00:0C\tCisco\tCisco Systems, Inc
+00:00:0D\tFibronic
+00:1B:C5:00:10:00/36\tAcme\tAcme Sensors Ltd  # comment
+zz:zz:zz\tBroken
+";
+        let mut registry = OuiRegistry::new();
+        assert_eq!(registry.load_manuf(manuf), 3);
+        assert_eq!(
+            registry.vendor(&[0x00, 0x00, 0x0C, 0, 0, 1]),
+            Some("Cisco Systems, Inc")
+        );
+        assert_eq!(
+            registry.vendor(&[0x00, 0x00, 0x0D, 0, 0, 1]),
+            Some("Fibronic")
+        );
+        assert_eq!(
+            registry.vendor(&[0x00, 0x1B, 0xC5, 0x00, 0x10, 0xAB]),
+            Some("Acme Sensors Ltd")
+        );
+    }
+
+    // ── Lookup ──────────────────────────────────────────────────────
+
+    #[test]
+    fn smaller_blocks_take_precedence() {
+        let mut registry = OuiRegistry::new();
+        registry.load_csv(CSV);
+        assert_eq!(
+            registry.vendor(&[0x00, 0x1B, 0xC5, 0x00, 0x1F, 0xFF]),
+            Some("Acme \"Sensors\" Ltd")
+        );
+        assert_eq!(
+            registry.vendor(&[0x00, 0x1B, 0xC5, 0x00, 0x20, 0x00]),
+            Some("IEEE Registration Authority")
+        );
+    }
+
+    #[test]
+    fn falls_back_to_packs_and_skips_randomized() {
+        let registry = OuiRegistry::new();
+        assert_eq!(
+            registry.vendor(&[0xB4, 0x1E, 0x52, 0x01, 0x02, 0x03]),
+            Some("Flock Safety")
+        );
+        assert_eq!(registry.vendor(&[0x10, 0x34, 0x56, 0x01, 0x02, 0x03]), None);
+        assert_eq!(registry.vendor(&[0xDE, 0xAD, 0xBE, 0xEF, 0x00, 0x01]), None);
+    }
+}
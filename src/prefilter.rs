@@ -0,0 +1,199 @@
+/// Cheap pre-check for the WiFi sniffer callback.
+///
+/// On a busy channel the sniffer sees far more frames than `filter_task` can
+/// drain, and every frame that reaches `SCAN_CHANNEL` competes for its 16
+/// slots with the ones that matter. [`Prefilter::allows`] runs in the ISR
+/// before any parsing and drops frames that cannot produce a report:
+/// beacons, probes and FTM ranging frames always pass (SSID signatures,
+/// downgrade detection, ranging from any device), everything else only if the OUI of its transmitter address — or of the
+/// source a data frame was relayed for — hits a one-hash Bloom filter of
+/// known prefixes. The first byte alone would not do: vendor OUIs cover most
+/// universally administered first bytes. False positives still go through
+/// the full filter, but a frame the filter would match is never dropped.
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use crate::defaults;
+use crate::scanner::{self, ActionCategory};
+
+/// Management subtypes that always pass: probe request (4), probe
+/// response (5), and beacon (8). Bit `subtype` of the mask.
+const ALWAYS_PASS_MGMT: u16 = (1 << 4) | (1 << 5) | (1 << 8);
+
+/// Bloom filter size in bits, a power of two
+const BLOOM_BITS: usize = 1024;
+
+/// Bloom filter bit for a three-byte OUI (multiplicative hash, top bits)
+fn bloom_bit(oui: [u8; 3]) -> usize {
+    let key = u32::from_be_bytes([0, oui[0], oui[1], oui[2]]);
+    (key.wrapping_mul(0x9E37_79B1) >> (32 - BLOOM_BITS.trailing_zeros())) as usize
+}
+
+/// Frame filter shared between the sniffer ISR and the tasks configuring
+/// it. All state is atomic, so `allows` never blocks.
+pub struct Prefilter {
+    /// Bloom filter over admitted OUIs
+    ouis: [AtomicU32; BLOOM_BITS / 32],
+    /// Let every frame with a transmitter address through, e.g. while the
+    /// traffic monitor needs to see all data frames
+    pass_all: AtomicBool,
+}
+
+impl Prefilter {
+    /// An empty prefilter: only beacons and probes pass.
+    pub const fn new() -> Self {
+        Self {
+            ouis: [const { AtomicU32::new(0) }; BLOOM_BITS / 32],
+            pass_all: AtomicBool::new(false),
+        }
+    }
+
+    /// Admit the MAC prefixes of every enabled signature pack.
+    pub fn add_defaults(&self) {
+        for &(prefix, ..) in defaults::mac_prefixes() {
+            self.add_oui(prefix);
+        }
+    }
+
+    /// Admit transmitters whose address starts with `oui`.
+    pub fn add_oui(&self, oui: [u8; 3]) {
+        let bit = bloom_bit(oui);
+        self.ouis[bit / 32].fetch_or(1 << (bit % 32), Ordering::Relaxed);
+    }
+
+    pub fn set_pass_all(&self, pass_all: bool) {
+        self.pass_all.store(pass_all, Ordering::Relaxed);
+    }
+
+    /// Whether a raw 802.11 frame is worth parsing.
+    pub fn allows(&self, frame: &[u8]) -> bool {
+        let Some(&control) = frame.first() else {
+            return false;
+        };
+        let kind = (control >> 2) & 0x3;
+        let subtype = control >> 4;
+        if kind == 0 && ALWAYS_PASS_MGMT & (1 << subtype) != 0 {
+            return true;
+        }
+        if scanner::action_category(frame) == Some(ActionCategory::FineTiming) {
+            return true;
+        }
+        // Transmitter address (Address 2) at offset 10
+        let Some(transmitter) = frame.get(10..16) else {
+            return false;
+        };
+        if self.pass_all.load(Ordering::Relaxed) || self.admits(transmitter) {
+            return true;
+        }
+        // A data frame relayed by an AP or WDS bridge is reported under the
+        // device it came from
+        kind == 2
+            && scanner::data_addresses(frame)
+                .is_some_and(|addresses| self.admits(&addresses.source))
+    }
+
+    /// Whether the OUI of `mac` hits the Bloom filter
+    fn admits(&self, mac: &[u8]) -> bool {
+        let bit = bloom_bit([mac[0], mac[1], mac[2]]);
+        self.ouis[bit / 32].load(Ordering::Relaxed) & (1 << (bit % 32)) != 0
+    }
+}
+
+impl Default for Prefilter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 24-byte header with the given frame control byte and transmitter
+    fn frame(control: u8, transmitter: [u8; 6]) -> [u8; 24] {
+        let mut frame = [0u8; 24];
+        frame[0] = control;
+        frame[10..16].copy_from_slice(&transmitter);
+        frame
+    }
+
+    const DATA: u8 = 0x08;
+    const BEACON: u8 = 0x80;
+    const PROBE_REQ: u8 = 0x40;
+    const DEAUTH: u8 = 0xC0;
+    const ACTION: u8 = 0xD0;
+
+    #[test]
+    fn beacons_and_probes_always_pass() {
+        let filter = Prefilter::new();
+        let random = [0x7A, 1, 2, 3, 4, 5];
+        assert!(filter.allows(&frame(BEACON, random)));
+        assert!(filter.allows(&frame(PROBE_REQ, random)));
+        assert!(!filter.allows(&frame(DATA, random)));
+        assert!(!filter.allows(&frame(DEAUTH, random)));
+    }
+
+    #[test]
+    fn known_prefixes_pass() {
+        let filter = Prefilter::new();
+        filter.add_defaults();
+        for (oui, ..) in defaults::mac_prefixes() {
+            let known = [oui[0], oui[1], oui[2], 1, 2, 3];
+            assert!(filter.allows(&frame(DATA, known)));
+            assert!(filter.allows(&frame(DEAUTH, known)));
+        }
+        assert!(!filter.allows(&frame(DATA, [0x7A, 1, 2, 3, 4, 5])));
+        assert!(!filter.allows(&frame(DATA, [0x00, 0x12, 0x34, 0, 0, 0])));
+    }
+
+    #[test]
+    fn relayed_data_frames_pass_on_their_source() {
+        let filter = Prefilter::new();
+        let (oui, ..) = defaults::mac_prefixes().next().unwrap();
+        let known = [oui[0], oui[1], oui[2], 1, 2, 3];
+        let ap = [0x02, 0xAA, 0xBB, 0xCC, 0xDD, 0xEE];
+        filter.add_defaults();
+
+        let mut relayed = frame(DATA, ap);
+        relayed[1] = 0x02; // FromDS
+        relayed[16..22].copy_from_slice(&known);
+        assert!(filter.allows(&relayed));
+        // Address 3 is only the source of FromDS frames
+        relayed[1] = 0x01;
+        assert!(!filter.allows(&relayed));
+    }
+
+    #[test]
+    fn ranging_frames_always_pass() {
+        let filter = Prefilter::new();
+        let mut action = [0u8; 26];
+        action[..24].copy_from_slice(&frame(ACTION, [0x7A, 1, 2, 3, 4, 5]));
+        action[24] = 4; // Public
+        action[25] = 32; // FTM request
+        assert!(filter.allows(&action));
+        action[24] = 3; // Block Ack
+        assert!(!filter.allows(&action));
+    }
+
+    #[test]
+    fn added_prefix_and_pass_all() {
+        let filter = Prefilter::new();
+        let watched = [0xFE, 0xED, 0, 0, 0, 1];
+        assert!(!filter.allows(&frame(DATA, watched)));
+        filter.add_oui([watched[0], watched[1], watched[2]]);
+        assert!(filter.allows(&frame(DATA, watched)));
+
+        let other = [0x12, 0, 0, 0, 0, 0];
+        assert!(!filter.allows(&frame(DATA, other)));
+        filter.set_pass_all(true);
+        assert!(filter.allows(&frame(DATA, other)));
+    }
+
+    #[test]
+    fn short_frames_are_dropped() {
+        let filter = Prefilter::new();
+        filter.set_pass_all(true);
+        assert!(!filter.allows(&[]));
+        // ACK: no transmitter address
+        assert!(!filter.allows(&[0xD4, 0, 0, 0, 0, 1, 2, 3, 4, 5]));
+    }
+}
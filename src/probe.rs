@@ -0,0 +1,196 @@
+/// Probe-request client profiling.
+///
+/// A client probing for a network by name (a directed probe) gives away the
+/// SSIDs it has joined before. Surveillance vehicles and covert devices tend
+/// to ask for distinctive internal networks, which the beacon-centric filter
+/// never sees. [`ProbeProfiler`] collects the directed SSIDs each client MAC
+/// probes for and reports its profile when it learns a new one, at most once
+/// per [`PROFILE_INTERVAL_MS`], as a `client_profile` message.
+use heapless::{String, Vec};
+
+use crate::hidden;
+
+/// A client's profile is reported at most this often
+pub const PROFILE_INTERVAL_MS: u32 = 60 * 1000;
+
+/// A client not heard probing for this long is forgotten
+pub const PROFILE_TTL_MS: u32 = 10 * 60 * 1000;
+
+/// Directed SSIDs kept per client; later ones are counted but not kept
+pub const MAX_PROFILE_SSIDS: usize = 4;
+
+/// What one client has probed for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientProfile {
+    pub mac: [u8; 6],
+    /// Directed SSIDs, in the order first asked for
+    pub ssids: Vec<String<33>, MAX_PROFILE_SSIDS>,
+    /// Directed SSIDs seen beyond [`MAX_PROFILE_SSIDS`]
+    pub dropped: u8,
+    /// Probe requests seen, wildcard ones included
+    pub probes: u16,
+    /// RSSI of the latest probe
+    pub rssi: i8,
+    /// First probe heard, in milliseconds since boot
+    pub first_ms: u32,
+    last_ms: u32,
+    reported_ms: Option<u32>,
+    /// Learned an SSID since the last report
+    changed: bool,
+}
+
+/// Probe profiles of up to `N` clients. Timestamps are milliseconds since
+/// boot (`u32`, wrapping).
+pub struct ProbeProfiler<const N: usize> {
+    clients: Vec<ClientProfile, N>,
+}
+
+impl<const N: usize> ProbeProfiler<N> {
+    pub const fn new() -> Self {
+        Self {
+            clients: Vec::new(),
+        }
+    }
+
+    /// Record a probe request from `mac` for `ssid` (empty or NUL for a
+    /// wildcard probe). Returns the client's profile when it is due to be
+    /// reported: it has a directed SSID not yet reported, and was last
+    /// reported at least [`PROFILE_INTERVAL_MS`] ago.
+    pub fn observe(
+        &mut self,
+        mac: &[u8; 6],
+        ssid: &str,
+        rssi: i8,
+        now_ms: u32,
+    ) -> Option<&ClientProfile> {
+        self.clients
+            .retain(|c| now_ms.wrapping_sub(c.last_ms) < PROFILE_TTL_MS);
+
+        let index = match self.clients.iter().position(|c| c.mac == *mac) {
+            Some(index) => index,
+            None => self.insert(ClientProfile {
+                mac: *mac,
+                ssids: Vec::new(),
+                dropped: 0,
+                probes: 0,
+                rssi,
+                first_ms: now_ms,
+                last_ms: now_ms,
+                reported_ms: None,
+                changed: false,
+            })?,
+        };
+
+        let client = &mut self.clients[index];
+        client.probes = client.probes.saturating_add(1);
+        client.rssi = rssi;
+        client.last_ms = now_ms;
+        if !hidden::is_hidden(ssid) && !client.ssids.iter().any(|s| s == ssid) {
+            let mut name = String::new();
+            let _ = name.push_str(ssid);
+            if client.ssids.push(name).is_ok() {
+                client.changed = true;
+            } else {
+                client.dropped = client.dropped.saturating_add(1);
+            }
+        }
+
+        let due = client
+            .reported_ms
+            .is_none_or(|at| now_ms.wrapping_sub(at) >= PROFILE_INTERVAL_MS);
+        if !client.changed || !due {
+            return None;
+        }
+        client.changed = false;
+        client.reported_ms = Some(now_ms);
+        Some(client)
+    }
+
+    /// Add a client, replacing the one heard from longest ago when full.
+    /// Returns its index.
+    fn insert(&mut self, client: ClientProfile) -> Option<usize> {
+        match self.clients.push(client) {
+            Ok(()) => Some(self.clients.len() - 1),
+            Err(client) => {
+                let now_ms = client.last_ms;
+                let (index, stalest) = self
+                    .clients
+                    .iter_mut()
+                    .enumerate()
+                    .max_by_key(|(_, c)| now_ms.wrapping_sub(c.last_ms))?;
+                *stalest = client;
+                Some(index)
+            }
+        }
+    }
+}
+
+impl<const N: usize> Default for ProbeProfiler<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PHONE: [u8; 6] = [0x5A, 0x01, 0x02, 0x03, 0x04, 0x05];
+    const VAN: [u8; 6] = [0x00, 0x11, 0x22, 0x33, 0x44, 0x55];
+
+    #[test]
+    fn wildcard_probes_are_not_reported() {
+        let mut profiles = ProbeProfiler::<4>::new();
+        assert_eq!(profiles.observe(&PHONE, "", -60, 0), None);
+        assert_eq!(profiles.observe(&PHONE, "\0\0", -60, 100), None);
+    }
+
+    #[test]
+    fn new_ssids_are_reported_once_per_interval() {
+        let mut profiles = ProbeProfiler::<4>::new();
+        let first = profiles.observe(&VAN, "PD-MOBILE-07", -70, 0).unwrap();
+        assert_eq!(first.ssids.len(), 1);
+        assert_eq!(first.probes, 1);
+
+        // The same SSID again, then a new one before the interval is up
+        assert_eq!(profiles.observe(&VAN, "PD-MOBILE-07", -68, 1_000), None);
+        assert_eq!(profiles.observe(&VAN, "LPR-UPLINK", -66, 2_000), None);
+
+        let second = profiles
+            .observe(&VAN, "", -65, PROFILE_INTERVAL_MS)
+            .unwrap();
+        assert_eq!(second.ssids.as_slice(), ["PD-MOBILE-07", "LPR-UPLINK"]);
+        assert_eq!(second.probes, 4);
+        assert_eq!(second.rssi, -65);
+        assert_eq!(second.first_ms, 0);
+
+        // Nothing new to report
+        assert_eq!(
+            profiles.observe(&VAN, "LPR-UPLINK", -65, 3 * PROFILE_INTERVAL_MS),
+            None
+        );
+    }
+
+    #[test]
+    fn extra_ssids_are_counted() {
+        let mut profiles = ProbeProfiler::<4>::new();
+        for (i, ssid) in ["a", "b", "c", "d", "e", "f"].iter().enumerate() {
+            profiles.observe(&PHONE, ssid, -60, i as u32 * PROFILE_INTERVAL_MS);
+        }
+        // Nothing kept, so nothing to report
+        assert_eq!(
+            profiles.observe(&PHONE, "g", -60, 6 * PROFILE_INTERVAL_MS),
+            None
+        );
+        assert_eq!(profiles.clients[0].ssids.len(), MAX_PROFILE_SSIDS);
+        assert_eq!(profiles.clients[0].dropped, 3);
+    }
+
+    #[test]
+    fn clients_expire() {
+        let mut profiles = ProbeProfiler::<4>::new();
+        profiles.observe(&VAN, "PD-MOBILE-07", -70, 0);
+        let again = profiles.observe(&VAN, "PD-MOBILE-07", -70, PROFILE_TTL_MS);
+        assert_eq!(again.map(|p| p.first_ms), Some(PROFILE_TTL_MS));
+    }
+}
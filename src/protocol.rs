@@ -5,62 +5,318 @@
 use heapless::{String, Vec};
 use serde::{Deserialize, Serialize};
 
+use crate::channel::{ChannelMask, Region};
+use crate::comm::DeviceSettings;
+use crate::defaults::{Category, Confidence, DeviceClass, Severity, SignatureMeta};
+use crate::filter::{Categories, MAX_MATCHES};
+use crate::following::FollowingPolicy;
+use crate::gps::GpsStatus;
+use crate::irk::Irk;
+use crate::lingering::LingeringPolicy;
+use crate::location::{Location, StalePolicy};
+use crate::remote_id::RemoteId;
+use crate::route::Subscription;
+use crate::rssi::{Proximity, ProximityZones};
+use crate::rules::RuleMatch;
+use crate::scanner::{BleScanConfig, FindMy, NetworkKind, P2pRole};
+use crate::schedule::{QuietHours, TsSource};
+use crate::sigbundle::MAX_CHUNK_LEN;
+use crate::sigfile::RuntimeSignature;
+
 /// Maximum length for MAC address strings ("AA:BB:CC:DD:EE:FF")
 pub type MacString = String<18>;
 
-/// Maximum length for SSID / device name strings
-pub type NameString = String<33>;
+/// Default capacity of [`NameString`] (32-byte SSID plus slack)
+pub const NAME_LEN: usize = 33;
+
+/// Default capacity of [`MatchDetail`]
+pub const MATCH_DETAIL_LEN: usize = 32;
+
+/// SSID / device name string. Firmware uses the default capacity; std
+/// consumers can pick a larger `N`.
+pub type NameString<const N: usize = NAME_LEN> = String<N>;
 
 /// Maximum length for UUID strings
 pub type UuidString = String<37>;
 
-/// Maximum length for filter match detail strings
-pub type MatchDetail = String<32>;
+/// Filter match detail string, capacity `N` (default [`MATCH_DETAIL_LEN`]).
+pub type MatchDetail<const N: usize = MATCH_DETAIL_LEN> = String<N>;
+
+/// Copy `text` into a string of capacity `N`, cutting at the last character
+/// boundary that fits. Returns the string and whether anything was cut.
+pub fn truncate_str<const N: usize>(text: &str) -> (String<N>, bool) {
+    let mut end = text.len().min(N);
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    let mut out = String::new();
+    let _ = out.push_str(&text[..end]);
+    (out, end < text.len())
+}
 
 /// A single filter match reason
 #[derive(Debug, Clone, Serialize)]
-pub struct MatchReason {
+pub struct MatchReason<const N: usize = MATCH_DETAIL_LEN> {
     /// Filter type that matched: "mac_oui", "ssid_pattern", "ssid_keyword",
-    /// "ble_name", "ble_uuid", "ble_mfr"
+    /// "ble_name", "ble_uuid", "ble_mfr", "bt_name", "bt_cod"
     #[serde(rename = "type")]
     pub filter_type: &'static str,
     /// Human-readable detail about what matched
-    pub detail: MatchDetail,
+    pub detail: MatchDetail<N>,
+    /// Rating of the matched signature. Not serialized per match — messages
+    /// carry the strongest one at the top level to stay within
+    /// [`MAX_MSG_LEN`].
+    #[serde(skip)]
+    pub meta: SignatureMeta,
+}
+
+impl<const N: usize> MatchReason<N> {
+    /// Build a reason, truncating `detail` to the capacity if needed.
+    pub fn new(filter_type: &'static str, detail: &str, meta: SignatureMeta) -> Self {
+        let (detail, truncated) = truncate_str(detail);
+        if truncated {
+            log::debug!("Match detail truncated to {} bytes", N);
+        }
+        Self {
+            filter_type,
+            detail,
+            meta,
+        }
+    }
 }
 
-/// Messages sent from the device to the companion app
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+/// Messages sent from the device to the companion app, as the firmware
+/// builds them: default name and match detail capacities
+pub type DeviceMessage<'a> = DeviceMessageSized<'a, NAME_LEN, MATCH_DETAIL_LEN>;
+
+/// Messages sent from the device to the companion app, with SSIDs and device
+/// names of up to `N` bytes and match details of up to `D`. Std consumers
+/// pair this with the `*_sized` filter functions to report full detail text;
+/// the firmware uses [`DeviceMessage`].
 #[derive(Debug, Serialize)]
 #[serde(tag = "type")]
-pub enum DeviceMessage<'a> {
+pub enum DeviceMessageSized<'a, const N: usize, const D: usize> {
     /// WiFi scan result
     #[serde(rename = "wifi")]
     WiFiScan {
         mac: &'a MacString,
-        ssid: &'a NameString,
+        /// Randomized (locally administered or BLE random) address, so the
+        /// OUI names no vendor
+        #[serde(skip_serializing_if = "is_false")]
+        mac_randomized: bool,
+        /// Access point or WDS bridge that relayed the frame from `mac`; the
+        /// RSSI is the relay's
+        #[serde(skip_serializing_if = "Option::is_none")]
+        via: Option<&'a MacString>,
+        ssid: &'a NameString<N>,
         rssi: i8,
+        /// Moving average of this device's RSSI, when smoothing is enabled
+        #[serde(skip_serializing_if = "Option::is_none")]
+        rssi_avg: Option<i8>,
+        /// Proximity zone of the smoothed RSSI, or the reading without
+        /// smoothing
+        zone: Proximity,
+        /// Signal has risen steadily over the last few seconds
+        #[serde(skip_serializing_if = "is_false")]
+        approaching: bool,
         ch: u8,
-        /// Frame type: "beacon", "probe_req", "probe_resp", "data", "other"
+        /// Frame type: "beacon", "probe_req", "probe_resp", "action", "data",
+        /// "other"
         frame: &'static str,
+        /// Category of an action frame: "block_ack", "radio_measurement",
+        /// "ftm" or "other"
+        #[serde(skip_serializing_if = "Option::is_none")]
+        action: Option<&'static str>,
+        /// Advertised security of a beacon or probe response: "open", "wep",
+        /// "wpa", "wpa2" or "wpa3"
+        #[serde(skip_serializing_if = "Option::is_none")]
+        security: Option<&'static str>,
+        /// Strongest matched signature's rating
+        severity: Severity,
+        category: Category,
+        device_class: DeviceClass,
+        confidence: Confidence,
         /// Why this result matched the filter
         #[serde(rename = "match")]
-        matches: &'a Vec<MatchReason, 4>,
-        /// Uptime in milliseconds when captured
-        ts: u32,
+        matches: &'a Vec<MatchReason<D>, MAX_MATCHES>,
+        /// Detection rules the matched signatures satisfy
+        #[serde(skip_serializing_if = "<[_]>::is_empty")]
+        rules: &'a [RuleMatch],
+        /// Matches or rules were dropped for lack of room
+        #[serde(skip_serializing_if = "is_false")]
+        truncated: bool,
+        /// User-assigned label for this MAC, if any
+        #[serde(skip_serializing_if = "Option::is_none")]
+        label: Option<&'a str>,
+        /// Synthetic ID shared by the MACs a randomized device rotated
+        /// through (see `identity::IdentityLinker`)
+        #[serde(skip_serializing_if = "Option::is_none")]
+        device_id: Option<u16>,
+        /// Decoded drone Remote ID broadcast
+        #[serde(skip_serializing_if = "Option::is_none")]
+        remote_id: Option<&'a RemoteId>,
+        /// WiFi Direct role, if the frame carried a P2P element
+        #[serde(skip_serializing_if = "Option::is_none")]
+        p2p: Option<P2pRole>,
+        /// 802.11s mesh or WiFi Direct group the frame announces
+        #[serde(skip_serializing_if = "Option::is_none")]
+        network: Option<NetworkKind>,
+        /// Detected during quiet hours — log without notifying
+        #[serde(skip_serializing_if = "is_false")]
+        quiet: bool,
+        /// Freshest GPS or companion position, once either reported one
+        #[serde(skip_serializing_if = "Option::is_none")]
+        loc: Option<Location>,
+        /// `loc` is older than the configured age — the detection may be
+        /// far from it
+        #[serde(skip_serializing_if = "is_false")]
+        location_stale: bool,
+        /// When captured: uptime in milliseconds, or Unix milliseconds per
+        /// `ts_source`
+        ts: u64,
+        #[serde(skip_serializing_if = "TsSource::is_uptime")]
+        ts_source: TsSource,
     },
     /// BLE scan result
     #[serde(rename = "ble")]
     BleScan {
         mac: &'a MacString,
-        name: &'a NameString,
+        /// Randomized (locally administered or BLE random) address, so the
+        /// OUI names no vendor
+        #[serde(skip_serializing_if = "is_false")]
+        mac_randomized: bool,
+        name: &'a NameString<N>,
         rssi: i8,
+        /// Moving average of this device's RSSI, when smoothing is enabled
+        #[serde(skip_serializing_if = "Option::is_none")]
+        rssi_avg: Option<i8>,
+        /// Proximity zone of the smoothed RSSI, or the reading without
+        /// smoothing
+        zone: Proximity,
+        /// Signal has risen steadily over the last few seconds
+        #[serde(skip_serializing_if = "is_false")]
+        approaching: bool,
+        /// Rough distance in meters, when the device advertises its TX power
+        #[serde(skip_serializing_if = "Option::is_none")]
+        dist_m: Option<f32>,
         /// Primary service UUID if detected
         #[serde(skip_serializing_if = "Option::is_none")]
         uuid: Option<&'a UuidString>,
         /// Manufacturer company ID
         mfr: u16,
+        /// Secondary PHY of an extended advertisement: "1m", "2m" or "coded"
+        #[serde(skip_serializing_if = "Option::is_none")]
+        phy: Option<&'static str>,
+        /// Strongest matched signature's rating
+        severity: Severity,
+        category: Category,
+        device_class: DeviceClass,
+        confidence: Confidence,
+        /// Why this result matched the filter
+        #[serde(rename = "match")]
+        matches: &'a Vec<MatchReason<D>, MAX_MATCHES>,
+        /// Detection rules the matched signatures satisfy
+        #[serde(skip_serializing_if = "<[_]>::is_empty")]
+        rules: &'a [RuleMatch],
+        /// Matches or rules were dropped for lack of room
+        #[serde(skip_serializing_if = "is_false")]
+        truncated: bool,
+        /// User-assigned label for this MAC, if any
+        #[serde(skip_serializing_if = "Option::is_none")]
+        label: Option<&'a str>,
+        /// Synthetic ID shared by the MACs a randomized device rotated
+        /// through (see `identity::IdentityLinker`)
+        #[serde(skip_serializing_if = "Option::is_none")]
+        device_id: Option<u16>,
+        /// Decoded drone Remote ID broadcast
+        #[serde(skip_serializing_if = "Option::is_none")]
+        remote_id: Option<&'a RemoteId>,
+        /// Find My accessory state and battery level
+        #[serde(skip_serializing_if = "Option::is_none")]
+        findmy: Option<FindMy>,
+        /// Address resolved against a provisioned IRK — the user's own device
+        #[serde(skip_serializing_if = "is_false")]
+        mine: bool,
+        /// Detected during quiet hours — log without notifying
+        #[serde(skip_serializing_if = "is_false")]
+        quiet: bool,
+        /// Freshest GPS or companion position, once either reported one
+        #[serde(skip_serializing_if = "Option::is_none")]
+        loc: Option<Location>,
+        /// `loc` is older than the configured age — the detection may be
+        /// far from it
+        #[serde(skip_serializing_if = "is_false")]
+        location_stale: bool,
+        /// When captured: uptime in milliseconds, or Unix milliseconds per
+        /// `ts_source`
+        ts: u64,
+        #[serde(skip_serializing_if = "TsSource::is_uptime")]
+        ts_source: TsSource,
+    },
+    /// Bluetooth Classic inquiry result
+    #[serde(rename = "bt_classic")]
+    BtClassicScan {
+        mac: &'a MacString,
+        name: &'a NameString<N>,
+        rssi: i8,
+        /// Moving average of this device's RSSI, when smoothing is enabled
+        #[serde(skip_serializing_if = "Option::is_none")]
+        rssi_avg: Option<i8>,
+        /// Proximity zone of the smoothed RSSI, or the reading without
+        /// smoothing
+        zone: Proximity,
+        /// Signal has risen steadily over the last few seconds
+        #[serde(skip_serializing_if = "is_false")]
+        approaching: bool,
+        /// 24-bit Class of Device
+        cod: u32,
+        /// Strongest matched signature's rating
+        severity: Severity,
+        category: Category,
+        device_class: DeviceClass,
+        confidence: Confidence,
         /// Why this result matched the filter
         #[serde(rename = "match")]
-        matches: &'a Vec<MatchReason, 4>,
+        matches: &'a Vec<MatchReason<D>, MAX_MATCHES>,
+        /// Detection rules the matched signatures satisfy
+        #[serde(skip_serializing_if = "<[_]>::is_empty")]
+        rules: &'a [RuleMatch],
+        /// Matches or rules were dropped for lack of room
+        #[serde(skip_serializing_if = "is_false")]
+        truncated: bool,
+        /// User-assigned label for this MAC, if any
+        #[serde(skip_serializing_if = "Option::is_none")]
+        label: Option<&'a str>,
+        /// Detected during quiet hours — log without notifying
+        #[serde(skip_serializing_if = "is_false")]
+        quiet: bool,
+        /// Freshest GPS or companion position, once either reported one
+        #[serde(skip_serializing_if = "Option::is_none")]
+        loc: Option<Location>,
+        /// `loc` is older than the configured age — the detection may be
+        /// far from it
+        #[serde(skip_serializing_if = "is_false")]
+        location_stale: bool,
+        /// When captured: uptime in milliseconds, or Unix milliseconds per
+        /// `ts_source`
+        ts: u64,
+        #[serde(skip_serializing_if = "TsSource::is_uptime")]
+        ts_source: TsSource,
+    },
+    /// Direction estimate from a Constant Tone Extension (see `cte`)
+    #[serde(rename = "direction")]
+    Direction {
+        mac: &'a MacString,
+        rssi: i8,
+        /// CTE type: "aoa", "aod_1us", "aod_2us"
+        cte: &'static str,
+        /// Angle of arrival in degrees from broadside, if estimable
+        #[serde(skip_serializing_if = "Option::is_none")]
+        angle: Option<i8>,
         /// Uptime in milliseconds when captured
         ts: u32,
     },
@@ -78,6 +334,298 @@ pub enum DeviceMessage<'a> {
         board: &'static str,
         /// Firmware version
         version: &'static str,
+        /// Compiled-in signature version, see `get_sig_version`
+        sig_version: u32,
+        /// Regulatory region: "fcc", "etsi", or "mkk"
+        region: &'static str,
+        /// GPS receiver state; omitted on builds without a GPS module
+        #[serde(skip_serializing_if = "Option::is_none")]
+        gps: Option<GpsStatus>,
+    },
+    /// Another AirHound unit's advertised detection summary
+    #[serde(rename = "peer")]
+    Peer {
+        mac: &'a MacString,
+        rssi: i8,
+        scanning: bool,
+        /// WiFi matches since the peer booted (saturates at 65535)
+        wifi: u16,
+        /// BLE matches since the peer booted (saturates at 65535)
+        ble: u16,
+        /// Highest active severity (0 = none)
+        severity: u8,
+        /// Battery percent, omitted if the peer cannot measure it
+        #[serde(skip_serializing_if = "Option::is_none")]
+        battery: Option<u8>,
+        /// Uptime in milliseconds when received
+        ts: u32,
+    },
+    /// Subsystem health change (see `health::HealthMonitor`)
+    #[serde(rename = "health")]
+    Health {
+        /// Subsystem name: "wifi_sniffer", "channel_hop", "ble_scanner"
+        subsystem: &'static str,
+        /// "stalled", "recovered", or "restarting"
+        status: &'static str,
+        /// Milliseconds since the subsystem last checked in
+        idle_ms: u32,
+        /// Uptime in milliseconds when detected
+        ts: u32,
+    },
+    /// Sighting of a MAC on the watch list, sent whether or not it matched
+    #[serde(rename = "watch")]
+    Watch {
+        mac: &'a MacString,
+        /// Radio it was seen on: "wifi", "ble", or "bt_classic"
+        radio: &'static str,
+        rssi: i8,
+        /// Recent RSSI samples, oldest first, ending with this sighting
+        history: &'a [i8],
+        /// Strongest RSSI of the current encounter
+        closest_rssi: i8,
+        /// Uptime in milliseconds at the strongest sighting
+        closest_ts: u32,
+        /// User-assigned label for this MAC, if any
+        #[serde(skip_serializing_if = "Option::is_none")]
+        label: Option<&'a str>,
+        /// Uptime in milliseconds when captured
+        ts: u32,
+    },
+    /// End of an encounter: a watched MAC silent for `watch::LOST_AFTER_MS`
+    #[serde(rename = "watch_lost")]
+    WatchLost {
+        mac: &'a MacString,
+        /// Radio of the last sighting
+        radio: &'static str,
+        /// Uptime in milliseconds at the first and last sightings
+        first_seen: u32,
+        last_seen: u32,
+        sightings: u16,
+        /// Strongest RSSI of the encounter and its uptime in milliseconds
+        closest_rssi: i8,
+        closest_ts: u32,
+        /// User-assigned label for this MAC, if any
+        #[serde(skip_serializing_if = "Option::is_none")]
+        label: Option<&'a str>,
+        /// Uptime in milliseconds when sent
+        ts: u32,
+    },
+    /// A known WPA2/WPA3 network advertised open, with WEP, or with WPA1
+    /// (see `wids::SecurityMonitor`)
+    #[serde(rename = "downgrade")]
+    Downgrade {
+        ssid: &'a NameString<N>,
+        /// Transmitter of the downgraded beacon or probe response
+        bssid: &'a MacString,
+        /// Security the network is known for: "wpa2" or "wpa3"
+        expected: &'static str,
+        /// Security advertised now: "open", "wep", or "wpa"
+        seen: &'static str,
+        rssi: i8,
+        ch: u8,
+        /// Uptime in milliseconds when captured
+        ts: u32,
+    },
+    /// Reply to `get_sig_version`: which signatures the device carries, so
+    /// the companion can decide whether to push an update. Always delivered,
+    /// like `sig_update`.
+    #[serde(rename = "sig_version")]
+    SigVersion {
+        /// Hash of the enabled compiled-in packs
+        /// (`defaults::signature_version`)
+        defaults: u32,
+        /// Names of the enabled compiled-in packs
+        packs: &'a [&'static str],
+        /// Serial of the installed signature bundle
+        #[serde(skip_serializing_if = "Option::is_none")]
+        bundle: Option<u32>,
+        /// Signatures added with `add_signature`
+        runtime: u16,
+    },
+    /// Progress of a signature bundle update (see `sigbundle`). A reply to
+    /// the `sig_*` commands, so not a subscribable type: always delivered.
+    #[serde(rename = "sig_update")]
+    SigUpdate {
+        /// "receiving", "installed", "rolled_back", or "rejected"
+        state: &'static str,
+        /// Bundle bytes received so far
+        received: u16,
+        /// Serial of the bundle now installed
+        #[serde(skip_serializing_if = "Option::is_none")]
+        serial: Option<u32>,
+        /// Why the step was rejected
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<&'static str>,
+    },
+    /// Dry run of a rule pushed with `test_rule` over the recently logged
+    /// detections (see `rules::test_rule`): one per rule, or a single one
+    /// carrying `error` if the rules were rejected. A reply, so always
+    /// delivered like `sig_update`.
+    #[serde(rename = "rule_test")]
+    RuleTest {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        id: Option<&'a str>,
+        /// Logged detections the rule matched
+        matched: u16,
+        /// Logged detections replayed
+        events: u16,
+        /// Why the rules were rejected
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<&'static str>,
+    },
+    /// Raw bytes behind a recent match (see `capture::EvidenceRing`): one
+    /// per entry, oldest first, in reply to `get_evidence`. A reply, so
+    /// always delivered like `rule_test`.
+    #[serde(rename = "evidence")]
+    Evidence {
+        /// "wifi" or "ble"
+        radio: &'static str,
+        mac: &'a MacString,
+        /// Position among the entries sent, from 1
+        index: u8,
+        /// Entries sent
+        count: u8,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        ch: Option<u8>,
+        rssi: i8,
+        /// Length of the whole frame or advertising data
+        len: u16,
+        /// Hex: the 802.11 frame without FCS, or the AD structures, cut
+        /// short after `capture::MAX_EVIDENCE_LEN` bytes
+        data: &'a str,
+        /// When received: uptime in milliseconds, or Unix milliseconds per
+        /// `ts_source`
+        ts: u64,
+        #[serde(skip_serializing_if = "TsSource::is_uptime")]
+        ts_source: TsSource,
+    },
+    /// Filter counters since boot (`filter::FilterStats`), sent periodically
+    /// and in reply to `get_stats`
+    #[serde(rename = "stats")]
+    Stats {
+        /// Scan results evaluated
+        evaluated: u32,
+        /// Results with at least one match
+        matched: u32,
+        /// Results dropped below the RSSI threshold
+        rssi_rejected: u32,
+        /// Signature hits not broken down in `sigs` for lack of room
+        untracked: u32,
+        /// `[pack, signature, hits]` per compiled-in signature that
+        /// matched: the pack's position in `sig_version`'s `packs` and the
+        /// signature's index in its pack file
+        sigs: &'a [(u8, u16, u32)],
+        /// Uptime in milliseconds
+        ts: u32,
+    },
+    /// Per-channel WiFi activity since boot (`scanner::ChannelStats`), sent
+    /// with `stats` and in reply to `get_channel_stats`
+    #[serde(rename = "channel_stats")]
+    ChannelStats {
+        /// `[channel, frames, matches]` per channel a frame was heard on:
+        /// frames the sniffer heard and scan results that matched
+        channels: &'a [(u8, u32, u32)],
+        /// Uptime in milliseconds
+        ts: u32,
+    },
+    /// A WiFi and a BLE detection from adjacent MACs in one OUI, taken to be
+    /// the same device (see `correlate::CrossRadio`). Sent once per pairing,
+    /// after both detections' own messages.
+    #[serde(rename = "correlated")]
+    Correlated {
+        wifi_mac: &'a MacString,
+        ble_mac: &'a MacString,
+        wifi_rssi: i8,
+        ble_rssi: i8,
+        /// Rating of the stronger detection
+        severity: Severity,
+        category: Category,
+        device_class: DeviceClass,
+        /// The stronger detection's confidence, raised one step
+        confidence: Confidence,
+        /// Detected during quiet hours — log without notifying
+        #[serde(skip_serializing_if = "is_false")]
+        quiet: bool,
+        /// Uptime in milliseconds when paired
+        ts: u32,
+    },
+    /// A device heard all along the user's way (see
+    /// `following::FollowingDetector`). Sent after the detection's own
+    /// message, at most once per window per device.
+    #[serde(rename = "following_alert")]
+    FollowingAlert {
+        mac: &'a MacString,
+        /// Synthetic ID linking the device's rotating MACs
+        #[serde(skip_serializing_if = "Option::is_none")]
+        device_id: Option<u16>,
+        /// Always high
+        severity: Severity,
+        category: Category,
+        device_class: DeviceClass,
+        /// Sightings within the window, at least a minute apart
+        sightings: u8,
+        /// Greatest distance between the user's locations at two sightings
+        span_m: u32,
+        /// Time from the first sighting in the window to this one
+        duration_s: u32,
+        /// User-assigned label for this MAC, if any
+        #[serde(skip_serializing_if = "Option::is_none")]
+        label: Option<&'a str>,
+        /// Detected during quiet hours — log without notifying
+        #[serde(skip_serializing_if = "is_false")]
+        quiet: bool,
+        /// The user's location at this sighting
+        loc: Location,
+        ts: u64,
+        #[serde(skip_serializing_if = "TsSource::is_uptime")]
+        ts_source: TsSource,
+    },
+    /// A tracker that has stayed within range of the user (see
+    /// `lingering::LingeringDetector`). Sent after the detection's own
+    /// message, once per stay.
+    #[serde(rename = "lingering_alert")]
+    LingeringAlert {
+        mac: &'a MacString,
+        /// Synthetic ID linking the device's rotating MACs
+        #[serde(skip_serializing_if = "Option::is_none")]
+        device_id: Option<u16>,
+        /// Always medium
+        severity: Severity,
+        category: Category,
+        device_class: DeviceClass,
+        rssi: i8,
+        /// Time since it came within range
+        duration_s: u32,
+        /// User-assigned label for this MAC, if any
+        #[serde(skip_serializing_if = "Option::is_none")]
+        label: Option<&'a str>,
+        /// Detected during quiet hours — log without notifying
+        #[serde(skip_serializing_if = "is_false")]
+        quiet: bool,
+        /// The user's location, unless none is known or it is stale
+        #[serde(skip_serializing_if = "Option::is_none")]
+        loc: Option<Location>,
+        ts: u64,
+        #[serde(skip_serializing_if = "TsSource::is_uptime")]
+        ts_source: TsSource,
+    },
+    /// Directed SSIDs a client has probed for (see `probe::ProbeProfiler`).
+    /// Sent when it asks for a new one, at most once a minute per client.
+    #[serde(rename = "client_profile")]
+    ClientProfile {
+        mac: &'a MacString,
+        #[serde(skip_serializing_if = "is_false")]
+        mac_randomized: bool,
+        /// In the order first probed for
+        ssids: &'a [String<33>],
+        /// Further SSIDs probed for that didn't fit
+        dropped: u8,
+        /// Probe requests heard, wildcard ones included
+        probes: u16,
+        rssi: i8,
+        /// Uptime in milliseconds of the first probe heard
+        first_ts: u32,
+        ts: u32,
     },
 }
 
@@ -93,6 +641,14 @@ pub enum HostCommand {
     Stop,
     /// Request current status
     GetStatus,
+    /// Request the signature versions the device carries
+    GetSigVersion,
+    /// Request the filter counters
+    GetStats,
+    /// Request the per-channel activity counters
+    GetChannelStats,
+    /// Request the raw bytes of the latest matches
+    GetEvidence,
     /// Update minimum RSSI threshold
     SetRssi {
         /// Minimum RSSI (negative dBm value)
@@ -100,6 +656,85 @@ pub enum HostCommand {
     },
     /// Enable or disable the buzzer (M5StickC only)
     SetBuzzer { enabled: bool },
+    /// Change the BLE scan duty cycle and active/passive mode
+    SetBleScan(BleScanConfig),
+    /// Enable or disable the detection summary in our BLE advertisement
+    SetBeacon { enabled: bool },
+    /// Enable or disable per-device RSSI smoothing in scan results
+    SetRssiSmoothing { enabled: bool },
+    /// Report or drop our own advertisement and other AirHound units'
+    SetReportSelf { enabled: bool },
+    /// Enable or disable the signature-independent SSID heuristics
+    SetSsidHeuristics { enabled: bool },
+    /// Enable or disable flagging irregular beacon timing per BSSID
+    SetTimingHeuristics { enabled: bool },
+    /// Enable or disable flagging sequence number jumps per transmitter
+    SetSequenceHeuristics { enabled: bool },
+    /// Enable or disable dropping WiFi frames that fail their FCS check
+    SetFcsCheck { enabled: bool },
+    /// Set the RSSI thresholds of the proximity zones
+    SetZones(ProximityZones),
+    /// Switch low-power BLE-only mode on or off
+    SetLowPower { enabled: bool },
+    /// Select the regulatory region and with it the WiFi channel plan
+    SetRegion(Region),
+    /// Restrict WiFi hopping and results to these channels of the plan
+    SetChannelFilter(ChannelMask),
+    /// Set wall-clock time (Unix seconds) and the local UTC offset
+    SetTime { epoch_s: u32, utc_offset_min: i16 },
+    /// The companion's own position, to tag detections with when it is
+    /// fresher than the on-board GPS
+    SetLocation(Location),
+    /// Set how old a location may get before detections are marked
+    /// `location_stale`
+    SetLocationStale(StalePolicy),
+    /// Set the sightings, window and distance that make a device a
+    /// follower
+    SetFollowing(FollowingPolicy),
+    /// Set how long a tracker must stay within range to be reported as
+    /// lingering
+    SetLingering(LingeringPolicy),
+    /// Configure the daily quiet window
+    SetQuietHours(QuietHours),
+    /// Choose which signature categories are reported
+    SetCategories(Categories),
+    /// Provision the Identity Resolving Key of one of the user's devices
+    AddIrk(Irk),
+    /// Forget all provisioned IRKs
+    ClearIrks,
+    /// Replace all user settings at once. Produced by a write to the GATT
+    /// settings characteristic; has no NDJSON form.
+    Configure(DeviceSettings),
+    /// Choose which messages this connection receives. Only meaningful over
+    /// BLE, where each client keeps its own subscription.
+    Subscribe(Subscription),
+    /// Report every sighting of `mac` for `duration_s` seconds
+    Watch { mac: [u8; 6], duration_s: u32 },
+    /// Remove `mac` from the watch list
+    Unwatch { mac: [u8; 6] },
+    /// Label `mac` in later messages; an empty name removes the label
+    Label { mac: [u8; 6], name: NameString },
+    /// Start receiving a signature bundle of `len` bytes
+    SigBegin { len: u16 },
+    /// Next piece of the bundle, starting at byte `offset`
+    SigChunk {
+        offset: u16,
+        data: Vec<u8, MAX_CHUNK_LEN>,
+    },
+    /// Verify and install the received bundle
+    SigCommit,
+    /// Reinstate the previously installed bundle
+    SigRollback,
+    /// Match `sig` alongside the compiled-in signatures until reboot
+    AddSignature {
+        sig: RuntimeSignature,
+        description: MatchDetail,
+    },
+    /// Drop a signature added with `AddSignature`
+    RemoveSignature(RuntimeSignature),
+    /// Dry-run a rule database blob (`rules::RuleDb::parse`) over the
+    /// recently logged detections, without alerting
+    TestRule { blob: Vec<u8, MAX_CHUNK_LEN> },
 }
 
 /// Wire format for host commands — flat struct that `serde_json_core` can
@@ -107,11 +742,95 @@ pub enum HostCommand {
 /// `comm::parse_command()`.
 #[derive(Deserialize)]
 pub(crate) struct RawCommand {
-    pub cmd: heapless::String<16>,
+    pub cmd: heapless::String<24>,
     #[serde(default)]
     pub min_rssi: Option<i8>,
     #[serde(default)]
+    pub immediate: Option<i8>,
+    #[serde(default)]
+    pub near: Option<i8>,
+    #[serde(default)]
     pub enabled: Option<bool>,
+    #[serde(default)]
+    pub interval_ms: Option<u16>,
+    #[serde(default)]
+    pub window_ms: Option<u16>,
+    #[serde(default)]
+    pub active: Option<bool>,
+    #[serde(default)]
+    pub types: Option<Vec<heapless::String<15>, 16>>,
+    #[serde(default)]
+    pub min_severity: Option<u8>,
+    #[serde(default)]
+    pub mac: Option<MacString>,
+    #[serde(default)]
+    pub duration_s: Option<u32>,
+    #[serde(default)]
+    pub name: Option<NameString>,
+    #[serde(default)]
+    pub region: Option<heapless::String<8>>,
+    /// `set_channel_filter` allowlist
+    #[serde(default)]
+    pub channels: Option<Vec<u8, 14>>,
+    #[serde(default)]
+    pub epoch: Option<u32>,
+    #[serde(default)]
+    pub utc_offset_min: Option<i16>,
+    /// `set_location` position in 1e-7 degrees and accuracy in metres
+    #[serde(default)]
+    pub lat: Option<i32>,
+    #[serde(default)]
+    pub lon: Option<i32>,
+    #[serde(default)]
+    pub acc: Option<u16>,
+    /// `set_location_stale` age in seconds
+    #[serde(default)]
+    pub max_age_s: Option<u16>,
+    /// `set_following` sightings, window in minutes and distance in metres
+    #[serde(default)]
+    pub sightings: Option<u8>,
+    #[serde(default)]
+    pub window_min: Option<u16>,
+    #[serde(default)]
+    pub distance_m: Option<u16>,
+    /// `set_lingering` duration in minutes, with `min_rssi`
+    #[serde(default)]
+    pub duration_min: Option<u16>,
+    #[serde(default)]
+    pub start_min: Option<u16>,
+    #[serde(default)]
+    pub end_min: Option<u16>,
+    #[serde(default)]
+    pub irk: Option<heapless::String<47>>,
+    #[serde(default)]
+    pub len: Option<u16>,
+    #[serde(default)]
+    pub offset: Option<u16>,
+    /// Hex-encoded bundle chunk, or rule database for `test_rule`
+    #[serde(default)]
+    pub data: Option<heapless::String<{ 2 * MAX_CHUNK_LEN }>>,
+    /// Runtime signature kind
+    #[serde(default, rename = "type")]
+    pub sig_type: Option<heapless::String<16>>,
+    #[serde(default)]
+    pub value: Option<heapless::String<33>>,
+    #[serde(default)]
+    pub description: Option<MatchDetail>,
+    /// `set_categories` switches; categories left out are enabled
+    #[serde(default)]
+    pub alpr: Option<bool>,
+    #[serde(default)]
+    pub gunshot_detector: Option<bool>,
+    #[serde(default)]
+    pub camera: Option<bool>,
+    #[serde(default)]
+    pub tracker: Option<bool>,
+    #[serde(default)]
+    pub drone: Option<bool>,
+    #[serde(default)]
+    pub attack_tool: Option<bool>,
+    #[serde(default)]
+    pub other: Option<bool>,
 }
 
 /// Firmware version string
@@ -126,6 +845,8 @@ pub type MsgBuffer = Vec<u8, MAX_MSG_LEN>;
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::gps::FixQuality;
+    use crate::scanner::{FindMyBattery, FindMyState};
 
     // ── HostCommand parsing (via comm::parse_command) ──────────────
 
@@ -139,6 +860,28 @@ mod tests {
         assert_ne!(HostCommand::Start, HostCommand::Stop);
     }
 
+    // ── String capacities ───────────────────────────────────────────
+
+    #[test]
+    fn truncate_str_respects_char_boundaries() {
+        let (s, cut) = truncate_str::<4>("abcdef");
+        assert_eq!((s.as_str(), cut), ("abcd", true));
+        // 'é' is two bytes and would straddle the limit
+        let (s, cut) = truncate_str::<4>("abcé");
+        assert_eq!((s.as_str(), cut), ("abc", true));
+        let (s, cut) = truncate_str::<4>("ab");
+        assert_eq!((s.as_str(), cut), ("ab", false));
+    }
+
+    #[test]
+    fn match_reason_capacity_is_configurable() {
+        let long = "Flock Safety Falcon LPR camera with solar power";
+        let short = MatchReason::<MATCH_DETAIL_LEN>::new("mac_oui", long, SignatureMeta::UNRATED);
+        assert_eq!(short.detail.len(), MATCH_DETAIL_LEN);
+        let full = MatchReason::<64>::new("mac_oui", long, SignatureMeta::UNRATED);
+        assert_eq!(full.detail.as_str(), long);
+    }
+
     // ── DeviceMessage serialization ─────────────────────────────────
 
     #[test]
@@ -150,6 +893,9 @@ mod tests {
             ble_clients: 1,
             board: "test_board",
             version: "0.1.0",
+            sig_version: 0x1234_5678,
+            region: "etsi",
+            gps: None,
         };
         let mut buf = [0u8; 256];
         let len = serde_json_core::to_slice(&msg, &mut buf).unwrap();
@@ -158,6 +904,49 @@ mod tests {
         assert!(json.contains(r#""scanning":true"#));
         assert!(json.contains(r#""uptime":120"#));
         assert!(json.contains(r#""board":"test_board""#));
+        assert!(json.contains(r#""region":"etsi""#));
+        assert!(json.contains(r#""sig_version":305419896"#));
+        assert!(!json.contains("gps"));
+    }
+
+    #[test]
+    fn serialize_status_with_gps() {
+        let msg = DeviceMessage::Status {
+            scanning: true,
+            uptime: 120,
+            heap_free: 48000,
+            ble_clients: 1,
+            board: "test_board",
+            version: "0.1.0",
+            sig_version: 0x1234_5678,
+            region: "etsi",
+            gps: Some(GpsStatus {
+                fix: FixQuality::Dgps,
+                satellites: Some(9),
+                hdop: Some(110),
+                fix_age: Some(2),
+            }),
+        };
+        let mut buf = [0u8; 320];
+        let len = serde_json_core::to_slice(&msg, &mut buf).unwrap();
+        let json = core::str::from_utf8(&buf[..len]).unwrap();
+        assert!(json.ends_with(r#""gps":{"fix":"dgps","satellites":9,"hdop":110,"fix_age":2}}"#));
+    }
+
+    #[test]
+    fn serialize_sig_version_message() {
+        let msg = DeviceMessage::SigVersion {
+            defaults: 0xDEAD_BEEF,
+            packs: &["core-flock", "trackers"],
+            bundle: None,
+            runtime: 2,
+        };
+        let mut buf = [0u8; 256];
+        let len = serde_json_core::to_slice(&msg, &mut buf).unwrap();
+        assert_eq!(
+            core::str::from_utf8(&buf[..len]).unwrap(),
+            r#"{"type":"sig_version","defaults":3735928559,"packs":["core-flock","trackers"],"runtime":2}"#
+        );
     }
 
     #[test]
@@ -170,16 +959,39 @@ mod tests {
         let _ = matches.push(MatchReason {
             filter_type: "mac_oui",
             detail,
+            meta: SignatureMeta::new(Category::Alpr, Severity::High, Confidence::High),
         });
 
         let msg = DeviceMessage::WiFiScan {
             mac: &mac,
+            mac_randomized: false,
+            via: None,
             ssid: &ssid,
             rssi: -45,
+            rssi_avg: Some(-48),
+            zone: Proximity::Immediate,
+            approaching: true,
             ch: 6,
             frame: "beacon",
+            action: None,
+            security: Some("wpa2"),
+            severity: Severity::High,
+            category: Category::Alpr,
+            device_class: DeviceClass::LprCamera,
+            confidence: Confidence::High,
             matches: &matches,
+            rules: &[],
+            truncated: false,
+            label: None,
+            device_id: None,
+            remote_id: None,
+            p2p: None,
+            network: None,
+            quiet: false,
+            loc: None,
+            location_stale: false,
             ts: 1000,
+            ts_source: TsSource::Uptime,
         };
 
         let mut buf = [0u8; 512];
@@ -188,9 +1000,58 @@ mod tests {
         assert!(json.contains(r#""type":"wifi""#));
         assert!(json.contains(r#""mac":"B4:1E:52:AB:CD:EF""#));
         assert!(json.contains(r#""ssid":"Flock-A1B2C3""#));
-        assert!(json.contains(r#""rssi":-45"#));
+        assert!(json.contains(r#""rssi":-45,"rssi_avg":-48,"zone":"immediate","approaching":true"#));
         assert!(json.contains(r#""ch":6"#));
-        assert!(json.contains(r#""frame":"beacon""#));
+        assert!(json.contains(r#""frame":"beacon","security":"wpa2""#));
+        // Rated at the top level, where subscription severity floors look
+        assert!(json.contains(r#""severity":3,"category":"alpr","device_class":"lpr_camera","confidence":"high","match":[{"type":"mac_oui","detail":"Flock Safety"}]"#));
+        assert_eq!(crate::route::message_severity(json.as_bytes()), 3);
+    }
+
+    #[test]
+    fn sized_message_carries_full_detail() {
+        let long = "Flock Safety Falcon LPR camera with solar power";
+        let mac = MacString::try_from("B4:1E:52:AB:CD:EF").unwrap();
+        let ssid = NameString::<NAME_LEN>::try_from("Flock-A1B2C3").unwrap();
+        let mut matches = Vec::<MatchReason<64>, MAX_MATCHES>::new();
+        let _ = matches.push(MatchReason::new("mac_oui", long, SignatureMeta::UNRATED));
+
+        let msg: DeviceMessageSized<'_, NAME_LEN, 64> = DeviceMessageSized::WiFiScan {
+            mac: &mac,
+            mac_randomized: false,
+            via: None,
+            ssid: &ssid,
+            rssi: -45,
+            rssi_avg: None,
+            zone: Proximity::Immediate,
+            approaching: false,
+            ch: 6,
+            frame: "beacon",
+            action: None,
+            security: None,
+            severity: Severity::Low,
+            category: Category::Other,
+            device_class: DeviceClass::Unknown,
+            confidence: Confidence::Low,
+            matches: &matches,
+            rules: &[],
+            truncated: false,
+            label: None,
+            device_id: None,
+            remote_id: None,
+            p2p: None,
+            network: None,
+            quiet: false,
+            loc: None,
+            location_stale: false,
+            ts: 1000,
+            ts_source: TsSource::Uptime,
+        };
+
+        let mut buf = [0u8; 512];
+        let len = serde_json_core::to_slice(&msg, &mut buf).unwrap();
+        let json = core::str::from_utf8(&buf[..len]).unwrap();
+        assert!(json.contains(long), "{}", json);
     }
 
     #[test]
@@ -201,12 +1062,33 @@ mod tests {
 
         let msg = DeviceMessage::BleScan {
             mac: &mac,
+            mac_randomized: false,
             name: &name,
             rssi: -60,
+            rssi_avg: None,
+            zone: Proximity::Near,
+            approaching: false,
+            dist_m: Some(2.5),
             uuid: None,
             mfr: 0x09C8,
+            phy: Some("coded"),
+            severity: Severity::Medium,
+            category: Category::Other,
+            device_class: DeviceClass::Unknown,
+            confidence: Confidence::Medium,
             matches: &matches,
+            rules: &[],
+            truncated: false,
+            label: None,
+            device_id: None,
+            remote_id: None,
+            findmy: None,
+            mine: false,
+            quiet: false,
+            loc: None,
+            location_stale: false,
             ts: 2000,
+            ts_source: TsSource::Uptime,
         };
 
         let mut buf = [0u8; 512];
@@ -214,9 +1096,14 @@ mod tests {
         let json = core::str::from_utf8(&buf[..len]).unwrap();
         assert!(json.contains(r#""type":"ble""#));
         assert!(json.contains(r#""name":"FS Ext Battery""#));
-        assert!(json.contains(r#""mfr":2504"#)); // 0x09C8 = 2504
-                                                 // uuid should be omitted when None
+        assert!(json.contains(r#""mfr":2504,"phy":"coded""#)); // 0x09C8 = 2504
+        assert!(json.contains(r#""zone":"near","dist_m":2.5,"#));
+        // uuid should be omitted when None
         assert!(!json.contains("uuid"));
+        assert!(!json.contains("mac_randomized"));
+        assert!(!json.contains("rssi_avg"));
+        assert!(!json.contains("approaching"));
+        assert!(!json.contains("truncated"));
     }
 
     #[test]
@@ -228,18 +1115,573 @@ mod tests {
 
         let msg = DeviceMessage::BleScan {
             mac: &mac,
+            mac_randomized: true,
             name: &name,
             rssi: -70,
+            rssi_avg: None,
+            zone: Proximity::Near,
+            approaching: false,
+            dist_m: None,
             uuid: Some(&uuid),
             mfr: 0,
+            phy: None,
+            severity: Severity::Medium,
+            category: Category::Other,
+            device_class: DeviceClass::Unknown,
+            confidence: Confidence::Medium,
             matches: &matches,
+            rules: &[],
+            truncated: false,
+            label: None,
+            device_id: None,
+            remote_id: None,
+            findmy: None,
+            mine: false,
+            quiet: false,
+            loc: None,
+            location_stale: false,
             ts: 3000,
+            ts_source: TsSource::Uptime,
         };
 
         let mut buf = [0u8; 512];
         let len = serde_json_core::to_slice(&msg, &mut buf).unwrap();
         let json = core::str::from_utf8(&buf[..len]).unwrap();
         assert!(json.contains(r#""uuid":"00003100-0000-1000-8000-00805f9b34fb""#));
+        assert!(json.contains(r#""mac":"00:11:22:33:44:55","mac_randomized":true"#));
+    }
+
+    #[test]
+    fn serialize_ble_scan_with_remote_id() {
+        let mac = MacString::try_from("60:60:1F:00:00:01").unwrap();
+        let name = NameString::new();
+        let matches = Vec::<MatchReason, 4>::new();
+        let remote_id = RemoteId {
+            uas_id: String::try_from("1581F5FJD228400").unwrap(),
+            lat: Some(407_128_000),
+            lon: Some(-740_060_000),
+        };
+
+        let msg = DeviceMessage::BleScan {
+            mac: &mac,
+            mac_randomized: false,
+            name: &name,
+            rssi: -80,
+            rssi_avg: None,
+            zone: Proximity::Near,
+            approaching: false,
+            dist_m: None,
+            uuid: None,
+            mfr: 0,
+            phy: None,
+            severity: Severity::Medium,
+            category: Category::Drone,
+            device_class: DeviceClass::Unknown,
+            confidence: Confidence::High,
+            matches: &matches,
+            rules: &[],
+            truncated: false,
+            label: None,
+            device_id: None,
+            remote_id: Some(&remote_id),
+            findmy: None,
+            mine: false,
+            quiet: false,
+            loc: None,
+            location_stale: false,
+            ts: 4000,
+            ts_source: TsSource::Uptime,
+        };
+
+        let mut buf = [0u8; 512];
+        let len = serde_json_core::to_slice(&msg, &mut buf).unwrap();
+        let json = core::str::from_utf8(&buf[..len]).unwrap();
+        assert!(json
+            .contains(r#""remote_id":{"id":"1581F5FJD228400","lat":407128000,"lon":-740060000}"#));
+    }
+
+    #[test]
+    fn serialize_health_message() {
+        let msg = DeviceMessage::Health {
+            subsystem: "ble_scanner",
+            status: "stalled",
+            idle_ms: 61000,
+            ts: 90000,
+        };
+        let mut buf = [0u8; 128];
+        let len = serde_json_core::to_slice(&msg, &mut buf).unwrap();
+        let json = core::str::from_utf8(&buf[..len]).unwrap();
+        assert_eq!(
+            json,
+            r#"{"type":"health","subsystem":"ble_scanner","status":"stalled","idle_ms":61000,"ts":90000}"#
+        );
+    }
+
+    #[test]
+    fn serialize_bt_classic_message() {
+        let mac = MacString::try_from("00:11:22:33:44:55").unwrap();
+        let name = NameString::new();
+        let matches = Vec::<MatchReason, 4>::new();
+        let msg = DeviceMessage::BtClassicScan {
+            mac: &mac,
+            name: &name,
+            rssi: -60,
+            rssi_avg: None,
+            zone: Proximity::Near,
+            approaching: false,
+            cod: 0x200430,
+            severity: Severity::Medium,
+            category: Category::Other,
+            device_class: DeviceClass::Unknown,
+            confidence: Confidence::Medium,
+            matches: &matches,
+            rules: &[],
+            truncated: false,
+            label: None,
+            quiet: false,
+            loc: Location::try_host(476_062_000, -1_223_321_000, Some(5)),
+            location_stale: true,
+            ts: 1_767_225_600_250,
+            ts_source: TsSource::Gps,
+        };
+        let mut buf = [0u8; 320];
+        let len = serde_json_core::to_slice(&msg, &mut buf).unwrap();
+        let json = core::str::from_utf8(&buf[..len]).unwrap();
+        assert!(json.contains(r#""type":"bt_classic""#));
+        assert!(json.contains(r#""cod":2098224"#));
+        assert!(json.contains(
+            r#""loc":{"lat":476062000,"lon":-1223321000,"acc":5,"src":"host"},"location_stale":true"#
+        ));
+        assert!(json.ends_with(r#""ts":1767225600250,"ts_source":"gps"}"#));
+    }
+
+    #[test]
+    fn serialize_direction_message() {
+        let mac = MacString::try_from("C0:11:22:33:44:55").unwrap();
+        let msg = DeviceMessage::Direction {
+            mac: &mac,
+            rssi: -62,
+            cte: "aoa",
+            angle: Some(-25),
+            ts: 7,
+        };
+        let mut buf = [0u8; 128];
+        let len = serde_json_core::to_slice(&msg, &mut buf).unwrap();
+        let json = core::str::from_utf8(&buf[..len]).unwrap();
+        assert_eq!(
+            json,
+            r#"{"type":"direction","mac":"C0:11:22:33:44:55","rssi":-62,"cte":"aoa","angle":-25,"ts":7}"#
+        );
+    }
+
+    #[test]
+    fn serialize_peer_message() {
+        let mac = MacString::try_from("AA:BB:CC:DD:EE:FF").unwrap();
+        let msg = DeviceMessage::Peer {
+            mac: &mac,
+            rssi: -55,
+            scanning: true,
+            wifi: 3,
+            ble: 1,
+            severity: 0,
+            battery: None,
+            ts: 500,
+        };
+        let mut buf = [0u8; 256];
+        let len = serde_json_core::to_slice(&msg, &mut buf).unwrap();
+        let json = core::str::from_utf8(&buf[..len]).unwrap();
+        assert!(json.contains(r#""type":"peer""#));
+        assert!(json.contains(r#""wifi":3"#));
+        assert!(!json.contains("battery"));
+    }
+
+    #[test]
+    fn serialize_watch_message() {
+        let mac = MacString::try_from("AA:BB:CC:DD:EE:FF").unwrap();
+        let msg = DeviceMessage::Watch {
+            mac: &mac,
+            radio: "ble",
+            rssi: -61,
+            history: &[-70, -66, -61],
+            closest_rssi: -61,
+            closest_ts: 900,
+            label: None,
+            ts: 900,
+        };
+        let mut buf = [0u8; 256];
+        let len = serde_json_core::to_slice(&msg, &mut buf).unwrap();
+        let json = core::str::from_utf8(&buf[..len]).unwrap();
+        assert!(json.starts_with(r#"{"type":"watch""#));
+        assert!(json.contains(r#""history":[-70,-66,-61]"#));
+        assert!(!json.contains("label"));
+    }
+
+    #[test]
+    fn serialize_watch_lost_message() {
+        let mac = MacString::try_from("AA:BB:CC:DD:EE:FF").unwrap();
+        let msg = DeviceMessage::WatchLost {
+            mac: &mac,
+            radio: "wifi",
+            first_seen: 1_000,
+            last_seen: 30_000,
+            sightings: 42,
+            closest_rssi: -48,
+            closest_ts: 12_500,
+            label: Some("neighbour's car"),
+            ts: 90_000,
+        };
+        let mut buf = [0u8; 256];
+        let len = serde_json_core::to_slice(&msg, &mut buf).unwrap();
+        let json = core::str::from_utf8(&buf[..len]).unwrap();
+        assert!(json.starts_with(r#"{"type":"watch_lost""#));
+        assert!(json.contains(r#""closest_rssi":-48,"closest_ts":12500"#));
+        assert!(json.contains(r#""label":"neighbour's car""#));
+    }
+
+    #[test]
+    fn serialize_downgrade_message() {
+        let ssid = NameString::try_from("Home").unwrap();
+        let bssid = MacString::try_from("02:AA:BB:CC:DD:EE").unwrap();
+        let msg = DeviceMessage::Downgrade {
+            ssid: &ssid,
+            bssid: &bssid,
+            expected: "wpa2",
+            seen: "open",
+            rssi: -52,
+            ch: 6,
+            ts: 75_000,
+        };
+        let mut buf = [0u8; 256];
+        let len = serde_json_core::to_slice(&msg, &mut buf).unwrap();
+        let json = core::str::from_utf8(&buf[..len]).unwrap();
+        assert_eq!(
+            json,
+            r#"{"type":"downgrade","ssid":"Home","bssid":"02:AA:BB:CC:DD:EE","expected":"wpa2","seen":"open","rssi":-52,"ch":6,"ts":75000}"#
+        );
+    }
+
+    #[test]
+    fn serialize_sig_update_message() {
+        let msg = DeviceMessage::SigUpdate {
+            state: "rejected",
+            received: 128,
+            serial: None,
+            error: Some("bad signature"),
+        };
+        let mut buf = [0u8; 128];
+        let len = serde_json_core::to_slice(&msg, &mut buf).unwrap();
+        assert_eq!(
+            core::str::from_utf8(&buf[..len]).unwrap(),
+            r#"{"type":"sig_update","state":"rejected","received":128,"error":"bad signature"}"#
+        );
+    }
+
+    #[test]
+    fn serialize_stats_message() {
+        let msg = DeviceMessage::Stats {
+            evaluated: 1200,
+            matched: 14,
+            rssi_rejected: 310,
+            untracked: 0,
+            sigs: &[(0, 0, 9), (1, 4, 5)],
+            ts: 60_000,
+        };
+        let mut buf = [0u8; 192];
+        let len = serde_json_core::to_slice(&msg, &mut buf).unwrap();
+        assert_eq!(
+            core::str::from_utf8(&buf[..len]).unwrap(),
+            r#"{"type":"stats","evaluated":1200,"matched":14,"rssi_rejected":310,"untracked":0,"sigs":[[0,0,9],[1,4,5]],"ts":60000}"#
+        );
+    }
+
+    #[test]
+    fn serialize_correlated_message() {
+        let wifi_mac = MacString::try_from("58:8E:81:01:02:03").unwrap();
+        let ble_mac = MacString::try_from("58:8E:81:01:02:05").unwrap();
+        let msg = DeviceMessage::Correlated {
+            wifi_mac: &wifi_mac,
+            ble_mac: &ble_mac,
+            wifi_rssi: -62,
+            ble_rssi: -70,
+            severity: Severity::High,
+            category: Category::Alpr,
+            device_class: DeviceClass::LprCamera,
+            confidence: Confidence::High,
+            quiet: false,
+            ts: 90_000,
+        };
+        let mut buf = [0u8; 256];
+        let len = serde_json_core::to_slice(&msg, &mut buf).unwrap();
+        assert_eq!(
+            core::str::from_utf8(&buf[..len]).unwrap(),
+            r#"{"type":"correlated","wifi_mac":"58:8E:81:01:02:03","ble_mac":"58:8E:81:01:02:05","wifi_rssi":-62,"ble_rssi":-70,"severity":3,"category":"alpr","device_class":"lpr_camera","confidence":"high","ts":90000}"#
+        );
+    }
+
+    #[test]
+    fn serialize_following_alert() {
+        let mac = MacString::try_from("7C:11:22:33:44:55").unwrap();
+        let msg = DeviceMessage::FollowingAlert {
+            mac: &mac,
+            device_id: Some(7),
+            severity: Severity::High,
+            category: Category::Tracker,
+            device_class: DeviceClass::PersonalTracker,
+            sightings: 4,
+            span_m: 1_250,
+            duration_s: 1_140,
+            label: None,
+            quiet: false,
+            loc: Location::try_host(455_231_000, -1_226_765_000, None).unwrap(),
+            ts: 1_200_000,
+            ts_source: TsSource::Uptime,
+        };
+        let mut buf = [0u8; 320];
+        let len = serde_json_core::to_slice(&msg, &mut buf).unwrap();
+        assert_eq!(
+            core::str::from_utf8(&buf[..len]).unwrap(),
+            r#"{"type":"following_alert","mac":"7C:11:22:33:44:55","device_id":7,"severity":3,"category":"tracker","device_class":"personal_tracker","sightings":4,"span_m":1250,"duration_s":1140,"loc":{"lat":455231000,"lon":-1226765000,"src":"host"},"ts":1200000}"#
+        );
+    }
+
+    #[test]
+    fn serialize_lingering_alert() {
+        let mac = MacString::try_from("7C:11:22:33:44:55").unwrap();
+        let msg = DeviceMessage::LingeringAlert {
+            mac: &mac,
+            device_id: None,
+            severity: Severity::Medium,
+            category: Category::Tracker,
+            device_class: DeviceClass::PersonalTracker,
+            rssi: -64,
+            duration_s: 600,
+            label: None,
+            quiet: true,
+            loc: None,
+            ts: 900_000,
+            ts_source: TsSource::Uptime,
+        };
+        let mut buf = [0u8; 256];
+        let len = serde_json_core::to_slice(&msg, &mut buf).unwrap();
+        assert_eq!(
+            core::str::from_utf8(&buf[..len]).unwrap(),
+            r#"{"type":"lingering_alert","mac":"7C:11:22:33:44:55","severity":2,"category":"tracker","device_class":"personal_tracker","rssi":-64,"duration_s":600,"quiet":true,"ts":900000}"#
+        );
+    }
+
+    #[test]
+    fn serialize_channel_stats() {
+        let msg = DeviceMessage::ChannelStats {
+            channels: &[(1, 5200, 3), (6, 18400, 11)],
+            ts: 60_000,
+        };
+        let mut buf = [0u8; 128];
+        let len = serde_json_core::to_slice(&msg, &mut buf).unwrap();
+        assert_eq!(
+            core::str::from_utf8(&buf[..len]).unwrap(),
+            r#"{"type":"channel_stats","channels":[[1,5200,3],[6,18400,11]],"ts":60000}"#
+        );
+    }
+
+    #[test]
+    fn serialize_client_profile() {
+        let mac = MacString::try_from("00:11:22:33:44:55").unwrap();
+        let ssids = [
+            String::<33>::try_from("PD-MOBILE-07").unwrap(),
+            String::<33>::try_from("LPR-UPLINK").unwrap(),
+        ];
+        let msg = DeviceMessage::ClientProfile {
+            mac: &mac,
+            mac_randomized: false,
+            ssids: &ssids,
+            dropped: 0,
+            probes: 9,
+            rssi: -66,
+            first_ts: 12_000,
+            ts: 72_000,
+        };
+        let mut buf = [0u8; 256];
+        let len = serde_json_core::to_slice(&msg, &mut buf).unwrap();
+        assert_eq!(
+            core::str::from_utf8(&buf[..len]).unwrap(),
+            r#"{"type":"client_profile","mac":"00:11:22:33:44:55","ssids":["PD-MOBILE-07","LPR-UPLINK"],"dropped":0,"probes":9,"rssi":-66,"first_ts":12000,"ts":72000}"#
+        );
+    }
+
+    #[test]
+    fn serialize_rule_test() {
+        let msg = DeviceMessage::RuleTest {
+            id: Some("lone-tile"),
+            matched: 3,
+            events: 32,
+            error: None,
+        };
+        let mut buf = [0u8; 128];
+        let len = serde_json_core::to_slice(&msg, &mut buf).unwrap();
+        assert_eq!(
+            core::str::from_utf8(&buf[..len]).unwrap(),
+            r#"{"type":"rule_test","id":"lone-tile","matched":3,"events":32}"#
+        );
+    }
+
+    #[test]
+    fn serialize_evidence() {
+        let mac = MacString::try_from("B4:1E:52:01:02:03").unwrap();
+        let msg = DeviceMessage::Evidence {
+            radio: "wifi",
+            mac: &mac,
+            index: 1,
+            count: 8,
+            ch: Some(6),
+            rssi: -52,
+            len: 212,
+            data: "80000000ffffffffffff",
+            ts: 61_000,
+            ts_source: TsSource::Uptime,
+        };
+        let mut buf = [0u8; 256];
+        let len = serde_json_core::to_slice(&msg, &mut buf).unwrap();
+        assert_eq!(
+            core::str::from_utf8(&buf[..len]).unwrap(),
+            r#"{"type":"evidence","radio":"wifi","mac":"B4:1E:52:01:02:03","index":1,"count":8,"ch":6,"rssi":-52,"len":212,"data":"80000000ffffffffffff","ts":61000}"#
+        );
+
+        // A full entry fits in a message
+        let data = "ff".repeat(crate::capture::MAX_EVIDENCE_LEN);
+        let msg = DeviceMessage::Evidence {
+            radio: "wifi",
+            mac: &mac,
+            index: 255,
+            count: 255,
+            ch: Some(165),
+            rssi: -128,
+            len: u16::MAX,
+            data: &data,
+            ts: u64::MAX,
+            ts_source: TsSource::Gps,
+        };
+        let mut buf = [0u8; MAX_MSG_LEN];
+        assert!(serde_json_core::to_slice(&msg, &mut buf).is_ok());
+    }
+
+    #[test]
+    fn serialize_label_when_set() {
+        let mac = MacString::try_from("7C:11:22:33:44:55").unwrap();
+        let name = NameString::new();
+        let matches = Vec::new();
+        let msg = DeviceMessage::BleScan {
+            mac: &mac,
+            mac_randomized: false,
+            name: &name,
+            rssi: -70,
+            rssi_avg: None,
+            zone: Proximity::Near,
+            approaching: false,
+            dist_m: None,
+            uuid: None,
+            mfr: 0x004C,
+            phy: None,
+            severity: Severity::Medium,
+            category: Category::Other,
+            device_class: DeviceClass::Unknown,
+            confidence: Confidence::Medium,
+            matches: &matches,
+            rules: &[],
+            truncated: false,
+            label: Some("Mom's AirTag"),
+            device_id: None,
+            remote_id: None,
+            findmy: None,
+            mine: false,
+            quiet: false,
+            loc: None,
+            location_stale: false,
+            ts: 1,
+            ts_source: TsSource::Uptime,
+        };
+        let mut buf = [0u8; 256];
+        let len = serde_json_core::to_slice(&msg, &mut buf).unwrap();
+        let json = core::str::from_utf8(&buf[..len]).unwrap();
+        assert!(json.contains(r#""label":"Mom's AirTag""#));
+        assert!(!json.contains("quiet"));
+    }
+
+    #[test]
+    fn serialize_device_id_and_findmy_state() {
+        let mac = MacString::try_from("5A:11:22:33:44:55").unwrap();
+        let name = NameString::new();
+        let matches = Vec::new();
+        let msg = DeviceMessage::BleScan {
+            mac: &mac,
+            mac_randomized: true,
+            name: &name,
+            rssi: -70,
+            rssi_avg: None,
+            zone: Proximity::Near,
+            approaching: false,
+            dist_m: None,
+            uuid: None,
+            mfr: 0x004C,
+            phy: None,
+            severity: Severity::Medium,
+            category: Category::Tracker,
+            device_class: DeviceClass::PersonalTracker,
+            confidence: Confidence::Medium,
+            matches: &matches,
+            rules: &[],
+            truncated: false,
+            label: None,
+            device_id: Some(12),
+            remote_id: None,
+            findmy: Some(FindMy {
+                state: FindMyState::Separated,
+                battery: Some(FindMyBattery::Low),
+            }),
+            mine: false,
+            quiet: false,
+            loc: None,
+            location_stale: false,
+            ts: 1,
+            ts_source: TsSource::Uptime,
+        };
+        let mut buf = [0u8; 512];
+        let len = serde_json_core::to_slice(&msg, &mut buf).unwrap();
+        let json = core::str::from_utf8(&buf[..len]).unwrap();
+        assert!(json
+            .contains(r#""device_id":12,"findmy":{"state":"separated","battery":"low"},"ts":1"#));
+    }
+
+    #[test]
+    fn serialize_quiet_flag_when_set() {
+        let mac = MacString::try_from("7C:11:22:33:44:55").unwrap();
+        let name = NameString::new();
+        let matches = Vec::new();
+        let msg = DeviceMessage::BtClassicScan {
+            mac: &mac,
+            name: &name,
+            rssi: -70,
+            rssi_avg: None,
+            zone: Proximity::Near,
+            approaching: false,
+            cod: 0x240404,
+            severity: Severity::Medium,
+            category: Category::Other,
+            device_class: DeviceClass::Unknown,
+            confidence: Confidence::Medium,
+            matches: &matches,
+            rules: &[],
+            truncated: false,
+            label: None,
+            quiet: true,
+            loc: None,
+            location_stale: false,
+            ts: 1,
+            ts_source: TsSource::Uptime,
+        };
+        let mut buf = [0u8; 256];
+        let len = serde_json_core::to_slice(&msg, &mut buf).unwrap();
+        let json = core::str::from_utf8(&buf[..len]).unwrap();
+        assert!(json.contains(r#""quiet":true"#));
     }
 
     // ── Version constant ────────────────────────────────────────────
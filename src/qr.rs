@@ -0,0 +1,697 @@
+/// QR code encoder for the on-device detection screen.
+///
+/// Byte-mode symbols at error-correction level M (15% recovery — enough for
+/// a phone camera pointed at a small LCD), versions 1–[`MAX_VERSION`]. The
+/// whole symbol is a bitmap of one `u64` per row, so encoding needs no heap
+/// and about 1 KiB of stack. Follows ISO/IEC 18004: Reed–Solomon over
+/// GF(2^8), block interleaving, and the lowest-penalty of the eight masks.
+use core::fmt;
+
+/// Largest version encoded — 57×57 modules, up to 213 bytes. Denser symbols
+/// would drop below two pixels per module on a 135 px display.
+pub const MAX_VERSION: u8 = 10;
+
+/// Most bytes a symbol can carry
+pub const MAX_DATA_LEN: usize = 213;
+
+/// Modules per side of the largest symbol
+const MAX_SIZE: usize = 17 + 4 * MAX_VERSION as usize;
+
+/// Total codewords (data + ECC) in the largest symbol
+const MAX_CODEWORDS: usize = 346;
+
+/// ECC codewords per block at level M, by version
+const ECC_PER_BLOCK: [u8; MAX_VERSION as usize + 1] = [0, 10, 16, 26, 18, 24, 16, 18, 22, 22, 26];
+
+/// Error-correction blocks at level M, by version
+const NUM_BLOCKS: [u8; MAX_VERSION as usize + 1] = [0, 1, 1, 1, 2, 2, 4, 4, 4, 5, 5];
+
+/// Format-information bits for level M
+const ECC_LEVEL_M: u16 = 0b00;
+
+/// A QR code symbol. Module `(x, y)` is dark when [`QrCode::is_dark`].
+#[derive(Clone, PartialEq, Eq)]
+pub struct QrCode {
+    version: u8,
+    size: u8,
+    mask: u8,
+    /// Bit `x` of row `y` set = dark module
+    modules: [u64; MAX_SIZE],
+}
+
+impl QrCode {
+    /// Encode `data` in byte mode in the smallest version that fits.
+    /// Returns `None` if it exceeds [`MAX_DATA_LEN`].
+    pub fn encode(data: &[u8]) -> Option<Self> {
+        let version = (1..=MAX_VERSION).find(|&v| {
+            let header_bits = 4 + char_count_bits(v);
+            header_bits + data.len() * 8 <= num_data_codewords(v) * 8
+        })?;
+
+        let mut codewords = [0u8; MAX_CODEWORDS];
+        let data_len = num_data_codewords(version);
+        let mut bits = BitWriter::new(&mut codewords[..data_len]);
+        bits.push(0b0100, 4); // byte mode
+        bits.push(data.len() as u32, char_count_bits(version));
+        for &byte in data {
+            bits.push(byte as u32, 8);
+        }
+        // Terminator, then pad to a byte and fill with alternating pad codewords
+        let terminator = (data_len * 8 - bits.len).min(4);
+        bits.push(0, terminator);
+        bits.push(0, (8 - bits.len % 8) % 8);
+        for pad in [0xEC, 0x11].iter().cycle() {
+            if bits.len >= data_len * 8 {
+                break;
+            }
+            bits.push(*pad, 8);
+        }
+
+        let mut interleaved = [0u8; MAX_CODEWORDS];
+        let total = add_ecc_and_interleave(version, &codewords[..data_len], &mut interleaved);
+
+        let size = 17 + 4 * version;
+        let mut qr = Self {
+            version,
+            size,
+            mask: 0,
+            modules: [0; MAX_SIZE],
+        };
+        let mut function = [0u64; MAX_SIZE];
+        qr.draw_function_patterns(&mut function);
+        qr.draw_codewords(&interleaved[..total], &function);
+
+        // Pick the mask with the lowest penalty; masks are XOR so applying
+        // one twice undoes it
+        let mut best = (0, i32::MAX);
+        for mask in 0..8 {
+            qr.apply_mask(mask, &function);
+            qr.draw_format_bits(mask, &mut function);
+            let penalty = qr.penalty();
+            if penalty < best.1 {
+                best = (mask, penalty);
+            }
+            qr.apply_mask(mask, &function);
+        }
+        qr.mask = best.0;
+        qr.apply_mask(best.0, &function);
+        qr.draw_format_bits(best.0, &mut function);
+        Some(qr)
+    }
+
+    /// Symbol version (1–[`MAX_VERSION`])
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// Modules per side, excluding the quiet zone
+    pub fn size(&self) -> u8 {
+        self.size
+    }
+
+    /// Mask pattern chosen (0–7)
+    pub fn mask(&self) -> u8 {
+        self.mask
+    }
+
+    /// Whether module `(x, y)` is dark. Out-of-range coordinates are light,
+    /// like the quiet zone.
+    pub fn is_dark(&self, x: u8, y: u8) -> bool {
+        x < self.size && y < self.size && self.modules[y as usize] >> x & 1 != 0
+    }
+
+    fn set(&mut self, x: usize, y: usize, dark: bool) {
+        if dark {
+            self.modules[y] |= 1 << x;
+        } else {
+            self.modules[y] &= !(1 << x);
+        }
+    }
+
+    fn set_function(&mut self, function: &mut [u64; MAX_SIZE], x: usize, y: usize, dark: bool) {
+        self.set(x, y, dark);
+        function[y] |= 1 << x;
+    }
+
+    fn draw_function_patterns(&mut self, function: &mut [u64; MAX_SIZE]) {
+        let size = self.size as usize;
+
+        // Timing patterns
+        for i in 0..size {
+            self.set_function(function, 6, i, i % 2 == 0);
+            self.set_function(function, i, 6, i % 2 == 0);
+        }
+
+        // Finder patterns with separators, in three corners
+        for (cx, cy) in [(3, 3), (size - 4, 3), (3, size - 4)] {
+            for dy in -4i32..=4 {
+                for dx in -4i32..=4 {
+                    let (x, y) = (cx as i32 + dx, cy as i32 + dy);
+                    if (0..size as i32).contains(&x) && (0..size as i32).contains(&y) {
+                        let dist = dx.abs().max(dy.abs());
+                        self.set_function(function, x as usize, y as usize, dist != 2 && dist != 4);
+                    }
+                }
+            }
+        }
+
+        // Alignment patterns, except where they would overlap finders
+        let (positions, count) = alignment_positions(self.version);
+        for i in 0..count {
+            for j in 0..count {
+                let corner = (i == 0 && (j == 0 || j == count - 1)) || (i == count - 1 && j == 0);
+                if corner {
+                    continue;
+                }
+                let (cx, cy) = (positions[i] as i32, positions[j] as i32);
+                for dy in -2i32..=2 {
+                    for dx in -2i32..=2 {
+                        let dark = dx.abs().max(dy.abs()) != 1;
+                        self.set_function(function, (cx + dx) as usize, (cy + dy) as usize, dark);
+                    }
+                }
+            }
+        }
+
+        // Reserve the format areas; real bits are drawn once the mask is known
+        self.draw_format_bits(0, function);
+
+        // Version information (version 7 and up)
+        if self.version >= 7 {
+            let mut rem = self.version as u32;
+            for _ in 0..12 {
+                rem = (rem << 1) ^ ((rem >> 11) * 0x1F25);
+            }
+            let bits = (self.version as u32) << 12 | rem;
+            for i in 0..18 {
+                let dark = bits >> i & 1 != 0;
+                let a = size - 11 + i % 3;
+                let b = i / 3;
+                self.set_function(function, a, b, dark);
+                self.set_function(function, b, a, dark);
+            }
+        }
+    }
+
+    fn draw_format_bits(&mut self, mask: u8, function: &mut [u64; MAX_SIZE]) {
+        let data = ECC_LEVEL_M << 3 | mask as u16;
+        let mut rem = data;
+        for _ in 0..10 {
+            rem = (rem << 1) ^ ((rem >> 9) * 0x537);
+        }
+        let bits = (data << 10 | rem) ^ 0x5412;
+        let bit = |i: usize| bits >> i & 1 != 0;
+        let size = self.size as usize;
+
+        // First copy, around the top-left finder
+        for i in 0..6 {
+            self.set_function(function, 8, i, bit(i));
+        }
+        self.set_function(function, 8, 7, bit(6));
+        self.set_function(function, 8, 8, bit(7));
+        self.set_function(function, 7, 8, bit(8));
+        for i in 9..15 {
+            self.set_function(function, 14 - i, 8, bit(i));
+        }
+
+        // Second copy, split between the other two finders
+        for i in 0..8 {
+            self.set_function(function, size - 1 - i, 8, bit(i));
+        }
+        for i in 8..15 {
+            self.set_function(function, 8, size - 15 + i, bit(i));
+        }
+        // Always-dark module
+        self.set_function(function, 8, size - 8, true);
+    }
+
+    /// Place codewords in the two-column zigzag from the bottom-right corner.
+    fn draw_codewords(&mut self, codewords: &[u8], function: &[u64; MAX_SIZE]) {
+        let size = self.size as usize;
+        let total_bits = codewords.len() * 8;
+        let mut i = 0;
+        let mut right = size as i32 - 1;
+        while right >= 1 {
+            // Skip the vertical timing pattern
+            if right == 6 {
+                right = 5;
+            }
+            let upward = (right + 1) & 2 == 0;
+            for vert in 0..size {
+                let y = if upward { size - 1 - vert } else { vert };
+                for j in 0..2 {
+                    let x = right as usize - j;
+                    if function[y] >> x & 1 == 0 && i < total_bits {
+                        let dark = codewords[i / 8] >> (7 - i % 8) & 1 != 0;
+                        self.set(x, y, dark);
+                        i += 1;
+                    }
+                }
+            }
+            right -= 2;
+        }
+    }
+
+    fn apply_mask(&mut self, mask: u8, function: &[u64; MAX_SIZE]) {
+        let size = self.size as usize;
+        for (y, reserved) in function.iter().enumerate().take(size) {
+            for x in 0..size {
+                let invert = match mask {
+                    0 => (x + y) % 2 == 0,
+                    1 => y % 2 == 0,
+                    2 => x % 3 == 0,
+                    3 => (x + y) % 3 == 0,
+                    4 => (x / 3 + y / 2) % 2 == 0,
+                    5 => x * y % 2 + x * y % 3 == 0,
+                    6 => (x * y % 2 + x * y % 3) % 2 == 0,
+                    _ => ((x + y) % 2 + x * y % 3) % 2 == 0,
+                };
+                if invert && reserved >> x & 1 == 0 {
+                    self.modules[y] ^= 1 << x;
+                }
+            }
+        }
+    }
+
+    /// Penalty score from the spec's four rules: runs, 2×2 blocks,
+    /// finder-like patterns, and dark/light balance.
+    fn penalty(&self) -> i32 {
+        let size = self.size;
+        let mut result = 0;
+
+        for transpose in [false, true] {
+            for a in 0..size {
+                let mut run_color = false;
+                let mut run_len = 0;
+                let mut history = RunHistory::new(size);
+                for b in 0..size {
+                    let dark = if transpose {
+                        self.is_dark(a, b)
+                    } else {
+                        self.is_dark(b, a)
+                    };
+                    if dark == run_color {
+                        run_len += 1;
+                        if run_len == 5 {
+                            result += 3;
+                        } else if run_len > 5 {
+                            result += 1;
+                        }
+                    } else {
+                        history.push(run_len);
+                        if !run_color {
+                            result += history.finder_patterns() * 40;
+                        }
+                        run_color = dark;
+                        run_len = 1;
+                    }
+                }
+                result += history.terminate(run_color, run_len) * 40;
+            }
+        }
+
+        for y in 0..size - 1 {
+            for x in 0..size - 1 {
+                let dark = self.is_dark(x, y);
+                if dark == self.is_dark(x + 1, y)
+                    && dark == self.is_dark(x, y + 1)
+                    && dark == self.is_dark(x + 1, y + 1)
+                {
+                    result += 3;
+                }
+            }
+        }
+
+        let dark: i32 = self.modules.iter().map(|row| row.count_ones() as i32).sum();
+        let total = size as i32 * size as i32;
+        let k = ((dark * 20 - total * 10).abs() + total - 1) / total - 1;
+        result + k * 10
+    }
+}
+
+impl fmt::Debug for QrCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "QrCode v{} mask {}", self.version, self.mask)?;
+        for y in 0..self.size {
+            for x in 0..self.size {
+                f.write_str(if self.is_dark(x, y) { "##" } else { "  " })?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Recent run lengths along a row or column, for the finder-like penalty
+struct RunHistory {
+    runs: [i32; 7],
+    size: i32,
+}
+
+impl RunHistory {
+    fn new(size: u8) -> Self {
+        Self {
+            runs: [0; 7],
+            size: size as i32,
+        }
+    }
+
+    fn push(&mut self, mut run_len: i32) {
+        // The light border before the symbol counts as part of the first run
+        if self.runs[0] == 0 {
+            run_len += self.size;
+        }
+        self.runs.copy_within(0..6, 1);
+        self.runs[0] = run_len;
+    }
+
+    /// Number of 1:1:3:1:1 dark/light patterns with 4 light modules on
+    /// either side, ending at the latest run
+    fn finder_patterns(&self) -> i32 {
+        let r = &self.runs;
+        let n = r[1];
+        let core = n > 0 && r[2] == n && r[3] == n * 3 && r[4] == n && r[5] == n;
+        (core && r[0] >= n * 4 && r[6] >= n) as i32 + (core && r[6] >= n * 4 && r[0] >= n) as i32
+    }
+
+    /// Close the line, counting the light border after the symbol as a run
+    fn terminate(&mut self, run_color: bool, mut run_len: i32) -> i32 {
+        if run_color {
+            self.push(run_len);
+            run_len = 0;
+        }
+        self.push(run_len + self.size);
+        self.finder_patterns()
+    }
+}
+
+/// Appends bits MSB-first into a byte buffer
+struct BitWriter<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+}
+
+impl<'a> BitWriter<'a> {
+    fn new(buf: &'a mut [u8]) -> Self {
+        Self { buf, len: 0 }
+    }
+
+    fn push(&mut self, value: u32, count: usize) {
+        for i in (0..count).rev() {
+            if value >> i & 1 != 0 {
+                self.buf[self.len / 8] |= 0x80 >> (self.len % 8);
+            }
+            self.len += 1;
+        }
+    }
+}
+
+/// Width of the byte-mode character count field
+fn char_count_bits(version: u8) -> usize {
+    if version < 10 {
+        8
+    } else {
+        16
+    }
+}
+
+/// Modules available for data and ECC after function patterns
+fn num_raw_data_modules(version: u8) -> usize {
+    let v = version as usize;
+    let mut result = (16 * v + 128) * v + 64;
+    if v >= 2 {
+        let num_align = v / 7 + 2;
+        result -= (25 * num_align - 10) * num_align - 55;
+        if v >= 7 {
+            result -= 36;
+        }
+    }
+    result
+}
+
+fn num_data_codewords(version: u8) -> usize {
+    let v = version as usize;
+    num_raw_data_modules(version) / 8 - ECC_PER_BLOCK[v] as usize * NUM_BLOCKS[v] as usize
+}
+
+/// Centre coordinates of alignment patterns along each axis
+fn alignment_positions(version: u8) -> ([u8; 7], usize) {
+    let mut positions = [0u8; 7];
+    if version == 1 {
+        return (positions, 0);
+    }
+    let count = version as usize / 7 + 2;
+    let size = 17 + 4 * version as usize;
+    let step = (version as usize * 8 + count * 3 + 5) / (count * 4 - 4) * 2;
+    positions[0] = 6;
+    for (i, position) in positions.iter_mut().enumerate().take(count).skip(1) {
+        *position = (size - 7 - (count - 1 - i) * step) as u8;
+    }
+    (positions, count)
+}
+
+/// Split data into blocks, append each block's Reed–Solomon ECC, and
+/// interleave the result into `out`. Returns the total codeword count.
+fn add_ecc_and_interleave(version: u8, data: &[u8], out: &mut [u8; MAX_CODEWORDS]) -> usize {
+    let v = version as usize;
+    let num_blocks = NUM_BLOCKS[v] as usize;
+    let ecc_len = ECC_PER_BLOCK[v] as usize;
+    let raw_codewords = num_raw_data_modules(version) / 8;
+    let num_short = num_blocks - raw_codewords % num_blocks;
+    let short_len = raw_codewords / num_blocks;
+    let short_data_len = short_len - ecc_len;
+
+    let mut divisor = [0u8; 32];
+    rs_divisor(&mut divisor[..ecc_len]);
+
+    // Data codewords, column by column across blocks (long blocks have
+    // one extra data codeword at the end)
+    let block_start = |b: usize| b * short_data_len + b.saturating_sub(num_short);
+    let mut n = 0;
+    for i in 0..=short_data_len {
+        for b in 0..num_blocks {
+            if i < short_data_len || b >= num_short {
+                out[n] = data[block_start(b) + i];
+                n += 1;
+            }
+        }
+    }
+
+    // ECC codewords, also interleaved
+    let mut ecc = [[0u8; 32]; 8];
+    for (b, block_ecc) in ecc.iter_mut().enumerate().take(num_blocks) {
+        let len = short_data_len + usize::from(b >= num_short);
+        let start = block_start(b);
+        rs_remainder(
+            &data[start..start + len],
+            &divisor[..ecc_len],
+            &mut block_ecc[..ecc_len],
+        );
+    }
+    for i in 0..ecc_len {
+        for block_ecc in ecc.iter().take(num_blocks) {
+            out[n] = block_ecc[i];
+            n += 1;
+        }
+    }
+    n
+}
+
+/// Reed–Solomon generator polynomial of degree `out.len()`, highest
+/// coefficient (always 1) omitted
+fn rs_divisor(out: &mut [u8]) {
+    let degree = out.len();
+    out.fill(0);
+    out[degree - 1] = 1;
+    let mut root = 1u8;
+    for _ in 0..degree {
+        for j in 0..degree {
+            out[j] = gf_mul(out[j], root);
+            if j + 1 < degree {
+                out[j] ^= out[j + 1];
+            }
+        }
+        root = gf_mul(root, 0x02);
+    }
+}
+
+/// ECC codewords: remainder of `data` divided by the generator
+fn rs_remainder(data: &[u8], divisor: &[u8], out: &mut [u8]) {
+    out.fill(0);
+    for &byte in data {
+        let factor = byte ^ out[0];
+        out.copy_within(1.., 0);
+        let last = out.len() - 1;
+        out[last] = 0;
+        for (o, &d) in out.iter_mut().zip(divisor) {
+            *o ^= gf_mul(d, factor);
+        }
+    }
+}
+
+/// Multiplication in GF(2^8) modulo x^8 + x^4 + x^3 + x^2 + 1
+fn gf_mul(x: u8, y: u8) -> u8 {
+    let mut z = 0u8;
+    for i in (0..8).rev() {
+        z = (z << 1) ^ ((z >> 7) * 0x1D);
+        z ^= ((y >> i) & 1) * x;
+    }
+    z
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Read the 15 format bits back from the top-left copy
+    fn format_bits(qr: &QrCode) -> u16 {
+        let mut bits = 0u16;
+        let mut set = |i: usize, dark: bool| bits |= (dark as u16) << i;
+        for i in 0..6 {
+            set(i, qr.is_dark(8, i as u8));
+        }
+        set(6, qr.is_dark(8, 7));
+        set(7, qr.is_dark(8, 8));
+        set(8, qr.is_dark(7, 8));
+        for i in 9..15 {
+            set(i, qr.is_dark(14 - i as u8, 8));
+        }
+        bits
+    }
+
+    /// Undo the mask and read codewords back out of the zigzag
+    fn read_codewords(qr: &QrCode, out: &mut [u8]) {
+        let mut unmasked = qr.clone();
+        let mut function = [0u64; MAX_SIZE];
+        let mut scratch = qr.clone();
+        scratch.draw_function_patterns(&mut function);
+        unmasked.apply_mask(qr.mask, &function);
+
+        let size = qr.size as usize;
+        let mut i = 0;
+        let mut right = size as i32 - 1;
+        while right >= 1 {
+            if right == 6 {
+                right = 5;
+            }
+            let upward = (right + 1) & 2 == 0;
+            for vert in 0..size {
+                let y = if upward { size - 1 - vert } else { vert };
+                for j in 0..2 {
+                    let x = right as usize - j;
+                    if function[y] >> x & 1 == 0 && i < out.len() * 8 {
+                        if unmasked.is_dark(x as u8, y as u8) {
+                            out[i / 8] |= 0x80 >> (i % 8);
+                        }
+                        i += 1;
+                    }
+                }
+            }
+            right -= 2;
+        }
+    }
+
+    // ── Reed–Solomon ────────────────────────────────────────────────
+
+    #[test]
+    fn rs_matches_spec_example() {
+        // ISO/IEC 18004 Annex I: "01234567" as version 1-M
+        let data = [
+            0x10, 0x20, 0x0C, 0x56, 0x61, 0x80, 0xEC, 0x11, 0xEC, 0x11, 0xEC, 0x11, 0xEC, 0x11,
+            0xEC, 0x11,
+        ];
+        let mut divisor = [0u8; 10];
+        rs_divisor(&mut divisor);
+        let mut ecc = [0u8; 10];
+        rs_remainder(&data, &divisor, &mut ecc);
+        assert_eq!(
+            ecc,
+            [0xA5, 0x24, 0xD4, 0xC1, 0xED, 0x36, 0xC7, 0x87, 0x2C, 0x55]
+        );
+    }
+
+    #[test]
+    fn capacity_table_matches_spec() {
+        // Data codewords at level M, versions 1–10
+        let expected = [16, 28, 44, 64, 86, 108, 124, 154, 182, 216];
+        for (v, &n) in (1..=MAX_VERSION).zip(&expected) {
+            assert_eq!(num_data_codewords(v), n, "version {}", v);
+        }
+        assert_eq!(num_raw_data_modules(MAX_VERSION) / 8, MAX_CODEWORDS);
+    }
+
+    // ── Symbol layout ───────────────────────────────────────────────
+
+    #[test]
+    fn picks_smallest_version() {
+        assert_eq!(QrCode::encode(b"hello").unwrap().version(), 1);
+        assert_eq!(QrCode::encode(&[b'x'; 14]).unwrap().version(), 1);
+        assert_eq!(QrCode::encode(&[b'x'; 15]).unwrap().version(), 2);
+        let largest = QrCode::encode(&[b'x'; MAX_DATA_LEN]).unwrap();
+        assert_eq!(largest.version(), MAX_VERSION);
+        assert_eq!(largest.size(), 57);
+        assert!(QrCode::encode(&[b'x'; MAX_DATA_LEN + 1]).is_none());
+    }
+
+    #[test]
+    fn draws_finders_and_timing() {
+        let qr = QrCode::encode(b"AIRHOUND").unwrap();
+        let last = qr.size() - 1;
+        for (x, y) in [(0, 0), (last, 0), (0, last)] {
+            assert!(qr.is_dark(x, y));
+        }
+        // Finder centre and its light ring
+        assert!(qr.is_dark(3, 3));
+        assert!(!qr.is_dark(1, 1));
+        assert!(!qr.is_dark(7, 7));
+        // Timing row alternates between the finders
+        for x in 8..qr.size() - 8 {
+            assert_eq!(qr.is_dark(x, 6), x % 2 == 0);
+        }
+        assert!(qr.is_dark(8, qr.size() - 8));
+        assert!(!qr.is_dark(qr.size(), 0));
+    }
+
+    #[test]
+    fn format_bits_carry_level_and_mask() {
+        let qr = QrCode::encode(b"https://deflock.me").unwrap();
+        let bits = format_bits(&qr) ^ 0x5412;
+        assert_eq!(bits >> 13, ECC_LEVEL_M);
+        assert_eq!((bits >> 10 & 7) as u8, qr.mask());
+    }
+
+    #[test]
+    fn codewords_round_trip() {
+        let payload = b"AIRHOUND wifi B4:1E:52:AA:BB:CC -65dBm";
+        let qr = QrCode::encode(payload).unwrap();
+        assert_eq!(qr.version(), 3); // single block, no interleaving
+
+        let mut read = [0u8; 70];
+        read_codewords(&qr, &mut read);
+        // Byte mode, 8-bit length, then the payload shifted by 4 bits
+        assert_eq!(read[0], 0x40 | (payload.len() >> 4) as u8);
+        for (i, &byte) in payload.iter().enumerate() {
+            let got = read[i + 1] << 4 | read[i + 2] >> 4;
+            assert_eq!(got, byte);
+        }
+
+        let mut divisor = [0u8; 26];
+        rs_divisor(&mut divisor);
+        let mut ecc = [0u8; 26];
+        rs_remainder(&read[..44], &divisor, &mut ecc);
+        assert_eq!(ecc, read[44..]);
+    }
+
+    #[test]
+    fn version_info_drawn_from_seven() {
+        let qr = QrCode::encode(&[b'x'; 110]).unwrap();
+        assert_eq!(qr.version(), 7);
+        // Version 7 information is 0x07C94, bit 0 at (size-11, 0)
+        let bits = 0x07C94u32;
+        for i in 0..18u8 {
+            let dark = bits >> i & 1 != 0;
+            assert_eq!(qr.is_dark(qr.size() - 11 + i % 3, i / 3), dark);
+            assert_eq!(qr.is_dark(i / 3, qr.size() - 11 + i % 3), dark);
+        }
+    }
+}
@@ -0,0 +1,181 @@
+/// ASTM F3411 drone Remote ID broadcasts.
+///
+/// Drones flying under FAA and EU Remote ID rules broadcast their identity and
+/// position as 25-byte messages: one per BLE advertisement (service data for
+/// [`SERVICE_UUID`]), or bundled into a message pack in a WiFi beacon vendor
+/// element ([`WIFI_OUI`]). Only the Basic ID and Location messages are decoded;
+/// the scanner attaches the result to the event so scan messages can name the
+/// drone. WiFi NAN broadcasts are action frames and not parsed.
+use heapless::String;
+use serde::Serialize;
+
+/// 16-bit service UUID of Remote ID service data (ASTM International)
+pub const SERVICE_UUID: u16 = 0xFFFA;
+
+/// OUI of the Remote ID vendor element in WiFi beacons (ASTM International)
+pub const WIFI_OUI: [u8; 3] = [0xFA, 0x0B, 0xBC];
+
+/// Application code / vendor type marking Open Drone ID payloads
+pub const APP_CODE: u8 = 0x0D;
+
+/// Length of every Remote ID message
+pub const MESSAGE_LEN: usize = 25;
+
+/// Longest UAS ID (serial number, registration or session ID)
+pub const MAX_UAS_ID_LEN: usize = 20;
+
+const MSG_BASIC_ID: u8 = 0x0;
+const MSG_LOCATION: u8 = 0x1;
+const MSG_PACK: u8 = 0xF;
+
+/// What one broadcast said about a drone. BLE advertisements rotate through
+/// message types, so a single one usually fills in only some fields.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RemoteId {
+    /// UAS ID from a Basic ID message
+    #[serde(rename = "id", skip_serializing_if = "str::is_empty")]
+    pub uas_id: String<MAX_UAS_ID_LEN>,
+    /// Latitude from a Location message, in 1e-7 degrees
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lat: Option<i32>,
+    /// Longitude from a Location message, in 1e-7 degrees
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lon: Option<i32>,
+}
+
+impl RemoteId {
+    /// Parse BLE service data following the UUID, or a WiFi vendor element
+    /// body following the OUI: `[app_code, counter, message or pack]`.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        match data {
+            [APP_CODE, _counter, message @ ..] => Self::from_message(message),
+            _ => None,
+        }
+    }
+
+    /// Decode a single message or a message pack.
+    fn from_message(message: &[u8]) -> Option<Self> {
+        let mut id = Self::default();
+        match *message.first()? >> 4 {
+            // Pack header: [type|version, message size, count, messages...]
+            MSG_PACK => {
+                let [_, size, count, messages @ ..] = message else {
+                    return None;
+                };
+                if *size as usize != MESSAGE_LEN {
+                    return None;
+                }
+                for message in messages.chunks_exact(MESSAGE_LEN).take(*count as usize) {
+                    id.apply(message);
+                }
+            }
+            _ => id.apply(message),
+        }
+        Some(id)
+    }
+
+    /// Fill in the fields carried by one message.
+    fn apply(&mut self, message: &[u8]) {
+        let Some(message) = message.get(..MESSAGE_LEN) else {
+            return;
+        };
+        match message[0] >> 4 {
+            // [type|version, id_type|ua_type, uas_id: 20 bytes, reserved]
+            MSG_BASIC_ID => {
+                let raw = &message[2..2 + MAX_UAS_ID_LEN];
+                let end = raw.iter().position(|&b| b == 0).unwrap_or(raw.len());
+                if let Ok(uas_id) = core::str::from_utf8(&raw[..end]) {
+                    self.uas_id = String::try_from(uas_id).unwrap_or_default();
+                }
+            }
+            // [type|version, status|flags, direction, speed, vertical speed,
+            //  lat: i32, lon: i32, ...]; 0/0 means unknown
+            MSG_LOCATION => {
+                let lat = i32::from_le_bytes([message[5], message[6], message[7], message[8]]);
+                let lon = i32::from_le_bytes([message[9], message[10], message[11], message[12]]);
+                if (lat, lon) != (0, 0) {
+                    self.lat = Some(lat);
+                    self.lon = Some(lon);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn basic_id(uas_id: &str) -> [u8; MESSAGE_LEN] {
+        let mut message = [0u8; MESSAGE_LEN];
+        message[0] = (MSG_BASIC_ID << 4) | 2;
+        message[1] = 0x12; // serial number, helicopter or multirotor
+        message[2..2 + uas_id.len()].copy_from_slice(uas_id.as_bytes());
+        message
+    }
+
+    fn location(lat: i32, lon: i32) -> [u8; MESSAGE_LEN] {
+        let mut message = [0u8; MESSAGE_LEN];
+        message[0] = (MSG_LOCATION << 4) | 2;
+        message[5..9].copy_from_slice(&lat.to_le_bytes());
+        message[9..13].copy_from_slice(&lon.to_le_bytes());
+        message
+    }
+
+    // ── Decoding ────────────────────────────────────────────────────
+
+    #[test]
+    fn ble_basic_id_and_location() {
+        let mut data = [APP_CODE, 7].to_vec();
+        data.extend_from_slice(&basic_id("1596F12345678"));
+        let id = RemoteId::parse(&data).unwrap();
+        assert_eq!(id.uas_id.as_str(), "1596F12345678");
+        assert_eq!(id.lat, None);
+
+        let mut data = [APP_CODE, 8].to_vec();
+        data.extend_from_slice(&location(407_128_000, -740_060_000));
+        let id = RemoteId::parse(&data).unwrap();
+        assert_eq!((id.lat, id.lon), (Some(407_128_000), Some(-740_060_000)));
+        assert!(id.uas_id.is_empty());
+
+        // Unknown position
+        let mut data = [APP_CODE, 9].to_vec();
+        data.extend_from_slice(&location(0, 0));
+        assert_eq!(RemoteId::parse(&data).unwrap().lat, None);
+    }
+
+    #[test]
+    fn wifi_message_pack() {
+        let mut data = [APP_CODE, 1, (MSG_PACK << 4) | 2, MESSAGE_LEN as u8, 2].to_vec();
+        data.extend_from_slice(&basic_id("DRONE-01"));
+        data.extend_from_slice(&location(1, 2));
+        let id = RemoteId::parse(&data).unwrap();
+        assert_eq!(id.uas_id.as_str(), "DRONE-01");
+        assert_eq!((id.lat, id.lon), (Some(1), Some(2)));
+
+        // Wrong message size
+        data[3] = 24;
+        assert!(RemoteId::parse(&data).is_none());
+    }
+
+    #[test]
+    fn rejects_other_payloads() {
+        assert!(RemoteId::parse(&[]).is_none());
+        assert!(RemoteId::parse(&[0x0C, 0, 0x02]).is_none());
+        // Truncated message decodes to nothing
+        let id = RemoteId::parse(&[APP_CODE, 0, 0x02, 0x12, b'A']).unwrap();
+        assert_eq!(id, RemoteId::default());
+    }
+
+    #[test]
+    fn serializes_known_fields_only() {
+        let mut buf = [0u8; 64];
+        let id = RemoteId {
+            uas_id: String::try_from("X1").unwrap(),
+            ..Default::default()
+        };
+        let len = serde_json_core::to_slice(&id, &mut buf).unwrap();
+        assert_eq!(&buf[..len], br#"{"id":"X1"}"#);
+    }
+}
@@ -0,0 +1,265 @@
+/// Per-client output routing.
+///
+/// Each BLE connection holds a [`Subscription`] — a message-type mask and a
+/// minimum severity — negotiated with a `subscribe` command. Serialized
+/// messages are classified straight from their NDJSON bytes (serde always
+/// writes the `type` tag first), so the output path can stay a stream of
+/// [`crate::protocol::MsgBuffer`]s.
+///
+/// Device message types, as carried in the `type` tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageKind {
+    WiFi,
+    Ble,
+    BtClassic,
+    Direction,
+    Status,
+    Peer,
+    Health,
+    Watch,
+    WatchLost,
+    Downgrade,
+    Stats,
+    Correlated,
+    ClientProfile,
+    ChannelStats,
+    FollowingAlert,
+    LingeringAlert,
+}
+
+impl MessageKind {
+    pub const ALL: [MessageKind; 16] = [
+        MessageKind::WiFi,
+        MessageKind::Ble,
+        MessageKind::BtClassic,
+        MessageKind::Direction,
+        MessageKind::Status,
+        MessageKind::Peer,
+        MessageKind::Health,
+        MessageKind::Watch,
+        MessageKind::WatchLost,
+        MessageKind::Downgrade,
+        MessageKind::Stats,
+        MessageKind::Correlated,
+        MessageKind::ClientProfile,
+        MessageKind::ChannelStats,
+        MessageKind::FollowingAlert,
+        MessageKind::LingeringAlert,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MessageKind::WiFi => "wifi",
+            MessageKind::Ble => "ble",
+            MessageKind::BtClassic => "bt_classic",
+            MessageKind::Direction => "direction",
+            MessageKind::Status => "status",
+            MessageKind::Peer => "peer",
+            MessageKind::Health => "health",
+            MessageKind::Watch => "watch",
+            MessageKind::WatchLost => "watch_lost",
+            MessageKind::Downgrade => "downgrade",
+            MessageKind::Stats => "stats",
+            MessageKind::Correlated => "correlated",
+            MessageKind::ClientProfile => "client_profile",
+            MessageKind::ChannelStats => "channel_stats",
+            MessageKind::FollowingAlert => "following_alert",
+            MessageKind::LingeringAlert => "lingering_alert",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.as_str() == name)
+    }
+
+    /// Classify a serialized message by its leading `{"type":"..."` tag.
+    pub fn of_message(msg: &[u8]) -> Option<Self> {
+        let rest = msg.strip_prefix(br#"{"type":""#)?;
+        let end = rest.iter().position(|&b| b == b'"')?;
+        Self::from_name(core::str::from_utf8(&rest[..end]).ok()?)
+    }
+
+    const fn bit(self) -> u16 {
+        1 << self as u16
+    }
+}
+
+/// Severity of a serialized message: its top-level `"severity"` value, or 0
+/// for messages that don't carry one.
+pub fn message_severity(msg: &[u8]) -> u8 {
+    const KEY: &[u8] = br#""severity":"#;
+    let Some(start) = msg.windows(KEY.len()).position(|w| w == KEY) else {
+        return 0;
+    };
+    msg[start + KEY.len()..]
+        .iter()
+        .take_while(|b| b.is_ascii_digit())
+        .fold(0u16, |acc, &b| {
+            (acc * 10 + (b - b'0') as u16).min(u8::MAX as u16)
+        }) as u8
+}
+
+/// What one client wants to receive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Subscription {
+    types: u16,
+    pub min_severity: u8,
+}
+
+impl Subscription {
+    /// Everything — the default for a new connection.
+    pub const fn all() -> Self {
+        Self {
+            types: u16::MAX,
+            min_severity: 0,
+        }
+    }
+
+    /// Nothing; add types with [`with`](Self::with).
+    pub const fn none() -> Self {
+        Self {
+            types: 0,
+            min_severity: 0,
+        }
+    }
+
+    pub const fn with(mut self, kind: MessageKind) -> Self {
+        self.types |= kind.bit();
+        self
+    }
+
+    pub const fn with_min_severity(mut self, min_severity: u8) -> Self {
+        self.min_severity = min_severity;
+        self
+    }
+
+    /// Build from message type names, e.g. from a `subscribe` command.
+    /// Returns `None` if any name is unknown.
+    pub fn from_names<'a>(
+        names: impl IntoIterator<Item = &'a str>,
+        min_severity: u8,
+    ) -> Option<Self> {
+        let mut sub = Self::none().with_min_severity(min_severity);
+        for name in names {
+            sub = sub.with(MessageKind::from_name(name)?);
+        }
+        Some(sub)
+    }
+
+    pub fn includes(&self, kind: MessageKind) -> bool {
+        self.types & kind.bit() != 0
+    }
+
+    /// Whether a serialized message should be sent to this client.
+    /// Unclassifiable messages are always delivered. The severity floor only
+    /// applies to detections and peer reports — status, health, watch,
+    /// downgrade and stats messages always pass.
+    pub fn accepts(&self, msg: &[u8]) -> bool {
+        let Some(kind) = MessageKind::of_message(msg) else {
+            return true;
+        };
+        if !self.includes(kind) {
+            return false;
+        }
+        match kind {
+            MessageKind::Status
+            | MessageKind::Health
+            | MessageKind::Watch
+            | MessageKind::WatchLost
+            | MessageKind::Downgrade
+            | MessageKind::Stats
+            | MessageKind::ChannelStats => true,
+            _ => message_severity(msg) >= self.min_severity,
+        }
+    }
+}
+
+impl Default for Subscription {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WIFI: &[u8] = br#"{"type":"wifi","mac":"B4:1E:52:AB:CD:EF","ssid":"","rssi":-45,"ch":6,"frame":"data","match":[],"ts":1}
+"#;
+    const STATUS: &[u8] = br#"{"type":"status","scanning":true}
+"#;
+    const DOWNGRADE: &[u8] = br#"{"type":"downgrade","ssid":"Home","bssid":"02:AA:BB:CC:DD:EE","expected":"wpa2","seen":"open","rssi":-52,"ch":6,"ts":1}
+"#;
+    const PEER: &[u8] = br#"{"type":"peer","mac":"AA:BB:CC:DD:EE:FF","rssi":-58,"scanning":true,"wifi":3,"ble":1,"severity":2,"ts":1}
+"#;
+
+    // ── Classification ──────────────────────────────────────────────
+
+    #[test]
+    fn classify_by_type_tag() {
+        assert_eq!(MessageKind::of_message(WIFI), Some(MessageKind::WiFi));
+        assert_eq!(MessageKind::of_message(STATUS), Some(MessageKind::Status));
+        assert_eq!(MessageKind::of_message(b"garbage"), None);
+        assert_eq!(MessageKind::of_message(br#"{"type":"nope"}"#), None);
+    }
+
+    #[test]
+    fn kind_names_round_trip() {
+        for kind in MessageKind::ALL {
+            assert_eq!(MessageKind::from_name(kind.as_str()), Some(kind));
+        }
+    }
+
+    #[test]
+    fn severity_extraction() {
+        assert_eq!(message_severity(PEER), 2);
+        assert_eq!(message_severity(WIFI), 0);
+        assert_eq!(message_severity(br#"{"severity":999}"#), 255);
+    }
+
+    // ── Subscription ────────────────────────────────────────────────
+
+    #[test]
+    fn default_subscription_accepts_everything() {
+        let sub = Subscription::default();
+        assert!(sub.accepts(WIFI));
+        assert!(sub.accepts(STATUS));
+        assert!(sub.accepts(b"not json"));
+    }
+
+    #[test]
+    fn type_mask_filters_messages() {
+        let sub = Subscription::none().with(MessageKind::Status);
+        assert!(sub.accepts(STATUS));
+        assert!(!sub.accepts(WIFI));
+        assert!(!sub.accepts(PEER));
+    }
+
+    #[test]
+    fn from_names_rejects_unknown_types() {
+        let sub = Subscription::from_names(["wifi", "health"], 1).unwrap();
+        assert!(sub.includes(MessageKind::WiFi));
+        assert!(sub.includes(MessageKind::Health));
+        assert!(!sub.includes(MessageKind::Ble));
+        assert_eq!(sub.min_severity, 1);
+        assert!(Subscription::from_names(["wifi", "bogus"], 0).is_none());
+    }
+
+    #[test]
+    fn severity_floor_skips_status() {
+        let sub = Subscription {
+            min_severity: 3,
+            ..Subscription::all()
+        };
+        assert!(!sub.accepts(WIFI));
+        assert!(!sub.accepts(PEER));
+        assert!(sub.accepts(STATUS));
+        assert!(sub.accepts(DOWNGRADE));
+
+        let sub = Subscription {
+            min_severity: 2,
+            ..Subscription::all()
+        };
+        assert!(sub.accepts(PEER));
+    }
+}
@@ -0,0 +1,303 @@
+/// Per-device RSSI smoothing.
+///
+/// Received signal strength jumps ±10 dBm from one frame to the next with
+/// multipath and body shadowing — too noisy to tell whether a device is
+/// getting closer. [`RssiSmoother`] keeps an exponential moving average per
+/// MAC, each reading moving it `1 / 2^ALPHA_SHIFT` of the way, so scan
+/// messages can carry a stable `rssi_avg` next to the instantaneous `rssi`.
+///
+/// It also keeps a short trend of each device's average, sampled once per
+/// [`TREND_STEP_MS`]: a device whose average has only risen over the last
+/// [`TREND_LEN`] samples, by [`APPROACH_DB`] in all, is
+/// [`approaching`](RssiSmoother::approaching).
+///
+/// [`ProximityZones`] turn a reading into a coarse [`Proximity`] zone, so
+/// the display and companions say "near" the same way.
+use heapless::Vec;
+use serde::{Deserialize, Serialize};
+
+/// Each reading moves the average a quarter of the way toward it
+pub const ALPHA_SHIFT: u32 = 2;
+
+/// A device not heard for this long starts a fresh average
+pub const STALE_MS: u32 = 30_000;
+
+/// Fraction bits of the stored fixed-point average
+const FRAC_BITS: u32 = 4;
+
+/// Interval between trend samples
+pub const TREND_STEP_MS: u32 = 1_000;
+
+/// Trend samples a device needs before it can be approaching
+pub const TREND_LEN: usize = 5;
+
+/// Rise over the trend that counts as approaching
+pub const APPROACH_DB: i8 = 6;
+
+/// How close a device is, judged from its RSSI
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Proximity {
+    Immediate,
+    Near,
+    Far,
+}
+
+impl Proximity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Proximity::Immediate => "immediate",
+            Proximity::Near => "near",
+            Proximity::Far => "far",
+        }
+    }
+}
+
+/// Weakest RSSI (dBm) that still counts as [`Proximity::Immediate`] and
+/// [`Proximity::Near`]; anything weaker is [`Proximity::Far`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProximityZones {
+    pub immediate: i8,
+    pub near: i8,
+}
+
+impl ProximityZones {
+    /// Roughly within arm's reach, and within the same room or a car length
+    pub const DEFAULT: Self = Self {
+        immediate: -50,
+        near: -70,
+    };
+
+    /// Build zones, rejecting an immediate threshold not above the near one
+    pub fn try_new(immediate: i8, near: i8) -> Option<Self> {
+        (immediate > near).then_some(Self { immediate, near })
+    }
+
+    pub fn classify(&self, rssi: i8) -> Proximity {
+        if rssi >= self.immediate {
+            Proximity::Immediate
+        } else if rssi >= self.near {
+            Proximity::Near
+        } else {
+            Proximity::Far
+        }
+    }
+}
+
+impl Default for ProximityZones {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+struct Entry {
+    mac: [u8; 6],
+    /// dBm in 1/16 steps
+    avg: i16,
+    last_ms: u32,
+    /// Averages sampled every [`TREND_STEP_MS`], oldest first
+    trend: Vec<i8, TREND_LEN>,
+    trend_ms: u32,
+}
+
+impl Entry {
+    fn new(mac: [u8; 6], sample: i16, now_ms: u32) -> Self {
+        let mut entry = Self {
+            mac,
+            avg: sample,
+            last_ms: now_ms,
+            trend: Vec::new(),
+            trend_ms: now_ms,
+        };
+        let _ = entry.trend.push(entry.dbm());
+        entry
+    }
+
+    fn sample_trend(&mut self, now_ms: u32) {
+        if now_ms.wrapping_sub(self.trend_ms) < TREND_STEP_MS {
+            return;
+        }
+        if self.trend.is_full() {
+            self.trend.remove(0);
+        }
+        let _ = self.trend.push(self.dbm());
+        self.trend_ms = now_ms;
+    }
+
+    fn approaching(&self) -> bool {
+        self.trend.is_full()
+            && self.trend.windows(2).all(|pair| pair[1] >= pair[0])
+            && self.trend[TREND_LEN - 1] as i16 - self.trend[0] as i16 >= APPROACH_DB as i16
+    }
+
+    /// The average rounded to whole dBm
+    fn dbm(&self) -> i8 {
+        ((self.avg + (1 << (FRAC_BITS - 1))) >> FRAC_BITS) as i8
+    }
+}
+
+/// Smoothed RSSI for up to `N` devices. Timestamps are milliseconds since
+/// boot (`u32`, wrapping).
+pub struct RssiSmoother<const N: usize> {
+    entries: Vec<Entry, N>,
+}
+
+impl<const N: usize> RssiSmoother<N> {
+    pub const fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Fold a reading from `mac` into its average and return the smoothed
+    /// RSSI. A device's first reading, or its first after [`STALE_MS`] of
+    /// silence, is its own average. When the table is full the device heard
+    /// longest ago is replaced.
+    pub fn observe(&mut self, mac: &[u8; 6], rssi: i8, now_ms: u32) -> i8 {
+        let sample = (rssi as i16) << FRAC_BITS;
+        if let Some(entry) = self.entries.iter_mut().find(|e| e.mac == *mac) {
+            if now_ms.wrapping_sub(entry.last_ms) >= STALE_MS {
+                *entry = Entry::new(*mac, sample, now_ms);
+            } else {
+                entry.avg += (sample - entry.avg) >> ALPHA_SHIFT;
+                entry.last_ms = now_ms;
+                entry.sample_trend(now_ms);
+            }
+            return entry.dbm();
+        }
+
+        let entry = Entry::new(*mac, sample, now_ms);
+        if let Err(entry) = self.entries.push(entry) {
+            let stalest = self
+                .entries
+                .iter_mut()
+                .max_by_key(|e| now_ms.wrapping_sub(e.last_ms));
+            if let Some(stalest) = stalest {
+                *stalest = entry;
+            }
+        }
+        rssi
+    }
+
+    /// Drop `mac`'s average and trend, so its next reading starts afresh
+    pub fn forget(&mut self, mac: &[u8; 6]) {
+        self.entries.retain(|e| e.mac != *mac);
+    }
+
+    /// Whether `mac`'s average has risen steadily over its recent trend
+    pub fn approaching(&self, mac: &[u8; 6]) -> bool {
+        self.entries
+            .iter()
+            .find(|e| e.mac == *mac)
+            .is_some_and(Entry::approaching)
+    }
+}
+
+impl<const N: usize> Default for RssiSmoother<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAC: [u8; 6] = [0xB4, 0x1E, 0x52, 0x01, 0x02, 0x03];
+    const OTHER: [u8; 6] = [0xB4, 0x1E, 0x52, 0x0A, 0x0B, 0x0C];
+
+    #[test]
+    fn zones_classify_by_threshold() {
+        let zones = ProximityZones::DEFAULT;
+        assert_eq!(zones.classify(-40), Proximity::Immediate);
+        assert_eq!(zones.classify(-50), Proximity::Immediate);
+        assert_eq!(zones.classify(-51), Proximity::Near);
+        assert_eq!(zones.classify(-70), Proximity::Near);
+        assert_eq!(zones.classify(-71), Proximity::Far);
+        assert!(ProximityZones::try_new(-60, -60).is_none());
+        assert!(ProximityZones::try_new(-60, -80).is_some());
+    }
+
+    #[test]
+    fn first_reading_is_the_average() {
+        let mut smoother = RssiSmoother::<4>::new();
+        assert_eq!(smoother.observe(&MAC, -60, 0), -60);
+        assert_eq!(smoother.observe(&OTHER, -80, 0), -80);
+    }
+
+    #[test]
+    fn readings_move_the_average_a_quarter_of_the_way() {
+        let mut smoother = RssiSmoother::<4>::new();
+        smoother.observe(&MAC, -60, 0);
+        assert_eq!(smoother.observe(&MAC, -40, 100), -55);
+        // A single outlier barely moves it
+        assert_eq!(smoother.observe(&MAC, -90, 200), -64);
+        // A steady reading wins out
+        let mut avg = 0;
+        for i in 0..30 {
+            avg = smoother.observe(&MAC, -45, 300 + i * 100);
+        }
+        assert_eq!(avg, -45);
+    }
+
+    #[test]
+    fn stale_devices_start_over() {
+        let mut smoother = RssiSmoother::<4>::new();
+        smoother.observe(&MAC, -60, 0);
+        assert_eq!(smoother.observe(&MAC, -40, STALE_MS), -40);
+    }
+
+    #[test]
+    fn forgotten_devices_start_over() {
+        let mut smoother = RssiSmoother::<4>::new();
+        smoother.observe(&MAC, -60, 0);
+        smoother.observe(&OTHER, -80, 0);
+        smoother.forget(&MAC);
+        assert_eq!(smoother.observe(&MAC, -40, 100), -40);
+        assert_eq!(smoother.observe(&OTHER, -80, 100), -80);
+    }
+
+    #[test]
+    fn steadily_rising_devices_are_approaching() {
+        let mut smoother = RssiSmoother::<4>::new();
+        let mut now = 0;
+        for rssi in [-80, -80, -76, -72, -68, -64, -60] {
+            // Several frames per trend step
+            for _ in 0..4 {
+                smoother.observe(&MAC, rssi, now);
+                now += TREND_STEP_MS / 4;
+            }
+        }
+        assert!(smoother.approaching(&MAC));
+        assert!(!smoother.approaching(&OTHER));
+
+        // Moving away breaks the trend
+        for _ in 0..8 {
+            smoother.observe(&MAC, -85, now);
+            now += TREND_STEP_MS / 4;
+        }
+        assert!(!smoother.approaching(&MAC));
+    }
+
+    #[test]
+    fn steady_devices_are_not_approaching() {
+        let mut smoother = RssiSmoother::<4>::new();
+        // Noisy but level: the average wobbles without a sustained rise
+        for (i, rssi) in [-70, -64, -72, -66, -70, -65, -71, -66, -70, -64]
+            .into_iter()
+            .enumerate()
+        {
+            smoother.observe(&MAC, rssi, i as u32 * TREND_STEP_MS);
+        }
+        assert!(!smoother.approaching(&MAC));
+    }
+
+    #[test]
+    fn full_table_replaces_the_device_heard_longest_ago() {
+        let mut smoother = RssiSmoother::<1>::new();
+        smoother.observe(&MAC, -60, 0);
+        smoother.observe(&OTHER, -80, 1_000);
+        // MAC was evicted, so its next reading starts over
+        assert_eq!(smoother.observe(&MAC, -40, 2_000), -40);
+    }
+}
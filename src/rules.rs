@@ -0,0 +1,1573 @@
+/// Detection rules: named devices composed from signatures with boolean logic.
+///
+/// A signature file's `rules` name the devices its signatures identify, e.g.
+/// "Apple AirTag" for the Find My offline-finding payload. `build.rs` compiles
+/// each pack's rules into a [`RuleDb`]: expressions are flattened into
+/// post-order [`ExprNode`]s over the pack's signatures, numbered in file order
+/// ([`SigIdx`]), so evaluation is a small bounded stack machine. The filter
+/// records which compiled-in signatures matched ([`SigHit`]) and
+/// [`evaluate_rules`] reports the rules they satisfy, each with the severity
+/// it rates the detection at. A reverse index from signature to the rules
+/// using it ([`RuleDb::by_sig`]) keeps evaluation to the rules a match can
+/// affect. Runtime signatures take no part in rules.
+///
+/// A rule may also carry a [`RuleAction`] — how many beeps, and which LED
+/// color — and [`alert_action`] picks the one a detection physically alerts
+/// with, so the firmware's buzzer and LED just play it back.
+///
+/// A rule database also travels as a binary blob — pushed by a companion or
+/// loaded from disk — that [`RuleDb::encode`] writes and [`RuleDb::parse`]
+/// validates into a [`RuleSet`]. Layout, little-endian:
+///
+/// ```text
+/// "AHRD" | version u8 | rule count u8 | node count u16 | pack len u8 | pack | nodes | rules
+/// node: op u8 (0 sig, 1 anyOf, 2 allOf, 3 not, 4 atLeast, 5 within) | arg u16
+/// rule: severity u8 | beeps u8 | pattern u8 | led u8 | start u16 | len u16
+///       | id len u8 | id | name len u8 | name
+/// ```
+///
+/// `pack` names the pack whose signature numbering the `sig` nodes use.
+/// An `atLeast` node's arg is its operand count in the low byte and the
+/// number of them that must hold in the high byte. A `within` node's arg is
+/// its signature, followed by its window in seconds as a u16.
+/// `beeps` 0xFF means the rule has no action, `led` 0xFF no LED color.
+///
+/// A `within` node also looks back in time: it holds if its signature
+/// matched, on any device, in the last so many seconds. The firmware keeps
+/// those sightings in a [`RecentSigs`] and evaluates with
+/// [`evaluate_rules_recent`]; without one, `within` only sees the current
+/// detection.
+///
+/// A prospective rule can be dry-run with [`test_rule`] against
+/// [`SyntheticEvent`]s — typically the firmware's [`EventLog`] of recent
+/// detections — without reporting or alerting anything.
+///
+/// Rather than writing post-order nodes by hand, build rules from [`Expr`]
+/// trees ([`sig`], [`within`], [`any_of`], [`all_of`], [`at_least`], [`not`])
+/// with a [`RuleBuilder`],
+/// which flattens and validates them into a [`RuleSet`].
+use heapless::{Deque, Vec};
+use serde::Serialize;
+
+use crate::defaults::{Severity, SigTable, SignaturePack, PACKS};
+use crate::error::AirhoundError;
+use crate::filter::MAX_HITS;
+
+/// Index of a signature in its pack file's `signatures` array
+pub type SigIdx = u16;
+
+/// Deepest evaluation stack a rule expression may need (checked by `build.rs`)
+pub const MAX_DEPTH: usize = 16;
+
+/// Most beeps a [`RuleAction`] plays (checked by `build.rs`)
+pub const MAX_BEEPS: u8 = 5;
+
+/// Rules reported per detection
+pub const MAX_RULE_MATCHES: usize = 4;
+
+/// Rules [`RuleDb::rules_using`] collects before giving up and having every
+/// rule evaluated
+pub const MAX_CANDIDATES: usize = 32;
+
+pub const MAGIC: [u8; 4] = *b"AHRD";
+
+/// Blob format version written by [`RuleDb::encode`]
+pub const FORMAT_VERSION: u8 = 1;
+
+/// Rules a [`RuleSet`] holds
+pub const MAX_BLOB_RULES: usize = 32;
+
+/// Expression nodes a [`RuleSet`] holds
+pub const MAX_BLOB_NODES: usize = 256;
+
+const OP_SIG: u8 = 0;
+const OP_ANY_OF: u8 = 1;
+const OP_ALL_OF: u8 = 2;
+const OP_NOT: u8 = 3;
+const OP_AT_LEAST: u8 = 4;
+const OP_WITHIN: u8 = 5;
+
+/// `beeps`/`led` byte of a rule without an action or LED color
+const NONE: u8 = 0xFF;
+
+const LED_COLORS: [LedColor; 7] = [
+    LedColor::Red,
+    LedColor::Orange,
+    LedColor::Yellow,
+    LedColor::Green,
+    LedColor::Blue,
+    LedColor::Purple,
+    LedColor::White,
+];
+
+/// One node of a post-order expression. Operators combine the values their
+/// operands left on the stack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExprNode {
+    /// Whether the signature matched
+    Sig(SigIdx),
+    /// Whether the signature matched this detection or, on any device, one
+    /// in the `secs` seconds before it
+    Within { sig: SigIdx, secs: u16 },
+    /// True if any of the last `n` values is
+    AnyOf(u8),
+    /// True if all of the last `n` values are
+    AllOf(u8),
+    /// True if at least `n` of the last `count` values are
+    AtLeast { n: u8, count: u8 },
+    /// Negates the last value
+    Not,
+}
+
+/// Beep length of a [`RuleAction`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BeepPattern {
+    /// The board's beep length
+    Short,
+    /// Three times the board's beep length
+    Long,
+}
+
+/// LED colors a rule can light, in palette order. Boards with a
+/// single-color LED light it for any.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum LedColor {
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Blue,
+    Purple,
+    White,
+}
+
+impl LedColor {
+    /// Palette entry as 8-bit RGB
+    pub const fn rgb(self) -> [u8; 3] {
+        match self {
+            LedColor::Red => [255, 0, 0],
+            LedColor::Orange => [255, 96, 0],
+            LedColor::Yellow => [255, 200, 0],
+            LedColor::Green => [0, 255, 0],
+            LedColor::Blue => [0, 0, 255],
+            LedColor::Purple => [160, 0, 255],
+            LedColor::White => [255, 255, 255],
+        }
+    }
+}
+
+/// How a detection physically alerts the user
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RuleAction {
+    /// Number of beeps (0 = silent)
+    pub beeps: u8,
+    pub pattern: BeepPattern,
+    /// LED flashed alongside the beeps, if any
+    pub led: Option<LedColor>,
+}
+
+impl RuleAction {
+    /// Alert for detections no rule sets an action for: one short beep
+    pub const DEFAULT: RuleAction = RuleAction {
+        beeps: 1,
+        pattern: BeepPattern::Short,
+        led: None,
+    };
+
+    /// Alert for a device that is following the user: as many long beeps
+    /// as allowed, with the red LED
+    pub const URGENT: RuleAction = RuleAction {
+        beeps: MAX_BEEPS,
+        pattern: BeepPattern::Long,
+        led: Some(LedColor::Red),
+    };
+
+    /// The alert for a device that is getting closer: long beeps, one more
+    /// of them (at most [`MAX_BEEPS`]). A silent action stays silent.
+    pub const fn escalated(self) -> Self {
+        let beeps = if self.beeps == 0 || self.beeps >= MAX_BEEPS {
+            self.beeps
+        } else {
+            self.beeps + 1
+        };
+        Self {
+            beeps,
+            pattern: BeepPattern::Long,
+            led: self.led,
+        }
+    }
+}
+
+/// A named detection rule
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rule<'a> {
+    pub id: &'a str,
+    /// Device name reported when the rule matches
+    pub name: &'a str,
+    /// How urgently a detection matching this rule should be surfaced
+    pub severity: Severity,
+    /// How a detection matching this rule alerts, if not the default
+    pub action: Option<RuleAction>,
+    /// First of the rule's nodes in [`RuleDb::nodes`]
+    pub start: u16,
+    /// Number of nodes in the expression
+    pub len: u16,
+}
+
+/// A pack's rules and the expression nodes they index into
+#[derive(Debug, Clone, Copy)]
+pub struct RuleDb<'a> {
+    pub nodes: &'a [ExprNode],
+    pub rules: &'a [Rule<'a>],
+    /// Reverse index: `(signature, rule)` for every signature a rule uses,
+    /// sorted, so only rules touching a matched signature are evaluated
+    pub by_sig: &'a [(SigIdx, u16)],
+}
+
+impl<'a> RuleDb<'a> {
+    pub const EMPTY: RuleDb<'static> = RuleDb {
+        nodes: &[],
+        rules: &[],
+        by_sig: &[],
+    };
+
+    /// Indices of the rules using any of `sigs`, ascending. `None` if there
+    /// are more than [`MAX_CANDIDATES`], and every rule needs evaluating.
+    ///
+    /// A rule is only a candidate once one of its signatures matched, so a
+    /// rule built purely from negations never matches, and `within` nodes
+    /// only look back from a detection matching one of the rule's signatures.
+    pub fn rules_using(
+        &self,
+        sigs: impl IntoIterator<Item = SigIdx>,
+    ) -> Option<Vec<u16, MAX_CANDIDATES>> {
+        let mut found: Vec<u16, MAX_CANDIDATES> = Vec::new();
+        for sig in sigs {
+            let first = self.by_sig.partition_point(|&(s, _)| s < sig);
+            for &(_, rule) in self.by_sig[first..].iter().take_while(|(s, _)| *s == sig) {
+                if !found.contains(&rule) {
+                    found.push(rule).ok()?;
+                }
+            }
+        }
+        found.sort_unstable();
+        Some(found)
+    }
+
+    /// Whether a `within` node names `sig`, so its sightings are worth
+    /// keeping in a [`RecentSigs`]
+    pub fn looks_back(&self, sig: SigIdx) -> bool {
+        self.nodes
+            .iter()
+            .any(|node| matches!(*node, ExprNode::Within { sig: s, .. } if s == sig))
+    }
+
+    /// Evaluate `rule`, with `hit` telling whether a signature matched. A
+    /// malformed expression (out of range, unbalanced or too deep) never
+    /// matches. `within` nodes only see the current detection.
+    pub fn matches(&self, rule: &Rule, hit: impl Fn(SigIdx) -> bool) -> bool {
+        self.matches_recent(rule, hit, |_, _| false)
+    }
+
+    /// [`matches`](Self::matches), with `recent` telling `within` nodes
+    /// whether a signature matched in the given number of seconds before
+    /// this detection
+    pub fn matches_recent(
+        &self,
+        rule: &Rule,
+        hit: impl Fn(SigIdx) -> bool,
+        recent: impl Fn(SigIdx, u16) -> bool,
+    ) -> bool {
+        let start = rule.start as usize;
+        let Some(nodes) = self.nodes.get(start..start + rule.len as usize) else {
+            return false;
+        };
+        let mut stack: Vec<bool, MAX_DEPTH> = Vec::new();
+        for node in nodes {
+            let value = match *node {
+                ExprNode::Sig(sig) => hit(sig),
+                ExprNode::Within { sig, secs } => hit(sig) || recent(sig, secs),
+                ExprNode::AnyOf(n) | ExprNode::AllOf(n) | ExprNode::AtLeast { count: n, .. } => {
+                    let Some(first) = stack.len().checked_sub(n as usize).filter(|_| n > 0) else {
+                        return false;
+                    };
+                    let operands = &stack[first..];
+                    let value = match *node {
+                        ExprNode::AnyOf(_) => operands.iter().any(|&v| v),
+                        ExprNode::AtLeast { n, .. } => {
+                            operands.iter().filter(|&&v| v).count() >= n as usize
+                        }
+                        _ => operands.iter().all(|&v| v),
+                    };
+                    stack.truncate(first);
+                    value
+                }
+                ExprNode::Not => match stack.pop() {
+                    Some(value) => !value,
+                    None => return false,
+                },
+            };
+            if stack.push(value).is_err() {
+                return false;
+            }
+        }
+        stack.as_slice() == [true]
+    }
+
+    /// Whether every rule's expression lies within the nodes, is
+    /// [`well_formed`], and only names signatures below `sig_count` (the
+    /// pack's `SIG_COUNT`). Compiled-in packs are checked at build time
+    /// ([`crate::defaults::SignaturePack::checked`]).
+    pub const fn validate(&self, sig_count: usize) -> bool {
+        let mut r = 0;
+        while r < self.rules.len() {
+            let rule = &self.rules[r];
+            let (start, len) = (rule.start as usize, rule.len as usize);
+            if start + len > self.nodes.len() {
+                return false;
+            }
+            let nodes = self.nodes.split_at(start).1.split_at(len).0;
+            if !well_formed(nodes) {
+                return false;
+            }
+            let mut i = 0;
+            while i < nodes.len() {
+                if let ExprNode::Sig(sig) | ExprNode::Within { sig, .. } = nodes[i] {
+                    if sig as usize >= sig_count || !self.indexed(sig, r as u16) {
+                        return false;
+                    }
+                }
+                i += 1;
+            }
+            r += 1;
+        }
+        // The index is sorted and only names existing rules
+        let mut i = 0;
+        while i < self.by_sig.len() {
+            let (sig, rule) = self.by_sig[i];
+            if rule as usize >= self.rules.len() {
+                return false;
+            }
+            if i > 0 {
+                let (prev_sig, prev_rule) = self.by_sig[i - 1];
+                if prev_sig > sig || (prev_sig == sig && prev_rule >= rule) {
+                    return false;
+                }
+            }
+            i += 1;
+        }
+        true
+    }
+
+    /// Whether the reverse index lists `rule` under `sig`
+    const fn indexed(&self, sig: SigIdx, rule: u16) -> bool {
+        let mut i = 0;
+        while i < self.by_sig.len() {
+            if self.by_sig[i].0 == sig && self.by_sig[i].1 == rule {
+                return true;
+            }
+            i += 1;
+        }
+        false
+    }
+
+    /// Encode as a blob of `pack`'s rules into `out`. Returns the bytes
+    /// written.
+    pub fn encode(&self, pack: &str, out: &mut [u8]) -> Result<usize, AirhoundError> {
+        let rule_count =
+            u8::try_from(self.rules.len()).map_err(|_| AirhoundError::InvalidArgument)?;
+        let node_count =
+            u16::try_from(self.nodes.len()).map_err(|_| AirhoundError::InvalidArgument)?;
+        let mut pos = 0;
+        let mut put = |bytes: &[u8]| {
+            let dest = out
+                .get_mut(pos..pos + bytes.len())
+                .ok_or(AirhoundError::BufferTooSmall)?;
+            dest.copy_from_slice(bytes);
+            pos += bytes.len();
+            Ok::<_, AirhoundError>(())
+        };
+        let text = |s: &str| u8::try_from(s.len()).map_err(|_| AirhoundError::InvalidArgument);
+
+        put(&MAGIC)?;
+        put(&[FORMAT_VERSION, rule_count])?;
+        put(&node_count.to_le_bytes())?;
+        put(&[text(pack)?])?;
+        put(pack.as_bytes())?;
+        for node in self.nodes {
+            let (op, arg) = match *node {
+                ExprNode::Sig(sig) => (OP_SIG, sig),
+                ExprNode::AnyOf(n) => (OP_ANY_OF, n as u16),
+                ExprNode::AllOf(n) => (OP_ALL_OF, n as u16),
+                ExprNode::Not => (OP_NOT, 0),
+                ExprNode::AtLeast { n, count } => (OP_AT_LEAST, u16::from_le_bytes([count, n])),
+                ExprNode::Within { sig, .. } => (OP_WITHIN, sig),
+            };
+            put(&[op])?;
+            put(&arg.to_le_bytes())?;
+            if let ExprNode::Within { secs, .. } = *node {
+                put(&secs.to_le_bytes())?;
+            }
+        }
+        for rule in self.rules {
+            let action = match rule.action {
+                Some(action) => [
+                    action.beeps,
+                    action.pattern as u8,
+                    action.led.map_or(NONE, |led| led as u8),
+                ],
+                None => [NONE, 0, NONE],
+            };
+            put(&[rule.severity as u8])?;
+            put(&action)?;
+            put(&rule.start.to_le_bytes())?;
+            put(&rule.len.to_le_bytes())?;
+            put(&[text(rule.id)?])?;
+            put(rule.id.as_bytes())?;
+            put(&[text(rule.name)?])?;
+            put(rule.name.as_bytes())?;
+        }
+        Ok(pos)
+    }
+
+    /// Parse and validate a rule-database blob. Every rule's expression must
+    /// lie within the nodes and be well-formed ([`well_formed`]). The
+    /// reverse index is rebuilt from the expressions.
+    pub fn parse(blob: &'a [u8]) -> Result<RuleSet<'a>, AirhoundError> {
+        let mut rest = blob;
+        let mut take = |n: usize| {
+            if rest.len() < n {
+                return Err(AirhoundError::Malformed);
+            }
+            let (head, tail) = rest.split_at(n);
+            rest = tail;
+            Ok(head)
+        };
+        let text =
+            |bytes: &'a [u8]| core::str::from_utf8(bytes).map_err(|_| AirhoundError::Malformed);
+
+        if take(4)? != MAGIC {
+            return Err(AirhoundError::Malformed);
+        }
+        let header = take(5)?;
+        if header[0] != FORMAT_VERSION {
+            return Err(AirhoundError::Unsupported);
+        }
+        let rule_count = header[1] as usize;
+        let node_count = u16::from_le_bytes([header[2], header[3]]) as usize;
+        if rule_count > MAX_BLOB_RULES || node_count > MAX_BLOB_NODES {
+            return Err(AirhoundError::Unsupported);
+        }
+        let mut set = RuleSet {
+            pack: text(take(header[4] as usize)?)?,
+            nodes: Vec::new(),
+            rules: Vec::new(),
+            by_sig: Vec::new(),
+        };
+        for _ in 0..node_count {
+            let node = take(3)?;
+            let arg = u16::from_le_bytes([node[1], node[2]]);
+            let count = || u8::try_from(arg).map_err(|_| AirhoundError::Malformed);
+            let node = match node[0] {
+                OP_SIG => ExprNode::Sig(arg),
+                OP_ANY_OF => ExprNode::AnyOf(count()?),
+                OP_ALL_OF => ExprNode::AllOf(count()?),
+                OP_NOT => ExprNode::Not,
+                OP_AT_LEAST => {
+                    let [count, n] = arg.to_le_bytes();
+                    ExprNode::AtLeast { n, count }
+                }
+                OP_WITHIN => {
+                    let secs = take(2)?;
+                    ExprNode::Within {
+                        sig: arg,
+                        secs: u16::from_le_bytes([secs[0], secs[1]]),
+                    }
+                }
+                _ => return Err(AirhoundError::Malformed),
+            };
+            let _ = set.nodes.push(node);
+        }
+        for _ in 0..rule_count {
+            let fixed = take(8)?;
+            let [severity, beeps, pattern, led] = [fixed[0], fixed[1], fixed[2], fixed[3]];
+            let severity = match severity {
+                1 => Severity::Low,
+                2 => Severity::Medium,
+                3 => Severity::High,
+                _ => return Err(AirhoundError::Malformed),
+            };
+            let action = match beeps {
+                NONE => None,
+                beeps => Some(RuleAction {
+                    beeps,
+                    pattern: match pattern {
+                        0 => BeepPattern::Short,
+                        1 => BeepPattern::Long,
+                        _ => return Err(AirhoundError::Malformed),
+                    },
+                    led: match led {
+                        NONE => None,
+                        led => Some(
+                            *LED_COLORS
+                                .get(led as usize)
+                                .ok_or(AirhoundError::Malformed)?,
+                        ),
+                    },
+                }),
+            };
+            let id_len = take(1)?[0] as usize;
+            let id = text(take(id_len)?)?;
+            let name_len = take(1)?[0] as usize;
+            let name = text(take(name_len)?)?;
+            let rule = Rule {
+                id,
+                name,
+                severity,
+                action,
+                start: u16::from_le_bytes([fixed[4], fixed[5]]),
+                len: u16::from_le_bytes([fixed[6], fixed[7]]),
+            };
+            let start = rule.start as usize;
+            let nodes = set
+                .nodes
+                .get(start..start + rule.len as usize)
+                .ok_or(AirhoundError::Malformed)?;
+            if !well_formed(nodes) {
+                return Err(AirhoundError::Malformed);
+            }
+            let _ = set.rules.push(rule);
+            set.index_last_rule()?;
+        }
+        if !rest.is_empty() {
+            return Err(AirhoundError::Malformed);
+        }
+        Ok(set)
+    }
+}
+
+/// Whether `nodes` is one complete post-order expression that fits the
+/// evaluation stack: every operator has its operands, `atLeast` asks for
+/// between one and all of them, and exactly one value is left.
+pub const fn well_formed(nodes: &[ExprNode]) -> bool {
+    let mut depth = 0usize;
+    let mut i = 0;
+    while i < nodes.len() {
+        depth = match nodes[i] {
+            ExprNode::Sig(_) | ExprNode::Within { .. } => depth + 1,
+            ExprNode::AnyOf(n) | ExprNode::AllOf(n) if n > 0 && n as usize <= depth => {
+                depth - n as usize + 1
+            }
+            ExprNode::AtLeast { n, count } if n > 0 && n <= count && count as usize <= depth => {
+                depth - count as usize + 1
+            }
+            ExprNode::Not if depth > 0 => depth,
+            _ => return false,
+        };
+        if depth > MAX_DEPTH {
+            return false;
+        }
+        i += 1;
+    }
+    depth == 1
+}
+
+/// A rule database parsed from a blob ([`RuleDb::parse`]), names borrowing
+/// the blob's bytes
+#[derive(Debug, Clone)]
+pub struct RuleSet<'a> {
+    /// Pack whose signatures the rules' `sig` nodes index
+    pub pack: &'a str,
+    nodes: Vec<ExprNode, MAX_BLOB_NODES>,
+    rules: Vec<Rule<'a>, MAX_BLOB_RULES>,
+    by_sig: Vec<(SigIdx, u16), MAX_BLOB_NODES>,
+}
+
+impl<'a> RuleSet<'a> {
+    pub fn db(&self) -> RuleDb<'_> {
+        RuleDb {
+            nodes: &self.nodes,
+            rules: &self.rules,
+            by_sig: &self.by_sig,
+        }
+    }
+
+    /// Add the last rule's signatures to the reverse index. `Malformed` if
+    /// they don't fit, which only rules sharing node ranges can cause:
+    /// otherwise there are at most as many entries as `Sig` and `Within`
+    /// nodes.
+    fn index_last_rule(&mut self) -> Result<(), AirhoundError> {
+        let Some(rule) = self.rules.last() else {
+            return Ok(());
+        };
+        let r = self.rules.len() as u16 - 1;
+        let start = rule.start as usize;
+        for node in &self.nodes[start..start + rule.len as usize] {
+            if let ExprNode::Sig(sig) | ExprNode::Within { sig, .. } = *node {
+                if !self.by_sig.contains(&(sig, r)) {
+                    self.by_sig
+                        .push((sig, r))
+                        .map_err(|_| AirhoundError::Malformed)?;
+                }
+            }
+        }
+        self.by_sig.sort_unstable();
+        Ok(())
+    }
+
+    /// Index in [`PACKS`] of the pack the rules are written against, if it's
+    /// enabled
+    pub fn pack_index(&self) -> Option<usize> {
+        PACKS.iter().position(|pack| pack.name == self.pack)
+    }
+}
+
+/// A rule expression tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Expr<'e> {
+    Sig(SigIdx),
+    Within(SigIdx, u16),
+    AnyOf(&'e [Expr<'e>]),
+    AllOf(&'e [Expr<'e>]),
+    AtLeast(u8, &'e [Expr<'e>]),
+    Not(&'e Expr<'e>),
+}
+
+/// Whether the signature matched
+pub const fn sig(sig: SigIdx) -> Expr<'static> {
+    Expr::Sig(sig)
+}
+
+/// Whether the signature matched this detection or, on any device, one in
+/// the `secs` seconds before it
+pub const fn within(sig: SigIdx, secs: u16) -> Expr<'static> {
+    Expr::Within(sig, secs)
+}
+
+/// True if any operand is
+pub const fn any_of<'e>(operands: &'e [Expr<'e>]) -> Expr<'e> {
+    Expr::AnyOf(operands)
+}
+
+/// True if all operands are
+pub const fn all_of<'e>(operands: &'e [Expr<'e>]) -> Expr<'e> {
+    Expr::AllOf(operands)
+}
+
+/// True if at least `n` operands are
+pub const fn at_least<'e>(n: u8, operands: &'e [Expr<'e>]) -> Expr<'e> {
+    Expr::AtLeast(n, operands)
+}
+
+/// Negation
+pub const fn not<'e>(operand: &'e Expr<'e>) -> Expr<'e> {
+    Expr::Not(operand)
+}
+
+/// Builds a [`RuleSet`] from expression trees, flattening each into
+/// post-order nodes
+#[derive(Debug, Clone)]
+pub struct RuleBuilder<'a> {
+    set: RuleSet<'a>,
+}
+
+impl<'a> RuleBuilder<'a> {
+    /// Start a rule set over `pack`'s signatures
+    pub fn new(pack: &'a str) -> Self {
+        Self {
+            set: RuleSet {
+                pack,
+                nodes: Vec::new(),
+                rules: Vec::new(),
+                by_sig: Vec::new(),
+            },
+        }
+    }
+
+    /// Add a rule. An expression that doesn't fit — an operator without
+    /// operands or with more than 255, an `at_least` no operand count can
+    /// satisfy, nesting or a stack deeper than
+    /// [`MAX_DEPTH`], or more nodes or rules than a set holds — is
+    /// `InvalidArgument` and leaves the set unchanged.
+    pub fn rule(
+        &mut self,
+        id: &'a str,
+        name: &'a str,
+        severity: Severity,
+        action: Option<RuleAction>,
+        detect: &Expr,
+    ) -> Result<&mut Self, AirhoundError> {
+        let start = self.set.nodes.len();
+        let flattened = self.flatten(detect, 0);
+        let nodes = &self.set.nodes[start..];
+        if flattened.is_err() || !well_formed(nodes) || self.set.rules.is_full() {
+            self.set.nodes.truncate(start);
+            return Err(AirhoundError::InvalidArgument);
+        }
+        let rule = Rule {
+            id,
+            name,
+            severity,
+            action,
+            start: start as u16,
+            len: nodes.len() as u16,
+        };
+        let _ = self.set.rules.push(rule);
+        // Each built rule has nodes of its own, so its entries always fit
+        let _ = self.set.index_last_rule();
+        Ok(self)
+    }
+
+    pub fn build(self) -> RuleSet<'a> {
+        self.set
+    }
+
+    /// Append `expr` in post-order
+    fn flatten(&mut self, expr: &Expr, nesting: usize) -> Result<(), AirhoundError> {
+        if nesting >= MAX_DEPTH {
+            return Err(AirhoundError::InvalidArgument);
+        }
+        let node = match *expr {
+            Expr::Sig(sig) => ExprNode::Sig(sig),
+            Expr::Within(sig, secs) => ExprNode::Within { sig, secs },
+            Expr::AnyOf(operands) | Expr::AllOf(operands) | Expr::AtLeast(_, operands) => {
+                let n = u8::try_from(operands.len())
+                    .ok()
+                    .filter(|&n| n > 0)
+                    .ok_or(AirhoundError::InvalidArgument)?;
+                for operand in operands {
+                    self.flatten(operand, nesting + 1)?;
+                }
+                match *expr {
+                    Expr::AnyOf(_) => ExprNode::AnyOf(n),
+                    Expr::AtLeast(at_least, _) => ExprNode::AtLeast {
+                        n: at_least,
+                        count: n,
+                    },
+                    _ => ExprNode::AllOf(n),
+                }
+            }
+            Expr::Not(operand) => {
+                self.flatten(operand, nesting + 1)?;
+                ExprNode::Not
+            }
+        };
+        self.set
+            .nodes
+            .push(node)
+            .map_err(|_| AirhoundError::InvalidArgument)
+    }
+}
+
+/// A compiled-in signature that matched: its pack's index in [`PACKS`] and
+/// its position in one of the pack's tables
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SigHit {
+    pub pack: u8,
+    pub table: SigTable,
+    pub index: u16,
+}
+
+impl SigHit {
+    /// The match detail the signature reports
+    pub fn description(&self) -> &'static str {
+        PACKS
+            .get(self.pack as usize)
+            .and_then(|pack| pack.describe(self.table, self.index as usize))
+            .unwrap_or("")
+    }
+}
+
+/// A rule satisfied by a detection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct RuleMatch {
+    pub name: &'static str,
+    pub severity: Severity,
+    #[serde(skip)]
+    pub action: Option<RuleAction>,
+}
+
+/// The rules of the enabled packs satisfied by `hits`, in pack and file
+/// order, up to `N`, and whether more were satisfied than fit. Only rules
+/// using a matched signature ([`RuleDb::rules_using`]) are evaluated.
+/// `within` nodes only see `hits`.
+pub fn evaluate_rules<const N: usize>(hits: &[SigHit]) -> (Vec<RuleMatch, N>, bool) {
+    evaluate(hits, |_, _| false)
+}
+
+/// [`evaluate_rules`], with `within` nodes also looking back over the
+/// sightings in `recent` before `now_ms`
+pub fn evaluate_rules_recent<const N: usize>(
+    hits: &[SigHit],
+    recent: &RecentSigs,
+    now_ms: u32,
+) -> (Vec<RuleMatch, N>, bool) {
+    evaluate(hits, |hit, secs| recent.seen_within(hit, secs, now_ms))
+}
+
+fn evaluate<const N: usize>(
+    hits: &[SigHit],
+    recent: impl Fn(SigHit, u16) -> bool,
+) -> (Vec<RuleMatch, N>, bool) {
+    let mut matched = Vec::new();
+    for (p, pack) in PACKS.iter().enumerate() {
+        let sigs = hits
+            .iter()
+            .filter(|hit| hit.pack as usize == p)
+            .filter_map(|hit| {
+                let entry = (hit.table, hit.index);
+                pack.signatures.iter().position(|&sig| sig == entry)
+            })
+            .map(|sig| sig as SigIdx);
+        let candidates = pack.rules.rules_using(sigs);
+        if candidates.as_ref().is_some_and(|rules| rules.is_empty()) {
+            continue;
+        }
+        let hit = |sig| signature_hit(p, pack, hits, sig);
+        let seen = |sig: SigIdx, secs| {
+            pack.signatures
+                .get(sig as usize)
+                .is_some_and(|&(table, index)| {
+                    let pack = p as u8;
+                    recent(SigHit { pack, table, index }, secs)
+                })
+        };
+        let selected = candidates.as_ref().map(|indices| {
+            indices
+                .iter()
+                .filter_map(|&r| pack.rules.rules.get(r as usize))
+        });
+        let everything = candidates.is_none().then(|| pack.rules.rules.iter());
+        let rules = selected.into_iter().flatten();
+        for rule in rules.chain(everything.into_iter().flatten()) {
+            if !pack.rules.matches_recent(rule, hit, seen) {
+                continue;
+            }
+            let found = RuleMatch {
+                name: rule.name,
+                severity: rule.severity,
+                action: rule.action,
+            };
+            if matched.push(found).is_err() {
+                return (matched, true);
+            }
+        }
+    }
+    (matched, false)
+}
+
+/// Whether signature `sig` of the pack at `p` in [`PACKS`] is among `hits`
+fn signature_hit(p: usize, pack: &SignaturePack, hits: &[SigHit], sig: SigIdx) -> bool {
+    pack.signatures
+        .get(sig as usize)
+        .is_some_and(|&(table, index)| {
+            hits.contains(&SigHit {
+                pack: p as u8,
+                table,
+                index,
+            })
+        })
+}
+
+/// Signatures [`RecentSigs`] remembers
+pub const RECENT_SIGS: usize = 16;
+
+/// When each signature a `within` node looks back for last matched, on any
+/// device. Timestamps are milliseconds since boot (`u32`, wrapping).
+#[derive(Debug, Clone)]
+pub struct RecentSigs<const N: usize = RECENT_SIGS> {
+    seen: Vec<(SigHit, u32), N>,
+}
+
+impl<const N: usize> RecentSigs<N> {
+    pub const fn new() -> Self {
+        Self { seen: Vec::new() }
+    }
+
+    /// Note a detection's `hits` as seen at `now_ms`. Signatures no `within`
+    /// node names are skipped; when full, the one seen longest ago is
+    /// replaced.
+    pub fn record(&mut self, hits: &[SigHit], now_ms: u32) {
+        for &hit in hits {
+            let Some(pack) = PACKS.get(hit.pack as usize) else {
+                continue;
+            };
+            let entry = (hit.table, hit.index);
+            let looked_for = pack
+                .signatures
+                .iter()
+                .position(|&sig| sig == entry)
+                .is_some_and(|sig| pack.rules.looks_back(sig as SigIdx));
+            if !looked_for {
+                continue;
+            }
+            if let Some(seen) = self.seen.iter_mut().find(|(h, _)| *h == hit) {
+                seen.1 = now_ms;
+            } else if let Err(fresh) = self.seen.push((hit, now_ms)) {
+                let stalest = self
+                    .seen
+                    .iter_mut()
+                    .max_by_key(|(_, at)| now_ms.wrapping_sub(*at));
+                if let Some(stalest) = stalest {
+                    *stalest = fresh;
+                }
+            }
+        }
+    }
+
+    /// Whether `hit` was recorded in the `secs` seconds up to `now_ms`
+    pub fn seen_within(&self, hit: SigHit, secs: u16, now_ms: u32) -> bool {
+        self.seen
+            .iter()
+            .any(|&(h, at)| h == hit && now_ms.wrapping_sub(at) <= secs as u32 * 1000)
+    }
+}
+
+impl<const N: usize> Default for RecentSigs<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A detection as a rule sees it: the compiled-in signatures it matched
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyntheticEvent<'e> {
+    pub hits: &'e [SigHit],
+}
+
+/// Dry-run `rule` of `db`, written against the pack at `pack` in [`PACKS`],
+/// over `events`: the number of events it matches. Nothing is reported, and
+/// `within` nodes only see each event's own hits.
+pub fn test_rule<'e>(
+    db: &RuleDb,
+    rule: &Rule,
+    pack: usize,
+    events: impl IntoIterator<Item = SyntheticEvent<'e>>,
+) -> u16 {
+    let Some(signatures) = PACKS.get(pack) else {
+        return 0;
+    };
+    let matched = events
+        .into_iter()
+        .filter(|event| db.matches(rule, |sig| signature_hit(pack, signatures, event.hits, sig)));
+    matched.count().min(u16::MAX as usize) as u16
+}
+
+/// Detections kept for rule dry runs
+pub const LOGGED_EVENTS: usize = 32;
+
+/// The compiled-in signature matches of the latest detections, oldest
+/// first, for [`test_rule`]
+#[derive(Debug, Clone)]
+pub struct EventLog<const N: usize = LOGGED_EVENTS> {
+    events: Deque<Vec<SigHit, MAX_HITS>, N>,
+}
+
+impl<const N: usize> EventLog<N> {
+    pub const fn new() -> Self {
+        Self {
+            events: Deque::new(),
+        }
+    }
+
+    /// Log a detection's hits, dropping the oldest once full. Detections
+    /// without compiled-in matches are skipped.
+    pub fn push(&mut self, hits: &Vec<SigHit, MAX_HITS>) {
+        if hits.is_empty() {
+            return;
+        }
+        if self.events.is_full() {
+            self.events.pop_front();
+        }
+        let _ = self.events.push_back(hits.clone());
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    pub fn events(&self) -> impl Iterator<Item = SyntheticEvent<'_>> {
+        self.events.iter().map(|hits| SyntheticEvent { hits })
+    }
+}
+
+impl<const N: usize> Default for EventLog<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The alert for a detection that satisfied `rules`: the action of the most
+/// severe rule that sets one (the first among equals), else
+/// [`RuleAction::DEFAULT`].
+pub fn alert_action(rules: &[RuleMatch]) -> RuleAction {
+    rules
+        .iter()
+        .filter_map(|rule| Some((rule.severity, rule.action?)))
+        .rev()
+        .max_by_key(|&(severity, _)| severity)
+        .map_or(RuleAction::DEFAULT, |(_, action)| action)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::defaults::trackers;
+
+    fn rule(len: u16) -> Rule<'static> {
+        Rule {
+            id: "test",
+            name: "Test",
+            severity: Severity::Medium,
+            action: None,
+            start: 0,
+            len,
+        }
+    }
+
+    fn eval(nodes: &[ExprNode], hits: &[SigIdx]) -> bool {
+        let db = RuleDb {
+            nodes,
+            ..RuleDb::EMPTY
+        };
+        db.matches(&rule(nodes.len() as u16), |sig| hits.contains(&sig))
+    }
+
+    // ── Expression evaluation ───────────────────────────────────────
+
+    #[test]
+    fn post_order_expressions() {
+        use ExprNode::*;
+        // allOf(0, anyOf(1, 2), not 3)
+        let nodes = [Sig(0), Sig(1), Sig(2), AnyOf(2), Sig(3), Not, AllOf(3)];
+        assert!(eval(&nodes, &[0, 2]));
+        assert!(eval(&nodes, &[0, 1, 2]));
+        assert!(!eval(&nodes, &[0]));
+        assert!(!eval(&nodes, &[1, 2]));
+        assert!(!eval(&nodes, &[0, 1, 3]));
+        assert!(eval(&[Sig(5)], &[5]));
+    }
+
+    #[test]
+    fn at_least_counts_true_operands() {
+        use ExprNode::*;
+        let nodes = [Sig(0), Sig(1), Sig(2), AtLeast { n: 2, count: 3 }];
+        assert!(eval(&nodes, &[0, 2]));
+        assert!(eval(&nodes, &[0, 1, 2]));
+        assert!(!eval(&nodes, &[1]));
+        assert!(!eval(&nodes, &[]));
+
+        let mut builder = RuleBuilder::new("test");
+        let operands = [sig(0), sig(1), sig(2)];
+        builder
+            .rule("a", "A", Severity::Low, None, &at_least(2, &operands))
+            .unwrap();
+        assert!(builder
+            .rule("b", "B", Severity::Low, None, &at_least(4, &operands))
+            .is_err());
+        assert!(builder
+            .rule("b", "B", Severity::Low, None, &at_least(0, &operands))
+            .is_err());
+        let set = builder.build();
+        assert_eq!(set.db().nodes, nodes);
+
+        let mut blob = [0u8; 64];
+        let len = set.db().encode("test", &mut blob).unwrap();
+        let parsed = RuleDb::parse(&blob[..len]).unwrap();
+        assert_eq!(parsed.db().nodes, nodes);
+    }
+
+    #[test]
+    fn within_looks_back_for_recent_signatures() {
+        use ExprNode::*;
+        let nodes = [Within { sig: 0, secs: 30 }, Sig(1), AllOf(2)];
+        let db = RuleDb {
+            nodes: &nodes,
+            ..RuleDb::EMPTY
+        };
+        let rule = rule(3);
+        // Without history only the detection itself counts
+        assert!(db.matches(&rule, |sig| sig < 2));
+        assert!(!db.matches(&rule, |sig| sig == 1));
+        assert!(db.matches_recent(&rule, |sig| sig == 1, |sig, secs| sig == 0 && secs == 30));
+        assert!(!db.matches_recent(&rule, |_| false, |_, _| true));
+        assert!(db.looks_back(0));
+        assert!(!db.looks_back(1));
+    }
+
+    #[test]
+    fn recent_sigs_remember_looked_back_signatures() {
+        let (p, pack) = PACKS
+            .iter()
+            .enumerate()
+            .find(|(_, pack)| pack.name == "core-flock")
+            .unwrap();
+        let hit = |sig: usize| {
+            let (table, index) = pack.signatures[sig];
+            SigHit {
+                pack: p as u8,
+                table,
+                index,
+            }
+        };
+        let looked_back: Vec<SigHit, 8> = (0..pack.signatures.len())
+            .filter(|&sig| pack.rules.looks_back(sig as SigIdx))
+            .map(hit)
+            .collect();
+        let ignored = (0..pack.signatures.len())
+            .find(|&sig| !pack.rules.looks_back(sig as SigIdx))
+            .map(hit)
+            .unwrap();
+
+        let mut recent = RecentSigs::<1>::new();
+        recent.record(&[ignored, looked_back[0]], 1_000);
+        assert!(recent.seen_within(looked_back[0], 30, 31_000));
+        assert!(!recent.seen_within(looked_back[0], 30, 31_001));
+        assert!(!recent.seen_within(ignored, 30, 1_000));
+
+        // Full: the stalest signature makes room
+        recent.record(&[looked_back[1]], 2_000);
+        assert!(recent.seen_within(looked_back[1], 1, 2_000));
+        assert!(!recent.seen_within(looked_back[0], 30, 2_000));
+    }
+
+    #[test]
+    fn malformed_expressions_never_match() {
+        use ExprNode::*;
+        assert!(!eval(&[], &[]));
+        assert!(!eval(&[Not], &[]));
+        assert!(!eval(&[Sig(0), AnyOf(2)], &[0]));
+        assert!(!eval(&[Sig(0), AllOf(0)], &[0]));
+        assert!(!eval(&[Sig(0), AtLeast { n: 1, count: 2 }], &[0]));
+        // Unbalanced: two values left
+        assert!(!eval(&[Sig(0), Sig(1)], &[0, 1]));
+        // Deeper than the stack
+        let deep = [Sig(0); MAX_DEPTH + 1];
+        assert!(!eval(&deep, &[0]));
+        // Range past the nodes
+        let db = RuleDb {
+            nodes: &[Sig(0)],
+            ..RuleDb::EMPTY
+        };
+        assert!(!db.matches(&rule(2), |_| true));
+    }
+
+    // ── Compiled-in rules ───────────────────────────────────────────
+
+    #[test]
+    fn validate_checks_ranges_shape_and_signatures() {
+        use ExprNode::*;
+        let nodes = [Sig(0), Sig(2), AnyOf(2)];
+        let valid = |len, sig_count, by_sig: &[(SigIdx, u16)]| {
+            let rules = [rule(len)];
+            let db = RuleDb {
+                nodes: &nodes,
+                rules: &rules,
+                by_sig,
+            };
+            db.validate(sig_count)
+        };
+        let index = [(0, 0), (2, 0)];
+        assert!(valid(3, 3, &index));
+        // Sig(2) names a signature the pack doesn't have
+        assert!(!valid(3, 2, &index));
+        assert!(!valid(4, 3, &index));
+        assert!(!valid(2, 3, &index));
+        // Index missing a signature, unsorted, or naming a missing rule
+        assert!(!valid(3, 3, &[(0, 0)]));
+        assert!(!valid(3, 3, &[(2, 0), (0, 0)]));
+        assert!(!valid(3, 3, &[(0, 0), (2, 0), (2, 1)]));
+        assert!(RuleDb::EMPTY.validate(0));
+        for pack in PACKS {
+            assert!(pack.rules.validate(pack.signatures.len()), "{}", pack.name);
+        }
+    }
+
+    #[test]
+    fn compiled_rules_resolve_signature_hits() {
+        let pack = PACKS
+            .iter()
+            .position(|pack| pack.name == "trackers")
+            .unwrap() as u8;
+        let tile = SigHit {
+            pack,
+            table: SigTable::BleServiceUuids16,
+            index: trackers::SIG_IDX_TILE_FEEC as u16,
+        };
+        assert_eq!(tile.description(), "Tile tracker");
+        let (rules, truncated) = evaluate_rules::<MAX_RULE_MATCHES>(&[tile]);
+        assert!(!truncated);
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].name, "Tile Tracker");
+
+        // The same position in another pack is a different signature
+        let other = SigHit { pack: 0, ..tile };
+        assert!(evaluate_rules::<MAX_RULE_MATCHES>(&[other]).0.is_empty());
+
+        // No room for the match
+        assert_eq!(evaluate_rules::<0>(&[tile]), (Vec::new(), true));
+    }
+
+    // ── Builder ─────────────────────────────────────────────────────
+
+    #[test]
+    fn builder_flattens_expression_trees() {
+        use ExprNode::*;
+        let mut builder = RuleBuilder::new("test");
+        let either = [sig(1), sig(2)];
+        let absent = sig(3);
+        let operands = [sig(0), any_of(&either), not(&absent)];
+        let detect = all_of(&operands);
+        builder
+            .rule("a", "A", Severity::High, None, &detect)
+            .unwrap()
+            .rule("b", "B", Severity::Low, None, &sig(5))
+            .unwrap();
+        let set = builder.build();
+        let db = set.db();
+        let expected = [
+            Sig(0),
+            Sig(1),
+            Sig(2),
+            AnyOf(2),
+            Sig(3),
+            Not,
+            AllOf(3),
+            Sig(5),
+        ];
+        assert_eq!(db.nodes, expected);
+        assert_eq!((db.rules[1].start, db.rules[1].len), (7, 1));
+        assert!(db.matches(&db.rules[0], |sig| sig == 0 || sig == 2));
+    }
+
+    #[test]
+    fn reverse_index_selects_rules_using_signatures() {
+        let mut builder = RuleBuilder::new("test");
+        let pair = [sig(1), sig(2)];
+        let absent = sig(2);
+        builder
+            .rule("a", "A", Severity::Low, None, &any_of(&pair))
+            .unwrap()
+            .rule("b", "B", Severity::Low, None, &sig(3))
+            .unwrap()
+            .rule("c", "C", Severity::Low, None, &not(&absent))
+            .unwrap();
+        let set = builder.build();
+        let db = set.db();
+        assert_eq!(db.by_sig, [(1, 0), (2, 0), (2, 2), (3, 1)]);
+        assert!(db.validate(4));
+        assert_eq!(db.rules_using([3, 1]).unwrap().as_slice(), [0, 1]);
+        assert_eq!(db.rules_using([2]).unwrap().as_slice(), [0, 2]);
+        assert!(db.rules_using([0]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn builder_rejects_invalid_expressions() {
+        let mut builder = RuleBuilder::new("test");
+        let empty = any_of(&[]);
+        assert!(builder.rule("a", "A", Severity::Low, None, &empty).is_err());
+        let wide = [sig(0); 256];
+        assert!(builder
+            .rule("a", "A", Severity::Low, None, &all_of(&wide))
+            .is_err());
+        // 17 operands on the stack at once
+        let deep = [sig(0); MAX_DEPTH + 1];
+        assert!(builder
+            .rule("a", "A", Severity::Low, None, &any_of(&deep))
+            .is_err());
+        let set = builder.build();
+        assert!(set.db().nodes.is_empty());
+        assert!(set.db().rules.is_empty());
+    }
+
+    #[test]
+    fn builder_reproduces_compiled_rules() {
+        let pack = PACKS.iter().find(|pack| pack.name == "trackers").unwrap();
+        let file_index = |index: usize| {
+            let entry = (SigTable::BleServiceUuids16, index as u16);
+            let sig_idx = pack.signatures.iter().position(|&sig| sig == entry);
+            sig(sig_idx.unwrap() as SigIdx)
+        };
+        let tile = [
+            file_index(trackers::SIG_IDX_TILE_FEEC),
+            file_index(trackers::SIG_IDX_TILE_FEED),
+        ];
+        let compiled = pack
+            .rules
+            .rules
+            .iter()
+            .find(|rule| rule.id == "tile-tracker")
+            .unwrap();
+        let mut builder = RuleBuilder::new(pack.name);
+        builder
+            .rule(
+                compiled.id,
+                compiled.name,
+                compiled.severity,
+                None,
+                &any_of(&tile),
+            )
+            .unwrap();
+        let set = builder.build();
+        let start = compiled.start as usize;
+        assert_eq!(
+            set.db().nodes,
+            &pack.rules.nodes[start..start + compiled.len as usize]
+        );
+    }
+
+    // ── Blobs ───────────────────────────────────────────────────────
+
+    #[test]
+    fn compiled_rules_round_trip_through_blobs() {
+        for pack in PACKS.iter().filter(|pack| !pack.rules.rules.is_empty()) {
+            let mut blob = [0u8; 1024];
+            let len = pack.rules.encode(pack.name, &mut blob).unwrap();
+            let set = RuleDb::parse(&blob[..len]).unwrap();
+            assert_eq!(set.pack, pack.name);
+            assert_eq!(set.db().nodes, pack.rules.nodes);
+            assert_eq!(set.db().rules, pack.rules.rules);
+            assert_eq!(set.db().by_sig, pack.rules.by_sig);
+        }
+    }
+
+    #[test]
+    fn malformed_blobs_are_rejected() {
+        use ExprNode::*;
+        let rules = [Rule {
+            action: Some(RuleAction {
+                beeps: 2,
+                pattern: BeepPattern::Long,
+                led: Some(LedColor::Purple),
+            }),
+            ..rule(3)
+        }];
+        let db = RuleDb {
+            nodes: &[Sig(0), Sig(1), AllOf(2)],
+            rules: &rules,
+            by_sig: &[(0, 0), (1, 0)],
+        };
+        let mut blob = [0u8; 64];
+        let len = db.encode("test", &mut blob).unwrap();
+        {
+            let set = RuleDb::parse(&blob[..len]).unwrap();
+            assert_eq!(set.db().rules, &rules);
+            assert_eq!(set.db().by_sig, db.by_sig);
+            assert!(set.db().matches(&rules[0], |sig| sig < 2));
+        }
+
+        // Truncated or padded
+        for end in 0..len {
+            assert_eq!(
+                RuleDb::parse(&blob[..end]).unwrap_err(),
+                AirhoundError::Malformed
+            );
+        }
+        assert!(RuleDb::parse(&blob[..len + 1]).is_err());
+
+        // Newer format
+        let mut newer = blob;
+        newer[4] = FORMAT_VERSION + 1;
+        assert_eq!(
+            RuleDb::parse(&newer[..len]).unwrap_err(),
+            AirhoundError::Unsupported
+        );
+
+        // Expression past the nodes, and one that leaves two values
+        let short = RuleDb {
+            nodes: &[Sig(0), Sig(1)],
+            rules: &[rule(3)],
+            by_sig: &[],
+        };
+        let len = short.encode("test", &mut blob).unwrap();
+        assert_eq!(
+            RuleDb::parse(&blob[..len]).unwrap_err(),
+            AirhoundError::Malformed
+        );
+        let unbalanced = RuleDb {
+            nodes: &[Sig(0), Sig(1)],
+            rules: &[rule(2)],
+            by_sig: &[],
+        };
+        let len = unbalanced.encode("test", &mut blob).unwrap();
+        assert_eq!(
+            RuleDb::parse(&blob[..len]).unwrap_err(),
+            AirhoundError::Malformed
+        );
+
+        // Too small to encode into
+        assert_eq!(
+            db.encode("test", &mut blob[..8]),
+            Err(AirhoundError::BufferTooSmall)
+        );
+    }
+
+    #[test]
+    fn blob_overflowing_the_signature_index_is_rejected() {
+        use ExprNode::*;
+        // 128 signatures chained pairwise: 255 nodes, 128 index entries
+        let mut nodes = Vec::<ExprNode, MAX_BLOB_NODES>::new();
+        nodes.push(Sig(0)).unwrap();
+        for sig in 1..128 {
+            nodes.push(Sig(sig)).unwrap();
+            nodes.push(AnyOf(2)).unwrap();
+        }
+        let mut blob = [0u8; 1024];
+
+        // Two rules over the same nodes fill the index exactly
+        let shared = [rule(nodes.len() as u16); 2];
+        let db = RuleDb {
+            nodes: &nodes,
+            rules: &shared,
+            by_sig: &[],
+        };
+        let len = db.encode("test", &mut blob).unwrap();
+        assert_eq!(
+            RuleDb::parse(&blob[..len]).unwrap().db().by_sig.len(),
+            MAX_BLOB_NODES
+        );
+
+        // A third can't be indexed, so it could never be evaluated
+        let shared = [rule(nodes.len() as u16); 3];
+        let db = RuleDb {
+            rules: &shared,
+            ..db
+        };
+        let len = db.encode("test", &mut blob).unwrap();
+        assert_eq!(
+            RuleDb::parse(&blob[..len]).unwrap_err(),
+            AirhoundError::Malformed
+        );
+    }
+
+    #[test]
+    fn well_formed_expressions() {
+        use ExprNode::*;
+        assert!(well_formed(&[Sig(0)]));
+        assert!(well_formed(&[Sig(0), Sig(1), AnyOf(2), Not]));
+        assert!(!well_formed(&[]));
+        assert!(!well_formed(&[Not]));
+        assert!(!well_formed(&[Sig(0), AnyOf(0)]));
+        assert!(!well_formed(&[Sig(0), AllOf(2)]));
+        assert!(well_formed(&[Sig(0), Sig(1), AtLeast { n: 2, count: 2 }]));
+        assert!(!well_formed(&[Sig(0), Sig(1), AtLeast { n: 0, count: 2 }]));
+        assert!(!well_formed(&[Sig(0), Sig(1), AtLeast { n: 3, count: 2 }]));
+        assert!(!well_formed(&[Sig(0), AtLeast { n: 1, count: 2 }]));
+        assert!(well_formed(&[Within { sig: 0, secs: 30 }, Not]));
+        assert!(!well_formed(&[Sig(0), Sig(1)]));
+        assert!(!well_formed(&[Sig(0); MAX_DEPTH + 1]));
+    }
+
+    // ── Dry runs ────────────────────────────────────────────────────
+
+    #[test]
+    fn dry_run_counts_matching_events() {
+        let p = PACKS
+            .iter()
+            .position(|pack| pack.name == "trackers")
+            .unwrap();
+        let pack = PACKS[p];
+        let hit = |table, index: usize| SigHit {
+            pack: p as u8,
+            table,
+            index: index as u16,
+        };
+        let tile = hit(SigTable::BleServiceUuids16, trackers::SIG_IDX_TILE_FEEC);
+        let airtag = hit(SigTable::BleAdPatterns, trackers::SIG_IDX_FINDMY_OFFLINE_AD);
+        let sig_of = |hit: SigHit| {
+            let entry = (hit.table, hit.index);
+            pack.signatures
+                .iter()
+                .position(|&sig| sig == entry)
+                .unwrap() as SigIdx
+        };
+
+        // A prospective rule: Tile without an AirTag in the same event
+        let mut builder = RuleBuilder::new(pack.name);
+        let absent = sig(sig_of(airtag));
+        let operands = [sig(sig_of(tile)), not(&absent)];
+        builder
+            .rule(
+                "lone-tile",
+                "Lone Tile",
+                Severity::Low,
+                None,
+                &all_of(&operands),
+            )
+            .unwrap();
+        let set = builder.build();
+        assert_eq!(set.pack_index(), Some(p));
+
+        let mut log = EventLog::<2>::new();
+        log.push(&Vec::from_slice(&[tile]).unwrap());
+        log.push(&Vec::new());
+        log.push(&Vec::from_slice(&[tile, airtag]).unwrap());
+        assert_eq!(log.len(), 2);
+        let db = set.db();
+        assert_eq!(test_rule(&db, &db.rules[0], p, log.events()), 1);
+
+        // The oldest event is dropped once full
+        log.push(&Vec::from_slice(&[airtag]).unwrap());
+        assert_eq!(test_rule(&db, &db.rules[0], p, log.events()), 0);
+        let hits = [tile];
+        let synthetic = [SyntheticEvent { hits: &hits }; 3];
+        assert_eq!(test_rule(&db, &db.rules[0], p, synthetic), 3);
+    }
+
+    // ── Alert actions ───────────────────────────────────────────────
+
+    #[test]
+    fn escalated_actions_beep_longer() {
+        let escalated = RuleAction::DEFAULT.escalated();
+        assert_eq!((escalated.beeps, escalated.pattern), (2, BeepPattern::Long));
+        let loudest = RuleAction {
+            beeps: MAX_BEEPS,
+            ..RuleAction::DEFAULT
+        };
+        assert_eq!(loudest.escalated().beeps, MAX_BEEPS);
+        let silent = RuleAction {
+            beeps: 0,
+            led: Some(LedColor::Red),
+            ..RuleAction::DEFAULT
+        };
+        assert_eq!(silent.escalated().beeps, 0);
+        assert_eq!(silent.escalated().led, Some(LedColor::Red));
+    }
+
+    #[test]
+    fn alert_action_follows_most_severe_rule() {
+        let siren = RuleAction {
+            beeps: 3,
+            pattern: BeepPattern::Long,
+            led: Some(LedColor::Red),
+        };
+        let chirp = RuleAction {
+            beeps: 2,
+            pattern: BeepPattern::Short,
+            led: Some(LedColor::Blue),
+        };
+        let found = |severity, action| RuleMatch {
+            name: "Test",
+            severity,
+            action,
+        };
+        assert_eq!(alert_action(&[]), RuleAction::DEFAULT);
+        assert_eq!(
+            alert_action(&[found(Severity::High, None)]),
+            RuleAction::DEFAULT
+        );
+        let rules = [
+            found(Severity::Medium, Some(chirp)),
+            found(Severity::High, Some(siren)),
+            found(Severity::High, Some(chirp)),
+            found(Severity::High, None),
+        ];
+        assert_eq!(alert_action(&rules), siren);
+        assert_eq!(alert_action(&rules[..1]), chirp);
+    }
+}
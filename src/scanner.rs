@@ -4,29 +4,361 @@
 /// WiFi: ieee80211 crate for 802.11 frame parsing.
 /// BLE: AD structure parser for advertisement data.
 ///
+/// WiFi frames arrive through [`parse_wifi_frame`], or
+/// [`parse_wifi_frame_with_fcs`] for sniffer frames that still carry their FCS
+/// (stripped, and verified with [`fcs_valid`] when `fcs_check` is on; a bad
+/// frame is `AirhoundError::BadChecksum`). Beacon and probe IEs are walked with
+/// the bounds-checked [`ie::elements`] iterator into vendor elements
+/// ([`VendorIe`]), WPS identity ([`WpsInfo`]), Remote ID, the P2P role, the
+/// mesh or P2P group [`NetworkKind`], capability, security and beacon timing.
+/// Data frames are attributed by their ToDS/FromDS bits ([`data_addresses`]):
+/// relayed traffic reports its source as `mac` and the transmitter as `relay`.
+/// Action frames are classified by [`action_category`].
+///
+/// BLE advertisements are parsed by [`BleAdvParser`] (service UUID lists,
+/// service data, iBeacon, Eddystone, Find My, TX power; `parse_extended` for
+/// BLE 5 extended reports). An AD structure overrunning the data is
+/// `AirhoundError::Malformed`, so the firmware trims each report with
+/// [`complete_ad_structures`] first. [`ScanResponseCache`] keeps the latest
+/// advertisement and scan response per address so either parses with both.
+///
+/// Bluetooth Classic inquiry results ([`BtClassicEvent`], EIR via
+/// [`parse_eir`]) are parsed here, but nothing on-device produces them: the
+/// controller runs BLE-only. [`ChannelStats`] holds the per-channel counters of
+/// frames heard and WiFi results matched.
+///
 /// Hardware-specific code (sniffer callback, channel hopping, BLE event handler)
 /// lives in the firmware binary (`main.rs`).
+use core::sync::atomic::{AtomicU32, Ordering};
+
 use heapless::Vec;
+use serde::{Deserialize, Serialize};
+
+use crate::beacon::BeaconSummary;
+use crate::crc::crc32;
+use crate::error::AirhoundError;
+use crate::remote_id::{self, RemoteId};
 
 use ieee80211::match_frames;
 use ieee80211::mgmt_frame::{BeaconFrame, ProbeRequestFrame, ProbeResponseFrame};
 
-/// WiFi channels to scan (2.4 GHz only — ESP32/ESP32-S3 promiscuous mode is 2.4 GHz)
-pub const WIFI_CHANNELS: &[u8] = &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13];
-
 /// Default dwell time per channel in milliseconds.
 /// 120ms ensures reliable beacon capture (beacons broadcast every ~100ms).
-/// Full cycle: 13 channels × 120ms = 1.56s.
+/// Full cycle with the default ETSI plan ([`crate::channel::Region`]):
+/// 13 channels × 120ms = 1.56s. [`crate::channel::DwellScheduler`] adapts
+/// it per channel from there.
 pub const DEFAULT_DWELL_MS: u64 = 120;
 
+/// Highest 2.4 GHz WiFi channel (Japan's channel 14)
+pub const MAX_CHANNEL: u8 = 14;
+
+/// Per-channel WiFi activity since boot: frames the sniffer heard on each
+/// channel, and scan results from it that matched. Shows the user where
+/// activity concentrates and gives channel hopping something to weigh
+/// dwell time by. All state is atomic, so the sniffer ISR can count
+/// without blocking.
+pub struct ChannelStats {
+    frames: [AtomicU32; MAX_CHANNEL as usize],
+    matches: [AtomicU32; MAX_CHANNEL as usize],
+}
+
+impl ChannelStats {
+    pub const fn new() -> Self {
+        Self {
+            frames: [const { AtomicU32::new(0) }; MAX_CHANNEL as usize],
+            matches: [const { AtomicU32::new(0) }; MAX_CHANNEL as usize],
+        }
+    }
+
+    /// Count a frame heard on `channel`. Channels outside 1–14 are ignored.
+    pub fn record_frame(&self, channel: u8) {
+        if let Some(count) = Self::slot(&self.frames, channel) {
+            count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Count a matched scan result from `channel`.
+    pub fn record_match(&self, channel: u8) {
+        if let Some(count) = Self::slot(&self.matches, channel) {
+            count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Frames heard and matches from `channel` so far; zero outside 1–14
+    pub fn counts(&self, channel: u8) -> (u32, u32) {
+        let load = |counts: &[AtomicU32]| {
+            Self::slot(counts, channel).map_or(0, |c| c.load(Ordering::Relaxed))
+        };
+        (load(&self.frames), load(&self.matches))
+    }
+
+    /// `(channel, frames, matches)` for every channel with a frame heard,
+    /// lowest channel first
+    pub fn snapshot(&self) -> Vec<(u8, u32, u32), { MAX_CHANNEL as usize }> {
+        (1..=MAX_CHANNEL)
+            .zip(self.frames.iter().zip(&self.matches))
+            .map(|(channel, (frames, matches))| {
+                let frames = frames.load(Ordering::Relaxed);
+                (channel, frames, matches.load(Ordering::Relaxed))
+            })
+            .filter(|&(_, frames, _)| frames > 0)
+            .collect()
+    }
+
+    fn slot(counts: &[AtomicU32], channel: u8) -> Option<&AtomicU32> {
+        counts.get((channel as usize).checked_sub(1)?)
+    }
+}
+
+impl Default for ChannelStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// BLE scan duty cycle.
+///
+/// The controller listens for `window_ms` out of every `interval_ms`. Equal
+/// values scan continuously (lowest detection latency, highest power draw and
+/// the most airtime taken from WiFi under coexistence). Passive scanning
+/// skips SCAN_REQ, so scan-response data (often the device name) is not seen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BleScanConfig {
+    pub interval_ms: u16,
+    pub window_ms: u16,
+    pub active: bool,
+}
+
+impl BleScanConfig {
+    /// Bluetooth Core spec limits for LE scan interval/window (2.5 ms – 10.24 s),
+    /// rounded to whole milliseconds.
+    pub const MIN_MS: u16 = 3;
+    pub const MAX_MS: u16 = 10_240;
+
+    /// Continuous active scanning — the firmware's original behavior.
+    pub const fn new() -> Self {
+        Self {
+            interval_ms: 100,
+            window_ms: 100,
+            active: true,
+        }
+    }
+
+    /// Relaxed passive scanning for low-power BLE-only mode: a 10% duty
+    /// cycle still catches trackers that advertise every ~2 s within seconds.
+    pub const LOW_POWER: Self = Self {
+        interval_ms: 1000,
+        window_ms: 100,
+        active: false,
+    };
+
+    /// Build a validated config. Returns `None` if either value is outside
+    /// the spec range or the window exceeds the interval.
+    pub fn try_new(interval_ms: u16, window_ms: u16, active: bool) -> Option<Self> {
+        let in_range = |v: u16| (Self::MIN_MS..=Self::MAX_MS).contains(&v);
+        if !in_range(interval_ms) || !in_range(window_ms) || window_ms > interval_ms {
+            return None;
+        }
+        Some(Self {
+            interval_ms,
+            window_ms,
+            active,
+        })
+    }
+
+    /// Fraction of time spent listening, in percent.
+    pub fn duty_cycle_percent(&self) -> u8 {
+        ((self.window_ms as u32 * 100) / self.interval_ms.max(1) as u32) as u8
+    }
+}
+
+impl Default for BleScanConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// A parsed WiFi frame event
 #[derive(Debug, Clone)]
 pub struct WiFiEvent {
+    /// Device the frame came from: the transmitter, or for a data frame
+    /// relayed by an access point or WDS bridge, the original source
     pub mac: [u8; 6],
     pub ssid: heapless::String<33>,
     pub rssi: i8,
     pub channel: u8,
     pub frame_type: FrameType,
+    /// Data frame from the distribution system (FromDS set, ToDS clear) —
+    /// the transmitter is an access point
+    pub from_ds: bool,
+    /// Transmitter of a data frame relayed on behalf of [`mac`](Self::mac)
+    pub relay: Option<[u8; 6]>,
+    /// BSSID of a data frame; none for WDS frames between access points
+    pub bssid: Option<[u8; 6]>,
+    /// Advertised security; only known for beacons and probe responses
+    pub security: Security,
+    /// Leading information elements of a beacon or probe, truncated to
+    /// [`MAX_IE_LEN`]; empty for other frames
+    pub ies: Vec<u8, MAX_IE_LEN>,
+    /// Vendor-specific elements of a beacon or probe, from anywhere in the
+    /// frame
+    pub vendor_ies: Vec<VendorIe, MAX_VENDOR_IES>,
+    /// Device identity from the WPS element of a beacon or probe response
+    pub wps: Option<WpsInfo>,
+    /// Drone Remote ID broadcast in a beacon vendor element
+    pub remote_id: Option<RemoteId>,
+    /// WiFi Direct role from the P2P element of a beacon or probe
+    pub p2p: Option<P2pRole>,
+    /// Kind of network a beacon or probe response announces, when it isn't
+    /// an ordinary access point's
+    pub network: Option<NetworkKind>,
+    /// Capability information field of a beacon or probe response
+    pub capability: Option<u16>,
+    /// TSF timestamp and beacon interval of a beacon
+    pub timing: Option<BeaconTiming>,
+    /// What an action frame is for
+    pub action: Option<ActionCategory>,
+    /// Sequence number of a management frame
+    pub seq: Option<u16>,
+    /// The frame as received, without FCS, truncated to
+    /// [`MAX_FRAME_CAPTURE`] — kept as evidence if it matches, and whole
+    /// with the `raw-payload` feature
+    pub raw: Vec<u8, MAX_FRAME_CAPTURE>,
+    /// Length of the whole frame
+    pub raw_len: u16,
+}
+
+/// Fixed timing fields of a beacon
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BeaconTiming {
+    /// The access point's TSF timer when the beacon was sent, in microseconds
+    pub tsf: u64,
+    /// Beacon interval in time units (1024 µs)
+    pub interval: u16,
+}
+
+/// Frame bytes kept per WiFi event — the header and leading elements of a
+/// beacon or probe, or up to [`MAX_RAW_PAYLOAD`] with `raw-payload`
+pub const MAX_FRAME_CAPTURE: usize = if cfg!(feature = "raw-payload") {
+    MAX_RAW_PAYLOAD
+} else {
+    128
+};
+
+/// Undecoded bytes kept per scan event with the `raw-payload` feature, for
+/// consumers that need more than the parsers extract: an 802.11 frame, or
+/// the AD data of an advertisement (with its scan response) or extended
+/// advertisement
+pub const MAX_RAW_PAYLOAD: usize = 1024;
+
+/// Information element bytes kept per management frame — enough for the SSID,
+/// rates and the first vendor elements that device fingerprints key on
+pub const MAX_IE_LEN: usize = 64;
+
+/// Vendor-specific elements kept per management frame
+pub const MAX_VENDOR_IES: usize = 4;
+
+/// Vendor element bytes kept after the OUI
+pub const MAX_VENDOR_IE_DATA: usize = 8;
+
+/// Microsoft's OUI, carried by the WPA, WMM and WPS elements of nearly
+/// every access point (WPS is parsed separately, see [`parse_wps`])
+const MICROSOFT_OUI: [u8; 3] = [0x00, 0x50, 0xF2];
+
+/// Vendor type of the WPS element under [`MICROSOFT_OUI`]
+const WPS_OUI_TYPE: u8 = 0x04;
+
+/// WPS attribute text kept per field
+pub const MAX_WPS_TEXT: usize = 32;
+
+/// WPS attribute types of the text fields kept
+const WPS_DEVICE_NAME: u16 = 0x1011;
+const WPS_MANUFACTURER: u16 = 0x1021;
+const WPS_MODEL_NAME: u16 = 0x1023;
+
+/// Wi-Fi Alliance OUI and the vendor type of its P2P (WiFi Direct) element
+const WFA_OUI: [u8; 3] = [0x50, 0x6F, 0x9A];
+const P2P_OUI_TYPE: u8 = 0x09;
+
+/// P2P Capability attribute, and the Group Owner bit of its group capability
+const P2P_CAPABILITY: u8 = 2;
+const P2P_GROUP_OWNER: u8 = 0x01;
+
+/// Role a WiFi Direct (P2P) element advertises. Body cameras, printers and
+/// phones sharing a connection run their own P2P group, beaconing a
+/// `DIRECT-xy-<name>` SSID as its owner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum P2pRole {
+    /// P2P device looking for peers, not owning a group
+    Device,
+    /// Owner of a P2P group, acting as its access point
+    GroupOwner,
+}
+
+/// Kind of network a beacon or probe response announces, other than an
+/// ordinary infrastructure BSS. Municipal camera meshes and body-camera
+/// P2P groups beacon like access points but are neither.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NetworkKind {
+    /// 802.11s mesh station, carrying a Mesh ID element
+    Mesh,
+    /// WiFi Direct group, beaconed by its group owner
+    P2pGroup,
+}
+
+impl NetworkKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NetworkKind::Mesh => "mesh",
+            NetworkKind::P2pGroup => "p2p_group",
+        }
+    }
+}
+
+/// Device identity from a Wi-Fi Protected Setup element (221, 00:50:F2
+/// type 4). Many cameras and body-camera hotspots name their vendor only
+/// here. Fields missing from the element are empty.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WpsInfo {
+    pub manufacturer: heapless::String<MAX_WPS_TEXT>,
+    pub model_name: heapless::String<MAX_WPS_TEXT>,
+    pub device_name: heapless::String<MAX_WPS_TEXT>,
+}
+
+/// A WPS text field signatures can match
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WpsField {
+    Manufacturer,
+    ModelName,
+    DeviceName,
+}
+
+impl WpsInfo {
+    pub const FIELDS: [WpsField; 3] = [
+        WpsField::Manufacturer,
+        WpsField::ModelName,
+        WpsField::DeviceName,
+    ];
+
+    pub fn field(&self, field: WpsField) -> &str {
+        match field {
+            WpsField::Manufacturer => &self.manufacturer,
+            WpsField::ModelName => &self.model_name,
+            WpsField::DeviceName => &self.device_name,
+        }
+    }
+}
+
+/// A vendor-specific information element (221)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VendorIe {
+    pub oui: [u8; 3],
+    /// Leading bytes after the OUI — usually the vendor's element type
+    /// first — truncated to [`MAX_VENDOR_IE_DATA`]
+    pub data: Vec<u8, MAX_VENDOR_IE_DATA>,
 }
 
 /// WiFi frame type classification
@@ -35,6 +367,8 @@ pub enum FrameType {
     Beacon,
     ProbeRequest,
     ProbeResponse,
+    /// Management action frame, see [`ActionCategory`]
+    Action,
     Data,
     Other,
 }
@@ -45,12 +379,314 @@ impl FrameType {
             FrameType::Beacon => "beacon",
             FrameType::ProbeRequest => "probe_req",
             FrameType::ProbeResponse => "probe_resp",
+            FrameType::Action => "action",
             FrameType::Data => "data",
             FrameType::Other => "other",
         }
     }
 }
 
+/// What a management action frame is for, from its category and action
+/// fields
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActionCategory {
+    /// Block Ack session setup and teardown (ADDBA, DELBA) — routine
+    /// traffic between associated stations
+    BlockAck,
+    /// Radio measurement requests and reports (802.11k): neighbor reports,
+    /// link and beacon measurements
+    RadioMeasurement,
+    /// Fine Timing Measurement (802.11mc/az): round-trip timing exchanges
+    /// that range a station to within a metre or two
+    FineTiming,
+    Other,
+}
+
+impl ActionCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ActionCategory::BlockAck => "block_ack",
+            ActionCategory::RadioMeasurement => "radio_measurement",
+            ActionCategory::FineTiming => "ftm",
+            ActionCategory::Other => "other",
+        }
+    }
+}
+
+/// Security advertised by a beacon or probe response, weakest first
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Security {
+    /// Not carried by this frame type
+    #[default]
+    Unknown,
+    Open,
+    Wep,
+    /// WPA1 vendor IE without RSN
+    Wpa,
+    /// RSN with PSK or 802.1X key management
+    Wpa2,
+    /// RSN with SAE or Suite B key management
+    Wpa3,
+}
+
+impl Security {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Security::Unknown => "unknown",
+            Security::Open => "open",
+            Security::Wep => "wep",
+            Security::Wpa => "wpa",
+            Security::Wpa2 => "wpa2",
+            Security::Wpa3 => "wpa3",
+        }
+    }
+}
+
+/// Offset of the first information element in a beacon or probe response:
+/// 24-byte header, 8-byte timestamp, 2-byte interval, 2-byte capability
+const MGMT_IE_OFFSET: usize = 36;
+
+/// Offset of the first information element in a probe request, which has no
+/// fixed fields after the 24-byte header
+const PROBE_REQ_IE_OFFSET: usize = 24;
+
+/// Offset of the TSF timestamp in a beacon or probe response
+const TIMESTAMP_OFFSET: usize = 24;
+
+/// Offset of the beacon interval in a beacon or probe response
+const BEACON_INTERVAL_OFFSET: usize = 32;
+
+/// Offset of the capability information field in a beacon or probe response
+const CAPABILITY_OFFSET: usize = 34;
+
+/// Offset of the category field of an action frame, after the 24-byte
+/// header; its action field follows
+const ACTION_CATEGORY_OFFSET: usize = 24;
+
+/// Management subtypes of an action frame and of one that wants no ack
+const ACTION_SUBTYPES: [u8; 2] = [13, 14];
+
+/// Action frame categories: Block Ack, Public, Radio Measurement, and
+/// Protected Dual of Public Action
+const CATEGORY_BLOCK_ACK: u8 = 3;
+const CATEGORY_PUBLIC: u8 = 4;
+const CATEGORY_RADIO_MEASUREMENT: u8 = 5;
+const CATEGORY_PROTECTED_DUAL: u8 = 9;
+
+/// Public actions of an FTM exchange: the initiator's request and the
+/// responder's measurement
+const FTM_ACTIONS: [u8; 2] = [32, 33];
+
+/// Offset of the sequence control field, after the frame control, duration
+/// and three addresses of a management or data frame
+const SEQ_CTRL_OFFSET: usize = 22;
+
+/// Length of the frame check sequence (CRC-32) trailing a captured frame
+pub const FCS_LEN: usize = 4;
+
+/// Capability information bit set when the BSS requires encryption
+const CAPABILITY_PRIVACY: u16 = 0x0010;
+
+/// RSN AKM suite types (OUI 00-0F-AC) that mean WPA3: SAE, FT-SAE,
+/// Suite B 802.1X, and SAE with the extended key
+const WPA3_AKM_SUITES: [u8; 6] = [8, 9, 12, 13, 24, 25];
+
+/// Classify the security of a beacon or probe response from its capability
+/// field, RSN element (48), and WPA1 vendor element (221, 00:50:F2 type 1).
+/// A network offering both WPA2 and WPA3 (transition mode) counts as WPA3.
+pub fn parse_security(frame: &[u8]) -> Security {
+    if frame.len() < MGMT_IE_OFFSET {
+        return Security::Unknown;
+    }
+    let capability = u16::from_le_bytes([frame[CAPABILITY_OFFSET], frame[CAPABILITY_OFFSET + 1]]);
+
+    let mut wpa = false;
+    for (id, body) in ie::elements(&frame[MGMT_IE_OFFSET..]) {
+        match id {
+            ie::RSN => return rsn_security(body),
+            ie::VENDOR_SPECIFIC if body.starts_with(&[0x00, 0x50, 0xF2, 0x01]) => wpa = true,
+            _ => {}
+        }
+    }
+
+    if wpa {
+        Security::Wpa
+    } else if capability & CAPABILITY_PRIVACY != 0 {
+        Security::Wep
+    } else {
+        Security::Open
+    }
+}
+
+/// 802.11 information elements: `(id, length, payload)` runs following a
+/// management frame's fixed fields. Parsers walk them with [`ie::elements`]
+/// rather than indexing by hand.
+pub mod ie {
+    /// SSID element
+    pub const SSID: u8 = 0;
+    /// Country element
+    pub const COUNTRY: u8 = 7;
+    /// Robust Security Network element
+    pub const RSN: u8 = 48;
+    /// Mesh ID element (802.11s)
+    pub const MESH_ID: u8 = 114;
+    /// Vendor-specific element: a 3-byte OUI, then vendor-defined payload
+    pub const VENDOR_SPECIFIC: u8 = 221;
+
+    /// Iterator over the `(id, payload)` pairs of an information element
+    /// list. Stops at the first element whose length runs past the end, so a
+    /// truncated capture yields its complete elements and nothing more.
+    #[derive(Debug, Clone)]
+    pub struct Elements<'a> {
+        rest: &'a [u8],
+    }
+
+    impl<'a> Elements<'a> {
+        /// Bytes not yet walked — empty once every element was complete
+        pub fn remainder(&self) -> &'a [u8] {
+            self.rest
+        }
+    }
+
+    impl<'a> Iterator for Elements<'a> {
+        type Item = (u8, &'a [u8]);
+
+        fn next(&mut self) -> Option<Self::Item> {
+            let [id, len, rest @ ..] = self.rest else {
+                return None;
+            };
+            let payload = rest.get(..*len as usize)?;
+            self.rest = &rest[payload.len()..];
+            Some((*id, payload))
+        }
+    }
+
+    /// Walk the information elements in `ies`, the frame body after its
+    /// fixed fields.
+    pub fn elements(ies: &[u8]) -> Elements<'_> {
+        Elements { rest: ies }
+    }
+
+    /// Payload of the first vendor-specific element from `oui` with vendor
+    /// type `vendor_type`, after the OUI and type bytes.
+    pub fn vendor(ies: &[u8], oui: [u8; 3], vendor_type: u8) -> Option<&[u8]> {
+        elements(ies)
+            .filter(|&(id, _)| id == VENDOR_SPECIFIC)
+            .find_map(|(_, payload)| match payload {
+                [o0, o1, o2, t, rest @ ..] if [*o0, *o1, *o2] == oui && *t == vendor_type => {
+                    Some(rest)
+                }
+                _ => None,
+            })
+    }
+}
+
+/// Collect the vendor-specific elements among `ies`, skipping Microsoft's
+/// ubiquitous WPA/WMM/WPS elements.
+pub fn parse_vendor_ies(ies: &[u8]) -> Vec<VendorIe, MAX_VENDOR_IES> {
+    ie::elements(ies)
+        .filter(|&(id, body)| {
+            id == ie::VENDOR_SPECIFIC && body.len() >= 3 && body[..3] != MICROSOFT_OUI
+        })
+        .map(|(_, body)| VendorIe {
+            oui: [body[0], body[1], body[2]],
+            data: Vec::from_slice(&body[3..body.len().min(3 + MAX_VENDOR_IE_DATA)])
+                .unwrap_or_default(),
+        })
+        .take(MAX_VENDOR_IES)
+        .collect()
+}
+
+/// Parse the WPS element among `ies`, if there is one.
+pub fn parse_wps(ies: &[u8]) -> Option<WpsInfo> {
+    let mut attrs = ie::vendor(ies, MICROSOFT_OUI, WPS_OUI_TYPE)?;
+
+    // Attributes are big-endian (type, length, value)
+    let mut wps = WpsInfo::default();
+    while let [t0, t1, l0, l1, rest @ ..] = attrs {
+        let len = u16::from_be_bytes([*l0, *l1]) as usize;
+        let Some(value) = rest.get(..len) else {
+            break;
+        };
+        let field = match u16::from_be_bytes([*t0, *t1]) {
+            WPS_MANUFACTURER => Some(&mut wps.manufacturer),
+            WPS_MODEL_NAME => Some(&mut wps.model_name),
+            WPS_DEVICE_NAME => Some(&mut wps.device_name),
+            _ => None,
+        };
+        if let Some(field) = field {
+            *field = wps_text(value);
+        }
+        attrs = &rest[len..];
+    }
+    Some(wps)
+}
+
+/// Classify the P2P element among `ies`, if there is one: a group owner if
+/// its P2P Capability attribute says so, a plain P2P device otherwise.
+pub fn parse_p2p(ies: &[u8]) -> Option<P2pRole> {
+    let mut attrs = ie::vendor(ies, WFA_OUI, P2P_OUI_TYPE)?;
+
+    // Attributes are (id, little-endian length, value)
+    while let [id, l0, l1, rest @ ..] = attrs {
+        let len = u16::from_le_bytes([*l0, *l1]) as usize;
+        let Some(value) = rest.get(..len) else {
+            break;
+        };
+        // Value: device capability, group capability
+        if *id == P2P_CAPABILITY {
+            if value
+                .get(1)
+                .is_some_and(|group| group & P2P_GROUP_OWNER != 0)
+            {
+                return Some(P2pRole::GroupOwner);
+            }
+            break;
+        }
+        attrs = &rest[len..];
+    }
+    Some(P2pRole::Device)
+}
+
+/// Parse a Remote ID vendor element among `ies`, if there is one.
+pub fn parse_remote_id(ies: &[u8]) -> Option<RemoteId> {
+    ie::elements(ies)
+        .find(|&(id, body)| id == ie::VENDOR_SPECIFIC && body.starts_with(&remote_id::WIFI_OUI))
+        .and_then(|(_, body)| RemoteId::parse(&body[3..]))
+}
+
+/// A WPS text attribute: the valid UTF-8 prefix without NUL padding,
+/// truncated to [`MAX_WPS_TEXT`]
+fn wps_text(value: &[u8]) -> heapless::String<MAX_WPS_TEXT> {
+    let text = match core::str::from_utf8(value) {
+        Ok(text) => text,
+        Err(e) => core::str::from_utf8(&value[..e.valid_up_to()]).unwrap_or(""),
+    };
+    crate::protocol::truncate_str(text.trim_end_matches('\0')).0
+}
+
+/// RSN element body: version, group cipher, pairwise suites, AKM suites
+fn rsn_security(body: &[u8]) -> Security {
+    let pairwise = body
+        .get(6..8)
+        .map_or(0, |n| u16::from_le_bytes([n[0], n[1]]));
+    let akm_start = 8 + 4 * pairwise as usize;
+    let Some(count) = body.get(akm_start..akm_start + 2) else {
+        return Security::Wpa2;
+    };
+    let count = u16::from_le_bytes([count[0], count[1]]) as usize;
+    let suites = body.get(akm_start + 2..).unwrap_or(&[]);
+    let wpa3 = suites
+        .chunks_exact(4)
+        .take(count)
+        .any(|s| s[..3] == [0x00, 0x0F, 0xAC] && WPA3_AKM_SUITES.contains(&s[3]));
+    if wpa3 {
+        Security::Wpa3
+    } else {
+        Security::Wpa2
+    }
+}
+
 /// A parsed BLE advertisement event
 #[derive(Debug, Clone)]
 pub struct BleEvent {
@@ -59,25 +695,604 @@ pub struct BleEvent {
     pub rssi: i8,
     /// 16-bit service UUIDs extracted from AD structures
     pub service_uuids_16: Vec<u16, 8>,
+    /// 32-bit service UUIDs extracted from AD structures
+    pub service_uuids_32: Vec<u32, 4>,
+    /// 128-bit service UUIDs, as the numeric value of their canonical
+    /// (big-endian) form
+    pub service_uuids_128: Vec<u128, 2>,
     /// Manufacturer company ID (0 if not present)
     pub manufacturer_id: u16,
+    /// Detection summary if the advertiser is another AirHound unit
+    pub peer: Option<BeaconSummary>,
+    /// Matter commissioning info if the device is in BLE pairing mode
+    pub matter: Option<MatterCommissioning>,
+    /// Advertises Thread commissioning over BLE (TCAT)
+    pub thread_commissioning: bool,
+    /// Drone Remote ID broadcast
+    pub remote_id: Option<RemoteId>,
+    /// iBeacon manufacturer data
+    pub ibeacon: Option<IBeacon>,
+    /// Eddystone service data
+    pub eddystone: Option<Eddystone>,
+    /// Apple Find My accessory state
+    pub findmy: Option<FindMy>,
+    /// Raw advertisement payload, truncated to [`MAX_AD_DATA`]
+    pub ad_data: Vec<u8, MAX_AD_DATA>,
+    /// Random (private or static) advertiser address. The advertisement
+    /// doesn't carry the address type; the caller sets it from the report.
+    pub mac_randomized: bool,
+    /// Expected RSSI at 1 m (dBm): an iBeacon's or Eddystone beacon's
+    /// calibrated power, else the TX Power Level AD — or an extended
+    /// advertisement's transmit power — less [`TX_POWER_LOSS_1M`]
+    pub tx_power: Option<i8>,
+    /// PHY carrying an extended advertisement's data; `None` for legacy
+    /// advertising
+    pub secondary_phy: Option<BlePhy>,
+}
+
+/// Legacy advertising payload size
+pub const MAX_AD_LEN: usize = 31;
+
+/// AD bytes kept per BLE event. Extended advertisements and scan responses
+/// are parsed in full but keep only the legacy size in
+/// [`BleEvent::ad_data`], or up to [`MAX_RAW_PAYLOAD`] with `raw-payload`.
+pub const MAX_AD_DATA: usize = if cfg!(feature = "raw-payload") {
+    MAX_RAW_PAYLOAD
+} else {
+    MAX_AD_LEN
+};
+
+/// BLE physical layer of an extended advertisement's auxiliary packets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlePhy {
+    Le1M,
+    Le2M,
+    /// Long range (S=2 or S=8 coding)
+    LeCoded,
+}
+
+impl BlePhy {
+    /// From the HCI LE Extended Advertising Report's secondary PHY field:
+    /// `None` for 0 (no auxiliary packets) or a reserved value
+    pub fn from_hci(phy: u8) -> Option<Self> {
+        match phy {
+            1 => Some(BlePhy::Le1M),
+            2 => Some(BlePhy::Le2M),
+            3 => Some(BlePhy::LeCoded),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BlePhy::Le1M => "1m",
+            BlePhy::Le2M => "2m",
+            BlePhy::LeCoded => "coded",
+        }
+    }
+}
+
+/// TX power of an HCI LE Extended Advertising Report that doesn't give one
+pub const EXT_ADV_TX_POWER_UNKNOWN: i8 = 127;
+
+/// 16-bit service UUID of Matter BLE commissioning service data
+pub const MATTER_SERVICE_UUID: u16 = 0xFFF6;
+
+/// 16-bit service UUID of Thread commissioning over authenticated TLS (TCAT)
+pub const THREAD_TCAT_SERVICE_UUID: u16 = 0xFFFB;
+
+/// 16-bit service UUID of Eddystone beacon service data
+pub const EDDYSTONE_SERVICE_UUID: u16 = 0xFEAA;
+
+/// Free-space path loss over the first meter at 2.4 GHz, between a
+/// transmit power (or Eddystone's 0 m calibration) and the RSSI at 1 m
+pub const TX_POWER_LOSS_1M: i8 = 41;
+
+/// Apple's company ID, which prefixes iBeacon manufacturer data
+const APPLE_COMPANY_ID: u16 = 0x004C;
+
+/// An Apple iBeacon advertisement. Commercial asset trackers and
+/// retail-analytics beacons use iBeacon framing, usually under their
+/// vendor's default UUID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IBeacon {
+    /// Proximity UUID, as the numeric value of its canonical form
+    pub uuid: u128,
+    pub major: u16,
+    pub minor: u16,
+    /// Calibrated RSSI at 1 m
+    pub power: i8,
+}
+
+impl IBeacon {
+    /// Parse manufacturer data, company ID included:
+    /// `[0x4C, 0x00, 0x02, 0x15, uuid: 16, major: u16, minor: u16, power: i8]`,
+    /// with the UUID, major and minor big-endian.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let [c0, c1, 0x02, 0x15, rest @ ..] = data else {
+            return None;
+        };
+        if u16::from_le_bytes([*c0, *c1]) != APPLE_COMPANY_ID || rest.len() < 21 {
+            return None;
+        }
+        let mut uuid = [0u8; 16];
+        uuid.copy_from_slice(&rest[..16]);
+        Some(Self {
+            uuid: u128::from_be_bytes(uuid),
+            major: u16::from_be_bytes([rest[16], rest[17]]),
+            minor: u16::from_be_bytes([rest[18], rest[19]]),
+            power: rest[20] as i8,
+        })
+    }
+}
+
+/// Calibrated RSSI at 1 m from Eddystone service data following the UUID.
+/// UID (0x00) and URL (0x10) frames carry the power at 0 m in their second
+/// byte; TLM and EID frames carry none.
+fn eddystone_power(data: &[u8]) -> Option<i8> {
+    match data {
+        [0x00 | 0x10, power, ..] => Some((*power as i8).saturating_sub(TX_POWER_LOSS_1M)),
+        _ => None,
+    }
+}
+
+/// Where a Find My accessory stands with its owner
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FindMyState {
+    /// Short offline-finding payload: seen by its owner's devices recently
+    Nearby,
+    /// Full-key offline-finding payload, sent once the accessory has been
+    /// away from its owner for a while — the one a stalker's AirTag sends
+    Separated,
+    /// AirTag not yet paired to an Apple ID
+    Unregistered,
+}
+
+/// Battery level from a Find My status byte
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FindMyBattery {
+    Full,
+    Medium,
+    Low,
+    Critical,
+}
+
+/// An Apple Find My (AirTag and compatible accessory) advertisement
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct FindMy {
+    pub state: FindMyState,
+    /// From the status byte of offline-finding payloads
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub battery: Option<FindMyBattery>,
+}
+
+impl FindMy {
+    /// Parse manufacturer data, company ID included. Offline-finding
+    /// payloads are type 0x12 — length 0x19 when separated, 0x02 when near
+    /// the owner — with the status byte first; an unregistered AirTag sends
+    /// proximity-pairing type 0x07, length 0x19, model byte 0x05.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let [c0, c1, kind, len, first, ..] = data else {
+            return None;
+        };
+        if u16::from_le_bytes([*c0, *c1]) != APPLE_COMPANY_ID {
+            return None;
+        }
+        let state = match (kind, len, first) {
+            (0x12, 0x19, _) => FindMyState::Separated,
+            (0x12, 0x02, _) => FindMyState::Nearby,
+            (0x07, 0x19, 0x05) => {
+                return Some(Self {
+                    state: FindMyState::Unregistered,
+                    battery: None,
+                })
+            }
+            _ => return None,
+        };
+        // Status byte bits 6–7
+        let battery = match first >> 6 {
+            0 => FindMyBattery::Full,
+            1 => FindMyBattery::Medium,
+            2 => FindMyBattery::Low,
+            _ => FindMyBattery::Critical,
+        };
+        Some(Self {
+            state,
+            battery: Some(battery),
+        })
+    }
+}
+
+/// Decoded Eddystone URL characters kept
+pub const MAX_EDDYSTONE_URL: usize = 48;
+
+/// URL scheme prefixes, by an Eddystone-URL frame's scheme byte
+const EDDYSTONE_SCHEMES: [&str; 4] = ["http://www.", "https://www.", "http://", "https://"];
+
+/// Eddystone-URL expansion codes 0x00–0x0D
+const EDDYSTONE_EXPANSIONS: [&str; 14] = [
+    ".com/", ".org/", ".edu/", ".net/", ".info/", ".biz/", ".gov/", ".com", ".org", ".edu", ".net",
+    ".info", ".biz", ".gov",
+];
+
+/// An Eddystone frame (service data for [`EDDYSTONE_SERVICE_UUID`]).
+/// Google's open beacon format, common on generic beacons used for asset
+/// tracking; a UID beacon's namespace usually names the fleet's owner or
+/// the beacon vendor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Eddystone {
+    /// Beacon ID: 10-byte namespace, 6-byte instance
+    Uid {
+        namespace: [u8; 10],
+        instance: [u8; 6],
+    },
+    /// Broadcast URL, decoded and truncated to [`MAX_EDDYSTONE_URL`]
+    Url(heapless::String<MAX_EDDYSTONE_URL>),
+    /// Unencrypted telemetry
+    Tlm {
+        /// Battery voltage, 0 if not supported
+        battery_mv: u16,
+        /// Temperature in 1/256 °C, -32768 if not supported
+        temperature: i16,
+        /// Advertisements sent since power-up
+        adv_count: u32,
+        /// Time since power-up, in 0.1 s
+        uptime_ds: u32,
+    },
+}
+
+impl Eddystone {
+    /// Parse the service data payload following the UUID. EID and other
+    /// frame types aren't recognized.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        match data {
+            [0x00, _power, rest @ ..] if rest.len() >= 16 => {
+                let mut namespace = [0u8; 10];
+                let mut instance = [0u8; 6];
+                namespace.copy_from_slice(&rest[..10]);
+                instance.copy_from_slice(&rest[10..16]);
+                Some(Eddystone::Uid {
+                    namespace,
+                    instance,
+                })
+            }
+            [0x10, _power, scheme, encoded @ ..] => {
+                let mut url = heapless::String::new();
+                let _ = url.push_str(EDDYSTONE_SCHEMES.get(*scheme as usize)?);
+                for &b in encoded {
+                    let pushed = match EDDYSTONE_EXPANSIONS.get(b as usize) {
+                        Some(expansion) => url.push_str(expansion),
+                        None if b.is_ascii_graphic() => url.push(b as char),
+                        None => return None,
+                    };
+                    if pushed.is_err() {
+                        break;
+                    }
+                }
+                Some(Eddystone::Url(url))
+            }
+            [0x20, 0x00, rest @ ..] if rest.len() >= 12 => Some(Eddystone::Tlm {
+                battery_mv: u16::from_be_bytes([rest[0], rest[1]]),
+                temperature: i16::from_be_bytes([rest[2], rest[3]]),
+                adv_count: u32::from_be_bytes([rest[4], rest[5], rest[6], rest[7]]),
+                uptime_ds: u32::from_be_bytes([rest[8], rest[9], rest[10], rest[11]]),
+            }),
+            _ => None,
+        }
+    }
+
+    /// The namespace of a UID frame
+    pub fn namespace(&self) -> Option<&[u8; 10]> {
+        match self {
+            Eddystone::Uid { namespace, .. } => Some(namespace),
+            _ => None,
+        }
+    }
+}
+
+/// Matter commissionable-device advertisement (service data for
+/// [`MATTER_SERVICE_UUID`]). Devices only send this while in pairing mode; the
+/// device type is not advertised over BLE, so signatures key on vendor ID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MatterCommissioning {
+    /// 12-bit discriminator shown on the pairing code
+    pub discriminator: u16,
+    /// CSA-assigned vendor ID
+    pub vendor_id: u16,
+    pub product_id: u16,
+}
+
+impl MatterCommissioning {
+    /// Parse the service data payload following the UUID:
+    /// `[opcode, discriminator|version: u16, vendor_id: u16, product_id: u16, ...]`.
+    /// Only opcode 0 (commissionable) is recognized.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        if data.len() < 7 || data[0] != 0x00 {
+            return None;
+        }
+        Some(Self {
+            discriminator: u16::from_le_bytes([data[1], data[2]]) & 0x0FFF,
+            vendor_id: u16::from_le_bytes([data[3], data[4]]),
+            product_id: u16::from_le_bytes([data[5], data[6]]),
+        })
+    }
+}
+
+/// A Bluetooth Classic (BR/EDR) inquiry response
+#[derive(Debug, Clone)]
+pub struct BtClassicEvent {
+    /// Device address in display order (BR/EDR addresses are always public)
+    pub mac: [u8; 6],
+    /// Remote name, if known: from extended inquiry response data, or a
+    /// remote name request (plain inquiry results don't carry one)
+    pub name: heapless::String<33>,
+    pub rssi: i8,
+    /// 24-bit Class of Device
+    pub class_of_device: u32,
+    /// Manufacturer company ID from extended inquiry response data (0 if
+    /// not present)
+    pub manufacturer_id: u16,
+}
+
+/// Fields of Extended Inquiry Response (EIR) data, the AD-formatted block a
+/// Classic device returns with its inquiry response
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Eir {
+    /// Shortened or complete local name
+    pub name: heapless::String<33>,
+    /// Class of Device structure (0x0D), which some devices repeat in EIR
+    pub class_of_device: Option<u32>,
+    /// Manufacturer company ID (0 if not present)
+    pub manufacturer_id: u16,
+}
+
+/// Parse EIR data. Structures run until a zero length byte, after which the
+/// block is zero padding.
+pub fn parse_eir(data: &[u8]) -> Eir {
+    let mut eir = Eir::default();
+    let mut rest = data;
+    while let [len, tail @ ..] = rest {
+        let Some(structure) = tail.get(..*len as usize) else {
+            break;
+        };
+        rest = &tail[structure.len()..];
+        let [ad_type, value @ ..] = structure else {
+            break;
+        };
+        match ad_type {
+            0x08 | 0x09 => {
+                if let Ok(name) = core::str::from_utf8(value) {
+                    eir.name = crate::protocol::truncate_str(name).0;
+                }
+            }
+            0x0D if value.len() == 3 => {
+                eir.class_of_device = Some(u32::from_le_bytes([value[0], value[1], value[2], 0]));
+            }
+            0xFF if value.len() >= 2 => {
+                eir.manufacturer_id = u16::from_le_bytes([value[0], value[1]]);
+            }
+            _ => {}
+        }
+    }
+    eir
+}
+
+/// Major device class (bits 8–12) of a Class of Device value.
+pub fn cod_major_class(cod: u32) -> u8 {
+    ((cod >> 8) & 0x1F) as u8
+}
+
+/// Minor device class (bits 2–7) of a Class of Device value.
+pub fn cod_minor_class(cod: u32) -> u8 {
+    ((cod >> 2) & 0x3F) as u8
+}
+
+/// HCI "Inquiry Result with RSSI" event code
+pub const HCI_EVT_INQUIRY_RESULT_RSSI: u8 = 0x22;
+
+/// HCI "Extended Inquiry Result" event code
+pub const HCI_EVT_EXTENDED_INQUIRY_RESULT: u8 = 0x2F;
+
+/// Parse an HCI inquiry result event into Classic events.
+///
+/// Handles Inquiry Result with RSSI (0x22) and Extended Inquiry Result (0x2F).
+/// `params` is the event parameter block (after event code and length).
+/// Multi-response events store each field as an array across responses, per
+/// the HCI spec. An extended result's EIR data (see [`parse_eir`]) fills in
+/// the name and manufacturer, and the Class of Device if the header has none.
+pub fn parse_hci_inquiry_event(event_code: u8, params: &[u8]) -> Vec<BtClassicEvent, 4> {
+    let mut events = Vec::new();
+    let Some(&count) = params.first() else {
+        return events;
+    };
+    let n = match event_code {
+        HCI_EVT_INQUIRY_RESULT_RSSI => count as usize,
+        // Extended Inquiry Result always carries exactly one response
+        HCI_EVT_EXTENDED_INQUIRY_RESULT => 1,
+        _ => return events,
+    };
+    if params.len() < 1 + 14 * n {
+        return events;
+    }
+
+    for i in 0..n {
+        let addr = &params[1 + 6 * i..1 + 6 * i + 6];
+        let cod_at = 1 + 8 * n + 3 * i;
+        let cod = &params[cod_at..cod_at + 3];
+        let rssi = params[1 + 13 * n + i] as i8;
+
+        // HCI sends BD_ADDR little-endian
+        let mut mac = [0u8; 6];
+        for (dst, src) in mac.iter_mut().zip(addr.iter().rev()) {
+            *dst = *src;
+        }
+
+        let mut event = BtClassicEvent {
+            mac,
+            name: heapless::String::new(),
+            rssi,
+            class_of_device: u32::from_le_bytes([cod[0], cod[1], cod[2], 0]),
+            manufacturer_id: 0,
+        };
+        if event_code == HCI_EVT_EXTENDED_INQUIRY_RESULT {
+            let eir = parse_eir(&params[1 + 14 * n..]);
+            event.name = eir.name;
+            event.manufacturer_id = eir.manufacturer_id;
+            if event.class_of_device == 0 {
+                event.class_of_device = eir.class_of_device.unwrap_or(0);
+            }
+        }
+        let _ = events.push(event);
+    }
+    events
 }
 
 /// Unified scan event for the filter task
+///
+/// Passed by value through a fixed-capacity channel with no heap to box into,
+/// so the WiFi variant's kept IEs set the size of every slot.
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone)]
 pub enum ScanEvent {
     WiFi(WiFiEvent),
     Ble(BleEvent),
+    BtClassic(BtClassicEvent),
+}
+
+/// Addresses of a data frame, placed according to its ToDS/FromDS bits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DataAddresses {
+    /// Station that put the frame on the air (Address 2)
+    pub transmitter: [u8; 6],
+    /// Station the frame originated from: the transmitter, or the device
+    /// behind the access point (FromDS, Address 3) or WDS bridge (ToDS and
+    /// FromDS, Address 4) relaying it
+    pub source: [u8; 6],
+    /// BSS the frame belongs to (Address 3, 1 or 2 as neither, ToDS or
+    /// FromDS is set); WDS frames name none
+    pub bssid: Option<[u8; 6]>,
+}
+
+/// Resolve the addresses of a data frame. `None` when the frame is too
+/// short to hold the transmitter and source.
+///
+/// Safe to call from ISR context (no allocation, no blocking).
+pub fn data_addresses(frame: &[u8]) -> Option<DataAddresses> {
+    let address =
+        |offset: usize| -> Option<[u8; 6]> { frame.get(offset..offset + 6)?.try_into().ok() };
+    let transmitter = address(10)?;
+    let (source, bssid) = match *frame.get(1)? & 0x03 {
+        0x00 => (transmitter, address(16)),
+        0x01 => (transmitter, address(4)),
+        0x02 => (address(16)?, Some(transmitter)),
+        _ => (address(24)?, None),
+    };
+    Some(DataAddresses {
+        transmitter,
+        source,
+        bssid,
+    })
+}
+
+/// Classify a raw 802.11 frame by its action category. `None` when it is
+/// not an action frame or too short to name a category.
+///
+/// Safe to call from ISR context (no allocation, no blocking).
+pub fn action_category(frame: &[u8]) -> Option<ActionCategory> {
+    let control = *frame.first()?;
+    if (control >> 2) & 0x3 != 0 || !ACTION_SUBTYPES.contains(&(control >> 4)) {
+        return None;
+    }
+    let category = *frame.get(ACTION_CATEGORY_OFFSET)?;
+    let action = frame.get(ACTION_CATEGORY_OFFSET + 1).copied();
+    Some(match category {
+        CATEGORY_BLOCK_ACK => ActionCategory::BlockAck,
+        CATEGORY_RADIO_MEASUREMENT => ActionCategory::RadioMeasurement,
+        CATEGORY_PUBLIC | CATEGORY_PROTECTED_DUAL
+            if action.is_some_and(|action| FTM_ACTIONS.contains(&action)) =>
+        {
+            ActionCategory::FineTiming
+        }
+        _ => ActionCategory::Other,
+    })
+}
+
+/// Whether the trailing frame check sequence of `frame` matches the CRC-32
+/// of the rest. `false` when the frame is too short to hold one.
+///
+/// Safe to call from ISR context (no allocation, no blocking).
+pub fn fcs_valid(frame: &[u8]) -> bool {
+    let Some(body_len) = frame.len().checked_sub(FCS_LEN) else {
+        return false;
+    };
+    let (body, fcs) = frame.split_at(body_len);
+    crc32(body) == u32::from_le_bytes([fcs[0], fcs[1], fcs[2], fcs[3]])
+}
+
+/// The sequence number of a management frame, from its sequence control
+/// field. `None` for other frame types — QoS data frames count per traffic
+/// class, so their numbers skip between counters anyway — and frames too
+/// short to hold one.
+///
+/// Safe to call from ISR context (no allocation, no blocking).
+pub fn sequence_number(frame: &[u8]) -> Option<u16> {
+    if (frame.first()? >> 2) & 0x3 != 0 {
+        return None;
+    }
+    let control = frame.get(SEQ_CTRL_OFFSET..SEQ_CTRL_OFFSET + 2)?;
+    Some(u16::from_le_bytes([control[0], control[1]]) >> 4)
+}
+
+/// [`parse_wifi_frame`] for a frame captured with its trailing frame check
+/// sequence, as the ESP32 sniffer delivers them. The FCS is stripped before
+/// parsing; with `check_fcs`, a frame whose FCS doesn't match is
+/// [`AirhoundError::BadChecksum`] rather than a phantom transmitter.
+///
+/// Safe to call from ISR context (no allocation, no blocking).
+pub fn parse_wifi_frame_with_fcs(
+    frame: &[u8],
+    rssi: i8,
+    channel: u8,
+    check_fcs: bool,
+) -> Result<WiFiEvent, AirhoundError> {
+    let body_len = frame
+        .len()
+        .checked_sub(FCS_LEN)
+        .ok_or(AirhoundError::Malformed)?;
+    if check_fcs && !fcs_valid(frame) {
+        return Err(AirhoundError::BadChecksum);
+    }
+    parse_wifi_frame(&frame[..body_len], rssi, channel)
+}
+
+/// The Mesh ID among `ies`, if there is one. Mesh beacons carry a wildcard
+/// SSID; the Mesh ID names the network instead. An ID that isn't UTF-8
+/// reads as empty.
+pub fn parse_mesh_id(ies: &[u8]) -> Option<&str> {
+    ie::elements(ies)
+        .find(|&(id, _)| id == ie::MESH_ID)
+        .map(|(_, body)| core::str::from_utf8(body).unwrap_or(""))
 }
 
 /// Parse a raw 802.11 frame into a WiFiEvent using the ieee80211 crate.
 ///
 /// Management frames (beacons, probes) are parsed with full SSID extraction.
 /// Data and other frame types fall through to a raw header parse that extracts
-/// the transmitter MAC (Address 2, offset 10) for OUI-prefix matching.
+/// the transmitter MAC (Address 2, offset 10) for OUI-prefix matching; data
+/// frames relayed through an access point or WDS bridge are attributed to
+/// their source instead ([`data_addresses`]), with the transmitter as
+/// [`WiFiEvent::relay`]. Action frames are classified by
+/// [`action_category`]. Beacons and probe responses from an 802.11s mesh or
+/// a WiFi Direct group owner set [`WiFiEvent::network`]; a mesh beacon's
+/// Mesh ID stands in for its empty SSID.
+/// Control frames without a transmitter address (ACK, CTS) are
+/// [`AirhoundError::Unsupported`]; anything else too short to hold one is
+/// [`AirhoundError::Malformed`].
 ///
 /// Safe to call from ISR context (no allocation, no blocking).
-pub fn parse_wifi_frame(frame: &[u8], rssi: i8, channel: u8) -> Option<WiFiEvent> {
+pub fn parse_wifi_frame(frame: &[u8], rssi: i8, channel: u8) -> Result<WiFiEvent, AirhoundError> {
     let result = match_frames! {
         frame,
         beacon = BeaconFrame<'_> => {
@@ -103,22 +1318,75 @@ pub fn parse_wifi_frame(frame: &[u8], rssi: i8, channel: u8) -> Option<WiFiEvent
         }
     };
 
-    match result {
-        Ok(event) => Some(event),
+    let mut event = match result {
+        Ok(mut event) => {
+            let ie_offset = if event.frame_type == FrameType::ProbeRequest {
+                PROBE_REQ_IE_OFFSET
+            } else {
+                event.security = parse_security(frame);
+                event.capability = frame
+                    .get(CAPABILITY_OFFSET..MGMT_IE_OFFSET)
+                    .map(|c| u16::from_le_bytes([c[0], c[1]]));
+                event.wps = frame.get(MGMT_IE_OFFSET..).and_then(parse_wps);
+                event.remote_id = frame.get(MGMT_IE_OFFSET..).and_then(parse_remote_id);
+                if event.frame_type == FrameType::Beacon {
+                    event.timing = parse_beacon_timing(frame);
+                }
+                MGMT_IE_OFFSET
+            };
+            let ies = frame.get(ie_offset..).unwrap_or(&[]);
+            event.ies = Vec::from_slice(&ies[..ies.len().min(MAX_IE_LEN)]).unwrap_or_default();
+            event.vendor_ies = parse_vendor_ies(ies);
+            event.p2p = parse_p2p(ies);
+            if event.frame_type != FrameType::ProbeRequest {
+                if let Some(mesh_id) = parse_mesh_id(ies) {
+                    event.network = Some(NetworkKind::Mesh);
+                    if event.ssid.is_empty() {
+                        let _ = event.ssid.push_str(mesh_id);
+                    }
+                } else if event.p2p == Some(P2pRole::GroupOwner) {
+                    event.network = Some(NetworkKind::P2pGroup);
+                }
+            }
+            event.seq = sequence_number(frame);
+            event
+        }
         Err(_) => {
             // Fallback: extract transmitter MAC (Address 2) from any frame.
             // Minimum 16 bytes: 2 (frame ctrl) + 2 (duration) + 6 (addr1) + 6 (addr2)
-            if frame.len() < 16 {
-                return None;
-            }
-            let frame_type = match (frame[0] >> 2) & 0x3 {
+            let kind = (frame.first().ok_or(AirhoundError::Malformed)? >> 2) & 0x3;
+            let Some(mac) = frame.get(10..16) else {
+                return Err(if kind == 1 {
+                    AirhoundError::Unsupported
+                } else {
+                    AirhoundError::Malformed
+                });
+            };
+            let action = action_category(frame);
+            let frame_type = match kind {
                 2 => FrameType::Data,
+                0 if action.is_some() => FrameType::Action,
                 _ => FrameType::Other,
             };
-            let mac: [u8; 6] = frame[10..16].try_into().ok()?;
-            Some(build_wifi_event(&mac, "", rssi, channel, frame_type))
+            let mac: [u8; 6] = mac.try_into().map_err(|_| AirhoundError::Malformed)?;
+            let mut event = build_wifi_event(&mac, "", rssi, channel, frame_type);
+            event.action = action;
+            event.seq = sequence_number(frame);
+            if frame_type == FrameType::Data {
+                event.from_ds = frame[1] & 0x03 == 0x02;
+                if let Some(addresses) = data_addresses(frame) {
+                    event.mac = addresses.source;
+                    event.relay = (addresses.source != addresses.transmitter)
+                        .then_some(addresses.transmitter);
+                    event.bssid = addresses.bssid;
+                }
+            }
+            event
         }
-    }
+    };
+    event.raw = Vec::from_slice(&frame[..frame.len().min(MAX_FRAME_CAPTURE)]).unwrap_or_default();
+    event.raw_len = frame.len().min(u16::MAX as usize) as u16;
+    Ok(event)
 }
 
 /// Build a WiFiEvent from parsed frame components.
@@ -137,9 +1405,35 @@ fn build_wifi_event(
         rssi,
         channel,
         frame_type,
+        from_ds: false,
+        relay: None,
+        bssid: None,
+        security: Security::Unknown,
+        ies: Vec::new(),
+        vendor_ies: Vec::new(),
+        wps: None,
+        remote_id: None,
+        p2p: None,
+        network: None,
+        capability: None,
+        timing: None,
+        action: None,
+        seq: None,
+        raw: Vec::new(),
+        raw_len: 0,
     }
 }
 
+/// The TSF timestamp and beacon interval of a beacon or probe response.
+fn parse_beacon_timing(frame: &[u8]) -> Option<BeaconTiming> {
+    let tsf = frame.get(TIMESTAMP_OFFSET..BEACON_INTERVAL_OFFSET)?;
+    let interval = frame.get(BEACON_INTERVAL_OFFSET..CAPABILITY_OFFSET)?;
+    Some(BeaconTiming {
+        tsf: u64::from_le_bytes(tsf.try_into().ok()?),
+        interval: u16::from_le_bytes([interval[0], interval[1]]),
+    })
+}
+
 /// Parse BLE advertisement data (AD structures) to extract service UUIDs
 /// and manufacturer-specific data.
 ///
@@ -149,6 +1443,8 @@ fn build_wifi_event(
 ///   0x04/0x05 = Incomplete/Complete list of 32-bit service UUIDs
 ///   0x06/0x07 = Incomplete/Complete list of 128-bit service UUIDs
 ///   0x08/0x09 = Shortened/Complete local name
+///   0x0A      = TX Power Level (dBm)
+///   0x16      = Service data, 16-bit UUID (Matter, Thread commissioning, Eddystone)
 ///   0xFF      = Manufacturer specific data (first 2 bytes = company ID, little-endian)
 pub struct BleAdvParser;
 
@@ -157,21 +1453,46 @@ impl BleAdvParser {
     /// `addr` is the 6-byte advertiser address.
     /// `rssi` is the received signal strength.
     /// `ad_data` is the raw advertisement data bytes.
-    pub fn parse(addr: &[u8; 6], rssi: i8, ad_data: &[u8]) -> BleEvent {
+    ///
+    /// An AD structure running past the end of `ad_data` is
+    /// [`AirhoundError::Malformed`]; a zero length ends the data early, as
+    /// padding does.
+    pub fn parse(addr: &[u8; 6], rssi: i8, ad_data: &[u8]) -> Result<BleEvent, AirhoundError> {
         let mut event = BleEvent {
             mac: *addr,
             name: heapless::String::new(),
             rssi,
             service_uuids_16: Vec::new(),
+            service_uuids_32: Vec::new(),
+            service_uuids_128: Vec::new(),
             manufacturer_id: 0,
+            peer: None,
+            matter: None,
+            thread_commissioning: false,
+            remote_id: None,
+            ibeacon: None,
+            eddystone: None,
+            findmy: None,
+            ad_data: Vec::from_slice(&ad_data[..ad_data.len().min(MAX_AD_DATA)])
+                .unwrap_or_default(),
+            mac_randomized: false,
+            tx_power: None,
+            secondary_phy: None,
         };
 
+        // A beacon's calibrated power beats the raw TX Power Level
+        let mut ad_tx_power = None;
+        let mut beacon_power = None;
+
         let mut pos = 0;
         while pos < ad_data.len() {
             let len = ad_data[pos] as usize;
-            if len == 0 || pos + 1 + len > ad_data.len() {
+            if len == 0 {
                 break;
             }
+            if pos + 1 + len > ad_data.len() {
+                return Err(AirhoundError::Malformed);
+            }
 
             let ad_type = ad_data[pos + 1];
             let data = &ad_data[pos + 2..pos + 1 + len];
@@ -186,16 +1507,53 @@ impl BleAdvParser {
                         i += 2;
                     }
                 }
-                // Shortened or Complete local name
+                // 32-bit service UUID lists
+                0x04 | 0x05 => {
+                    for chunk in data.chunks_exact(4) {
+                        let uuid = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+                        let _ = event.service_uuids_32.push(uuid);
+                    }
+                }
+                // 128-bit service UUID lists, little-endian on air
+                0x06 | 0x07 => {
+                    for chunk in data.chunks_exact(16) {
+                        let mut bytes = [0u8; 16];
+                        bytes.copy_from_slice(chunk);
+                        let _ = event.service_uuids_128.push(u128::from_le_bytes(bytes));
+                    }
+                }
+                // Shortened or Complete local name, which extended
+                // advertising allows to run past `name`
                 0x08 | 0x09 => {
                     if let Ok(name) = core::str::from_utf8(data) {
-                        let _ = event.name.push_str(name);
+                        event.name = crate::protocol::truncate_str(name).0;
                     }
                 }
+                // TX Power Level
+                0x0A if data.len() == 1 => ad_tx_power = Some(data[0] as i8),
+                // Service data with a 16-bit UUID
+                0x16 if data.len() >= 2 => match u16::from_le_bytes([data[0], data[1]]) {
+                    MATTER_SERVICE_UUID => {
+                        event.matter = MatterCommissioning::parse(&data[2..]);
+                    }
+                    THREAD_TCAT_SERVICE_UUID => event.thread_commissioning = true,
+                    remote_id::SERVICE_UUID => event.remote_id = RemoteId::parse(&data[2..]),
+                    EDDYSTONE_SERVICE_UUID => {
+                        beacon_power = eddystone_power(&data[2..]);
+                        event.eddystone = Eddystone::parse(&data[2..]);
+                    }
+                    _ => {}
+                },
                 // Manufacturer specific data
                 0xFF => {
                     if data.len() >= 2 {
                         event.manufacturer_id = u16::from_le_bytes([data[0], data[1]]);
+                        event.peer = BeaconSummary::decode(data);
+                        if let Some(ibeacon) = IBeacon::parse(data) {
+                            event.ibeacon = Some(ibeacon);
+                            beacon_power = beacon_power.or(Some(ibeacon.power));
+                        }
+                        event.findmy = event.findmy.or(FindMy::parse(data));
                     }
                 }
                 _ => {}
@@ -204,7 +1562,147 @@ impl BleAdvParser {
             pos += 1 + len;
         }
 
-        event
+        event.tx_power =
+            beacon_power.or(ad_tx_power.map(|power| power.saturating_sub(TX_POWER_LOSS_1M)));
+        Ok(event)
+    }
+
+    /// Parse an extended advertisement (BLE 5), whose AD data can run past
+    /// the legacy 31 bytes. `secondary_phy` and `tx_power` are the report's
+    /// fields as sent over HCI; the transmit power, when given, stands in for
+    /// a missing TX Power Level AD.
+    pub fn parse_extended(
+        addr: &[u8; 6],
+        rssi: i8,
+        ad_data: &[u8],
+        secondary_phy: u8,
+        tx_power: i8,
+    ) -> Result<BleEvent, AirhoundError> {
+        let mut event = Self::parse(addr, rssi, ad_data)?;
+        event.secondary_phy = BlePhy::from_hci(secondary_phy);
+        if tx_power != EXT_ADV_TX_POWER_UNKNOWN {
+            event.tx_power = event
+                .tx_power
+                .or(Some(tx_power.saturating_sub(TX_POWER_LOSS_1M)));
+        }
+        Ok(event)
+    }
+}
+
+/// The leading AD structures of `ad_data` that are complete, for data cut
+/// off mid-structure such as the first fragment of chained extended
+/// advertising data. The firmware trims every report with it, so a broken
+/// trailing structure doesn't lose the fields parsed before it.
+pub fn complete_ad_structures(ad_data: &[u8]) -> &[u8] {
+    let mut end = 0;
+    while let Some(&len) = ad_data.get(end) {
+        let next = end + 1 + len as usize;
+        if len == 0 || next > ad_data.len() {
+            break;
+        }
+        end = next;
+    }
+    &ad_data[..end]
+}
+
+/// An advertisement and a scan response from one address this far apart
+/// are no longer joined
+pub const SCAN_RSP_TTL_MS: u32 = 10 * 1000;
+
+/// AD data of an advertisement followed by its scan response's
+pub type MergedAdData = Vec<u8, { 2 * MAX_AD_LEN }>;
+
+/// The latest legacy payloads heard from one address
+struct ScanPayloads {
+    addr: [u8; 6],
+    adv: Vec<u8, MAX_AD_LEN>,
+    rsp: Vec<u8, MAX_AD_LEN>,
+    last_ms: u32,
+}
+
+/// Joins advertisements with their scan responses.
+///
+/// Active scanning reports an advertisement and the scan response it
+/// solicits separately, and many devices only put their name — or their
+/// service UUIDs — in the response. The cache keeps the latest payload of
+/// each kind for up to `N` addresses so either report parses to a
+/// [`BleEvent`] carrying both. Timestamps are milliseconds since boot (`u32`,
+/// wrapping).
+pub struct ScanResponseCache<const N: usize> {
+    entries: Vec<ScanPayloads, N>,
+}
+
+impl<const N: usize> ScanResponseCache<N> {
+    pub const fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Record the AD data of an advertisement — or, with `scan_response`, of
+    /// a scan response — from `addr`, and return it joined with the latest
+    /// payload of the other kind, advertisement first. `None` when there is
+    /// nothing to join: parse `data` alone. Payloads past [`MAX_AD_LEN`]
+    /// (extended advertising) are neither kept nor joined.
+    pub fn merge(
+        &mut self,
+        addr: &[u8; 6],
+        scan_response: bool,
+        data: &[u8],
+        now_ms: u32,
+    ) -> Option<MergedAdData> {
+        self.entries
+            .retain(|e| now_ms.wrapping_sub(e.last_ms) < SCAN_RSP_TTL_MS);
+
+        let payload = Vec::from_slice(data).ok()?;
+        let Some(entry) = self.entries.iter_mut().find(|e| e.addr == *addr) else {
+            let (adv, rsp) = if scan_response {
+                (Vec::new(), payload)
+            } else {
+                (payload, Vec::new())
+            };
+            self.insert(ScanPayloads {
+                addr: *addr,
+                adv,
+                rsp,
+                last_ms: now_ms,
+            });
+            return None;
+        };
+
+        entry.last_ms = now_ms;
+        if scan_response {
+            entry.rsp = payload;
+        } else {
+            entry.adv = payload;
+        }
+        if entry.adv.is_empty() || entry.rsp.is_empty() {
+            return None;
+        }
+        let mut merged = MergedAdData::new();
+        let _ = merged.extend_from_slice(&entry.adv);
+        let _ = merged.extend_from_slice(&entry.rsp);
+        Some(merged)
+    }
+
+    /// Add an entry, replacing the one heard from longest ago when full.
+    fn insert(&mut self, entry: ScanPayloads) {
+        if let Err(entry) = self.entries.push(entry) {
+            let now_ms = entry.last_ms;
+            let stalest = self
+                .entries
+                .iter_mut()
+                .max_by_key(|e| now_ms.wrapping_sub(e.last_ms));
+            if let Some(stalest) = stalest {
+                *stalest = entry;
+            }
+        }
+    }
+}
+
+impl<const N: usize> Default for ScanResponseCache<N> {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -212,13 +1710,50 @@ impl BleAdvParser {
 mod tests {
     use super::*;
 
+    // ── BleScanConfig tests ─────────────────────────────────────────
+
+    #[test]
+    fn ble_scan_default_is_continuous_active() {
+        let config = BleScanConfig::default();
+        assert!(config.active);
+        assert_eq!(config.duty_cycle_percent(), 100);
+    }
+
+    #[test]
+    fn ble_scan_try_new_validates_ranges() {
+        let config = BleScanConfig::try_new(1000, 100, false).unwrap();
+        assert_eq!(config.duty_cycle_percent(), 10);
+        assert!(!config.active);
+
+        assert!(BleScanConfig::try_new(100, 200, true).is_none());
+        assert!(BleScanConfig::try_new(2, 2, true).is_none());
+        assert!(BleScanConfig::try_new(20_000, 100, true).is_none());
+        assert!(BleScanConfig::try_new(10_240, 10_240, true).is_some());
+    }
+
     // ── FrameType tests ─────────────────────────────────────────────
 
+    #[test]
+    fn channel_stats_count_per_channel() {
+        let stats = ChannelStats::new();
+        for _ in 0..3 {
+            stats.record_frame(6);
+        }
+        stats.record_frame(1);
+        stats.record_match(6);
+        // Out of range
+        stats.record_frame(0);
+        stats.record_frame(36);
+        stats.record_match(0);
+        assert_eq!(stats.snapshot().as_slice(), [(1, 1, 0), (6, 3, 1)]);
+    }
+
     #[test]
     fn frame_type_as_str() {
         assert_eq!(FrameType::Beacon.as_str(), "beacon");
         assert_eq!(FrameType::ProbeRequest.as_str(), "probe_req");
         assert_eq!(FrameType::ProbeResponse.as_str(), "probe_resp");
+        assert_eq!(FrameType::Action.as_str(), "action");
         assert_eq!(FrameType::Data.as_str(), "data");
         assert_eq!(FrameType::Other.as_str(), "other");
     }
@@ -288,6 +1823,63 @@ mod tests {
         assert_eq!(event.rssi, -50);
         assert_eq!(event.channel, 6);
         assert_eq!(event.frame_type, FrameType::Beacon);
+        assert_eq!(event.capability, Some(0x0001));
+        assert_eq!(
+            event.timing,
+            Some(BeaconTiming {
+                tsf: 0,
+                interval: 100
+            })
+        );
+    }
+
+    #[test]
+    fn sequence_numbers_of_management_frames() {
+        let mac = [0xB4, 0x1E, 0x52, 0x01, 0x02, 0x03];
+        let mut frame = make_beacon_frame("TestNet", &mac);
+        // Sequence number 0xABC, fragment 3
+        frame[22..24].copy_from_slice(&0xABC3u16.to_le_bytes());
+        assert_eq!(parse_wifi_frame(&frame, -50, 6).unwrap().seq, Some(0xABC));
+
+        // Data frames number per traffic class
+        frame[0] = 0x88;
+        assert_eq!(sequence_number(&frame), None);
+        assert_eq!(parse_wifi_frame(&frame, -50, 6).unwrap().seq, None);
+        assert_eq!(sequence_number(&[0x80, 0x00]), None);
+    }
+
+    #[test]
+    fn raw_frames_are_kept() {
+        let mac = [0xB4, 0x1E, 0x52, 0x01, 0x02, 0x03];
+        let frame = make_beacon_frame("TestNet", &mac);
+        let event = parse_wifi_frame(&frame, -50, 6).unwrap();
+        assert_eq!(event.raw.as_slice(), frame.as_slice());
+        assert_eq!(event.raw_len as usize, frame.len());
+
+        // Long frames keep their head
+        let mut long = [0u8; MAX_FRAME_CAPTURE + 40];
+        long[..frame.len()].copy_from_slice(&frame);
+        let event = parse_wifi_frame(&long, -50, 6).unwrap();
+        assert_eq!(event.raw.as_slice(), &long[..MAX_FRAME_CAPTURE]);
+        assert_eq!(event.raw_len as usize, long.len());
+    }
+
+    #[cfg(feature = "raw-payload")]
+    #[test]
+    fn raw_payloads_are_kept_whole() {
+        let mac = [0xB4, 0x1E, 0x52, 0x01, 0x02, 0x03];
+        let mut frame = [0u8; 300];
+        let beacon = make_beacon_frame("TestNet", &mac);
+        frame[..beacon.len()].copy_from_slice(&beacon);
+        let event = parse_wifi_frame(&frame, -50, 6).unwrap();
+        assert_eq!(event.raw.as_slice(), &frame[..]);
+
+        // Extended advertising data past the legacy 31 bytes
+        let mut ad_data = [0u8; 40];
+        ad_data[0] = 39;
+        ad_data[1] = 0xFF;
+        let event = BleAdvParser::parse(&[0x11; 6], -60, &ad_data).unwrap();
+        assert_eq!(event.ad_data.as_slice(), &ad_data[..]);
     }
 
     #[test]
@@ -298,11 +1890,303 @@ mod tests {
         assert_eq!(event.ssid.as_str(), "");
     }
 
+    /// Beacon with the privacy bit set and the given IEs appended
+    fn secured_beacon(ies: &[u8]) -> Vec<u8, 128> {
+        let mut frame = make_beacon_frame("Home", &[0x00, 0x11, 0x22, 0x33, 0x44, 0x55]);
+        frame[34] |= 0x10;
+        let _ = frame.extend_from_slice(ies);
+        frame
+    }
+
+    const RSN_PSK: [u8; 22] = [
+        48, 20, 1, 0, 0x00, 0x0F, 0xAC, 4, 1, 0, 0x00, 0x0F, 0xAC, 4, 1, 0, 0x00, 0x0F, 0xAC, 2, 0,
+        0,
+    ];
+
+    #[test]
+    fn security_from_beacon() {
+        let mac = [0x00, 0x11, 0x22, 0x33, 0x44, 0x55];
+        let open = make_beacon_frame("Cafe", &mac);
+        assert_eq!(parse_security(&open), Security::Open);
+        assert_eq!(
+            parse_wifi_frame(&open, -50, 6).unwrap().security,
+            Security::Open
+        );
+
+        assert_eq!(parse_security(&secured_beacon(&[])), Security::Wep);
+
+        let wpa1 = [221, 8, 0x00, 0x50, 0xF2, 0x01, 1, 0, 0, 0];
+        assert_eq!(parse_security(&secured_beacon(&wpa1)), Security::Wpa);
+
+        let wpa2 = secured_beacon(&RSN_PSK);
+        assert_eq!(parse_security(&wpa2), Security::Wpa2);
+        assert_eq!(
+            parse_wifi_frame(&wpa2, -50, 6).unwrap().security,
+            Security::Wpa2
+        );
+    }
+
+    #[test]
+    fn security_wpa3_and_transition_mode() {
+        let mut sae = RSN_PSK;
+        sae[19] = 8;
+        assert_eq!(parse_security(&secured_beacon(&sae)), Security::Wpa3);
+
+        // PSK + SAE, with the WPA1 IE also present
+        let transition = [
+            48, 24, 1, 0, 0x00, 0x0F, 0xAC, 4, 1, 0, 0x00, 0x0F, 0xAC, 4, 2, 0, 0x00, 0x0F, 0xAC,
+            2, 0x00, 0x0F, 0xAC, 8, 0, 0, 221, 4, 0x00, 0x50, 0xF2, 0x01,
+        ];
+        assert_eq!(parse_security(&secured_beacon(&transition)), Security::Wpa3);
+    }
+
+    #[test]
+    fn security_truncated_elements() {
+        // RSN without AKM list still means RSN
+        assert_eq!(
+            parse_security(&secured_beacon(&[48, 2, 1, 0])),
+            Security::Wpa2
+        );
+        // Length running past the frame end stops the walk
+        assert_eq!(parse_security(&secured_beacon(&[48, 40, 1])), Security::Wep);
+        assert_eq!(parse_security(&[0x80; 20]), Security::Unknown);
+    }
+
+    #[test]
+    fn security_unknown_for_other_frames() {
+        let mut frame = [0u8; 24];
+        frame[0] = 0x08;
+        assert_eq!(
+            parse_wifi_frame(&frame, -50, 1).unwrap().security,
+            Security::Unknown
+        );
+    }
+
+    #[test]
+    fn information_elements_are_kept_for_fingerprints() {
+        let event = parse_wifi_frame(&secured_beacon(&[0xDD, 1, 0xAA]), -50, 1).unwrap();
+        assert_eq!(event.ies.as_slice(), b"\x00\x04Home\xDD\x01\xAA");
+
+        // Probe requests carry their IEs straight after the header
+        let mut probe = [0u8; 28];
+        probe[0] = 0x40;
+        probe[24..].copy_from_slice(&[0x00, 0x02, b'h', b'i']);
+        let event = parse_wifi_frame(&probe, -50, 1).unwrap();
+        assert_eq!(event.frame_type, FrameType::ProbeRequest);
+        assert_eq!(event.ies.as_slice(), b"\x00\x02hi");
+
+        let mut vendor = [0x11; 82];
+        vendor[..2].copy_from_slice(&[0xDD, 80]);
+        let long = secured_beacon(&vendor);
+        assert_eq!(
+            parse_wifi_frame(&long, -50, 1).unwrap().ies.len(),
+            MAX_IE_LEN
+        );
+
+        let mut data = [0u8; 24];
+        data[0] = 0x08;
+        assert!(parse_wifi_frame(&data, -50, 1).unwrap().ies.is_empty());
+    }
+
     #[test]
-    fn parse_too_short_frame_returns_none() {
+    fn information_elements_are_walked_with_bounds() {
+        let ies = [
+            0, 3, b'C', b'a', b'm', 7, 0, 0xDD, 5, 0x50, 0x6F, 0x9A, 0x09, 0x02,
+        ];
+        let mut walk = ie::elements(&ies);
+        assert_eq!(walk.next(), Some((ie::SSID, &b"Cam"[..])));
+        assert_eq!(walk.next(), Some((ie::COUNTRY, &[][..])));
+        assert_eq!(
+            walk.next(),
+            Some((ie::VENDOR_SPECIFIC, &[0x50, 0x6F, 0x9A, 0x09, 0x02][..]))
+        );
+        assert_eq!(walk.next(), None);
+        assert!(walk.remainder().is_empty());
+        assert_eq!(ie::vendor(&ies, WFA_OUI, P2P_OUI_TYPE), Some(&[0x02][..]));
+        assert_eq!(ie::vendor(&ies, WFA_OUI, 0x10), None);
+
+        // A length running past the end stops the walk there
+        let truncated = [0, 1, b'x', 48, 20, 1, 0];
+        let mut walk = ie::elements(&truncated);
+        assert_eq!(walk.next(), Some((ie::SSID, &b"x"[..])));
+        assert_eq!(walk.next(), None);
+        assert_eq!(walk.remainder(), [48, 20, 1, 0]);
+        assert_eq!(ie::elements(&[0xDD]).count(), 0);
+    }
+
+    #[test]
+    fn vendor_elements_are_extracted() {
+        let mut ies: Vec<u8, 128> = Vec::new();
+        // Long element pushing the vendor ones past the kept `ies`
+        let _ = ies.extend_from_slice(&[0x2D, 50]);
+        let _ = ies.extend_from_slice(&[0; 50]);
+        // WMM, skipped
+        let _ = ies.extend_from_slice(&[0xDD, 7, 0x00, 0x50, 0xF2, 0x02, 0x01, 0x01, 0x00]);
+        let _ = ies.extend_from_slice(&[0xDD, 14, 0x00, 0x40, 0x8C, 0x01]);
+        let _ = ies.extend_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
+        // Too short to carry an OUI
+        let _ = ies.extend_from_slice(&[0xDD, 2, 0xAA, 0xBB]);
+        let event = parse_wifi_frame(&secured_beacon(&ies), -50, 1).unwrap();
+        assert_eq!(event.vendor_ies.len(), 1);
+        assert_eq!(event.vendor_ies[0].oui, [0x00, 0x40, 0x8C]);
+        assert_eq!(
+            event.vendor_ies[0].data.as_slice(),
+            [0x01, 1, 2, 3, 4, 5, 6, 7]
+        );
+
+        let many = [0xDD, 4, 0xAA, 0xBB, 0xCC, 0x01].repeat(6);
+        assert_eq!(parse_vendor_ies(&many).len(), MAX_VENDOR_IES);
+        // A truncated element ends the walk
+        assert!(parse_vendor_ies(&[0xDD, 9, 0xAA, 0xBB, 0xCC]).is_empty());
+    }
+
+    /// WPS element with the given (type, value) attributes
+    fn wps_element(attrs: &[(u16, &[u8])]) -> Vec<u8, 128> {
+        let mut body: Vec<u8, 128> = Vec::from_slice(&[0x00, 0x50, 0xF2, 0x04]).unwrap();
+        for &(kind, value) in attrs {
+            let _ = body.extend_from_slice(&kind.to_be_bytes());
+            let _ = body.extend_from_slice(&(value.len() as u16).to_be_bytes());
+            let _ = body.extend_from_slice(value);
+        }
+        let mut ie = Vec::from_slice(&[0xDD, body.len() as u8]).unwrap();
+        let _ = ie.extend_from_slice(&body);
+        ie
+    }
+
+    #[test]
+    fn wps_identity_is_extracted() {
+        let ie = wps_element(&[
+            (0x104A, &[0x10]), // version, skipped
+            (WPS_MANUFACTURER, b"Axon Enterprise\0"),
+            (WPS_MODEL_NAME, b"Body 4"),
+            (WPS_DEVICE_NAME, b"X1234567890123456789012345678901234"),
+        ]);
+        let event = parse_wifi_frame(&secured_beacon(&ie), -50, 1).unwrap();
+        let wps = event.wps.unwrap();
+        assert_eq!(wps.manufacturer.as_str(), "Axon Enterprise");
+        assert_eq!(wps.field(WpsField::ModelName), "Body 4");
+        assert_eq!(wps.device_name.len(), MAX_WPS_TEXT);
+        // WPS is not reported as a vendor element
+        assert!(event.vendor_ies.is_empty());
+
+        // Missing attributes stay empty; invalid UTF-8 keeps the valid prefix
+        let wps = parse_wps(&wps_element(&[(WPS_MODEL_NAME, b"Cam\xFF2")])).unwrap();
+        assert_eq!(
+            (wps.manufacturer.as_str(), wps.model_name.as_str()),
+            ("", "Cam")
+        );
+        // A truncated attribute ends the walk
+        let mut ie = wps_element(&[(WPS_MANUFACTURER, b"Acme")]);
+        ie[1] -= 1;
+        ie.truncate(ie.len() - 1);
+        assert_eq!(parse_wps(&ie).unwrap().manufacturer.as_str(), "");
+
+        assert!(parse_wps(&[0xDD, 4, 0x00, 0x50, 0xF2, 0x01]).is_none());
+        let event = parse_wifi_frame(&make_beacon_frame("x", &[0; 6]), -50, 1).unwrap();
+        assert!(event.wps.is_none());
+    }
+
+    #[test]
+    fn remote_id_vendor_element_is_decoded() {
+        // Single Location message: lat 1.0, lon -2.0 degrees
+        let mut ie: Vec<u8, 64> = Vec::new();
+        let _ = ie.extend_from_slice(&[0xDD, 30, 0xFA, 0x0B, 0xBC, 0x0D, 0x00, 0x12, 0, 0, 0, 0]);
+        let _ = ie.extend_from_slice(&10_000_000i32.to_le_bytes());
+        let _ = ie.extend_from_slice(&(-20_000_000i32).to_le_bytes());
+        let _ = ie.extend_from_slice(&[0; 12]);
+        let event = parse_wifi_frame(&secured_beacon(&ie), -50, 1).unwrap();
+        let id = event.remote_id.unwrap();
+        assert_eq!((id.lat, id.lon), (Some(10_000_000), Some(-20_000_000)));
+        // Still reported as a vendor element for signatures
+        assert_eq!(event.vendor_ies[0].oui, remote_id::WIFI_OUI);
+
+        assert!(parse_remote_id(&[0xDD, 5, 0xFA, 0x0B, 0xBC, 0x0C, 0x00]).is_none());
+        let event = parse_wifi_frame(&make_beacon_frame("x", &[0; 6]), -50, 1).unwrap();
+        assert!(event.remote_id.is_none());
+    }
+
+    #[test]
+    fn p2p_element_is_classified() {
+        // P2P element: capability attribute (device 0x25, group 0x0B), then
+        // a device info attribute
+        let mut ie: Vec<u8, 64> = Vec::new();
+        let _ = ie.extend_from_slice(&[0xDD, 13, 0x50, 0x6F, 0x9A, 0x09]);
+        let _ = ie.extend_from_slice(&[0x02, 0x02, 0x00, 0x25, 0x0B]);
+        let _ = ie.extend_from_slice(&[0x0D, 0x01, 0x00, 0x00]);
+        let event = parse_wifi_frame(&secured_beacon(&ie), -50, 1).unwrap();
+        assert_eq!(event.p2p, Some(P2pRole::GroupOwner));
+
+        // Group capability without the owner bit
+        ie[10] = 0x0A;
+        assert_eq!(parse_p2p(&ie), Some(P2pRole::Device));
+        // No capability attribute
+        assert_eq!(
+            parse_p2p(&[0xDD, 4, 0x50, 0x6F, 0x9A, 0x09]),
+            Some(P2pRole::Device)
+        );
+        // Other Wi-Fi Alliance elements (Hotspot 2.0) are not P2P
+        assert_eq!(parse_p2p(&[0xDD, 5, 0x50, 0x6F, 0x9A, 0x10, 0x00]), None);
+        let event = parse_wifi_frame(&make_beacon_frame("x", &[0; 6]), -50, 1).unwrap();
+        assert!(event.p2p.is_none());
+        assert!(event.network.is_none());
+    }
+
+    #[test]
+    fn mesh_and_p2p_group_networks() {
+        let mac = [0x02, 0x11, 0x22, 0x33, 0x44, 0x55];
+        // Wildcard SSID, then a Mesh ID
+        let mut frame = make_beacon_frame("", &mac);
+        let _ = frame.extend_from_slice(&[114, 8]);
+        let _ = frame.extend_from_slice(b"citymesh");
+        let event = parse_wifi_frame(&frame, -60, 6).unwrap();
+        assert_eq!(event.network, Some(NetworkKind::Mesh));
+        assert_eq!(event.ssid.as_str(), "citymesh");
+        assert_eq!(event.network.unwrap().as_str(), "mesh");
+
+        // A mesh beacon that names an SSID keeps it
+        let mut frame = make_beacon_frame("Home", &mac);
+        let _ = frame.extend_from_slice(&[114, 0]);
+        let event = parse_wifi_frame(&frame, -60, 6).unwrap();
+        assert_eq!(event.network, Some(NetworkKind::Mesh));
+        assert_eq!(event.ssid.as_str(), "Home");
+
+        // P2P group owner
+        let mut frame = make_beacon_frame("DIRECT-xy-Cam", &mac);
+        let _ = frame.extend_from_slice(&[0xDD, 9, 0x50, 0x6F, 0x9A, 0x09]);
+        let _ = frame.extend_from_slice(&[0x02, 0x02, 0x00, 0x25, 0x0B]);
+        let event = parse_wifi_frame(&frame, -60, 6).unwrap();
+        assert_eq!(event.network, Some(NetworkKind::P2pGroup));
+
+        // A P2P device that doesn't own a group is no network of its own
+        let last = frame.len() - 1;
+        frame[last] = 0x0A;
+        let event = parse_wifi_frame(&frame, -60, 6).unwrap();
+        assert_eq!(event.p2p, Some(P2pRole::Device));
+        assert!(event.network.is_none());
+    }
+
+    #[test]
+    fn parse_too_short_frame_is_malformed() {
         // Less than 16 bytes — can't even extract MAC
         let short = [0x80, 0x00, 0x00, 0x00, 0xFF, 0xFF];
-        assert!(parse_wifi_frame(&short, -50, 1).is_none());
+        assert_eq!(
+            parse_wifi_frame(&short, -50, 1).unwrap_err(),
+            AirhoundError::Malformed
+        );
+        assert_eq!(
+            parse_wifi_frame(&[], -50, 1).unwrap_err(),
+            AirhoundError::Malformed
+        );
+    }
+
+    #[test]
+    fn parse_ack_frame_is_unsupported() {
+        // ACK: control frame with only a receiver address
+        let ack = [0xD4, 0x00, 0x00, 0x00, 0x00, 0x11, 0x22, 0x33, 0x44, 0x55];
+        assert_eq!(
+            parse_wifi_frame(&ack, -50, 1).unwrap_err(),
+            AirhoundError::Unsupported
+        );
     }
 
     #[test]
@@ -320,6 +2204,213 @@ mod tests {
         assert_eq!(event.mac, [0xAA, 0xBB, 0xCC, 0x11, 0x22, 0x33]);
         assert_eq!(event.frame_type, FrameType::Data);
         assert_eq!(event.ssid.as_str(), "");
+        assert!(!event.from_ds);
+    }
+
+    #[test]
+    fn parse_data_frame_from_ap_sets_from_ds() {
+        let mut frame = [0u8; 24];
+        frame[0] = 0x08;
+        frame[1] = 0x02; // FromDS
+        frame[10..16].copy_from_slice(&[0xAA, 0xBB, 0xCC, 0x11, 0x22, 0x33]);
+        assert!(parse_wifi_frame(&frame, -60, 3).unwrap().from_ds);
+        frame[1] = 0x03; // WDS (both bits) is not an AP downlink
+        assert!(!parse_wifi_frame(&frame, -60, 3).unwrap().from_ds);
+    }
+
+    #[test]
+    fn data_frames_are_attributed_to_their_source() {
+        const STATION: [u8; 6] = [0xB4, 0x1E, 0x52, 0x01, 0x02, 0x03];
+        const AP: [u8; 6] = [0x02, 0xAA, 0xBB, 0xCC, 0xDD, 0xEE];
+        const PEER_AP: [u8; 6] = [0x02, 0x11, 0x22, 0x33, 0x44, 0x55];
+        const SERVER: [u8; 6] = [0x58, 0x8E, 0x81, 0x0A, 0x0B, 0x0C];
+        let frame = |flags: u8, addresses: [[u8; 6]; 4]| {
+            let mut frame = [0u8; 30];
+            frame[0] = 0x08;
+            frame[1] = flags;
+            for (i, address) in addresses.iter().enumerate() {
+                let offset = if i == 3 { 24 } else { 4 + 6 * i };
+                frame[offset..offset + 6].copy_from_slice(address);
+            }
+            frame
+        };
+
+        // Station to AP: sent by the station itself
+        let event = parse_wifi_frame(&frame(0x01, [AP, STATION, SERVER, [0; 6]]), -60, 6).unwrap();
+        assert_eq!(event.mac, STATION);
+        assert_eq!(event.relay, None);
+        assert_eq!(event.bssid, Some(AP));
+
+        // AP to station, relaying the source's frame
+        let event = parse_wifi_frame(&frame(0x02, [STATION, AP, SERVER, [0; 6]]), -60, 6).unwrap();
+        assert_eq!(event.mac, SERVER);
+        assert_eq!(event.relay, Some(AP));
+        assert_eq!(event.bssid, Some(AP));
+
+        // An AP's own frames aren't relayed
+        let event = parse_wifi_frame(&frame(0x02, [STATION, AP, AP, [0; 6]]), -60, 6).unwrap();
+        assert_eq!(event.mac, AP);
+        assert_eq!(event.relay, None);
+
+        // WDS bridge: the source is Address 4, and there is no BSSID
+        let event = parse_wifi_frame(&frame(0x03, [PEER_AP, AP, STATION, SERVER]), -60, 6).unwrap();
+        assert_eq!(event.mac, SERVER);
+        assert_eq!(event.relay, Some(AP));
+        assert_eq!(event.bssid, None);
+
+        // A WDS frame cut short of Address 4 keeps its transmitter
+        let event =
+            parse_wifi_frame(&frame(0x03, [PEER_AP, AP, STATION, SERVER])[..24], -60, 6).unwrap();
+        assert_eq!(event.mac, AP);
+        assert_eq!(event.relay, None);
+    }
+
+    #[test]
+    fn fcs_is_checked_and_stripped() {
+        let mut check = Vec::<u8, 16>::from_slice(b"123456789").unwrap();
+        check
+            .extend_from_slice(&0xCBF4_3926u32.to_le_bytes())
+            .unwrap();
+        assert!(fcs_valid(&check));
+        assert!(!fcs_valid(&check[1..]));
+        assert!(!fcs_valid(&[0x26, 0x39, 0xF4]));
+
+        let mac = [0xB4, 0x1E, 0x52, 0x01, 0x02, 0x03];
+        let mut frame = make_beacon_frame("Flock-A1B2C3", &mac);
+        let crc = crc32(&frame);
+        frame.extend_from_slice(&crc.to_le_bytes()).unwrap();
+        let event = parse_wifi_frame_with_fcs(&frame, -60, 6, true).unwrap();
+        assert_eq!(event.mac, mac);
+        assert_eq!(event.ssid.as_str(), "Flock-A1B2C3");
+        // The FCS is not read as a trailing element
+        assert_eq!(event.ies.len(), frame.len() - FCS_LEN - MGMT_IE_OFFSET);
+
+        // A bit flipped on air
+        frame[12] ^= 0x04;
+        assert_eq!(
+            parse_wifi_frame_with_fcs(&frame, -60, 6, true).unwrap_err(),
+            AirhoundError::BadChecksum
+        );
+        let phantom = parse_wifi_frame_with_fcs(&frame, -60, 6, false).unwrap();
+        assert_ne!(phantom.mac, mac);
+        assert_eq!(
+            parse_wifi_frame_with_fcs(&frame[..3], -60, 6, false).unwrap_err(),
+            AirhoundError::Malformed
+        );
+    }
+
+    #[test]
+    fn action_frames_are_classified() {
+        const PHONE: [u8; 6] = [0x5A, 0x01, 0x02, 0x03, 0x04, 0x05];
+        let frame = |control: u8, category: u8, action: u8| {
+            let mut frame = [0u8; 26];
+            frame[0] = control;
+            frame[10..16].copy_from_slice(&PHONE);
+            frame[24] = category;
+            frame[25] = action;
+            frame
+        };
+        let ftm = parse_wifi_frame(&frame(0xD0, 4, 32), -55, 6).unwrap();
+        assert_eq!(ftm.frame_type, FrameType::Action);
+        assert_eq!(ftm.action, Some(ActionCategory::FineTiming));
+        assert_eq!(ftm.mac, PHONE);
+
+        let classify =
+            |control, category, action| action_category(&frame(control, category, action));
+        assert_eq!(classify(0xD0, 4, 33), Some(ActionCategory::FineTiming));
+        assert_eq!(classify(0xE0, 9, 33), Some(ActionCategory::FineTiming));
+        assert_eq!(classify(0xD0, 3, 0), Some(ActionCategory::BlockAck));
+        assert_eq!(classify(0xD0, 5, 4), Some(ActionCategory::RadioMeasurement));
+        // Other public actions, e.g. GAS queries
+        assert_eq!(classify(0xD0, 4, 10), Some(ActionCategory::Other));
+        // Deauthentication and data frames carry no category
+        assert_eq!(classify(0xC0, 4, 32), None);
+        assert_eq!(classify(0x08, 4, 32), None);
+        assert_eq!(action_category(&frame(0xD0, 4, 32)[..24]), None);
+        let deauth = parse_wifi_frame(&frame(0xC0, 4, 32), -55, 6).unwrap();
+        assert_eq!(deauth.frame_type, FrameType::Other);
+        assert_eq!(deauth.action, None);
+    }
+
+    // ── Bluetooth Classic tests ─────────────────────────────────────
+
+    #[test]
+    fn cod_class_fields() {
+        // Audio/Video major class, video camera minor class, plus a service bit
+        let cod = 0x20_0430;
+        assert_eq!(cod_major_class(cod), 0x04);
+        assert_eq!(cod_minor_class(cod), 0x0C);
+    }
+
+    #[test]
+    fn hci_inquiry_result_with_rssi_single() {
+        let params = [
+            0x01, // one response
+            0x66, 0x55, 0x44, 0x33, 0x22, 0x11, // BD_ADDR (little-endian)
+            0x01, // page scan repetition mode
+            0x00, // reserved
+            0x30, 0x04, 0x20, // CoD 0x200430
+            0x00, 0x00, // clock offset
+            0xC4, // RSSI -60
+        ];
+        let events = parse_hci_inquiry_event(HCI_EVT_INQUIRY_RESULT_RSSI, &params);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].mac, [0x11, 0x22, 0x33, 0x44, 0x55, 0x66]);
+        assert_eq!(events[0].class_of_device, 0x20_0430);
+        assert_eq!(events[0].rssi, -60);
+    }
+
+    #[test]
+    fn hci_inquiry_result_with_rssi_multiple_uses_field_arrays() {
+        let mut params = [0u8; 1 + 14 * 2];
+        params[0] = 2;
+        params[1..7].copy_from_slice(&[1, 0, 0, 0, 0, 0xAA]);
+        params[7..13].copy_from_slice(&[2, 0, 0, 0, 0, 0xBB]);
+        params[17..20].copy_from_slice(&[0x34, 0x04, 0x00]);
+        params[20..23].copy_from_slice(&[0x0C, 0x02, 0x5A]);
+        params[27] = 0xB0; // -80
+        params[28] = 0xD8; // -40
+        let events = parse_hci_inquiry_event(HCI_EVT_INQUIRY_RESULT_RSSI, &params);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].mac[0], 0xAA);
+        assert_eq!(events[0].class_of_device, 0x0434);
+        assert_eq!(events[1].mac, [0xBB, 0, 0, 0, 0, 2]);
+        assert_eq!(events[1].rssi, -40);
+    }
+
+    #[test]
+    fn hci_extended_inquiry_result_and_unknown_events() {
+        let mut params = [0u8; 1 + 14 + 240];
+        params[0] = 1;
+        params[9..12].copy_from_slice(&[0x30, 0x04, 0x00]);
+        params[14] = 0xCE;
+        let events = parse_hci_inquiry_event(HCI_EVT_EXTENDED_INQUIRY_RESULT, &params);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].rssi, -50);
+
+        assert!(parse_hci_inquiry_event(0x3E, &params).is_empty());
+        assert!(parse_hci_inquiry_event(HCI_EVT_INQUIRY_RESULT_RSSI, &params[..10]).is_empty());
+    }
+
+    #[test]
+    fn hci_extended_inquiry_result_parses_eir() {
+        let mut params = [0u8; 1 + 14 + 240];
+        params[0] = 1;
+        params[14] = 0xCE;
+        let eir = [
+            0x06, 0x09, b'G', b'o', b'P', b'r', b'o', // complete name
+            0x04, 0x0D, 0x30, 0x04, 0x20, // class of device
+            0x03, 0xFF, 0xF2, 0x02, // manufacturer
+        ];
+        params[15..15 + eir.len()].copy_from_slice(&eir);
+        let events = parse_hci_inquiry_event(HCI_EVT_EXTENDED_INQUIRY_RESULT, &params);
+        assert_eq!(events[0].name.as_str(), "GoPro");
+        assert_eq!(events[0].class_of_device, 0x20_0430);
+        assert_eq!(events[0].manufacturer_id, 0x02F2);
+
+        // Zero length ends the data; a structure running past the end is dropped
+        assert_eq!(parse_eir(&[0x00, 0x03, 0xFF, 0x01, 0x02]), Eir::default());
+        assert_eq!(parse_eir(&[0x05, 0x09, b'a']).name.as_str(), "");
     }
 
     // ── BleAdvParser tests ──────────────────────────────────────────
@@ -327,7 +2418,7 @@ mod tests {
     #[test]
     fn ble_parse_empty_ad_data() {
         let addr = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66];
-        let event = BleAdvParser::parse(&addr, -50, &[]);
+        let event = BleAdvParser::parse(&addr, -50, &[]).unwrap();
         assert_eq!(event.mac, addr);
         assert_eq!(event.rssi, -50);
         assert!(event.name.is_empty());
@@ -335,12 +2426,28 @@ mod tests {
         assert_eq!(event.manufacturer_id, 0);
     }
 
+    #[test]
+    fn ble_parse_airhound_peer_summary() {
+        let addr = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66];
+        let summary = BeaconSummary {
+            battery_pct: Some(64),
+            ..BeaconSummary::new(true, 5, 9)
+        };
+        let mut ad_data = [0u8; 2 + crate::beacon::ENCODED_LEN];
+        ad_data[0] = 1 + crate::beacon::ENCODED_LEN as u8;
+        ad_data[1] = 0xFF;
+        ad_data[2..].copy_from_slice(&summary.encode());
+        let event = BleAdvParser::parse(&addr, -60, &ad_data).unwrap();
+        assert_eq!(event.manufacturer_id, crate::beacon::COMPANY_ID);
+        assert_eq!(event.peer, Some(summary));
+    }
+
     #[test]
     fn ble_parse_complete_local_name() {
         let addr = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66];
         // AD structure: len=6, type=0x09 (Complete Local Name), data="Flock"
         let ad_data = [0x06, 0x09, b'F', b'l', b'o', b'c', b'k'];
-        let event = BleAdvParser::parse(&addr, -40, &ad_data);
+        let event = BleAdvParser::parse(&addr, -40, &ad_data).unwrap();
         assert_eq!(event.name.as_str(), "Flock");
     }
 
@@ -349,7 +2456,7 @@ mod tests {
         let addr = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66];
         // AD structure: len=3, type=0x08 (Shortened Local Name), data="FS"
         let ad_data = [0x03, 0x08, b'F', b'S'];
-        let event = BleAdvParser::parse(&addr, -40, &ad_data);
+        let event = BleAdvParser::parse(&addr, -40, &ad_data).unwrap();
         assert_eq!(event.name.as_str(), "FS");
     }
 
@@ -359,19 +2466,50 @@ mod tests {
         // AD structure: len=5, type=0x03 (Complete List 16-bit UUIDs)
         // UUIDs: 0x3100, 0x180A (little-endian)
         let ad_data = [0x05, 0x03, 0x00, 0x31, 0x0A, 0x18];
-        let event = BleAdvParser::parse(&addr, -50, &ad_data);
+        let event = BleAdvParser::parse(&addr, -50, &ad_data).unwrap();
         assert_eq!(event.service_uuids_16.len(), 2);
         assert_eq!(event.service_uuids_16[0], 0x3100);
         assert_eq!(event.service_uuids_16[1], 0x180A);
     }
 
+    #[test]
+    fn ble_parse_service_uuids_32() {
+        let addr = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66];
+        // AD structure: len=9, type=0x05 (Complete List 32-bit UUIDs)
+        // UUIDs: 0x12345678, 0x0000FEAA (little-endian), one stray byte
+        let ad_data = [
+            0x0A, 0x05, 0x78, 0x56, 0x34, 0x12, 0xAA, 0xFE, 0x00, 0x00, 0xFF,
+        ];
+        let event = BleAdvParser::parse(&addr, -50, &ad_data).unwrap();
+        assert_eq!(event.service_uuids_32.as_slice(), [0x12345678, 0x0000FEAA]);
+        assert!(event.service_uuids_16.is_empty());
+    }
+
+    #[test]
+    fn ble_parse_service_uuids_128() {
+        let addr = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66];
+        // AD structure: len=17, type=0x07 (Complete List 128-bit UUIDs),
+        // 6e400001-b5a3-f393-e0a9-e50e24dcca9e little-endian, then a
+        // truncated second UUID that is ignored
+        let mut ad_data = [0u8; 22];
+        ad_data[..2].copy_from_slice(&[0x15, 0x07]);
+        ad_data[2..18].copy_from_slice(&0x6E400001_B5A3_F393_E0A9_E50E24DCCA9E_u128.to_le_bytes());
+        ad_data[18..].copy_from_slice(&[0xAA, 0xBB, 0xCC, 0xDD]);
+        let event = BleAdvParser::parse(&addr, -50, &ad_data).unwrap();
+        assert_eq!(
+            event.service_uuids_128.as_slice(),
+            [0x6E400001_B5A3_F393_E0A9_E50E24DCCA9E]
+        );
+        assert!(event.service_uuids_16.is_empty());
+    }
+
     #[test]
     fn ble_parse_manufacturer_data() {
         let addr = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66];
         // AD structure: len=5, type=0xFF (Manufacturer Specific)
         // Company ID: 0x09C8 (little-endian: 0xC8, 0x09), then 2 bytes payload
         let ad_data = [0x05, 0xFF, 0xC8, 0x09, 0x01, 0x02];
-        let event = BleAdvParser::parse(&addr, -50, &ad_data);
+        let event = BleAdvParser::parse(&addr, -50, &ad_data).unwrap();
         assert_eq!(event.manufacturer_id, 0x09C8);
     }
 
@@ -387,7 +2525,7 @@ mod tests {
             0x03, 0xFF, 0xC8, 0x09, // UUID
             0x03, 0x03, 0x00, 0x31,
         ];
-        let event = BleAdvParser::parse(&addr, -45, &ad_data);
+        let event = BleAdvParser::parse(&addr, -45, &ad_data).unwrap();
         assert_eq!(event.name.as_str(), "FS");
         assert_eq!(event.manufacturer_id, 0x09C8);
         assert_eq!(event.service_uuids_16.len(), 1);
@@ -395,20 +2533,268 @@ mod tests {
     }
 
     #[test]
-    fn ble_parse_truncated_ad_structure_stops() {
+    fn ble_parse_matter_commissioning() {
         let addr = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66];
-        // Structure claims len=10 but only 3 data bytes follow — should stop
-        let ad_data = [0x0A, 0x09, b'A', b'B', b'C'];
-        let event = BleAdvParser::parse(&addr, -50, &ad_data);
-        // Parser should stop, not crash
-        assert!(event.name.is_empty());
+        // Service data: UUID 0xFFF6, opcode 0, discriminator 0xF00 (version 0),
+        // vendor 0x6006, product 0x0001, flags
+        let ad_data = [
+            0x0B, 0x16, 0xF6, 0xFF, 0x00, 0x00, 0x0F, 0x06, 0x60, 0x01, 0x00, 0x00,
+        ];
+        let event = BleAdvParser::parse(&addr, -60, &ad_data).unwrap();
+        assert_eq!(
+            event.matter,
+            Some(MatterCommissioning {
+                discriminator: 0xF00,
+                vendor_id: 0x6006,
+                product_id: 0x0001,
+            })
+        );
+        assert!(!event.thread_commissioning);
+    }
+
+    #[test]
+    fn ble_parse_matter_rejects_unknown_opcode() {
+        assert!(MatterCommissioning::parse(&[0x01, 0, 0, 0, 0, 0, 0]).is_none());
+        assert!(MatterCommissioning::parse(&[0x00, 0, 0]).is_none());
+    }
+
+    #[test]
+    fn ble_parse_thread_tcat() {
+        let addr = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66];
+        let ad_data = [0x05, 0x16, 0xFB, 0xFF, 0x01, 0x02];
+        let event = BleAdvParser::parse(&addr, -60, &ad_data).unwrap();
+        assert!(event.thread_commissioning);
+        assert!(event.matter.is_none());
+    }
+
+    #[test]
+    fn ble_parse_remote_id() {
+        let addr = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66];
+        // Basic ID message carrying serial "AB"
+        let mut ad_data = [0u8; 31];
+        ad_data[..9].copy_from_slice(&[0x1E, 0x16, 0xFA, 0xFF, 0x0D, 0x03, 0x02, 0x12, b'A']);
+        ad_data[9] = b'B';
+        let event = BleAdvParser::parse(&addr, -60, &ad_data).unwrap();
+        assert_eq!(event.remote_id.unwrap().uas_id.as_str(), "AB");
+
+        let ad_data = [0x05, 0x16, 0xFA, 0xFF, 0x0C, 0x00];
+        assert!(BleAdvParser::parse(&addr, -60, &ad_data)
+            .unwrap()
+            .remote_id
+            .is_none());
+    }
+
+    #[test]
+    fn ble_parse_tx_power_level() {
+        let addr = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66];
+        // AD structure: len=2, type=0x0A (TX Power Level), -4 dBm
+        let ad_data = [0x02, 0x0A, 0xFC];
+        let event = BleAdvParser::parse(&addr, -60, &ad_data).unwrap();
+        assert_eq!(event.tx_power, Some(-45));
+        assert_eq!(BleAdvParser::parse(&addr, -60, &[]).unwrap().tx_power, None);
+    }
+
+    #[test]
+    fn ble_parse_extended_advertisement() {
+        let addr = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66];
+        let mut ad_data: Vec<u8, 96> = Vec::new();
+        // 40-character name, then manufacturer data past the legacy 31 bytes
+        let _ = ad_data.extend_from_slice(&[41, 0x09]);
+        let _ = ad_data.extend_from_slice(&[b'T'; 40]);
+        let _ = ad_data.extend_from_slice(&[0x03, 0xFF, 0xC8, 0x09]);
+        let event = BleAdvParser::parse_extended(&addr, -70, &ad_data, 3, 8).unwrap();
+        assert_eq!(event.name.len(), 33);
+        assert_eq!(event.manufacturer_id, 0x09C8);
+        assert_eq!(event.ad_data.len(), ad_data.len().min(MAX_AD_DATA));
+        assert_eq!(event.secondary_phy, Some(BlePhy::LeCoded));
+        assert_eq!(event.tx_power, Some(8 - TX_POWER_LOSS_1M));
+
+        // Legacy PDU over an extended report, no TX power given
+        let event =
+            BleAdvParser::parse_extended(&addr, -70, &[], 0, EXT_ADV_TX_POWER_UNKNOWN).unwrap();
+        assert_eq!(event.secondary_phy, None);
+        assert_eq!(event.tx_power, None);
+        assert_eq!(BlePhy::from_hci(2).map(|phy| phy.as_str()), Some("2m"));
+    }
+
+    #[test]
+    fn ble_parse_ibeacon_power() {
+        let addr = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66];
+        // TX Power Level, then iBeacon data calibrated to -59 dBm at 1 m
+        let mut ad_data = [0u8; 30];
+        ad_data[..3].copy_from_slice(&[0x02, 0x0A, 0x00]);
+        ad_data[3..9].copy_from_slice(&[0x1A, 0xFF, 0x4C, 0x00, 0x02, 0x15]);
+        ad_data[29] = 0xC5;
+        let event = BleAdvParser::parse(&addr, -60, &ad_data).unwrap();
+        assert_eq!(event.manufacturer_id, 0x004C);
+        assert_eq!(event.tx_power, Some(-59));
+    }
+
+    #[test]
+    fn ble_parse_ibeacon_fields() {
+        let addr = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66];
+        let mut ad_data: Vec<u8, 30> = Vec::new();
+        let _ = ad_data.extend_from_slice(&[0x1A, 0xFF, 0x4C, 0x00, 0x02, 0x15]);
+        let _ =
+            ad_data.extend_from_slice(&0xB9407F30_F5F8_466E_AFF9_25556B57FE6D_u128.to_be_bytes());
+        let _ = ad_data.extend_from_slice(&[0x01, 0x02, 0x00, 0x2A, 0xC5]);
+        let event = BleAdvParser::parse(&addr, -60, &ad_data).unwrap();
+        assert_eq!(
+            event.ibeacon,
+            Some(IBeacon {
+                uuid: 0xB9407F30_F5F8_466E_AFF9_25556B57FE6D,
+                major: 0x0102,
+                minor: 42,
+                power: -59,
+            })
+        );
+
+        // Truncated, or another Apple payload type
+        assert_eq!(IBeacon::parse(&ad_data[2..20]), None);
+        assert_eq!(IBeacon::parse(&[0x4C, 0x00, 0x12, 0x19, 0x10]), None);
+    }
+
+    #[test]
+    fn ble_parse_eddystone_power() {
+        let addr = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66];
+        // Eddystone-URL frame, -18 dBm at 0 m
+        let ad_data = [0x06, 0x16, 0xAA, 0xFE, 0x10, 0xEE, 0x00];
+        assert_eq!(
+            BleAdvParser::parse(&addr, -60, &ad_data).unwrap().tx_power,
+            Some(-59)
+        );
+        // TLM frames carry no power
+        let ad_data = [0x06, 0x16, 0xAA, 0xFE, 0x20, 0x00, 0x00];
+        assert_eq!(
+            BleAdvParser::parse(&addr, -60, &ad_data).unwrap().tx_power,
+            None
+        );
+    }
+
+    #[test]
+    fn ble_parse_findmy_status() {
+        let addr = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66];
+        // Separated AirTag, battery low (status 0x90)
+        let mut ad_data = [0u8; 31];
+        ad_data[..7].copy_from_slice(&[0x1E, 0xFF, 0x4C, 0x00, 0x12, 0x19, 0x90]);
+        let event = BleAdvParser::parse(&addr, -60, &ad_data).unwrap();
+        assert_eq!(
+            event.findmy,
+            Some(FindMy {
+                state: FindMyState::Separated,
+                battery: Some(FindMyBattery::Low),
+            })
+        );
+
+        let nearby = FindMy::parse(&[0x4C, 0x00, 0x12, 0x02, 0x10, 0x00]).unwrap();
+        assert_eq!(nearby.state, FindMyState::Nearby);
+        assert_eq!(nearby.battery, Some(FindMyBattery::Full));
+        let unregistered = FindMy::parse(&[0x4C, 0x00, 0x07, 0x19, 0x05, 0x00]).unwrap();
+        assert_eq!(unregistered.state, FindMyState::Unregistered);
+        assert_eq!(unregistered.battery, None);
+        // AirPods proximity pairing, an iBeacon, and another company's data
+        assert_eq!(FindMy::parse(&[0x4C, 0x00, 0x07, 0x19, 0x01, 0x00]), None);
+        assert_eq!(FindMy::parse(&[0x4C, 0x00, 0x02, 0x15, 0x00]), None);
+        assert_eq!(FindMy::parse(&[0x4D, 0x00, 0x12, 0x19, 0x00]), None);
+    }
+
+    #[test]
+    fn ble_parse_eddystone_frames() {
+        let addr = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66];
+        let mut ad_data: Vec<u8, 31> = Vec::new();
+        let _ = ad_data.extend_from_slice(&[0x17, 0x16, 0xAA, 0xFE, 0x00, 0xEE]);
+        let _ = ad_data.extend_from_slice(&[0xED, 0xD1, 0xEB, 0xEA, 0xC0, 0x4E, 0x5D, 0xEF, 0xA0]);
+        let _ = ad_data.extend_from_slice(&[0x17, 1, 2, 3, 4, 5, 6, 0, 0]);
+        let event = BleAdvParser::parse(&addr, -60, &ad_data).unwrap();
+        assert_eq!(
+            event.eddystone,
+            Some(Eddystone::Uid {
+                namespace: [0xED, 0xD1, 0xEB, 0xEA, 0xC0, 0x4E, 0x5D, 0xEF, 0xA0, 0x17],
+                instance: [1, 2, 3, 4, 5, 6],
+            })
+        );
+        assert_eq!(event.tx_power, Some(-59));
+
+        // https:// goo.gl/ S3 (.com expansion is 0x07)
+        let url = Eddystone::parse(&[0x10, 0xEE, 0x03, b'g', b'o', b'o', 0x07, b'/', b'x']);
+        assert_eq!(url.as_ref().and_then(Eddystone::namespace), None);
+        let Some(Eddystone::Url(url)) = url else {
+            panic!("not a URL frame");
+        };
+        assert_eq!(url.as_str(), "https://goo.com/x");
+        // Unknown scheme, or a control character outside the expansions
+        assert_eq!(Eddystone::parse(&[0x10, 0xEE, 0x09, b'a']), None);
+        assert_eq!(Eddystone::parse(&[0x10, 0xEE, 0x02, 0x1F]), None);
+
+        let tlm = [
+            0x20, 0x00, 0x0B, 0xB8, 0x16, 0x80, 0, 0, 0, 10, 0, 0, 0x03, 0xE8,
+        ];
+        assert_eq!(
+            Eddystone::parse(&tlm),
+            Some(Eddystone::Tlm {
+                battery_mv: 3000,
+                temperature: 0x1680,
+                adv_count: 10,
+                uptime_ds: 1000,
+            })
+        );
+        // Truncated UID frame
+        assert_eq!(Eddystone::parse(&[0x00, 0xEE, 1, 2, 3]), None);
+    }
+
+    #[test]
+    fn ble_parse_truncated_ad_structure_is_malformed() {
+        let addr = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66];
+        // Structure claims len=10 but only 3 data bytes follow
+        let ad_data = [0x02, 0x01, 0x06, 0x0A, 0x09, b'A', b'B', b'C'];
+        assert_eq!(
+            BleAdvParser::parse(&addr, -50, &ad_data).unwrap_err(),
+            AirhoundError::Malformed
+        );
+        // Trimmed as the firmware does, the whole structures before it parse
+        let ad_data = [0x03, 0x03, 0x6F, 0xFD, 0x0A, 0x09, b'A', b'B', b'C'];
+        let whole = complete_ad_structures(&ad_data);
+        assert_eq!(whole, &[0x03, 0x03, 0x6F, 0xFD]);
+        let event = BleAdvParser::parse(&addr, -50, whole).unwrap();
+        assert_eq!(event.service_uuids_16.as_slice(), &[0xFD6F]);
     }
 
     #[test]
     fn ble_parse_zero_length_ad_stops() {
         let addr = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66];
         let ad_data = [0x00, 0x09, b'A'];
-        let event = BleAdvParser::parse(&addr, -50, &ad_data);
+        let event = BleAdvParser::parse(&addr, -50, &ad_data).unwrap();
         assert!(event.name.is_empty());
     }
+
+    #[test]
+    fn ble_scan_response_is_merged() {
+        let addr = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66];
+        let mut cache = ScanResponseCache::<4>::new();
+        // Flags and a 16-bit service UUID; the name only in the response
+        let adv = [0x02, 0x01, 0x06, 0x03, 0x03, 0x0F, 0x18];
+        let rsp = [0x05, 0x09, b'T', b'a', b'g', b'1'];
+
+        assert_eq!(cache.merge(&addr, false, &adv, 0), None);
+        let merged = cache.merge(&addr, true, &rsp, 20).unwrap();
+        let event = BleAdvParser::parse(&addr, -50, &merged).unwrap();
+        assert_eq!(event.name.as_str(), "Tag1");
+        assert_eq!(event.service_uuids_16.as_slice(), &[0x180F]);
+
+        // The next advertisement carries the response heard before it
+        let merged = cache.merge(&addr, false, &adv, 1_000).unwrap();
+        assert_eq!(&merged[..adv.len()], &adv);
+        assert_eq!(&merged[adv.len()..], &rsp);
+
+        // Other addresses, stale responses and extended payloads stay alone
+        assert_eq!(cache.merge(&[0x77; 6], false, &adv, 1_000), None);
+        assert_eq!(
+            cache.merge(&addr, true, &[0u8; MAX_AD_LEN + 1], 1_000),
+            None
+        );
+        assert_eq!(
+            cache.merge(&addr, false, &adv, 1_000 + SCAN_RSP_TTL_MS),
+            None
+        );
+    }
 }
@@ -0,0 +1,239 @@
+/// Wall-clock time and quiet-hours scheduling.
+///
+/// The device has no RTC battery or network time, so wall-clock time comes
+/// from the companion's `set_time` command, or from GPS fixes on boards with
+/// a GPS module, and is lost on reboot. GPS time is also precise enough to
+/// stamp messages with, so offline exports need no time reconstruction.
+/// During configured quiet hours the buzzer stays silent and detections are
+/// marked `quiet` so the companion can log them without notifying — a
+/// stationary home sensor shouldn't beep at 3 a.m. Until time is synced,
+/// quiet hours never apply.
+use serde::{Deserialize, Serialize};
+
+/// Minutes in a day
+pub const MINUTES_PER_DAY: u16 = 24 * 60;
+
+/// Where a message's `ts` comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TsSource {
+    /// Milliseconds since boot
+    Uptime,
+    /// Unix milliseconds from a GPS-disciplined clock
+    Gps,
+}
+
+impl TsSource {
+    pub fn is_uptime(&self) -> bool {
+        *self == TsSource::Uptime
+    }
+}
+
+/// Wall-clock time anchored to uptime at the last sync.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WallClock {
+    /// (Unix time in ms, uptime in ms) at the last sync
+    anchor: Option<(u64, u32)>,
+    /// Local time offset from UTC in minutes
+    utc_offset_min: i16,
+    /// The anchor is a GPS fix's time rather than the companion's
+    gps: bool,
+}
+
+impl WallClock {
+    pub const fn new() -> Self {
+        Self {
+            anchor: None,
+            utc_offset_min: 0,
+            gps: false,
+        }
+    }
+
+    /// Sync to `epoch_s` (Unix seconds) at uptime `now_ms`. A clock
+    /// disciplined by GPS keeps its time and only takes the offset.
+    pub fn set(&mut self, epoch_s: u32, utc_offset_min: i16, now_ms: u32) {
+        if !self.gps {
+            self.anchor = Some((epoch_s as u64 * 1000, now_ms));
+        }
+        self.utc_offset_min = utc_offset_min;
+    }
+
+    /// Sync to a GPS fix's time (Unix ms) received at uptime `now_ms`.
+    pub fn discipline(&mut self, unix_ms: u64, now_ms: u32) {
+        self.anchor = Some((unix_ms, now_ms));
+        self.gps = true;
+    }
+
+    pub fn is_synced(&self) -> bool {
+        self.anchor.is_some()
+    }
+
+    /// Current Unix time in seconds. Uptime is `u32` milliseconds, so the
+    /// companion should resync at least every 49 days.
+    pub fn epoch_s(&self, now_ms: u32) -> Option<u32> {
+        let (epoch_ms, at_ms) = self.anchor?;
+        Some(((epoch_ms + now_ms.wrapping_sub(at_ms) as u64) / 1000) as u32)
+    }
+
+    /// Timestamp for a message about uptime `at_ms`, which may lie before
+    /// the last sync: Unix milliseconds once GPS disciplined the clock,
+    /// else `at_ms` itself. Fixes arrive every second or so, so `at_ms` is
+    /// always well within the ±24 days a signed offset covers.
+    pub fn timestamp(&self, at_ms: u32) -> (u64, TsSource) {
+        match self.anchor {
+            Some((epoch_ms, sync_ms)) if self.gps => {
+                let offset = at_ms.wrapping_sub(sync_ms) as i32 as i64;
+                ((epoch_ms as i64 + offset) as u64, TsSource::Gps)
+            }
+            _ => (at_ms as u64, TsSource::Uptime),
+        }
+    }
+
+    /// Local minute of the day (0..1440).
+    pub fn local_minute(&self, now_ms: u32) -> Option<u16> {
+        let local_s = self.epoch_s(now_ms)? as i64 + self.utc_offset_min as i64 * 60;
+        Some((local_s / 60).rem_euclid(MINUTES_PER_DAY as i64) as u16)
+    }
+}
+
+/// Daily quiet window in local time, as minutes since midnight. The window
+/// may wrap past midnight (22:00–07:00); `start == end` disables it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuietHours {
+    pub start_min: u16,
+    pub end_min: u16,
+}
+
+impl QuietHours {
+    pub const DISABLED: Self = Self {
+        start_min: 0,
+        end_min: 0,
+    };
+
+    /// Build a window, rejecting minutes outside the day.
+    pub fn try_new(start_min: u16, end_min: u16) -> Option<Self> {
+        (start_min < MINUTES_PER_DAY && end_min < MINUTES_PER_DAY)
+            .then_some(Self { start_min, end_min })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.start_min != self.end_min
+    }
+
+    /// Whether `minute` (local minute of the day) falls in the window.
+    pub fn contains(&self, minute: u16) -> bool {
+        if self.start_min <= self.end_min {
+            (self.start_min..self.end_min).contains(&minute)
+        } else {
+            minute >= self.start_min || minute < self.end_min
+        }
+    }
+
+    /// Whether alerts should be quiet now. Always `false` without synced time.
+    pub fn is_quiet(&self, clock: &WallClock, now_ms: u32) -> bool {
+        self.is_enabled()
+            && clock
+                .local_minute(now_ms)
+                .is_some_and(|minute| self.contains(minute))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2026-01-01 00:00:00 UTC
+    const MIDNIGHT: u32 = 1_767_225_600;
+
+    // ── WallClock ───────────────────────────────────────────────────
+
+    #[test]
+    fn unsynced_clock_has_no_time() {
+        let clock = WallClock::new();
+        assert!(!clock.is_synced());
+        assert_eq!(clock.epoch_s(5_000), None);
+        assert_eq!(clock.local_minute(5_000), None);
+    }
+
+    #[test]
+    fn clock_advances_with_uptime() {
+        let mut clock = WallClock::new();
+        clock.set(MIDNIGHT, 0, 10_000);
+        assert_eq!(clock.epoch_s(70_000), Some(MIDNIGHT + 60));
+        assert_eq!(clock.local_minute(10_000 + 90 * 60_000), Some(90));
+    }
+
+    #[test]
+    fn utc_offset_shifts_local_minute() {
+        let mut clock = WallClock::new();
+        // UTC-7: UTC midnight is 17:00 the previous day
+        clock.set(MIDNIGHT, -420, 0);
+        assert_eq!(clock.local_minute(0), Some(17 * 60));
+        clock.set(MIDNIGHT, 330, 0);
+        assert_eq!(clock.local_minute(0), Some(5 * 60 + 30));
+    }
+
+    #[test]
+    fn gps_disciplines_message_timestamps() {
+        let mut clock = WallClock::new();
+        assert_eq!(clock.timestamp(5_000), (5_000, TsSource::Uptime));
+        // The companion's time doesn't stamp messages
+        clock.set(MIDNIGHT, 0, 0);
+        assert_eq!(clock.timestamp(5_000), (5_000, TsSource::Uptime));
+
+        let fix_ms = MIDNIGHT as u64 * 1000 + 250;
+        clock.discipline(fix_ms, 10_000);
+        assert_eq!(clock.timestamp(10_500), (fix_ms + 500, TsSource::Gps));
+        // An event logged before the fix
+        assert_eq!(clock.timestamp(9_000), (fix_ms - 1_000, TsSource::Gps));
+        assert_eq!(clock.epoch_s(10_750), Some(MIDNIGHT + 1));
+
+        // set_time now only moves the offset
+        clock.set(MIDNIGHT + 3_600, -420, 10_000);
+        assert_eq!(clock.timestamp(10_000), (fix_ms, TsSource::Gps));
+        assert_eq!(clock.local_minute(10_000), Some(17 * 60));
+    }
+
+    #[test]
+    fn clock_survives_uptime_wraparound() {
+        let mut clock = WallClock::new();
+        clock.set(MIDNIGHT, 0, u32::MAX - 999);
+        assert_eq!(clock.epoch_s(1_000), Some(MIDNIGHT + 2));
+    }
+
+    // ── QuietHours ──────────────────────────────────────────────────
+
+    #[test]
+    fn window_within_day() {
+        let hours = QuietHours::try_new(13 * 60, 14 * 60).unwrap();
+        assert!(!hours.contains(13 * 60 - 1));
+        assert!(hours.contains(13 * 60));
+        assert!(!hours.contains(14 * 60));
+    }
+
+    #[test]
+    fn window_wrapping_midnight() {
+        let hours = QuietHours::try_new(22 * 60, 7 * 60).unwrap();
+        assert!(hours.contains(23 * 60));
+        assert!(hours.contains(3 * 60));
+        assert!(!hours.contains(7 * 60));
+        assert!(!hours.contains(12 * 60));
+    }
+
+    #[test]
+    fn rejects_minutes_outside_day() {
+        assert!(QuietHours::try_new(1440, 0).is_none());
+        assert!(QuietHours::try_new(0, 2000).is_none());
+    }
+
+    #[test]
+    fn quiet_requires_enabled_window_and_synced_clock() {
+        let hours = QuietHours::try_new(22 * 60, 7 * 60).unwrap();
+        let mut clock = WallClock::new();
+        assert!(!hours.is_quiet(&clock, 0));
+        clock.set(MIDNIGHT, 0, 0);
+        assert!(hours.is_quiet(&clock, 0));
+        assert!(!hours.is_quiet(&clock, 12 * 3_600_000));
+        assert!(!QuietHours::DISABLED.is_quiet(&clock, 0));
+    }
+}
@@ -0,0 +1,92 @@
+/// SD card signature loader (XIAO ESP32-S3 Sense expansion board).
+///
+/// Reads [`sigfile::SD_FILE_NAME`] from the root of a FAT-formatted card once
+/// at boot and appends its signatures to the runtime overlay. A missing card
+/// or file is not an error — the compiled-in defaults still apply.
+use alloc::vec;
+
+use embedded_hal_bus::spi::ExclusiveDevice;
+use embedded_sdmmc::{Mode, SdCard, TimeSource, Timestamp, VolumeIdx, VolumeManager};
+use esp_hal::delay::Delay;
+use esp_hal::gpio::{Level, Output, OutputConfig};
+use esp_hal::spi::master::{Config as SpiConfig, Spi};
+use esp_hal::spi::Mode as SpiMode;
+use esp_hal::time::Rate;
+
+use crate::sigfile::{self, LoadReport, SignatureOverlay};
+
+/// Largest signature file we will read. Allocated from the heap only for the
+/// duration of the load (before the radio stacks claim it).
+const MAX_FILE_LEN: usize = 16 * 1024;
+
+/// The card is read-only for us, so file timestamps never matter.
+struct NoClock;
+
+impl TimeSource for NoClock {
+    fn get_timestamp(&self) -> Timestamp {
+        Timestamp {
+            year_since_1970: 0,
+            zero_indexed_month: 0,
+            zero_indexed_day: 0,
+            hours: 0,
+            minutes: 0,
+            seconds: 0,
+        }
+    }
+}
+
+/// Load the signature file from SD into `overlay`. Returns `None` if there is
+/// no card, no file, or the file is not a signature document.
+pub fn load_signatures(
+    spi2: esp_hal::peripherals::SPI2<'static>,
+    sck: esp_hal::peripherals::GPIO7<'static>,
+    miso: esp_hal::peripherals::GPIO8<'static>,
+    mosi: esp_hal::peripherals::GPIO9<'static>,
+    cs_pin: esp_hal::peripherals::GPIO21<'static>,
+    overlay: &mut SignatureOverlay,
+) -> Option<LoadReport> {
+    // SD cards must be initialized at <= 400 kHz; one small read doesn't
+    // justify reconfiguring the bus afterwards.
+    let spi_config = SpiConfig::default()
+        .with_frequency(Rate::from_khz(400))
+        .with_mode(SpiMode::_0);
+    let spi = Spi::new(spi2, spi_config)
+        .ok()?
+        .with_sck(sck)
+        .with_miso(miso)
+        .with_mosi(mosi);
+    let cs = Output::new(cs_pin, Level::High, OutputConfig::default());
+    let spi_device = ExclusiveDevice::new_no_delay(spi, cs).ok()?;
+
+    let card = SdCard::new(spi_device, Delay::new());
+    let volume_mgr = VolumeManager::new(card, NoClock);
+    let volume = volume_mgr.open_volume(VolumeIdx(0)).ok()?;
+    let root = volume.open_root_dir().ok()?;
+    let file = root
+        .open_file_in_dir(sigfile::SD_FILE_NAME, Mode::ReadOnly)
+        .ok()?;
+
+    if file.length() as usize > MAX_FILE_LEN {
+        log::warn!(
+            "{} is {} bytes, only the first {} are read",
+            sigfile::SD_FILE_NAME,
+            file.length(),
+            MAX_FILE_LEN
+        );
+    }
+
+    let mut buf = vec![0u8; MAX_FILE_LEN];
+    let mut len = 0;
+    while !file.is_eof() && len < buf.len() {
+        match file.read(&mut buf[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(e) => {
+                log::error!("SD read error: {:?}", e);
+                return None;
+            }
+        }
+    }
+
+    sigfile::load_signatures(&buf[..len], overlay).ok()
+}
@@ -0,0 +1,204 @@
+/// 802.11 sequence number continuity per transmitter.
+///
+/// A station numbers the management frames it sends with a 12-bit sequence
+/// counter, one step per frame. An attacker spoofing an access point's
+/// address — injecting deauthentications or beacons under it — runs its own
+/// counter, and so do two devices sharing one address, so frames under that
+/// address jump back and forth. [`SequenceMonitor`] follows each
+/// transmitter's counter against uptime and flags a jump the time since its
+/// last frame can't explain, and frames alternating between two counters;
+/// with `FilterConfig::sequence_heuristics` on, `filter::SequenceHeuristics`
+/// reports these as `seq_anomaly` matches.
+use heapless::Vec;
+
+/// Sequence numbers wrap at 4096
+const SEQ_MASK: u16 = 0x0FFF;
+
+/// A transmitter not heard for this long is forgotten
+pub const SEQ_TTL_MS: u32 = 60 * 1000;
+
+/// How far a counter may always advance between two frames we hear —
+/// retransmissions, and frames sent while we listened elsewhere
+pub const SEQ_SLACK: u32 = 64;
+
+/// Frames per second a transmitter may have sent unheard, on top of
+/// [`SEQ_SLACK`], while we hopped the other channels
+pub const SEQ_RATE_PER_S: u32 = 250;
+
+/// An address's anomalies are reported at most this often
+pub const SEQ_REPORT_INTERVAL_MS: u32 = 10 * 1000;
+
+/// What is off about a transmitter's sequence numbers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SequenceAnomaly {
+    /// The counter jumped further than the frames since the last one
+    /// explain, or went back
+    Jump,
+    /// Frames alternate between two counters: two devices send under the
+    /// address
+    Interleaved,
+}
+
+impl SequenceAnomaly {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SequenceAnomaly::Jump => "sequence jump",
+            SequenceAnomaly::Interleaved => "interleaved sequences",
+        }
+    }
+}
+
+/// One sequence counter heard under an address
+#[derive(Debug, Clone, Copy)]
+struct Counter {
+    seq: u16,
+    last_ms: u32,
+}
+
+impl Counter {
+    /// Whether `seq` can be this counter's at `now_ms`
+    fn follows(&self, seq: u16, now_ms: u32) -> bool {
+        let advance = seq.wrapping_sub(self.seq) & SEQ_MASK;
+        let elapsed_ms = now_ms.wrapping_sub(self.last_ms);
+        let allowance = SEQ_SLACK.saturating_add(elapsed_ms / 1000 * SEQ_RATE_PER_S);
+        advance as u32 <= allowance
+    }
+}
+
+struct Entry {
+    mac: [u8; 6],
+    counter: Counter,
+    /// The counter heard before the latest jump
+    other: Option<Counter>,
+    reported_ms: Option<u32>,
+}
+
+/// Sequence counters of up to `N` transmitters. Timestamps are milliseconds
+/// since boot (`u32`, wrapping).
+pub struct SequenceMonitor<const N: usize> {
+    entries: Vec<Entry, N>,
+}
+
+impl<const N: usize> SequenceMonitor<N> {
+    pub const fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Record a management frame from `mac` numbered `seq` and return what
+    /// is off about its sequence, if anything and not reported in the last
+    /// [`SEQ_REPORT_INTERVAL_MS`].
+    pub fn observe(&mut self, mac: &[u8; 6], seq: u16, now_ms: u32) -> Option<SequenceAnomaly> {
+        self.entries
+            .retain(|e| now_ms.wrapping_sub(e.counter.last_ms) < SEQ_TTL_MS);
+
+        let counter = Counter {
+            seq: seq & SEQ_MASK,
+            last_ms: now_ms,
+        };
+        let Some(entry) = self.entries.iter_mut().find(|e| e.mac == *mac) else {
+            self.insert(Entry {
+                mac: *mac,
+                counter,
+                other: None,
+                reported_ms: None,
+            });
+            return None;
+        };
+
+        if entry.counter.follows(counter.seq, now_ms) {
+            entry.counter = counter;
+            return None;
+        }
+        let switched_back = entry
+            .other
+            .is_some_and(|other| other.follows(counter.seq, now_ms));
+        entry.other = Some(entry.counter);
+        entry.counter = counter;
+
+        let due = entry
+            .reported_ms
+            .is_none_or(|at| now_ms.wrapping_sub(at) >= SEQ_REPORT_INTERVAL_MS);
+        if !due {
+            return None;
+        }
+        entry.reported_ms = Some(now_ms);
+        Some(if switched_back {
+            SequenceAnomaly::Interleaved
+        } else {
+            SequenceAnomaly::Jump
+        })
+    }
+
+    /// Add an entry, replacing the one heard from longest ago when full.
+    fn insert(&mut self, entry: Entry) {
+        if let Err(entry) = self.entries.push(entry) {
+            let now_ms = entry.counter.last_ms;
+            let stalest = self
+                .entries
+                .iter_mut()
+                .max_by_key(|e| now_ms.wrapping_sub(e.counter.last_ms));
+            if let Some(stalest) = stalest {
+                *stalest = entry;
+            }
+        }
+    }
+}
+
+impl<const N: usize> Default for SequenceMonitor<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AP: [u8; 6] = [0x02, 0xAA, 0xBB, 0xCC, 0xDD, 0xEE];
+
+    #[test]
+    fn steady_counter_passes() {
+        let mut seqs = SequenceMonitor::<4>::new();
+        // Wrapping at 4096, with gaps while we listened elsewhere
+        for (i, seq) in [4000, 4001, 4030, 4090, 10, 10, 50].into_iter().enumerate() {
+            assert_eq!(seqs.observe(&AP, seq, i as u32 * 100), None);
+        }
+        // A long absence explains a long advance
+        assert_eq!(seqs.observe(&AP, 1_050, 5_000), None);
+    }
+
+    #[test]
+    fn backward_step_is_a_jump() {
+        let mut seqs = SequenceMonitor::<4>::new();
+        seqs.observe(&AP, 1_000, 0);
+        assert_eq!(seqs.observe(&AP, 900, 100), Some(SequenceAnomaly::Jump));
+        // The new counter is followed from here
+        assert_eq!(seqs.observe(&AP, 901, 200), None);
+    }
+
+    #[test]
+    fn alternating_counters_interleave() {
+        let mut seqs = SequenceMonitor::<4>::new();
+        seqs.observe(&AP, 100, 0);
+        // Two devices taking turns every 100 ms, reported once per interval
+        let reports: std::vec::Vec<_> = (1..=110u16)
+            .filter_map(|i| {
+                let seq = if i % 2 == 1 { 3_000 + i } else { 100 + i };
+                seqs.observe(&AP, seq, i as u32 * 100)
+            })
+            .collect();
+        assert_eq!(
+            reports,
+            [SequenceAnomaly::Jump, SequenceAnomaly::Interleaved]
+        );
+    }
+
+    #[test]
+    fn transmitters_expire() {
+        let mut seqs = SequenceMonitor::<4>::new();
+        seqs.observe(&AP, 1_000, 0);
+        assert_eq!(seqs.observe(&AP, 10, SEQ_TTL_MS), None);
+    }
+}
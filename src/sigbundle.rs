@@ -0,0 +1,933 @@
+/// Signed signature bundles: detection updates without a reflash.
+///
+/// A bundle is a compact binary list of signature records stamped with a
+/// serial number and signed with an ed25519 key whose public half is compiled
+/// into the firmware. The companion streams it in hex chunks over the command
+/// channel (`sig_begin`, `sig_chunk`, `sig_commit`) into a [`BundleStager`].
+/// [`install`] verifies the staged bundle and persists it through the config
+/// store, keeping the bundle it replaces for [`rollback`]; the firmware
+/// reboots and [`load_installed`] adds it to the [`SignatureOverlay`]. A
+/// bundle that fails verification at boot is rolled back automatically.
+///
+/// Layout, little-endian:
+///
+/// ```text
+/// "AHSB" | version u8 | reserved u8 | serial u32 | payload len u16 | payload | signature [64]
+/// ```
+///
+/// The signature covers everything before it. The payload is a sequence of
+/// `[kind u8] [len u8] [body]` records, see [`Record`]. Unknown kinds are
+/// skipped. Bundles carry signatures only: detection rules are evaluated
+/// over the compiled-in packs' signatures and ship with the firmware.
+///
+/// Signing ([`sign_bundle`], [`public_key`]) is for host tools and tests and
+/// is only built with `std`; the firmware only verifies.
+use ed25519_dalek::{Signature, VerifyingKey};
+#[cfg(any(test, feature = "std"))]
+use ed25519_dalek::{Signer, SigningKey};
+use heapless::{String, Vec};
+
+use crate::defaults::SignatureMeta;
+use crate::error::AirhoundError;
+use crate::sigfile::{self, LoadReport, SignatureOverlay, StringMatch, StringSig};
+use crate::store::{ConfigKey, ConfigStore, StoreError, RECORD_HEADER_LEN};
+
+pub const MAGIC: [u8; 4] = *b"AHSB";
+
+/// Bundle format version
+pub const FORMAT_VERSION: u8 = 1;
+
+pub const HEADER_LEN: usize = 12;
+
+pub const SIGNATURE_LEN: usize = 64;
+
+/// Largest bundle the device accepts. The installed and the previous bundle
+/// share one 4 KiB NVS sector.
+pub const MAX_BUNDLE_LEN: usize = 2048 - RECORD_HEADER_LEN;
+
+/// Largest `sig_chunk` payload: 256 hex digits keep the command line well
+/// under [`crate::protocol::MAX_MSG_LEN`]
+pub const MAX_CHUNK_LEN: usize = 128;
+
+const KIND_MAC_OUI: u8 = 1;
+const KIND_WIFI_SSID: u8 = 2;
+const KIND_BLE_NAME: u8 = 3;
+const KIND_SERVICE_UUID_16: u8 = 4;
+const KIND_MANUFACTURER_ID: u8 = 5;
+const KIND_WIFI_TRAFFIC: u8 = 6;
+
+/// String signature body: match kind and case sensitivity, then the value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StringRecord<'a> {
+    pub kind: StringMatch,
+    pub case_sensitive: bool,
+    pub value: &'a str,
+    pub min_sightings: u8,
+    pub description: &'a str,
+}
+
+/// One bundle record. Bodies, after the `[kind] [len]` header:
+///
+/// - `MacOui`: oui [3], min_sightings, description
+/// - `WifiSsid`/`BleName`: flags (bits 0–1 match kind: exact, prefix,
+///   contains, glob; bit 7 case-insensitive), min_sightings, value len, value,
+///   description
+/// - `ServiceUuid16`/`ManufacturerId`: id u16, min_sightings, description
+/// - `WifiTraffic`: description
+///
+/// Descriptions run to the end of the body. A `min_sightings` of 0 means 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Record<'a> {
+    MacOui {
+        oui: [u8; 3],
+        min_sightings: u8,
+        description: &'a str,
+    },
+    WifiSsid(StringRecord<'a>),
+    BleName(StringRecord<'a>),
+    ServiceUuid16 {
+        uuid: u16,
+        min_sightings: u8,
+        description: &'a str,
+    },
+    ManufacturerId {
+        id: u16,
+        min_sightings: u8,
+        description: &'a str,
+    },
+    WifiTraffic {
+        description: &'a str,
+    },
+}
+
+impl<'a> Record<'a> {
+    /// Encode with its record header into `out`. Returns the bytes written.
+    pub fn encode(&self, out: &mut [u8]) -> Result<usize, AirhoundError> {
+        let mut body: Vec<u8, 255> = Vec::new();
+        let mut put = |bytes: &[u8]| {
+            body.extend_from_slice(bytes)
+                .map_err(|_| AirhoundError::InvalidArgument)
+        };
+        let kind = match *self {
+            Record::MacOui {
+                oui,
+                min_sightings,
+                description,
+            } => {
+                put(&oui)?;
+                put(&[min_sightings])?;
+                put(description.as_bytes())?;
+                KIND_MAC_OUI
+            }
+            Record::WifiSsid(s) | Record::BleName(s) => {
+                let mut flags = match s.kind {
+                    StringMatch::Exact => 0,
+                    StringMatch::Prefix => 1,
+                    StringMatch::Contains => 2,
+                    StringMatch::Glob => 3,
+                };
+                if !s.case_sensitive {
+                    flags |= 0x80;
+                }
+                let value_len =
+                    u8::try_from(s.value.len()).map_err(|_| AirhoundError::InvalidArgument)?;
+                put(&[flags, s.min_sightings, value_len])?;
+                put(s.value.as_bytes())?;
+                put(s.description.as_bytes())?;
+                if matches!(self, Record::WifiSsid(_)) {
+                    KIND_WIFI_SSID
+                } else {
+                    KIND_BLE_NAME
+                }
+            }
+            Record::ServiceUuid16 {
+                uuid: id,
+                min_sightings,
+                description,
+            }
+            | Record::ManufacturerId {
+                id,
+                min_sightings,
+                description,
+            } => {
+                put(&id.to_le_bytes())?;
+                put(&[min_sightings])?;
+                put(description.as_bytes())?;
+                if matches!(self, Record::ServiceUuid16 { .. }) {
+                    KIND_SERVICE_UUID_16
+                } else {
+                    KIND_MANUFACTURER_ID
+                }
+            }
+            Record::WifiTraffic { description } => {
+                put(description.as_bytes())?;
+                KIND_WIFI_TRAFFIC
+            }
+        };
+        let total = 2 + body.len();
+        let out = out.get_mut(..total).ok_or(AirhoundError::BufferTooSmall)?;
+        out[0] = kind;
+        out[1] = body.len() as u8;
+        out[2..].copy_from_slice(&body);
+        Ok(total)
+    }
+
+    /// Decode a record body. Unknown kinds are `Ok(None)`.
+    fn decode(kind: u8, body: &'a [u8]) -> Result<Option<Self>, AirhoundError> {
+        let text =
+            |bytes: &'a [u8]| core::str::from_utf8(bytes).map_err(|_| AirhoundError::Malformed);
+        let record = match (kind, body) {
+            (KIND_MAC_OUI, &[a, b, c, min_sightings, ref description @ ..]) => Record::MacOui {
+                oui: [a, b, c],
+                min_sightings,
+                description: text(description)?,
+            },
+            (KIND_WIFI_SSID | KIND_BLE_NAME, &[flags, min_sightings, value_len, ref rest @ ..]) => {
+                let value_len = value_len as usize;
+                if rest.len() < value_len {
+                    return Err(AirhoundError::Malformed);
+                }
+                let kind_bits = match flags & 0x03 {
+                    0 => StringMatch::Exact,
+                    1 => StringMatch::Prefix,
+                    2 => StringMatch::Contains,
+                    _ => StringMatch::Glob,
+                };
+                let s = StringRecord {
+                    kind: kind_bits,
+                    case_sensitive: flags & 0x80 == 0,
+                    value: text(&rest[..value_len])?,
+                    min_sightings,
+                    description: text(&rest[value_len..])?,
+                };
+                if kind == KIND_WIFI_SSID {
+                    Record::WifiSsid(s)
+                } else {
+                    Record::BleName(s)
+                }
+            }
+            (KIND_SERVICE_UUID_16, &[lo, hi, min_sightings, ref description @ ..]) => {
+                Record::ServiceUuid16 {
+                    uuid: u16::from_le_bytes([lo, hi]),
+                    min_sightings,
+                    description: text(description)?,
+                }
+            }
+            (KIND_MANUFACTURER_ID, &[lo, hi, min_sightings, ref description @ ..]) => {
+                Record::ManufacturerId {
+                    id: u16::from_le_bytes([lo, hi]),
+                    min_sightings,
+                    description: text(description)?,
+                }
+            }
+            (KIND_WIFI_TRAFFIC, description) => Record::WifiTraffic {
+                description: text(description)?,
+            },
+            (KIND_MAC_OUI..=KIND_MANUFACTURER_ID, _) => return Err(AirhoundError::Malformed),
+            _ => return Ok(None),
+        };
+        Ok(Some(record))
+    }
+}
+
+/// Iterate the records of a bundle payload. Unknown kinds yield `Ok(None)`;
+/// iteration stops after the first error.
+pub fn records(payload: &[u8]) -> impl Iterator<Item = Result<Option<Record<'_>>, AirhoundError>> {
+    let mut rest = payload;
+    core::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let record = match rest {
+            &[kind, len, ref tail @ ..] if tail.len() >= len as usize => {
+                let (body, tail) = tail.split_at(len as usize);
+                rest = tail;
+                Record::decode(kind, body)
+            }
+            _ => {
+                rest = &[];
+                Err(AirhoundError::Malformed)
+            }
+        };
+        Some(record)
+    })
+}
+
+/// A parsed bundle, borrowing its bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bundle<'a> {
+    /// Monotonic bundle serial; a device only installs newer serials
+    pub serial: u32,
+    pub payload: &'a [u8],
+}
+
+impl<'a> Bundle<'a> {
+    /// Parse the header. Does not check the signature.
+    pub fn parse(data: &'a [u8]) -> Result<Self, AirhoundError> {
+        let Some((header, _)) = data.split_first_chunk::<HEADER_LEN>() else {
+            return Err(AirhoundError::Malformed);
+        };
+        if header[..4] != MAGIC {
+            return Err(AirhoundError::Malformed);
+        }
+        if header[4] != FORMAT_VERSION {
+            return Err(AirhoundError::Unsupported);
+        }
+        let serial = u32::from_le_bytes([header[6], header[7], header[8], header[9]]);
+        let payload_len = u16::from_le_bytes([header[10], header[11]]) as usize;
+        if data.len() != HEADER_LEN + payload_len + SIGNATURE_LEN {
+            return Err(AirhoundError::Malformed);
+        }
+        Ok(Self {
+            serial,
+            payload: &data[HEADER_LEN..HEADER_LEN + payload_len],
+        })
+    }
+
+    /// Parse a bundle, check its signature against `public_key`, and check
+    /// that every record decodes.
+    pub fn verify(data: &'a [u8], public_key: &[u8; 32]) -> Result<Self, AirhoundError> {
+        let bundle = Self::parse(data)?;
+        let key =
+            VerifyingKey::from_bytes(public_key).map_err(|_| AirhoundError::InvalidArgument)?;
+        let (signed, signature) = data.split_at(data.len() - SIGNATURE_LEN);
+        let signature =
+            Signature::from_slice(signature).map_err(|_| AirhoundError::BadSignature)?;
+        key.verify_strict(signed, &signature)
+            .map_err(|_| AirhoundError::BadSignature)?;
+        for record in records(bundle.payload) {
+            record?;
+        }
+        Ok(bundle)
+    }
+
+    /// Append the bundle's signatures to `overlay`. Call on a verified
+    /// bundle: malformed records are counted as skipped.
+    pub fn load(&self, overlay: &mut SignatureOverlay) -> LoadReport {
+        let mut report = LoadReport::default();
+        for record in records(self.payload) {
+            if record
+                .ok()
+                .flatten()
+                .is_some_and(|r| add_record(&r, overlay))
+            {
+                report.loaded += 1;
+            } else {
+                report.skipped += 1;
+            }
+        }
+        report
+    }
+}
+
+// Records carry no rating; bundle signatures load as `SignatureMeta::UNRATED`
+fn add_record(record: &Record<'_>, overlay: &mut SignatureOverlay) -> bool {
+    let detail = sigfile::detail;
+    let meta = SignatureMeta::UNRATED;
+    match *record {
+        Record::MacOui {
+            oui,
+            min_sightings,
+            description,
+        } => overlay
+            .mac_prefixes
+            .push((oui, detail(description), min_sightings.max(1), meta))
+            .is_ok(),
+        Record::WifiSsid(s) => string_sig(&s).is_some_and(|sig| overlay.ssids.push(sig).is_ok()),
+        Record::BleName(s) => string_sig(&s).is_some_and(|sig| overlay.ble_names.push(sig).is_ok()),
+        Record::ServiceUuid16 {
+            uuid,
+            min_sightings,
+            description,
+        } => overlay
+            .service_uuids_16
+            .push((uuid, detail(description), min_sightings.max(1), meta))
+            .is_ok(),
+        Record::ManufacturerId {
+            id,
+            min_sightings,
+            description,
+        } => overlay
+            .manufacturer_ids
+            .push((id, detail(description), min_sightings.max(1), meta))
+            .is_ok(),
+        // One per overlay, as in signature files
+        Record::WifiTraffic { description } if overlay.high_traffic_aps.is_none() => {
+            let description = detail(if description.is_empty() {
+                "high-traffic AP"
+            } else {
+                description
+            });
+            overlay.high_traffic_aps = Some((description, meta));
+            true
+        }
+        _ => false,
+    }
+}
+
+fn string_sig(s: &StringRecord<'_>) -> Option<StringSig> {
+    let mut value = String::new();
+    value.push_str(s.value).ok()?;
+    Some(StringSig {
+        kind: s.kind,
+        value,
+        case_sensitive: s.case_sensitive,
+        // Fall back to the matched value, as signature files do
+        description: sigfile::detail(if s.description.is_empty() {
+            s.value
+        } else {
+            s.description
+        }),
+        min_sightings: s.min_sightings.max(1),
+        meta: SignatureMeta::UNRATED,
+    })
+}
+
+/// Assemble a bundle from encoded records and sign it with `secret_key`.
+/// Returns the bundle length written to `out`.
+#[cfg(any(test, feature = "std"))]
+pub fn sign_bundle(
+    serial: u32,
+    payload: &[u8],
+    secret_key: &[u8; 32],
+    out: &mut [u8],
+) -> Result<usize, AirhoundError> {
+    let payload_len = u16::try_from(payload.len()).map_err(|_| AirhoundError::InvalidArgument)?;
+    let total = HEADER_LEN + payload.len() + SIGNATURE_LEN;
+    let out = out.get_mut(..total).ok_or(AirhoundError::BufferTooSmall)?;
+    out[..4].copy_from_slice(&MAGIC);
+    out[4] = FORMAT_VERSION;
+    out[5] = 0;
+    out[6..10].copy_from_slice(&serial.to_le_bytes());
+    out[10..12].copy_from_slice(&payload_len.to_le_bytes());
+    out[HEADER_LEN..total - SIGNATURE_LEN].copy_from_slice(payload);
+    let signature = SigningKey::from_bytes(secret_key).sign(&out[..total - SIGNATURE_LEN]);
+    out[total - SIGNATURE_LEN..].copy_from_slice(&signature.to_bytes());
+    Ok(total)
+}
+
+/// Public key of the ed25519 key pair for `secret_key`.
+#[cfg(any(test, feature = "std"))]
+pub fn public_key(secret_key: &[u8; 32]) -> [u8; 32] {
+    SigningKey::from_bytes(secret_key)
+        .verifying_key()
+        .to_bytes()
+}
+
+// ── Updates ────────────────────────────────────────────────────────────
+
+/// Why a bundle update step failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateError {
+    /// The bundle itself was rejected: malformed, unsupported version, or a
+    /// bad signature
+    Bundle(AirhoundError),
+    /// A chunk or commit without a preceding `sig_begin`
+    NotStarted,
+    /// A chunk that doesn't continue where the previous one ended
+    OutOfOrder,
+    /// More data than announced, or a bundle over [`MAX_BUNDLE_LEN`]
+    TooLarge,
+    /// Commit before all announced bytes arrived
+    Incomplete,
+    /// Serial not newer than the installed bundle
+    Stale,
+    /// Rollback without a previous bundle
+    NoPrevious,
+    /// Firmware built without a bundle verification key
+    NoKey,
+    Store(StoreError),
+}
+
+impl UpdateError {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            UpdateError::Bundle(e) => e.as_str(),
+            UpdateError::NotStarted => "not started",
+            UpdateError::OutOfOrder => "out of order",
+            UpdateError::TooLarge => "too large",
+            UpdateError::Incomplete => "incomplete",
+            UpdateError::Stale => "stale serial",
+            UpdateError::NoPrevious => "no previous bundle",
+            UpdateError::NoKey => "no key",
+            UpdateError::Store(_) => "store error",
+        }
+    }
+}
+
+impl From<AirhoundError> for UpdateError {
+    fn from(e: AirhoundError) -> Self {
+        UpdateError::Bundle(e)
+    }
+}
+
+impl From<StoreError> for UpdateError {
+    fn from(e: StoreError) -> Self {
+        UpdateError::Store(e)
+    }
+}
+
+/// Reassembles a bundle from in-order chunks
+pub struct BundleStager<const N: usize = MAX_BUNDLE_LEN> {
+    buf: [u8; N],
+    /// Announced length; `None` when no transfer is in progress
+    expected: Option<usize>,
+    received: usize,
+}
+
+impl<const N: usize> BundleStager<N> {
+    pub const fn new() -> Self {
+        Self {
+            buf: [0; N],
+            expected: None,
+            received: 0,
+        }
+    }
+
+    /// Start a transfer of `len` bytes, discarding any partial one.
+    pub fn begin(&mut self, len: usize) -> Result<(), UpdateError> {
+        self.expected = None;
+        self.received = 0;
+        if len > N {
+            return Err(UpdateError::TooLarge);
+        }
+        if len < HEADER_LEN + SIGNATURE_LEN {
+            return Err(AirhoundError::Malformed.into());
+        }
+        self.expected = Some(len);
+        Ok(())
+    }
+
+    /// Append a chunk at `offset`, which must be the number of bytes
+    /// received so far. Returns the new total.
+    pub fn chunk(&mut self, offset: usize, data: &[u8]) -> Result<usize, UpdateError> {
+        let expected = self.expected.ok_or(UpdateError::NotStarted)?;
+        if offset != self.received {
+            return Err(UpdateError::OutOfOrder);
+        }
+        let end = offset + data.len();
+        if end > expected {
+            return Err(UpdateError::TooLarge);
+        }
+        self.buf[offset..end].copy_from_slice(data);
+        self.received = end;
+        Ok(end)
+    }
+
+    /// Bytes received in the current transfer
+    pub fn received(&self) -> usize {
+        self.received
+    }
+
+    /// End the transfer and return the complete bundle bytes.
+    pub fn finish(&mut self) -> Result<&[u8], UpdateError> {
+        let expected = self.expected.ok_or(UpdateError::NotStarted)?;
+        if self.received != expected {
+            return Err(UpdateError::Incomplete);
+        }
+        self.expected = None;
+        self.received = 0;
+        Ok(&self.buf[..expected])
+    }
+}
+
+impl<const N: usize> Default for BundleStager<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Verify `bundle` and make it the installed bundle, keeping the current
+/// one as the rollback copy. `scratch` must hold [`MAX_BUNDLE_LEN`] bytes.
+/// Returns the new serial.
+pub fn install(
+    store: &mut impl ConfigStore,
+    bundle: &[u8],
+    public_key: &[u8; 32],
+    scratch: &mut [u8],
+) -> Result<u32, UpdateError> {
+    let new = Bundle::verify(bundle, public_key)?;
+    match store.load(ConfigKey::SigBundle, scratch) {
+        Ok(len) => {
+            // A current bundle that doesn't parse isn't worth keeping
+            if let Ok(current) = Bundle::parse(&scratch[..len]) {
+                if new.serial <= current.serial {
+                    return Err(UpdateError::Stale);
+                }
+                store.save(ConfigKey::SigBundlePrev, &scratch[..len])?;
+            }
+        }
+        Err(StoreError::NotFound | StoreError::Corrupt) => {}
+        Err(e) => return Err(e.into()),
+    }
+    store.save(ConfigKey::SigBundle, bundle)?;
+    Ok(new.serial)
+}
+
+/// Reinstate the previous bundle. On success `scratch` holds it and the
+/// returned [`Bundle`] borrows it.
+pub fn rollback<'a>(
+    store: &mut impl ConfigStore,
+    public_key: &[u8; 32],
+    scratch: &'a mut [u8],
+) -> Result<Bundle<'a>, UpdateError> {
+    let len = match store.load(ConfigKey::SigBundlePrev, scratch) {
+        Ok(len) => len,
+        Err(StoreError::NotFound) => return Err(UpdateError::NoPrevious),
+        Err(e) => return Err(e.into()),
+    };
+    Bundle::verify(&scratch[..len], public_key)?;
+    store.save(ConfigKey::SigBundle, &scratch[..len])?;
+    store.erase(ConfigKey::SigBundlePrev)?;
+    Ok(Bundle::parse(&scratch[..len])?)
+}
+
+/// Signatures loaded from the installed bundle at boot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Installed {
+    pub serial: u32,
+    pub report: LoadReport,
+    /// The installed bundle failed verification and the previous one was
+    /// reinstated
+    pub rolled_back: bool,
+}
+
+/// Load the installed bundle into `overlay`. A bundle that is missing or
+/// fails verification is replaced by the previous one, if that verifies.
+/// `buf` must hold [`MAX_BUNDLE_LEN`] bytes.
+pub fn load_installed(
+    store: &mut impl ConfigStore,
+    public_key: &[u8; 32],
+    buf: &mut [u8],
+    overlay: &mut SignatureOverlay,
+) -> Option<Installed> {
+    if let Ok(len) = store.load(ConfigKey::SigBundle, buf) {
+        if let Ok(bundle) = Bundle::verify(&buf[..len], public_key) {
+            return Some(Installed {
+                serial: bundle.serial,
+                report: bundle.load(overlay),
+                rolled_back: false,
+            });
+        }
+    }
+    let bundle = rollback(store, public_key, buf).ok()?;
+    Some(Installed {
+        serial: bundle.serial,
+        report: bundle.load(overlay),
+        rolled_back: true,
+    })
+}
+
+// ── Hex ────────────────────────────────────────────────────────────────
+
+/// Parse a 64-digit hex ed25519 public key.
+pub fn parse_key(hex: &str) -> Option<[u8; 32]> {
+    let mut key = [0u8; 32];
+    (decode_hex(hex, &mut key)? == key.len()).then_some(key)
+}
+
+/// Decode a `sig_chunk` hex payload.
+pub fn chunk_from_hex(hex: &str) -> Option<Vec<u8, MAX_CHUNK_LEN>> {
+    let mut chunk = Vec::new();
+    chunk.resize_default(hex.len() / 2).ok()?;
+    decode_hex(hex, &mut chunk)?;
+    Some(chunk)
+}
+
+/// Decode `hex` into the start of `out`, returning the bytes written.
+fn decode_hex(hex: &str, out: &mut [u8]) -> Option<usize> {
+    let pairs = hex.as_bytes().chunks_exact(2);
+    if !pairs.remainder().is_empty() || pairs.len() > out.len() {
+        return None;
+    }
+    let len = pairs.len();
+    for (byte, pair) in out.iter_mut().zip(pairs) {
+        let digit = |b: u8| (b as char).to_digit(16);
+        *byte = (digit(pair[0])? << 4 | digit(pair[1])?) as u8;
+    }
+    Some(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: [u8; 32] = [7; 32];
+
+    fn payload(records: &[Record<'_>]) -> Vec<u8, 512> {
+        let mut out = Vec::new();
+        for record in records {
+            let mut buf = [0u8; 258];
+            let len = record.encode(&mut buf).unwrap();
+            out.extend_from_slice(&buf[..len]).unwrap();
+        }
+        out
+    }
+
+    fn bundle(serial: u32, records: &[Record<'_>]) -> Vec<u8, MAX_BUNDLE_LEN> {
+        let mut out = Vec::new();
+        out.resize_default(MAX_BUNDLE_LEN).unwrap();
+        let len = sign_bundle(serial, &payload(records), &SECRET, &mut out).unwrap();
+        out.truncate(len);
+        out
+    }
+
+    fn oui(description: &str) -> Record<'_> {
+        Record::MacOui {
+            oui: [0xAA, 0xBB, 0xCC],
+            min_sightings: 0,
+            description,
+        }
+    }
+
+    /// In-memory store, one slot per key
+    struct MemStore {
+        slots: [Option<Vec<u8, MAX_BUNDLE_LEN>>; 7],
+    }
+
+    impl MemStore {
+        fn new() -> Self {
+            Self {
+                slots: Default::default(),
+            }
+        }
+    }
+
+    impl ConfigStore for MemStore {
+        fn load(&mut self, key: ConfigKey, buf: &mut [u8]) -> Result<usize, StoreError> {
+            let data = self.slots[key.slot() as usize]
+                .as_ref()
+                .ok_or(StoreError::NotFound)?;
+            buf[..data.len()].copy_from_slice(data);
+            Ok(data.len())
+        }
+
+        fn save(&mut self, key: ConfigKey, data: &[u8]) -> Result<(), StoreError> {
+            self.slots[key.slot() as usize] =
+                Some(Vec::from_slice(data).map_err(|_| StoreError::TooLarge)?);
+            Ok(())
+        }
+
+        fn erase(&mut self, key: ConfigKey) -> Result<(), StoreError> {
+            self.slots[key.slot() as usize] = None;
+            Ok(())
+        }
+    }
+
+    // ── Format ──────────────────────────────────────────────────────
+
+    #[test]
+    fn records_round_trip() {
+        let ssid = StringRecord {
+            kind: StringMatch::Prefix,
+            case_sensitive: false,
+            value: "Verkada-",
+            min_sightings: 2,
+            description: "Verkada camera",
+        };
+        let all = [
+            oui("Verkada"),
+            Record::WifiSsid(ssid),
+            Record::BleName(StringRecord {
+                kind: StringMatch::Contains,
+                case_sensitive: true,
+                ..ssid
+            }),
+            Record::ServiceUuid16 {
+                uuid: 0xFEED,
+                min_sightings: 1,
+                description: "Tile",
+            },
+            Record::ManufacturerId {
+                id: 0x09C8,
+                min_sightings: 1,
+                description: "",
+            },
+            Record::WifiTraffic {
+                description: "busy",
+            },
+        ];
+        let encoded = payload(&all);
+        let decoded: Vec<Record<'_>, 8> = records(&encoded).map(|r| r.unwrap().unwrap()).collect();
+        assert_eq!(decoded.as_slice(), all);
+    }
+
+    #[test]
+    fn truncated_records_are_malformed() {
+        let encoded = payload(&[oui("Verkada")]);
+        let mut all = records(&encoded[..encoded.len() - 1]);
+        assert_eq!(all.next(), Some(Err(AirhoundError::Malformed)));
+        assert_eq!(all.next(), None);
+        // A known kind with a short body
+        assert_eq!(
+            records(&[1, 2, 0xAA, 0xBB]).next(),
+            Some(Err(AirhoundError::Malformed))
+        );
+        // Unknown kinds are skipped, not errors
+        assert_eq!(records(&[0x7F, 1, 0]).next(), Some(Ok(None)));
+    }
+
+    #[test]
+    fn signed_bundle_verifies_and_loads() {
+        let data = bundle(3, &[oui("Verkada")]);
+        let bundle = Bundle::verify(&data, &public_key(&SECRET)).unwrap();
+        assert_eq!(bundle.serial, 3);
+
+        let mut overlay = SignatureOverlay::new();
+        let report = bundle.load(&mut overlay);
+        assert_eq!(
+            report,
+            LoadReport {
+                loaded: 1,
+                skipped: 0
+            }
+        );
+        let (prefix, description, min_sightings, meta) = &overlay.mac_prefixes[0];
+        assert_eq!(*prefix, [0xAA, 0xBB, 0xCC]);
+        assert_eq!(description.as_str(), "Verkada");
+        assert_eq!(*min_sightings, 1);
+        assert_eq!(*meta, SignatureMeta::UNRATED);
+    }
+
+    #[test]
+    fn tampered_or_foreign_bundles_are_rejected() {
+        let key = public_key(&SECRET);
+        let mut data = bundle(1, &[oui("Verkada")]);
+        data[HEADER_LEN + 3] ^= 0x01;
+        assert_eq!(
+            Bundle::verify(&data, &key),
+            Err(AirhoundError::BadSignature)
+        );
+
+        let data = bundle(1, &[oui("Verkada")]);
+        let other = public_key(&[9; 32]);
+        assert_eq!(
+            Bundle::verify(&data, &other),
+            Err(AirhoundError::BadSignature)
+        );
+
+        let mut bad_version = data.clone();
+        bad_version[4] = 2;
+        assert_eq!(Bundle::parse(&bad_version), Err(AirhoundError::Unsupported));
+        assert_eq!(
+            Bundle::parse(&data[..data.len() - 1]),
+            Err(AirhoundError::Malformed)
+        );
+        assert_eq!(Bundle::parse(b"AHSB"), Err(AirhoundError::Malformed));
+    }
+
+    // ── Transfer ────────────────────────────────────────────────────
+
+    #[test]
+    fn stager_reassembles_in_order_chunks() {
+        let data = bundle(1, &[oui("Verkada"), oui("Verkada again")]);
+        let mut stager = BundleStager::<MAX_BUNDLE_LEN>::new();
+        assert_eq!(stager.chunk(0, &data), Err(UpdateError::NotStarted));
+
+        stager.begin(data.len()).unwrap();
+        let (first, rest) = data.split_at(40);
+        assert_eq!(stager.chunk(0, first), Ok(40));
+        assert_eq!(stager.chunk(0, first), Err(UpdateError::OutOfOrder));
+        assert_eq!(stager.finish(), Err(UpdateError::Incomplete));
+        assert_eq!(stager.chunk(40, rest), Ok(data.len()));
+        assert_eq!(stager.chunk(data.len(), &[0]), Err(UpdateError::TooLarge));
+        assert_eq!(stager.finish(), Ok(data.as_slice()));
+        assert_eq!(stager.finish(), Err(UpdateError::NotStarted));
+    }
+
+    #[test]
+    fn stager_rejects_impossible_lengths() {
+        let mut stager = BundleStager::<256>::new();
+        assert_eq!(stager.begin(257), Err(UpdateError::TooLarge));
+        assert_eq!(
+            stager.begin(HEADER_LEN),
+            Err(UpdateError::Bundle(AirhoundError::Malformed))
+        );
+        assert_eq!(stager.chunk(0, &[0]), Err(UpdateError::NotStarted));
+    }
+
+    // ── Install and rollback ────────────────────────────────────────
+
+    #[test]
+    fn install_keeps_previous_and_rejects_stale() {
+        let key = public_key(&SECRET);
+        let mut store = MemStore::new();
+        let mut scratch = [0u8; MAX_BUNDLE_LEN];
+        assert_eq!(
+            install(&mut store, &bundle(1, &[oui("one")]), &key, &mut scratch),
+            Ok(1)
+        );
+        assert!(store.slots[ConfigKey::SigBundlePrev.slot() as usize].is_none());
+        assert_eq!(
+            install(&mut store, &bundle(2, &[oui("two")]), &key, &mut scratch),
+            Ok(2)
+        );
+        assert_eq!(
+            install(&mut store, &bundle(2, &[oui("again")]), &key, &mut scratch),
+            Err(UpdateError::Stale)
+        );
+
+        let previous = rollback(&mut store, &key, &mut scratch).unwrap();
+        assert_eq!(previous.serial, 1);
+        assert_eq!(
+            rollback(&mut store, &key, &mut scratch),
+            Err(UpdateError::NoPrevious)
+        );
+    }
+
+    #[test]
+    fn install_rejects_unsigned_bundles() {
+        let mut store = MemStore::new();
+        let mut scratch = [0u8; MAX_BUNDLE_LEN];
+        let data = bundle(1, &[oui("one")]);
+        assert_eq!(
+            install(&mut store, &data, &public_key(&[9; 32]), &mut scratch),
+            Err(UpdateError::Bundle(AirhoundError::BadSignature))
+        );
+        assert!(store.slots.iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn boot_falls_back_to_previous_bundle() {
+        let key = public_key(&SECRET);
+        let mut store = MemStore::new();
+        let mut buf = [0u8; MAX_BUNDLE_LEN];
+        let mut overlay = SignatureOverlay::new();
+        assert_eq!(
+            load_installed(&mut store, &key, &mut buf, &mut overlay),
+            None
+        );
+
+        install(&mut store, &bundle(1, &[oui("one")]), &key, &mut buf).unwrap();
+        install(&mut store, &bundle(2, &[oui("two")]), &key, &mut buf).unwrap();
+        let installed = load_installed(&mut store, &key, &mut buf, &mut overlay).unwrap();
+        assert_eq!((installed.serial, installed.rolled_back), (2, false));
+
+        // Flip a payload bit in the installed bundle
+        let active = store.slots[ConfigKey::SigBundle.slot() as usize]
+            .as_mut()
+            .unwrap();
+        active[HEADER_LEN + 4] ^= 0x01;
+        let mut overlay = SignatureOverlay::new();
+        let installed = load_installed(&mut store, &key, &mut buf, &mut overlay).unwrap();
+        assert_eq!((installed.serial, installed.rolled_back), (1, true));
+        assert_eq!(overlay.mac_prefixes[0].1.as_str(), "one");
+        // The rollback is persisted
+        assert!(store.slots[ConfigKey::SigBundlePrev.slot() as usize].is_none());
+    }
+
+    #[test]
+    fn hex_keys_and_chunks() {
+        let key = public_key(&SECRET);
+        let mut hex: String<64> = String::new();
+        for byte in key {
+            core::fmt::Write::write_fmt(&mut hex, format_args!("{byte:02x}")).unwrap();
+        }
+        assert_eq!(parse_key(&hex), Some(key));
+        assert_eq!(parse_key(&hex[..62]), None);
+        assert_eq!(chunk_from_hex("0aFF").unwrap().as_slice(), [0x0A, 0xFF]);
+        assert!(chunk_from_hex("0g").is_none());
+        let too_long: String<258> = core::iter::repeat_n('0', 258).collect();
+        assert!(chunk_from_hex(&too_long).is_none());
+    }
+}
@@ -0,0 +1,851 @@
+/// Runtime-loadable signature database — the heap-backed counterpart of
+/// [`SignatureOverlay`](crate::sigfile::SignatureOverlay).
+///
+/// Tables grow without a fixed capacity, descriptions are kept in full, and
+/// `ble_ad_bytes` and `randomized_mac_fingerprint` patterns are evaluated, so a companion or host daemon can
+/// push new signatures at any time instead of reflashing. The filter functions
+/// accept it through [`SignatureSource`]. Requires the `alloc` feature.
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::defaults::SignatureMeta;
+use crate::error::AirhoundError;
+use crate::filter::SignatureSource;
+use crate::scanner::{NetworkKind, WpsField};
+use crate::sigfile::{
+    self, AdPatternRef, ExclusionRef, LoadReport, RawSignature, StringMatch, StringSigRef,
+    VendorIePatternRef,
+};
+
+/// A string signature (SSID or BLE name)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StringSignature {
+    pub kind: StringMatch,
+    pub value: String,
+    pub case_sensitive: bool,
+    pub description: String,
+    /// Sightings needed before a match is reported
+    pub min_sightings: u8,
+    pub meta: SignatureMeta,
+}
+
+impl StringSignature {
+    pub fn as_ref(&self) -> StringSigRef<'_> {
+        StringSigRef {
+            kind: self.kind,
+            value: &self.value,
+            case_sensitive: self.case_sensitive,
+            description: &self.description,
+            min_sightings: self.min_sightings,
+            meta: self.meta,
+        }
+    }
+}
+
+/// An `exclusion` signature: devices whose SSID or name matches `pattern`
+/// lose the matches with detail `excludes` (all if `None`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Exclusion {
+    pub pattern: StringSignature,
+    pub excludes: Option<String>,
+}
+
+impl Exclusion {
+    pub fn as_ref(&self) -> ExclusionRef<'_> {
+        ExclusionRef {
+            pattern: self.pattern.as_ref(),
+            excludes: self.excludes.as_deref(),
+        }
+    }
+}
+
+/// A raw advertisement byte pattern. `None` bytes are wildcards.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdPattern {
+    pub bytes: Vec<Option<u8>>,
+    /// Fixed offset into the advertisement; `None` searches the whole payload
+    pub offset: Option<u16>,
+    pub description: String,
+    /// Sightings needed before a match is reported
+    pub min_sightings: u8,
+    pub meta: SignatureMeta,
+}
+
+impl AdPattern {
+    pub fn as_ref(&self) -> AdPatternRef<'_> {
+        AdPatternRef {
+            bytes: &self.bytes,
+            offset: self.offset,
+            description: &self.description,
+            min_sightings: self.min_sightings,
+            meta: self.meta,
+        }
+    }
+}
+
+/// A WiFi vendor-specific information element pattern: the vendor's OUI and
+/// the leading bytes after it. `None` bytes are wildcards.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VendorIePattern {
+    pub oui: [u8; 3],
+    pub bytes: Vec<Option<u8>>,
+    pub description: String,
+    /// Sightings needed before a match is reported
+    pub min_sightings: u8,
+    pub meta: SignatureMeta,
+}
+
+impl VendorIePattern {
+    pub fn as_ref(&self) -> VendorIePatternRef<'_> {
+        VendorIePatternRef {
+            oui: self.oui,
+            bytes: &self.bytes,
+            description: &self.description,
+            min_sightings: self.min_sightings,
+            meta: self.meta,
+        }
+    }
+}
+
+/// Heap-backed runtime signatures. Table entries are
+/// `(value, description, min_sightings, meta)`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SignatureDb {
+    pub mac_prefixes: Vec<([u8; 3], String, u8, SignatureMeta)>,
+    pub ssids: Vec<StringSignature>,
+    pub ble_names: Vec<StringSignature>,
+    pub service_uuids_16: Vec<(u16, String, u8, SignatureMeta)>,
+    pub service_uuids_32: Vec<(u32, String, u8, SignatureMeta)>,
+    /// 128-bit service UUIDs off the Bluetooth base UUID
+    pub service_uuids_128: Vec<(u128, String, u8, SignatureMeta)>,
+    pub manufacturer_ids: Vec<(u16, String, u8, SignatureMeta)>,
+    pub ad_patterns: Vec<AdPattern>,
+    /// IE patterns for randomized WiFi addresses
+    pub ie_fingerprints: Vec<AdPattern>,
+    /// Advertisement patterns for random BLE addresses
+    pub ad_fingerprints: Vec<AdPattern>,
+    pub vendor_ies: Vec<VendorIePattern>,
+    /// WPS signatures, each restricted to one field or matching any if `None`
+    pub wps: Vec<(Option<WpsField>, StringSignature)>,
+    /// Mesh and WiFi Direct group signatures, matched against the network's
+    /// name
+    pub networks: Vec<(NetworkKind, StringSignature)>,
+    /// Flag access points with sustained heavy data traffic even without
+    /// another match (`wifi_traffic` signature)
+    pub high_traffic_aps: Option<(String, SignatureMeta)>,
+    /// Exclusions keyed on the WiFi SSID
+    pub ssid_exclusions: Vec<Exclusion>,
+    /// Exclusions keyed on the BLE or Classic device name
+    pub name_exclusions: Vec<Exclusion>,
+}
+
+impl SignatureDb {
+    pub const fn new() -> Self {
+        Self {
+            mac_prefixes: Vec::new(),
+            ssids: Vec::new(),
+            ble_names: Vec::new(),
+            service_uuids_16: Vec::new(),
+            service_uuids_32: Vec::new(),
+            service_uuids_128: Vec::new(),
+            manufacturer_ids: Vec::new(),
+            ad_patterns: Vec::new(),
+            ie_fingerprints: Vec::new(),
+            ad_fingerprints: Vec::new(),
+            vendor_ies: Vec::new(),
+            wps: Vec::new(),
+            networks: Vec::new(),
+            high_traffic_aps: None,
+            ssid_exclusions: Vec::new(),
+            name_exclusions: Vec::new(),
+        }
+    }
+
+    /// Total number of loaded signatures
+    pub fn len(&self) -> usize {
+        self.mac_prefixes.len()
+            + self.ssids.len()
+            + self.ble_names.len()
+            + self.service_uuids_16.len()
+            + self.service_uuids_32.len()
+            + self.service_uuids_128.len()
+            + self.manufacturer_ids.len()
+            + self.ad_patterns.len()
+            + self.ie_fingerprints.len()
+            + self.ad_fingerprints.len()
+            + self.vendor_ies.len()
+            + self.wps.len()
+            + self.networks.len()
+            + self.high_traffic_aps.is_some() as usize
+            + self.ssid_exclusions.len()
+            + self.name_exclusions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drop every signature, e.g. before loading a replacement set.
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Parse a `signatures.v1` document and append its signatures.
+    ///
+    /// Fails with [`AirhoundError::Malformed`] if the document has no
+    /// `signatures` array.
+    pub fn load_signatures(&mut self, json: &[u8]) -> Result<LoadReport, AirhoundError> {
+        sigfile::for_each_signature(json, |raw| self.add_signature(raw))
+    }
+
+    fn add_signature(&mut self, raw: &RawSignature) -> bool {
+        let description = String::from(raw.description.as_deref().unwrap_or(""));
+        let min = sigfile::min_sightings(raw);
+        let meta = sigfile::meta(raw);
+        match raw.kind.as_str() {
+            "mac_oui" => match raw.oui.as_deref().and_then(sigfile::parse_oui) {
+                Some(oui) => {
+                    self.mac_prefixes.push((oui, description, min, meta));
+                    true
+                }
+                None => false,
+            },
+            "wifi_ssid" => match string_signature(raw, description) {
+                Some(sig) => {
+                    self.ssids.push(sig);
+                    true
+                }
+                None => false,
+            },
+            "ble_name" => match string_signature(raw, description) {
+                Some(sig) => {
+                    self.ble_names.push(sig);
+                    true
+                }
+                None => false,
+            },
+            "ble_service_uuid" => {
+                let uuid = raw.uuid.as_deref().unwrap_or("");
+                if let Some(uuid) = sigfile::parse_uuid_32(uuid) {
+                    match u16::try_from(uuid) {
+                        Ok(uuid) => self.service_uuids_16.push((uuid, description, min, meta)),
+                        Err(_) => self.service_uuids_32.push((uuid, description, min, meta)),
+                    }
+                } else if let Some(uuid) = sigfile::parse_uuid_128(uuid) {
+                    self.service_uuids_128.push((uuid, description, min, meta));
+                } else {
+                    return false;
+                }
+                true
+            }
+            "ble_manufacturer_id" => match raw.company_id {
+                Some(id) => {
+                    self.manufacturer_ids.push((id, description, min, meta));
+                    true
+                }
+                None => false,
+            },
+            "ble_ad_bytes" => match ad_pattern(raw, description) {
+                Some(pattern) => {
+                    self.ad_patterns.push(pattern);
+                    true
+                }
+                None => false,
+            },
+            "randomized_mac_fingerprint" => {
+                let table = match raw.transport.as_deref() {
+                    Some("wifi") => &mut self.ie_fingerprints,
+                    Some("ble") => &mut self.ad_fingerprints,
+                    _ => return false,
+                };
+                match ad_pattern(raw, description) {
+                    Some(pattern) => {
+                        table.push(pattern);
+                        true
+                    }
+                    None => false,
+                }
+            }
+            "wifi_vendor_ie" => match raw.oui.as_deref().and_then(sigfile::parse_oui) {
+                Some(oui) => {
+                    self.vendor_ies.push(VendorIePattern {
+                        oui,
+                        bytes: raw.bytes.as_deref().unwrap_or(&[]).to_vec(),
+                        description,
+                        min_sightings: min,
+                        meta,
+                    });
+                    true
+                }
+                None => false,
+            },
+            "wifi_wps" => match string_signature(raw, description) {
+                Some(sig) => {
+                    self.wps.push((raw.field, sig));
+                    true
+                }
+                None => false,
+            },
+            "wifi_network" => {
+                let Some(kind) = raw.network else {
+                    return false;
+                };
+                // Without a value, any network of the kind matches
+                let sig = match raw.value {
+                    Some(_) => string_signature(raw, description),
+                    None => Some(StringSignature {
+                        kind: StringMatch::Contains,
+                        value: String::new(),
+                        case_sensitive: false,
+                        description,
+                        min_sightings: min,
+                        meta,
+                    }),
+                };
+                match sig {
+                    Some(sig) => {
+                        self.networks.push((kind, sig));
+                        true
+                    }
+                    None => false,
+                }
+            }
+            "exclusion" => {
+                let table = match raw.transport.as_deref() {
+                    Some("wifi") => &mut self.ssid_exclusions,
+                    Some("ble") => &mut self.name_exclusions,
+                    _ => return false,
+                };
+                match string_signature(raw, description) {
+                    Some(pattern) => {
+                        table.push(Exclusion {
+                            pattern,
+                            excludes: raw.excludes.as_deref().map(String::from),
+                        });
+                        true
+                    }
+                    None => false,
+                }
+            }
+            // One per database; later duplicates are skipped
+            "wifi_traffic" if self.high_traffic_aps.is_none() => {
+                let description = if description.is_empty() {
+                    String::from("high-traffic AP")
+                } else {
+                    description
+                };
+                self.high_traffic_aps = Some((description, meta));
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+impl SignatureSource for SignatureDb {
+    fn mac_prefixes(&self) -> impl Iterator<Item = ([u8; 3], &str, u8, SignatureMeta)> {
+        self.mac_prefixes
+            .iter()
+            .map(|(oui, description, min, meta)| (*oui, description.as_str(), *min, *meta))
+    }
+
+    fn ssids(&self) -> impl Iterator<Item = StringSigRef<'_>> {
+        self.ssids.iter().map(StringSignature::as_ref)
+    }
+
+    fn ble_names(&self) -> impl Iterator<Item = StringSigRef<'_>> {
+        self.ble_names.iter().map(StringSignature::as_ref)
+    }
+
+    fn service_uuids_16(&self) -> impl Iterator<Item = (u16, &str, u8, SignatureMeta)> {
+        self.service_uuids_16
+            .iter()
+            .map(|(uuid, description, min, meta)| (*uuid, description.as_str(), *min, *meta))
+    }
+
+    fn service_uuids_32(&self) -> impl Iterator<Item = (u32, &str, u8, SignatureMeta)> {
+        self.service_uuids_32
+            .iter()
+            .map(|(uuid, description, min, meta)| (*uuid, description.as_str(), *min, *meta))
+    }
+
+    fn service_uuids_128(&self) -> impl Iterator<Item = (u128, &str, u8, SignatureMeta)> {
+        self.service_uuids_128
+            .iter()
+            .map(|(uuid, description, min, meta)| (*uuid, description.as_str(), *min, *meta))
+    }
+
+    fn manufacturer_ids(&self) -> impl Iterator<Item = (u16, &str, u8, SignatureMeta)> {
+        self.manufacturer_ids
+            .iter()
+            .map(|(id, description, min, meta)| (*id, description.as_str(), *min, *meta))
+    }
+
+    fn ad_patterns(&self) -> impl Iterator<Item = AdPatternRef<'_>> {
+        self.ad_patterns.iter().map(AdPattern::as_ref)
+    }
+
+    fn ie_fingerprints(&self) -> impl Iterator<Item = AdPatternRef<'_>> {
+        self.ie_fingerprints.iter().map(AdPattern::as_ref)
+    }
+
+    fn ad_fingerprints(&self) -> impl Iterator<Item = AdPatternRef<'_>> {
+        self.ad_fingerprints.iter().map(AdPattern::as_ref)
+    }
+
+    fn vendor_ies(&self) -> impl Iterator<Item = VendorIePatternRef<'_>> {
+        self.vendor_ies.iter().map(VendorIePattern::as_ref)
+    }
+
+    fn wps(&self) -> impl Iterator<Item = (Option<WpsField>, StringSigRef<'_>)> {
+        self.wps.iter().map(|(field, sig)| (*field, sig.as_ref()))
+    }
+
+    fn networks(&self) -> impl Iterator<Item = (NetworkKind, StringSigRef<'_>)> {
+        self.networks
+            .iter()
+            .map(|(kind, sig)| (*kind, sig.as_ref()))
+    }
+
+    fn high_traffic_aps(&self) -> Option<(&str, SignatureMeta)> {
+        self.high_traffic_aps
+            .as_ref()
+            .map(|(description, meta)| (description.as_str(), *meta))
+    }
+
+    fn ssid_exclusions(&self) -> impl Iterator<Item = ExclusionRef<'_>> {
+        self.ssid_exclusions.iter().map(Exclusion::as_ref)
+    }
+
+    fn name_exclusions(&self) -> impl Iterator<Item = ExclusionRef<'_>> {
+        self.name_exclusions.iter().map(Exclusion::as_ref)
+    }
+}
+
+fn ad_pattern(raw: &RawSignature, description: String) -> Option<AdPattern> {
+    let bytes = raw.bytes.as_ref().filter(|bytes| !bytes.is_empty())?;
+    Some(AdPattern {
+        bytes: bytes.to_vec(),
+        offset: raw.offset,
+        description,
+        min_sightings: sigfile::min_sightings(raw),
+        meta: sigfile::meta(raw),
+    })
+}
+
+fn string_signature(raw: &RawSignature, description: String) -> Option<StringSignature> {
+    let kind = sigfile::string_match(raw)?;
+    let value = String::from(raw.value.as_deref()?);
+    // Fall back to the matched value when the file has no description
+    let description = if description.is_empty() {
+        value.clone()
+    } else {
+        description
+    };
+    Some(StringSignature {
+        kind,
+        value,
+        case_sensitive: raw.case_sensitive.unwrap_or(true),
+        description,
+        min_sightings: sigfile::min_sightings(raw),
+        meta: sigfile::meta(raw),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::defaults::{Category, DeviceClass};
+    use crate::filter::{filter_ble_with, filter_wifi_with, BleScanInput, FilterConfig};
+    use crate::traffic::Traffic;
+
+    const EXAMPLE: &[u8] = include_bytes!("../schemas/examples/flock-raven-airtag.sigs.json");
+
+    fn ble_input<'a>(mac: &'a [u8; 6], ad_data: &'a [u8]) -> BleScanInput<'a> {
+        BleScanInput {
+            mac,
+            mac_randomized: false,
+            name: "",
+            rssi: -60,
+            service_uuids_16: &[],
+            service_uuids_32: &[],
+            service_uuids_128: &[],
+            manufacturer_id: 0,
+            matter: None,
+            ibeacon: None,
+            eddystone: None,
+            thread_commissioning: false,
+            ad_data,
+        }
+    }
+
+    // ── Loading ─────────────────────────────────────────────────────
+
+    #[test]
+    fn loads_schema_example_with_ad_patterns() {
+        let mut db = SignatureDb::new();
+        let report = db.load_signatures(EXAMPLE).unwrap();
+        // Everything the overlay takes plus the 3 ble_ad_bytes
+        assert_eq!(report.loaded, 16);
+        // Only the regex SSID
+        assert_eq!(report.skipped, 1);
+        assert_eq!(db.len(), 16);
+        assert_eq!(db.ad_patterns.len(), 3);
+        assert_eq!(db.ad_patterns[0].offset, Some(0));
+        assert_eq!(db.ad_patterns[1].bytes, [Some(0x80), Some(0x30)]);
+    }
+
+    #[test]
+    fn grows_past_overlay_capacity() {
+        let mut db = SignatureDb::new();
+        for id in 0..(2 * sigfile::OVERLAY_CAPACITY as u16) {
+            let json = alloc::format!(
+                r#"{{"signatures":[{{"id":"m{id}","type":"ble_manufacturer_id","company_id":{id}}}]}}"#
+            );
+            assert_eq!(db.load_signatures(json.as_bytes()).unwrap().loaded, 1);
+        }
+        assert_eq!(db.manufacturer_ids.len(), 2 * sigfile::OVERLAY_CAPACITY);
+        db.clear();
+        assert!(db.is_empty());
+    }
+
+    #[test]
+    fn keeps_full_descriptions() {
+        let description = "Very long vendor description that does not fit a match detail";
+        let json = alloc::format!(
+            r#"{{"signatures":[{{"id":"o","type":"mac_oui","oui":"AA:BB:CC","description":"{description}"}}]}}"#
+        );
+        let mut db = SignatureDb::new();
+        db.load_signatures(json.as_bytes()).unwrap();
+        assert_eq!(db.mac_prefixes[0].1, description);
+    }
+
+    // ── Filtering ───────────────────────────────────────────────────
+
+    #[test]
+    fn filters_accept_runtime_db() {
+        let mut db = SignatureDb::new();
+        db.load_signatures(EXAMPLE).unwrap();
+        let mac = [0xB4, 0x1E, 0x52, 0x01, 0x02, 0x03];
+        let input = crate::filter::WiFiScanInput {
+            mac: &mac,
+            mac_randomized: false,
+            ssid: "",
+            rssi: -60,
+            channel: 6,
+            traffic: Traffic::Normal,
+            ies: &[],
+            vendor_ies: &[],
+            wps: None,
+            capability: None,
+            timing: None,
+            sequence: None,
+            action: None,
+            network: None,
+        };
+        let result = filter_wifi_with(&input, &FilterConfig::default(), &db);
+        assert!(result.matched);
+        assert_eq!(result.matches[0].filter_type, "mac_oui");
+    }
+
+    #[test]
+    fn matches_wide_service_uuids() {
+        let json = br#"{"signatures":[
+            {"id":"custom","type":"ble_service_uuid","uuid":"6e400001-b5a3-f393-e0a9-e50e24dcca9e","description":"Custom service"},
+            {"id":"base","type":"ble_service_uuid","uuid":"00003100-0000-1000-8000-00805f9b34fb"},
+            {"id":"wide","type":"ble_service_uuid","uuid":"0001fe2c","description":"32-bit service"}
+        ]}"#;
+        let mut db = SignatureDb::new();
+        assert_eq!(db.load_signatures(json).unwrap().loaded, 3);
+        // Base UUID expansions are matched as their 16-bit alias
+        assert_eq!(db.service_uuids_16[0].0, 0x3100);
+        let mac = [0x00, 0x11, 0x22, 0x33, 0x44, 0x55];
+        let input = BleScanInput {
+            service_uuids_32: &[0x0001_FE2C],
+            ..ble_input(&mac, &[])
+        };
+        let result = filter_ble_with(&input, &FilterConfig::default(), &db);
+        assert_eq!(result.matches[0].filter_type, "ble_uuid");
+        assert_eq!(result.matches[0].detail.as_str(), "32-bit service");
+        let uuids = [0x6E400001_B5A3_F393_E0A9_E50E24DCCA9E];
+        let input = BleScanInput {
+            service_uuids_128: &uuids,
+            ..ble_input(&mac, &[])
+        };
+        let result = filter_ble_with(&input, &FilterConfig::default(), &db);
+        assert!(result.matched);
+        assert_eq!(result.matches[0].filter_type, "ble_uuid");
+        assert_eq!(result.matches[0].detail.as_str(), "Custom service");
+    }
+
+    #[test]
+    fn ad_patterns_match_raw_advertisements() {
+        let mut db = SignatureDb::new();
+        db.ad_patterns.push(AdPattern {
+            bytes: alloc::vec![Some(0x80), None, Some(0x30)],
+            offset: None,
+            description: String::from("wildcard"),
+            min_sightings: 2,
+            meta: SignatureMeta::UNRATED,
+        });
+        db.ad_patterns.push(AdPattern {
+            bytes: alloc::vec![Some(0x02), Some(0x01)],
+            offset: Some(0),
+            description: String::from("anchored"),
+            min_sightings: 1,
+            meta: SignatureMeta::UNRATED,
+        });
+        let config = FilterConfig::default();
+        let mac = [0x02, 0, 0, 0, 0, 1];
+
+        let result = filter_ble_with(
+            &ble_input(&mac, &[0x05, 0x16, 0x80, 0x99, 0x30]),
+            &config,
+            &db,
+        );
+        assert!(result.matched);
+        assert_eq!(result.matches[0].filter_type, "ble_ad");
+        assert_eq!(result.min_sightings, 2);
+
+        // Anchored patterns only match at their offset
+        assert!(filter_ble_with(&ble_input(&mac, &[0x02, 0x01, 0x06]), &config, &db).matched);
+        assert!(!filter_ble_with(&ble_input(&mac, &[0x00, 0x02, 0x01]), &config, &db).matched);
+        // Payload shorter than the pattern
+        assert!(!filter_ble_with(&ble_input(&mac, &[0x80]), &config, &db).matched);
+    }
+
+    #[test]
+    fn fingerprints_only_match_random_addresses() {
+        let mut db = SignatureDb::new();
+        let json = br#"{"signatures": [
+            {"id": "a", "type": "randomized_mac_fingerprint", "transport": "ble",
+             "bytes": [255, 76, 0, 18], "description": "Find My", "category": "tracker"},
+            {"id": "b", "type": "randomized_mac_fingerprint", "transport": "wifi",
+             "bytes": [221, null, 0, 80, 242], "offset": 0, "description": "probe"},
+            {"id": "c", "type": "randomized_mac_fingerprint", "bytes": [1]}
+        ]}"#;
+        let report = db.load_signatures(json).unwrap();
+        assert_eq!((report.loaded, report.skipped), (2, 1));
+        assert_eq!(db.ad_fingerprints[0].meta.category, Category::Tracker);
+
+        let config = FilterConfig::default();
+        let mac = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66];
+        // Short Find My payload, not the compiled-in AirTag layout
+        let ad = [0x1E, 0xFF, 0x4C, 0x00, 0x12, 0x02];
+        assert!(!filter_ble_with(&ble_input(&mac, &ad), &config, &db).matched);
+        let random = BleScanInput {
+            mac_randomized: true,
+            ..ble_input(&mac, &ad)
+        };
+        let result = filter_ble_with(&random, &config, &db);
+        assert_eq!(result.matches[0].filter_type, "fingerprint");
+        assert_eq!(result.matches[0].detail, "Find My");
+
+        let ies = [0xDD, 0x07, 0x00, 0x50, 0xF2, 0x08];
+        let mut wifi = crate::filter::WiFiScanInput {
+            mac: &[0x02, 0x22, 0x33, 0x44, 0x55, 0x66],
+            mac_randomized: false,
+            ssid: "",
+            rssi: -60,
+            channel: 6,
+            traffic: Traffic::Normal,
+            ies: &ies,
+            vendor_ies: &[],
+            wps: None,
+            capability: None,
+            timing: None,
+            sequence: None,
+            action: None,
+            network: None,
+        };
+        assert!(!filter_wifi_with(&wifi, &config, &db).matched);
+        wifi.mac_randomized = true;
+        assert!(filter_wifi_with(&wifi, &config, &db).matched);
+    }
+
+    #[test]
+    fn exclusions_cancel_runtime_matches() {
+        let mut db = SignatureDb::new();
+        let json = br#"{"signatures": [
+            {"id": "a", "type": "mac_oui", "oui": "AA:BB:CC", "description": "Acme"},
+            {"id": "b", "type": "wifi_ssid", "match": "prefix", "value": "cam-"},
+            {"id": "c", "type": "exclusion", "transport": "wifi", "match": "contains",
+             "value": "guest", "case_sensitive": false, "excludes": "Acme"},
+            {"id": "d", "type": "exclusion", "transport": "wifi", "match": "exact",
+             "value": "cam-lab"},
+            {"id": "e", "type": "exclusion", "match": "exact", "value": "x"}
+        ]}"#;
+        let report = db.load_signatures(json).unwrap();
+        assert_eq!((report.loaded, report.skipped), (4, 1));
+        assert_eq!(db.ssid_exclusions.len(), 2);
+
+        let config = FilterConfig::default();
+        let mut wifi = crate::filter::WiFiScanInput {
+            mac: &[0xAA, 0xBB, 0xCC, 0x01, 0x02, 0x03],
+            mac_randomized: false,
+            ssid: "cam-Guest",
+            rssi: -60,
+            channel: 6,
+            traffic: Traffic::Normal,
+            ies: &[],
+            vendor_ies: &[],
+            wps: None,
+            capability: None,
+            timing: None,
+            sequence: None,
+            action: None,
+            network: None,
+        };
+        let result = filter_wifi_with(&wifi, &config, &db);
+        assert_eq!(result.matches.len(), 1);
+        assert_eq!(result.matches[0].detail, "cam-");
+
+        // No `excludes`: every match goes
+        wifi.ssid = "cam-lab";
+        assert!(!filter_wifi_with(&wifi, &config, &db).matched);
+        wifi.ssid = "cam-1";
+        assert_eq!(filter_wifi_with(&wifi, &config, &db).matches.len(), 2);
+    }
+
+    #[test]
+    fn vendor_ie_patterns_match_beacon_elements() {
+        let mut db = SignatureDb::new();
+        let json = br#"{"signatures": [
+            {"id": "a", "type": "wifi_vendor_ie", "oui": "AA:BB:CC", "bytes": [1, null, 3],
+             "description": "Acme camera IE", "category": "camera"},
+            {"id": "b", "type": "wifi_vendor_ie", "oui": "DD:EE:FF"},
+            {"id": "c", "type": "wifi_vendor_ie", "oui": "bad"}
+        ]}"#;
+        let report = db.load_signatures(json).unwrap();
+        assert_eq!((report.loaded, report.skipped), (2, 1));
+
+        let ie = |oui: [u8; 3], data: &[u8]| crate::scanner::VendorIe {
+            oui,
+            data: heapless::Vec::from_slice(data).unwrap(),
+        };
+        let config = FilterConfig::default();
+        let scan = |vendor_ies: &[crate::scanner::VendorIe]| {
+            let wifi = crate::filter::WiFiScanInput {
+                mac: &[0x00, 0x11, 0x22, 0x33, 0x44, 0x55],
+                mac_randomized: false,
+                ssid: "",
+                rssi: -60,
+                channel: 6,
+                traffic: Traffic::Normal,
+                ies: &[],
+                vendor_ies,
+                wps: None,
+                capability: None,
+                timing: None,
+                sequence: None,
+                action: None,
+                network: None,
+            };
+            filter_wifi_with(&wifi, &config, &db)
+        };
+        let result = scan(&[ie([0xAA, 0xBB, 0xCC], &[1, 9, 3, 4])]);
+        assert_eq!(result.matches[0].filter_type, "wifi_vendor_ie");
+        assert_eq!(result.matches[0].detail, "Acme camera IE");
+        assert_eq!(result.meta.category, Category::Camera);
+
+        // Payload too short for the pattern, or another vendor
+        assert!(!scan(&[ie([0xAA, 0xBB, 0xCC], &[1, 9])]).matched);
+        assert!(!scan(&[ie([0xAA, 0xBB, 0xCD], &[1, 9, 3])]).matched);
+        // No bytes: the OUI alone matches
+        assert!(scan(&[ie([0xDD, 0xEE, 0xFF], &[])]).matched);
+    }
+
+    #[test]
+    fn wps_signatures_match_selected_fields() {
+        let mut db = SignatureDb::new();
+        let json = br#"{"signatures": [
+            {"id": "a", "type": "wifi_wps", "field": "manufacturer", "match": "prefix",
+             "value": "axon", "case_sensitive": false, "description": "Axon",
+             "category": "camera", "device_class": "body_camera"},
+            {"id": "b", "type": "wifi_wps", "match": "glob", "value": "CAM-??"}
+        ]}"#;
+        let report = db.load_signatures(json).unwrap();
+        assert_eq!(report.loaded, 2);
+
+        let config = FilterConfig::default();
+        let scan = |wps: &crate::scanner::WpsInfo| {
+            let wifi = crate::filter::WiFiScanInput {
+                mac: &[0x00, 0x11, 0x22, 0x33, 0x44, 0x55],
+                mac_randomized: false,
+                ssid: "",
+                rssi: -60,
+                channel: 6,
+                traffic: Traffic::Normal,
+                ies: &[],
+                vendor_ies: &[],
+                wps: Some(wps),
+                capability: None,
+                timing: None,
+                sequence: None,
+                action: None,
+                network: None,
+            };
+            filter_wifi_with(&wifi, &config, &db)
+        };
+        let mut wps = crate::scanner::WpsInfo::default();
+        wps.manufacturer.push_str("Axon Enterprise").unwrap();
+        let result = scan(&wps);
+        assert_eq!(result.matches[0].filter_type, "wps");
+        assert_eq!(result.matches[0].detail, "Axon");
+        assert_eq!(result.meta.device_class, DeviceClass::BodyCamera);
+
+        // Restricted to the manufacturer field
+        let mut wps = crate::scanner::WpsInfo::default();
+        wps.device_name.push_str("Axon").unwrap();
+        assert!(!scan(&wps).matched);
+        // Any field, reported once
+        wps.model_name.push_str("CAM-01").unwrap();
+        wps.device_name = wps.model_name.clone();
+        assert_eq!(scan(&wps).matches.len(), 1);
+    }
+
+    #[test]
+    fn network_signatures_match_kind_and_name() {
+        let mut db = SignatureDb::new();
+        let json = br#"{"signatures": [
+            {"id": "a", "type": "wifi_network", "network": "mesh",
+             "description": "802.11s mesh"},
+            {"id": "b", "type": "wifi_network", "network": "p2p_group", "match": "prefix",
+             "value": "DIRECT-", "description": "WiFi Direct group"},
+            {"id": "c", "type": "wifi_network", "network": "adhoc"}
+        ]}"#;
+        let report = db.load_signatures(json).unwrap();
+        assert_eq!((report.loaded, report.skipped), (2, 1));
+
+        let config = FilterConfig::default();
+        let scan = |ssid: &str, network: Option<NetworkKind>| {
+            let wifi = crate::filter::WiFiScanInput {
+                mac: &[0x00, 0x11, 0x22, 0x33, 0x44, 0x55],
+                mac_randomized: false,
+                ssid,
+                rssi: -60,
+                channel: 6,
+                traffic: Traffic::Normal,
+                ies: &[],
+                vendor_ies: &[],
+                wps: None,
+                capability: None,
+                timing: None,
+                sequence: None,
+                action: None,
+                network,
+            };
+            filter_wifi_with(&wifi, &config, &db)
+        };
+        let mesh = scan("citymesh", Some(NetworkKind::Mesh));
+        // The compiled-in mesh signature matches too, so look for ours
+        assert!(mesh
+            .matches
+            .iter()
+            .any(|m| m.filter_type == "wifi_network" && m.detail == "802.11s mesh"));
+        assert!(scan("DIRECT-ab-Cam", Some(NetworkKind::P2pGroup)).matched);
+        assert!(!scan("Cam", Some(NetworkKind::P2pGroup)).matched);
+        // An access point with the same name is not the network
+        assert!(!scan("DIRECT-ab-Cam", None).matched);
+    }
+}
@@ -0,0 +1,1093 @@
+/// Signature file loader — overlays runtime signatures on the compiled-in defaults.
+///
+/// Reads files in the `signatures.v1.schema.json` format (see `schemas/`) into a
+/// fixed-capacity [`SignatureOverlay`] that `filter::filter_wifi_with` and
+/// `filter::filter_ble_with` evaluate alongside `defaults.rs`. Firmware loads the
+/// file from SD card at boot; std builds load `*.sigs.json` from a config directory.
+///
+/// Signature types the device cannot evaluate (`regex` string matches, raw AD
+/// byte patterns, randomized-MAC fingerprints and vendor IE patterns, UUIDs
+/// wider than 16 bits, WPS and network signatures, exclusions) are counted
+/// as skipped, not errors.
+/// The `rules` array is ignored — only compiled-in packs evaluate rules.
+use heapless::{String, Vec};
+use serde::Deserialize;
+
+use crate::defaults::{Category, Confidence, DeviceClass, Severity, SignatureMeta};
+use crate::error::AirhoundError;
+use crate::filter::SignatureSource;
+use crate::protocol::MatchDetail;
+use crate::scanner::VendorIe;
+
+/// Maximum entries per overlay table
+pub const OVERLAY_CAPACITY: usize = 32;
+
+/// Longest `ble_ad_bytes` pattern accepted
+pub const MAX_AD_PATTERN_LEN: usize = crate::scanner::MAX_AD_LEN;
+
+/// Name of the signature file on the firmware SD card (FAT 8.3 name)
+pub const SD_FILE_NAME: &str = "SIGS.JSN";
+
+/// String matching strategy from the schema's `string_match`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StringMatch {
+    Exact,
+    Prefix,
+    Contains,
+    /// Shell-style glob over the whole text, see [`crate::glob`]
+    Glob,
+}
+
+/// A string signature (SSID or BLE name)
+#[derive(Debug, Clone)]
+pub struct StringSig {
+    pub kind: StringMatch,
+    pub value: String<33>,
+    pub case_sensitive: bool,
+    pub description: MatchDetail,
+    /// Sightings needed before a match is reported
+    pub min_sightings: u8,
+    pub meta: SignatureMeta,
+}
+
+impl StringSig {
+    /// Check whether `text` satisfies this signature.
+    pub fn matches(&self, text: &str) -> bool {
+        self.as_ref().matches(text)
+    }
+
+    pub fn as_ref(&self) -> StringSigRef<'_> {
+        StringSigRef {
+            kind: self.kind,
+            value: &self.value,
+            case_sensitive: self.case_sensitive,
+            description: &self.description,
+            min_sightings: self.min_sightings,
+            meta: self.meta,
+        }
+    }
+}
+
+/// Borrowed view of a string signature, independent of how it is stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StringSigRef<'a> {
+    pub kind: StringMatch,
+    pub value: &'a str,
+    pub case_sensitive: bool,
+    pub description: &'a str,
+    pub min_sightings: u8,
+    pub meta: SignatureMeta,
+}
+
+impl StringSigRef<'_> {
+    /// Check whether `text` satisfies this signature.
+    pub fn matches(&self, text: &str) -> bool {
+        let needle = self.value.as_bytes();
+        let hay = text.as_bytes();
+        let eq = |a: &[u8]| {
+            if self.case_sensitive {
+                a == needle
+            } else {
+                a.eq_ignore_ascii_case(needle)
+            }
+        };
+        match self.kind {
+            StringMatch::Exact => eq(hay),
+            StringMatch::Prefix => hay.len() >= needle.len() && eq(&hay[..needle.len()]),
+            StringMatch::Contains => needle.is_empty() || hay.windows(needle.len()).any(eq),
+            StringMatch::Glob => crate::glob::matches(self.value, text, self.case_sensitive),
+        }
+    }
+}
+
+/// Borrowed view of an `exclusion` signature: devices whose SSID or name
+/// matches `pattern` lose the matches with detail `excludes` (all if `None`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExclusionRef<'a> {
+    pub pattern: StringSigRef<'a>,
+    pub excludes: Option<&'a str>,
+}
+
+/// Borrowed view of a `ble_ad_bytes` signature. `None` bytes are wildcards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AdPatternRef<'a> {
+    pub bytes: &'a [Option<u8>],
+    /// Fixed offset into the advertisement; `None` searches the whole payload
+    pub offset: Option<u16>,
+    pub description: &'a str,
+    pub min_sightings: u8,
+    pub meta: SignatureMeta,
+}
+
+impl AdPatternRef<'_> {
+    /// Check whether the raw advertisement payload contains this pattern.
+    pub fn matches(&self, ad_data: &[u8]) -> bool {
+        let eq = |window: &[u8]| {
+            window
+                .iter()
+                .zip(self.bytes)
+                .all(|(b, want)| want.is_none_or(|want| want == *b))
+        };
+        if self.bytes.is_empty() || self.bytes.len() > ad_data.len() {
+            return false;
+        }
+        match self.offset {
+            Some(offset) => ad_data
+                .get(offset as usize..offset as usize + self.bytes.len())
+                .is_some_and(eq),
+            None => ad_data.windows(self.bytes.len()).any(eq),
+        }
+    }
+}
+
+/// Borrowed view of a `wifi_vendor_ie` signature. `None` bytes are wildcards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VendorIePatternRef<'a> {
+    pub oui: [u8; 3],
+    /// Leading bytes of the element after the OUI
+    pub bytes: &'a [Option<u8>],
+    pub description: &'a str,
+    pub min_sightings: u8,
+    pub meta: SignatureMeta,
+}
+
+impl VendorIePatternRef<'_> {
+    /// Check whether a vendor element carries this OUI and leading bytes.
+    pub fn matches(&self, ie: &VendorIe) -> bool {
+        ie.oui == self.oui
+            && ie.data.len() >= self.bytes.len()
+            && ie
+                .data
+                .iter()
+                .zip(self.bytes)
+                .all(|(b, want)| want.is_none_or(|want| want == *b))
+    }
+}
+
+/// Runtime signatures loaded from a signature file. Table entries are
+/// `(value, description, min_sightings, meta)`.
+pub struct SignatureOverlay {
+    pub mac_prefixes: Vec<([u8; 3], MatchDetail, u8, SignatureMeta), OVERLAY_CAPACITY>,
+    pub ssids: Vec<StringSig, OVERLAY_CAPACITY>,
+    pub ble_names: Vec<StringSig, OVERLAY_CAPACITY>,
+    pub service_uuids_16: Vec<(u16, MatchDetail, u8, SignatureMeta), OVERLAY_CAPACITY>,
+    pub manufacturer_ids: Vec<(u16, MatchDetail, u8, SignatureMeta), OVERLAY_CAPACITY>,
+    /// Flag access points with sustained heavy data traffic even without
+    /// another match (`wifi_traffic` signature)
+    pub high_traffic_aps: Option<(MatchDetail, SignatureMeta)>,
+}
+
+impl SignatureOverlay {
+    pub const fn new() -> Self {
+        Self {
+            mac_prefixes: Vec::new(),
+            ssids: Vec::new(),
+            ble_names: Vec::new(),
+            service_uuids_16: Vec::new(),
+            manufacturer_ids: Vec::new(),
+            high_traffic_aps: None,
+        }
+    }
+
+    /// Total number of loaded signatures
+    pub fn len(&self) -> usize {
+        self.mac_prefixes.len()
+            + self.ssids.len()
+            + self.ble_names.len()
+            + self.service_uuids_16.len()
+            + self.manufacturer_ids.len()
+            + self.high_traffic_aps.is_some() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// A signature the companion adds or removes with the `add_signature` and
+/// `remove_signature` commands. String values match case-insensitively
+/// anywhere in the SSID or name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuntimeSignature {
+    MacOui([u8; 3]),
+    SsidKeyword(String<33>),
+    BleName(String<33>),
+}
+
+impl RuntimeSignature {
+    /// Build from the command's `type` and `value` fields. A `type` other
+    /// than the three above is [`AirhoundError::Unsupported`], a value that
+    /// doesn't fit it [`AirhoundError::InvalidArgument`].
+    pub fn parse(kind: &str, value: &str) -> Result<Self, AirhoundError> {
+        let text = || String::try_from(value).ok().filter(|s| !s.is_empty());
+        let sig = match kind {
+            "mac_oui" => parse_oui(value).map(Self::MacOui),
+            "ssid_keyword" => text().map(Self::SsidKeyword),
+            "ble_name" => text().map(Self::BleName),
+            _ => return Err(AirhoundError::Unsupported),
+        };
+        sig.ok_or(AirhoundError::InvalidArgument)
+    }
+}
+
+impl SignatureOverlay {
+    /// Add a runtime signature, replacing the description of an identical
+    /// one. Runtime signatures are [`SignatureMeta::UNRATED`]. Returns false
+    /// when its table is full.
+    pub fn add(&mut self, sig: &RuntimeSignature, description: &str) -> bool {
+        match sig {
+            RuntimeSignature::MacOui(oui) => {
+                let description = if description.is_empty() {
+                    default_description(PERSIST_MAC_OUI, oui)
+                } else {
+                    detail(description)
+                };
+                match self.mac_prefixes.iter_mut().find(|(o, ..)| o == oui) {
+                    Some(entry) => {
+                        entry.1 = description;
+                        true
+                    }
+                    None => self
+                        .mac_prefixes
+                        .push((*oui, description, 1, SignatureMeta::UNRATED))
+                        .is_ok(),
+                }
+            }
+            RuntimeSignature::SsidKeyword(value) => {
+                add_keyword(&mut self.ssids, value, description)
+            }
+            RuntimeSignature::BleName(value) => {
+                add_keyword(&mut self.ble_names, value, description)
+            }
+        }
+    }
+
+    /// Remove a runtime signature. Returns false if it wasn't present.
+    pub fn remove(&mut self, sig: &RuntimeSignature) -> bool {
+        let before = self.len();
+        match sig {
+            RuntimeSignature::MacOui(oui) => self.mac_prefixes.retain(|(o, ..)| o != oui),
+            RuntimeSignature::SsidKeyword(value) => {
+                self.ssids.retain(|s| !is_keyword(s, value));
+            }
+            RuntimeSignature::BleName(value) => {
+                self.ble_names.retain(|s| !is_keyword(s, value));
+            }
+        }
+        self.len() != before
+    }
+}
+
+/// Runtime signature kinds in the persisted layout
+const PERSIST_MAC_OUI: u8 = 0;
+const PERSIST_SSID_KEYWORD: u8 = 1;
+const PERSIST_BLE_NAME: u8 = 2;
+
+impl SignatureOverlay {
+    /// Encode the signatures [`add()`](Self::add) can recreate — OUIs and
+    /// case-insensitive keywords — for persisting the companion's runtime
+    /// signatures (`ConfigKey::RuntimeSignatures`). Binary layout:
+    /// `[count, (kind, len, value[len], desc_len, desc[desc_len])...]`, where
+    /// a description equal to the default is stored empty. Fails with
+    /// [`AirhoundError::BufferTooSmall`] if `buf` is too small.
+    pub fn encode_runtime(&self, buf: &mut [u8]) -> Result<usize, AirhoundError> {
+        let ouis = self
+            .mac_prefixes
+            .iter()
+            .map(|(oui, description, ..)| (PERSIST_MAC_OUI, &oui[..], description.as_str()));
+        let ssids = self
+            .ssids
+            .iter()
+            .filter(|s| is_keyword(s, &s.value))
+            .map(|s| {
+                (
+                    PERSIST_SSID_KEYWORD,
+                    s.value.as_bytes(),
+                    s.description.as_str(),
+                )
+            });
+        let names = self
+            .ble_names
+            .iter()
+            .filter(|s| is_keyword(s, &s.value))
+            .map(|s| (PERSIST_BLE_NAME, s.value.as_bytes(), s.description.as_str()));
+
+        let mut count = 0u8;
+        let mut pos = 1;
+        for (kind, value, description) in ouis.chain(ssids).chain(names) {
+            let default = default_description(kind, value);
+            let description = if description == default.as_str() {
+                ""
+            } else {
+                description
+            };
+            let end = pos + 3 + value.len() + description.len();
+            let record = buf.get_mut(pos..end).ok_or(AirhoundError::BufferTooSmall)?;
+            record[0] = kind;
+            record[1] = value.len() as u8;
+            record[2..2 + value.len()].copy_from_slice(value);
+            record[2 + value.len()] = description.len() as u8;
+            record[3 + value.len()..].copy_from_slice(description.as_bytes());
+            pos = end;
+            count += 1;
+        }
+        *buf.first_mut().ok_or(AirhoundError::BufferTooSmall)? = count;
+        Ok(pos)
+    }
+
+    /// Re-add signatures written by [`encode_runtime()`](Self::encode_runtime),
+    /// in place — the overlay is too large to decode onto the stack. Returns
+    /// the number restored, or [`AirhoundError::Malformed`] if `data` is
+    /// malformed; records before the malformed one are kept.
+    pub fn restore_runtime(&mut self, data: &[u8]) -> Result<usize, AirhoundError> {
+        let (&count, mut rest) = data.split_first().ok_or(AirhoundError::Malformed)?;
+        let mut restored = 0;
+        for _ in 0..count {
+            let (sig, description, tail) = runtime_record(rest).ok_or(AirhoundError::Malformed)?;
+            rest = tail;
+            if self.add(&sig, description) {
+                restored += 1;
+            }
+        }
+        Ok(restored)
+    }
+}
+
+/// Split the first [`SignatureOverlay::encode_runtime`] record off `data`
+fn runtime_record(data: &[u8]) -> Option<(RuntimeSignature, &str, &[u8])> {
+    let kind = *data.first()?;
+    let len = *data.get(1)? as usize;
+    let value = data.get(2..2 + len)?;
+    let desc_len = *data.get(2 + len)? as usize;
+    let description = core::str::from_utf8(data.get(3 + len..3 + len + desc_len)?).ok()?;
+
+    let text = || {
+        core::str::from_utf8(value)
+            .ok()
+            .and_then(|v| String::try_from(v).ok())
+    };
+    let sig = match kind {
+        PERSIST_MAC_OUI => RuntimeSignature::MacOui(value.try_into().ok()?),
+        PERSIST_SSID_KEYWORD => RuntimeSignature::SsidKeyword(text()?),
+        PERSIST_BLE_NAME => RuntimeSignature::BleName(text()?),
+        _ => return None,
+    };
+    Some((sig, description, &data[3 + len + desc_len..]))
+}
+
+/// Description [`SignatureOverlay::add`] gives a runtime signature added
+/// without one
+fn default_description(kind: u8, value: &[u8]) -> MatchDetail {
+    match value {
+        [a, b, c] if kind == PERSIST_MAC_OUI => {
+            let mut d = MatchDetail::new();
+            let _ = core::fmt::Write::write_fmt(&mut d, format_args!("{a:02X}:{b:02X}:{c:02X}"));
+            d
+        }
+        _ => detail(core::str::from_utf8(value).unwrap_or("")),
+    }
+}
+
+fn is_keyword(sig: &StringSig, value: &str) -> bool {
+    sig.kind == StringMatch::Contains
+        && !sig.case_sensitive
+        && sig.value.eq_ignore_ascii_case(value)
+}
+
+fn add_keyword(
+    table: &mut Vec<StringSig, OVERLAY_CAPACITY>,
+    value: &String<33>,
+    description: &str,
+) -> bool {
+    let description = detail(if description.is_empty() {
+        value
+    } else {
+        description
+    });
+    if let Some(sig) = table.iter_mut().find(|s| is_keyword(s, value)) {
+        sig.description = description;
+        return true;
+    }
+    table
+        .push(StringSig {
+            kind: StringMatch::Contains,
+            value: value.clone(),
+            case_sensitive: false,
+            description,
+            min_sightings: 1,
+            meta: SignatureMeta::UNRATED,
+        })
+        .is_ok()
+}
+
+impl SignatureSource for SignatureOverlay {
+    fn mac_prefixes(&self) -> impl Iterator<Item = ([u8; 3], &str, u8, SignatureMeta)> {
+        self.mac_prefixes
+            .iter()
+            .map(|(oui, description, min, meta)| (*oui, description.as_str(), *min, *meta))
+    }
+
+    fn ssids(&self) -> impl Iterator<Item = StringSigRef<'_>> {
+        self.ssids.iter().map(StringSig::as_ref)
+    }
+
+    fn ble_names(&self) -> impl Iterator<Item = StringSigRef<'_>> {
+        self.ble_names.iter().map(StringSig::as_ref)
+    }
+
+    fn service_uuids_16(&self) -> impl Iterator<Item = (u16, &str, u8, SignatureMeta)> {
+        self.service_uuids_16
+            .iter()
+            .map(|(uuid, description, min, meta)| (*uuid, description.as_str(), *min, *meta))
+    }
+
+    fn manufacturer_ids(&self) -> impl Iterator<Item = (u16, &str, u8, SignatureMeta)> {
+        self.manufacturer_ids
+            .iter()
+            .map(|(id, description, min, meta)| (*id, description.as_str(), *min, *meta))
+    }
+
+    fn high_traffic_aps(&self) -> Option<(&str, SignatureMeta)> {
+        self.high_traffic_aps
+            .as_ref()
+            .map(|(description, meta)| (description.as_str(), *meta))
+    }
+}
+
+impl Default for SignatureOverlay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Outcome of loading a signature file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LoadReport {
+    /// Signatures added to the overlay
+    pub loaded: u16,
+    /// Signatures that were malformed, unsupported on-device, or didn't fit
+    pub skipped: u16,
+}
+
+/// Flat wire format for one signature object — `serde_json_core` cannot
+/// deserialize the schema's internally tagged `type` discriminator directly.
+#[derive(Deserialize)]
+pub(crate) struct RawSignature {
+    #[serde(rename = "type")]
+    pub(crate) kind: String<32>,
+    #[serde(default)]
+    pub(crate) oui: Option<String<8>>,
+    #[serde(default, rename = "match")]
+    pub(crate) match_kind: Option<String<12>>,
+    #[serde(default)]
+    pub(crate) value: Option<String<33>>,
+    #[serde(default)]
+    pub(crate) case_sensitive: Option<bool>,
+    #[serde(default)]
+    pub(crate) uuid: Option<String<36>>,
+    #[serde(default)]
+    pub(crate) company_id: Option<u16>,
+    #[serde(default)]
+    pub(crate) description: Option<String<128>>,
+    #[serde(default)]
+    pub(crate) min_sightings: Option<u8>,
+    #[serde(default)]
+    pub(crate) category: Option<Category>,
+    #[serde(default)]
+    pub(crate) device_class: Option<DeviceClass>,
+    #[serde(default)]
+    pub(crate) severity: Option<Severity>,
+    #[serde(default)]
+    pub(crate) confidence: Option<Confidence>,
+    #[cfg(feature = "alloc")]
+    #[serde(default)]
+    pub(crate) bytes: Option<Vec<Option<u8>, MAX_AD_PATTERN_LEN>>,
+    #[cfg(feature = "alloc")]
+    #[serde(default)]
+    pub(crate) offset: Option<u16>,
+    #[cfg(feature = "alloc")]
+    #[serde(default)]
+    pub(crate) transport: Option<String<8>>,
+    #[cfg(feature = "alloc")]
+    #[serde(default)]
+    pub(crate) excludes: Option<String<128>>,
+    #[cfg(feature = "alloc")]
+    #[serde(default)]
+    pub(crate) field: Option<crate::scanner::WpsField>,
+    #[cfg(feature = "alloc")]
+    #[serde(default)]
+    pub(crate) network: Option<crate::scanner::NetworkKind>,
+}
+
+/// Parse a signature file and append its signatures to `overlay`.
+///
+/// Fails with [`AirhoundError::Malformed`] if the document has no
+/// `signatures` array.
+pub fn load_signatures(
+    json: &[u8],
+    overlay: &mut SignatureOverlay,
+) -> Result<LoadReport, AirhoundError> {
+    for_each_signature(json, |raw| add_signature(raw, overlay))
+}
+
+/// Hand each signature object of a `signatures.v1` document to `add`, which
+/// returns whether it kept the signature. Objects that don't parse are
+/// counted as skipped.
+pub(crate) fn for_each_signature(
+    json: &[u8],
+    mut add: impl FnMut(&RawSignature) -> bool,
+) -> Result<LoadReport, AirhoundError> {
+    let mut pos = find_array(json, b"\"signatures\"").ok_or(AirhoundError::Malformed)?;
+    let mut report = LoadReport::default();
+
+    while let Some((start, end)) = next_object(json, pos) {
+        pos = end;
+        let added = serde_json_core::from_slice::<RawSignature>(&json[start..end])
+            .ok()
+            .is_some_and(|(raw, _)| add(&raw));
+        if added {
+            report.loaded += 1;
+        } else {
+            report.skipped += 1;
+        }
+    }
+
+    Ok(report)
+}
+
+fn add_signature(raw: &RawSignature, overlay: &mut SignatureOverlay) -> bool {
+    let description = detail(raw.description.as_deref().unwrap_or(""));
+    let min = min_sightings(raw);
+    let meta = meta(raw);
+    match raw.kind.as_str() {
+        "mac_oui" => raw.oui.as_deref().and_then(parse_oui).is_some_and(|oui| {
+            overlay
+                .mac_prefixes
+                .push((oui, description, min, meta))
+                .is_ok()
+        }),
+        "wifi_ssid" => string_sig(raw, description).is_some_and(|s| overlay.ssids.push(s).is_ok()),
+        "ble_name" => {
+            string_sig(raw, description).is_some_and(|s| overlay.ble_names.push(s).is_ok())
+        }
+        "ble_service_uuid" => raw
+            .uuid
+            .as_deref()
+            .and_then(parse_uuid_16)
+            .is_some_and(|uuid| {
+                overlay
+                    .service_uuids_16
+                    .push((uuid, description, min, meta))
+                    .is_ok()
+            }),
+        "ble_manufacturer_id" => raw.company_id.is_some_and(|id| {
+            overlay
+                .manufacturer_ids
+                .push((id, description, min, meta))
+                .is_ok()
+        }),
+        // One per overlay; later duplicates are skipped
+        "wifi_traffic" if overlay.high_traffic_aps.is_none() => {
+            let description = if description.is_empty() {
+                detail("high-traffic AP")
+            } else {
+                description
+            };
+            overlay.high_traffic_aps = Some((description, meta));
+            true
+        }
+        _ => false,
+    }
+}
+
+fn string_sig(raw: &RawSignature, description: MatchDetail) -> Option<StringSig> {
+    let kind = string_match(raw)?;
+    let value = raw.value.clone()?;
+    // Fall back to the matched value when the file has no description
+    let description = if description.is_empty() {
+        detail(&value)
+    } else {
+        description
+    };
+    Some(StringSig {
+        kind,
+        value,
+        case_sensitive: raw.case_sensitive.unwrap_or(true),
+        description,
+        min_sightings: min_sightings(raw),
+        meta: meta(raw),
+    })
+}
+
+/// A string signature's match strategy
+pub(crate) fn string_match(raw: &RawSignature) -> Option<StringMatch> {
+    match raw.match_kind.as_deref()? {
+        "exact" => Some(StringMatch::Exact),
+        "prefix" => Some(StringMatch::Prefix),
+        "contains" => Some(StringMatch::Contains),
+        "glob" => Some(StringMatch::Glob),
+        _ => None, // regex is not evaluated on-device
+    }
+}
+
+/// A signature's `min_sightings`; absent (or 0) means report on first sight
+pub(crate) fn min_sightings(raw: &RawSignature) -> u8 {
+    raw.min_sightings.unwrap_or(1).max(1)
+}
+
+/// A signature's rating; fields the file leaves out fall back to
+/// [`SignatureMeta::UNRATED`], and the device class to the category's
+pub(crate) fn meta(raw: &RawSignature) -> SignatureMeta {
+    let unrated = SignatureMeta::UNRATED;
+    let meta = SignatureMeta::new(
+        raw.category.unwrap_or(unrated.category),
+        raw.severity.unwrap_or(unrated.severity),
+        raw.confidence.unwrap_or(unrated.confidence),
+    );
+    match raw.device_class {
+        Some(class) => meta.with_device_class(class),
+        None => meta,
+    }
+}
+
+pub(crate) fn detail(s: &str) -> MatchDetail {
+    let mut d = MatchDetail::new();
+    for c in s.chars() {
+        if d.push(c).is_err() {
+            break;
+        }
+    }
+    d
+}
+
+/// Parse "B4:1E:52" into OUI bytes
+pub(crate) fn parse_oui(s: &str) -> Option<[u8; 3]> {
+    let mut oui = [0u8; 3];
+    let mut parts = s.split(':');
+    for byte in oui.iter_mut() {
+        let part = parts.next()?;
+        if part.len() != 2 {
+            return None;
+        }
+        *byte = u8::from_str_radix(part, 16).ok()?;
+    }
+    parts.next().is_none().then_some(oui)
+}
+
+/// Parse a 16-bit UUID ("3100") or its expansion on the Bluetooth base UUID
+/// ("00003100-0000-1000-8000-00805f9b34fb").
+pub(crate) fn parse_uuid_16(s: &str) -> Option<u16> {
+    parse_uuid_32(s).and_then(|uuid| u16::try_from(uuid).ok())
+}
+
+/// Parse a 16 or 32-bit UUID ("3100", "0001fe2c") or its expansion on the
+/// Bluetooth base UUID ("0001fe2c-0000-1000-8000-00805f9b34fb").
+pub(crate) fn parse_uuid_32(s: &str) -> Option<u32> {
+    const BASE_SUFFIX: &str = "-0000-1000-8000-00805f9b34fb";
+    let short = match s.len() {
+        4 | 8 => s,
+        36 if s
+            .get(8..)
+            .is_some_and(|suffix| suffix.eq_ignore_ascii_case(BASE_SUFFIX)) =>
+        {
+            &s[..8]
+        }
+        _ => return None,
+    };
+    u32::from_str_radix(short, 16).ok()
+}
+
+/// Parse a full 128-bit UUID ("6e400001-b5a3-f393-e0a9-e50e24dcca9e") into
+/// its canonical big-endian value.
+#[cfg_attr(not(feature = "alloc"), allow(dead_code))]
+pub(crate) fn parse_uuid_128(s: &str) -> Option<u128> {
+    let bytes = s.as_bytes();
+    if bytes.len() != 36 || [8, 13, 18, 23].iter().any(|&i| bytes[i] != b'-') {
+        return None;
+    }
+    s.split('-').try_fold(0u128, |acc, group| {
+        let value = u128::from_str_radix(group, 16).ok()?;
+        Some(acc << (group.len() * 4) | value)
+    })
+}
+
+// ── Minimal JSON scanning ──────────────────────────────────────────────
+//
+// serde_json_core needs the whole document in one typed struct, which would
+// put every signature on the stack at once. Instead we locate the array and
+// hand each top-level object to serde individually.
+
+/// Find `key` followed by `[` and return the position just inside the array.
+fn find_array(json: &[u8], key: &[u8]) -> Option<usize> {
+    let key_pos = json.windows(key.len()).position(|w| w == key)?;
+    let mut pos = key_pos + key.len();
+    while pos < json.len() && matches!(json[pos], b' ' | b'\t' | b'\r' | b'\n' | b':') {
+        pos += 1;
+    }
+    (json.get(pos) == Some(&b'[')).then_some(pos + 1)
+}
+
+/// Return the byte range of the next `{...}` object in the current array,
+/// or `None` at the closing `]`.
+fn next_object(json: &[u8], mut pos: usize) -> Option<(usize, usize)> {
+    while pos < json.len() && json[pos] != b'{' {
+        if json[pos] == b']' {
+            return None;
+        }
+        pos += 1;
+    }
+    let start = pos;
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    while pos < json.len() {
+        let b = json[pos];
+        pos += 1;
+        if in_string {
+            match b {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match b {
+            b'"' => in_string = true,
+            b'{' | b'[' => depth += 1,
+            b'}' | b']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some((start, pos));
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+// ── Directory loader (std) ─────────────────────────────────────────────
+
+/// Load every `*.sigs.json` file in `dir` into `overlay`, in file-name order.
+#[cfg(feature = "std")]
+pub fn load_dir(
+    dir: &std::path::Path,
+    overlay: &mut SignatureOverlay,
+) -> std::io::Result<LoadReport> {
+    let mut paths: std::vec::Vec<_> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| p.to_str().is_some_and(|s| s.ends_with(".sigs.json")))
+        .collect();
+    paths.sort();
+
+    let mut total = LoadReport::default();
+    for path in paths {
+        let json = std::fs::read(&path)?;
+        if let Ok(report) = load_signatures(&json, overlay) {
+            total.loaded += report.loaded;
+            total.skipped += report.skipped;
+        }
+    }
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &[u8] = include_bytes!("../schemas/examples/flock-raven-airtag.sigs.json");
+
+    #[test]
+    fn loads_schema_example() {
+        let mut overlay = SignatureOverlay::new();
+        let report = load_signatures(EXAMPLE, &mut overlay).unwrap();
+        // 3 OUIs, 2 SSIDs (regex skipped), 2 BLE names, 1 mfr, 5 UUIDs
+        assert_eq!(report.loaded, 13);
+        // 1 regex SSID + 3 ble_ad_bytes
+        assert_eq!(report.skipped, 4);
+        assert_eq!(overlay.mac_prefixes.len(), 3);
+        assert_eq!(overlay.mac_prefixes[0].0, [0xB4, 0x1E, 0x52]);
+        assert_eq!(overlay.service_uuids_16[0].0, 0x3100);
+        assert_eq!(overlay.manufacturer_ids[0].0, 2504);
+    }
+
+    #[test]
+    fn rejects_document_without_signatures() {
+        let mut overlay = SignatureOverlay::new();
+        assert_eq!(
+            load_signatures(br#"{"version":1}"#, &mut overlay),
+            Err(AirhoundError::Malformed)
+        );
+    }
+
+    #[test]
+    fn skips_malformed_entries() {
+        let json = br#"{"version":1,"signatures":[
+            {"id":"bad-oui","type":"mac_oui","oui":"B4-1E-52"},
+            {"id":"no-type"},
+            {"id":"ok","type":"ble_manufacturer_id","company_id":76}
+        ]}"#;
+        let mut overlay = SignatureOverlay::new();
+        let report = load_signatures(json, &mut overlay).unwrap();
+        assert_eq!(
+            report,
+            LoadReport {
+                loaded: 1,
+                skipped: 2
+            }
+        );
+    }
+
+    #[test]
+    fn braces_inside_strings_do_not_confuse_scanner() {
+        let json = br#"{"signatures":[
+            {"id":"a","type":"wifi_ssid","match":"exact","value":"x}{]","description":"odd \"}"},
+            {"id":"b","type":"ble_manufacturer_id","company_id":1}
+        ]}"#;
+        let mut overlay = SignatureOverlay::new();
+        let report = load_signatures(json, &mut overlay).unwrap();
+        assert_eq!(report.loaded, 2);
+        assert_eq!(overlay.ssids[0].value.as_str(), "x}{]");
+    }
+
+    #[test]
+    fn wifi_traffic_signature_enables_high_traffic_aps() {
+        let json = br#"{"signatures":[
+            {"id":"t","type":"wifi_traffic"},
+            {"id":"t2","type":"wifi_traffic","description":"dup"}
+        ]}"#;
+        let mut overlay = SignatureOverlay::new();
+        let report = load_signatures(json, &mut overlay).unwrap();
+        assert_eq!((report.loaded, report.skipped), (1, 1));
+        let (description, _) = overlay.high_traffic_aps.as_ref().unwrap();
+        assert_eq!(description.as_str(), "high-traffic AP");
+        assert_eq!(overlay.len(), 1);
+    }
+
+    #[test]
+    fn expanded_base_uuid_is_accepted() {
+        assert_eq!(
+            parse_uuid_16("00003100-0000-1000-8000-00805f9b34fb"),
+            Some(0x3100)
+        );
+        assert_eq!(parse_uuid_16("3500"), Some(0x3500));
+        assert_eq!(parse_uuid_16("12345678-0000-1000-8000-00805f9b34fb"), None);
+        assert_eq!(
+            parse_uuid_32("12345678-0000-1000-8000-00805f9b34fb"),
+            Some(0x12345678)
+        );
+        assert_eq!(parse_uuid_32("0001fe2c"), Some(0x0001FE2C));
+        assert_eq!(parse_uuid_32("12345678-0000-1000-8000-000000000000"), None);
+        assert_eq!(
+            parse_uuid_128("6e400001-b5a3-f393-e0a9-e50e24dcca9e"),
+            Some(0x6E400001_B5A3_F393_E0A9_E50E24DCCA9E)
+        );
+        assert_eq!(parse_uuid_128("6e400001b5a3-f393-e0a9-e50e24dcca9e0"), None);
+        assert_eq!(parse_uuid_128("3100"), None);
+    }
+
+    #[test]
+    fn uuid_with_multibyte_char_is_rejected() {
+        // 36 bytes with a two-byte character across byte 8
+        let uuid = "1234567é0000-1000-8000-00805f9b34fb";
+        assert_eq!(uuid.len(), 36);
+        assert_eq!(parse_uuid_32(uuid), None);
+        assert_eq!(parse_uuid_16(uuid), None);
+    }
+
+    #[test]
+    fn min_sightings_is_read_and_defaults_to_one() {
+        let json = br#"{"signatures":[
+            {"id":"weak","type":"mac_oui","oui":"AA:BB:CC","min_sightings":4},
+            {"id":"strong","type":"ble_name","match":"exact","value":"Cam"},
+            {"id":"zero","type":"ble_manufacturer_id","company_id":9,"min_sightings":0}
+        ]}"#;
+        let mut overlay = SignatureOverlay::new();
+        load_signatures(json, &mut overlay).unwrap();
+        assert_eq!(overlay.mac_prefixes[0].2, 4);
+        assert_eq!(overlay.ble_names[0].min_sightings, 1);
+        assert_eq!(overlay.manufacturer_ids[0].2, 1);
+    }
+
+    #[test]
+    fn ratings_are_read_and_default_to_unrated() {
+        let json = br#"{"signatures":[
+            {"id":"rated","type":"mac_oui","oui":"AA:BB:CC","category":"drone","severity":"high","confidence":"low"},
+            {"id":"partial","type":"ble_name","match":"exact","value":"Cam","severity":"low"},
+            {"id":"future","type":"ble_manufacturer_id","company_id":9,"category":"doorbell","device_class":"robot"},
+            {"id":"glasses","type":"mac_oui","oui":"CC:A1:74","category":"camera","device_class":"body_camera"}
+        ]}"#;
+        let mut overlay = SignatureOverlay::new();
+        load_signatures(json, &mut overlay).unwrap();
+        assert_eq!(
+            overlay.mac_prefixes[0].3,
+            SignatureMeta::new(Category::Drone, Severity::High, Confidence::Low)
+        );
+        assert_eq!(
+            overlay.ble_names[0].meta,
+            SignatureMeta {
+                severity: Severity::Low,
+                ..SignatureMeta::UNRATED
+            }
+        );
+        // Categories this firmware doesn't know are kept as `other`
+        assert_eq!(overlay.manufacturer_ids[0].3.category, Category::Other);
+        assert_eq!(
+            overlay.manufacturer_ids[0].3.device_class,
+            DeviceClass::Unknown
+        );
+        // The device class follows the category unless the signature names one
+        assert_eq!(overlay.mac_prefixes[0].3.device_class, DeviceClass::Drone);
+        assert_eq!(
+            overlay.mac_prefixes[1].3,
+            SignatureMeta::new(Category::Camera, Severity::Medium, Confidence::Medium)
+                .with_device_class(DeviceClass::BodyCamera)
+        );
+    }
+
+    #[test]
+    fn string_sig_match_kinds() {
+        let sig = |kind, case_sensitive| StringSig {
+            kind,
+            value: String::try_from("Cam").unwrap(),
+            case_sensitive,
+            description: MatchDetail::new(),
+            min_sightings: 1,
+            meta: SignatureMeta::UNRATED,
+        };
+        assert!(sig(StringMatch::Exact, true).matches("Cam"));
+        assert!(!sig(StringMatch::Exact, true).matches("cam"));
+        assert!(sig(StringMatch::Exact, false).matches("cam"));
+        assert!(sig(StringMatch::Prefix, false).matches("CAMERA-1"));
+        assert!(!sig(StringMatch::Prefix, false).matches("ACam"));
+        assert!(sig(StringMatch::Contains, false).matches("my-cam-01"));
+        assert!(!sig(StringMatch::Contains, true).matches("my-cam-01"));
+        assert!(sig(StringMatch::Glob, false).matches("CAM"));
+        assert!(!sig(StringMatch::Glob, false).matches("CAM-1"));
+    }
+
+    #[test]
+    fn runtime_signatures_add_and_remove() {
+        let mut overlay = SignatureOverlay::new();
+        let oui = RuntimeSignature::parse("mac_oui", "AA:BB:CC").unwrap();
+        let ssid = RuntimeSignature::parse("ssid_keyword", "Verkada").unwrap();
+        assert!(overlay.add(&oui, ""));
+        assert!(overlay.add(&oui, "Verkada"));
+        assert!(overlay.add(&ssid, ""));
+        assert_eq!(overlay.len(), 2);
+        assert_eq!(overlay.mac_prefixes[0].1.as_str(), "Verkada");
+        assert!(overlay.ssids[0].matches("my-verkada-cam"));
+        assert_eq!(overlay.ssids[0].description.as_str(), "Verkada");
+
+        let lower = RuntimeSignature::parse("ssid_keyword", "verkada").unwrap();
+        assert!(overlay.remove(&lower));
+        assert!(!overlay.remove(&lower));
+        assert!(overlay.remove(&oui));
+        assert!(overlay.is_empty());
+    }
+
+    #[test]
+    fn runtime_signatures_keep_loaded_ones() {
+        // An exact-match name from a signature file is not a keyword
+        let json =
+            br#"{"signatures":[{"id":"n","type":"ble_name","match":"exact","value":"Cam"}]}"#;
+        let mut overlay = SignatureOverlay::new();
+        load_signatures(json, &mut overlay).unwrap();
+        assert!(!overlay.remove(&RuntimeSignature::parse("ble_name", "Cam").unwrap()));
+        assert_eq!(overlay.ble_names.len(), 1);
+    }
+
+    #[test]
+    fn runtime_signature_parse_rejects_bad_values() {
+        assert_eq!(
+            RuntimeSignature::parse("mac_oui", "AA:BB"),
+            Err(AirhoundError::InvalidArgument)
+        );
+        assert_eq!(
+            RuntimeSignature::parse("ble_name", ""),
+            Err(AirhoundError::InvalidArgument)
+        );
+        assert_eq!(
+            RuntimeSignature::parse("ble_uuid", "3100"),
+            Err(AirhoundError::Unsupported)
+        );
+    }
+
+    #[test]
+    fn runtime_signatures_round_trip() {
+        let mut overlay = SignatureOverlay::new();
+        let oui = RuntimeSignature::parse("mac_oui", "AA:BB:CC").unwrap();
+        overlay.add(&oui, "");
+        overlay.add(
+            &RuntimeSignature::parse("ssid_keyword", "Verkada").unwrap(),
+            "Verkada cam",
+        );
+        overlay.add(&RuntimeSignature::parse("ble_name", "Axon").unwrap(), "");
+        // File-loaded signatures add() can't recreate are left out
+        load_signatures(
+            br#"{"signatures":[{"id":"x","type":"wifi_ssid","match":"exact","value":"Cam"}]}"#,
+            &mut overlay,
+        )
+        .unwrap();
+
+        let mut buf = [0u8; 64];
+        let len = overlay.encode_runtime(&mut buf).unwrap();
+        // Default descriptions are stored empty
+        assert_eq!(len, 1 + (3 + 3) + (3 + 7 + 11) + (3 + 4));
+
+        let mut restored = SignatureOverlay::new();
+        assert_eq!(restored.restore_runtime(&buf[..len]), Ok(3));
+        assert_eq!(restored.len(), 3);
+        assert_eq!(restored.mac_prefixes[0].1, "AA:BB:CC");
+        assert_eq!(restored.ssids[0].description, "Verkada cam");
+        assert_eq!(restored.ble_names[0].description, "Axon");
+        assert!(restored.remove(&oui));
+
+        assert_eq!(
+            overlay.encode_runtime(&mut [0u8; 8]),
+            Err(AirhoundError::BufferTooSmall)
+        );
+        assert_eq!(
+            restored.restore_runtime(&buf[..len - 1]),
+            Err(AirhoundError::Malformed)
+        );
+        assert_eq!(
+            restored.restore_runtime(&[1, 9, 0, 0]),
+            Err(AirhoundError::Malformed)
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn load_dir_reads_sigs_json_files() {
+        let dir = std::env::temp_dir().join(format!("airhound-sigs-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("example.sigs.json"), EXAMPLE).unwrap();
+        std::fs::write(dir.join("notes.json"), b"{}").unwrap();
+        let mut overlay = SignatureOverlay::new();
+        let report = load_dir(&dir, &mut overlay).unwrap();
+        assert_eq!(report.loaded, 13);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn overlay_capacity_overflow_is_skipped() {
+        let mut overlay = SignatureOverlay::new();
+        for _ in 0..OVERLAY_CAPACITY {
+            overlay
+                .manufacturer_ids
+                .push((0, MatchDetail::new(), 1, SignatureMeta::UNRATED))
+                .unwrap();
+        }
+        let json = br#"{"signatures":[{"id":"x","type":"ble_manufacturer_id","company_id":9}]}"#;
+        let report = load_signatures(json, &mut overlay).unwrap();
+        assert_eq!(
+            report,
+            LoadReport {
+                loaded: 0,
+                skipped: 1
+            }
+        );
+    }
+}
@@ -0,0 +1,104 @@
+/// Per-device sighting counts for minimum-occurrence thresholds.
+///
+/// Some indicators are too generic to report on first sight — a Silicon Labs
+/// OUI is as likely a smart bulb as a Flock camera. Signatures can require
+/// `min_sightings`; [`crate::filter::FilterResult::min_sightings`] carries the
+/// threshold and the firmware holds back a match until [`SightingCounter`]
+/// has seen the device that often. Strong indicators keep a threshold of 1
+/// and fire immediately.
+use heapless::Vec;
+
+/// A device not seen for this long starts counting from zero again
+pub const SIGHTING_WINDOW_MS: u32 = 10 * 60 * 1000;
+
+struct Entry {
+    mac: [u8; 6],
+    count: u16,
+    last_ms: u32,
+}
+
+/// Sighting counts for up to `N` devices. Timestamps are milliseconds since
+/// boot (`u32`, wrapping).
+pub struct SightingCounter<const N: usize> {
+    entries: Vec<Entry, N>,
+}
+
+impl<const N: usize> SightingCounter<N> {
+    pub const fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Record a sighting of `mac` and return how many times it has been seen
+    /// within [`SIGHTING_WINDOW_MS`] gaps, this one included. When the table
+    /// is full the device seen longest ago is replaced.
+    pub fn observe(&mut self, mac: &[u8; 6], now_ms: u32) -> u16 {
+        if let Some(entry) = self.entries.iter_mut().find(|e| e.mac == *mac) {
+            if now_ms.wrapping_sub(entry.last_ms) >= SIGHTING_WINDOW_MS {
+                entry.count = 0;
+            }
+            entry.count = entry.count.saturating_add(1);
+            entry.last_ms = now_ms;
+            return entry.count;
+        }
+
+        let entry = Entry {
+            mac: *mac,
+            count: 1,
+            last_ms: now_ms,
+        };
+        if let Err(entry) = self.entries.push(entry) {
+            let stalest = self
+                .entries
+                .iter_mut()
+                .max_by_key(|e| now_ms.wrapping_sub(e.last_ms));
+            if let Some(stalest) = stalest {
+                *stalest = entry;
+            }
+        }
+        1
+    }
+}
+
+impl<const N: usize> Default for SightingCounter<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAC: [u8; 6] = [0x58, 0x8E, 0x81, 0x01, 0x02, 0x03];
+
+    #[test]
+    fn counts_repeat_sightings() {
+        let mut counter = SightingCounter::<4>::new();
+        assert_eq!(counter.observe(&MAC, 0), 1);
+        assert_eq!(counter.observe(&MAC, 1_000), 2);
+        assert_eq!(counter.observe(&[1; 6], 1_500), 1);
+        assert_eq!(counter.observe(&MAC, 2_000), 3);
+    }
+
+    #[test]
+    fn long_gap_restarts_count() {
+        let mut counter = SightingCounter::<4>::new();
+        counter.observe(&MAC, 0);
+        counter.observe(&MAC, 1_000);
+        assert_eq!(counter.observe(&MAC, 1_000 + SIGHTING_WINDOW_MS), 1);
+    }
+
+    #[test]
+    fn full_table_replaces_stalest() {
+        let mut counter = SightingCounter::<2>::new();
+        counter.observe(&MAC, 0);
+        counter.observe(&[1; 6], 100);
+        counter.observe(&MAC, 200);
+        counter.observe(&[2; 6], 300);
+        // [1; 6] was evicted; MAC kept its count
+        assert_eq!(counter.observe(&MAC, 400), 3);
+        assert_eq!(counter.observe(&[1; 6], 500), 1);
+    }
+}
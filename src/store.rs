@@ -0,0 +1,471 @@
+/// Persistent configuration storage abstraction.
+///
+/// [`ConfigStore`] is a small key/blob interface implemented per platform:
+/// NVS flash on the ESP firmwares (`nvs.rs` in the binary crate) and plain
+/// files on std hosts ([`FileStore`], `std` feature). Types that persist
+/// themselves implement [`ConfigBlob`], so the encoded bytes are owned by the
+/// library and identical on every platform.
+///
+/// Maximum encoded size of a single configuration blob.
+pub const MAX_BLOB_LEN: usize = 1024;
+
+/// Size of the record header written by [`encode_record`].
+pub const RECORD_HEADER_LEN: usize = 6;
+
+/// Magic byte marking a valid record (erased flash reads as 0xFF).
+const RECORD_MAGIC: u8 = 0xA7;
+
+/// Identifies a stored configuration blob.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigKey {
+    /// Runtime filter configuration ([`crate::filter::FilterConfig`])
+    FilterConfig,
+    /// Named configuration profiles
+    Profiles,
+    /// Device allowlist
+    Allowlist,
+    /// User-assigned device labels ([`crate::label::LabelTable`])
+    Labels,
+    /// Identity Resolving Keys of the user's own devices ([`crate::irk::IrkTable`])
+    Irks,
+    /// Installed signature bundle ([`crate::sigbundle`])
+    SigBundle,
+    /// Previously installed signature bundle, kept for rollback
+    SigBundlePrev,
+    /// Signatures added with `add_signature`
+    /// ([`crate::sigfile::SignatureOverlay::encode_runtime`])
+    RuntimeSignatures,
+}
+
+impl ConfigKey {
+    /// All keys, in slot order.
+    pub const ALL: &'static [ConfigKey] = &[
+        ConfigKey::FilterConfig,
+        ConfigKey::Profiles,
+        ConfigKey::Allowlist,
+        ConfigKey::Labels,
+        ConfigKey::Irks,
+        ConfigKey::SigBundle,
+        ConfigKey::SigBundlePrev,
+        ConfigKey::RuntimeSignatures,
+    ];
+
+    /// Stable name used as the file name / storage key.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConfigKey::FilterConfig => "filter_config",
+            ConfigKey::Profiles => "profiles",
+            ConfigKey::Allowlist => "allowlist",
+            ConfigKey::Labels => "labels",
+            ConfigKey::Irks => "irks",
+            ConfigKey::SigBundle => "sig_bundle",
+            ConfigKey::SigBundlePrev => "sig_bundle_prev",
+            ConfigKey::RuntimeSignatures => "runtime_sigs",
+        }
+    }
+
+    /// Stable slot number for backends with fixed-size regions (NVS sectors).
+    pub const fn slot(&self) -> u8 {
+        match self {
+            ConfigKey::FilterConfig => 0,
+            ConfigKey::Profiles => 1,
+            ConfigKey::Allowlist => 2,
+            ConfigKey::Labels => 3,
+            ConfigKey::Irks => 4,
+            ConfigKey::SigBundle => 5,
+            ConfigKey::SigBundlePrev => 6,
+            ConfigKey::RuntimeSignatures => 7,
+        }
+    }
+
+    /// Largest blob this key holds.
+    pub const fn max_len(&self) -> usize {
+        match self {
+            ConfigKey::SigBundle | ConfigKey::SigBundlePrev => crate::sigbundle::MAX_BUNDLE_LEN,
+            _ => MAX_BLOB_LEN,
+        }
+    }
+}
+
+/// Errors reported by a [`ConfigStore`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StoreError {
+    /// No blob has been saved under this key
+    NotFound,
+    /// The caller's buffer is smaller than the stored blob
+    BufferTooSmall,
+    /// The blob exceeds what the backend can hold
+    TooLarge,
+    /// Stored data failed validation (bad header, checksum, or encoding)
+    Corrupt,
+    /// The underlying medium reported an error
+    Io,
+}
+
+/// Key/blob persistence backend.
+pub trait ConfigStore {
+    /// Read the blob stored under `key` into `buf`, returning its length.
+    fn load(&mut self, key: ConfigKey, buf: &mut [u8]) -> Result<usize, StoreError>;
+
+    /// Store `data` under `key`, replacing any previous blob.
+    fn save(&mut self, key: ConfigKey, data: &[u8]) -> Result<(), StoreError>;
+
+    /// Remove the blob stored under `key`. Erasing a missing key is not an error.
+    fn erase(&mut self, key: ConfigKey) -> Result<(), StoreError>;
+}
+
+/// A configuration type that can be persisted through a [`ConfigStore`].
+pub trait ConfigBlob: Sized {
+    /// Key this type is stored under
+    const KEY: ConfigKey;
+
+    /// Encode into `buf`. Returns the number of bytes written.
+    fn encode(&self, buf: &mut [u8]) -> Option<usize>;
+
+    /// Decode from previously encoded bytes.
+    fn decode(data: &[u8]) -> Option<Self>;
+}
+
+/// Load and decode a typed blob.
+pub fn load_blob<T: ConfigBlob>(store: &mut impl ConfigStore) -> Result<T, StoreError> {
+    let mut buf = [0u8; MAX_BLOB_LEN];
+    let len = store.load(T::KEY, &mut buf)?;
+    T::decode(&buf[..len]).ok_or(StoreError::Corrupt)
+}
+
+/// Encode and save a typed blob.
+pub fn save_blob<T: ConfigBlob>(store: &mut impl ConfigStore, value: &T) -> Result<(), StoreError> {
+    let mut buf = [0u8; MAX_BLOB_LEN];
+    let len = value.encode(&mut buf).ok_or(StoreError::TooLarge)?;
+    store.save(T::KEY, &buf[..len])
+}
+
+// ── Record framing ─────────────────────────────────────────────────────
+//
+// Raw-flash backends have no file length, so blobs are framed as:
+// [magic] [slot] [len lo] [len hi] [sum1] [sum2] [data...]
+// where sum1/sum2 are a Fletcher-16 checksum over the data.
+
+/// Frame `data` for `key` into `out`. Returns the total record length.
+pub fn encode_record(key: ConfigKey, data: &[u8], out: &mut [u8]) -> Option<usize> {
+    let total = RECORD_HEADER_LEN + data.len();
+    if out.len() < total {
+        return None;
+    }
+    out[..RECORD_HEADER_LEN].copy_from_slice(&record_header(key, data)?);
+    out[RECORD_HEADER_LEN..total].copy_from_slice(data);
+    Some(total)
+}
+
+/// Validate a framed record for `key` and return its payload.
+pub fn decode_record(key: ConfigKey, raw: &[u8]) -> Result<&[u8], StoreError> {
+    let header = raw.get(..RECORD_HEADER_LEN).ok_or(StoreError::NotFound)?;
+    let len = record_len(key, header)?;
+    let data = raw
+        .get(RECORD_HEADER_LEN..RECORD_HEADER_LEN + len)
+        .ok_or(StoreError::Corrupt)?;
+    check_record(header, data)?;
+    Ok(data)
+}
+
+/// Record header for `data` stored under `key`, for backends that write the
+/// header and payload separately.
+pub fn record_header(key: ConfigKey, data: &[u8]) -> Option<[u8; RECORD_HEADER_LEN]> {
+    let len = u16::try_from(data.len()).ok()?.to_le_bytes();
+    let (sum1, sum2) = fletcher16(data);
+    Some([RECORD_MAGIC, key.slot(), len[0], len[1], sum1, sum2])
+}
+
+/// Payload length announced by a record header read on its own.
+pub fn record_len(key: ConfigKey, header: &[u8]) -> Result<usize, StoreError> {
+    if header.len() < RECORD_HEADER_LEN || header[0] != RECORD_MAGIC {
+        return Err(StoreError::NotFound);
+    }
+    if header[1] != key.slot() {
+        return Err(StoreError::Corrupt);
+    }
+    Ok(u16::from_le_bytes([header[2], header[3]]) as usize)
+}
+
+/// Check a payload read separately against its record header.
+pub fn check_record(header: &[u8], data: &[u8]) -> Result<(), StoreError> {
+    if fletcher16(data) != (header[4], header[5]) {
+        return Err(StoreError::Corrupt);
+    }
+    Ok(())
+}
+
+fn fletcher16(data: &[u8]) -> (u8, u8) {
+    let mut sum1: u16 = 0;
+    let mut sum2: u16 = 0;
+    for &b in data {
+        sum1 = (sum1 + b as u16) % 255;
+        sum2 = (sum2 + sum1) % 255;
+    }
+    (sum1 as u8, sum2 as u8)
+}
+
+// ── File backend (std) ─────────────────────────────────────────────────
+
+/// File-backed store: one `<key>.bin` file per blob in a config directory.
+#[cfg(feature = "std")]
+pub struct FileStore {
+    dir: std::path::PathBuf,
+}
+
+#[cfg(feature = "std")]
+impl FileStore {
+    /// Create a store rooted at `dir`. The directory is created on first save.
+    pub fn new(dir: impl Into<std::path::PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn path(&self, key: ConfigKey) -> std::path::PathBuf {
+        self.dir.join(key.as_str()).with_extension("bin")
+    }
+}
+
+#[cfg(feature = "std")]
+impl ConfigStore for FileStore {
+    fn load(&mut self, key: ConfigKey, buf: &mut [u8]) -> Result<usize, StoreError> {
+        let data = match std::fs::read(self.path(key)) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Err(StoreError::NotFound),
+            Err(_) => return Err(StoreError::Io),
+        };
+        let dest = buf
+            .get_mut(..data.len())
+            .ok_or(StoreError::BufferTooSmall)?;
+        dest.copy_from_slice(&data);
+        Ok(data.len())
+    }
+
+    fn save(&mut self, key: ConfigKey, data: &[u8]) -> Result<(), StoreError> {
+        std::fs::create_dir_all(&self.dir).map_err(|_| StoreError::Io)?;
+        // Write-then-rename so a crash never leaves a half-written blob
+        let path = self.path(key);
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, data).map_err(|_| StoreError::Io)?;
+        std::fs::rename(&tmp, &path).map_err(|_| StoreError::Io)
+    }
+
+    fn erase(&mut self, key: ConfigKey) -> Result<(), StoreError> {
+        match std::fs::remove_file(self.path(key)) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(_) => Err(StoreError::Io),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::FilterConfig;
+
+    /// In-memory store backed by framed records, mirroring the NVS layout.
+    struct MemStore {
+        slots: [[u8; MAX_BLOB_LEN + RECORD_HEADER_LEN]; 3],
+    }
+
+    impl MemStore {
+        fn new() -> Self {
+            Self {
+                slots: [[0xFF; MAX_BLOB_LEN + RECORD_HEADER_LEN]; 3],
+            }
+        }
+    }
+
+    impl ConfigStore for MemStore {
+        fn load(&mut self, key: ConfigKey, buf: &mut [u8]) -> Result<usize, StoreError> {
+            let data = decode_record(key, &self.slots[key.slot() as usize])?;
+            let dest = buf
+                .get_mut(..data.len())
+                .ok_or(StoreError::BufferTooSmall)?;
+            dest.copy_from_slice(data);
+            Ok(data.len())
+        }
+
+        fn save(&mut self, key: ConfigKey, data: &[u8]) -> Result<(), StoreError> {
+            let slot = &mut self.slots[key.slot() as usize];
+            encode_record(key, data, slot).ok_or(StoreError::TooLarge)?;
+            Ok(())
+        }
+
+        fn erase(&mut self, key: ConfigKey) -> Result<(), StoreError> {
+            self.slots[key.slot() as usize].fill(0xFF);
+            Ok(())
+        }
+    }
+
+    // ── Record framing ──────────────────────────────────────────────
+
+    #[test]
+    fn record_round_trip() {
+        let mut out = [0u8; 32];
+        let len = encode_record(ConfigKey::Profiles, b"hello", &mut out).unwrap();
+        assert_eq!(len, RECORD_HEADER_LEN + 5);
+        assert_eq!(
+            decode_record(ConfigKey::Profiles, &out[..len]).unwrap(),
+            b"hello"
+        );
+    }
+
+    #[test]
+    fn record_erased_flash_is_not_found() {
+        let erased = [0xFFu8; 16];
+        assert_eq!(
+            decode_record(ConfigKey::FilterConfig, &erased),
+            Err(StoreError::NotFound)
+        );
+    }
+
+    #[test]
+    fn record_detects_corruption() {
+        let mut out = [0u8; 32];
+        let len = encode_record(ConfigKey::Allowlist, b"data", &mut out).unwrap();
+        out[RECORD_HEADER_LEN] ^= 0x01;
+        assert_eq!(
+            decode_record(ConfigKey::Allowlist, &out[..len]),
+            Err(StoreError::Corrupt)
+        );
+    }
+
+    #[test]
+    fn record_rejects_wrong_key() {
+        let mut out = [0u8; 32];
+        let len = encode_record(ConfigKey::Allowlist, b"data", &mut out).unwrap();
+        assert_eq!(
+            decode_record(ConfigKey::Profiles, &out[..len]),
+            Err(StoreError::Corrupt)
+        );
+    }
+
+    #[test]
+    fn record_rejects_small_output() {
+        let mut out = [0u8; 4];
+        assert!(encode_record(ConfigKey::Profiles, b"hello", &mut out).is_none());
+    }
+
+    // ── Typed blobs ─────────────────────────────────────────────────
+
+    #[test]
+    fn filter_config_blob_round_trip() {
+        let mut store = MemStore::new();
+        let config = FilterConfig {
+            min_rssi: -72,
+            ble_enabled: false,
+            ..FilterConfig::new()
+        };
+        save_blob(&mut store, &config).unwrap();
+        let loaded: FilterConfig = load_blob(&mut store).unwrap();
+        assert_eq!(loaded.min_rssi, -72);
+        assert!(loaded.wifi_enabled);
+        assert!(!loaded.ble_enabled);
+    }
+
+    #[test]
+    fn longest_filter_config_fits_blob() {
+        let config = FilterConfig {
+            min_rssi: -128,
+            wifi_enabled: false,
+            ble_enabled: false,
+            ble_scan: crate::scanner::BleScanConfig::try_new(10_240, 10_240, false).unwrap(),
+            quiet_hours: crate::schedule::QuietHours {
+                start_min: 1439,
+                end_min: 1438,
+            },
+            categories: crate::filter::Categories {
+                alpr: false,
+                gunshot_detector: false,
+                camera: false,
+                tracker: false,
+                drone: false,
+                attack_tool: false,
+                other: false,
+            },
+            zones: crate::rssi::ProximityZones {
+                immediate: -128,
+                near: -128,
+            },
+            location_stale: crate::location::StalePolicy { max_age_s: 65_535 },
+            following: crate::following::FollowingPolicy {
+                sightings: 8,
+                window_min: 65_535,
+                distance_m: 65_535,
+            },
+            lingering: crate::lingering::LingeringPolicy {
+                duration_min: 65_535,
+                min_rssi: -128,
+            },
+            ..FilterConfig::new()
+        };
+        let mut buf = [0u8; MAX_BLOB_LEN];
+        assert!(config.encode(&mut buf).is_some());
+    }
+
+    #[test]
+    fn filter_config_blob_without_ble_scan_uses_default() {
+        let legacy = br#"{"min_rssi":-80,"wifi_enabled":true,"ble_enabled":true}"#;
+        let config = FilterConfig::decode(legacy).unwrap();
+        assert_eq!(config.min_rssi, -80);
+        assert_eq!(config.ble_scan, crate::scanner::BleScanConfig::new());
+        assert_eq!(config.categories, crate::filter::Categories::ALL);
+
+        // Categories a newer or partial blob leaves out stay enabled
+        let partial = br#"{"min_rssi":-80,"wifi_enabled":true,"ble_enabled":true,"categories":{"tracker":false}}"#;
+        let config = FilterConfig::decode(partial).unwrap();
+        assert!(!config.categories.tracker);
+        assert!(config.categories.alpr);
+    }
+
+    #[test]
+    fn load_missing_blob_is_not_found() {
+        let mut store = MemStore::new();
+        assert_eq!(
+            load_blob::<FilterConfig>(&mut store).err(),
+            Some(StoreError::NotFound)
+        );
+    }
+
+    #[test]
+    fn erase_removes_blob() {
+        let mut store = MemStore::new();
+        save_blob(&mut store, &FilterConfig::new()).unwrap();
+        store.erase(ConfigKey::FilterConfig).unwrap();
+        assert!(load_blob::<FilterConfig>(&mut store).is_err());
+    }
+
+    #[test]
+    fn config_key_slots_are_unique() {
+        for (i, a) in ConfigKey::ALL.iter().enumerate() {
+            for b in &ConfigKey::ALL[i + 1..] {
+                assert_ne!(a.slot(), b.slot());
+                assert_ne!(a.as_str(), b.as_str());
+            }
+        }
+    }
+
+    // ── File backend ────────────────────────────────────────────────
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn file_store_round_trip() {
+        let dir = std::env::temp_dir().join(format!("airhound-store-{}", std::process::id()));
+        let mut store = FileStore::new(&dir);
+        assert_eq!(
+            load_blob::<FilterConfig>(&mut store).err(),
+            Some(StoreError::NotFound)
+        );
+        let config = FilterConfig {
+            min_rssi: -55,
+            ..FilterConfig::new()
+        };
+        save_blob(&mut store, &config).unwrap();
+        let loaded: FilterConfig = load_blob(&mut store).unwrap();
+        assert_eq!(loaded.min_rssi, -55);
+        store.erase(ConfigKey::FilterConfig).unwrap();
+        store.erase(ConfigKey::FilterConfig).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,273 @@
+/// Beacon timing continuity per BSSID.
+///
+/// An access point stamps each beacon with its TSF timer, a microsecond clock
+/// running since it started, and sends beacons on a fixed beacon interval.
+/// Beacon spoofers and covert devices often get this wrong, and two radios
+/// claiming one BSSID — an evil twin — carry two unrelated clocks.
+/// [`TimingMonitor`] follows each BSSID's clock against uptime and flags
+/// irregular beacon timing, a clock that jumps, and beacons alternating
+/// between two clocks; with `FilterConfig::timing_heuristics` on,
+/// `filter::TimingHeuristics` reports these as `beacon_timing` matches.
+use heapless::Vec;
+
+use crate::scanner::BeaconTiming;
+
+/// A BSSID not heard for this long is forgotten
+pub const TIMING_TTL_MS: u32 = 5 * 60 * 1000;
+
+/// How far (µs) a beacon's TSF may stray from where its clock should be
+/// by now — our timestamps are taken after queueing, not on receipt
+pub const CLOCK_TOLERANCE_US: u64 = 500 * 1000;
+
+/// How far (µs) a beacon's position within the beacon interval may move
+/// from the previous beacon's before it counts as jitter
+pub const MAX_PHASE_SHIFT_US: u32 = 10 * 1000;
+
+/// Jittery beacons in a row that flag the BSSID
+pub const JITTER_BEACONS: u8 = 3;
+
+/// What is off about a BSSID's beacon timing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimingAnomaly {
+    /// Beacons keep landing at irregular points of the beacon interval, or
+    /// the advertised interval changed
+    Jitter,
+    /// The TSF clock jumped, as if the access point restarted
+    TsfReset,
+    /// Beacons alternate between two TSF clocks: two devices share the BSSID
+    BssidConflict,
+}
+
+impl TimingAnomaly {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TimingAnomaly::Jitter => "interval jitter",
+            TimingAnomaly::TsfReset => "TSF reset",
+            TimingAnomaly::BssidConflict => "BSSID conflict",
+        }
+    }
+}
+
+/// One TSF clock heard under a BSSID
+#[derive(Debug, Clone, Copy)]
+struct Clock {
+    tsf: u64,
+    last_ms: u32,
+    /// TSF offset (µs) into the beacon interval
+    phase: u32,
+}
+
+impl Clock {
+    /// How far `tsf` is from where this clock should be at `now_ms`
+    fn error(&self, tsf: u64, now_ms: u32) -> u64 {
+        let expected = self
+            .tsf
+            .wrapping_add(now_ms.wrapping_sub(self.last_ms) as u64 * 1000);
+        tsf.abs_diff(expected)
+    }
+}
+
+struct Entry {
+    bssid: [u8; 6],
+    interval: u16,
+    clock: Clock,
+    /// The clock heard before the latest jump
+    other: Option<Clock>,
+    jittery: u8,
+}
+
+/// Beacon clocks of up to `N` BSSIDs. Timestamps are milliseconds since boot
+/// (`u32`, wrapping).
+pub struct TimingMonitor<const N: usize> {
+    entries: Vec<Entry, N>,
+}
+
+impl<const N: usize> TimingMonitor<N> {
+    pub const fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Record a beacon from `bssid` and return what is off about its
+    /// timing, if anything. Jitter is reported once per run of
+    /// [`JITTER_BEACONS`] jittery beacons; a conflict with every beacon that
+    /// switches back to the other clock.
+    pub fn observe(
+        &mut self,
+        bssid: &[u8; 6],
+        timing: BeaconTiming,
+        now_ms: u32,
+    ) -> Option<TimingAnomaly> {
+        self.entries
+            .retain(|e| now_ms.wrapping_sub(e.clock.last_ms) < TIMING_TTL_MS);
+
+        let period = timing.interval as u64 * 1024;
+        let clock = Clock {
+            tsf: timing.tsf,
+            last_ms: now_ms,
+            phase: timing.tsf.checked_rem(period).unwrap_or(0) as u32,
+        };
+        let Some(entry) = self.entries.iter_mut().find(|e| e.bssid == *bssid) else {
+            self.insert(Entry {
+                bssid: *bssid,
+                interval: timing.interval,
+                clock,
+                other: None,
+                jittery: 0,
+            });
+            return None;
+        };
+
+        if entry.clock.error(timing.tsf, now_ms) > CLOCK_TOLERANCE_US {
+            let switched_back = entry
+                .other
+                .is_some_and(|other| other.error(timing.tsf, now_ms) <= CLOCK_TOLERANCE_US);
+            entry.other = Some(entry.clock);
+            entry.clock = clock;
+            entry.interval = timing.interval;
+            entry.jittery = 0;
+            return Some(if switched_back {
+                TimingAnomaly::BssidConflict
+            } else {
+                TimingAnomaly::TsfReset
+            });
+        }
+
+        let shift = entry.clock.phase.abs_diff(clock.phase);
+        let shift = shift.min((period as u32).saturating_sub(shift));
+        let changed = entry.interval != timing.interval;
+        entry.clock = clock;
+        entry.interval = timing.interval;
+        if changed {
+            entry.jittery = 0;
+            return Some(TimingAnomaly::Jitter);
+        }
+        if shift <= MAX_PHASE_SHIFT_US {
+            entry.jittery = 0;
+            return None;
+        }
+        entry.jittery = entry.jittery.saturating_add(1);
+        (entry.jittery == JITTER_BEACONS).then_some(TimingAnomaly::Jitter)
+    }
+
+    /// Add an entry, replacing the one heard from longest ago when full.
+    fn insert(&mut self, entry: Entry) {
+        if let Err(entry) = self.entries.push(entry) {
+            let now_ms = entry.clock.last_ms;
+            let stalest = self
+                .entries
+                .iter_mut()
+                .max_by_key(|e| now_ms.wrapping_sub(e.clock.last_ms));
+            if let Some(stalest) = stalest {
+                *stalest = entry;
+            }
+        }
+    }
+}
+
+impl<const N: usize> Default for TimingMonitor<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BSSID: [u8; 6] = [0x02, 0xAA, 0xBB, 0xCC, 0xDD, 0xEE];
+
+    /// 100 TU, the usual beacon interval
+    const PERIOD_US: u64 = 102_400;
+
+    fn beacon(tsf: u64) -> BeaconTiming {
+        BeaconTiming { tsf, interval: 100 }
+    }
+
+    /// Feed `count` beacons from a clock started `start_us` before boot,
+    /// every tenth interval, with `phase_us` added to every other one
+    fn feed(
+        clocks: &mut TimingMonitor<4>,
+        start_us: u64,
+        phase_us: u64,
+        count: u64,
+    ) -> std::vec::Vec<Option<TimingAnomaly>> {
+        (0..count)
+            .map(|i| {
+                let uptime_us = i * 10 * PERIOD_US;
+                let jitter = if i % 2 == 1 { phase_us } else { 0 };
+                let now_ms = (uptime_us / 1000) as u32;
+                clocks.observe(&BSSID, beacon(start_us + uptime_us + jitter), now_ms)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn steady_beacons_pass() {
+        let mut clocks = TimingMonitor::<4>::new();
+        let seen = feed(&mut clocks, 50 * PERIOD_US, 0, 20);
+        assert!(seen.iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn irregular_beacons_are_jitter() {
+        let mut clocks = TimingMonitor::<4>::new();
+        let seen = feed(&mut clocks, 50 * PERIOD_US, 40_000, 8);
+        // Once, on the third jittery beacon in a row
+        assert_eq!(seen.iter().flatten().count(), 1);
+        assert_eq!(seen[3], Some(TimingAnomaly::Jitter));
+    }
+
+    #[test]
+    fn interval_change_is_jitter() {
+        let mut clocks = TimingMonitor::<4>::new();
+        clocks.observe(&BSSID, beacon(0), 0);
+        let changed = BeaconTiming {
+            tsf: 10 * PERIOD_US,
+            interval: 200,
+        };
+        assert_eq!(
+            clocks.observe(&BSSID, changed, 1_024),
+            Some(TimingAnomaly::Jitter)
+        );
+    }
+
+    #[test]
+    fn clock_jump_is_a_reset() {
+        let mut clocks = TimingMonitor::<4>::new();
+        clocks.observe(&BSSID, beacon(900 * 1000 * 1000), 0);
+        assert_eq!(
+            clocks.observe(&BSSID, beacon(PERIOD_US), 1_024),
+            Some(TimingAnomaly::TsfReset)
+        );
+        assert_eq!(clocks.observe(&BSSID, beacon(11 * PERIOD_US), 2_048), None);
+    }
+
+    #[test]
+    fn alternating_clocks_conflict() {
+        let mut clocks = TimingMonitor::<4>::new();
+        let genuine = |now_ms: u32| beacon(900 * 1000 * 1000 + now_ms as u64 * 1000);
+        let twin = |now_ms: u32| beacon(now_ms as u64 * 1000);
+        clocks.observe(&BSSID, genuine(0), 0);
+        assert_eq!(
+            clocks.observe(&BSSID, twin(1_024), 1_024),
+            Some(TimingAnomaly::TsfReset)
+        );
+        assert_eq!(
+            clocks.observe(&BSSID, genuine(2_048), 2_048),
+            Some(TimingAnomaly::BssidConflict)
+        );
+        assert_eq!(
+            clocks.observe(&BSSID, twin(3_072), 3_072),
+            Some(TimingAnomaly::BssidConflict)
+        );
+    }
+
+    #[test]
+    fn bssids_expire() {
+        let mut clocks = TimingMonitor::<4>::new();
+        clocks.observe(&BSSID, beacon(900 * 1000 * 1000), 0);
+        assert_eq!(clocks.observe(&BSSID, beacon(0), TIMING_TTL_MS), None);
+    }
+}
@@ -0,0 +1,209 @@
+/// Per-transmitter WiFi data-frame volume.
+///
+/// A camera streaming video sends a steady, heavy run of data frames that a
+/// thermostat or an idle phone doesn't. [`TrafficMonitor`] counts data frames
+/// per transmitter in fixed intervals and reports a device as
+/// [`Traffic::Station`] or [`Traffic::AccessPoint`] once it has stayed above
+/// the threshold for several consecutive intervals. For frames sent by an AP
+/// the transmitter is the BSSID, so AP-mode cameras are counted per BSSID.
+use heapless::Vec;
+
+/// Length of one counting interval
+pub const INTERVAL_MS: u32 = 5_000;
+
+/// Data frames seen per interval that count as heavy traffic. The sniffer
+/// hops 13 channels (default ETSI plan) and is on any one of them for about
+/// 360 ms of each interval, so this is roughly 100 frames/s on air — a video
+/// stream, not telemetry.
+pub const HIGH_TRAFFIC_FRAMES: u16 = 40;
+
+/// Consecutive heavy intervals before a transmitter is flagged
+pub const SUSTAINED_INTERVALS: u8 = 3;
+
+/// Sustained traffic level of a transmitter
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Traffic {
+    #[default]
+    Normal,
+    /// Heavy traffic from a client station (uplink — e.g. a camera uploading)
+    Station,
+    /// Heavy traffic from an access point (e.g. a camera in AP mode)
+    AccessPoint,
+}
+
+struct Counter {
+    mac: [u8; 6],
+    interval_start_ms: u32,
+    frames: u16,
+    heavy_intervals: u8,
+    /// Seen sending FromDS frames, i.e. acting as an AP
+    ap: bool,
+}
+
+/// Data-frame counters for up to `N` transmitters. Timestamps are
+/// milliseconds since boot (`u32`, wrapping).
+pub struct TrafficMonitor<const N: usize> {
+    counters: Vec<Counter, N>,
+}
+
+impl<const N: usize> TrafficMonitor<N> {
+    pub const fn new() -> Self {
+        Self {
+            counters: Vec::new(),
+        }
+    }
+
+    /// Count one data frame sent by `mac`; `from_ap` when the frame came
+    /// from the distribution system. Returns the transmitter's traffic level.
+    /// When the table is full the quietest transmitter is replaced.
+    pub fn record(&mut self, mac: &[u8; 6], from_ap: bool, now_ms: u32) -> Traffic {
+        let index = match self.counters.iter().position(|c| c.mac == *mac) {
+            Some(index) => index,
+            None => {
+                let counter = Counter {
+                    mac: *mac,
+                    interval_start_ms: now_ms,
+                    frames: 0,
+                    heavy_intervals: 0,
+                    ap: false,
+                };
+                match self.counters.push(counter) {
+                    Ok(()) => self.counters.len() - 1,
+                    Err(counter) => {
+                        let quietest = self
+                            .counters
+                            .iter()
+                            .enumerate()
+                            .min_by_key(|(_, c)| (c.heavy_intervals, c.frames))
+                            .map(|(i, _)| i);
+                        let Some(quietest) = quietest else {
+                            return Traffic::Normal;
+                        };
+                        self.counters[quietest] = counter;
+                        quietest
+                    }
+                }
+            }
+        };
+
+        let counter = &mut self.counters[index];
+        let elapsed = now_ms.wrapping_sub(counter.interval_start_ms);
+        if elapsed >= INTERVAL_MS {
+            // An idle interval in between breaks the run
+            let heavy = counter.frames >= HIGH_TRAFFIC_FRAMES && elapsed < 2 * INTERVAL_MS;
+            counter.heavy_intervals = if heavy {
+                counter.heavy_intervals.saturating_add(1)
+            } else {
+                0
+            };
+            counter.interval_start_ms = now_ms;
+            counter.frames = 0;
+        }
+        counter.frames = counter.frames.saturating_add(1);
+        counter.ap |= from_ap;
+        level(counter)
+    }
+
+    /// Current traffic level of `mac` without counting a frame.
+    pub fn traffic(&self, mac: &[u8; 6], now_ms: u32) -> Traffic {
+        self.counters
+            .iter()
+            .find(|c| c.mac == *mac)
+            .filter(|c| now_ms.wrapping_sub(c.interval_start_ms) < 2 * INTERVAL_MS)
+            .map_or(Traffic::Normal, level)
+    }
+}
+
+impl<const N: usize> Default for TrafficMonitor<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn level(counter: &Counter) -> Traffic {
+    match (counter.heavy_intervals >= SUSTAINED_INTERVALS, counter.ap) {
+        (false, _) => Traffic::Normal,
+        (true, false) => Traffic::Station,
+        (true, true) => Traffic::AccessPoint,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CAMERA: [u8; 6] = [0xB4, 0x1E, 0x52, 0x01, 0x02, 0x03];
+
+    /// Send `frames` evenly spread over one interval starting at `start_ms`
+    fn burst<const N: usize>(
+        monitor: &mut TrafficMonitor<N>,
+        mac: &[u8; 6],
+        from_ap: bool,
+        frames: u16,
+        start_ms: u32,
+    ) -> Traffic {
+        let mut traffic = Traffic::Normal;
+        for i in 0..frames as u32 {
+            traffic = monitor.record(mac, from_ap, start_ms + i * INTERVAL_MS / frames as u32);
+        }
+        traffic
+    }
+
+    // ── Counting ────────────────────────────────────────────────────
+
+    #[test]
+    fn sustained_heavy_traffic_is_flagged() {
+        let mut monitor = TrafficMonitor::<4>::new();
+        for i in 0..SUSTAINED_INTERVALS as u32 {
+            let traffic = burst(&mut monitor, &CAMERA, false, 60, i * INTERVAL_MS);
+            assert_eq!(traffic, Traffic::Normal);
+        }
+        // The next interval closes the third heavy one
+        let start = SUSTAINED_INTERVALS as u32 * INTERVAL_MS;
+        assert_eq!(monitor.record(&CAMERA, false, start), Traffic::Station);
+        assert_eq!(monitor.traffic(&CAMERA, start + 100), Traffic::Station);
+    }
+
+    #[test]
+    fn light_traffic_is_normal() {
+        let mut monitor = TrafficMonitor::<4>::new();
+        for i in 0..6 {
+            burst(&mut monitor, &CAMERA, false, 10, i * INTERVAL_MS);
+        }
+        assert_eq!(monitor.traffic(&CAMERA, 6 * INTERVAL_MS), Traffic::Normal);
+    }
+
+    #[test]
+    fn ap_transmitter_is_reported_as_access_point() {
+        let mut monitor = TrafficMonitor::<4>::new();
+        for i in 0..=SUSTAINED_INTERVALS as u32 {
+            burst(&mut monitor, &CAMERA, true, 60, i * INTERVAL_MS);
+        }
+        let now = (SUSTAINED_INTERVALS as u32 + 1) * INTERVAL_MS - 1;
+        assert_eq!(monitor.traffic(&CAMERA, now), Traffic::AccessPoint);
+    }
+
+    #[test]
+    fn gap_resets_the_run() {
+        let mut monitor = TrafficMonitor::<4>::new();
+        for i in 0..=SUSTAINED_INTERVALS as u32 {
+            burst(&mut monitor, &CAMERA, false, 60, i * INTERVAL_MS);
+        }
+        // Silent for a while: stale, then the run starts over
+        let later = 20 * INTERVAL_MS;
+        assert_eq!(monitor.traffic(&CAMERA, later), Traffic::Normal);
+        assert_eq!(monitor.record(&CAMERA, false, later), Traffic::Normal);
+    }
+
+    #[test]
+    fn full_table_replaces_quietest() {
+        let mut monitor = TrafficMonitor::<2>::new();
+        burst(&mut monitor, &CAMERA, false, 60, 0);
+        monitor.record(&[1; 6], false, 10);
+        monitor.record(&[2; 6], false, 20);
+        // CAMERA kept its count; [1; 6] was replaced by [2; 6]
+        assert_eq!(monitor.counters.len(), 2);
+        assert!(monitor.counters.iter().any(|c| c.mac == CAMERA));
+        assert!(monitor.counters.iter().any(|c| c.mac == [2; 6]));
+    }
+}
@@ -0,0 +1,319 @@
+/// Ephemeral watch list for targeted investigation.
+///
+/// A host `watch` command registers a MAC for a limited time. Every sighting
+/// of a watched MAC is reported — on any radio, regardless of signature
+/// matches or the RSSI threshold — together with its recent RSSI history, so
+/// the companion can tell whether a suspected follower is getting closer.
+///
+/// Each run of sightings is an encounter. The list keeps the strongest RSSI
+/// of the encounter and its timestamp — the closest approach — and once the
+/// device has been silent for [`LOST_AFTER_MS`] hands back a [`LostDevice`]
+/// summary. The companion matches the closest-approach timestamp against
+/// its GPS track to show where the device was nearest.
+use heapless::Vec;
+
+/// RSSI samples kept per watched device
+pub const HISTORY_LEN: usize = 8;
+
+/// Watch duration used when the command doesn't specify one
+pub const DEFAULT_WATCH_SECS: u32 = 600;
+
+/// Longest accepted watch duration (24 hours)
+pub const MAX_WATCH_SECS: u32 = 86_400;
+
+/// Silence after which a watched device's encounter is over
+pub const LOST_AFTER_MS: u32 = 60_000;
+
+/// Recent RSSI samples, oldest first
+pub type RssiHistory = Vec<i8, HISTORY_LEN>;
+
+/// Strongest sighting of an encounter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClosestApproach {
+    pub rssi: i8,
+    /// Uptime in milliseconds at that sighting
+    pub ts: u32,
+}
+
+/// A sighting of a watched device, as returned by [`WatchList::observe`]
+pub struct Sighting<'a> {
+    pub history: &'a RssiHistory,
+    pub closest: ClosestApproach,
+}
+
+/// Summary of an encounter with a watched device that has gone silent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LostDevice {
+    pub mac: [u8; 6],
+    /// Radio of the last sighting
+    pub radio: &'static str,
+    pub first_seen_ms: u32,
+    pub last_seen_ms: u32,
+    pub sightings: u16,
+    pub closest: ClosestApproach,
+}
+
+struct Encounter {
+    radio: &'static str,
+    first_seen_ms: u32,
+    last_seen_ms: u32,
+    sightings: u16,
+    closest: ClosestApproach,
+}
+
+struct Watch {
+    mac: [u8; 6],
+    expires_ms: u32,
+    history: RssiHistory,
+    /// Current encounter, until it is reported lost
+    encounter: Option<Encounter>,
+}
+
+/// Up to `N` watched MACs with expiry. Timestamps are milliseconds since boot
+/// (`u32`, wrapping).
+pub struct WatchList<const N: usize> {
+    watches: Vec<Watch, N>,
+}
+
+impl<const N: usize> WatchList<N> {
+    pub const fn new() -> Self {
+        Self {
+            watches: Vec::new(),
+        }
+    }
+
+    /// Watch `mac` for `duration_secs` (clamped to [`MAX_WATCH_SECS`]).
+    /// Re-watching extends the timeout and keeps the history. Returns `false`
+    /// if the list is full.
+    pub fn add(&mut self, mac: &[u8; 6], duration_secs: u32, now_ms: u32) -> bool {
+        self.expire(now_ms);
+        let expires_ms = now_ms.wrapping_add(duration_secs.min(MAX_WATCH_SECS) * 1000);
+        if let Some(watch) = self.watches.iter_mut().find(|w| w.mac == *mac) {
+            watch.expires_ms = expires_ms;
+            return true;
+        }
+        self.watches
+            .push(Watch {
+                mac: *mac,
+                expires_ms,
+                history: Vec::new(),
+                encounter: None,
+            })
+            .is_ok()
+    }
+
+    /// Stop watching `mac`. Returns `false` if it wasn't watched.
+    pub fn remove(&mut self, mac: &[u8; 6]) -> bool {
+        match self.watches.iter().position(|w| w.mac == *mac) {
+            Some(index) => {
+                self.watches.swap_remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Record a sighting on `radio`. Returns the updated RSSI history (this
+    /// sighting last) and the encounter's closest approach if `mac` is
+    /// watched.
+    pub fn observe(
+        &mut self,
+        mac: &[u8; 6],
+        radio: &'static str,
+        rssi: i8,
+        now_ms: u32,
+    ) -> Option<Sighting<'_>> {
+        self.expire(now_ms);
+        let watch = self.watches.iter_mut().find(|w| w.mac == *mac)?;
+        if watch.history.is_full() {
+            watch.history.remove(0);
+        }
+        let _ = watch.history.push(rssi);
+
+        let closest = ClosestApproach { rssi, ts: now_ms };
+        let encounter = watch.encounter.get_or_insert(Encounter {
+            radio,
+            first_seen_ms: now_ms,
+            last_seen_ms: now_ms,
+            sightings: 0,
+            closest,
+        });
+        encounter.radio = radio;
+        encounter.last_seen_ms = now_ms;
+        encounter.sightings = encounter.sightings.saturating_add(1);
+        if rssi > encounter.closest.rssi {
+            encounter.closest = closest;
+        }
+        Some(Sighting {
+            history: &watch.history,
+            closest: encounter.closest,
+        })
+    }
+
+    /// End one encounter that has been silent for [`LOST_AFTER_MS`] and
+    /// return its summary. Call until `None` to collect all of them. A watch
+    /// that expires or is removed mid-encounter ends without a summary.
+    pub fn take_lost(&mut self, now_ms: u32) -> Option<LostDevice> {
+        self.watches.iter_mut().find_map(|watch| {
+            let encounter = watch.encounter.as_ref()?;
+            if now_ms.wrapping_sub(encounter.last_seen_ms) < LOST_AFTER_MS {
+                return None;
+            }
+            let lost = LostDevice {
+                mac: watch.mac,
+                radio: encounter.radio,
+                first_seen_ms: encounter.first_seen_ms,
+                last_seen_ms: encounter.last_seen_ms,
+                sightings: encounter.sightings,
+                closest: encounter.closest,
+            };
+            watch.encounter = None;
+            Some(lost)
+        })
+    }
+
+    /// Drop watches whose timeout has passed.
+    pub fn expire(&mut self, now_ms: u32) {
+        // Wrapping-safe "now >= expires": the difference is small and positive
+        self.watches
+            .retain(|w| (now_ms.wrapping_sub(w.expires_ms) as i32) < 0);
+    }
+
+    pub fn len(&self) -> usize {
+        self.watches.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.watches.is_empty()
+    }
+}
+
+impl<const N: usize> Default for WatchList<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAC: [u8; 6] = [0xAA, 0xBB, 0xCC, 0x01, 0x02, 0x03];
+
+    // ── Watching ────────────────────────────────────────────────────
+
+    #[test]
+    fn unwatched_mac_is_ignored() {
+        let mut list = WatchList::<2>::new();
+        assert!(list.observe(&MAC, "ble", -60, 0).is_none());
+    }
+
+    #[test]
+    fn watched_mac_accumulates_history() {
+        let mut list = WatchList::<2>::new();
+        assert!(list.add(&MAC, 60, 0));
+        for (i, rssi) in (-80..-70).enumerate() {
+            list.observe(&MAC, "ble", rssi, i as u32 * 100);
+        }
+        let history = list.observe(&MAC, "ble", -50, 2_000).unwrap().history;
+        assert_eq!(history.len(), HISTORY_LEN);
+        assert_eq!(history.last(), Some(&-50));
+        assert_eq!(history[0], -77);
+    }
+
+    #[test]
+    fn watch_expires() {
+        let mut list = WatchList::<2>::new();
+        list.add(&MAC, 10, 1_000);
+        assert!(list.observe(&MAC, "ble", -60, 10_999).is_some());
+        assert!(list.observe(&MAC, "ble", -60, 11_000).is_none());
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn rewatch_extends_timeout() {
+        let mut list = WatchList::<2>::new();
+        list.add(&MAC, 10, 0);
+        list.observe(&MAC, "ble", -70, 5_000);
+        list.add(&MAC, 10, 8_000);
+        let history = list.observe(&MAC, "ble", -65, 15_000).unwrap().history;
+        assert_eq!(history.as_slice(), &[-70, -65]);
+        assert_eq!(list.len(), 1);
+    }
+
+    #[test]
+    fn full_list_rejects_and_remove_frees() {
+        let mut list = WatchList::<1>::new();
+        assert!(list.add(&MAC, 60, 0));
+        assert!(!list.add(&[1; 6], 60, 0));
+        assert!(list.remove(&MAC));
+        assert!(!list.remove(&MAC));
+        assert!(list.add(&[1; 6], 60, 0));
+    }
+
+    #[test]
+    fn duration_is_clamped_and_survives_wraparound() {
+        let mut list = WatchList::<1>::new();
+        let start = u32::MAX - 1_000;
+        list.add(&MAC, u32::MAX, start);
+        assert!(list.observe(&MAC, "ble", -60, 5_000).is_some());
+        let end = start.wrapping_add(MAX_WATCH_SECS * 1000);
+        assert!(list.observe(&MAC, "ble", -60, end).is_none());
+    }
+
+    // ── Encounters ──────────────────────────────────────────────────
+
+    #[test]
+    fn closest_approach_tracks_strongest_sighting() {
+        let mut list = WatchList::<2>::new();
+        list.add(&MAC, 600, 0);
+        list.observe(&MAC, "ble", -80, 1_000);
+        list.observe(&MAC, "ble", -55, 2_000);
+        let sighting = list.observe(&MAC, "wifi", -70, 3_000).unwrap();
+        assert_eq!(
+            sighting.closest,
+            ClosestApproach {
+                rssi: -55,
+                ts: 2_000
+            }
+        );
+    }
+
+    #[test]
+    fn silent_device_is_reported_lost_once() {
+        let mut list = WatchList::<2>::new();
+        list.add(&MAC, 600, 0);
+        list.observe(&MAC, "ble", -70, 1_000);
+        list.observe(&MAC, "ble", -50, 2_000);
+        list.observe(&MAC, "wifi", -60, 3_000);
+
+        assert!(list.take_lost(3_000 + LOST_AFTER_MS - 1).is_none());
+        let lost = list.take_lost(3_000 + LOST_AFTER_MS).unwrap();
+        assert_eq!(
+            lost,
+            LostDevice {
+                mac: MAC,
+                radio: "wifi",
+                first_seen_ms: 1_000,
+                last_seen_ms: 3_000,
+                sightings: 3,
+                closest: ClosestApproach {
+                    rssi: -50,
+                    ts: 2_000
+                },
+            }
+        );
+        assert!(list.take_lost(3_000 + 2 * LOST_AFTER_MS).is_none());
+        // Still watched: the next sighting starts a new encounter
+        let later = 200_000;
+        let sighting = list.observe(&MAC, "ble", -75, later).unwrap();
+        assert_eq!(sighting.closest.ts, later);
+    }
+
+    #[test]
+    fn unseen_watch_is_never_lost() {
+        let mut list = WatchList::<2>::new();
+        list.add(&MAC, 600, 0);
+        assert!(list.take_lost(LOST_AFTER_MS * 2).is_none());
+    }
+}
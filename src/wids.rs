@@ -0,0 +1,235 @@
+/// Wireless intrusion detection: security downgrades of known networks.
+///
+/// An evil-twin or downgrade setup often clones a nearby network's SSID but
+/// advertises it open or with WPA1, so clients fall back to a weaker
+/// handshake. [`SecurityMonitor`] remembers the security each SSID has been
+/// advertised with and reports a [`Downgrade`] when a network that has been
+/// around on WPA2 or WPA3 for a while shows up open, with WEP, or with WPA1.
+use heapless::{String, Vec};
+
+use crate::scanner::Security;
+
+/// How long an SSID has to be advertised with WPA2/WPA3 before it counts as
+/// known. Beacons arrive every ~100 ms, so this is about time, not counts:
+/// a network that has only been around for a moment isn't a baseline.
+pub const KNOWN_AFTER_MS: u32 = 60_000;
+
+/// A known network advertised with weaker security than its baseline
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Downgrade {
+    pub ssid: String<32>,
+    /// Security the network has been seen with
+    pub expected: Security,
+    /// Security of the offending frame
+    pub seen: Security,
+    /// Transmitter of the offending frame
+    pub bssid: [u8; 6],
+}
+
+struct Network {
+    ssid: String<32>,
+    /// Strongest security seen for this SSID
+    baseline: Security,
+    first_seen_ms: u32,
+    last_seen_ms: u32,
+    /// A downgrade has been reported and the baseline not seen since
+    alerted: bool,
+}
+
+/// Advertised security of up to `N` SSIDs. Timestamps are milliseconds since
+/// boot (`u32`, wrapping).
+pub struct SecurityMonitor<const N: usize> {
+    networks: Vec<Network, N>,
+}
+
+impl<const N: usize> SecurityMonitor<N> {
+    pub const fn new() -> Self {
+        Self {
+            networks: Vec::new(),
+        }
+    }
+
+    /// Record a beacon or probe response. Returns a [`Downgrade`] the first
+    /// time a known WPA2/WPA3 network is seen as open, WEP, or WPA1; it is
+    /// reported again only after the network has been seen with its
+    /// baseline security in between. Hidden SSIDs and frames of unknown
+    /// security are ignored. When the table is full the least recently
+    /// seen SSID is replaced.
+    pub fn observe(
+        &mut self,
+        ssid: &str,
+        bssid: &[u8; 6],
+        security: Security,
+        now_ms: u32,
+    ) -> Option<Downgrade> {
+        if ssid.is_empty() || security == Security::Unknown {
+            return None;
+        }
+
+        let Some(network) = self.networks.iter_mut().find(|n| n.ssid == ssid) else {
+            let mut name = String::new();
+            name.push_str(ssid).ok()?;
+            let network = Network {
+                ssid: name,
+                baseline: security,
+                first_seen_ms: now_ms,
+                last_seen_ms: now_ms,
+                alerted: false,
+            };
+            if let Err(network) = self.networks.push(network) {
+                let oldest = self
+                    .networks
+                    .iter_mut()
+                    .max_by_key(|n| now_ms.wrapping_sub(n.last_seen_ms))?;
+                *oldest = network;
+            }
+            return None;
+        };
+
+        let known = now_ms.wrapping_sub(network.first_seen_ms) >= KNOWN_AFTER_MS;
+        network.last_seen_ms = now_ms;
+
+        if security >= network.baseline {
+            if security > network.baseline {
+                // Upgrades restart the clock on the new baseline
+                network.baseline = security;
+                network.first_seen_ms = now_ms;
+            }
+            network.alerted = false;
+            return None;
+        }
+
+        if !known || network.baseline < Security::Wpa2 || security > Security::Wpa {
+            return None;
+        }
+        if network.alerted {
+            return None;
+        }
+        network.alerted = true;
+        Some(Downgrade {
+            ssid: network.ssid.clone(),
+            expected: network.baseline,
+            seen: security,
+            bssid: *bssid,
+        })
+    }
+}
+
+impl<const N: usize> Default for SecurityMonitor<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AP: [u8; 6] = [0x00, 0x11, 0x22, 0x33, 0x44, 0x55];
+    const TWIN: [u8; 6] = [0x02, 0xAA, 0xBB, 0xCC, 0xDD, 0xEE];
+
+    fn known(security: Security) -> SecurityMonitor<4> {
+        let mut monitor = SecurityMonitor::new();
+        assert!(monitor.observe("Home", &AP, security, 0).is_none());
+        assert!(monitor
+            .observe("Home", &AP, security, KNOWN_AFTER_MS)
+            .is_none());
+        monitor
+    }
+
+    // ── Downgrades ──────────────────────────────────────────────────
+
+    #[test]
+    fn open_twin_of_wpa2_network() {
+        let mut monitor = known(Security::Wpa2);
+        let downgrade = monitor
+            .observe("Home", &TWIN, Security::Open, KNOWN_AFTER_MS + 100)
+            .unwrap();
+        assert_eq!(downgrade.ssid.as_str(), "Home");
+        assert_eq!(downgrade.expected, Security::Wpa2);
+        assert_eq!(downgrade.seen, Security::Open);
+        assert_eq!(downgrade.bssid, TWIN);
+    }
+
+    #[test]
+    fn wpa1_counts_as_downgrade_from_wpa3() {
+        let mut monitor = known(Security::Wpa3);
+        let downgrade = monitor.observe("Home", &TWIN, Security::Wpa, KNOWN_AFTER_MS + 1);
+        assert_eq!(downgrade.unwrap().expected, Security::Wpa3);
+    }
+
+    #[test]
+    fn wpa3_to_wpa2_is_not_reported() {
+        let mut monitor = known(Security::Wpa3);
+        assert!(monitor
+            .observe("Home", &TWIN, Security::Wpa2, KNOWN_AFTER_MS + 1)
+            .is_none());
+    }
+
+    #[test]
+    fn reported_once_until_baseline_returns() {
+        let mut monitor = known(Security::Wpa2);
+        let t = KNOWN_AFTER_MS;
+        assert!(monitor
+            .observe("Home", &TWIN, Security::Open, t + 1)
+            .is_some());
+        assert!(monitor
+            .observe("Home", &TWIN, Security::Open, t + 2)
+            .is_none());
+        assert!(monitor
+            .observe("Home", &AP, Security::Wpa2, t + 3)
+            .is_none());
+        assert!(monitor
+            .observe("Home", &TWIN, Security::Open, t + 4)
+            .is_some());
+    }
+
+    // ── Baselines ───────────────────────────────────────────────────
+
+    #[test]
+    fn new_networks_are_not_known_yet() {
+        let mut monitor = SecurityMonitor::<4>::new();
+        monitor.observe("Home", &AP, Security::Wpa2, 0);
+        assert!(monitor
+            .observe("Home", &TWIN, Security::Open, 1_000)
+            .is_none());
+    }
+
+    #[test]
+    fn open_networks_have_nothing_to_lose() {
+        let mut monitor = known(Security::Open);
+        assert!(monitor
+            .observe("Home", &TWIN, Security::Open, KNOWN_AFTER_MS + 1)
+            .is_none());
+    }
+
+    #[test]
+    fn upgrade_restarts_the_clock() {
+        let mut monitor = known(Security::Open);
+        monitor.observe("Home", &AP, Security::Wpa2, KNOWN_AFTER_MS + 1);
+        assert!(monitor
+            .observe("Home", &TWIN, Security::Open, KNOWN_AFTER_MS + 2)
+            .is_none());
+        assert!(monitor
+            .observe("Home", &TWIN, Security::Open, 2 * KNOWN_AFTER_MS + 1)
+            .is_some());
+    }
+
+    #[test]
+    fn hidden_and_unknown_are_ignored() {
+        let mut monitor = SecurityMonitor::<4>::new();
+        assert!(monitor.observe("", &AP, Security::Wpa2, 0).is_none());
+        assert!(monitor.observe("Home", &AP, Security::Unknown, 0).is_none());
+        assert!(monitor.networks.is_empty());
+    }
+
+    #[test]
+    fn full_table_replaces_least_recent() {
+        let mut monitor = SecurityMonitor::<2>::new();
+        monitor.observe("A", &AP, Security::Wpa2, 0);
+        monitor.observe("B", &AP, Security::Wpa2, 10);
+        monitor.observe("C", &AP, Security::Wpa2, 20);
+        let names: Vec<&str, 2> = monitor.networks.iter().map(|n| n.ssid.as_str()).collect();
+        assert_eq!(names.as_slice(), ["C", "B"]);
+    }
+}